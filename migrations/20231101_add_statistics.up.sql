@@ -0,0 +1,13 @@
+-- biomedgps_degree_statistics table is used to store the degree distribution summaries of the entities, it is refreshed from the relation table instead of computing the degrees on demand
+CREATE TABLE
+  IF NOT EXISTS biomedgps_degree_statistics (
+    id BIGSERIAL PRIMARY KEY, -- The degree statistics ID
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Anatomy, Disease, Gene, Compound, Biological Process, etc.
+    entity_count BIGINT NOT NULL, -- The number of entities which have at least one relation
+    min_degree BIGINT NOT NULL, -- The minimum degree of the entities
+    max_degree BIGINT NOT NULL, -- The maximum degree of the entities
+    mean_degree FLOAT NOT NULL, -- The mean degree of the entities
+    median_degree FLOAT NOT NULL, -- The median degree of the entities
+    p90_degree FLOAT NOT NULL, -- The 90th percentile of the degrees
+    UNIQUE (entity_type)
+  );
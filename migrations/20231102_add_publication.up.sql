@@ -0,0 +1,10 @@
+-- biomedgps_publication table is used to store the metadata of the publications which are fetched from the PubMed E-utilities by pmid
+CREATE TABLE
+  IF NOT EXISTS biomedgps_publication (
+    pmid BIGINT PRIMARY KEY, -- The PubMed ID
+    title TEXT NOT NULL, -- The title of the publication
+    journal VARCHAR(255) NOT NULL, -- The full name of the journal
+    year INTEGER, -- The publication year
+    abstract_text TEXT, -- The abstract of the publication, it may be empty
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the metadata was fetched from PubMed
+  );
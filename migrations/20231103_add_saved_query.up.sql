@@ -0,0 +1,14 @@
+-- biomedgps_saved_query table is used to store the named query definitions (a ComposeQuery json string and a default sort) which can be shared and executed by reference
+CREATE TABLE
+  IF NOT EXISTS biomedgps_saved_query (
+    id BIGSERIAL PRIMARY KEY, -- The saved query ID
+    name VARCHAR(64) NOT NULL, -- The name of the saved query
+    description TEXT, -- The description of the saved query
+    target_table VARCHAR(64) NOT NULL, -- The table which the query is executed on, such as entity, relation, entity2d, knowledge_curation and subgraph
+    query TEXT NOT NULL, -- The ComposeQuery json string
+    order_by VARCHAR(255), -- The default sort, such as `score DESC, id ASC`
+    owner VARCHAR(64) NOT NULL, -- The owner of the saved query
+    shared BOOLEAN NOT NULL DEFAULT FALSE, -- Whether the saved query can be listed and executed by other users
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- The created time of the saved query
+    UNIQUE (owner, name)
+  );
@@ -0,0 +1,21 @@
+-- Only the embeddings of the default model are kept
+DELETE FROM biomedgps_entity_embedding WHERE model_name <> 'default';
+DELETE FROM biomedgps_relation_embedding WHERE model_name <> 'default';
+
+ALTER TABLE biomedgps_entity_embedding
+  DROP CONSTRAINT biomedgps_entity_embedding_uniq_key,
+  ADD CONSTRAINT biomedgps_entity_embedding_entity_id_entity_type_key UNIQUE (entity_id, entity_type),
+  DROP CONSTRAINT biomedgps_entity_embedding_pkey,
+  ADD PRIMARY KEY (embedding_id),
+  ALTER COLUMN embedding TYPE vector(400),
+  DROP COLUMN model_name;
+
+ALTER TABLE biomedgps_relation_embedding
+  DROP CONSTRAINT biomedgps_relation_embedding_uniq_key,
+  ADD CONSTRAINT biomedgps_relation_embedding_relation_type_key UNIQUE (relation_type),
+  DROP CONSTRAINT biomedgps_relation_embedding_pkey,
+  ADD PRIMARY KEY (embedding_id),
+  ALTER COLUMN embedding TYPE vector(400),
+  DROP COLUMN model_name;
+
+DROP TABLE IF EXISTS biomedgps_embedding_model;
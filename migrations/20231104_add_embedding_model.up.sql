@@ -0,0 +1,33 @@
+-- biomedgps_embedding_model table is used to register the embedding models, so that we can keep multiple versions of the embeddings (such as a monthly retrained model) in one database
+CREATE TABLE
+  IF NOT EXISTS biomedgps_embedding_model (
+    id BIGSERIAL PRIMARY KEY, -- The embedding model ID
+    model_name VARCHAR(64) NOT NULL UNIQUE, -- The name of the embedding model, such as transe_202311
+    algorithm VARCHAR(64) NOT NULL, -- The algorithm of the embedding model, such as TransE, DistMult, etc.
+    dimension INTEGER NOT NULL, -- The dimension of the embeddings
+    trained_at TIMESTAMPTZ, -- The training date of the embedding model
+    metrics TEXT, -- The evaluation metrics of the embedding model, a json string such as {"mrr": 0.35, "hits@10": 0.52}
+    description TEXT, -- The description of the embedding model
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The registered time of the embedding model
+  );
+
+-- The existing embeddings belong to the default model
+INSERT INTO biomedgps_embedding_model (model_name, algorithm, dimension, description)
+VALUES ('default', 'unknown', 400, 'The embeddings which are imported before the model registry is added.')
+ON CONFLICT (model_name) DO NOTHING;
+
+ALTER TABLE biomedgps_entity_embedding
+  ADD COLUMN model_name VARCHAR(64) NOT NULL DEFAULT 'default' REFERENCES biomedgps_embedding_model (model_name) ON DELETE CASCADE,
+  ALTER COLUMN embedding TYPE vector,
+  DROP CONSTRAINT biomedgps_entity_embedding_pkey,
+  ADD PRIMARY KEY (embedding_id, model_name),
+  DROP CONSTRAINT biomedgps_entity_embedding_entity_id_entity_type_key,
+  ADD CONSTRAINT biomedgps_entity_embedding_uniq_key UNIQUE (entity_id, entity_type, model_name);
+
+ALTER TABLE biomedgps_relation_embedding
+  ADD COLUMN model_name VARCHAR(64) NOT NULL DEFAULT 'default' REFERENCES biomedgps_embedding_model (model_name) ON DELETE CASCADE,
+  ALTER COLUMN embedding TYPE vector,
+  DROP CONSTRAINT biomedgps_relation_embedding_pkey,
+  ADD PRIMARY KEY (embedding_id, model_name),
+  DROP CONSTRAINT biomedgps_relation_embedding_relation_type_key,
+  ADD CONSTRAINT biomedgps_relation_embedding_uniq_key UNIQUE (relation_type, model_name);
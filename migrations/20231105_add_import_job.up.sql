@@ -0,0 +1,14 @@
+-- biomedgps_import_job table is used to record the checkpoints of the imports, so that an interrupted import can be resumed from the last committed batch
+CREATE TABLE
+  IF NOT EXISTS biomedgps_import_job (
+    id BIGSERIAL PRIMARY KEY, -- The import job ID
+    table_name VARCHAR(64) NOT NULL, -- The table which the data is imported into, such as entity, relation, entity2d, knowledge_curation and subgraph
+    filepath TEXT NOT NULL, -- The path of the data file
+    status VARCHAR(16) NOT NULL DEFAULT 'running', -- The status of the import job, such as running, completed and failed
+    batch_size BIGINT NOT NULL, -- The number of rows which are committed in one transaction
+    file_offset BIGINT NOT NULL DEFAULT 0, -- The byte offset of the next row in the normalized data file (only the expected columns are kept), the import is resumed from here
+    committed_rows BIGINT NOT NULL DEFAULT 0, -- The number of rows which have been committed
+    message TEXT, -- The error message if the import job failed
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- The created time of the import job
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time of the last checkpoint
+  );
@@ -0,0 +1,4 @@
+ALTER TABLE biomedgps_import_job
+  DROP COLUMN rows_inserted,
+  DROP COLUMN errors,
+  DROP COLUMN rows_per_second;
@@ -0,0 +1,5 @@
+-- The progress counters of the import jobs, the committed_rows column is the number of rows which have been read and committed
+ALTER TABLE biomedgps_import_job
+  ADD COLUMN rows_inserted BIGINT NOT NULL DEFAULT 0, -- The number of rows which are inserted, the rows which already exist in the table are skipped
+  ADD COLUMN errors BIGINT NOT NULL DEFAULT 0, -- The number of the validation errors and the failed batches
+  ADD COLUMN rows_per_second DOUBLE PRECISION NOT NULL DEFAULT 0; -- The throughput of the last run of the import job
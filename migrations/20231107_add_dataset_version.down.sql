@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS biomedgps_dataset_file;
+
+DROP TABLE IF EXISTS biomedgps_dataset_version;
@@ -0,0 +1,19 @@
+-- biomedgps_dataset_version table is used to record the releases of the knowledge graph which have been imported, the latest one is the active dataset version
+CREATE TABLE
+  IF NOT EXISTS biomedgps_dataset_version (
+    id BIGSERIAL PRIMARY KEY, -- The dataset version ID
+    release_tag VARCHAR(36) NOT NULL UNIQUE, -- The release tag of the dataset, such as v20231101, it is also saved as the db_version of the subgraphs
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- The time when the release was imported for the first time
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the last file of the release was imported
+  );
+
+-- biomedgps_dataset_file table is used to record the data files and their checksums of a release
+CREATE TABLE
+  IF NOT EXISTS biomedgps_dataset_file (
+    id BIGSERIAL PRIMARY KEY, -- The dataset file ID
+    release_tag VARCHAR(36) NOT NULL REFERENCES biomedgps_dataset_version (release_tag) ON DELETE CASCADE, -- The release tag of the dataset
+    table_name VARCHAR(64) NOT NULL, -- The table which the data file is imported into
+    filepath TEXT NOT NULL, -- The path of the data file
+    sha256 VARCHAR(64) NOT NULL, -- The sha256 checksum of the data file
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the data file was imported
+  );
@@ -0,0 +1,18 @@
+-- biomedgps_entity_merge table is used to record the history of the entity merges, a duplicated entity is merged into the surviving entity and its relations, embeddings and curations are rewritten to the surviving entity
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_merge (
+    id BIGSERIAL PRIMARY KEY, -- The entity merge ID
+    source_id VARCHAR(64) NOT NULL, -- The ID of the duplicated entity which is removed
+    source_type VARCHAR(64) NOT NULL, -- The label of the duplicated entity
+    target_id VARCHAR(64) NOT NULL, -- The ID of the surviving entity
+    target_type VARCHAR(64) NOT NULL, -- The label of the surviving entity
+    source_entity TEXT NOT NULL, -- The removed entity in json format, it can be used to restore the entity
+    rewritten_relations BIGINT NOT NULL DEFAULT 0, -- The number of the relations which are rewritten to the surviving entity
+    rewritten_embeddings BIGINT NOT NULL DEFAULT 0, -- The number of the entity embeddings and entity2d records which are rewritten to the surviving entity
+    rewritten_curations BIGINT NOT NULL DEFAULT 0, -- The number of the curated knowledges which are rewritten to the surviving entity
+    deleted_rows BIGINT NOT NULL DEFAULT 0, -- The number of the rows which are deleted because the surviving entity already has the same records
+    merged_by VARCHAR(64) NOT NULL, -- The user who merged the entities
+    merged_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the entities were merged
+  );
+
+CREATE INDEX IF NOT EXISTS idx_entity_merge_source ON biomedgps_entity_merge (source_id, source_type);
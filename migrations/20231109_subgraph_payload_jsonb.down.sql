@@ -0,0 +1,2 @@
+ALTER TABLE biomedgps_subgraph
+  ALTER COLUMN payload TYPE TEXT USING payload::TEXT;
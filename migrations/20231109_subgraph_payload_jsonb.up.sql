@@ -0,0 +1,14 @@
+-- The payload of the subgraphs is stored as jsonb for server-side querying, such as finding the subgraphs which contain a node
+-- The payloads which are not valid json strings are kept in the invalid_payload field of an empty graph
+CREATE OR REPLACE FUNCTION biomedgps_try_jsonb(value TEXT) RETURNS JSONB AS $$
+BEGIN
+  RETURN value::JSONB;
+EXCEPTION WHEN others THEN
+  RETURN jsonb_build_object('nodes', '[]'::JSONB, 'edges', '[]'::JSONB, 'invalid_payload', value);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+ALTER TABLE biomedgps_subgraph
+  ALTER COLUMN payload TYPE JSONB USING biomedgps_try_jsonb(payload);
+
+DROP FUNCTION biomedgps_try_jsonb(TEXT);
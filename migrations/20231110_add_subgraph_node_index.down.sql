@@ -0,0 +1,2 @@
+-- biomedgps_subgraph
+DROP INDEX IF EXISTS idx_nodes_subgraph_table;
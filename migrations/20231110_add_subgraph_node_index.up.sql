@@ -0,0 +1,3 @@
+-- biomedgps_subgraph
+-- Speed up finding the subgraphs which contain a node, such as payload -> 'nodes' @> '[{"id": "Gene::ENTREZ:7157"}]'
+CREATE INDEX IF NOT EXISTS idx_nodes_subgraph_table ON biomedgps_subgraph USING GIN ((payload -> 'nodes') jsonb_path_ops);
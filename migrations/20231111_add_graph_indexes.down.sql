@@ -0,0 +1,12 @@
+-- biomedgps_relation
+DROP INDEX IF EXISTS idx_source_relation_table;
+DROP INDEX IF EXISTS idx_target_relation_table;
+
+-- biomedgps_knowledge_curation
+DROP INDEX IF EXISTS idx_source_curation_table;
+DROP INDEX IF EXISTS idx_target_curation_table;
+DROP INDEX IF EXISTS idx_curator_curation_table;
+DROP INDEX IF EXISTS idx_payload_curation_table;
+
+-- biomedgps_subgraph
+DROP INDEX IF EXISTS idx_owner_subgraph_table;
@@ -0,0 +1,17 @@
+-- The indexes for the graph expansion queries, they filter the relations by the source/target entity and the relation type. The unique constraints already cover the queries which start with the relation_type (biomedgps_relation and biomedgps_knowledge_curation) or the entity id (biomedgps_entity, biomedgps_entity2d and the embedding tables).
+-- The `biomedgps-cli check-indexes` command reports the missing indexes, keep the expected indexes in `GRAPH_INDEXES` (src/model/util.rs) in sync with this file.
+
+-- biomedgps_relation
+-- Such as source_id = 'ENTREZ:7157' AND source_type = 'Gene', it also serves the queries which only filter on source_id.
+CREATE INDEX IF NOT EXISTS idx_source_relation_table ON biomedgps_relation (source_id, source_type);
+CREATE INDEX IF NOT EXISTS idx_target_relation_table ON biomedgps_relation (target_id, target_type);
+
+-- biomedgps_knowledge_curation
+CREATE INDEX IF NOT EXISTS idx_source_curation_table ON biomedgps_knowledge_curation (source_id, source_type);
+CREATE INDEX IF NOT EXISTS idx_target_curation_table ON biomedgps_knowledge_curation (target_id, target_type);
+CREATE INDEX IF NOT EXISTS idx_curator_curation_table ON biomedgps_knowledge_curation (curator);
+-- Such as payload @> '{"project_id": "1"}'
+CREATE INDEX IF NOT EXISTS idx_payload_curation_table ON biomedgps_knowledge_curation USING GIN (payload jsonb_path_ops);
+
+-- biomedgps_subgraph
+CREATE INDEX IF NOT EXISTS idx_owner_subgraph_table ON biomedgps_subgraph (owner);
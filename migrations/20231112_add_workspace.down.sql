@@ -0,0 +1,6 @@
+ALTER TABLE biomedgps_subgraph DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE biomedgps_saved_query DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE biomedgps_knowledge_curation DROP COLUMN IF EXISTS workspace_id;
+
+DROP TABLE IF EXISTS biomedgps_workspace_member;
+DROP TABLE IF EXISTS biomedgps_workspace;
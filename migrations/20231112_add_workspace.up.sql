@@ -0,0 +1,33 @@
+-- biomedgps_workspace table is used to group the subgraphs, saved queries and curated knowledges of a team, the members of a workspace can access all its records
+CREATE TABLE
+  IF NOT EXISTS biomedgps_workspace (
+    id BIGSERIAL PRIMARY KEY, -- The workspace ID
+    name VARCHAR(64) NOT NULL, -- The name of the workspace
+    description TEXT, -- The description of the workspace
+    owner VARCHAR(64) NOT NULL, -- The user who created the workspace, the owner is also a member with the owner role
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- The created time of the workspace
+    UNIQUE (owner, name)
+  );
+
+-- biomedgps_workspace_member table is used to record the members of the workspaces and their roles
+CREATE TABLE
+  IF NOT EXISTS biomedgps_workspace_member (
+    id BIGSERIAL PRIMARY KEY, -- The workspace member ID
+    workspace_id BIGINT NOT NULL REFERENCES biomedgps_workspace (id) ON DELETE CASCADE, -- The ID of the workspace
+    username VARCHAR(64) NOT NULL, -- The username of the member
+    role VARCHAR(16) NOT NULL, -- The role of the member, one of viewer, editor and owner
+    invited_by VARCHAR(64) NOT NULL, -- The user who invited the member
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- The time when the member joined the workspace
+    UNIQUE (workspace_id, username)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_workspace_member_username ON biomedgps_workspace_member (username);
+
+-- The records without a workspace are personal records, they are kept when their workspace is deleted
+ALTER TABLE biomedgps_subgraph ADD COLUMN IF NOT EXISTS workspace_id BIGINT REFERENCES biomedgps_workspace (id) ON DELETE SET NULL;
+ALTER TABLE biomedgps_saved_query ADD COLUMN IF NOT EXISTS workspace_id BIGINT REFERENCES biomedgps_workspace (id) ON DELETE SET NULL;
+ALTER TABLE biomedgps_knowledge_curation ADD COLUMN IF NOT EXISTS workspace_id BIGINT REFERENCES biomedgps_workspace (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_workspace_subgraph_table ON biomedgps_subgraph (workspace_id);
+CREATE INDEX IF NOT EXISTS idx_workspace_saved_query_table ON biomedgps_saved_query (workspace_id);
+CREATE INDEX IF NOT EXISTS idx_workspace_knowledge_curation_table ON biomedgps_knowledge_curation (workspace_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS biomedgps_notification;
+DROP TABLE IF EXISTS biomedgps_webhook;
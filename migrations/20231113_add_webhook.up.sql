@@ -0,0 +1,28 @@
+-- biomedgps_webhook table is used to register the webhooks of the external pipelines, the events (such as a new curated knowledge or a finished import) are sent to the url as signed POST requests
+CREATE TABLE
+  IF NOT EXISTS biomedgps_webhook (
+    id BIGSERIAL PRIMARY KEY, -- The webhook ID
+    url VARCHAR(1024) NOT NULL, -- The url which receives the events
+    secret VARCHAR(255) NOT NULL, -- The secret to sign the request body with HMAC-SHA256
+    event_types TEXT[] NOT NULL, -- The subscribed event types, such as curation.created, or * for all events
+    owner VARCHAR(64) NOT NULL, -- The user who registered the webhook
+    active BOOLEAN NOT NULL DEFAULT TRUE, -- Whether the events are sent to the webhook
+    last_status_code INTEGER, -- The status code of the last delivery, it is empty if the request failed without a response
+    last_error TEXT, -- The error of the last delivery, it is empty if the last delivery succeeded
+    last_delivered_at TIMESTAMPTZ, -- The time of the last delivery
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The created time of the webhook
+  );
+
+-- biomedgps_notification table is used to keep the in-app notification feed, each event is saved for its recipients
+CREATE TABLE
+  IF NOT EXISTS biomedgps_notification (
+    id BIGSERIAL PRIMARY KEY, -- The notification ID
+    event_type VARCHAR(64) NOT NULL, -- The event type, such as curation.created
+    actor VARCHAR(64) NOT NULL, -- The user who triggered the event
+    username VARCHAR(64), -- The recipient of the notification, it is visible to all users if it is empty
+    workspace_id BIGINT REFERENCES biomedgps_workspace (id) ON DELETE CASCADE, -- The workspace of the event
+    payload JSONB NOT NULL, -- The data of the event, such as the curated knowledge
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the event was triggered
+  );
+
+CREATE INDEX IF NOT EXISTS idx_notification_username ON biomedgps_notification (username, id);
@@ -0,0 +1,15 @@
+-- biomedgps_entity_attribute table is used to keep the type-specific attributes of the entities, such as the symbol of a gene, the SMILES and InChIKey of a compound and the ICD codes of a disease
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_attribute (
+    id BIGSERIAL PRIMARY KEY, -- The attribute ID
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID, such as ENTREZ:7157
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Gene
+    key VARCHAR(64) NOT NULL, -- The attribute key in snake case, such as symbol, smiles, inchikey and icd10
+    value TEXT NOT NULL, -- The attribute value, it is parsed by the value type
+    value_type VARCHAR(16) NOT NULL DEFAULT 'string', -- One of string, integer, float and boolean
+    CONSTRAINT biomedgps_entity_attribute_uniq_key UNIQUE (entity_id, entity_type, key, value)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_entity_attribute_entity ON biomedgps_entity_attribute (entity_type, entity_id);
+-- Find the entities by an attribute, such as an InChIKey
+CREATE INDEX IF NOT EXISTS idx_entity_attribute_key_value ON biomedgps_entity_attribute (key, value);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_attributes_relation_table;
+
+ALTER TABLE biomedgps_relation
+  DROP COLUMN IF EXISTS directed,
+  DROP COLUMN IF EXISTS attributes;
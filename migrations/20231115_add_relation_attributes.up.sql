@@ -0,0 +1,7 @@
+-- Add the directed flag and the qualifiers (such as the tissue, the dosage, the direction of effect and the confidence) to the relations
+ALTER TABLE biomedgps_relation
+  ADD COLUMN IF NOT EXISTS directed BOOLEAN NOT NULL DEFAULT TRUE, -- Whether the relation goes from the source to the target, it is false for the symmetric relations such as a protein-protein interaction
+  ADD COLUMN IF NOT EXISTS attributes JSONB; -- The qualifiers of the relation, such as {"tissue": "liver", "confidence": 0.8}
+
+-- Such as attributes @> '{"tissue": "liver"}'
+CREATE INDEX IF NOT EXISTS idx_attributes_relation_table ON biomedgps_relation USING GIN (attributes jsonb_path_ops);
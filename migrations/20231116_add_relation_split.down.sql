@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_split_relation_table;
+
+ALTER TABLE biomedgps_relation DROP COLUMN IF EXISTS split;
@@ -0,0 +1,5 @@
+-- Add the dataset split to the relations, it is generated by `biomedgps-cli splitdb` for training the knowledge graph embeddings
+ALTER TABLE biomedgps_relation
+  ADD COLUMN IF NOT EXISTS split VARCHAR(8) CHECK (split IN ('train', 'valid', 'test')); -- One of train, valid and test, it is empty if the relation is not split yet
+
+CREATE INDEX IF NOT EXISTS idx_split_relation_table ON biomedgps_relation (split);
@@ -0,0 +1,9 @@
+-- biomedgps_entity_degree table is used to store the global degree of each entity, it is refreshed from the relation table by `biomedgps-cli statdb` and attached to the nodes of the graph responses
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_degree (
+    id BIGSERIAL PRIMARY KEY, -- The entity degree ID
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Anatomy, Disease, Gene, Compound, Biological Process, etc.
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID
+    degree BIGINT NOT NULL, -- The number of the relations which use the entity as the source or target node
+    UNIQUE (entity_type, entity_id)
+  );
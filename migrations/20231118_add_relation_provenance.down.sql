@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_dataset_version_relation_table;
+DROP INDEX IF EXISTS idx_created_at_relation_table;
+
+ALTER TABLE biomedgps_relation
+  DROP COLUMN IF EXISTS created_at,
+  DROP COLUMN IF EXISTS dataset_version;
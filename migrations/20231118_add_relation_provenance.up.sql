@@ -0,0 +1,7 @@
+-- Add the provenance of the relations, so we can find the relations which are new in a release
+ALTER TABLE biomedgps_relation
+  ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now(), -- The time when the relation was imported
+  ADD COLUMN IF NOT EXISTS dataset_version VARCHAR(36); -- The release tag of the dataset which the relation was imported from, it is NULL for the relations imported before the provenance was recorded
+
+CREATE INDEX IF NOT EXISTS idx_dataset_version_relation_table ON biomedgps_relation (dataset_version);
+CREATE INDEX IF NOT EXISTS idx_created_at_relation_table ON biomedgps_relation (created_at);
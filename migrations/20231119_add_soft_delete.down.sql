@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_deleted_at_knowledge_curation_table;
+DROP INDEX IF EXISTS idx_deleted_at_subgraph_table;
+
+-- The soft-deleted rows would become visible again, so they are removed before the columns are dropped
+DELETE FROM biomedgps_knowledge_curation WHERE deleted_at IS NOT NULL;
+DELETE FROM biomedgps_subgraph WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE biomedgps_knowledge_curation DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE biomedgps_subgraph DROP COLUMN IF EXISTS deleted_at;
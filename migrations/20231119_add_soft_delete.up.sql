@@ -0,0 +1,7 @@
+-- Soft delete the curated knowledges and the subgraphs, the deleted rows are kept until they are purged by `biomedgps-cli purgedb`
+ALTER TABLE biomedgps_knowledge_curation ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ; -- The time when the curated knowledge was deleted, NULL if it is not deleted
+ALTER TABLE biomedgps_subgraph ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ; -- The time when the subgraph was deleted, NULL if it is not deleted
+
+-- The purge job finds the rows which were deleted before the retention window
+CREATE INDEX IF NOT EXISTS idx_deleted_at_knowledge_curation_table ON biomedgps_knowledge_curation (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_deleted_at_subgraph_table ON biomedgps_subgraph (deleted_at) WHERE deleted_at IS NOT NULL;
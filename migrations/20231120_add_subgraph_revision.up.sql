@@ -0,0 +1,2 @@
+-- The revision of a subgraph is bumped by every update, the clients send it in the If-Match header to avoid overwriting the changes of others
+ALTER TABLE biomedgps_subgraph ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 1; -- Starts from 1 when the subgraph is created
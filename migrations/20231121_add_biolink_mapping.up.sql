@@ -0,0 +1,10 @@
+-- biomedgps_biolink_mapping table is used to map the entity labels and relation types to the BioLink model classes and predicates, the built-in mappings are used for the terms which are not in the table
+CREATE TABLE
+  IF NOT EXISTS biomedgps_biolink_mapping (
+    id BIGSERIAL PRIMARY KEY, -- The mapping ID
+    kind VARCHAR(16) NOT NULL, -- category (an entity label) or predicate (a relation type or a relation key)
+    term VARCHAR(255) NOT NULL, -- The entity label (such as Gene), the relation type (such as DRUGBANK::treats::Compound:Disease) or the relation key (such as treats)
+    biolink_id VARCHAR(128) NOT NULL, -- The CURIE of the BioLink class or predicate, such as biolink:Gene and biolink:treats
+    CONSTRAINT biomedgps_biolink_mapping_uniq_key UNIQUE (kind, term),
+    CONSTRAINT biomedgps_biolink_mapping_kind_check CHECK (kind IN ('category', 'predicate'))
+  );
@@ -0,0 +1,13 @@
+-- biomedgps_gene_set table is used to store the gene sets (such as the pathways in a GMT file or the genes of a disease in the knowledge graph) for the enrichment analysis
+CREATE TABLE
+  IF NOT EXISTS biomedgps_gene_set (
+    id BIGSERIAL PRIMARY KEY, -- The gene set ID
+    name VARCHAR(255) NOT NULL, -- The name of the gene set, such as HALLMARK_APOPTOSIS or Disease::MESH:D001249
+    source VARCHAR(64) NOT NULL, -- Where the gene set comes from, such as MSigDB_Hallmark or a relation type (for the derived gene sets)
+    description TEXT, -- The description of the gene set, such as the url in a GMT file or the entity name
+    genes TEXT[] NOT NULL, -- The genes of the gene set, they should be the same kind of ids as the submitted gene lists, such as ENTREZ:7157
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(), -- The time when the gene set was imported
+    CONSTRAINT biomedgps_gene_set_uniq_key UNIQUE (name, source)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_source_gene_set_table ON biomedgps_gene_set (source);
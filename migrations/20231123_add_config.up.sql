@@ -0,0 +1,8 @@
+-- biomedgps_config table is used to keep the settings which are changed by the admins at runtime, such as the graph theme
+CREATE TABLE
+  IF NOT EXISTS biomedgps_config (
+    key VARCHAR(64) PRIMARY KEY, -- The name of the setting, such as graph_theme
+    value JSONB NOT NULL, -- The value of the setting
+    updated_by VARCHAR(64) NOT NULL, -- The admin who changed the setting last time
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the setting was changed
+  );
@@ -0,0 +1,12 @@
+-- biomedgps_entity_translation table is used to keep the names of the entities in other languages, such as the Chinese names of the diseases
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_translation (
+    id BIGSERIAL PRIMARY KEY, -- The translation ID
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID, such as MESH:D001249
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Disease
+    locale VARCHAR(32) NOT NULL, -- The language tag, such as zh or zh-CN
+    name VARCHAR(255) NOT NULL, -- The name of the entity in the language
+    CONSTRAINT biomedgps_entity_translation_uniq_key UNIQUE (entity_id, entity_type, locale)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_entity_translation_entity ON biomedgps_entity_translation (entity_type, entity_id);
@@ -0,0 +1,18 @@
+-- biomedgps_relation_flag table is used to keep the flags of the relations which are reported by the users, such as a wrong edge which is spotted in a rendered graph
+CREATE TABLE
+  IF NOT EXISTS biomedgps_relation_flag (
+    id BIGSERIAL PRIMARY KEY, -- The flag ID
+    relation_id BIGINT NOT NULL REFERENCES biomedgps_relation (id) ON DELETE CASCADE, -- The flagged relation
+    reason VARCHAR(16) NOT NULL, -- wrong, outdated or duplicate
+    comment TEXT, -- Why the relation is flagged
+    flagged_by VARCHAR(64) NOT NULL, -- The user who flagged the relation
+    status VARCHAR(16) NOT NULL DEFAULT 'open', -- open, accepted or rejected
+    resolved_by VARCHAR(64), -- The admin who triaged the flag
+    resolved_at TIMESTAMPTZ, -- The time when the flag was triaged
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP -- The time when the relation was flagged
+  );
+
+CREATE INDEX IF NOT EXISTS idx_relation_flag_relation ON biomedgps_relation_flag (relation_id, status);
+CREATE INDEX IF NOT EXISTS idx_relation_flag_status ON biomedgps_relation_flag (status, created_at);
+-- A user can only open one flag with the same reason for a relation
+CREATE UNIQUE INDEX IF NOT EXISTS idx_relation_flag_open_uniq ON biomedgps_relation_flag (relation_id, flagged_by, reason) WHERE status = 'open';
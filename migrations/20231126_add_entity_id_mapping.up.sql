@@ -0,0 +1,11 @@
+-- biomedgps_entity_id_mapping table is used to map the ids of the same entity in different resources to a canonical id, such as DOID:2841 and MONDO:0004979 to MESH:D001249
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_id_mapping (
+    id BIGSERIAL PRIMARY KEY, -- The mapping ID
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID, such as DOID:2841
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Disease
+    canonical_id VARCHAR(64) NOT NULL, -- The canonical ID of the entity with the same type, such as MESH:D001249
+    CONSTRAINT biomedgps_entity_id_mapping_uniq_key UNIQUE (entity_id, entity_type)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_entity_id_mapping_canonical ON biomedgps_entity_id_mapping (entity_type, canonical_id);
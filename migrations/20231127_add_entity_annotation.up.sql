@@ -0,0 +1,11 @@
+-- biomedgps_entity_annotation table is used to cache the annotations of the entities which are fetched from the external APIs, such as the gene summaries from MyGene.info
+CREATE TABLE
+  IF NOT EXISTS biomedgps_entity_annotation (
+    id BIGSERIAL PRIMARY KEY, -- The annotation ID
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID, such as ENTREZ:7157
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Gene
+    source VARCHAR(32) NOT NULL, -- The external API, such as mygene, pubchem or mondo
+    data JSONB NOT NULL, -- The annotation which is normalized from the response of the external API
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT now(), -- When the annotation was fetched
+    CONSTRAINT biomedgps_entity_annotation_uniq_key UNIQUE (entity_id, entity_type, source)
+  );
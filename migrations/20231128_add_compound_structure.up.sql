@@ -0,0 +1,13 @@
+-- biomedgps_compound_structure table is used to store the chemical structures of the compounds, which are searched by substructure or similarity
+CREATE TABLE
+  IF NOT EXISTS biomedgps_compound_structure (
+    id BIGSERIAL PRIMARY KEY, -- The structure ID
+    entity_id VARCHAR(64) NOT NULL, -- The entity ID, such as DrugBank:DB00945
+    entity_type VARCHAR(64) NOT NULL, -- The entity type, such as Compound
+    smiles TEXT NOT NULL, -- The SMILES of the compound, such as CC(=O)Oc1ccccc1C(=O)O
+    inchi TEXT, -- The standard InChI of the compound
+    inchikey VARCHAR(27), -- The InChIKey of the compound, such as BSYNRYMUTXBXSQ-UHFFFAOYSA-N
+    CONSTRAINT biomedgps_compound_structure_uniq_key UNIQUE (entity_id, entity_type)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_compound_structure_inchikey ON biomedgps_compound_structure (inchikey);
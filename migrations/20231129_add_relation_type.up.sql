@@ -0,0 +1,11 @@
+-- biomedgps_relation_type table is a registry of the relation types, which is used to render the labels of the edges, match the inverse relations in the queries and group the relation types in the frontend
+CREATE TABLE
+  IF NOT EXISTS biomedgps_relation_type (
+    id BIGSERIAL PRIMARY KEY, -- The registry ID
+    relation_type VARCHAR(64) NOT NULL, -- The relation type, such as GNBR::T::Compound:Disease
+    label VARCHAR(255) NOT NULL, -- The human-readable label of the relation type, such as treats
+    description TEXT, -- The description of the relation type
+    inverse_relation_type VARCHAR(64), -- The relation type in the reversed direction, such as GNBR::treated_by::Disease:Compound, it is the relation type itself for the symmetric relation types
+    parent_relation_type VARCHAR(64), -- The more general relation type, the relation types are grouped by their parents in the frontend
+    CONSTRAINT biomedgps_relation_type_uniq_key UNIQUE (relation_type)
+  );
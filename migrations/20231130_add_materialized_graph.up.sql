@@ -0,0 +1,13 @@
+-- biomedgps_materialized_graph table keeps the graphs of the expensive expansions (such as the neighborhoods of the popular diseases), so the same expansion is computed once per dataset version until it expires
+CREATE TABLE
+  IF NOT EXISTS biomedgps_materialized_graph (
+    id BIGSERIAL PRIMARY KEY, -- The materialization ID
+    query_hash VARCHAR(64) NOT NULL, -- The sha256 hash of the query key
+    query_key TEXT NOT NULL, -- The query key, such as one-step:0:50:{"operator": "=", ...}, it is kept for the admins to find the expansion
+    dataset_version VARCHAR(64) NOT NULL DEFAULT '', -- The release tag of the dataset version which the graph is computed from, it is empty if no dataset version is active
+    payload JSONB NOT NULL, -- The computed graph
+    hit_count BIGINT NOT NULL DEFAULT 0, -- How many times the graph is reused
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(), -- When the graph was computed
+    expires_at TIMESTAMPTZ NOT NULL, -- When the graph expires, it is computed again after that
+    CONSTRAINT biomedgps_materialized_graph_uniq_key UNIQUE (query_hash, dataset_version)
+  );
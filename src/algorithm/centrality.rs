@@ -0,0 +1,169 @@
+//! Centrality algorithms for finding the hubs of a graph, such as the betweenness centrality and the PageRank. They are computed within the fetched subgraphs, the global degrees of the entities come from the `biomedgps_entity_degree` table instead.
+//!
+//! The graphs are treated as undirected and unweighted, the multiple edges between two nodes and the self-loops are ignored.
+
+use petgraph::graph::UnGraph;
+use petgraph::visit::EdgeRef;
+use std::collections::VecDeque;
+
+/// The damping factor of the PageRank, it is the probability to follow an edge instead of jumping to a random node.
+pub const PAGERANK_DAMPING: f64 = 0.85;
+
+// The max number of the iterations of the PageRank.
+const MAX_PAGERANK_ITERATIONS: usize = 100;
+// The PageRank stops when the sum of the changes is less than it.
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+
+/// The sorted neighbours of each node without the duplicates and the self-loops.
+fn adjacency<N, E>(graph: &UnGraph<N, E>) -> Vec<Vec<usize>> {
+    let mut neighbours = vec![vec![]; graph.node_count()];
+    for edge in graph.edge_references() {
+        let (source, target) = (edge.source().index(), edge.target().index());
+        if source != target {
+            neighbours[source].push(target);
+            neighbours[target].push(source);
+        }
+    }
+
+    for node_neighbours in neighbours.iter_mut() {
+        node_neighbours.sort_unstable();
+        node_neighbours.dedup();
+    }
+
+    neighbours
+}
+
+/// Compute the betweenness centrality of each node by the Brandes algorithm, it is the fraction of the shortest paths between the other nodes which pass through the node. The values are normalized by the number of the node pairs, so they are between 0 and 1.
+///
+/// ```
+/// use biomedgps::algorithm::centrality::betweenness_centrality;
+/// use petgraph::graph::UnGraph;
+///
+/// // A path 0 - 1 - 2, all shortest paths between 0 and 2 pass through 1.
+/// let graph = UnGraph::<(), ()>::from_edges(&[(0, 1), (1, 2)]);
+/// assert_eq!(betweenness_centrality(&graph), vec![0.0, 1.0, 0.0]);
+/// ```
+pub fn betweenness_centrality<N, E>(graph: &UnGraph<N, E>) -> Vec<f64> {
+    let neighbours = adjacency(graph);
+    let n = neighbours.len();
+    let mut centrality = vec![0.0; n];
+
+    for source in 0..n {
+        let mut stack = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![vec![]; n];
+        let mut paths = vec![0.0; n];
+        let mut distances: Vec<Option<usize>> = vec![None; n];
+        paths[source] = 1.0;
+        distances[source] = Some(0);
+
+        let mut queue = VecDeque::from(vec![source]);
+        while let Some(node) = queue.pop_front() {
+            stack.push(node);
+            let distance = distances[node].unwrap_or_default();
+            for other in neighbours[node].iter() {
+                if distances[*other].is_none() {
+                    distances[*other] = Some(distance + 1);
+                    queue.push_back(*other);
+                }
+                if distances[*other] == Some(distance + 1) {
+                    paths[*other] += paths[node];
+                    predecessors[*other].push(node);
+                }
+            }
+        }
+
+        let mut dependencies = vec![0.0; n];
+        while let Some(node) = stack.pop() {
+            for predecessor in predecessors[node].iter() {
+                dependencies[*predecessor] +=
+                    paths[*predecessor] / paths[node] * (1.0 + dependencies[node]);
+            }
+            if node != source {
+                centrality[node] += dependencies[node];
+            }
+        }
+    }
+
+    // Each pair is counted from both ends in an undirected graph.
+    let pairs = (n as f64 - 1.0) * (n as f64 - 2.0);
+    if pairs > 0.0 {
+        for value in centrality.iter_mut() {
+            *value /= pairs;
+        }
+    }
+
+    centrality
+}
+
+/// Compute the PageRank of each node, an undirected edge is followed in both directions. The isolated nodes jump to all nodes, so the values sum to 1.
+///
+/// ```
+/// use biomedgps::algorithm::centrality::{pagerank, PAGERANK_DAMPING};
+/// use petgraph::graph::UnGraph;
+///
+/// // A star, the center is the hub.
+/// let graph = UnGraph::<(), ()>::from_edges(&[(0, 1), (0, 2), (0, 3)]);
+/// let ranks = pagerank(&graph, PAGERANK_DAMPING);
+/// assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+/// assert!(ranks[0] > ranks[1]);
+/// ```
+pub fn pagerank<N, E>(graph: &UnGraph<N, E>, damping: f64) -> Vec<f64> {
+    let neighbours = adjacency(graph);
+    let n = neighbours.len();
+    if n == 0 {
+        return vec![];
+    }
+
+    let mut ranks = vec![1.0 / n as f64; n];
+    for _ in 0..MAX_PAGERANK_ITERATIONS {
+        let isolated = (0..n)
+            .filter(|node| neighbours[*node].is_empty())
+            .map(|node| ranks[node])
+            .sum::<f64>();
+        let base = (1.0 - damping) / n as f64 + damping * isolated / n as f64;
+
+        let mut next = vec![base; n];
+        for (node, node_neighbours) in neighbours.iter().enumerate() {
+            for other in node_neighbours.iter() {
+                next[*other] += damping * ranks[node] / node_neighbours.len() as f64;
+            }
+        }
+
+        let change = next
+            .iter()
+            .zip(ranks.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>();
+        ranks = next;
+        if change < PAGERANK_TOLERANCE {
+            break;
+        }
+    }
+
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_centrality() {
+        // A star with a duplicated edge and a self-loop, and an isolated node.
+        let mut graph = UnGraph::<(), ()>::from_edges(&[(0, 1), (0, 2), (0, 3), (0, 1), (2, 2)]);
+        graph.add_node(());
+
+        // The center is on the shortest paths of the 3 leaf pairs, and there are 6 pairs except the center.
+        let betweenness = betweenness_centrality(&graph);
+        assert!((betweenness[0] - 3.0 / 6.0).abs() < 1e-9);
+        assert_eq!(&betweenness[1..], &[0.0, 0.0, 0.0, 0.0]);
+
+        let ranks = pagerank(&graph, PAGERANK_DAMPING);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+        assert!(ranks[0] > ranks[1]);
+        assert!((ranks[1] - ranks[2]).abs() < 1e-9);
+        assert!(ranks[4] < ranks[1]);
+
+        assert!(pagerank(&UnGraph::<(), ()>::new_undirected(), PAGERANK_DAMPING).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+//! Community detection algorithms for grouping the nodes of a graph, such as the Louvain method and the label propagation. They are used to cluster the fetched subgraphs, so the frontend can color or collapse the groups.
+//!
+//! The communities are numbered from 0 by their sizes (the largest first) and the ties are broken by the smallest node index, so the same graph always gets the same numbers.
+
+use petgraph::graph::UnGraph;
+use petgraph::visit::EdgeRef;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+
+/// The max number of the passes over the nodes, in each level of the Louvain method and in the label propagation.
+const MAX_PASSES: usize = 100;
+// The minimal modularity gain to move a node, it avoids the endless moves caused by the floating point errors.
+const MIN_GAIN: f64 = 1e-12;
+// The seed of the label propagation, the node order and the ties are random but reproducible.
+const LABEL_PROPAGATION_SEED: u64 = 42;
+
+/// A weighted graph in the adjacency form, the self-loops are kept separately. The maps are ordered, so the results don't depend on the hash order.
+struct WeightedGraph {
+    neighbours: Vec<BTreeMap<usize, f64>>,
+    loops: Vec<f64>,
+}
+
+impl WeightedGraph {
+    fn from_graph<N>(graph: &UnGraph<N, f64>) -> Self {
+        let mut neighbours = vec![BTreeMap::new(); graph.node_count()];
+        let mut loops = vec![0.0; graph.node_count()];
+        for edge in graph.edge_references() {
+            let (source, target) = (edge.source().index(), edge.target().index());
+            if source == target {
+                loops[source] += *edge.weight();
+            } else {
+                *neighbours[source].entry(target).or_insert(0.0) += *edge.weight();
+                *neighbours[target].entry(source).or_insert(0.0) += *edge.weight();
+            }
+        }
+
+        WeightedGraph { neighbours, loops }
+    }
+
+    fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    /// The weighted degree of a node, a self-loop is counted twice.
+    fn degree(&self, node: usize) -> f64 {
+        self.neighbours[node].values().sum::<f64>() + 2.0 * self.loops[node]
+    }
+
+    /// Merge the nodes of each community into one node, the edges in a community become its self-loop.
+    fn aggregate(&self, communities: &[usize], num_communities: usize) -> Self {
+        let mut neighbours = vec![BTreeMap::new(); num_communities];
+        let mut loops = vec![0.0; num_communities];
+        for (node, edges) in self.neighbours.iter().enumerate() {
+            let community = communities[node];
+            loops[community] += self.loops[node];
+            for (other, weight) in edges.iter() {
+                let other_community = communities[*other];
+                if community == other_community {
+                    // Each edge is visited from both ends.
+                    loops[community] += weight / 2.0;
+                } else {
+                    *neighbours[community].entry(other_community).or_insert(0.0) += weight;
+                }
+            }
+        }
+
+        WeightedGraph { neighbours, loops }
+    }
+}
+
+/// Renumber the communities from 0 in the order of their first nodes, and return the number of the communities.
+fn renumber(communities: &[usize]) -> (Vec<usize>, usize) {
+    let mut numbers: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut renumbered = Vec::with_capacity(communities.len());
+    for community in communities {
+        let next = numbers.len();
+        renumbered.push(*numbers.entry(*community).or_insert(next));
+    }
+
+    (renumbered, numbers.len())
+}
+
+/// Renumber the communities by their sizes, the largest community is 0.
+fn sort_by_size(communities: &[usize]) -> Vec<usize> {
+    let (communities, num_communities) = renumber(communities);
+    let mut sizes = vec![0; num_communities];
+    for community in communities.iter() {
+        sizes[*community] += 1;
+    }
+
+    // The communities are already in the order of their first nodes, the stable sort keeps it for the same sizes.
+    let mut order = (0..num_communities).collect::<Vec<usize>>();
+    order.sort_by(|a, b| sizes[*b].cmp(&sizes[*a]));
+    let mut numbers = vec![0; num_communities];
+    for (number, community) in order.into_iter().enumerate() {
+        numbers[community] = number;
+    }
+
+    communities
+        .into_iter()
+        .map(|community| numbers[community])
+        .collect()
+}
+
+/// Move each node to the neighbouring community with the max modularity gain until no node moves, and return the communities and whether any node moved.
+fn move_nodes(graph: &WeightedGraph) -> (Vec<usize>, bool) {
+    let degrees = (0..graph.len())
+        .map(|node| graph.degree(node))
+        .collect::<Vec<f64>>();
+    let total = degrees.iter().sum::<f64>();
+    let mut communities = (0..graph.len()).collect::<Vec<usize>>();
+    let mut totals = degrees.clone();
+    let mut moved = false;
+    if total == 0.0 {
+        return (communities, moved);
+    }
+
+    for _ in 0..MAX_PASSES {
+        let mut changed = false;
+        for node in 0..graph.len() {
+            let current = communities[node];
+            totals[current] -= degrees[node];
+
+            let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+            for (other, weight) in graph.neighbours[node].iter() {
+                *links.entry(communities[*other]).or_insert(0.0) += weight;
+            }
+
+            let gain =
+                |community: usize, link: f64| link - totals[community] * degrees[node] / total;
+            let mut best = current;
+            let mut best_gain = gain(current, links.get(&current).cloned().unwrap_or(0.0));
+            for (community, link) in links.iter() {
+                let community_gain = gain(*community, *link);
+                if community_gain > best_gain + MIN_GAIN {
+                    best = *community;
+                    best_gain = community_gain;
+                }
+            }
+
+            totals[best] += degrees[node];
+            if best != current {
+                communities[node] = best;
+                changed = true;
+                moved = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    (communities, moved)
+}
+
+/// Detect the communities by the Louvain method, which maximizes the modularity by moving the nodes between the communities and merging the communities level by level. The edge weights must be positive, and it returns the community of each node by the node index.
+///
+/// ```
+/// use biomedgps::algorithm::community::louvain;
+/// use petgraph::graph::UnGraph;
+///
+/// // Two triangles which are connected by one edge.
+/// let graph = UnGraph::<(), f64>::from_edges(&[
+///     (0, 1, 1.0), (1, 2, 1.0), (0, 2, 1.0),
+///     (3, 4, 1.0), (4, 5, 1.0), (3, 5, 1.0),
+///     (2, 3, 1.0),
+/// ]);
+/// assert_eq!(louvain(&graph), vec![0, 0, 0, 1, 1, 1]);
+/// ```
+pub fn louvain<N>(graph: &UnGraph<N, f64>) -> Vec<usize> {
+    let mut level = WeightedGraph::from_graph(graph);
+    let mut membership = (0..graph.node_count()).collect::<Vec<usize>>();
+    loop {
+        let (communities, moved) = move_nodes(&level);
+        if !moved {
+            break;
+        }
+
+        let (communities, num_communities) = renumber(&communities);
+        for community in membership.iter_mut() {
+            *community = communities[*community];
+        }
+
+        if num_communities == level.len() {
+            break;
+        }
+        level = level.aggregate(&communities, num_communities);
+    }
+
+    sort_by_size(&membership)
+}
+
+/// Detect the communities by the label propagation, each node takes the label with the max weight in its neighbours until no label changes. The nodes are visited in a random order and the ties are broken randomly, the seed is fixed so the results are reproducible. It returns the community of each node by the node index.
+pub fn label_propagation<N>(graph: &UnGraph<N, f64>) -> Vec<usize> {
+    let graph = WeightedGraph::from_graph(graph);
+    let mut rng = StdRng::seed_from_u64(LABEL_PROPAGATION_SEED);
+    let mut labels = (0..graph.len()).collect::<Vec<usize>>();
+    let mut order = (0..graph.len()).collect::<Vec<usize>>();
+
+    for _ in 0..MAX_PASSES {
+        let mut changed = false;
+        order.shuffle(&mut rng);
+        for node in order.iter() {
+            let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+            for (other, weight) in graph.neighbours[*node].iter() {
+                *weights.entry(labels[*other]).or_insert(0.0) += weight;
+            }
+
+            let max_weight = weights.values().cloned().fold(f64::MIN, f64::max);
+            let candidates = weights
+                .iter()
+                .filter(|(_, weight)| **weight >= max_weight - MIN_GAIN)
+                .map(|(label, _)| *label)
+                .collect::<Vec<usize>>();
+            // An isolated node keeps its label, and so does a node whose label is one of the best.
+            if candidates.is_empty() || candidates.contains(&labels[*node]) {
+                continue;
+            }
+
+            labels[*node] = *candidates.choose(&mut rng).unwrap();
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    sort_by_size(&labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two 4-cliques which are connected by one edge, and an isolated node.
+    fn two_cliques() -> UnGraph<(), f64> {
+        let mut edges: Vec<(u32, u32, f64)> = vec![];
+        for offset in [0, 4] {
+            for i in 0..4 {
+                for j in (i + 1)..4 {
+                    edges.push((offset + i, offset + j, 1.0));
+                }
+            }
+        }
+        edges.push((3, 4, 1.0));
+
+        let mut graph = UnGraph::<(), f64>::from_edges(&edges);
+        graph.add_node(());
+        graph
+    }
+
+    #[test]
+    fn test_louvain() {
+        let graph = two_cliques();
+        assert_eq!(louvain(&graph), vec![0, 0, 0, 0, 1, 1, 1, 1, 2]);
+
+        // The graph without edges.
+        let mut graph = UnGraph::<(), f64>::new_undirected();
+        graph.add_node(());
+        graph.add_node(());
+        assert_eq!(louvain(&graph), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_label_propagation() {
+        let graph = two_cliques();
+        let communities = label_propagation(&graph);
+        assert_eq!(communities.len(), 9);
+        // Each clique is in one community, and the isolated node is alone.
+        assert!(communities[0..4].iter().all(|c| *c == communities[0]));
+        assert!(communities[4..8].iter().all(|c| *c == communities[4]));
+        assert_eq!(
+            communities.iter().filter(|c| **c == communities[8]).count(),
+            1
+        );
+        assert_eq!(sort_by_size(&[5, 5, 2, 7, 7, 7]), vec![1, 1, 2, 0, 0, 0]);
+    }
+}
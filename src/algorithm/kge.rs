@@ -0,0 +1,177 @@
+//! Score functions of the knowledge graph embedding models, they are used to rank the candidate entities of a triple when evaluating the embeddings.
+//!
+//! The complex embeddings (ComplEx and RotatE) are stored as the real parts followed by the imaginary parts, which is same as DGL-KE. The relation embeddings of RotatE can be the phases (half of the entity dimension, as DGL-KE) or the complex values (same as the entity dimension).
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The score function of a model, a higher score means a more plausible triple.
+///
+/// * `TransE` - `-||h + r - t||`, the L1 or L2 norm.
+/// * `DistMult` - `<h, r, t>`.
+/// * `ComplEx` - `Re(<h, r, conj(t)>)`.
+/// * `RotatE` - `-||h ∘ r - t||`, r is a rotation in the complex space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreFunction {
+    TransE { l1: bool },
+    DistMult,
+    ComplEx,
+    RotatE,
+}
+
+impl FromStr for ScoreFunction {
+    type Err = String;
+
+    /// Parse the algorithm of the embedding model, such as TransE, TransE_l1, DistMult, ComplEx and RotatE (case-insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "transe" | "transe_l2" => Ok(ScoreFunction::TransE { l1: false }),
+            "transe_l1" => Ok(ScoreFunction::TransE { l1: true }),
+            "distmult" => Ok(ScoreFunction::DistMult),
+            "complex" => Ok(ScoreFunction::ComplEx),
+            "rotate" => Ok(ScoreFunction::RotatE),
+            _ => Err(format!(
+                "Unsupported algorithm: {}, only TransE, TransE_l1, TransE_l2, DistMult, ComplEx and RotatE are supported.",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScoreFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreFunction::TransE { l1: true } => write!(f, "TransE_l1"),
+            ScoreFunction::TransE { l1: false } => write!(f, "TransE_l2"),
+            ScoreFunction::DistMult => write!(f, "DistMult"),
+            ScoreFunction::ComplEx => write!(f, "ComplEx"),
+            ScoreFunction::RotatE => write!(f, "RotatE"),
+        }
+    }
+}
+
+impl ScoreFunction {
+    /// Check whether the dimensions of the entity and relation embeddings fit the score function.
+    pub fn check_dimensions(
+        &self,
+        entity_dimension: usize,
+        relation_dimension: usize,
+    ) -> Result<(), String> {
+        let valid = match self {
+            ScoreFunction::TransE { .. } | ScoreFunction::DistMult => {
+                entity_dimension == relation_dimension
+            }
+            ScoreFunction::ComplEx => {
+                entity_dimension % 2 == 0 && entity_dimension == relation_dimension
+            }
+            ScoreFunction::RotatE => {
+                entity_dimension % 2 == 0
+                    && (relation_dimension == entity_dimension
+                        || relation_dimension * 2 == entity_dimension)
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "The dimensions of the entity embeddings ({}) and the relation embeddings ({}) don't fit {}.",
+                entity_dimension, relation_dimension, self
+            ))
+        }
+    }
+
+    /// Compute the score of a triple, the dimensions should be checked by [`check_dimensions`](#method.check_dimensions) first.
+    ///
+    /// ```
+    /// use biomedgps::algorithm::kge::ScoreFunction;
+    ///
+    /// let transe = ScoreFunction::TransE { l1: true };
+    /// assert_eq!(transe.score(&[1.0, 0.0], &[0.0, 1.0], &[1.0, 1.0]), 0.0);
+    /// assert_eq!(transe.score(&[1.0, 0.0], &[0.0, 1.0], &[0.0, 0.0]), -2.0);
+    /// assert_eq!(ScoreFunction::DistMult.score(&[1.0, 2.0], &[3.0, 1.0], &[1.0, 1.0]), 5.0);
+    /// ```
+    pub fn score(&self, head: &[f32], relation: &[f32], tail: &[f32]) -> f32 {
+        match self {
+            ScoreFunction::TransE { l1 } => {
+                let distances = head
+                    .iter()
+                    .zip(relation.iter())
+                    .zip(tail.iter())
+                    .map(|((h, r), t)| h + r - t);
+                if *l1 {
+                    -distances.map(|d| d.abs()).sum::<f32>()
+                } else {
+                    -distances.map(|d| d * d).sum::<f32>().sqrt()
+                }
+            }
+            ScoreFunction::DistMult => head
+                .iter()
+                .zip(relation.iter())
+                .zip(tail.iter())
+                .map(|((h, r), t)| h * r * t)
+                .sum(),
+            ScoreFunction::ComplEx => {
+                let half = head.len() / 2;
+                (0..half)
+                    .map(|i| {
+                        let (h_re, h_im) = (head[i], head[half + i]);
+                        let (r_re, r_im) = (relation[i], relation[half + i]);
+                        let (t_re, t_im) = (tail[i], tail[half + i]);
+                        h_re * r_re * t_re + h_im * r_re * t_im + h_re * r_im * t_im
+                            - h_im * r_im * t_re
+                    })
+                    .sum()
+            }
+            ScoreFunction::RotatE => {
+                let half = head.len() / 2;
+                let is_phase = relation.len() == half;
+                -(0..half)
+                    .map(|i| {
+                        let (r_re, r_im) = if is_phase {
+                            (relation[i].cos(), relation[i].sin())
+                        } else {
+                            (relation[i], relation[half + i])
+                        };
+                        let (h_re, h_im) = (head[i], head[half + i]);
+                        let re = h_re * r_re - h_im * r_im - tail[i];
+                        let im = h_re * r_im + h_im * r_re - tail[half + i];
+                        (re * re + im * im).sqrt()
+                    })
+                    .sum::<f32>()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_function() {
+        assert_eq!(
+            "TransE".parse::<ScoreFunction>(),
+            Ok(ScoreFunction::TransE { l1: false })
+        );
+        assert_eq!(
+            "complex".parse::<ScoreFunction>(),
+            Ok(ScoreFunction::ComplEx)
+        );
+        assert!("TuckER".parse::<ScoreFunction>().is_err());
+
+        // (1 + i) * i = -1 + i, so the rotation by pi/2 maps the head to the tail.
+        let head = [1.0, 1.0];
+        let tail = [-1.0, 1.0];
+        let phase = [std::f32::consts::FRAC_PI_2];
+        assert!(ScoreFunction::RotatE.score(&head, &phase, &tail).abs() < 1e-6);
+        assert!(ScoreFunction::RotatE.score(&head, &[0.0, 1.0], &tail).abs() < 1e-6);
+
+        // Re((1 + i) * i * conj(-1 + i)) = Re((-1 + i) * (-1 - i)) = 2
+        assert_eq!(ScoreFunction::ComplEx.score(&head, &[0.0, 1.0], &tail), 2.0);
+
+        assert!(ScoreFunction::RotatE.check_dimensions(400, 200).is_ok());
+        assert!(ScoreFunction::ComplEx.check_dimensions(400, 200).is_err());
+        assert!(ScoreFunction::DistMult.check_dimensions(400, 400).is_ok());
+    }
+}
@@ -84,7 +84,7 @@ mod tests {
     extern crate log;
     use super::*;
     use crate::{init_logger, setup_test_db};
-    use crate::model::core::{EmbeddingRecordResponse, EntityEmbedding};
+    use crate::model::core::{EmbeddingRecordResponse, EntityEmbedding, TableName};
     use log::debug;
 
     #[tokio::test]
@@ -93,7 +93,7 @@ mod tests {
 
         match EmbeddingRecordResponse::<EntityEmbedding>::get_records(
             &pool,
-            "biomedgps_entity_embedding",
+            TableName::EntityEmbedding,
             &None,
             Some(1),
             Some(10),
@@ -1,3 +1,7 @@
 //! Algorithms for machine learning
 
-pub mod knn;
\ No newline at end of file
+pub mod knn;
+pub mod reduction;
+pub mod kge;
+pub mod community;
+pub mod centrality;
@@ -0,0 +1,775 @@
+//! Dimensionality reduction algorithms for computing the 2D coordinates of the entities from their embeddings, such as PCA, t-SNE and UMAP.
+//!
+//! All algorithms are implemented natively, so the `biomedgps_entity2d` table can be generated without the python scripts:
+//!
+//! * `PCA` - The principal components are computed by the power iteration, it is fast and deterministic.
+//! * `t-SNE` - The Barnes-Hut t-SNE, the input similarities are computed from the nearest neighbours.
+//! * `UMAP` - A simplified UMAP, it uses the fuzzy simplicial set of the nearest neighbours and optimizes the layout by the SGD with negative sampling.
+//!
+//! The nearest neighbours are searched by brute force, so t-SNE and UMAP take O(n^2) time. The embeddings are reduced to at most 50 dimensions by PCA before running t-SNE and UMAP.
+
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// The embeddings are reduced to this dimension by PCA before running t-SNE and UMAP.
+const PCA_PREPROCESSING_DIMENSION: usize = 50;
+const PCA_MAX_ITERATIONS: usize = 100;
+
+// Hyperparameters of t-SNE, they are same as the defaults of scikit-learn.
+const TSNE_EARLY_EXAGGERATION: f64 = 12.0;
+const TSNE_EXAGGERATION_ITERATIONS: usize = 250;
+const TSNE_THETA: f64 = 0.5;
+
+// The a and b parameters of the UMAP curve for min_dist = 0.1 and spread = 1.0.
+const UMAP_A: f64 = 1.577;
+const UMAP_B: f64 = 0.895;
+const UMAP_NEGATIVE_SAMPLES: usize = 5;
+
+/// The dimensionality reduction method, the coordinates are stored in the umap_x/umap_y or tsne_x/tsne_y columns of the `biomedgps_entity2d` table.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReductionMethod {
+    Umap,
+    Tsne,
+    Pca,
+}
+
+impl FromStr for ReductionMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "umap" => Ok(ReductionMethod::Umap),
+            "tsne" | "t-sne" => Ok(ReductionMethod::Tsne),
+            "pca" => Ok(ReductionMethod::Pca),
+            _ => Err(format!(
+                "Unsupported method: {}, only umap, tsne and pca are supported.",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReductionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReductionMethod::Umap => write!(f, "umap"),
+            ReductionMethod::Tsne => write!(f, "tsne"),
+            ReductionMethod::Pca => write!(f, "pca"),
+        }
+    }
+}
+
+/// The options of the dimensionality reduction.
+///
+/// * `perplexity` - The perplexity of t-SNE, it is reduced automatically if there are not enough samples.
+/// * `n_neighbors` - The number of the nearest neighbours of UMAP.
+/// * `iterations` - The number of the iterations of t-SNE (default: 1000) or the epochs of UMAP (default: 200), it is ignored by PCA.
+/// * `seed` - The random seed, the same seed always produces the same coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReductionOptions {
+    pub perplexity: f64,
+    pub n_neighbors: usize,
+    pub iterations: Option<usize>,
+    pub seed: u64,
+}
+
+impl Default for ReductionOptions {
+    fn default() -> Self {
+        ReductionOptions {
+            perplexity: 30.0,
+            n_neighbors: 15,
+            iterations: None,
+            seed: 42,
+        }
+    }
+}
+
+/// Reduce the embeddings to 2D coordinates, all embeddings must have the same dimension.
+pub fn reduce(
+    data: &[Vec<f32>],
+    method: ReductionMethod,
+    options: &ReductionOptions,
+) -> Result<Vec<(f64, f64)>, anyhow::Error> {
+    let dimension = match data.first() {
+        Some(v) => v.len(),
+        None => return Ok(vec![]),
+    };
+
+    if dimension == 0 || data.iter().any(|v| v.len() != dimension) {
+        return Err(anyhow::anyhow!(
+            "The embeddings must have the same dimension and not be empty."
+        ));
+    }
+
+    if data.len() == 1 {
+        return Ok(vec![(0.0, 0.0)]);
+    }
+
+    let data = data
+        .iter()
+        .map(|v| v.iter().map(|x| *x as f64).collect())
+        .collect::<Vec<Vec<f64>>>();
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    let coordinates = match method {
+        ReductionMethod::Pca => pca(&data, 2, &mut rng),
+        ReductionMethod::Tsne | ReductionMethod::Umap => {
+            let data = if dimension > PCA_PREPROCESSING_DIMENSION {
+                info!(
+                    "Reduce the embeddings from {} to {} dimensions by PCA.",
+                    dimension, PCA_PREPROCESSING_DIMENSION
+                );
+                pca(&data, PCA_PREPROCESSING_DIMENSION, &mut rng)
+            } else {
+                data
+            };
+            let init = pca(&data, 2, &mut rng);
+
+            if method == ReductionMethod::Tsne {
+                tsne(&data, init, options, &mut rng)
+            } else {
+                umap(&data, init, options, &mut rng)
+            }
+        }
+    };
+
+    Ok(coordinates.into_iter().map(|v| (v[0], v[1])).collect())
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn normalize(v: &mut [f64]) -> bool {
+    let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm == 0.0 {
+        return false;
+    }
+
+    v.iter_mut().for_each(|x| *x /= norm);
+    true
+}
+
+/// Project the centered data on the top principal components. The components are computed one by one by the power iteration, and the previous components are removed from the candidate (Gram-Schmidt) in each step.
+fn pca(data: &[Vec<f64>], n_components: usize, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    let n = data.len();
+    let dimension = data[0].len();
+    let n_components = n_components.min(dimension);
+
+    let mut mean = vec![0.0; dimension];
+    for row in data {
+        for (i, v) in row.iter().enumerate() {
+            mean[i] += v / n as f64;
+        }
+    }
+    let centered = data
+        .iter()
+        .map(|row| row.iter().zip(mean.iter()).map(|(v, m)| v - m).collect())
+        .collect::<Vec<Vec<f64>>>();
+
+    let mut components: Vec<Vec<f64>> = Vec::with_capacity(n_components);
+    for _ in 0..n_components {
+        let mut component = (0..dimension)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect::<Vec<f64>>();
+
+        for _ in 0..PCA_MAX_ITERATIONS {
+            // X^T * (X * v), the covariance matrix is never built.
+            let mut next = vec![0.0; dimension];
+            for row in centered.iter() {
+                let projection = row
+                    .iter()
+                    .zip(component.iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<f64>();
+                for (i, v) in row.iter().enumerate() {
+                    next[i] += projection * v;
+                }
+            }
+
+            for previous in components.iter() {
+                let dot = next
+                    .iter()
+                    .zip(previous.iter())
+                    .map(|(a, b)| a * b)
+                    .sum::<f64>();
+                next.iter_mut()
+                    .zip(previous.iter())
+                    .for_each(|(a, b)| *a -= dot * b);
+            }
+
+            if !normalize(&mut next) {
+                // The remaining variance is zero, so the data is projected to zero on this component.
+                component = vec![0.0; dimension];
+                break;
+            }
+
+            let delta = squared_distance(&next, &component);
+            component = next;
+            if delta < 1e-12 {
+                break;
+            }
+        }
+
+        components.push(component);
+    }
+
+    centered
+        .iter()
+        .map(|row| {
+            let mut projected = components
+                .iter()
+                .map(|c| row.iter().zip(c.iter()).map(|(a, b)| a * b).sum::<f64>())
+                .collect::<Vec<f64>>();
+            projected.resize(n_components.max(2), 0.0);
+            projected
+        })
+        .collect()
+}
+
+/// Find the k nearest neighbours of each sample by brute force, the squared distances are returned in ascending order.
+fn nearest_neighbours(data: &[Vec<f64>], k: usize) -> Vec<Vec<(usize, f64)>> {
+    data.iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let mut distances = data
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(j, b)| (j, squared_distance(a, b)))
+                .collect::<Vec<(usize, f64)>>();
+
+            if distances.len() > k {
+                distances.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+                distances.truncate(k);
+            }
+            distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+            distances
+        })
+        .collect()
+}
+
+/// Scale the initial layout to make the standard deviation of the first axis equal to the target.
+fn scale_layout(layout: &mut [Vec<f64>], target_std: f64) {
+    let n = layout.len() as f64;
+    let mean = layout.iter().map(|v| v[0]).sum::<f64>() / n;
+    let std = (layout.iter().map(|v| (v[0] - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let scale = if std > 0.0 { target_std / std } else { 1.0 };
+
+    layout
+        .iter_mut()
+        .for_each(|v| v.iter_mut().for_each(|x| *x *= scale));
+}
+
+/// Merge the directed weights into a symmetric graph by the function, the graph is stored as the edge list.
+fn symmetrize<F>(graph: Vec<Vec<(usize, f64)>>, merge: F) -> Vec<(usize, usize, f64)>
+where
+    F: Fn(f64, f64) -> f64,
+{
+    let mut edges = graph
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().map(move |(j, w)| (i.min(*j), i.max(*j), *w)))
+        .collect::<Vec<(usize, usize, f64)>>();
+    edges.sort_by_key(|e| (e.0, e.1));
+
+    let mut merged: Vec<(usize, usize, f64)> = Vec::with_capacity(edges.len());
+    let mut index = 0;
+    while index < edges.len() {
+        let (i, j, w) = edges[index];
+        if index + 1 < edges.len() && (edges[index + 1].0, edges[index + 1].1) == (i, j) {
+            merged.push((i, j, merge(w, edges[index + 1].2)));
+            index += 2;
+        } else {
+            merged.push((i, j, merge(w, 0.0)));
+            index += 1;
+        }
+    }
+
+    merged
+}
+
+/// The conditional probabilities p(j|i) of t-SNE, the precision of each sample is searched to match the perplexity.
+fn conditional_probabilities(neighbours: &[(usize, f64)], perplexity: f64) -> Vec<(usize, f64)> {
+    let target_entropy = perplexity.ln();
+    let (mut beta, mut beta_min, mut beta_max) = (1.0, f64::NEG_INFINITY, f64::INFINITY);
+    let mut probabilities = vec![0.0; neighbours.len()];
+
+    for _ in 0..100 {
+        let min_distance = neighbours.first().map(|v| v.1).unwrap_or(0.0);
+        let mut sum = 0.0;
+        for (index, (_, d)) in neighbours.iter().enumerate() {
+            probabilities[index] = (-(d - min_distance) * beta).exp();
+            sum += probabilities[index];
+        }
+
+        let mut entropy = 0.0;
+        for (index, (_, d)) in neighbours.iter().enumerate() {
+            probabilities[index] /= sum;
+            entropy += beta * (d - min_distance) * probabilities[index];
+        }
+        entropy += sum.ln();
+
+        let diff = entropy - target_entropy;
+        if diff.abs() < 1e-5 {
+            break;
+        }
+
+        if diff > 0.0 {
+            beta_min = beta;
+            beta = if beta_max.is_infinite() {
+                beta * 2.0
+            } else {
+                (beta + beta_max) / 2.0
+            };
+        } else {
+            beta_max = beta;
+            beta = if beta_min.is_infinite() {
+                beta / 2.0
+            } else {
+                (beta + beta_min) / 2.0
+            };
+        }
+    }
+
+    neighbours
+        .iter()
+        .zip(probabilities)
+        .map(|((j, _), p)| (*j, p))
+        .collect()
+}
+
+/// A quadtree node for the Barnes-Hut approximation, the leaf node keeps its points so that the self-interaction can be skipped.
+struct QuadNode {
+    center_of_mass: (f64, f64),
+    count: usize,
+    width: f64,
+    children: Vec<QuadNode>,
+    points: Vec<usize>,
+}
+
+impl QuadNode {
+    fn build(
+        layout: &[Vec<f64>],
+        points: Vec<usize>,
+        bounds: (f64, f64, f64, f64),
+        depth: usize,
+    ) -> Self {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let count = points.len();
+        let center_of_mass = points.iter().fold((0.0, 0.0), |acc, i| {
+            (
+                acc.0 + layout[*i][0] / count as f64,
+                acc.1 + layout[*i][1] / count as f64,
+            )
+        });
+        let width = (max_x - min_x).max(max_y - min_y);
+
+        if count <= 1 || depth >= 32 {
+            return QuadNode {
+                center_of_mass,
+                count,
+                width,
+                children: vec![],
+                points,
+            };
+        }
+
+        let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let mut quadrants: Vec<Vec<usize>> = vec![vec![]; 4];
+        for i in points {
+            let index = (layout[i][0] > mid_x) as usize + 2 * (layout[i][1] > mid_y) as usize;
+            quadrants[index].push(i);
+        }
+
+        let children = quadrants
+            .into_iter()
+            .enumerate()
+            .filter(|(_, quadrant)| !quadrant.is_empty())
+            .map(|(index, quadrant)| {
+                let (x0, x1) = if index % 2 == 0 {
+                    (min_x, mid_x)
+                } else {
+                    (mid_x, max_x)
+                };
+                let (y0, y1) = if index / 2 == 0 {
+                    (min_y, mid_y)
+                } else {
+                    (mid_y, max_y)
+                };
+                QuadNode::build(layout, quadrant, (x0, y0, x1, y1), depth + 1)
+            })
+            .collect();
+
+        QuadNode {
+            center_of_mass,
+            count,
+            width,
+            children,
+            points: vec![],
+        }
+    }
+
+    /// Accumulate the unnormalized repulsive force and the normalization term Z of the point.
+    fn repulsive_force(&self, layout: &[Vec<f64>], i: usize, force: &mut (f64, f64), z: &mut f64) {
+        let (x, y) = (layout[i][0], layout[i][1]);
+
+        if self.children.is_empty() {
+            for j in self.points.iter().filter(|j| **j != i) {
+                let (dx, dy) = (x - layout[*j][0], y - layout[*j][1]);
+                let q = 1.0 / (1.0 + dx * dx + dy * dy);
+                *z += q;
+                force.0 += q * q * dx;
+                force.1 += q * q * dy;
+            }
+            return;
+        }
+
+        let (dx, dy) = (x - self.center_of_mass.0, y - self.center_of_mass.1);
+        let distance2 = dx * dx + dy * dy;
+        if distance2 > 0.0 && self.width / distance2.sqrt() < TSNE_THETA {
+            let q = 1.0 / (1.0 + distance2);
+            let mass = self.count as f64;
+            *z += mass * q;
+            force.0 += mass * q * q * dx;
+            force.1 += mass * q * q * dy;
+        } else {
+            for child in self.children.iter() {
+                child.repulsive_force(layout, i, force, z);
+            }
+        }
+    }
+}
+
+/// Barnes-Hut t-SNE, the input similarities are computed from the 3 * perplexity nearest neighbours.
+fn tsne(
+    data: &[Vec<f64>],
+    init: Vec<Vec<f64>>,
+    options: &ReductionOptions,
+    rng: &mut StdRng,
+) -> Vec<Vec<f64>> {
+    let n = data.len();
+    let iterations = options.iterations.unwrap_or(1000);
+    let perplexity = options.perplexity.min((n - 1) as f64 / 3.0).max(1.0);
+    let k = ((3.0 * perplexity) as usize).clamp(1, n - 1);
+
+    info!(
+        "Run t-SNE on {} samples with perplexity {} for {} iterations.",
+        n, perplexity, iterations
+    );
+    let neighbours = nearest_neighbours(data, k);
+    let conditional = neighbours
+        .iter()
+        .map(|row| conditional_probabilities(row, perplexity))
+        .collect::<Vec<Vec<(usize, f64)>>>();
+    let edges = symmetrize(conditional, |a, b| (a + b) / (2.0 * n as f64));
+
+    let mut layout = init;
+    scale_layout(&mut layout, 1e-4);
+    // Break the ties if the initial layout is degenerated.
+    layout
+        .iter_mut()
+        .for_each(|v| v.iter_mut().for_each(|x| *x += rng.gen_range(-1e-6..1e-6)));
+
+    let learning_rate = (n as f64 / TSNE_EARLY_EXAGGERATION / 4.0).max(50.0);
+    let mut velocity = vec![[0.0; 2]; n];
+    let mut gains = vec![[1.0; 2]; n];
+
+    for iteration in 0..iterations {
+        let (exaggeration, momentum) = if iteration < TSNE_EXAGGERATION_ITERATIONS {
+            (TSNE_EARLY_EXAGGERATION, 0.5)
+        } else {
+            (1.0, 0.8)
+        };
+
+        let mut attractive = vec![(0.0, 0.0); n];
+        for (i, j, p) in edges.iter() {
+            let (dx, dy) = (layout[*i][0] - layout[*j][0], layout[*i][1] - layout[*j][1]);
+            let pq = exaggeration * p / (1.0 + dx * dx + dy * dy);
+            attractive[*i].0 += pq * dx;
+            attractive[*i].1 += pq * dy;
+            attractive[*j].0 -= pq * dx;
+            attractive[*j].1 -= pq * dy;
+        }
+
+        let bounds = layout.iter().fold(
+            (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |b, v| (b.0.min(v[0]), b.1.min(v[1]), b.2.max(v[0]), b.3.max(v[1])),
+        );
+        let tree = QuadNode::build(&layout, (0..n).collect(), bounds, 0);
+        let mut repulsive = vec![(0.0, 0.0); n];
+        let mut z = 0.0;
+        for (i, force) in repulsive.iter_mut().enumerate() {
+            tree.repulsive_force(&layout, i, force, &mut z);
+        }
+        let z = z.max(f64::MIN_POSITIVE);
+
+        for i in 0..n {
+            let gradient = [
+                4.0 * (attractive[i].0 - repulsive[i].0 / z),
+                4.0 * (attractive[i].1 - repulsive[i].1 / z),
+            ];
+
+            for (y, g) in gradient.into_iter().enumerate() {
+                let (v, gain) = (&mut velocity[i][y], &mut gains[i][y]);
+                *gain = if (g > 0.0) != (*v > 0.0) {
+                    *gain + 0.2
+                } else {
+                    (*gain * 0.8_f64).max(0.01)
+                };
+                *v = momentum * *v - learning_rate * *gain * g;
+                layout[i][y] += *v;
+            }
+        }
+
+        if (iteration + 1) % 100 == 0 {
+            debug!("t-SNE iteration {}/{}", iteration + 1, iterations);
+        }
+    }
+
+    layout
+}
+
+/// A simplified UMAP, the layout is optimized by the SGD with negative sampling on the fuzzy simplicial set of the nearest neighbours.
+fn umap(
+    data: &[Vec<f64>],
+    init: Vec<Vec<f64>>,
+    options: &ReductionOptions,
+    rng: &mut StdRng,
+) -> Vec<Vec<f64>> {
+    let n = data.len();
+    let epochs = options.iterations.unwrap_or(200);
+    let k = options.n_neighbors.clamp(1, n - 1);
+
+    info!(
+        "Run UMAP on {} samples with {} neighbours for {} epochs.",
+        n, k, epochs
+    );
+    let neighbours = nearest_neighbours(data, k)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(j, d)| (j, d.sqrt()))
+                .collect::<Vec<(usize, f64)>>()
+        })
+        .collect::<Vec<Vec<(usize, f64)>>>();
+
+    // The membership strengths, rho is the distance to the nearest neighbour and sigma is searched to make the sum of the strengths equal to log2(k).
+    let target = (k as f64).log2().max(1e-3);
+    let memberships = neighbours
+        .into_iter()
+        .map(|row| {
+            let rho = row.iter().map(|v| v.1).find(|d| *d > 0.0).unwrap_or(0.0);
+            let (mut sigma, mut low, mut high) = (1.0, 0.0, f64::INFINITY);
+            for _ in 0..64 {
+                let sum = row
+                    .iter()
+                    .map(|(_, d)| (-(d - rho).max(0.0) / sigma).exp())
+                    .sum::<f64>();
+                if (sum - target).abs() < 1e-5 {
+                    break;
+                }
+
+                if sum > target {
+                    high = sigma;
+                    sigma = (low + high) / 2.0;
+                } else {
+                    low = sigma;
+                    sigma = if high.is_infinite() {
+                        sigma * 2.0
+                    } else {
+                        (low + high) / 2.0
+                    };
+                }
+            }
+
+            row.into_iter()
+                .map(|(j, d)| (j, (-(d - rho).max(0.0) / sigma.max(1e-12)).exp()))
+                .collect::<Vec<(usize, f64)>>()
+        })
+        .collect::<Vec<Vec<(usize, f64)>>>();
+    let edges = symmetrize(memberships, |a, b| a + b - a * b);
+    let max_weight = edges.iter().map(|e| e.2).fold(0.0, f64::max);
+
+    let mut layout = init;
+    scale_layout(&mut layout, 5.0);
+
+    // Each edge is sampled with the frequency which is proportional to its weight.
+    let epochs_per_sample = edges
+        .iter()
+        .map(|e| {
+            if e.2 > 0.0 {
+                max_weight / e.2
+            } else {
+                f64::INFINITY
+            }
+        })
+        .collect::<Vec<f64>>();
+    let mut next_sample = epochs_per_sample.clone();
+    let clip = |v: f64| v.clamp(-4.0, 4.0);
+
+    for epoch in 0..epochs {
+        let alpha = 1.0 - epoch as f64 / epochs as f64;
+
+        for (index, (i, j, _)) in edges.iter().enumerate() {
+            if next_sample[index] > (epoch + 1) as f64 {
+                continue;
+            }
+            next_sample[index] += epochs_per_sample[index];
+
+            for (head, tail) in [(*i, *j), (*j, *i)] {
+                let (dx, dy) = (
+                    layout[head][0] - layout[tail][0],
+                    layout[head][1] - layout[tail][1],
+                );
+                let d2 = dx * dx + dy * dy;
+                if d2 > 0.0 {
+                    let coefficient = -2.0 * UMAP_A * UMAP_B * d2.powf(UMAP_B - 1.0)
+                        / (1.0 + UMAP_A * d2.powf(UMAP_B));
+                    layout[head][0] += alpha * clip(coefficient * dx);
+                    layout[head][1] += alpha * clip(coefficient * dy);
+                }
+
+                for _ in 0..UMAP_NEGATIVE_SAMPLES {
+                    let other = rng.gen_range(0..n);
+                    if other == head {
+                        continue;
+                    }
+
+                    let (dx, dy) = (
+                        layout[head][0] - layout[other][0],
+                        layout[head][1] - layout[other][1],
+                    );
+                    let d2 = dx * dx + dy * dy;
+                    let coefficient =
+                        2.0 * UMAP_B / ((0.001 + d2) * (1.0 + UMAP_A * d2.powf(UMAP_B)));
+                    layout[head][0] += alpha
+                        * if d2 > 0.0 {
+                            clip(coefficient * dx)
+                        } else {
+                            4.0
+                        };
+                    layout[head][1] += alpha
+                        * if d2 > 0.0 {
+                            clip(coefficient * dy)
+                        } else {
+                            4.0
+                        };
+                }
+            }
+        }
+
+        if (epoch + 1) % 50 == 0 {
+            debug!("UMAP epoch {}/{}", epoch + 1, epochs);
+        }
+    }
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three well-separated clusters in 10 dimensions.
+    fn make_clusters(size: usize) -> (Vec<Vec<f32>>, Vec<usize>) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![];
+        let mut labels = vec![];
+        for label in 0..3 {
+            for _ in 0..size {
+                data.push(
+                    (0..10)
+                        .map(|i| {
+                            let center = if i % 3 == label { 10.0 } else { 0.0 };
+                            center + rng.gen_range(-1.0..1.0)
+                        })
+                        .collect(),
+                );
+                labels.push(label);
+            }
+        }
+
+        (data, labels)
+    }
+
+    // The mean distance between the samples in the same cluster should be smaller than the one between the clusters.
+    fn is_separated(coordinates: &[(f64, f64)], labels: &[usize]) -> bool {
+        let (mut intra, mut inter) = ((0.0, 0), (0.0, 0));
+        for i in 0..coordinates.len() {
+            for j in (i + 1)..coordinates.len() {
+                let (a, b) = (coordinates[i], coordinates[j]);
+                let d = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+                if labels[i] == labels[j] {
+                    intra = (intra.0 + d, intra.1 + 1);
+                } else {
+                    inter = (inter.0 + d, inter.1 + 1);
+                }
+            }
+        }
+
+        intra.0 / (intra.1 as f64) * 2.0 < inter.0 / (inter.1 as f64)
+    }
+
+    #[test]
+    fn test_reduce() {
+        let (data, labels) = make_clusters(20);
+        let options = ReductionOptions {
+            perplexity: 10.0,
+            ..Default::default()
+        };
+
+        for method in [
+            ReductionMethod::Pca,
+            ReductionMethod::Tsne,
+            ReductionMethod::Umap,
+        ] {
+            let coordinates = reduce(&data, method, &options).unwrap();
+            assert_eq!(coordinates.len(), data.len());
+            assert!(coordinates
+                .iter()
+                .all(|(x, y)| x.is_finite() && y.is_finite()));
+            assert!(is_separated(&coordinates, &labels), "{} failed", method);
+
+            // The same seed always produces the same coordinates.
+            assert_eq!(coordinates, reduce(&data, method, &options).unwrap());
+        }
+
+        assert_eq!(reduce(&[], ReductionMethod::Pca, &options).unwrap(), vec![]);
+        assert_eq!(
+            reduce(&[vec![1.0, 2.0]], ReductionMethod::Tsne, &options).unwrap(),
+            vec![(0.0, 0.0)]
+        );
+        assert!(reduce(&[vec![1.0, 2.0], vec![1.0]], ReductionMethod::Pca, &options).is_err());
+        assert_eq!(
+            "t-SNE".parse::<ReductionMethod>(),
+            Ok(ReductionMethod::Tsne)
+        );
+        assert!("mds".parse::<ReductionMethod>().is_err());
+    }
+
+    #[test]
+    fn test_pca() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // All variance is on the line y = 2x, so the second component is zero.
+        let data = (0..10)
+            .map(|i| vec![i as f64, 2.0 * i as f64])
+            .collect::<Vec<Vec<f64>>>();
+        let projected = pca(&data, 2, &mut rng);
+
+        let first = projected.iter().map(|v| v[0]).collect::<Vec<f64>>();
+        let step = (first[1] - first[0]).abs();
+        assert!((step - 5.0_f64.sqrt()).abs() < 1e-6);
+        assert!(projected.iter().all(|v| v[1].abs() < 1e-6));
+    }
+}
@@ -10,19 +10,92 @@ use std::collections::BTreeMap;
 
 pub const USERNAME_PLACEHOLDER: &str = "ANONYMOUS-USER-PLACEHOLDER";
 
+/// The role of a user, a role has all permissions of the lower roles.
+///
+/// * `Viewer` - Can read all data.
+/// * `Curator` - Can also create, update and delete the curated knowledges and subgraphs.
+/// * `Admin` - Can also manage the records which are owned by other users, import data and manage users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Curator,
+    Admin,
+}
+
+impl Role {
+    /// Parse the role from the role claim, it returns None if the role is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use biomedgps::api::auth::Role;
+    ///
+    /// assert_eq!(Role::from_claim("Admin"), Some(Role::Admin));
+    /// assert_eq!(Role::from_claim("guest"), None);
+    /// ```
+    pub fn from_claim(role: &str) -> Option<Role> {
+        match role.to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "curator" => Some(Role::Curator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Curator => "curator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct User {
     pub username: String,
     pub organizations: Vec<i32>,
-    pub projects: Vec<i32>
+    pub projects: Vec<i32>,
+    pub role: Role,
 }
 
 impl User {
     fn new(username: String) -> Self {
-        Self { 
+        // If the JWT verification is disabled, all users are the same placeholder user, so we treat it as an admin.
+        let role = if username == USERNAME_PLACEHOLDER {
+            Role::Admin
+        } else {
+            Role::Curator
+        };
+
+        Self {
             username,
             organizations: vec![-1],
-            projects: vec![-1]
+            projects: vec![-1],
+            role,
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Whether the user can manage a record which is owned by the owner.
+    pub fn can_manage(&self, owner: &str) -> bool {
+        self.is_admin() || self.username == owner
+    }
+
+    /// Check whether the user has the required role, it returns an error message for the 403 response if not.
+    pub fn check_role(&self, required_role: Role) -> Result<(), String> {
+        if self.role >= required_role {
+            Ok(())
+        } else {
+            Err(format!(
+                "Permission denied, the {} role is required but {} has the {} role.",
+                required_role.as_str(),
+                self.username,
+                self.role.as_str()
+            ))
         }
     }
 
@@ -39,7 +112,41 @@ impl User {
 #[oai(type = "bearer", checker = "jwt_token_checker")]
 pub struct CustomSecurityScheme(pub User);
 
+fn verify_token(jwt_secret_key: &str, token: &str) -> Option<BTreeMap<String, Value>> {
+    let key: Hmac<Sha256> = Hmac::new_from_slice(jwt_secret_key.as_bytes()).unwrap();
+    match token.verify_with_key(&key) {
+        Ok(claims) => Some(claims),
+        Err(err) => {
+            error!("Error: {}", err);
+            None
+        }
+    }
+}
+
+/// Get the username from a bearer token. It returns None if the JWT verification is disabled or the token is invalid.
+///
+/// It is used by the middlewares which run before the security scheme, such as the rate limiter.
+pub fn get_username_from_token(token: &str) -> Option<String> {
+    let jwt_secret_key = match std::env::var("JWT_SECRET_KEY") {
+        Ok(key) if !key.is_empty() => key,
+        _ => return None,
+    };
+
+    let claims = verify_token(&jwt_secret_key, token)?;
+    claims
+        .get("username")
+        .and_then(Value::as_str)
+        .map(|username| username.to_string())
+}
+
 async fn jwt_token_checker(_: &Request, bearer: Bearer) -> Option<User> {
+    get_user_from_token(&bearer.token)
+}
+
+/// Verify a bearer token and get the user from its claims, it returns None if the token is invalid. All users are the placeholder user if the JWT verification is disabled.
+///
+/// It is used by the endpoints which are not in the OpenAPI service, such as the websockets.
+pub fn get_user_from_token(token: &str) -> Option<User> {
     // Get jwt_secret_key from environment variable
     let default_user = Some(User::new(USERNAME_PLACEHOLDER.to_string()));
     let jwt_secret_key = match std::env::var("JWT_SECRET_KEY") {
@@ -55,14 +162,9 @@ async fn jwt_token_checker(_: &Request, bearer: Bearer) -> Option<User> {
 
     debug!("JWT_SECRET_KEY: {}", jwt_secret_key);
 
-    let key: Hmac<Sha256> = Hmac::new_from_slice(jwt_secret_key.as_bytes()).unwrap();
-    let token_str = bearer.token;
-    let claims: BTreeMap<String, Value> = match token_str.verify_with_key(&key) {
-        Ok(claims) => claims,
-        Err(err) => {
-            error!("Error: {}", err);
-            return None;
-        }
+    let claims = match verify_token(&jwt_secret_key, token) {
+        Some(claims) => claims,
+        None => return None,
     };
 
     let username = match claims.get("username").and_then(Value::as_str) {
@@ -101,6 +203,26 @@ async fn jwt_token_checker(_: &Request, bearer: Bearer) -> Option<User> {
     current_user.add_organizations(organizations);
     current_user.add_projects(projects);
 
+    // Be compatible with the old version, the token might not contain the role field, so we use the is_admin field or treat the user as a curator.
+    match claims.get("role").and_then(Value::as_str) {
+        Some(role) => match Role::from_claim(role) {
+            Some(role) => current_user.role = role,
+            None => {
+                error!("Error: unknown role {} in claims.", role);
+                return None;
+            }
+        },
+        None => {
+            if claims
+                .get("is_admin")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+            {
+                current_user.role = Role::Admin;
+            }
+        }
+    };
+
     info!("current_user: {:?}", current_user);
 
     Some(current_user)
@@ -0,0 +1,242 @@
+//! This module defines the websocket endpoint of the collaborative subgraph editing, see [`crate::model::collab`] for the messages.
+
+use crate::api::auth::{get_user_from_token, Role, User};
+use crate::api::route::check_workspace_role;
+use crate::api::schema::{ApiError, ErrorCode, SubgraphIdQuery};
+use crate::model::collab::{
+    apply_operations, broadcast, join_room, leave_room, ClientMessage, ServerMessage,
+};
+use crate::model::core::Subgraph;
+use crate::model::workspace::WorkspaceRole;
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use poem::http::header;
+use poem::web::websocket::{Message, WebSocket};
+use poem::web::{Data, Path, Query};
+use poem::{handler, IntoResponse, Request, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Debug, Deserialize)]
+pub struct SubgraphSocketParams {
+    /// The access token, the browsers cannot set the Authorization header of a websocket.
+    token: Option<String>,
+}
+
+fn error_response(err: ApiError) -> Response {
+    Response::builder()
+        .status(err.code.status())
+        .content_type("application/json")
+        .body(serde_json::json!(err).to_string())
+}
+
+/// Connect to `/ws/subgraphs/:id` (such as `/ws/subgraphs/<uuid>?token=<access token>`) to edit a subgraph with others. The access token is in the token param or the Authorization header.
+///
+/// The client gets a snapshot of the subgraph and the users who are editing it after it connects, then it receives the operations, cursors and presence changes of all users. The viewers only receive the messages, the curators (and the editors of the workspace if the subgraph is in a workspace) can also send the operations.
+#[handler]
+pub async fn subgraph_socket(
+    req: &Request,
+    Path(id): Path<String>,
+    Query(params): Query<SubgraphSocketParams>,
+    ws: WebSocket,
+    pool: Data<&Arc<sqlx::PgPool>>,
+) -> Response {
+    let token = params.token.unwrap_or_else(|| {
+        req.header(header::AUTHORIZATION)
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default()
+            .to_string()
+    });
+    let user = match get_user_from_token(&token) {
+        Some(user) => user,
+        None => {
+            let err = "Invalid access token.".to_string();
+            warn!("{}", err);
+            return error_response(ApiError::new(ErrorCode::Forbidden, err));
+        }
+    };
+
+    if let Err(e) = SubgraphIdQuery::new(&id) {
+        let err = format!("Failed to parse subgraph id: {}", e);
+        warn!("{}", err);
+        return error_response(ApiError::from_error(err, &e));
+    }
+
+    let subgraph = match Subgraph::get_record(&pool, &id).await {
+        Ok(Some(subgraph)) => subgraph,
+        Ok(None) => {
+            let err = format!("Cannot find the subgraph {}.", id);
+            warn!("{}", err);
+            return error_response(ApiError::new(ErrorCode::NotFound, err));
+        }
+        Err(e) => {
+            let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+            warn!("{}", err);
+            return error_response(ApiError::from_error(err, &*e));
+        }
+    };
+
+    let can_edit = match subgraph.workspace_id {
+        Some(workspace_id) => {
+            if let Err(e) =
+                check_workspace_role(&pool, &user, workspace_id, WorkspaceRole::Viewer).await
+            {
+                warn!("{}", e.message);
+                return error_response(e);
+            }
+
+            user.check_role(Role::Curator).is_ok()
+                && check_workspace_role(&pool, &user, workspace_id, WorkspaceRole::Editor)
+                    .await
+                    .is_ok()
+        }
+        None => user.check_role(Role::Curator).is_ok(),
+    };
+
+    let pool = pool.0.clone();
+    ws.on_upgrade(move |socket| async move {
+        let (mut sink, mut stream) = socket.split();
+        let username = user.username.clone();
+
+        // Join the room before the snapshot is fetched, so no operation is missed between them.
+        let (mut receiver, users) = join_room(&id, &username);
+        info!("{} joins the subgraph {}, can edit: {}.", username, id, can_edit);
+        broadcast(&id, &ServerMessage::Presence { users });
+        let snapshot = fetch_snapshot(&pool, &id).await;
+        if sink.send(Message::Text(snapshot.to_text())).await.is_err() {
+            leave(&id, &username);
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | None => break,
+                        // The pings are answered by the server automatically.
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => {
+                            warn!("The websocket of {} on the subgraph {} fails: {}", username, id, e);
+                            break;
+                        }
+                    };
+
+                    if let Some(reply) = handle_message(&pool, &id, &user, can_edit, &text).await {
+                        if sink.send(Message::Text(reply.to_text())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                message = receiver.recv() => {
+                    let text = match message {
+                        Ok(text) => text,
+                        // The client misses some messages, so it gets the latest subgraph instead.
+                        Err(RecvError::Lagged(count)) => {
+                            warn!("{} lags behind {} messages on the subgraph {}.", username, count, id);
+                            fetch_snapshot(&pool, &id).await.to_text()
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+
+                    if sink.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        leave(&id, &username);
+    })
+    .into_response()
+}
+
+fn leave(id: &str, username: &str) {
+    info!("{} leaves the subgraph {}.", username, id);
+    let users = leave_room(id, username);
+    broadcast(id, &ServerMessage::Presence { users });
+}
+
+async fn fetch_snapshot(pool: &sqlx::PgPool, id: &str) -> ServerMessage {
+    match Subgraph::get_record(pool, id).await {
+        Ok(Some(subgraph)) => ServerMessage::Snapshot { subgraph },
+        Ok(None) => ServerMessage::Error {
+            message: format!("The subgraph {} has been deleted.", id),
+        },
+        Err(e) => ServerMessage::Error {
+            message: format!("Failed to fetch the subgraph {}: {}", id, e),
+        },
+    }
+}
+
+/// Handle a message of a client, the operations and cursors are broadcast to the room. It returns the error which is only sent to the client.
+async fn handle_message(
+    pool: &sqlx::PgPool,
+    id: &str,
+    user: &User,
+    can_edit: bool,
+    text: &str,
+) -> Option<ServerMessage> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(ServerMessage::Error {
+                message: format!("Invalid message: {}", e),
+            })
+        }
+    };
+
+    match message {
+        ClientMessage::Cursor { cursor } => {
+            broadcast(
+                id,
+                &ServerMessage::Cursor {
+                    username: user.username.clone(),
+                    cursor,
+                },
+            );
+            None
+        }
+        ClientMessage::Operations { .. } if !can_edit => Some(ServerMessage::Error {
+            message: format!(
+                "Permission denied, {} cannot edit the subgraph.",
+                user.username
+            ),
+        }),
+        ClientMessage::Operations { operations } => {
+            match apply_operations(pool, id, &operations).await {
+                Ok(Some(subgraph)) => {
+                    debug!(
+                        "{} saves {} operations as the revision {} of the subgraph {}.",
+                        user.username,
+                        operations.len(),
+                        subgraph.revision,
+                        id
+                    );
+                    broadcast(
+                        id,
+                        &ServerMessage::Operations {
+                            username: user.username.clone(),
+                            operations,
+                            revision: subgraph.revision,
+                        },
+                    );
+                    None
+                }
+                Ok(None) => Some(ServerMessage::Error {
+                    message: format!("The subgraph {} has been deleted.", id),
+                }),
+                Err(e) => {
+                    warn!(
+                        "Failed to apply the operations on the subgraph {}: {}",
+                        id, e
+                    );
+                    Some(ServerMessage::Error {
+                        message: format!("Failed to apply the operations: {}", e),
+                    })
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,749 @@
+//! This module defines the middlewares of the API, such as the request id, the rate limiter, the ETag support and the database router.
+
+use crate::api::auth::get_username_from_token;
+use crate::api::schema::{ApiError, ErrorCode};
+use crate::model::database::{
+    split_database_path, DatabasePools, DATABASE, DATABASE_HEADER, DEFAULT_DATABASE,
+};
+use crate::telemetry::extract_context;
+use log::{debug, warn};
+use lru::LruCache;
+use poem::http::{header, Method, StatusCode, Uri};
+use poem::{async_trait, Endpoint, IntoResponse, Middleware, Request, Response, Result};
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// The routes which are expensive, they share a stricter budget.
+pub const STRICT_ROUTES: [&str; 5] = [
+    "/api/v1/similarity-nodes",
+    "/api/v1/embedding-ops",
+    "/api/v1/llm",
+    "/api/v1/compounds/structure-search",
+    "/api/v1/graphs/from-list",
+];
+
+/// The GET routes whose underlying data is versioned (only changed by importing data or updating a record), so the clients can cache them with the ETag.
+pub const ETAG_ROUTES: [&str; 5] = [
+    "/api/v1/statistics",
+    "/api/v1/entity-metadata",
+    "/api/v1/relation-metadata",
+    "/api/v1/entity-colormap",
+    "/api/v1/subgraphs",
+];
+
+/// The GET routes which are served by the primary database even if a read replica is set, because they write (such as the publications which are cached in the database) or their results are read back right after a write (such as the curated knowledges and the revisions of the subgraphs).
+pub const PRIMARY_ROUTES: [&str; 8] = [
+    "/api/v1/publications",
+    "/api/v1/curated-knowledges",
+    "/api/v1/curated-graph",
+    "/api/v1/subgraphs",
+    "/api/v1/workspaces",
+    "/api/v1/notifications",
+    "/api/v1/saved-queries",
+    "/ws/",
+];
+
+/// Whether a request can be served by the read replica, i.e. a GET or HEAD request which is not to the `PRIMARY_ROUTES` or the annotations of an entity (they are cached in the database).
+///
+/// # Example
+///
+/// ```
+/// use biomedgps::api::middleware::is_read_only_request;
+/// use poem::http::Method;
+///
+/// assert!(is_read_only_request(&Method::GET, "/api/v1/relations"));
+/// assert!(!is_read_only_request(&Method::POST, "/api/v1/relations/exists"));
+/// assert!(!is_read_only_request(&Method::GET, "/api/v1/subgraphs/1"));
+/// assert!(!is_read_only_request(&Method::GET, "/api/v1/entities/Gene/ENTREZ:7157/annotations"));
+/// ```
+pub fn is_read_only_request(method: &Method, path: &str) -> bool {
+    (method == Method::GET || method == Method::HEAD)
+        && !PRIMARY_ROUTES.iter().any(|route| path.starts_with(route))
+        && !path.ends_with("/annotations")
+}
+
+tokio::task_local! {
+    /// The id of the current request, it is set by the [`RequestId`] middleware.
+    pub static REQUEST_ID: String;
+}
+
+/// The id of the current request, None if it is not called in a request.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+// The least recently used buckets are dropped when the number of buckets exceeds the limit.
+const MAX_BUCKETS: usize = 10000;
+
+/// A token bucket which is refilled continuously.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    // Tokens per second
+    refill_rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket which allows `requests_per_minute` requests in a minute.
+    pub fn new(requests_per_minute: u32, now: Instant) -> Self {
+        TokenBucket {
+            capacity: requests_per_minute as f64,
+            refill_rate: requests_per_minute as f64 / 60.0,
+            tokens: requests_per_minute as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Take a token from the bucket.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The request is allowed.
+    /// * `Err(u64)` - The request is rejected, the value is the seconds to wait before the next token is available.
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), u64> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after = ((1.0 - self.tokens) / self.refill_rate).ceil() as u64;
+            Err(retry_after.max(1))
+        }
+    }
+}
+
+/// The default max number of requests per minute for each client.
+pub const DEFAULT_RATE_LIMIT: u32 = 600;
+/// The default max number of requests per minute to the `STRICT_ROUTES` for each client.
+pub const DEFAULT_STRICT_RATE_LIMIT: u32 = 60;
+
+/// A reverse proxy whose X-Forwarded-For header is trusted, it is an IP address (such as `127.0.0.1`) or a network (such as `10.0.0.0/8`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl TrustedProxy {
+    /// Whether the address is the proxy or in the network of the proxy.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for TrustedProxy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid trusted proxy: {}, it must be an IP address or a network, such as 10.0.0.0/8",
+                value
+            )
+        };
+        let (addr, prefix_len) = match value.trim().split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (value.trim(), None),
+        };
+
+        let network = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(invalid)?,
+            None => max_prefix_len,
+        };
+
+        Ok(TrustedProxy {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// The rate limiter middleware, it limits the requests per IP and per authenticated user with the token bucket algorithm.
+///
+/// The routes in the `STRICT_ROUTES` share a stricter budget. The rejected requests get a 429 response with a Retry-After header. A budget of 0 means no limit. The clones share the budgets and the buckets, so the budgets can be changed by [`RateLimit::set_limits`] while the server is running. At most 10000 buckets are kept, the least recently used ones are dropped.
+#[derive(Clone)]
+pub struct RateLimit {
+    requests_per_minute: Arc<AtomicU32>,
+    strict_requests_per_minute: Arc<AtomicU32>,
+    buckets: Arc<Mutex<LruCache<String, TokenBucket>>>,
+    trusted_proxies: Arc<Vec<TrustedProxy>>,
+}
+
+impl RateLimit {
+    pub fn new(requests_per_minute: u32, strict_requests_per_minute: u32) -> Self {
+        RateLimit {
+            requests_per_minute: Arc::new(AtomicU32::new(requests_per_minute)),
+            strict_requests_per_minute: Arc::new(AtomicU32::new(strict_requests_per_minute)),
+            buckets: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(MAX_BUCKETS).unwrap(),
+            ))),
+            trusted_proxies: Arc::new(vec![]),
+        }
+    }
+
+    /// Trust the X-Forwarded-For header of the requests from the proxies, the header of the other clients is ignored, so they cannot get a new bucket by forging it.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<TrustedProxy>) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+
+    /// Change the budgets, the buckets of the clients are refilled with the new budgets when they are used next time.
+    pub fn set_limits(&self, requests_per_minute: u32, strict_requests_per_minute: u32) {
+        self.requests_per_minute
+            .store(requests_per_minute, Ordering::Relaxed);
+        self.strict_requests_per_minute
+            .store(strict_requests_per_minute, Ordering::Relaxed);
+    }
+
+    /// The budgets of the default routes and the `STRICT_ROUTES`.
+    pub fn get_limits(&self) -> (u32, u32) {
+        (
+            self.requests_per_minute.load(Ordering::Relaxed),
+            self.strict_requests_per_minute.load(Ordering::Relaxed),
+        )
+    }
+
+    /// The client is identified by the username in the access token if the JWT verification is enabled, otherwise by the IP address. The X-Forwarded-For header is only used if the request comes from a trusted proxy, the client is the last address in it which is not a trusted proxy.
+    fn client_key(&self, req: &Request) -> String {
+        if let Some(token) = req
+            .header(header::AUTHORIZATION)
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            if let Some(username) = get_username_from_token(token) {
+                return format!("user:{}", username);
+            }
+        }
+
+        let peer_ip = match req.remote_addr().as_socket_addr() {
+            Some(addr) => addr.ip(),
+            None => return format!("ip:{}", req.remote_addr()),
+        };
+
+        format!(
+            "ip:{}",
+            self.forwarded_ip(peer_ip, req.header("X-Forwarded-For"))
+        )
+    }
+
+    /// The IP address of the client behind the trusted proxies, it is the peer address if the peer is not a trusted proxy.
+    fn forwarded_ip(&self, peer_ip: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let is_trusted = |ip: &IpAddr| self.trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+        if !is_trusted(&peer_ip) {
+            return peer_ip;
+        }
+
+        // Each proxy appends the address which it receives the request from, so the addresses on the left may be forged by the client.
+        let mut client_ip = peer_ip;
+        for ip in forwarded_for.unwrap_or_default().rsplit(',') {
+            match ip.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    client_ip = ip;
+                    if !is_trusted(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        client_ip
+    }
+
+    fn check(&self, key: String, requests_per_minute: u32) -> Result<(), u64> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let bucket = buckets.get_or_insert_mut(key, || TokenBucket::new(requests_per_minute, now));
+        // The budget has been changed since the bucket was created.
+        if bucket.capacity != requests_per_minute as f64 {
+            *bucket = TokenBucket::new(requests_per_minute, now);
+        }
+        bucket.try_acquire(now)
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimit {
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            ep,
+            limiter: self.clone(),
+        }
+    }
+}
+
+pub struct RateLimitEndpoint<E> {
+    ep: E,
+    limiter: RateLimit,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for RateLimitEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let path = req.uri().path().to_string();
+        let is_strict = STRICT_ROUTES.iter().any(|route| path.starts_with(route));
+        let (default_limit, strict_limit) = self.limiter.get_limits();
+        let (scope, requests_per_minute) = if is_strict {
+            ("strict", strict_limit)
+        } else {
+            ("default", default_limit)
+        };
+
+        if requests_per_minute > 0 {
+            let key = format!("{}:{}", scope, self.limiter.client_key(&req));
+            if let Err(retry_after) = self.limiter.check(key.clone(), requests_per_minute) {
+                warn!("Too many requests from {} to {}.", key, path);
+                let body = serde_json::json!(ApiError::new(
+                    ErrorCode::TooManyRequests,
+                    format!(
+                        "Too many requests, please retry after {} seconds.",
+                        retry_after
+                    ),
+                ));
+                return Ok(Response::builder()
+                    .status(StatusCode::TOO_MANY_REQUESTS)
+                    .header(header::RETRY_AFTER, retry_after.to_string())
+                    .content_type("application/json")
+                    .body(body.to_string()));
+            }
+            debug!("Rate limit passed for {} to {}.", key, path);
+        }
+
+        self.ep.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+/// The header which carries the request id, the id from the client (or a proxy) is reused if it is valid, otherwise a new one is generated.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// The max length of the request id from the client.
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Whether the request id from the client is acceptable, only the ids with at most 64 characters of `[A-Za-z0-9._-]` are kept, so they are safe to be logged and echoed back.
+pub fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+/// The request id middleware, it assigns an id to each request (or propagates the `X-Request-Id` header), so the error responses (the `request_id` field) and the logs of a request can be matched. The id is returned in the `X-Request-Id` header.
+///
+/// Each request is wrapped in a `request` tracing span with the request id, the method, the route and the user, and a completion event with the status and the latency is emitted. The failed requests are logged as warnings.
+///
+/// NOTICE: It must be the outermost middleware which returns the responses, so that the errors of the other middlewares (such as the rate limiter) also have the id.
+#[derive(Clone, Default)]
+pub struct RequestId;
+
+impl RequestId {
+    pub fn new() -> Self {
+        RequestId
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RequestId {
+    type Output = RequestIdEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequestIdEndpoint { ep }
+    }
+}
+
+pub struct RequestIdEndpoint<E> {
+    ep: E,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for RequestIdEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let request_id = match req.header(REQUEST_ID_HEADER) {
+            Some(request_id) if is_valid_request_id(request_id) => request_id.to_string(),
+            _ => uuid::Uuid::new_v4().to_string(),
+        };
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let user = req
+            .header(header::AUTHORIZATION)
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(get_username_from_token)
+            .unwrap_or_else(|| "-".to_string());
+
+        // The otel.* and http.* fields are the attributes of the exported span, see the telemetry module.
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %method,
+            route = %path,
+            user = %user,
+            otel.name = %format!("{} {}", method, path),
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+            http.status_code = tracing::field::Empty
+        );
+        span.set_parent(extract_context(req.headers()));
+        let start = Instant::now();
+        let mut resp = match REQUEST_ID
+            .scope(request_id.clone(), self.ep.call(req))
+            .instrument(span.clone())
+            .await
+        {
+            Ok(resp) => resp.into_response(),
+            Err(err) => err.into_response(),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let status = resp.status();
+        span.record("http.status_code", status.as_u16());
+        if status.is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        span.in_scope(|| {
+            if status.is_client_error() || status.is_server_error() {
+                tracing::warn!(
+                    status = status.as_u16(),
+                    latency_ms,
+                    "[{}] {} {} -> {}",
+                    request_id,
+                    method,
+                    path,
+                    status
+                );
+            } else {
+                tracing::info!(
+                    status = status.as_u16(),
+                    latency_ms,
+                    "[{}] {} {} -> {}",
+                    request_id,
+                    method,
+                    path,
+                    status
+                );
+            }
+        });
+
+        if let Ok(value) = header::HeaderValue::from_str(&request_id) {
+            resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        Ok(resp)
+    }
+}
+
+/// Compute a strong ETag from the response body.
+pub fn compute_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex = digest
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("\"{}\"", hex)
+}
+
+/// Whether the If-None-Match header matches the ETag, the header may contain several ETags or `*`.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// The ETag middleware, it adds an ETag header to the successful GET responses of the `ETAG_ROUTES` and returns 304 Not Modified if the If-None-Match header matches the ETag.
+///
+/// NOTICE: It must be applied before the compression middleware, so that the ETag is computed from the uncompressed body.
+#[derive(Clone, Default)]
+pub struct ETag;
+
+impl ETag {
+    pub fn new() -> Self {
+        ETag
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for ETag {
+    type Output = ETagEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ETagEndpoint { ep }
+    }
+}
+
+pub struct ETagEndpoint<E> {
+    ep: E,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for ETagEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let path = req.uri().path().to_string();
+        let is_versioned =
+            req.method() == Method::GET && ETAG_ROUTES.iter().any(|route| path.starts_with(route));
+
+        if !is_versioned {
+            return self.ep.call(req).await.map(IntoResponse::into_response);
+        }
+
+        let if_none_match = req
+            .header(header::IF_NONE_MATCH)
+            .map(|value| value.to_string());
+        let resp = self.ep.call(req).await?.into_response();
+        if resp.status() != StatusCode::OK {
+            return Ok(resp);
+        }
+
+        let (parts, body) = resp.into_parts();
+        let body = body.into_bytes().await?;
+        let etag = compute_etag(&body);
+
+        if let Some(if_none_match) = if_none_match {
+            if etag_matches(&if_none_match, &etag) {
+                debug!("The ETag of {} is not modified.", path);
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_MODIFIED)
+                    .header(header::ETAG, etag)
+                    .finish());
+            }
+        }
+
+        let mut resp = Response::from_parts(parts, body.into());
+        resp.headers_mut()
+            .insert(header::ETAG, etag.parse().unwrap());
+        Ok(resp)
+    }
+}
+
+/// The database router middleware, it selects the database of a request by the `/db/<name>` prefix of the url (such as `/db/staging/api/v1/statistics`) or the `X-Database` header, and replaces the shared database pool of the request, so all routes query the selected database. The url prefix takes precedence over the header, the default database is used if neither is set, and the requests to an unknown database get a 404 response.
+///
+/// The read-only requests (see [`is_read_only_request`]) to the default database are served by its read replica while the replica is up, the other requests are served by the primary database.
+///
+/// NOTICE: It must be applied inside the `AddData` middleware of the default pool so that it can replace the pool, and outside the rate limiter and the ETag middleware so that they see the routes without the prefix.
+#[derive(Clone)]
+pub struct DatabaseRouter {
+    pools: Arc<DatabasePools>,
+}
+
+impl DatabaseRouter {
+    pub fn new(pools: Arc<DatabasePools>) -> Self {
+        DatabaseRouter { pools }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for DatabaseRouter {
+    type Output = DatabaseRouterEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        DatabaseRouterEndpoint {
+            ep,
+            pools: self.pools.clone(),
+        }
+    }
+}
+
+pub struct DatabaseRouterEndpoint<E> {
+    ep: E,
+    pools: Arc<DatabasePools>,
+}
+
+#[async_trait]
+impl<E: Endpoint> Endpoint for DatabaseRouterEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let path = req.uri().path().to_string();
+        let database = match split_database_path(&path) {
+            Some((database, route)) => {
+                let uri = match req.uri().query() {
+                    Some(query) => format!("{}?{}", route, query),
+                    None => route.to_string(),
+                };
+                match uri.parse::<Uri>() {
+                    Ok(uri) => req.set_uri(uri),
+                    Err(e) => {
+                        warn!("Failed to strip the database from {}: {}", path, e);
+                        return Ok(StatusCode::BAD_REQUEST.into_response());
+                    }
+                }
+                database.to_string()
+            }
+            None => req
+                .header(DATABASE_HEADER)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .unwrap_or_else(|| DEFAULT_DATABASE.to_string()),
+        };
+
+        let pool = if is_read_only_request(req.method(), req.uri().path()) {
+            self.pools.get_for_read(&database)
+        } else {
+            self.pools.get(&database)
+        };
+        let pool = match pool {
+            Some(pool) => pool,
+            None => {
+                warn!("The database {} of {} is not found.", database, path);
+                let body = serde_json::json!(ApiError::new(
+                    ErrorCode::NotFound,
+                    format!(
+                        "The database {} is not found, see /api/v1/databases for the available databases.",
+                        database
+                    ),
+                ));
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .content_type("application/json")
+                    .body(body.to_string()));
+            }
+        };
+
+        debug!("Route {} to the database {}.", path, database);
+        req.extensions_mut().insert(pool);
+        DATABASE
+            .scope(database, self.ep.call(req))
+            .await
+            .map(IntoResponse::into_response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+        assert!(bucket.try_acquire(now).is_ok());
+        assert!(bucket.try_acquire(now).is_ok());
+        // The bucket is refilled with 1 token every 30 seconds.
+        assert_eq!(bucket.try_acquire(now), Err(30));
+        assert!(bucket.try_acquire(now + Duration::from_secs(30)).is_ok());
+        assert!(bucket.try_acquire(now + Duration::from_secs(31)).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_reload() {
+        let limiter = RateLimit::new(1, 1);
+        assert!(limiter.check("ip:1".to_string(), 1).is_ok());
+        assert!(limiter.check("ip:1".to_string(), 1).is_err());
+
+        // The clones share the budgets, and the bucket is refilled with the new budget.
+        limiter.clone().set_limits(2, 1);
+        assert_eq!(limiter.get_limits(), (2, 1));
+        assert!(limiter.check("ip:1".to_string(), 2).is_ok());
+        assert!(limiter.check("ip:1".to_string(), 2).is_ok());
+        assert!(limiter.check("ip:1".to_string(), 2).is_err());
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let proxy = "10.0.0.0/8".parse::<TrustedProxy>().unwrap();
+        assert!(proxy.contains(&"10.1.2.3".parse().unwrap()));
+        assert!(!proxy.contains(&"11.1.2.3".parse().unwrap()));
+        assert!(!proxy.contains(&"::1".parse().unwrap()));
+        assert!("::1"
+            .parse::<TrustedProxy>()
+            .unwrap()
+            .contains(&"::1".parse().unwrap()));
+        assert!("0.0.0.0/0"
+            .parse::<TrustedProxy>()
+            .unwrap()
+            .contains(&"1.2.3.4".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<TrustedProxy>().is_err());
+        assert!("proxy".parse::<TrustedProxy>().is_err());
+
+        let peer = "10.0.0.2".parse::<IpAddr>().unwrap();
+        let client = "203.0.113.7".parse::<IpAddr>().unwrap();
+        // The forwarded addresses of an untrusted peer are ignored.
+        let limiter = RateLimit::new(1, 1);
+        assert_eq!(limiter.forwarded_ip(peer, Some("203.0.113.7")), peer);
+
+        let limiter = limiter.with_trusted_proxies(vec![proxy]);
+        assert_eq!(limiter.forwarded_ip(peer, Some("203.0.113.7")), client);
+        // The client cannot forge its address by prepending one.
+        assert_eq!(
+            limiter.forwarded_ip(peer, Some("198.51.100.1, 203.0.113.7, 10.0.0.3")),
+            client
+        );
+        assert_eq!(limiter.forwarded_ip(peer, None), peer);
+        assert_eq!(limiter.forwarded_ip(client, Some("198.51.100.1")), client);
+    }
+
+    #[test]
+    fn test_rate_limit_buckets() {
+        let limiter = RateLimit::new(1, 1);
+        for i in 0..MAX_BUCKETS + 10 {
+            assert!(limiter.check(format!("ip:{}", i), 1).is_ok());
+        }
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_BUCKETS);
+    }
+
+    #[tokio::test]
+    async fn test_request_id() {
+        assert_eq!(current_request_id(), None);
+        let err = REQUEST_ID
+            .scope("abc".to_string(), async {
+                ApiError::new(ErrorCode::NotFound, "Not found".to_string())
+            })
+            .await;
+        assert_eq!(err.request_id, Some("abc".to_string()));
+
+        assert!(is_valid_request_id("3f2a-01.b_c"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("abc def"));
+        assert!(!is_valid_request_id("abc\nINFO forged"));
+        assert!(!is_valid_request_id(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn test_etag() {
+        let etag = compute_etag(b"{}");
+        assert_eq!(etag, compute_etag(b"{}"));
+        assert_ne!(etag, compute_etag(b"[]"));
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"abc\", W/{}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"abc\"", &etag));
+    }
+}
@@ -2,4 +2,6 @@
 
 pub mod route;
 pub mod schema;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod middleware;
+pub mod collab;
@@ -1,21 +1,100 @@
 //! This module defines the routes of the API.
 
-use crate::api::auth::{CustomSecurityScheme, USERNAME_PLACEHOLDER};
+use crate::api::auth::{CustomSecurityScheme, Role, User, USERNAME_PLACEHOLDER};
+use crate::api::middleware::{RateLimit, DEFAULT_RATE_LIMIT, DEFAULT_STRICT_RATE_LIMIT};
 use crate::api::schema::{
-    ApiTags, DeleteResponse, GetEntityColorMapResponse, GetGraphResponse, GetRecordsResponse,
-    GetRelationCountResponse, GetStatisticsResponse, GetWholeTableResponse, NodeIdsQuery,
-    Pagination, PaginationQuery, PostResponse, SimilarityNodeQuery, SubgraphIdQuery,
+    ApiError, ApiTags, BatchSimilarityRequest, BulkDeleteResponse, ConfigReport,
+    ConfigReportResponse, DeleteResponse, EnrichmentRequest, EntityMergeRequest, ErrorCode,
+    GeneResolutionRequest, GetAggregateResponse, GetBatchSimilarityResponse, GetDatabasesResponse,
+    GetDatasetVersionDiffResponse, GetEmbeddingOpsResponse, GetEnrichmentResponse,
+    GetEntity2DResponse, GetEntityAnnotationsResponse, GetEntityColorMapResponse,
+    GetEntityDetailResponse, GetEntityRankingResponse, GetGeneResolutionResponse,
+    GetGraphFromListResponse, GetGraphResponse, GetGraphSchemaResponse,
+    GetMaterializedGraphsResponse, GetPublicationResponse, GetRdfResponse, GetRecordsResponse,
+    GetRelationCountResponse, GetRelationEvidenceResponse, GetRelationExistenceResponse,
+    GetRelationTypeGroupsResponse, GetStatisticsResponse, GetStructureSearchResponse,
+    GetSubgraphDiffResponse, GetTriplesResponse, GetWholeTableResponse, GraphFromList,
+    GraphFromListRequest, GraphQuery, GraphThemeResponse, ImportRelationTypesResponse,
+    InvalidateMaterializedGraphsResponse, NodeIdsQuery, OntologyRollupRequest, Pagination,
+    PaginationQuery, PostResponse, PutSubgraphResponse, RelationExistenceRequest,
+    RunSavedQueryResponse, SimilarityNodeQuery, StructureSearchRequest, SubgraphIdQuery,
+    SubgraphMergeRequest, WorkspaceMemberRequest,
 };
+use crate::config::{get_config_status, reload_config};
+use crate::model::annotation::EntityAnnotations;
+use crate::model::biolink::{BiolinkMapper, BiolinkTerm};
+use crate::model::cache::{cached, cached_with_refresh, invalidate_cache, CacheScope};
+use crate::model::collab::{broadcast, ServerMessage};
 use crate::model::core::{
-    Entity, Entity2D, EntityMetadata, KnowledgeCuration, RecordResponse, Relation, RelationCount,
-    RelationMetadata, Statistics, Subgraph,
+    get_aggregate_fields, stream_records, AggregateRecord, CheckData, Coordinate2D, CountStrategy,
+    DatabaseMetadata, DatasetVersion, DatasetVersionDiff, DegreeStatistics, DeletionResult,
+    EmbeddingModel, Entity, Entity2D, Entity2DMap, Entity2DViewport, EntityAttribute, EntityDetail,
+    EntityEmbedding, EntityMetadata, ImportJob, KnowledgeCuration, ProjectedRecordResponse,
+    RecordFormat, RecordResponse, Relation, RelationCount, RelationEvidence, RelationExistence,
+    RelationMetadata, SavedQuery, SavedQueryResult, Statistics, Subgraph, TableName,
+    DEFAULT_MAP_BINS, DEFAULT_MAX_GROUPS, ENTITY_LABEL_REGEX, MAX_MAP_BINS, MAX_MAP_POINTS,
+};
+use crate::model::database::{
+    current_database, get_database_pools, get_primary_pool, DatabaseInfo, DatabasePools,
+    DEFAULT_DATABASE,
+};
+use crate::model::dedup::EntityMerge;
+use crate::model::embedding::{
+    BatchSimilarity, EmbeddingOperation, EmbeddingOpsResult, EntityRanking,
+};
+use crate::model::enrichment::{
+    EnrichmentOptions, EnrichmentResult, GeneSet, GeneSetSource, DEFAULT_ADJUSTED_P_VALUE_CUTOFF,
+    DEFAULT_MAX_SET_SIZE, DEFAULT_MIN_SET_SIZE,
+};
+use crate::model::flag::{FlagStatus, RelationFlag};
+use crate::model::gene::{
+    split_identifiers, GeneResolution, DEFAULT_GENE_LABELS, DEFAULT_LIST_LABELS,
+    DEFAULT_MIN_CONFIDENCE, MAX_IDENTIFIERS,
+};
+use crate::model::graph::{
+    CentralityMetric, ClusterAlgorithm, EdgeRanking, Graph, GraphPayload, HubFilter, Node, NodeId,
+    DEFAULT_EXPANSION_EDGES, MAX_CONNECTION_HOPS, MAX_NODE_SET_SIZE,
+};
+use crate::model::materialize::{
+    invalidate_materialized_graphs, materialization_ttl, record_hit, MaterializationInvalidation,
+    MaterializedGraph,
+};
+use crate::model::neo4j::{DEFAULT_MAX_ROWS, DEFAULT_QUERY_TIMEOUT};
+use crate::model::notification::{
+    spawn_event, EventType, Notification, Webhook, DEFAULT_FEED_SIZE,
+};
+use crate::model::ontology::{
+    expand_descendants, OntologyNode, OntologyRollup, DEFAULT_ONTOLOGY_DEPTH, DEFAULT_ROLLUP_LIMIT,
+    MAX_ONTOLOGY_DEPTH,
+};
+use crate::model::path::{DEFAULT_MAX_PATHS, MAX_PATH_HOPS};
+use crate::model::publication::{
+    enqueue_publications, fetch_publication_summaries, fetch_publications, Publication,
+};
+use crate::model::rdf::{stream_rdf, PrefixMap, RdfFormat};
+use crate::model::relation_type::{
+    get_relation_type_registry, load_relation_types, RelationTypeDefinition, RelationTypeRegistry,
+    MAX_IMPORTED_RELATION_TYPES,
+};
+use crate::model::scheduler::get_maintenance_tasks;
+use crate::model::split::{
+    stream_negatives, stream_triples, CorruptionSide, RelationSplit, DEFAULT_SPLIT_SEED,
+    MAX_NEGATIVES_PER_TRIPLE,
+};
+use crate::model::structure::{
+    StructureSearchResult, DEFAULT_MIN_SIMILARITY, DEFAULT_SEARCH_LIMIT,
+};
+use crate::model::theme::{get_active_theme, load_theme, GraphTheme};
+use crate::model::translation::{parse_locales, EntityTranslation};
+use crate::model::util::{get_max_page_size, match_color};
+use crate::model::vocabulary::GraphSchema;
+use crate::model::workspace::{Workspace, WorkspaceMember, WorkspaceRole};
+use crate::query_builder::sql_builder::{
+    get_all_field_pairs, make_order_clause_by_pairs, AggregateQuery, ComposeQuery,
 };
-use crate::model::graph::Graph;
-use crate::model::util::match_color;
-use crate::query_builder::sql_builder::{get_all_field_pairs, make_order_clause_by_pairs};
 use log::{debug, info, warn};
 use poem::web::Data;
-use poem_openapi::{param::Path, param::Query, payload::Json, OpenApi};
+use poem_openapi::{param::Header, param::Path, param::Query, payload::Json, OpenApi};
 use std::sync::Arc;
 use validator::Validate;
 
@@ -23,7 +102,9 @@ pub struct BiomedgpsApi;
 
 #[OpenApi(prefix_path = "/api/v1")]
 impl BiomedgpsApi {
-    /// Call `/api/v1/statistics` with query params to fetch all entity & relation metadata.
+    /// Call `/api/v1/statistics` with query params to fetch all entity & relation metadata, degree distribution summaries and version metadata.
+    ///
+    /// All statistics are read from the materialized statistics tables, you can refresh them by `biomedgps-cli statdb`. They are cached until the next import or for `--cache-ttl` seconds.
     #[oai(
         path = "/statistics",
         method = "get",
@@ -38,27 +119,56 @@ impl BiomedgpsApi {
         info!("Username: {}", _token.0.username);
         let pool_arc = pool.clone();
 
-        let entity_metadata = match EntityMetadata::get_entity_metadata(&pool_arc).await {
-            Ok(entity_metadata) => entity_metadata,
-            Err(e) => {
-                let err = format!("Failed to fetch entity metadata: {}", e);
-                warn!("{}", err);
-                return GetStatisticsResponse::bad_request(err);
-            }
-        };
+        let statistics = cached(&CacheScope::Metadata.key("statistics"), || async {
+            let entity_metadata = match EntityMetadata::get_entity_metadata(&pool_arc).await {
+                Ok(entity_metadata) => entity_metadata,
+                Err(e) => {
+                    let err = format!("Failed to fetch entity metadata: {}", e);
+                    warn!("{}", err);
+                    return Err(GetStatisticsResponse::error(ApiError::from_error(err, &*e)));
+                }
+            };
 
-        let relation_metadata = match RelationMetadata::get_relation_metadata(&pool_arc).await {
-            Ok(relation_metadata) => relation_metadata,
-            Err(e) => {
-                let err = format!("Failed to fetch relation metadata: {}", e);
-                warn!("{}", err);
-                return GetStatisticsResponse::bad_request(err);
-            }
-        };
+            let relation_metadata = match RelationMetadata::get_relation_metadata(&pool_arc).await {
+                Ok(relation_metadata) => relation_metadata,
+                Err(e) => {
+                    let err = format!("Failed to fetch relation metadata: {}", e);
+                    warn!("{}", err);
+                    return Err(GetStatisticsResponse::error(ApiError::from_error(err, &*e)));
+                }
+            };
+
+            let degree_statistics = match DegreeStatistics::get_degree_statistics(&pool_arc).await {
+                Ok(degree_statistics) => degree_statistics,
+                Err(e) => {
+                    let err = format!("Failed to fetch degree statistics: {}", e);
+                    warn!("{}", err);
+                    return Err(GetStatisticsResponse::error(ApiError::from_error(err, &*e)));
+                }
+            };
+
+            let metadata = match DatabaseMetadata::get_database_metadata(&pool_arc).await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    let err = format!("Failed to fetch database metadata: {}", e);
+                    warn!("{}", err);
+                    return Err(GetStatisticsResponse::error(ApiError::from_error(err, &*e)));
+                }
+            };
 
-        let statistics = Statistics::new(entity_metadata, relation_metadata);
+            Ok(Statistics::new(
+                entity_metadata,
+                relation_metadata,
+                degree_statistics,
+                metadata,
+            ))
+        })
+        .await;
 
-        GetStatisticsResponse::ok(statistics)
+        match statistics {
+            Ok(statistics) => GetStatisticsResponse::ok(statistics),
+            Err(response) => response,
+        }
     }
 
     /// Call `/api/v1/entity-metadata` with query params to fetch all entity metadata.
@@ -75,17 +185,21 @@ impl BiomedgpsApi {
     ) -> GetWholeTableResponse<EntityMetadata> {
         let pool_arc = pool.clone();
 
-        match EntityMetadata::get_entity_metadata(&pool_arc).await {
+        match cached(&CacheScope::Metadata.key("entity"), || {
+            EntityMetadata::get_entity_metadata(&pool_arc)
+        })
+        .await
+        {
             Ok(entity_metadata) => GetWholeTableResponse::ok(entity_metadata),
             Err(e) => {
                 let err = format!("Failed to fetch entity metadata: {}", e);
                 warn!("{}", err);
-                return GetWholeTableResponse::bad_request(err);
+                return GetWholeTableResponse::error(ApiError::from_error(err, &*e));
             }
         }
     }
 
-    /// Call `/api/v1/entity-colormap` with query params to fetch all entity colormap.
+    /// Call `/api/v1/entity-colormap` with query params to fetch all entity colormap. The colors of the graph theme (see `/api/v1/graph-theme`) take precedence over the built-in colors.
     #[oai(
         path = "/entity-colormap",
         method = "get",
@@ -99,18 +213,29 @@ impl BiomedgpsApi {
     ) -> GetEntityColorMapResponse {
         let pool_arc = pool.clone();
 
-        let entity_metadata = match EntityMetadata::get_entity_metadata(&pool_arc).await {
+        let entity_metadata = match cached(&CacheScope::Metadata.key("entity"), || {
+            EntityMetadata::get_entity_metadata(&pool_arc)
+        })
+        .await
+        {
             Ok(entity_metadata) => entity_metadata,
             Err(e) => {
                 let err = format!("Failed to fetch entity metadata: {}", e);
                 warn!("{}", err);
-                return GetEntityColorMapResponse::bad_request(err);
+                return GetEntityColorMapResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
+        let theme = get_active_theme();
         let color_map = entity_metadata
             .iter()
-            .map(|em| (em.entity_type.clone(), match_color(&em.entity_type)))
+            .map(|em| {
+                let color = theme
+                    .as_ref()
+                    .and_then(|theme| theme.get_node_color(&em.entity_type))
+                    .unwrap_or_else(|| match_color(&em.entity_type));
+                (em.entity_type.clone(), color)
+            })
             .collect();
 
         return GetEntityColorMapResponse::ok(color_map);
@@ -130,17 +255,87 @@ impl BiomedgpsApi {
     ) -> GetWholeTableResponse<RelationMetadata> {
         let pool_arc = pool.clone();
 
-        match RelationMetadata::get_relation_metadata(&pool_arc).await {
+        match cached(&CacheScope::Metadata.key("relation"), || {
+            RelationMetadata::get_relation_metadata(&pool_arc)
+        })
+        .await
+        {
             Ok(relation_metadata) => GetWholeTableResponse::ok(relation_metadata),
             Err(e) => {
                 let err = format!("Failed to fetch relation metadata: {}", e);
                 warn!("{}", err);
-                return GetWholeTableResponse::bad_request(err);
+                return GetWholeTableResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/schema` to fetch the controlled vocabularies of the knowledge graph, i.e. the valid entity labels and the relation types with their start and end entity types. The `label`, `relation_type` and the other type filters of the queries and the curated knowledges are validated against them. They come from the entity and relation metadata, so they are empty until `biomedgps-cli statdb` is run and nothing is validated then.
+    #[oai(
+        path = "/schema",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSchema"
+    )]
+    async fn fetch_schema(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphSchemaResponse {
+        let pool_arc = pool.clone();
+
+        match cached(&CacheScope::Metadata.key("schema"), || {
+            GraphSchema::get(&pool_arc)
+        })
+        .await
+        {
+            Ok(schema) => GetGraphSchemaResponse::ok(schema),
+            Err(e) => {
+                let err = format!("Failed to fetch schema: {}", e);
+                warn!("{}", err);
+                return GetGraphSchemaResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/biolink-mappings` to fetch the BioLink classes of the entity labels and the BioLink predicates of the relation types. The mappings come from the `biolink_mapping` table (imported by `biomedgps-cli importdb`) and the built-in mappings, the terms which are mapped by `fallback` use `biolink:NamedThing` or `biolink:related_to` and need a mapping.
+    #[oai(
+        path = "/biolink-mappings",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchBiolinkMappings"
+    )]
+    async fn fetch_biolink_mappings(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<BiolinkTerm> {
+        let pool_arc = pool.clone();
+
+        match cached(&CacheScope::Metadata.key("biolink"), || {
+            BiolinkMapper::get_terms(&pool_arc)
+        })
+        .await
+        {
+            Ok(terms) => GetWholeTableResponse::ok(terms),
+            Err(e) => {
+                let err = format!("Failed to fetch biolink mappings: {}", e);
+                warn!("{}", err);
+                return GetWholeTableResponse::error(ApiError::from_error(err, &*e));
             }
         }
     }
 
     /// Call `/api/v1/entities` with query params to fetch entities.
+    ///
+    /// Use the `fields` param (such as `id,name,label`) to fetch the selected columns only, the other columns are null.
+    ///
+    /// Use the `order_by` param (such as `name DESC, id ASC`) to sort the entities, otherwise they are sorted by the fields in the query.
+    ///
+    /// Use the `count` param (exact, estimated or cached, default: exact) to choose how the `total` is counted, the estimated count comes from the table statistics and the cached count may be a few minutes old.
+    ///
+    /// Use the `format` param (ndjson or csv) or the `Accept: application/x-ndjson` (or `text/csv`) header to export all the matched entities in one streamed response, the `page` and `page_size` params are ignored.
+    ///
+    /// Use the `locale` param (such as `zh-CN`) or the `Accept-Language` header to get the translated names of the entities (imported by `biomedgps-cli importdb -t entity_translation`), the entities without a translation keep the default names. The projected and exported entities always have the default names.
     #[oai(
         path = "/entities",
         method = "get",
@@ -153,12 +348,57 @@ impl BiomedgpsApi {
         page: Query<Option<u64>>,
         page_size: Query<Option<u64>>,
         query_str: Query<Option<String>>,
+        fields: Query<Option<String>>,
+        order_by: Query<Option<String>>,
+        count: Query<Option<CountStrategy>>,
+        format: Query<Option<RecordFormat>>,
+        #[oai(name = "Accept")] accept: Header<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
         _token: CustomSecurityScheme,
     ) -> GetRecordsResponse<Entity> {
         let pool_arc = pool.clone();
+        let count_strategy = count.0.unwrap_or(CountStrategy::Exact);
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        };
         let page = page.0;
         let page_size = page_size.0;
 
+        if let Err(e) = Pagination::new(page, page_size) {
+            let err = format!("Failed to parse pagination: {}", e);
+            warn!("{}", err);
+            return GetRecordsResponse::error(ApiError::from_error(err, &e));
+        }
+
+        let fields = match fields.0 {
+            Some(fields) => match Entity::parse_selected_fields(&fields) {
+                Ok(fields) => Some(fields),
+                Err(e) => {
+                    let err = format!("Failed to parse fields: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let order_by = match order_by.0 {
+            Some(order_by) => match Entity::parse_order_by(&order_by) {
+                Ok(order_by) => Some(order_by),
+                Err(e) => {
+                    let err = format!("Failed to parse order_by: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
         let query_str = match query_str.0 {
             Some(query_str) => query_str,
             None => {
@@ -177,13 +417,26 @@ impl BiomedgpsApi {
                 Err(e) => {
                     let err = format!("Failed to parse query string: {}", e);
                     warn!("{}", err);
-                    return GetRecordsResponse::bad_request(err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
                 }
             }
         };
 
-        let order_by_clause = match query.clone() {
-            Some(q) => {
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Entity>(query) {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+
+            if let Err(e) = check_query_vocabulary(&pool_arc, query).await {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let order_by_clause = match (order_by, query.clone()) {
+            (Some(order_by), _) => order_by,
+            (None, Some(q)) => {
                 let pairs = get_all_field_pairs(&q);
                 if pairs.len() == 0 {
                     "id ASC".to_string()
@@ -192,571 +445,1020 @@ impl BiomedgpsApi {
                     make_order_clause_by_pairs(pairs, 2)
                 }
             }
-            None => "id ASC".to_string(),
+            (None, None) => "id ASC".to_string(),
         };
 
-        match RecordResponse::<Entity>::get_records(
+        let format = RecordFormat::negotiate(format.0, accept.0.as_deref());
+        if format != RecordFormat::Json {
+            let records = stream_records::<Entity>(
+                pool.0.clone(),
+                TableName::Entity,
+                fields,
+                &query,
+                Some(order_by_clause.as_str()),
+                format,
+            );
+            return GetRecordsResponse::stream(records, format);
+        }
+
+        if let Some(fields) = fields {
+            return match ProjectedRecordResponse::get_records::<Entity>(
+                &pool_arc,
+                TableName::Entity,
+                &fields,
+                &query,
+                page,
+                page_size,
+                Some(order_by_clause.as_str()),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(records) => GetRecordsResponse::projected(records),
+                Err(e) => {
+                    let err = format!("Failed to fetch entities: {}", e);
+                    warn!("{}", err);
+                    GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        match RecordResponse::<Entity>::get_records_with_count(
             &pool_arc,
-            "biomedgps_entity",
+            TableName::Entity,
             &query,
             page,
             page_size,
             Some(order_by_clause.as_str()),
+            count_strategy,
         )
         .await
         {
-            Ok(entities) => GetRecordsResponse::ok(entities),
+            Ok(mut entities) => {
+                translate_entities(&pool_arc, &locales, &mut entities.records).await;
+                GetRecordsResponse::ok(entities)
+            }
             Err(e) => {
                 let err = format!("Failed to fetch entities: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &*e));
             }
         }
     }
 
-    /// Call `/api/v1/curated-graph` with query params to fetch curated graph.
+    /// Call `/api/v1/entities/:label/:id` to fetch everything about one entity, such as `/api/v1/entities/Disease/MESH:D001755`.
+    ///
+    /// It returns the entity, the metadata of its type, its degree per relation type, its 2D coordinates, the curated knowledges which involve it, its names in other languages and the top-K similar nodes (only when the topk parameter is specified). The names are translated by the `locale` param or the `Accept-Language` header, same as `/api/v1/entities`.
     #[oai(
-        path = "/curated-graph",
+        path = "/entities/:label/:id",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchCuratedGraph"
+        operation_id = "fetchEntityDetail"
     )]
-    async fn fetch_curated_graph(
+    async fn fetch_entity_detail(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        curator: Query<String>,
-        project_id: Query<Option<String>>,
-        organization_id: Query<Option<String>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        strict_mode: Query<bool>,
+        label: Path<String>,
+        id: Path<String>,
+        topk: Query<Option<u64>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
         _token: CustomSecurityScheme,
-    ) -> GetGraphResponse {
+    ) -> GetEntityDetailResponse {
         let pool_arc = pool.clone();
-        let curator = curator.0;
+        let label = label.0;
+        let id = id.0;
+        let node_id = Node::format_id(&label, &id);
 
-        // if curator != _token.0.username {
-        //     let err = format!(
-        //         "You cannot query curated graph from other users. You are {} and you are querying {}'s curated graph.",
-        //         _token.0.username, curator
-        //     );
-        //     warn!("{}", err);
-        //     return GetGraphResponse::bad_request(err);
-        // }
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetEntityDetailResponse::error(e);
+            }
+        };
 
-        let project_id = match project_id.0 {
-            Some(project_id) => {
-                // Convert project_id to i32
-                match project_id.parse::<i32>() {
-                    Ok(project_id) => project_id,
+        let similarity_query = match SimilarityNodeQuery::new(&node_id, &None, topk.0, &None, None)
+        {
+            Ok(query) => query,
+            Err(e) => {
+                let err = format!("Failed to validate the label, id or topk: {}", e);
+                warn!("{}", err);
+                return GetEntityDetailResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let key = format!("{}:{}", node_id, topk.0.unwrap_or(0));
+        let detail = cached(&CacheScope::Entity.key(&key), || async {
+            let entity = match Entity::get_entity(&pool_arc, &label, &id).await {
+                Ok(Some(entity)) => entity,
+                Ok(None) => {
+                    let err = format!("Cannot find the entity {}.", node_id);
+                    warn!("{}", err);
+                    return Err(GetEntityDetailResponse::not_found(err));
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch the entity: {}", e);
+                    warn!("{}", err);
+                    return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                        err, &*e,
+                    )));
+                }
+            };
+
+            let metadata = match EntityMetadata::get_entity_metadata(&pool_arc).await {
+                Ok(metadata) => metadata
+                    .into_iter()
+                    .filter(|m| m.entity_type == label)
+                    .collect::<Vec<EntityMetadata>>(),
+                Err(e) => {
+                    let err = format!("Failed to fetch entity metadata: {}", e);
+                    warn!("{}", err);
+                    return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                        err, &*e,
+                    )));
+                }
+            };
+
+            let degrees = match RelationCount::get_records_by_node(&pool_arc, &label, &id).await {
+                Ok(degrees) => degrees,
+                Err(e) => {
+                    let err = format!("Failed to fetch the degrees: {}", e);
+                    warn!("{}", err);
+                    return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                        err, &*e,
+                    )));
+                }
+            };
+
+            let entity2d = match Entity2D::get_records_by_entity(&pool_arc, &label, &id).await {
+                Ok(entity2d) => entity2d,
+                Err(e) => {
+                    let err = format!("Failed to fetch the 2D coordinates: {}", e);
+                    warn!("{}", err);
+                    return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                        err, &*e,
+                    )));
+                }
+            };
+
+            let curated_knowledges =
+                match KnowledgeCuration::get_records_by_node(&pool_arc, &label, &id).await {
+                    Ok(curated_knowledges) => curated_knowledges,
                     Err(e) => {
-                        let err = format!("Failed to parse project id: {}", e);
+                        let err = format!("Failed to fetch the curated knowledges: {}", e);
                         warn!("{}", err);
-                        return GetGraphResponse::bad_request(err);
+                        return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                            err, &*e,
+                        )));
                     }
-                }
-            }
-            None => {
-                warn!("Project id is empty.");
-                -1
-            }
-        };
+                };
 
-        let organization_id = match organization_id.0 {
-            Some(organization_id) => {
-                // Convert organization_id to i32
-                match organization_id.parse::<i32>() {
-                    Ok(organization_id) => organization_id,
+            let attributes =
+                match EntityAttribute::get_records_by_entity(&pool_arc, &label, &id).await {
+                    Ok(attributes) => attributes,
                     Err(e) => {
-                        let err = format!("Failed to parse organization id: {}", e);
+                        let err = format!("Failed to fetch the attributes: {}", e);
                         warn!("{}", err);
-                        return GetGraphResponse::bad_request(err);
+                        return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                            err, &*e,
+                        )));
                     }
-                }
-            }
-            None => {
-                warn!("Organization id is empty.");
-                -1
-            }
-        };
+                };
 
-        // Get organizations and projects from the token
-        let user = &_token.0;
-        if organization_id != -1 && !user.organizations.contains(&organization_id) {
-            let err = format!(
-                "User {} doesn't have access to organization {}. Your system might not support querying curated graph by organization or you don't have access to this organization.",
-                user.username, organization_id
-            );
-            warn!("{}", err);
-            return GetGraphResponse::bad_request(err);
-        };
+            let translations =
+                match EntityTranslation::get_records_by_entity(&pool_arc, &label, &id).await {
+                    Ok(translations) => translations,
+                    Err(e) => {
+                        let err = format!("Failed to fetch the translations: {}", e);
+                        warn!("{}", err);
+                        return Err(GetEntityDetailResponse::error(ApiError::from_error(
+                            err, &*e,
+                        )));
+                    }
+                };
 
-        if project_id != -1 && !user.projects.contains(&project_id) {
-            let err = format!(
-                "User {} doesn't have access to project {}. Your system might not support querying curated graph by project or you don't have access to this project.",
-                user.username, project_id
-            );
-            warn!("{}", err);
-            return GetGraphResponse::bad_request(err);
-        };
+            // The entity may not have an embedding, so we don't treat it as an error.
+            let similar_nodes = match topk.0 {
+                Some(topk) => {
+                    let mut graph = Graph::new();
+                    match graph
+                        .fetch_similarity_nodes(
+                            &pool_arc,
+                            &similarity_query.node_id,
+                            &None,
+                            Some(topk),
+                            None,
+                            None,
+                            None,
+                        )
+                        .await
+                    {
+                        Ok(graph) => Some(graph.to_owned().get_graph(None).unwrap()),
+                        Err(e) => {
+                            warn!("Failed to fetch similarity nodes for {}: {}", node_id, e);
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
 
-        let mut graph = Graph::new();
-        let page = page.0;
-        let page_size = page_size.0;
-        let strict_mode = strict_mode.0;
+            Ok(EntityDetail {
+                entity,
+                metadata,
+                degrees,
+                entity2d,
+                curated_knowledges,
+                attributes,
+                translations,
+                similar_nodes,
+            })
+        })
+        .await;
 
-        match graph
-            .fetch_curated_knowledges(
-                &pool_arc,
-                &curator[..],
-                project_id,
-                organization_id,
-                page,
-                page_size,
-                Some("id ASC"),
-                strict_mode,
-            )
-            .await
-        {
-            Ok(data) => GetGraphResponse::ok(data.to_owned().get_graph(None).unwrap()),
-            Err(e) => {
-                let err = format!("Failed to fetch curated graph: {}", e);
-                warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+        match detail {
+            Ok(mut detail) => {
+                translate_entities(
+                    &pool_arc,
+                    &locales,
+                    std::slice::from_mut(&mut detail.entity),
+                )
+                .await;
+                if let Some(similar_nodes) = detail.similar_nodes.as_mut() {
+                    translate_graph(&pool_arc, &locales, similar_nodes).await;
+                }
+                GetEntityDetailResponse::ok(detail)
             }
+            Err(response) => response,
         }
     }
 
-    /// Call `/api/v1/curated-knowledges-by-owner` with query params to fetch curated knowledges by owner.
+    /// Call `/api/v1/entities/:label/:id/annotations` to fetch the live annotations of an entity from the external APIs, such as `/api/v1/entities/Gene/ENTREZ:7157/annotations`.
+    ///
+    /// The gene summaries come from MyGene.info, the compound structures from PubChem and the disease definitions from MONDO, only the sources which apply to the entity type and the id (or the xrefs) are requested. The annotations are cached for 30 days (see ANNOTATION_CACHE_TTL_DAYS), `refresh=true` requests the sources again. The failed sources are listed in `failures`, and a stale annotation is returned if its source is unavailable.
     #[oai(
-        path = "/curated-knowledges-by-owner",
+        path = "/entities/:label/:id/annotations",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchCuratedKnowledgesByOwner"
+        operation_id = "fetchEntityAnnotations"
     )]
-    async fn fetch_curated_knowledges_by_owner(
+    async fn fetch_entity_annotations(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        curator: Query<String>,
-        project_id: Query<Option<String>>,
-        organization_id: Query<Option<String>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        // We need to confirm the token is valid and contains all projects and organizations which the user has access to.
+        label: Path<String>,
+        id: Path<String>,
+        refresh: Query<Option<bool>>,
         _token: CustomSecurityScheme,
-    ) -> GetRecordsResponse<KnowledgeCuration> {
+    ) -> GetEntityAnnotationsResponse {
         let pool_arc = pool.clone();
-        let curator = curator.0;
+        let node_id = match NodeId::new(&label.0, &id.0) {
+            Ok(node_id) => node_id,
+            Err(e) => {
+                let err = format!("Failed to validate the label or id: {}", e);
+                warn!("{}", err);
+                return GetEntityAnnotationsResponse::error(ApiError::from_error(err, &e));
+            }
+        };
 
-        if curator != _token.0.username {
-            let err = format!(
-                "You cannot query curated knowledges from other users. You are {} and you are querying {}'s curated knowledges.",
-                _token.0.username, curator
-            );
-            warn!("{}", err);
-            return GetRecordsResponse::bad_request(err);
-        }
-
-        let project_id = match project_id.0 {
-            Some(project_id) => {
-                // Convert project_id to i32
-                match project_id.parse::<i32>() {
-                    Ok(project_id) => project_id,
-                    Err(e) => {
-                        let err = format!("Failed to parse project id: {}", e);
-                        warn!("{}", err);
-                        return GetRecordsResponse::bad_request(err);
-                    }
-                }
+        let entity = match Entity::get_entity(&pool_arc, node_id.label(), node_id.entity_id()).await
+        {
+            Ok(Some(entity)) => entity,
+            Ok(None) => {
+                let err = format!("Cannot find the entity {}.", node_id);
+                warn!("{}", err);
+                return GetEntityAnnotationsResponse::not_found(err);
             }
-            None => {
-                warn!("Project id is empty.");
-                -1
+            Err(e) => {
+                let err = format!("Failed to fetch the entity: {}", e);
+                warn!("{}", err);
+                return GetEntityAnnotationsResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
-        let organization_id = match organization_id.0 {
-            Some(organization_id) => {
-                // Convert organization_id to i32
-                match organization_id.parse::<i32>() {
-                    Ok(organization_id) => organization_id,
-                    Err(e) => {
-                        let err = format!("Failed to parse organization id: {}", e);
-                        warn!("{}", err);
-                        return GetRecordsResponse::bad_request(err);
-                    }
-                }
-            }
-            None => {
-                warn!("Organization id is empty.");
-                -1
+        match EntityAnnotations::fetch(&pool_arc, &entity, refresh.0.unwrap_or(false)).await {
+            Ok(annotations) => GetEntityAnnotationsResponse::ok(annotations),
+            Err(e) => {
+                let err = format!("Failed to fetch the annotations: {}", e);
+                warn!("{}", err);
+                GetEntityAnnotationsResponse::error(ApiError::from_error(err, &*e))
             }
-        };
+        }
+    }
 
-        // Get organizations and projects from the token
-        let user = &_token.0;
-        if organization_id != -1 && !user.organizations.contains(&organization_id) {
-            let err = format!(
-                "User {} doesn't have access to organization {}. Your system might not support querying curated knowledges by organization or you don't have access to this organization.",
-                user.username, organization_id
-            );
-            warn!("{}", err);
-            return GetRecordsResponse::bad_request(err);
-        };
+    /// Call `/api/v1/entities/:label/:id/ancestors` to fetch the ancestors of an entity in an ontology which is imported by `biomedgps-cli import-ontology`, such as `/api/v1/entities/Disease/DOID:9352/ancestors?max_depth=3`.
+    ///
+    /// The ancestors are found by the is_a relations from the child to the parent within `max_depth` (between 1 and 30, 10 by default) steps, the depth of an ancestor is the number of the steps and the parents (depth 1) go first.
+    #[oai(
+        path = "/entities/:label/:id/ancestors",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEntityAncestors"
+    )]
+    async fn fetch_entity_ancestors(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        label: Path<String>,
+        id: Path<String>,
+        max_depth: Query<Option<usize>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<OntologyNode> {
+        fetch_ontology_lineage(&pool, &label.0, &id.0, max_depth.0, true).await
+    }
 
-        if project_id != -1 && !user.projects.contains(&project_id) {
-            let err = format!(
-                "User {} doesn't have access to project {}. Your system might not support querying curated knowledges by project or you don't have access to this project.",
-                user.username, project_id
-            );
+    /// Call `/api/v1/entities/:label/:id/descendants` to fetch the descendants of an entity in an ontology which is imported by `biomedgps-cli import-ontology`, such as `/api/v1/entities/Disease/DOID:4/descendants?max_depth=1`.
+    ///
+    /// The descendants are found by the is_a relations from the parent to the child within `max_depth` (between 1 and 30, 10 by default) steps, the depth of a descendant is the number of the steps and the children (depth 1) go first.
+    #[oai(
+        path = "/entities/:label/:id/descendants",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEntityDescendants"
+    )]
+    async fn fetch_entity_descendants(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        label: Path<String>,
+        id: Path<String>,
+        max_depth: Query<Option<usize>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<OntologyNode> {
+        fetch_ontology_lineage(&pool, &label.0, &id.0, max_depth.0, false).await
+    }
+
+    /// Call `/api/v1/ontology-rollups` with a json body to roll up the entities (such as the diseases in a query result, at most 1000) to their ancestors in an ontology which is imported by `biomedgps-cli import-ontology`.
+    ///
+    /// Each returned term is an entity or one of its ancestors within `max_depth` (between 1 and 30, 10 by default) steps, with the entities it covers as the members. The terms which cover more entities go first, and at most `limit` (100 by default) terms are returned, so the top-level categories of a result can be counted without listing the whole hierarchy.
+    #[oai(
+        path = "/ontology-rollups",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postOntologyRollups"
+    )]
+    async fn post_ontology_rollups(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<OntologyRollupRequest>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<OntologyRollup> {
+        let pool_arc = pool.clone();
+
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the rollup request: {}", e);
             warn!("{}", err);
-            return GetRecordsResponse::bad_request(err);
-        };
+            return GetWholeTableResponse::error(ApiError::from_error(err, &e));
+        }
 
-        match KnowledgeCuration::get_records_by_owner(
+        match OntologyRollup::get_records(
             &pool_arc,
-            &curator,
-            project_id,
-            organization_id,
-            page.0,
-            page_size.0,
-            // TODO: get an order_by clause from query
-            Some("id ASC"),
+            &payload.label,
+            &payload.ids,
+            payload.max_depth.unwrap_or(DEFAULT_ONTOLOGY_DEPTH),
+            payload.limit.unwrap_or(DEFAULT_ROLLUP_LIMIT),
         )
         .await
         {
-            Ok(entities) => GetRecordsResponse::ok(entities),
+            Ok(records) => GetWholeTableResponse::ok(records),
             Err(e) => {
-                let err = format!("Failed to fetch curated knowledges: {}", e);
+                let err = format!("Failed to roll up the entities: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/curated-knowledges` with query params to fetch curated knowledges.
+    /// Call `/api/v1/relations/:id/evidence` to fetch the evidence behind a relation, such as `/api/v1/relations/1/evidence?enrich_pubmed=true`.
+    ///
+    /// It aggregates the key sentences, the pmids and the resources of the relation and the curated knowledges which have the same relation type, source node and target node. If enrich_pubmed is true, the title, journal and year of the pmids will be fetched from the PubMed E-utilities (cached on disk).
     #[oai(
-        path = "/curated-knowledges",
+        path = "/relations/:id/evidence",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchCuratedKnowledges"
+        operation_id = "fetchRelationEvidence"
     )]
-    async fn fetch_curated_knowledges(
+    async fn fetch_relation_evidence(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        query_str: Query<Option<String>>,
+        id: Path<i64>,
+        enrich_pubmed: Query<Option<bool>>,
         _token: CustomSecurityScheme,
-    ) -> GetRecordsResponse<KnowledgeCuration> {
+    ) -> GetRelationEvidenceResponse {
         let pool_arc = pool.clone();
-        let page = page.0;
-        let page_size = page_size.0;
+        let id = id.0;
 
-        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to parse query string: {}", e);
+        let relation = match Relation::get_record(&pool_arc, id).await {
+            Ok(Some(relation)) => relation,
+            Ok(None) => {
+                let err = format!("Cannot find the relation {}.", id);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                return GetRelationEvidenceResponse::not_found(err);
             }
-        }
-
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
-            None => {
-                warn!("Query string is empty.");
-                "".to_string()
+            Err(e) => {
+                let err = format!("Failed to fetch the relation: {}", e);
+                warn!("{}", err);
+                return GetRelationEvidenceResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
-                    warn!("{}", err);
-                    return GetRecordsResponse::bad_request(err);
-                }
+        // The curated knowledges of the workspaces which the user is not a member of are hidden.
+        let workspace_ids = match member_workspace_ids(&pool_arc, &_token.0).await {
+            Ok(workspace_ids) => workspace_ids,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetRelationEvidenceResponse::error(e);
             }
         };
 
-        match RecordResponse::<KnowledgeCuration>::get_records(
+        let curated_knowledges = match KnowledgeCuration::get_records_by_relation(
             &pool_arc,
-            "biomedgps_knowledge_curation",
-            &query,
-            page,
-            page_size,
-            Some("id ASC"),
+            &relation,
+            workspace_ids.as_deref(),
         )
         .await
         {
-            Ok(entities) => GetRecordsResponse::ok(entities),
+            Ok(curated_knowledges) => curated_knowledges,
             Err(e) => {
-                let err = format!("Failed to fetch curated knowledges: {}", e);
+                let err = format!("Failed to fetch the curated knowledges: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                return GetRelationEvidenceResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        let mut evidence = RelationEvidence::new(relation, curated_knowledges);
+
+        // PubMed may be unavailable, so we don't treat it as an error.
+        if enrich_pubmed.0.unwrap_or(false) && !evidence.pmids.is_empty() {
+            match fetch_publication_summaries(&evidence.pmids).await {
+                Ok(publications) => evidence.publications = Some(publications),
+                Err(e) => warn!("Failed to fetch the publications from PubMed: {}", e),
             }
         }
+
+        GetRelationEvidenceResponse::ok(evidence)
     }
 
-    /// Call `/api/v1/curated-knowledges` with payload to create a curated knowledge.
+    /// Call `/api/v1/relations/:id/flags` with payload to flag a relation as wrong, outdated or duplicated, such as `{"reason": "wrong", "comment": "The compound doesn't treat the disease, see PMID:123456."}`. The id of a relation is in the `relation_id` of the edges returned by the graph endpoints. A user can only open one flag with the same reason for a relation, the admins triage the flags by `/api/v1/relation-flags`.
     #[oai(
-        path = "/curated-knowledges",
+        path = "/relations/:id/flags",
         method = "post",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "postCuratedKnowledge"
+        operation_id = "postRelationFlag"
     )]
-    async fn post_curated_knowledge(
+    async fn post_relation_flag(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        payload: Json<KnowledgeCuration>,
+        id: Path<i64>,
+        payload: Json<RelationFlagRequest>,
         _token: CustomSecurityScheme,
-    ) -> PostResponse<KnowledgeCuration> {
+    ) -> PostResponse<RelationFlag> {
         let pool_arc = pool.clone();
+        let id = id.0;
         let payload = payload.0;
 
-        match payload.validate() {
-            Ok(_) => {}
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate payload: {}", e);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::from_error(err, &e));
+        }
+
+        match Relation::get_record(&pool_arc, id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                let err = format!("Cannot find the relation {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
             Err(e) => {
-                let err = format!("Failed to validate payload: {}", e);
+                let err = format!("Failed to fetch the relation: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
-        match payload.insert(&pool_arc).await {
-            Ok(kc) => PostResponse::Created(Json(kc)),
+        match RelationFlag::insert(
+            &pool_arc,
+            id,
+            payload.reason,
+            payload.comment.as_deref(),
+            &_token.0.username,
+        )
+        .await
+        {
+            Ok(flag) => PostResponse::created(flag),
             Err(e) => {
-                let err = format!("Failed to insert curated knowledge: {}", e);
+                let err = format!("Failed to flag the relation: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                PostResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/curated-knowledges/:id` with payload to create a curated knowledge.
+    /// Call `/api/v1/relation-flags` with query params to fetch the flags of the relations for the triage, such as `/api/v1/relation-flags?status=open`. The open flags are returned by default and the latest one comes first, each flag carries the provenance of the flagged relation (the resource, the pmids and the dataset version). Only the admins can triage the flags.
     #[oai(
-        path = "/curated-knowledges/:id",
-        method = "put",
+        path = "/relation-flags",
+        method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "putCuratedKnowledge"
+        operation_id = "fetchRelationFlags"
     )]
-    async fn put_curated_knowledge(
+    async fn fetch_relation_flags(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        payload: Json<KnowledgeCuration>,
-        id: Path<i64>,
+        status: Query<Option<FlagStatus>>,
+        relation_id: Query<Option<i64>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
         _token: CustomSecurityScheme,
-    ) -> PostResponse<KnowledgeCuration> {
+    ) -> GetRecordsResponse<RelationFlag> {
         let pool_arc = pool.clone();
-        let payload = payload.0;
-        let id = id.0;
 
-        if id < 0 {
-            let err = format!("Invalid id: {}", id);
+        if let Err(err) = _token.0.check_role(Role::Admin) {
             warn!("{}", err);
-            return PostResponse::bad_request(err);
+            return GetRecordsResponse::error(ApiError::new(ErrorCode::Forbidden, err));
         }
 
-        match payload.validate() {
-            Ok(_) => {}
+        let pagination = match PaginationQuery::new(page.0, page_size.0, None) {
+            Ok(pagination) => pagination,
             Err(e) => {
-                let err = format!("Failed to validate payload: {}", e);
+                let err = format!("Failed to parse query string: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &e));
             }
         };
 
-        match payload.update(&pool_arc, id).await {
-            Ok(kc) => PostResponse::Created(Json(kc)),
+        match RelationFlag::get_records(
+            &pool_arc,
+            Some(status.0.unwrap_or(FlagStatus::Open)),
+            relation_id.0,
+            pagination.page.unwrap_or(1),
+            pagination.page_size.unwrap_or(10),
+        )
+        .await
+        {
+            Ok(flags) => GetRecordsResponse::ok(flags),
             Err(e) => {
-                let err = format!("Failed to insert curated knowledge: {}", e);
+                let err = format!("Failed to fetch the relation flags: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                GetRecordsResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/curated-knowledges/:id` with payload to delete a curated knowledge.
+    /// Call `/api/v1/relation-flags/:id` with payload to accept or reject an open flag, such as `{"status": "accepted"}`. The flagged relation is not changed, an accepted flag only records the triage, so the relation should be fixed or removed by the next import. Only the admins can triage the flags.
     #[oai(
-        path = "/curated-knowledges/:id",
-        method = "delete",
+        path = "/relation-flags/:id",
+        method = "put",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "deleteCuratedKnowledge"
+        operation_id = "putRelationFlag"
     )]
-    async fn delete_curated_knowledge(
+    async fn put_relation_flag(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
         id: Path<i64>,
+        payload: Json<RelationFlagResolution>,
         _token: CustomSecurityScheme,
-    ) -> DeleteResponse {
+    ) -> PostResponse<RelationFlag> {
         let pool_arc = pool.clone();
         let id = id.0;
 
-        if id < 0 {
-            let err = format!("Invalid id: {}", id);
+        if let Err(err) = _token.0.check_role(Role::Admin) {
             warn!("{}", err);
-            return DeleteResponse::bad_request(err);
+            return PostResponse::forbidden(err);
         }
 
-        match KnowledgeCuration::delete(&pool_arc, id).await {
-            Ok(_) => DeleteResponse::no_content(),
+        let status = payload.0.status;
+        if status == FlagStatus::Open {
+            return PostResponse::bad_request(
+                "The status of a triaged flag must be accepted or rejected.".to_string(),
+            );
+        }
+
+        match RelationFlag::resolve(&pool_arc, id, status, &_token.0.username).await {
+            Ok(Some(flag)) => PostResponse::created(flag),
+            Ok(None) => {
+                let err = format!("The flag {} doesn't exist or has been triaged.", id);
+                warn!("{}", err);
+                PostResponse::not_found(err)
+            }
             Err(e) => {
-                let err = format!("Failed to delete curated knowledge: {}", e);
+                let err = format!("Failed to triage the flag: {}", e);
                 warn!("{}", err);
-                DeleteResponse::not_found(err)
+                PostResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/relations` with query params to fetch relations.
+    /// Call `/api/v1/publications/:pmid` to fetch the title, journal, year and abstract of a publication, such as `/api/v1/publications/31452104`.
+    ///
+    /// The publication will be fetched from the PubMed E-utilities and saved into the database if it doesn't exist in the database. The fetches share the throttle of the background fetcher, so the concurrent requests may wait for the rate limit of the E-utilities.
     #[oai(
-        path = "/relations",
+        path = "/publications/:pmid",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchRelations"
+        operation_id = "fetchPublication"
     )]
-    async fn fetch_relations(
+    async fn fetch_publication(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        query_str: Query<Option<String>>,
+        pmid: Path<i64>,
         _token: CustomSecurityScheme,
-    ) -> GetRecordsResponse<Relation> {
+    ) -> GetPublicationResponse {
         let pool_arc = pool.clone();
-        let page = page.0;
-        let page_size = page_size.0;
+        let pmid = pmid.0;
 
-        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
-            Ok(_) => {}
+        if pmid <= 0 {
+            let err = format!("Invalid pmid: {}", pmid);
+            warn!("{}", err);
+            return GetPublicationResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        match Publication::get_record(&pool_arc, pmid).await {
+            Ok(Some(publication)) => return GetPublicationResponse::ok(publication),
+            Ok(None) => {}
             Err(e) => {
-                let err = format!("Failed to parse query string: {}", e);
+                let err = format!("Failed to fetch the publication: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
-            }
-        };
-
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
-            None => {
-                warn!("Query string is empty.");
-                "".to_string()
+                return GetPublicationResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
+        let publication = match fetch_publications(&[pmid]).await {
+            Ok(publications) => match publications.into_iter().find(|p| p.pmid == pmid) {
+                Some(publication) => publication,
+                None => {
+                    let err = format!("Cannot find the publication {} in PubMed.", pmid);
                     warn!("{}", err);
-                    return GetRecordsResponse::bad_request(err);
+                    return GetPublicationResponse::not_found(err);
                 }
+            },
+            Err(e) => {
+                let err = format!("Failed to fetch the publication from PubMed: {}", e);
+                warn!("{}", err);
+                return GetPublicationResponse::error(ApiError::from_error(err, &*e));
             }
         };
 
-        match RecordResponse::<Relation>::get_records(
-            &pool_arc,
-            "biomedgps_relation",
-            &query,
-            page,
-            page_size,
-            Some("id ASC"),
-        )
-        .await
-        {
-            Ok(entities) => GetRecordsResponse::ok(entities),
+        match publication.upsert(&pool_arc).await {
+            Ok(publication) => GetPublicationResponse::ok(publication),
             Err(e) => {
-                let err = format!("Failed to fetch relations: {}", e);
+                let err = format!("Failed to save the publication: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                GetPublicationResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/relation-counts` with query params to fetch relation counts.
+    /// Call `/api/v1/curated-graph` with query params to fetch curated graph.
     #[oai(
-        path = "/relation-counts",
+        path = "/curated-graph",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchRelationCounts"
+        operation_id = "fetchCuratedGraph"
     )]
-    async fn fetch_relation_counts(
+    async fn fetch_curated_graph(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        query_str: Query<Option<String>>,
+        curator: Query<String>,
+        project_id: Query<Option<String>>,
+        organization_id: Query<Option<String>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        strict_mode: Query<bool>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
         _token: CustomSecurityScheme,
-    ) -> GetRelationCountResponse {
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
         let pool_arc = pool.clone();
+        let curator = curator.0;
 
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
+        if let Err(e) = Pagination::new(page.0, page_size.0) {
+            let err = format!("Failed to parse pagination: {}", e);
+            warn!("{}", err);
+            return GetGraphResponse::error(ApiError::from_error(err, &e));
+        }
+
+        // if curator != _token.0.username {
+        //     let err = format!(
+        //         "You cannot query curated graph from other users. You are {} and you are querying {}'s curated graph.",
+        //         _token.0.username, curator
+        //     );
+        //     warn!("{}", err);
+        //     return GetGraphResponse::bad_request(err);
+        // }
+
+        let project_id = match project_id.0 {
+            Some(project_id) => {
+                // Convert project_id to i32
+                match project_id.parse::<i32>() {
+                    Ok(project_id) => project_id,
+                    Err(e) => {
+                        let err = format!("Failed to parse project id: {}", e);
+                        warn!("{}", err);
+                        return GetGraphResponse::error(ApiError::from_error(err, &e));
+                    }
+                }
+            }
             None => {
-                warn!("Query string is empty.");
-                "".to_string()
+                warn!("Project id is empty.");
+                -1
             }
         };
 
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
-                    warn!("{}", err);
-                    return GetRelationCountResponse::bad_request(err);
+        let organization_id = match organization_id.0 {
+            Some(organization_id) => {
+                // Convert organization_id to i32
+                match organization_id.parse::<i32>() {
+                    Ok(organization_id) => organization_id,
+                    Err(e) => {
+                        let err = format!("Failed to parse organization id: {}", e);
+                        warn!("{}", err);
+                        return GetGraphResponse::error(ApiError::from_error(err, &e));
+                    }
                 }
             }
+            None => {
+                warn!("Organization id is empty.");
+                -1
+            }
         };
 
-        match RelationCount::get_records(&pool_arc, &query).await {
-            Ok(entities) => GetRelationCountResponse::ok(entities),
+        // Get organizations and projects from the token
+        let user = &_token.0;
+        if organization_id != -1 && !user.organizations.contains(&organization_id) {
+            let err = format!(
+                "User {} doesn't have access to organization {}. Your system might not support querying curated graph by organization or you don't have access to this organization.",
+                user.username, organization_id
+            );
+            warn!("{}", err);
+            return GetGraphResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        };
+
+        if project_id != -1 && !user.projects.contains(&project_id) {
+            let err = format!(
+                "User {} doesn't have access to project {}. Your system might not support querying curated graph by project or you don't have access to this project.",
+                user.username, project_id
+            );
+            warn!("{}", err);
+            return GetGraphResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        };
+
+        let mut graph = Graph::new();
+        let page = page.0;
+        let page_size = page_size.0;
+        let strict_mode = strict_mode.0;
+
+        match graph
+            .fetch_curated_knowledges(
+                &pool_arc,
+                &curator[..],
+                project_id,
+                organization_id,
+                page,
+                page_size,
+                Some("id ASC"),
+                strict_mode,
+            )
+            .await
+        {
+            Ok(data) => {
+                let mut graph = data.to_owned().get_graph(None).unwrap();
+                graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                count_graph_flags(&pool_arc, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
             Err(e) => {
-                let err = format!("Failed to fetch relations: {}", e);
+                let err = format!("Failed to fetch curated graph: {}", e);
                 warn!("{}", err);
-                return GetRelationCountResponse::bad_request(err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
             }
         }
     }
 
-    /// Call `/api/v1/entity2d` with query params to fetch entity2d.
+    /// Call `/api/v1/curated-knowledges-by-owner` with query params to fetch curated knowledges by owner.
+    ///
+    /// If workspace_id is set, only the curated knowledges in the workspace are returned, and the current user must be a member of the workspace.
     #[oai(
-        path = "/entity2d",
+        path = "/curated-knowledges-by-owner",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchEntity2d"
+        operation_id = "fetchCuratedKnowledgesByOwner"
     )]
-    async fn fetch_entity2d(
+    async fn fetch_curated_knowledges_by_owner(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        curator: Query<String>,
+        project_id: Query<Option<String>>,
+        organization_id: Query<Option<String>>,
+        workspace_id: Query<Option<i64>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        // We need to confirm the token is valid and contains all projects and organizations which the user has access to.
+        _token: CustomSecurityScheme,
+    ) -> GetRecordsResponse<KnowledgeCuration> {
+        let pool_arc = pool.clone();
+        let curator = curator.0;
+
+        if let Err(e) = Pagination::new(page.0, page_size.0) {
+            let err = format!("Failed to parse pagination: {}", e);
+            warn!("{}", err);
+            return GetRecordsResponse::error(ApiError::from_error(err, &e));
+        }
+
+        if curator != _token.0.username {
+            let err = format!(
+                "You cannot query curated knowledges from other users. You are {} and you are querying {}'s curated knowledges.",
+                _token.0.username, curator
+            );
+            warn!("{}", err);
+            return GetRecordsResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        }
+
+        let project_id = match project_id.0 {
+            Some(project_id) => {
+                // Convert project_id to i32
+                match project_id.parse::<i32>() {
+                    Ok(project_id) => project_id,
+                    Err(e) => {
+                        let err = format!("Failed to parse project id: {}", e);
+                        warn!("{}", err);
+                        return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                    }
+                }
+            }
+            None => {
+                warn!("Project id is empty.");
+                -1
+            }
+        };
+
+        let organization_id = match organization_id.0 {
+            Some(organization_id) => {
+                // Convert organization_id to i32
+                match organization_id.parse::<i32>() {
+                    Ok(organization_id) => organization_id,
+                    Err(e) => {
+                        let err = format!("Failed to parse organization id: {}", e);
+                        warn!("{}", err);
+                        return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                    }
+                }
+            }
+            None => {
+                warn!("Organization id is empty.");
+                -1
+            }
+        };
+
+        // Get organizations and projects from the token
+        let user = &_token.0;
+        if organization_id != -1 && !user.organizations.contains(&organization_id) {
+            let err = format!(
+                "User {} doesn't have access to organization {}. Your system might not support querying curated knowledges by organization or you don't have access to this organization.",
+                user.username, organization_id
+            );
+            warn!("{}", err);
+            return GetRecordsResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        };
+
+        if project_id != -1 && !user.projects.contains(&project_id) {
+            let err = format!(
+                "User {} doesn't have access to project {}. Your system might not support querying curated knowledges by project or you don't have access to this project.",
+                user.username, project_id
+            );
+            warn!("{}", err);
+            return GetRecordsResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        };
+
+        let workspace_id = workspace_id.0;
+        if let Some(workspace_id) = workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Viewer)
+                    .await
+            {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        match KnowledgeCuration::get_records_by_owner(
+            &pool_arc,
+            &curator,
+            project_id,
+            organization_id,
+            workspace_id,
+            page.0,
+            page_size.0,
+            // TODO: get an order_by clause from query
+            Some("id ASC"),
+        )
+        .await
+        {
+            Ok(entities) => GetRecordsResponse::ok(entities),
+            Err(e) => {
+                let err = format!("Failed to fetch curated knowledges: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges` with query params to fetch curated knowledges.
+    ///
+    /// If workspace_id is set, only the curated knowledges in the workspace are returned, and the current user must be a member of the workspace. Otherwise the curated knowledges without a workspace and the ones in the workspaces of the current user are returned.
+    ///
+    /// Use the `count` param (exact, estimated or cached, default: exact) to choose how the `total` is counted.
+    #[oai(
+        path = "/curated-knowledges",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchCuratedKnowledges"
+    )]
+    async fn fetch_curated_knowledges(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
         page: Query<Option<u64>>,
         page_size: Query<Option<u64>>,
         query_str: Query<Option<String>>,
+        fields: Query<Option<String>>,
+        order_by: Query<Option<String>>,
+        workspace_id: Query<Option<i64>>,
+        count: Query<Option<CountStrategy>>,
         _token: CustomSecurityScheme,
-    ) -> GetRecordsResponse<Entity2D> {
+    ) -> GetRecordsResponse<KnowledgeCuration> {
         let pool_arc = pool.clone();
+        let count_strategy = count.0.unwrap_or(CountStrategy::Exact);
         let page = page.0;
         let page_size = page_size.0;
+        let workspace_id = workspace_id.0;
+
+        if let Some(workspace_id) = workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Viewer)
+                    .await
+            {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let fields = match fields.0 {
+            Some(fields) => match KnowledgeCuration::parse_selected_fields(&fields) {
+                Ok(fields) => Some(fields),
+                Err(e) => {
+                    let err = format!("Failed to parse fields: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let order_by = match order_by.0 {
+            Some(order_by) => match KnowledgeCuration::parse_order_by(&order_by) {
+                Ok(order_by) => Some(order_by),
+                Err(e) => {
+                    let err = format!("Failed to parse order_by: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
 
         match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
             Ok(_) => {}
             Err(e) => {
                 let err = format!("Failed to parse query string: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &e));
             }
         }
 
@@ -778,441 +1480,5071 @@ impl BiomedgpsApi {
                 Err(e) => {
                     let err = format!("Failed to parse query string: {}", e);
                     warn!("{}", err);
-                    return GetRecordsResponse::bad_request(err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
                 }
             }
         };
 
-        match RecordResponse::<Entity2D>::get_records(
-            &pool_arc,
-            "biomedgps_entity2d",
-            &query,
-            page,
-            page_size,
-            Some("embedding_id ASC"),
-        )
-        .await
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<KnowledgeCuration>(query) {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+
+            if let Err(e) = check_query_vocabulary(&pool_arc, query).await {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let query = match workspace_id {
+            Some(workspace_id) => Some(Workspace::merge_workspace_query(workspace_id, query)),
+            None => match merge_member_workspaces(&pool_arc, &_token.0, query).await {
+                Ok(query) => query,
+                Err(e) => {
+                    warn!("{}", e.message);
+                    return GetRecordsResponse::error(e);
+                }
+            },
+        };
+
+        let order_by_clause = order_by.unwrap_or("id ASC".to_string());
+
+        if let Some(fields) = fields {
+            return match ProjectedRecordResponse::get_records::<KnowledgeCuration>(
+                &pool_arc,
+                TableName::KnowledgeCuration,
+                &fields,
+                &query,
+                page,
+                page_size,
+                Some(order_by_clause.as_str()),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(records) => GetRecordsResponse::projected(records),
+                Err(e) => {
+                    let err = format!("Failed to fetch curated knowledges: {}", e);
+                    warn!("{}", err);
+                    GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        match RecordResponse::<KnowledgeCuration>::get_records_with_count(
+            &pool_arc,
+            TableName::KnowledgeCuration,
+            &query,
+            page,
+            page_size,
+            Some(order_by_clause.as_str()),
+            count_strategy,
+        )
+        .await
         {
             Ok(entities) => GetRecordsResponse::ok(entities),
             Err(e) => {
-                let err = format!("Failed to fetch entity2d: {}", e);
+                let err = format!("Failed to fetch curated knowledges: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges` with payload to create a curated knowledge.
+    #[oai(
+        path = "/curated-knowledges",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postCuratedKnowledge"
+    )]
+    async fn post_curated_knowledge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<KnowledgeCuration>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<KnowledgeCuration> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let mut payload = payload.0;
+        let username = _token.0.username.clone();
+
+        // When we enabled auth mode, we need to use the username from an access_token as the curator, so that only the curator can delete it.
+        if username != USERNAME_PLACEHOLDER.to_string() {
+            payload.update_curator(username);
+        }
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate payload: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Err(e) = check_curation_vocabulary(&pool_arc, &payload).await {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        if let Some(workspace_id) = payload.workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PostResponse::error(e);
+            }
+        }
+
+        match payload.insert(&pool_arc).await {
+            Ok(kc) => {
+                // Resolve the title and abstract of the pmid in the background.
+                enqueue_publications(&[kc.pmid]);
+                invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                spawn_event(
+                    pool.0.clone(),
+                    EventType::CurationCreated,
+                    &_token.0.username,
+                    kc.workspace_id,
+                    serde_json::json!(kc),
+                );
+                PostResponse::Created(Json(kc))
+            }
+            Err(e) => {
+                let err = format!("Failed to insert curated knowledge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges/:id` with payload to update a curated knowledge. Only the curator of the knowledge or an admin can update it, and the curator is kept.
+    #[oai(
+        path = "/curated-knowledges/:id",
+        method = "put",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "putCuratedKnowledge"
+    )]
+    async fn put_curated_knowledge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<KnowledgeCuration>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<KnowledgeCuration> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let payload = payload.0;
+        let id = id.0;
+
+        if id < 0 {
+            let err = format!("Invalid id: {}", id);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate payload: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Err(e) = check_curation_vocabulary(&pool_arc, &payload).await {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        let current = match KnowledgeCuration::get_record(&pool_arc, id).await {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                let err = format!("Cannot find the curated knowledge {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch curated knowledge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if !_token.0.can_manage(&current.curator) {
+            let err = format!(
+                "The curated knowledge {} is owned by {}, you cannot update it.",
+                id, current.curator
+            );
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        // The editor role is required in the workspace which the knowledge is in and the one which it is moved into.
+        for workspace_id in [current.workspace_id, payload.workspace_id]
+            .into_iter()
+            .flatten()
+        {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PostResponse::error(e);
+            }
+        }
+
+        match payload.update(&pool_arc, id).await {
+            Ok(kc) => {
+                invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                spawn_event(
+                    pool.0.clone(),
+                    EventType::CurationUpdated,
+                    &_token.0.username,
+                    kc.workspace_id,
+                    serde_json::json!(kc),
+                );
+                PostResponse::Created(Json(kc))
+            }
+            Err(e) => {
+                let err = format!("Failed to insert curated knowledge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges/:id` with payload to delete a curated knowledge. Only the curator of the knowledge or an admin can delete it. The curated knowledge is soft deleted, it can be restored by `/api/v1/curated-knowledges/:id/restore` until it is purged by `biomedgps-cli purgedb`.
+    #[oai(
+        path = "/curated-knowledges/:id",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteCuratedKnowledge"
+    )]
+    async fn delete_curated_knowledge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return DeleteResponse::forbidden(err);
+        }
+
+        let id = id.0;
+
+        if id < 0 {
+            let err = format!("Invalid id: {}", id);
+            warn!("{}", err);
+            return DeleteResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        match KnowledgeCuration::get_record(&pool_arc, id).await {
+            Ok(Some(record)) => {
+                if !_token.0.can_manage(&record.curator) {
+                    let err = format!(
+                        "The curated knowledge {} is owned by {}, you cannot delete it.",
+                        id, record.curator
+                    );
+                    warn!("{}", err);
+                    return DeleteResponse::forbidden(err);
+                }
+            }
+            Ok(None) => {
+                let err = format!("Cannot find the curated knowledge {}.", id);
+                warn!("{}", err);
+                return DeleteResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch curated knowledge: {}", e);
+                warn!("{}", err);
+                return DeleteResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        match KnowledgeCuration::delete(&pool_arc, id).await {
+            Ok(kc) => {
+                invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                spawn_event(
+                    pool.0.clone(),
+                    EventType::CurationDeleted,
+                    &_token.0.username,
+                    kc.workspace_id,
+                    serde_json::json!(kc),
+                );
+                DeleteResponse::no_content()
+            }
+            Err(e) => {
+                let err = format!("Failed to delete curated knowledge: {}", e);
+                warn!("{}", err);
+                DeleteResponse::not_found(err)
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges/:id/restore` to restore a deleted curated knowledge. Only the curator of the knowledge or an admin can restore it, and it cannot be restored after it is purged.
+    #[oai(
+        path = "/curated-knowledges/:id/restore",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "restoreCuratedKnowledge"
+    )]
+    async fn restore_curated_knowledge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<KnowledgeCuration> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let id = id.0;
+
+        if id < 0 {
+            let err = format!("Invalid id: {}", id);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        match KnowledgeCuration::get_deleted_record(&pool_arc, id).await {
+            Ok(Some(record)) => {
+                if !_token.0.can_manage(&record.curator) {
+                    let err = format!(
+                        "The curated knowledge {} is owned by {}, you cannot restore it.",
+                        id, record.curator
+                    );
+                    warn!("{}", err);
+                    return PostResponse::forbidden(err);
+                }
+            }
+            Ok(None) => {
+                let err = format!("Cannot find the deleted curated knowledge {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch curated knowledge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        match KnowledgeCuration::restore(&pool_arc, id).await {
+            Ok(kc) => {
+                invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                spawn_event(
+                    pool.0.clone(),
+                    EventType::CurationRestored,
+                    &_token.0.username,
+                    kc.workspace_id,
+                    serde_json::json!(kc),
+                );
+                PostResponse::Created(Json(kc))
+            }
+            Err(e) => {
+                let err = format!("Failed to restore curated knowledge: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/curated-knowledges` with a list of ids to delete curated knowledges in bulk.
+    ///
+    /// Only the curator of the knowledge or an admin can delete it. Each id is handled independently and the result (deleted, not_found, forbidden or failed) of each id is returned.
+    #[oai(
+        path = "/curated-knowledges",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteCuratedKnowledges"
+    )]
+    async fn delete_curated_knowledges(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<Vec<i64>>,
+        _token: CustomSecurityScheme,
+    ) -> BulkDeleteResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return BulkDeleteResponse::forbidden(err);
+        }
+
+        let mut ids = payload.0;
+        ids.sort();
+        ids.dedup();
+
+        if ids.is_empty() {
+            let err = "The list of ids cannot be empty.".to_string();
+            warn!("{}", err);
+            return BulkDeleteResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        let mut results: Vec<DeletionResult> = vec![];
+        for id in ids {
+            let record = match KnowledgeCuration::get_record(&pool_arc, id).await {
+                Ok(Some(record)) => record,
+                Ok(None) => {
+                    results.push(DeletionResult::new(id, "not_found", None));
+                    continue;
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch curated knowledge: {}", e);
+                    warn!("{}", err);
+                    results.push(DeletionResult::new(id, "failed", Some(err)));
+                    continue;
+                }
+            };
+
+            if !_token.0.can_manage(&record.curator) {
+                let err = format!("The curated knowledge is owned by {}.", record.curator);
+                results.push(DeletionResult::new(id, "forbidden", Some(err)));
+                continue;
+            }
+
+            match KnowledgeCuration::delete(&pool_arc, id).await {
+                Ok(kc) => {
+                    spawn_event(
+                        pool.0.clone(),
+                        EventType::CurationDeleted,
+                        &_token.0.username,
+                        kc.workspace_id,
+                        serde_json::json!(kc),
+                    );
+                    results.push(DeletionResult::new(id, "deleted", None));
+                }
+                Err(e) => {
+                    let err = format!("Failed to delete curated knowledge: {}", e);
+                    warn!("{}", err);
+                    results.push(DeletionResult::new(id, "failed", Some(err)));
+                }
+            }
+        }
+
+        if results.iter().any(|r| r.status == "deleted") {
+            invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+            invalidate_materialized_graphs(&pool_arc).await;
+        }
+
+        BulkDeleteResponse::ok(results)
+    }
+
+    /// Call `/api/v1/relations` with query params to fetch relations.
+    ///
+    /// Use the `count` param (exact, estimated or cached, default: cached) to choose how the `total` is counted, it is cached by default because the relation table is large.
+    ///
+    /// Use the `format` param (ndjson or csv) or the `Accept: application/x-ndjson` (or `text/csv`) header to export all the matched relations in one streamed response, the `page` and `page_size` params are ignored.
+    ///
+    /// Use `expand_descendants=true` to match the relations of the subclasses too, the ids in the `source_id`, `target_id` and `id` filters (`=` and `in` only) are expanded by their descendants within `max_depth` (between 1 and 30, 10 by default) is_a steps in the ontologies imported by `biomedgps-cli import-ontology`, such as `DOID:417` (autoimmune disease) to all autoimmune diseases.
+    ///
+    /// Use `with_inverse=true` to match the inverse relations of the queried relation types (see `/api/v1/relation-types`), such as the stored `treats` relations from the compounds to a disease for a query on the `treated_by` relations of the disease, they are returned as the `treated_by` relations with the swapped ends. The relation types are the `=` and `in` filters of the relation_type field at the top level of the query, and it cannot be used with `fields` or `format`.
+    #[oai(
+        path = "/relations",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchRelations"
+    )]
+    async fn fetch_relations(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        query_str: Query<Option<String>>,
+        fields: Query<Option<String>>,
+        order_by: Query<Option<String>>,
+        count: Query<Option<CountStrategy>>,
+        format: Query<Option<RecordFormat>>,
+        expand_descendants: Query<Option<bool>>,
+        max_depth: Query<Option<usize>>,
+        with_inverse: Query<Option<bool>>,
+        #[oai(name = "Accept")] accept: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetRecordsResponse<Relation> {
+        let pool_arc = pool.clone();
+        let count_strategy = count.0.unwrap_or(CountStrategy::Cached);
+        let page = page.0;
+        let page_size = page_size.0;
+
+        let fields = match fields.0 {
+            Some(fields) => match Relation::parse_selected_fields(&fields) {
+                Ok(fields) => Some(fields),
+                Err(e) => {
+                    let err = format!("Failed to parse fields: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let order_by = match order_by.0 {
+            Some(order_by) => match Relation::parse_order_by(&order_by) {
+                Ok(order_by) => Some(order_by),
+                Err(e) => {
+                    let err = format!("Failed to parse order_by: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to parse query string: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Relation>(query) {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+
+            if let Err(e) = check_query_vocabulary(&pool_arc, query).await {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let query = match query {
+            Some(query) if expand_descendants.0.unwrap_or(false) => {
+                match expand_query_descendants(&pool_arc, &query, max_depth.0).await {
+                    Ok(query) => Some(query),
+                    Err(e) => {
+                        warn!("{}", e.message);
+                        return GetRecordsResponse::error(e);
+                    }
+                }
+            }
+            query => query,
+        };
+
+        let order_by_clause = order_by.unwrap_or("id ASC".to_string());
+
+        let format = RecordFormat::negotiate(format.0, accept.0.as_deref());
+        let inverse_query = match &query {
+            Some(query) if with_inverse.0.unwrap_or(false) => get_relation_type_registry()
+                .and_then(|registry| registry.with_inverse_relations(query)),
+            _ => None,
+        };
+        if let Some(inverse_query) = inverse_query {
+            // The inverse relations are inverted after they are fetched, so only the full records are supported.
+            if fields.is_some() || format != RecordFormat::Json {
+                return GetRecordsResponse::bad_request(
+                    "The with_inverse param cannot be used with the fields or format params."
+                        .to_string(),
+                );
+            }
+
+            return match RecordResponse::<Relation>::get_records_with_count(
+                &pool_arc,
+                TableName::Relation,
+                &Some(inverse_query.query.clone()),
+                page,
+                page_size,
+                Some(order_by_clause.as_str()),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(mut relations) => {
+                    relations.records = inverse_query.invert_relations(relations.records);
+                    GetRecordsResponse::ok(relations)
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch relations: {}", e);
+                    warn!("{}", err);
+                    GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+        if format != RecordFormat::Json {
+            let records = stream_records::<Relation>(
+                pool.0.clone(),
+                TableName::Relation,
+                fields,
+                &query,
+                Some(order_by_clause.as_str()),
+                format,
+            );
+            return GetRecordsResponse::stream(records, format);
+        }
+
+        if let Some(fields) = fields {
+            return match ProjectedRecordResponse::get_records::<Relation>(
+                &pool_arc,
+                TableName::Relation,
+                &fields,
+                &query,
+                page,
+                page_size,
+                Some(order_by_clause.as_str()),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(records) => GetRecordsResponse::projected(records),
+                Err(e) => {
+                    let err = format!("Failed to fetch relations: {}", e);
+                    warn!("{}", err);
+                    GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        match RecordResponse::<Relation>::get_records_with_count(
+            &pool_arc,
+            TableName::Relation,
+            &query,
+            page,
+            page_size,
+            Some(order_by_clause.as_str()),
+            count_strategy,
+        )
+        .await
+        {
+            Ok(entities) => GetRecordsResponse::ok(entities),
+            Err(e) => {
+                let err = format!("Failed to fetch relations: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-counts` with query params to fetch relation counts.
+    #[oai(
+        path = "/relation-counts",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchRelationCounts"
+    )]
+    async fn fetch_relation_counts(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        query_str: Query<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetRelationCountResponse {
+        let pool_arc = pool.clone();
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetRelationCountResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Relation>(query) {
+                warn!("{}", e.message);
+                return GetRelationCountResponse::error(e);
+            }
+
+            if let Err(e) = check_query_vocabulary(&pool_arc, query).await {
+                warn!("{}", e.message);
+                return GetRelationCountResponse::error(e);
+            }
+        }
+
+        match RelationCount::get_records(&pool_arc, &query).await {
+            Ok(entities) => GetRelationCountResponse::ok(entities),
+            Err(e) => {
+                let err = format!("Failed to fetch relations: {}", e);
+                warn!("{}", err);
+                return GetRelationCountResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/entity2d` with query params to fetch entity2d.
+    ///
+    /// Use `x_min`, `x_max`, `y_min` and `y_max` to only fetch the entities in the viewport of the `coordinate` (umap or tsne, default: umap), and `entity_type` (such as `Gene,Disease`) to only fetch the entities with the entity types.
+    ///
+    /// If `max_points` is specified, all entities in the viewport are returned when there are no more than `max_points` entities, otherwise the viewport is split into `bins` x `bins` cells (default: 100) and the centroids and counts of the entities in each cell (per entity type) are returned. So the map can be zoomed in to see the entities.
+    ///
+    /// Use the `count` param (exact, estimated or cached, default: exact) to choose how the `total` is counted.
+    #[oai(
+        path = "/entity2d",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEntity2d"
+    )]
+    async fn fetch_entity2d(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        query_str: Query<Option<String>>,
+        fields: Query<Option<String>>,
+        coordinate: Query<Option<Coordinate2D>>,
+        x_min: Query<Option<f64>>,
+        x_max: Query<Option<f64>>,
+        y_min: Query<Option<f64>>,
+        y_max: Query<Option<f64>>,
+        entity_type: Query<Option<String>>,
+        max_points: Query<Option<u64>>,
+        bins: Query<Option<u64>>,
+        count: Query<Option<CountStrategy>>,
+        _token: CustomSecurityScheme,
+    ) -> GetEntity2DResponse {
+        let pool_arc = pool.clone();
+        let count_strategy = count.0.unwrap_or(CountStrategy::Exact);
+        let page = page.0;
+        let page_size = page_size.0;
+
+        let fields = match fields.0 {
+            Some(fields) => match Entity2D::parse_selected_fields(&fields) {
+                Ok(fields) => Some(fields),
+                Err(e) => {
+                    let err = format!("Failed to parse fields: {}", e);
+                    warn!("{}", err);
+                    return GetEntity2DResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let viewport = match Entity2DViewport::new(
+            coordinate.0.unwrap_or(Coordinate2D::Umap),
+            x_min.0,
+            x_max.0,
+            y_min.0,
+            y_max.0,
+            entity_type.0.as_deref(),
+        ) {
+            Ok(viewport) => viewport,
+            Err(e) => {
+                let err = format!("Failed to parse the viewport: {}", e);
+                warn!("{}", err);
+                return GetEntity2DResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let bins = bins.0.unwrap_or(DEFAULT_MAP_BINS);
+        if bins == 0 || bins > MAX_MAP_BINS {
+            let err = format!("The bins must be between 1 and {}.", MAX_MAP_BINS);
+            warn!("{}", err);
+            return GetEntity2DResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        if let Some(max_points) = max_points.0 {
+            if max_points > MAX_MAP_POINTS {
+                let err = format!(
+                    "The max_points must be less than or equal to {}.",
+                    MAX_MAP_POINTS
+                );
+                warn!("{}", err);
+                return GetEntity2DResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+            }
+        }
+
+        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to parse query string: {}", e);
+                warn!("{}", err);
+                return GetEntity2DResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetEntity2DResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Entity2D>(query) {
+                warn!("{}", e.message);
+                return GetEntity2DResponse::error(e);
+            }
+        }
+
+        if let Some(max_points) = max_points.0 {
+            return match Entity2DMap::get_map(&pool_arc, &viewport, &query, max_points, bins).await
+            {
+                Ok(map) => GetEntity2DResponse::map(map),
+                Err(e) => {
+                    let err = format!("Failed to fetch entity2d: {}", e);
+                    warn!("{}", err);
+                    GetEntity2DResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        let query = viewport.merge_query(query);
+        if let Some(fields) = fields {
+            return match ProjectedRecordResponse::get_records::<Entity2D>(
+                &pool_arc,
+                TableName::Entity2D,
+                &fields,
+                &query,
+                page,
+                page_size,
+                Some("embedding_id ASC"),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(records) => GetEntity2DResponse::projected(records),
+                Err(e) => {
+                    let err = format!("Failed to fetch entity2d: {}", e);
+                    warn!("{}", err);
+                    GetEntity2DResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        match RecordResponse::<Entity2D>::get_records_with_count(
+            &pool_arc,
+            TableName::Entity2D,
+            &query,
+            page,
+            page_size,
+            Some("embedding_id ASC"),
+            count_strategy,
+        )
+        .await
+        {
+            Ok(entities) => GetEntity2DResponse::ok(entities),
+            Err(e) => {
+                let err = format!("Failed to fetch entity2d: {}", e);
+                warn!("{}", err);
+                return GetEntity2DResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs` with query params to fetch subgraphs.
+    ///
+    /// If contains_node_id is set, such as `Gene::ENTREZ:7157`, only the subgraphs which contain the node are returned. It can be combined with the query string, such as filtering by owner.
+    ///
+    /// If workspace_id is set, only the subgraphs in the workspace are returned, and the current user must be a member of the workspace. Otherwise the subgraphs without a workspace and the ones in the workspaces of the current user are returned.
+    ///
+    /// Use the `count` param (exact, estimated or cached, default: exact) to choose how the `total` is counted. The payloads which are offloaded to the object storage are resolved, so the list is slower when the large payloads are selected.
+    #[oai(
+        path = "/subgraphs",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSubgraphs"
+    )]
+    async fn fetch_subgraphs(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        query_str: Query<Option<String>>,
+        fields: Query<Option<String>>,
+        order_by: Query<Option<String>>,
+        contains_node_id: Query<Option<String>>,
+        workspace_id: Query<Option<i64>>,
+        count: Query<Option<CountStrategy>>,
+        _token: CustomSecurityScheme,
+    ) -> GetRecordsResponse<Subgraph> {
+        let pool_arc = pool.clone();
+        let count_strategy = count.0.unwrap_or(CountStrategy::Exact);
+        let page = page.0;
+        let page_size = page_size.0;
+        let workspace_id = workspace_id.0;
+
+        if let Some(workspace_id) = workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Viewer)
+                    .await
+            {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let fields = match fields.0 {
+            Some(fields) => match Subgraph::parse_selected_fields(&fields) {
+                Ok(fields) => Some(fields),
+                Err(e) => {
+                    let err = format!("Failed to parse fields: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let order_by = match order_by.0 {
+            Some(order_by) => match Subgraph::parse_order_by(&order_by) {
+                Ok(order_by) => Some(order_by),
+                Err(e) => {
+                    let err = format!("Failed to parse order_by: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to parse query string: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Subgraph>(query) {
+                warn!("{}", e.message);
+                return GetRecordsResponse::error(e);
+            }
+        }
+
+        let query = match contains_node_id.0 {
+            Some(node_id) => match Subgraph::merge_node_query(&node_id, query) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse contains_node_id: {}", e);
+                    warn!("{}", err);
+                    return GetRecordsResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => query,
+        };
+
+        let query = match workspace_id {
+            Some(workspace_id) => Some(Workspace::merge_workspace_query(workspace_id, query)),
+            None => match merge_member_workspaces(&pool_arc, &_token.0, query).await {
+                Ok(query) => query,
+                Err(e) => {
+                    warn!("{}", e.message);
+                    return GetRecordsResponse::error(e);
+                }
+            },
+        };
+
+        let order_by_clause = order_by.unwrap_or("created_time DESC".to_string());
+
+        if let Some(fields) = fields {
+            return match ProjectedRecordResponse::get_records::<Subgraph>(
+                &pool_arc,
+                TableName::Subgraph,
+                &fields,
+                &query,
+                page,
+                page_size,
+                Some(order_by_clause.as_str()),
+                count_strategy,
+            )
+            .await
+            {
+                Ok(mut records) => {
+                    let projected = std::mem::take(&mut records.records);
+                    match Subgraph::resolve_projected_payloads(projected).await {
+                        Ok(resolved) => {
+                            records.records = resolved;
+                            GetRecordsResponse::projected(records)
+                        }
+                        Err(e) => {
+                            let err = format!("Failed to resolve the payloads of subgraphs: {}", e);
+                            warn!("{}", err);
+                            GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                        }
+                    }
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch subgraphs: {}", e);
+                    warn!("{}", err);
+                    GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                }
+            };
+        }
+
+        match RecordResponse::<Subgraph>::get_records_with_count(
+            &pool_arc,
+            TableName::Subgraph,
+            &query,
+            page,
+            page_size,
+            Some(order_by_clause.as_str()),
+            count_strategy,
+        )
+        .await
+        {
+            Ok(mut entities) => {
+                let records = std::mem::take(&mut entities.records);
+                match Subgraph::resolve_payloads(records).await {
+                    Ok(resolved) => {
+                        entities.records = resolved;
+                        GetRecordsResponse::ok(entities)
+                    }
+                    Err(e) => {
+                        let err = format!("Failed to resolve the payloads of subgraphs: {}", e);
+                        warn!("{}", err);
+                        GetRecordsResponse::error(ApiError::from_error(err, &*e))
+                    }
+                }
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch subgraphs: {}", e);
+                warn!("{}", err);
+                return GetRecordsResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs` with payload to create a subgraph.
+    ///
+    /// The payload must be a graph, such as `{"nodes": [{"id": "Gene::ENTREZ:7157", ...}], "edges": [{"source": "Gene::ENTREZ:7157", "target": "Disease::MESH:D001943", "reltype": "...", ...}]}`. The node ids must be unique and the edges must connect the nodes. If check_nodes is true, the nodes must be in the entity table.
+    #[oai(
+        path = "/subgraphs",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postSubgraph"
+    )]
+    async fn post_subgraph(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<Subgraph>,
+        check_nodes: Query<Option<bool>>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<Subgraph> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let mut payload = payload.0;
+        let username = _token.0.username.clone();
+
+        // When we enabled auth mode, we need to use the username from an access_token instead.
+        if username != USERNAME_PLACEHOLDER.to_string() {
+            payload.update_owner(username);
+        }
+
+        // The subgraph is built from the active dataset version, so we record it instead of trusting the client.
+        if let Some(release_tag) = DatasetVersion::get_active_tag(&pool_arc).await {
+            payload.update_db_version(release_tag);
+        }
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate subgraph: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Some(workspace_id) = payload.workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PostResponse::error(e);
+            }
+        }
+
+        let check_nodes = check_nodes.0.unwrap_or(false);
+        if let Err(e) =
+            GraphPayload::parse_and_check(&pool_arc, &payload.payload, check_nodes).await
+        {
+            let err = format!("Failed to validate the subgraph payload: {}", e);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::from_error(err, &e));
+        }
+
+        match payload.insert(&pool_arc).await {
+            Ok(kc) => {
+                if kc.workspace_id.is_some() {
+                    spawn_event(
+                        pool.0.clone(),
+                        EventType::SubgraphShared,
+                        &_token.0.username,
+                        kc.workspace_id,
+                        kc.get_share_summary(),
+                    );
+                }
+                PostResponse::Created(Json(kc))
+            }
+            Err(e) => {
+                let err = format!("Failed to insert curated knowledge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs/:id` with payload to update a subgraph. The payload is validated in the same way as `/api/v1/subgraphs`. Only the owner of the subgraph or an admin can update it, and the owner is kept.
+    ///
+    /// The If-Match header is required, it is the ETag (the revision) of the subgraph which the changes are based on, such as `"3"`. If the subgraph has been updated by others since then, the update is rejected with 409 and the current subgraph, so the changes of others are not overwritten silently. Use `*` to overwrite the subgraph without the check. The updated subgraph and its new ETag are returned.
+    #[oai(
+        path = "/subgraphs/:id",
+        method = "put",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "putSubgraph"
+    )]
+    async fn put_subgraph(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<String>,
+        payload: Json<Subgraph>,
+        check_nodes: Query<Option<bool>>,
+        #[oai(name = "If-Match")] if_match: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> PutSubgraphResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PutSubgraphResponse::forbidden(err);
+        }
+
+        let revision = match if_match.0.as_deref().map(Subgraph::parse_if_match) {
+            Some(Ok(revision)) => revision,
+            Some(Err(e)) => {
+                let err = format!("Failed to parse the If-Match header: {}", e);
+                warn!("{}", err);
+                return PutSubgraphResponse::error(ApiError::from_error(err, &e));
+            }
+            None => {
+                let err = "The If-Match header is required, it should be the ETag of the subgraph, such as \"3\".".to_string();
+                warn!("{}", err);
+                return PutSubgraphResponse::precondition_required(err);
+            }
+        };
+
+        let id = id.0;
+        let mut payload = payload.0;
+
+        match SubgraphIdQuery::new(&id) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to parse subgraph id: {}", e);
+                warn!("{}", err);
+                return PutSubgraphResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        let current = match Subgraph::get_record(&pool_arc, &id).await {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                let err = format!("Cannot find the subgraph {}.", id);
+                warn!("{}", err);
+                return PutSubgraphResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                warn!("{}", err);
+                return PutSubgraphResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if !_token.0.can_manage(&current.owner) {
+            let err = format!(
+                "The subgraph {} is owned by {}, you cannot update it.",
+                id, current.owner
+            );
+            warn!("{}", err);
+            return PutSubgraphResponse::forbidden(err);
+        }
+
+        // The subgraph is still owned by its owner after it is updated by an admin.
+        payload.update_owner(current.owner.clone());
+
+        if let Some(release_tag) = DatasetVersion::get_active_tag(&pool_arc).await {
+            payload.update_db_version(release_tag);
+        }
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate subgraph: {}", e);
+                warn!("{}", err);
+                return PutSubgraphResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        // The editor role is required in the workspace which the subgraph is in and the one which it is moved into.
+        for workspace_id in [current.workspace_id, payload.workspace_id]
+            .into_iter()
+            .flatten()
+        {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PutSubgraphResponse::error(e);
+            }
+        }
+
+        let check_nodes = check_nodes.0.unwrap_or(false);
+        if let Err(e) =
+            GraphPayload::parse_and_check(&pool_arc, &payload.payload, check_nodes).await
+        {
+            let err = format!("Failed to validate the subgraph payload: {}", e);
+            warn!("{}", err);
+            return PutSubgraphResponse::error(ApiError::from_error(err, &e));
+        }
+
+        if revision.is_some_and(|revision| revision != current.revision) {
+            warn!(
+                "The subgraph {} has been updated to revision {}, the update is based on revision {:?}.",
+                id, current.revision, revision
+            );
+            return PutSubgraphResponse::conflict(current);
+        }
+
+        // The subgraph is shared when it is moved into another workspace.
+        let shared = payload.workspace_id.is_some() && current.workspace_id != payload.workspace_id;
+
+        match payload.update(&pool_arc, &id, revision).await {
+            Ok(Some(subgraph)) => {
+                // The curators who are editing the subgraph by the websocket get the new revision.
+                broadcast(
+                    &id,
+                    &ServerMessage::Snapshot {
+                        subgraph: subgraph.clone(),
+                    },
+                );
+                if shared {
+                    spawn_event(
+                        pool.0.clone(),
+                        EventType::SubgraphShared,
+                        &_token.0.username,
+                        subgraph.workspace_id,
+                        subgraph.get_share_summary(),
+                    );
+                }
+                PutSubgraphResponse::created(subgraph)
+            }
+            // The subgraph is updated or deleted by others after it is fetched.
+            Ok(None) => match Subgraph::get_record(&pool_arc, &id).await {
+                Ok(Some(record)) => PutSubgraphResponse::conflict(record),
+                Ok(None) => {
+                    let err = format!("Cannot find the subgraph {}.", id);
+                    warn!("{}", err);
+                    PutSubgraphResponse::not_found(err)
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                    warn!("{}", err);
+                    PutSubgraphResponse::error(ApiError::from_error(err, &*e))
+                }
+            },
+            Err(e) => {
+                let err = format!("Failed to update subgraph: {}", e);
+                warn!("{}", err);
+                PutSubgraphResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs/:id` to delete a subgraph, the child subgraphs of a root subgraph are deleted together. Only the owner of the subgraph or an admin can delete it. The subgraph is soft deleted, it can be restored by `/api/v1/subgraphs/:id/restore` until it is purged by `biomedgps-cli purgedb`.
+    #[oai(
+        path = "/subgraphs/:id",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteSubgraph"
+    )]
+    async fn delete_subgraph(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<String>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return DeleteResponse::forbidden(err);
+        }
+
+        let id = id.0;
+
+        match SubgraphIdQuery::new(&id) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate subgraph id: {}", e);
+                warn!("{}", err);
+                return DeleteResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        match Subgraph::get_record(&pool_arc, &id).await {
+            Ok(Some(subgraph)) => {
+                if !_token.0.can_manage(&subgraph.owner) {
+                    let err = format!(
+                        "The subgraph {} is owned by {}, you cannot delete it.",
+                        id, subgraph.owner
+                    );
+                    warn!("{}", err);
+                    return DeleteResponse::forbidden(err);
+                }
+            }
+            Ok(None) => {
+                let err = format!("Cannot find the subgraph {}.", id);
+                warn!("{}", err);
+                return DeleteResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                warn!("{}", err);
+                return DeleteResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        match Subgraph::delete(&pool_arc, &id).await {
+            Ok(_) => DeleteResponse::NoContent,
+            Err(e) => {
+                let err = format!("Failed to delete a subgraph: {}", e);
+                warn!("{}", err);
+                DeleteResponse::not_found(err)
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs/:id/restore` to restore a deleted subgraph and the child subgraphs which were deleted together with it. Only the owner of the subgraph or an admin can restore it, and a child subgraph cannot be restored while its root subgraph is deleted.
+    #[oai(
+        path = "/subgraphs/:id/restore",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "restoreSubgraph"
+    )]
+    async fn restore_subgraph(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<String>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<Subgraph> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let id = id.0;
+
+        if let Err(e) = SubgraphIdQuery::new(&id) {
+            let err = format!("Failed to validate subgraph id: {}", e);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::from_error(err, &e));
+        }
+
+        let subgraph = match Subgraph::get_deleted_record(&pool_arc, &id).await {
+            Ok(Some(subgraph)) => subgraph,
+            Ok(None) => {
+                let err = format!("Cannot find the deleted subgraph {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if !_token.0.can_manage(&subgraph.owner) {
+            let err = format!(
+                "The subgraph {} is owned by {}, you cannot restore it.",
+                id, subgraph.owner
+            );
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        // The root subgraph is purged with its children, so it must be restored first.
+        if let Some(parent) = subgraph.parent.as_ref().filter(|parent| **parent != id) {
+            match Subgraph::get_record(&pool_arc, parent).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    let err = format!(
+                        "The root subgraph {} is deleted, please restore it first.",
+                        parent
+                    );
+                    warn!("{}", err);
+                    return PostResponse::bad_request(err);
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch the subgraph {}: {}", parent, e);
+                    warn!("{}", err);
+                    return PostResponse::error(ApiError::from_error(err, &*e));
+                }
+            }
+        }
+
+        match Subgraph::restore(&pool_arc, &id).await {
+            Ok(subgraph) => PostResponse::Created(Json(subgraph)),
+            Err(e) => {
+                let err = format!("Failed to restore the subgraph: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs/merge` with payload to merge several subgraphs into a new subgraph. The nodes and edges of the new subgraph are the union of the subgraphs.
+    #[oai(
+        path = "/subgraphs/merge",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postSubgraphMerge"
+    )]
+    async fn post_subgraph_merge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<SubgraphMergeRequest>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<Subgraph> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the merge request: {}", e);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::from_error(err, &e));
+        }
+
+        let mut subgraphs = vec![];
+        for id in payload.subgraph_ids.iter() {
+            if let Err(e) = SubgraphIdQuery::new(id) {
+                let err = format!("Failed to parse subgraph id: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+
+            match Subgraph::get_record(&pool_arc, id).await {
+                Ok(Some(subgraph)) => subgraphs.push(subgraph),
+                Ok(None) => {
+                    let err = format!("Cannot find the subgraph {}.", id);
+                    warn!("{}", err);
+                    return PostResponse::not_found(err);
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                    warn!("{}", err);
+                    return PostResponse::error(ApiError::from_error(err, &*e));
+                }
+            }
+        }
+
+        let mut subgraph = match Subgraph::merge(
+            &subgraphs,
+            &payload.name,
+            payload.description.clone(),
+            &_token.0.username,
+        ) {
+            Ok(subgraph) => subgraph,
+            Err(e) => {
+                let err = format!("Failed to merge subgraphs: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Some(release_tag) = DatasetVersion::get_active_tag(&pool_arc).await {
+            subgraph.update_db_version(release_tag);
+        }
+
+        match subgraph.insert(&pool_arc).await {
+            Ok(subgraph) => PostResponse::created(subgraph),
+            Err(e) => {
+                let err = format!("Failed to insert the merged subgraph: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/subgraphs/diff` with query params to compare two subgraphs, such as `/api/v1/subgraphs/diff?a=<subgraph_id>&b=<subgraph_id>`. The added nodes and edges are only in b, and the removed ones are only in a.
+    #[oai(
+        path = "/subgraphs/diff",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSubgraphDiff"
+    )]
+    async fn fetch_subgraph_diff(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        a: Query<String>,
+        b: Query<String>,
+        _token: CustomSecurityScheme,
+    ) -> GetSubgraphDiffResponse {
+        let pool_arc = pool.clone();
+
+        let mut subgraphs = vec![];
+        for id in [a.0, b.0] {
+            if let Err(e) = SubgraphIdQuery::new(&id) {
+                let err = format!("Failed to parse subgraph id: {}", e);
+                warn!("{}", err);
+                return GetSubgraphDiffResponse::error(ApiError::from_error(err, &e));
+            }
+
+            match Subgraph::get_record(&pool_arc, &id).await {
+                Ok(Some(subgraph)) => subgraphs.push(subgraph),
+                Ok(None) => {
+                    let err = format!("Cannot find the subgraph {}.", id);
+                    warn!("{}", err);
+                    return GetSubgraphDiffResponse::not_found(err);
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch the subgraph {}: {}", id, e);
+                    warn!("{}", err);
+                    return GetSubgraphDiffResponse::error(ApiError::from_error(err, &*e));
+                }
+            }
+        }
+
+        match subgraphs[0].diff(&subgraphs[1]) {
+            Ok(diff) => GetSubgraphDiffResponse::ok(diff),
+            Err(e) => {
+                let err = format!("Failed to compare subgraphs: {}", e);
+                warn!("{}", err);
+                GetSubgraphDiffResponse::error(ApiError::from_error(err, &e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/aggregate` with query params to fetch the grouped records for the dashboard charts, such as `/api/v1/aggregate?table=relation&group_by=relation_type,resource&function=count`.
+    ///
+    /// The table is one of entity, relation, knowledge_curation and subgraph, the function is one of count (default), sum and avg. The query_str is the same as the one of the record endpoints, it is used to filter the records before grouping. The groups are sorted by the aggregated value in descending order.
+    #[oai(
+        path = "/aggregate",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchAggregatedRecords"
+    )]
+    async fn fetch_aggregated_records(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        table: Query<String>,
+        group_by: Query<String>,
+        function: Query<Option<String>>,
+        field: Query<Option<String>>,
+        query_str: Query<Option<String>>,
+        limit: Query<Option<u64>>,
+        _token: CustomSecurityScheme,
+    ) -> GetAggregateResponse {
+        let pool_arc = pool.clone();
+        let table = table.0;
+
+        let (groupable_fields, numeric_fields) = match get_aggregate_fields(&table) {
+            Some(fields) => fields,
+            None => {
+                let err = format!(
+                    "Invalid table: {}, it must be one of entity, relation, knowledge_curation and subgraph.",
+                    table
+                );
+                warn!("{}", err);
+                return GetAggregateResponse::bad_request(err);
+            }
+        };
+
+        let table = match TableName::from_short_name(&table) {
+            Ok(table) => table,
+            Err(e) => {
+                let err = format!("Failed to parse the table: {}", e);
+                warn!("{}", err);
+                return GetAggregateResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let aggregate_query = AggregateQuery::new(
+            group_by
+                .0
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect(),
+            &function.0.unwrap_or("count".to_string()),
+            field.0,
+        );
+
+        if let Err(err) = aggregate_query.validate(&groupable_fields, &numeric_fields) {
+            warn!("{}", err);
+            return GetAggregateResponse::bad_request(err);
+        }
+
+        let query = match query_str.0 {
+            Some(query_str) if !query_str.is_empty() => {
+                debug!("Query string: {}", &query_str);
+                match serde_json::from_str(&query_str) {
+                    Ok(query) => Some(query),
+                    Err(e) => {
+                        let err = format!("Failed to parse query string: {}", e);
+                        warn!("{}", err);
+                        return GetAggregateResponse::error(ApiError::from_error(err, &e));
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(query) = &query {
+            let checked = match table {
+                TableName::Entity => check_query_fields::<Entity>(query),
+                TableName::Relation => check_query_fields::<Relation>(query),
+                TableName::KnowledgeCuration => check_query_fields::<KnowledgeCuration>(query),
+                TableName::Subgraph => check_query_fields::<Subgraph>(query),
+                _ => Ok(()),
+            };
+            if let Err(e) = checked {
+                warn!("{}", e.message);
+                return GetAggregateResponse::error(e);
+            }
+        }
+
+        // The curated knowledges and subgraphs of the workspaces which the user is not a member of are not aggregated.
+        let query = match table {
+            TableName::KnowledgeCuration | TableName::Subgraph => {
+                match merge_member_workspaces(&pool_arc, &_token.0, query).await {
+                    Ok(query) => query,
+                    Err(e) => {
+                        warn!("{}", e.message);
+                        return GetAggregateResponse::error(e);
+                    }
+                }
+            }
+            _ => query,
+        };
+
+        let limit = limit
+            .0
+            .unwrap_or(DEFAULT_MAX_GROUPS)
+            .min(DEFAULT_MAX_GROUPS);
+        match AggregateRecord::get_records(&pool_arc, table, &aggregate_query, &query, limit).await
+        {
+            Ok(records) => GetAggregateResponse::ok(records),
+            Err(e) => {
+                let err = format!("Failed to fetch aggregated records: {}", e);
+                warn!("{}", err);
+                GetAggregateResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/saved-queries` to fetch the saved queries which are owned by the current user or shared by other users.
+    ///
+    /// If workspace_id is set, all saved queries in the workspace are returned instead, and the current user must be a member of the workspace. Otherwise the saved queries in the workspaces which the current user is not a member of are not returned.
+    #[oai(
+        path = "/saved-queries",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSavedQueries"
+    )]
+    async fn fetch_saved_queries(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        workspace_id: Query<Option<i64>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<SavedQuery> {
+        let pool_arc = pool.clone();
+        let workspace_id = workspace_id.0;
+
+        if let Some(workspace_id) = workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Viewer)
+                    .await
+            {
+                warn!("{}", e.message);
+                return GetWholeTableResponse::error(e);
+            }
+        }
+
+        match SavedQuery::get_records(
+            &pool_arc,
+            &_token.0.username,
+            workspace_id,
+            _token.0.is_admin(),
+        )
+        .await
+        {
+            Ok(saved_queries) => GetWholeTableResponse::ok(saved_queries),
+            Err(e) => {
+                let err = format!("Failed to fetch saved queries: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/saved-queries` with payload to save a named query, the target_table is one of entity, relation, entity2d, knowledge_curation and subgraph.
+    #[oai(
+        path = "/saved-queries",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postSavedQuery"
+    )]
+    async fn post_saved_query(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<SavedQuery>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<SavedQuery> {
+        let pool_arc = pool.clone();
+        let mut payload = payload.0;
+        let username = _token.0.username.clone();
+
+        // When we enabled auth mode, we need to use the username from an access_token as the owner, so that only the owner can update or delete it.
+        if username != USERNAME_PLACEHOLDER.to_string() {
+            payload.update_owner(username);
+        }
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate saved query: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Some(workspace_id) = payload.workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PostResponse::error(e);
+            }
+        }
+
+        if let Err(e) = check_saved_query(&payload) {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        match payload.insert(&pool_arc).await {
+            Ok(saved_query) => PostResponse::Created(Json(saved_query)),
+            Err(e) => {
+                let err = format!("Failed to insert saved query: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/saved-queries/:id` with payload to update a saved query, only the owner can update it.
+    #[oai(
+        path = "/saved-queries/:id",
+        method = "put",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "putSavedQuery"
+    )]
+    async fn put_saved_query(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        payload: Json<SavedQuery>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<SavedQuery> {
+        let pool_arc = pool.clone();
+        let id = id.0;
+        let payload = payload.0;
+
+        match SavedQuery::get_record(&pool_arc, id).await {
+            Ok(Some(record)) => {
+                if !_token.0.can_manage(&record.owner) {
+                    let err = format!(
+                        "You are not the owner of the saved query {}, so you cannot update it.",
+                        id
+                    );
+                    warn!("{}", err);
+                    return PostResponse::forbidden(err);
+                }
+            }
+            Ok(None) => {
+                let err = format!("Cannot find the saved query {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the saved query: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate saved query: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Some(workspace_id) = payload.workspace_id {
+            if let Err(e) =
+                check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Editor)
+                    .await
+            {
+                warn!("{}", e.message);
+                return PostResponse::error(e);
+            }
+        }
+
+        if let Err(e) = check_saved_query(&payload) {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        match payload.update(&pool_arc, id).await {
+            Ok(saved_query) => PostResponse::Created(Json(saved_query)),
+            Err(e) => {
+                let err = format!("Failed to update saved query: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/saved-queries/:id` to delete a saved query, only the owner can delete it.
+    #[oai(
+        path = "/saved-queries/:id",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteSavedQuery"
+    )]
+    async fn delete_saved_query(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+        let id = id.0;
+
+        match SavedQuery::get_record(&pool_arc, id).await {
+            Ok(Some(record)) => {
+                if !_token.0.can_manage(&record.owner) {
+                    let err = format!(
+                        "You are not the owner of the saved query {}, so you cannot delete it.",
+                        id
+                    );
+                    warn!("{}", err);
+                    return DeleteResponse::forbidden(err);
+                }
+            }
+            Ok(None) => {
+                let err = format!("Cannot find the saved query {}.", id);
+                warn!("{}", err);
+                return DeleteResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the saved query: {}", e);
+                warn!("{}", err);
+                return DeleteResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        match SavedQuery::delete(&pool_arc, id).await {
+            Ok(_) => DeleteResponse::no_content(),
+            Err(e) => {
+                let err = format!("Failed to delete the saved query: {}", e);
+                warn!("{}", err);
+                DeleteResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/saved-queries/:id/run` with query params to execute a saved query, such as `/api/v1/saved-queries/1/run?page=1&page_size=10`.
+    ///
+    /// The records are the rows of the target table of the saved query. If the saved query doesn't have a default sort, the records are sorted by the fields in the query.
+    #[oai(
+        path = "/saved-queries/:id/run",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "runSavedQuery"
+    )]
+    async fn run_saved_query(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        _token: CustomSecurityScheme,
+    ) -> RunSavedQueryResponse {
+        let pool_arc = pool.clone();
+        let id = id.0;
+
+        if let Err(e) = Pagination::new(page.0, page_size.0) {
+            let err = format!("Failed to parse pagination: {}", e);
+            warn!("{}", err);
+            return RunSavedQueryResponse::error(ApiError::from_error(err, &e));
+        }
+
+        let saved_query = match SavedQuery::get_record(&pool_arc, id).await {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                let err = format!("Cannot find the saved query {}.", id);
+                warn!("{}", err);
+                return RunSavedQueryResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the saved query: {}", e);
+                warn!("{}", err);
+                return RunSavedQueryResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if !saved_query.shared && !_token.0.can_manage(&saved_query.owner) {
+            // The members of the workspace can execute all saved queries in the workspace.
+            let is_member = match saved_query.workspace_id {
+                Some(workspace_id) => {
+                    check_workspace_role(&pool_arc, &_token.0, workspace_id, WorkspaceRole::Viewer)
+                        .await
+                        .is_ok()
+                }
+                None => false,
+            };
+
+            if !is_member {
+                let err = format!("The saved query {} is not shared with you.", id);
+                warn!("{}", err);
+                return RunSavedQueryResponse::forbidden(err);
+            }
+        }
+
+        let order_by_clause = match &saved_query.order_by {
+            Some(order_by) => Some(order_by.clone()),
+            None => match saved_query.get_query() {
+                Ok(query) => {
+                    let pairs = get_all_field_pairs(&query);
+                    if pairs.len() == 0 {
+                        None
+                    } else {
+                        // More fields will cause bad performance
+                        Some(make_order_clause_by_pairs(pairs, 2))
+                    }
+                }
+                Err(_) => None,
+            },
+        };
+
+        match SavedQueryResult::run(
+            &pool_arc,
+            saved_query,
+            page.0,
+            page_size.0,
+            order_by_clause.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => RunSavedQueryResponse::ok(result),
+            Err(e) => {
+                let err = format!("Failed to run the saved query: {}", e);
+                warn!("{}", err);
+                RunSavedQueryResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/nodes` with query params to fetch nodes.
+    #[oai(
+        path = "/nodes",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchNodes"
+    )]
+    async fn fetch_nodes(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        node_ids: Query<String>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let pool_arc = pool.clone();
+        let node_ids = match NodeIdsQuery::new(&node_ids.0) {
+            Ok(query) => query.node_ids,
+            Err(e) => {
+                let err = format!("Failed to validate node ids: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let mut graph = Graph::new();
+        match graph.fetch_nodes_by_ids(&pool_arc, &node_ids).await {
+            Ok(graph) => {
+                let mut graph = graph.to_owned().get_graph(None).unwrap();
+                graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch nodes: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/graph-query` with a read-only cypher query to fetch a graph from the neo4j database.
+    ///
+    /// It is only available when the neo4j database is configured (NEO4J_URL). The queries with writing clauses (such as CREATE, DELETE and MERGE) or the procedures which may write (such as `apoc.*`) will be rejected, and the query runs in a transaction which is rolled back, so nothing is written to the graph. The query will be cancelled after 30 seconds and at most 1000 rows will be read.
+    #[oai(
+        path = "/graph-query",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchGraphByCypher"
+    )]
+    async fn fetch_graph_by_cypher(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        neo4j_graph: Data<&Option<Arc<neo4rs::Graph>>>,
+        payload: Json<GraphQuery>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let pool_arc = pool.clone();
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        info!("Username: {}", _token.0.username);
+        let payload = payload.0;
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate the graph query: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let neo4j_graph = match neo4j_graph.0 {
+            Some(neo4j_graph) => neo4j_graph.clone(),
+            None => {
+                let err = "The graph database is not configured, please set NEO4J_URL.".to_string();
+                warn!("{}", err);
+                return GetGraphResponse::bad_request(err);
+            }
+        };
+
+        let max_rows = match payload.max_rows {
+            Some(max_rows) => max_rows as usize,
+            None => DEFAULT_MAX_ROWS,
+        };
+
+        let mut graph = Graph::new();
+        match graph
+            .fetch_graph_by_cypher(
+                &neo4j_graph,
+                &payload.query,
+                max_rows,
+                DEFAULT_QUERY_TIMEOUT,
+            )
+            .await
+        {
+            Ok(graph) => {
+                let mut graph = graph.to_owned().get_graph(None).unwrap();
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                count_graph_flags(&pool_arc, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch graph by cypher: {}", e);
+                warn!("{}", err);
+                GetGraphResponse::error(ApiError::from_error(err, &e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/auto-connect-nodes` with query params to fetch edges which connect the input nodes.
+    ///
+    /// Use `max_node_degree` to skip the edges of the hub nodes whose global degree is greater than it, and `exclude_labels` (comma-separated, such as `Compound,Anatomy`) to skip the edges of the nodes with the labels. The skipped nodes are still returned.
+    #[oai(
+        path = "/auto-connect-nodes",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEdgesAutoConnectNodes"
+    )]
+    async fn fetch_edges_auto_connect_nodes(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        node_ids: Query<String>,
+        max_node_degree: Query<Option<u64>>,
+        exclude_labels: Query<Option<String>>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let hub_filter = match HubFilter::new(max_node_degree.0, exclude_labels.0.as_deref()) {
+            Ok(filter) => filter,
+            Err(e) => {
+                let err = format!("Failed to parse hub filter: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let pool_arc = pool.clone();
+        let node_ids = node_ids.0;
+
+        match NodeIdsQuery::new(&node_ids) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate node ids: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let mut graph = Graph::new();
+
+        if node_ids == "" {
+            return GetGraphResponse::ok(graph);
+        }
+
+        let node_ids: Vec<&str> = node_ids.split(",").collect();
+        match graph
+            .auto_connect_nodes(&pool_arc, &node_ids, Some(&hub_filter))
+            .await
+        {
+            Ok(graph) => {
+                let mut graph = graph.to_owned().get_graph(None).unwrap();
+                graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                count_graph_flags(&pool_arc, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch nodes: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &*e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/connect-node-sets` with query params to fetch the connections between two node sets, such as `/api/v1/connect-node-sets?set_a=Gene::ENTREZ:7157,Gene::ENTREZ:672&set_b=Disease::MONDO:0007254&max_hops=2`.
+    ///
+    /// The edges within each set are not returned. The direct edges are returned if `max_hops` is 1 (the default), and the 2-hop connections through the intermediate nodes (at most 200) are also returned if `max_hops` is 2. Each set has at most 500 node ids, and `relation_types` is a comma-separated relation type list to filter the edges.
+    #[oai(
+        path = "/connect-node-sets",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchNodeSetConnections"
+    )]
+    async fn fetch_node_set_connections(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        set_a: Query<String>,
+        set_b: Query<String>,
+        max_hops: Query<Option<usize>>,
+        relation_types: Query<Option<String>>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let pool_arc = pool.clone();
+
+        let mut sets = vec![];
+        for node_ids in [&set_a.0, &set_b.0] {
+            if let Err(e) = NodeIdsQuery::new(node_ids) {
+                let err = format!("Failed to validate node ids: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+
+            let mut node_ids = node_ids
+                .split(',')
+                .map(|node_id| node_id.to_string())
+                .collect::<Vec<String>>();
+            node_ids.sort_unstable();
+            node_ids.dedup();
+            if node_ids.len() > MAX_NODE_SET_SIZE {
+                let err = format!(
+                    "Each node set has at most {} node ids, but got {}.",
+                    MAX_NODE_SET_SIZE,
+                    node_ids.len()
+                );
+                warn!("{}", err);
+                return GetGraphResponse::bad_request(err);
+            }
+            sets.push(node_ids);
+        }
+
+        let max_hops = max_hops.0.unwrap_or(1);
+        if !(1..=MAX_CONNECTION_HOPS).contains(&max_hops) {
+            let err = format!(
+                "The max_hops should be between 1 and {}, but got {}.",
+                MAX_CONNECTION_HOPS, max_hops
+            );
+            warn!("{}", err);
+            return GetGraphResponse::bad_request(err);
+        }
+
+        let relation_types = relation_types.0.map(|relation_types| {
+            relation_types
+                .split(',')
+                .map(|relation_type| relation_type.trim().to_string())
+                .filter(|relation_type| !relation_type.is_empty())
+                .collect::<Vec<String>>()
+        });
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let mut graph = Graph::new();
+        match graph
+            .connect_node_sets(
+                &pool_arc,
+                &sets[0],
+                &sets[1],
+                max_hops,
+                relation_types.as_deref(),
+            )
+            .await
+        {
+            Ok(graph) => {
+                let mut graph = graph.to_owned().get_graph(None).unwrap();
+                graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                count_graph_flags(&pool_arc, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(e) => {
+                let err = format!("Failed to connect the node sets: {}", e);
+                warn!("{}", err);
+                GetGraphResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/graphs/from-list` with a json body to build a graph from a list of genes or compounds in one call, such as the 200 genes pasted from a paper.
+    ///
+    /// The identifiers are resolved like `/api/v1/genes/resolve`, the best matches of each identifier (whose confidences are at least `min_confidence`) are the nodes, and the nodes are connected like `/api/v1/auto-connect-nodes`. Use `expand_to` (such as Compound) to also fetch the neighbours of the type by one hop, at most `expand_limit` edges with the highest scores are kept. The graph is saved as a subgraph if `subgraph_name` is given.
+    #[oai(
+        path = "/graphs/from-list",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postGraphFromList"
+    )]
+    async fn post_graph_from_list(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<GraphFromListRequest>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphFromListResponse {
+        let pool_arc = pool.clone();
+
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the graph request: {}", e);
+            warn!("{}", err);
+            return GetGraphFromListResponse::error(ApiError::from_error(err, &e));
+        }
+
+        if payload.subgraph_name.is_some() {
+            if let Err(err) = _token.0.check_role(Role::Curator) {
+                warn!("{}", err);
+                return GetGraphFromListResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+            }
+        }
+
+        let identifiers = split_identifiers(&payload.identifiers);
+        if identifiers.is_empty() || identifiers.len() > MAX_NODE_SET_SIZE {
+            let err = format!(
+                "The number of identifiers should be between 1 and {}, but got {}.",
+                MAX_NODE_SET_SIZE,
+                identifiers.len()
+            );
+            warn!("{}", err);
+            return GetGraphFromListResponse::bad_request(err);
+        }
+
+        let labels = payload.labels.unwrap_or_else(|| {
+            DEFAULT_LIST_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect()
+        });
+        if let Some(label) = labels
+            .iter()
+            .find(|label| !ENTITY_LABEL_REGEX.is_match(label))
+        {
+            let err = format!("Invalid entity label: {}", label);
+            warn!("{}", err);
+            return GetGraphFromListResponse::bad_request(err);
+        }
+
+        let hub_filter =
+            match HubFilter::new(payload.max_node_degree, payload.exclude_labels.as_deref()) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    let err = format!("Failed to parse hub filter: {}", e);
+                    warn!("{}", err);
+                    return GetGraphFromListResponse::error(ApiError::from_error(err, &e));
+                }
+            };
+
+        let resolutions = match GeneResolution::resolve(
+            &pool_arc,
+            &identifiers,
+            &labels,
+            payload.taxid.as_deref(),
+        )
+        .await
+        {
+            Ok(resolutions) => resolutions,
+            Err(e) => {
+                let err = format!("Failed to resolve the identifiers: {}", e);
+                warn!("{}", err);
+                return GetGraphFromListResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        // The best matches of each identifier are the nodes, such as the gene and the protein of a symbol.
+        let min_confidence = payload.min_confidence.unwrap_or(DEFAULT_MIN_CONFIDENCE);
+        let mut node_ids = vec![];
+        let mut unresolved = vec![];
+        for resolution in resolutions.iter() {
+            let best = resolution
+                .matches
+                .first()
+                .map(|m| m.confidence)
+                .filter(|confidence| *confidence >= min_confidence);
+            match best {
+                Some(best) => node_ids.extend(
+                    resolution
+                        .matches
+                        .iter()
+                        .filter(|m| m.confidence == best)
+                        .map(|m| Node::format_id(&m.entity_type, &m.entity_id)),
+                ),
+                None => unresolved.push(resolution.identifier.clone()),
+            }
+        }
+        node_ids.sort_unstable();
+        node_ids.dedup();
+
+        let mut graph = Graph::new();
+        if !node_ids.is_empty() {
+            let ids = node_ids.iter().map(|id| id.as_str()).collect::<Vec<&str>>();
+            if let Err(e) = graph
+                .auto_connect_nodes(&pool_arc, &ids, Some(&hub_filter))
+                .await
+            {
+                let err = format!("Failed to connect the nodes: {}", e);
+                warn!("{}", err);
+                return GetGraphFromListResponse::error(ApiError::from_error(err, &*e));
+            }
+
+            if let Some(expand_to) = payload.expand_to.as_deref() {
+                let limit = payload.expand_limit.unwrap_or(DEFAULT_EXPANSION_EDGES);
+                if let Err(e) = graph
+                    .expand_nodes(&pool_arc, &node_ids, expand_to, limit, Some(&hub_filter))
+                    .await
+                {
+                    let err = format!("Failed to expand the nodes: {}", e);
+                    warn!("{}", err);
+                    return GetGraphFromListResponse::error(ApiError::from_error(err, &*e));
+                }
+            }
+        }
+
+        let mut graph = graph.get_graph(None).unwrap();
+        graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+        if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+            warn!("Failed to fetch the degrees of the nodes: {}", e);
+        }
+        merge_graph_nodes(&pool_arc, &mut graph).await;
+        count_graph_flags(&pool_arc, &mut graph).await;
+
+        let subgraph_id = match payload.subgraph_name.as_deref() {
+            Some(name) => {
+                let subgraph = match Subgraph::from_graph(
+                    &graph,
+                    name,
+                    payload.subgraph_description.clone(),
+                    &_token.0.username,
+                ) {
+                    Ok(subgraph) => subgraph,
+                    Err(e) => {
+                        let err = format!("Failed to make the subgraph: {}", e);
+                        warn!("{}", err);
+                        return GetGraphFromListResponse::error(ApiError::from_error(err, &e));
+                    }
+                };
+
+                match subgraph.insert(&pool_arc).await {
+                    Ok(subgraph) => Some(subgraph.id),
+                    Err(e) => {
+                        let err = format!("Failed to save the subgraph: {}", e);
+                        warn!("{}", err);
+                        return GetGraphFromListResponse::error(ApiError::from_error(err, &*e));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        GetGraphFromListResponse::ok(GraphFromList {
+            graph,
+            resolutions,
+            unresolved,
+            subgraph_id,
+        })
+    }
+
+    /// Call `/api/v1/one-step-linked-nodes` with query params to fetch linked nodes with one step.
+    ///
+    /// You can rank the edges by `rank_by` (score, resource or degree), it is useful when the node has too many neighbors. The `resources` param is a comma-separated resource priority list which is required when `rank_by` is resource. The degree ranking uses the global degrees of the linked nodes which are refreshed by `biomedgps-cli statdb`.
+    ///
+    /// The large neighborhoods can be grouped by `cluster` (louvain or label_propagation), the cluster of each node is set to its community id, `0` is the largest community. The hubs can be spotted by the global degree of each node, and by `centrality` (betweenness, pagerank or both, comma-separated) which is computed within the returned graph. The other graph endpoints accept the same params.
+    ///
+    /// Use `expand_descendants=true` and `max_depth` to link the subclasses of the queried entities too, and `with_inverse=true` to link the inverse relations of the queried relation types, same as `/api/v1/relations`.
+    ///
+    /// The edges are traversed in both directions, i.e. the relations which match the query with the swapped source and target fields are linked too, such as the stored `Compound -> treats -> Disease` relations for a query on the `source_id` of the disease. They keep their stored direction, and `page` and `page_size` apply to each direction. Use `directed=true` to only follow the edges from the queried source nodes.
+    ///
+    /// Use `max_node_degree` to skip the hub nodes (such as water or TP53) whose global degree is greater than it, and `exclude_labels` (comma-separated, such as `Compound,Anatomy`) to skip the nodes with the labels. The filters apply to the linked nodes of the edges (the target nodes, or the source nodes of the edges which are traversed backwards), same as ranking by degree.
+    ///
+    /// Use the `locale` param (such as `zh-CN`) or the `Accept-Language` header to get the translated names of the nodes, the other graph endpoints accept them too.
+    ///
+    /// The expansions are materialized in the database for the active dataset version (see `materialization_ttl_hours` in the config), so the same expansion is computed once for all users and server instances. Use `refresh=true` to compute it again, and `/api/v1/admin/materialized-graphs` to list or invalidate the materialized expansions.
+    #[oai(
+        path = "/one-step-linked-nodes",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchOneStepLinkedNodes"
+    )]
+    async fn fetch_one_step_linked_nodes(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        query_str: Query<Option<String>>,
+        rank_by: Query<Option<String>>,
+        resources: Query<Option<String>>,
+        max_node_degree: Query<Option<u64>>,
+        exclude_labels: Query<Option<String>>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        expand_descendants: Query<Option<bool>>,
+        max_depth: Query<Option<usize>>,
+        with_inverse: Query<Option<bool>>,
+        directed: Query<Option<bool>>,
+        refresh: Query<Option<bool>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let pool_arc = pool.clone();
+        let page = page.0;
+        let page_size = page_size.0;
+
+        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to parse query string: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetGraphResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Relation>(query) {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+
+            if let Err(e) = check_query_vocabulary(&pool_arc, query).await {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        }
+
+        let expand_descendants = expand_descendants.0.unwrap_or(false);
+        let query = match query {
+            Some(query) if expand_descendants => {
+                match expand_query_descendants(&pool_arc, &query, max_depth.0).await {
+                    Ok(query) => Some(query),
+                    Err(e) => {
+                        warn!("{}", e.message);
+                        return GetGraphResponse::error(e);
+                    }
+                }
+            }
+            query => query,
+        };
+
+        let inverse_query = match &query {
+            Some(query) if with_inverse.0.unwrap_or(false) => get_relation_type_registry()
+                .and_then(|registry| registry.with_inverse_relations(query)),
+            _ => None,
+        };
+        let query = match &inverse_query {
+            Some(inverse_query) => Some(inverse_query.query.clone()),
+            None => query,
+        };
+
+        let ranking = match rank_by.0 {
+            Some(rank_by) => match EdgeRanking::new(&rank_by, resources.0.as_deref()) {
+                Ok(ranking) => Some(ranking),
+                Err(e) => {
+                    let err = format!("Failed to parse ranking options: {}", e);
+                    warn!("{}", err);
+                    return GetGraphResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            None => None,
+        };
+
+        let hub_filter = match HubFilter::new(max_node_degree.0, exclude_labels.0.as_deref()) {
+            Ok(filter) => filter,
+            Err(e) => {
+                let err = format!("Failed to parse hub filter: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let undirected = !directed.0.unwrap_or(false);
+
+        // The popular nodes are expanded again and again, so the expansions are cached.
+        let key = format!(
+            "one-step:{}:{}:{}:{:?}:{:?}:{}:{}:{}",
+            page.unwrap_or(0),
+            page_size.unwrap_or(0),
+            query_str,
+            ranking,
+            hub_filter,
+            if expand_descendants {
+                format!(
+                    "descendants-{}",
+                    max_depth.0.unwrap_or(DEFAULT_ONTOLOGY_DEPTH)
+                )
+            } else {
+                "".to_string()
+            },
+            if inverse_query.is_some() {
+                "inverse"
+            } else {
+                ""
+            },
+            if undirected { "" } else { "directed" }
+        );
+        let cache_key = CacheScope::Expansion.key(&key);
+        let refresh = refresh.0.unwrap_or(false);
+        let graph = cached_with_refresh(&cache_key, refresh, || async {
+            // The pool may be the read replica, but the materialized graphs are written to the primary database.
+            let primary_pool = get_primary_pool(&pool_arc);
+            let dataset_version = DatasetVersion::get_active_tag(&pool_arc).await;
+            let ttl = materialization_ttl();
+            if ttl.is_some() && !refresh {
+                match MaterializedGraph::get_graph(&pool_arc, &key, dataset_version.as_deref())
+                    .await
+                {
+                    Ok(Some(graph)) => {
+                        record_hit(primary_pool.clone(), &key, dataset_version.as_deref());
+                        return Ok(graph);
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to fetch the materialized graph: {}", e),
+                }
+            }
+
+            let mut graph = Graph::new();
+            match graph
+                .fetch_linked_nodes(
+                    &pool_arc,
+                    &query,
+                    page,
+                    page_size,
+                    None,
+                    ranking.as_ref(),
+                    Some(&hub_filter),
+                    undirected,
+                )
+                .await
+            {
+                Ok(graph) => {
+                    let mut graph = graph.to_owned();
+                    if let Some(inverse_query) = &inverse_query {
+                        graph.invert_edges(&inverse_query.inverted);
+                    }
+                    let mut graph = graph.get_graph(None).unwrap();
+                    graph.set_dataset_version(dataset_version.clone());
+                    if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                        warn!("Failed to fetch the degrees of the nodes: {}", e);
+                    }
+                    if let Some(ttl) = ttl {
+                        if let Err(e) = MaterializedGraph::save_graph(
+                            &primary_pool,
+                            &key,
+                            dataset_version.as_deref(),
+                            &graph,
+                            ttl,
+                        )
+                        .await
+                        {
+                            warn!("Failed to materialize the graph: {}", e);
+                        }
+                    }
+                    Ok(graph)
+                }
+                Err(e) => {
+                    let err = format!("Failed to fetch linked nodes: {}", e);
+                    warn!("{}", err);
+                    Err(GetGraphResponse::error(ApiError::from_error(err, &e)))
+                }
+            }
+        })
+        .await;
+
+        match graph {
+            Ok(mut graph) => {
+                merge_graph_nodes(&pool_arc, &mut graph).await;
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                count_graph_flags(&pool_arc, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(response) => response,
+        }
+    }
+
+    /// Call `/api/v1/similarity-nodes` with query params to fetch similarity nodes.
+    ///
+    /// Use `entity_type` to only fetch the similar nodes with the entity type, and `min_score` to only fetch the similar nodes whose similarity score is greater than or equal to it. Each returned node and edge carries the similarity score (between 0 and 1) and the rank.
+    ///
+    /// Use `model` to choose the embedding model (see `/api/v1/embedding-models`), the latest registered model is used by default.
+    #[oai(
+        path = "/similarity-nodes",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSimilarityNodes"
+    )]
+    async fn fetch_similarity_nodes(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        node_id: Query<String>,
+        query_str: Query<Option<String>>,
+        topk: Query<Option<u64>>,
+        entity_type: Query<Option<String>>,
+        min_score: Query<Option<f64>>,
+        model: Query<Option<String>>,
+        cluster: Query<Option<ClusterAlgorithm>>,
+        centrality: Query<Option<String>>,
+        locale: Query<Option<String>>,
+        #[oai(name = "Accept-Language")] accept_language: Header<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetGraphResponse {
+        let locales = match parse_request_locales(&locale.0, &accept_language.0) {
+            Ok(locales) => locales,
+            Err(e) => {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        };
+
+        let centrality = match CentralityMetric::parse_list(centrality.0.as_deref()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                let err = format!("Failed to parse centrality: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let pool_arc = pool.clone();
+
+        let similarity_query = match SimilarityNodeQuery::new(
+            &node_id.0,
+            &query_str.0,
+            topk.0,
+            &entity_type.0,
+            min_score.0,
+        ) {
+            Ok(query) => query,
+            Err(e) => {
+                let err = format!("Failed to parse query string: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let query_str = match query_str.0 {
+            Some(query_str) => query_str,
+            None => {
+                warn!("Query string is empty.");
+                "".to_string()
+            }
+        };
+
+        let topk = topk.0;
+
+        let query = if query_str == "" {
+            None
+        } else {
+            debug!("Query string: {}", &query_str);
+            // Parse query string as json
+            match serde_json::from_str(&query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetGraphResponse::error(ApiError::from_error(err, &e));
+                }
+            }
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<EntityEmbedding>(query) {
+                warn!("{}", e.message);
+                return GetGraphResponse::error(e);
+            }
+        }
+
+        let mut graph = Graph::new();
+        match graph
+            .fetch_similarity_nodes(
+                &pool_arc,
+                &similarity_query.node_id,
+                &query,
+                topk,
+                entity_type.0.as_deref(),
+                min_score.0,
+                model.0.as_deref(),
+            )
+            .await
+        {
+            Ok(graph) => {
+                let mut graph = graph.to_owned().get_graph(None).unwrap();
+                graph.set_dataset_version(DatasetVersion::get_active_tag(&pool_arc).await);
+                if let Err(e) = graph.fetch_degrees(&pool_arc).await {
+                    warn!("Failed to fetch the degrees of the nodes: {}", e);
+                }
+                graph.compute_centrality(&centrality);
+                if let Some(algorithm) = cluster.0 {
+                    graph.detect_communities(algorithm);
+                }
+                translate_graph(&pool_arc, &locales, &mut graph).await;
+                GetGraphResponse::ok(graph)
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch similarity nodes: {}", e);
+                warn!("{}", err);
+                return GetGraphResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+    }
+
+    /// Call `/api/v1/embedding-models` to fetch the registered embedding models, the latest one comes first.
+    #[oai(
+        path = "/embedding-models",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEmbeddingModels"
+    )]
+    async fn fetch_embedding_models(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<EmbeddingModel> {
+        let pool_arc = pool.clone();
+
+        match EmbeddingModel::get_records(&pool_arc).await {
+            Ok(models) => GetWholeTableResponse::ok(models),
+            Err(e) => {
+                let err = format!("Failed to fetch embedding models: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/import-jobs` to fetch the import jobs and their progress (such as the committed rows, the inserted rows, the errors and the throughput), the latest one comes first.
+    #[oai(
+        path = "/import-jobs",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchImportJobs"
+    )]
+    async fn fetch_import_jobs(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<ImportJob> {
+        let pool_arc = pool.clone();
+
+        match ImportJob::get_records(&pool_arc).await {
+            Ok(jobs) => GetWholeTableResponse::ok(jobs),
+            Err(e) => {
+                let err = format!("Failed to fetch import jobs: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/tasks` to fetch the maintenance tasks which run inside the server (such as refreshing the statistics and cleaning the orphaned rows), their schedules and the status of their last runs. The status is kept by each server instance, a task which ran in another instance is reported as `skipped`.
+    #[oai(
+        path = "/tasks",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchMaintenanceTasks"
+    )]
+    async fn fetch_maintenance_tasks(
+        &self,
+        _token: CustomSecurityScheme,
+    ) -> GetMaintenanceTasksResponse {
+        GetMaintenanceTasksResponse::ok(get_maintenance_tasks())
+    }
+
+    /// Call `/api/v1/databases` to fetch the databases which are served by the server (such as staging and production) with their active dataset versions, and whether the read replica of the default database is up. A request selects a database by the `X-Database` header or the `/db/<name>` url prefix, such as `/db/staging/api/v1/statistics`, and the default database is used if neither is set.
+    #[oai(
+        path = "/databases",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchDatabases"
+    )]
+    async fn fetch_databases(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetDatabasesResponse {
+        let selected = current_database().unwrap_or_else(|| DEFAULT_DATABASE.to_string());
+        // The server is not started by the binary, such as in the tests.
+        let pools =
+            get_database_pools().unwrap_or_else(|| Arc::new(DatabasePools::new(pool.0.clone())));
+
+        let mut databases = vec![];
+        for name in pools.names() {
+            let dataset_version = match pools.get(&name) {
+                Some(pool) => DatasetVersion::get_active_tag(&pool).await,
+                None => None,
+            };
+            let replica_healthy = if name == DEFAULT_DATABASE {
+                pools.get_replica().map(|replica| replica.is_healthy())
+            } else {
+                None
+            };
+            databases.push(DatabaseInfo {
+                is_default: name == DEFAULT_DATABASE,
+                is_selected: name == selected,
+                name,
+                dataset_version,
+                replica_healthy,
+            });
+        }
+
+        GetDatabasesResponse::ok(databases)
+    }
+
+    /// Call `/api/v1/dataset-versions` to fetch the imported releases of the knowledge graph, the active one (i.e. the latest imported) comes first.
+    #[oai(
+        path = "/dataset-versions",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchDatasetVersions"
+    )]
+    async fn fetch_dataset_versions(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<DatasetVersion> {
+        let pool_arc = pool.clone();
+
+        match DatasetVersion::get_records(&pool_arc).await {
+            Ok(versions) => GetWholeTableResponse::ok(versions),
+            Err(e) => {
+                let err = format!("Failed to fetch dataset versions: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/dataset-versions/diff` with query params to fetch the relations which are new since a dataset version, such as `/api/v1/dataset-versions/diff?from=2023Q4&to=2024Q1`. The to version defaults to the active dataset version.
+    ///
+    /// The new relations are the ones stamped with the releases imported after the from version and up to the to version, they can be filtered by the query_str as the `/api/v1/relations` endpoint. The removed relations are not reported, because an import never deletes the existing relations.
+    #[oai(
+        path = "/dataset-versions/diff",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchDatasetVersionDiff"
+    )]
+    async fn fetch_dataset_version_diff(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        from: Query<String>,
+        to: Query<Option<String>>,
+        page: Query<Option<u64>>,
+        page_size: Query<Option<u64>>,
+        query_str: Query<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> GetDatasetVersionDiffResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(e) = PaginationQuery::new(page.0, page_size.0, query_str.0.clone()) {
+            let err = format!("Failed to parse query string: {}", e);
+            warn!("{}", err);
+            return GetDatasetVersionDiffResponse::error(ApiError::from_error(err, &e));
+        }
+
+        let query = match query_str.0.as_deref() {
+            Some(query_str) if !query_str.is_empty() => match serde_json::from_str(query_str) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    let err = format!("Failed to parse query string: {}", e);
+                    warn!("{}", err);
+                    return GetDatasetVersionDiffResponse::error(ApiError::from_error(err, &e));
+                }
+            },
+            _ => None,
+        };
+
+        if let Some(query) = &query {
+            if let Err(e) = check_query_fields::<Relation>(query) {
+                warn!("{}", e.message);
+                return GetDatasetVersionDiffResponse::error(e);
+            }
+        }
+
+        let from = match DatasetVersion::get_record(&pool_arc, &from.0).await {
+            Ok(Some(version)) => version,
+            Ok(None) => {
+                let err = format!("Cannot find the dataset version {}.", from.0);
+                warn!("{}", err);
+                return GetDatasetVersionDiffResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the dataset version {}: {}", from.0, e);
+                warn!("{}", err);
+                return GetDatasetVersionDiffResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        let record = match &to.0 {
+            Some(release_tag) => DatasetVersion::get_record(&pool_arc, release_tag).await,
+            None => DatasetVersion::get_active(&pool_arc).await,
+        };
+        let to = match record {
+            Ok(Some(version)) => version,
+            Ok(None) => {
+                let err = match &to.0 {
+                    Some(release_tag) => {
+                        format!("Cannot find the dataset version {}.", release_tag)
+                    }
+                    None => "No dataset version has been imported.".to_string(),
+                };
+                warn!("{}", err);
+                return GetDatasetVersionDiffResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the dataset version: {}", e);
+                warn!("{}", err);
+                return GetDatasetVersionDiffResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if (to.imported_at, to.id) < (from.imported_at, from.id) {
+            let err = format!(
+                "The dataset version {} was imported before {}, please swap them.",
+                to.release_tag, from.release_tag
+            );
+            warn!("{}", err);
+            return GetDatasetVersionDiffResponse::bad_request(err);
+        }
+
+        match DatasetVersionDiff::get(&pool_arc, &from, &to, &query, page.0, page_size.0).await {
+            Ok(diff) => GetDatasetVersionDiffResponse::ok(diff),
+            Err(e) => {
+                let err = format!("Failed to compare dataset versions: {}", e);
+                warn!("{}", err);
+                GetDatasetVersionDiffResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/entity-merges` with payload to merge a duplicated entity into the surviving entity. The relations, embeddings and curations of the duplicated entity are rewritten to the surviving entity, and the duplicated entity is removed and recorded in the merge history.
+    ///
+    /// The candidate duplicates can be detected by `biomedgps-cli dedupdb`. The statistics are not refreshed by a merge, run `biomedgps-cli statdb` after merging.
+    #[oai(
+        path = "/entity-merges",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postEntityMerge"
+    )]
+    async fn post_entity_merge(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<EntityMergeRequest>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<EntityMerge> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let payload = payload.0;
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate the entity merge: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        match EntityMerge::merge(
+            &pool_arc,
+            &payload.source_id,
+            &payload.target_id,
+            &payload.label,
+            &_token.0.username,
+        )
+        .await
+        {
+            Ok(record) => {
+                invalidate_cache(&[CacheScope::Entity, CacheScope::Expansion]).await;
+                // The merged entity may be in any expansion.
+                invalidate_materialized_graphs(&pool_arc).await;
+                PostResponse::created(record)
+            }
+            Err(e) => {
+                let err = format!("Failed to merge the entities: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/entity-merges` to fetch the merge history of the entities, the latest one comes first.
+    #[oai(
+        path = "/entity-merges",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchEntityMerges"
+    )]
+    async fn fetch_entity_merges(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<EntityMerge> {
+        let pool_arc = pool.clone();
+
+        match EntityMerge::get_records(&pool_arc).await {
+            Ok(records) => GetWholeTableResponse::ok(records),
+            Err(e) => {
+                let err = format!("Failed to fetch entity merges: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/workspaces` with payload to create a workspace, the current user becomes the owner of the workspace.
+    #[oai(
+        path = "/workspaces",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postWorkspace"
+    )]
+    async fn post_workspace(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<Workspace>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<Workspace> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Curator) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let mut payload = payload.0;
+        payload.update_owner(_token.0.username.clone());
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate the workspace: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        match payload.insert(&pool_arc).await {
+            Ok(workspace) => PostResponse::created(workspace),
+            Err(e) => {
+                let err = format!("Failed to create the workspace: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/workspaces` to fetch the workspaces which the current user is a member of, an admin can see all workspaces.
+    #[oai(
+        path = "/workspaces",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchWorkspaces"
+    )]
+    async fn fetch_workspaces(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<Workspace> {
+        let pool_arc = pool.clone();
+
+        match Workspace::get_records(&pool_arc, &_token.0.username, _token.0.is_admin()).await {
+            Ok(records) => GetWholeTableResponse::ok(records),
+            Err(e) => {
+                let err = format!("Failed to fetch workspaces: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/workspaces/:id/members` to fetch the members of a workspace, only the members can see them.
+    #[oai(
+        path = "/workspaces/:id/members",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchWorkspaceMembers"
+    )]
+    async fn fetch_workspace_members(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<WorkspaceMember> {
+        let pool_arc = pool.clone();
+        let id = id.0;
+
+        if let Err(e) = check_workspace_role(&pool_arc, &_token.0, id, WorkspaceRole::Viewer).await
+        {
+            warn!("{}", e.message);
+            return GetWholeTableResponse::error(e);
+        }
+
+        match WorkspaceMember::get_records(&pool_arc, id).await {
+            Ok(records) => GetWholeTableResponse::ok(records),
+            Err(e) => {
+                let err = format!("Failed to fetch the workspace members: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/workspaces/:id/members` with payload to invite a user to a workspace, or change the role of a member. Only the owners of the workspace can invite members.
+    #[oai(
+        path = "/workspaces/:id/members",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postWorkspaceMember"
+    )]
+    async fn post_workspace_member(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        payload: Json<WorkspaceMemberRequest>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<WorkspaceMember> {
+        let pool_arc = pool.clone();
+        let id = id.0;
+        let payload = payload.0;
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate the workspace member: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        let workspace = match Workspace::get_record(&pool_arc, id).await {
+            Ok(Some(workspace)) => workspace,
+            Ok(None) => {
+                let err = format!("Cannot find the workspace {}.", id);
+                warn!("{}", err);
+                return PostResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the workspace: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if let Err(e) = check_workspace_role(&pool_arc, &_token.0, id, WorkspaceRole::Owner).await {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        // The workspace must keep its owner, otherwise nobody can manage the members.
+        if payload.username == workspace.owner && payload.role != WorkspaceRole::Owner {
+            let err = format!(
+                "{} is the owner of the workspace {}, the role cannot be changed.",
+                payload.username, id
+            );
+            warn!("{}", err);
+            return PostResponse::error(ApiError::new(ErrorCode::Conflict, err));
+        }
+
+        match WorkspaceMember::upsert(
+            &pool_arc,
+            id,
+            &payload.username,
+            payload.role,
+            &_token.0.username,
+        )
+        .await
+        {
+            Ok(member) => PostResponse::created(member),
+            Err(e) => {
+                let err = format!("Failed to invite the workspace member: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/workspaces/:id/members/:username` to remove a member from a workspace. Only the owners of the workspace can remove members, and the owner who created the workspace cannot be removed.
+    #[oai(
+        path = "/workspaces/:id/members/:username",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteWorkspaceMember"
+    )]
+    async fn delete_workspace_member(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        username: Path<String>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+        let id = id.0;
+        let username = username.0;
+
+        let workspace = match Workspace::get_record(&pool_arc, id).await {
+            Ok(Some(workspace)) => workspace,
+            Ok(None) => {
+                let err = format!("Cannot find the workspace {}.", id);
+                warn!("{}", err);
+                return DeleteResponse::not_found(err);
+            }
+            Err(e) => {
+                let err = format!("Failed to fetch the workspace: {}", e);
+                warn!("{}", err);
+                return DeleteResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+
+        if let Err(e) = check_workspace_role(&pool_arc, &_token.0, id, WorkspaceRole::Owner).await {
+            warn!("{}", e.message);
+            return DeleteResponse::error(e);
+        }
+
+        if username == workspace.owner {
+            let err = format!(
+                "{} is the owner of the workspace {}, it cannot be removed.",
+                username, id
+            );
+            warn!("{}", err);
+            return DeleteResponse::error(ApiError::new(ErrorCode::Conflict, err));
+        }
+
+        match WorkspaceMember::delete(&pool_arc, id, &username).await {
+            Ok(Some(_)) => DeleteResponse::no_content(),
+            Ok(None) => {
+                let err = format!("{} is not a member of the workspace {}.", username, id);
+                warn!("{}", err);
+                DeleteResponse::not_found(err)
+            }
+            Err(e) => {
+                let err = format!("Failed to remove the workspace member: {}", e);
+                warn!("{}", err);
+                DeleteResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/webhooks` with payload to register a webhook, the subscribed events (see `event_types`) are sent to the url as signed POST requests. Only the admins can manage the webhooks.
+    #[oai(
+        path = "/webhooks",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postWebhook"
+    )]
+    async fn post_webhook(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<Webhook>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<Webhook> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        let mut payload = payload.0;
+        payload.update_owner(_token.0.username.clone());
+
+        match payload.validate() {
+            Ok(_) => {}
+            Err(e) => {
+                let err = format!("Failed to validate the webhook: {}", e);
+                warn!("{}", err);
+                return PostResponse::error(ApiError::from_error(err, &e));
+            }
+        };
+
+        if let Err(e) = payload.check() {
+            let err = format!("Failed to validate the webhook: {}", e);
+            warn!("{}", err);
+            return PostResponse::error(ApiError::from_error(err, &e));
+        }
+
+        match payload.insert(&pool_arc).await {
+            Ok(webhook) => PostResponse::created(webhook),
+            Err(e) => {
+                let err = format!("Failed to register the webhook: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/webhooks` to fetch the registered webhooks and the results of their last deliveries.
+    #[oai(
+        path = "/webhooks",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchWebhooks"
+    )]
+    async fn fetch_webhooks(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<Webhook> {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return GetWholeTableResponse::error(ApiError::new(ErrorCode::Forbidden, err));
+        }
+
+        match Webhook::get_records(&pool_arc).await {
+            Ok(records) => GetWholeTableResponse::ok(records),
+            Err(e) => {
+                let err = format!("Failed to fetch webhooks: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/webhooks/:id` to remove a webhook.
+    #[oai(
+        path = "/webhooks/:id",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteWebhook"
+    )]
+    async fn delete_webhook(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+        let id = id.0;
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return DeleteResponse::forbidden(err);
+        }
+
+        match Webhook::delete(&pool_arc, id).await {
+            Ok(Some(_)) => DeleteResponse::no_content(),
+            Ok(None) => {
+                let err = format!("Cannot find the webhook {}.", id);
+                warn!("{}", err);
+                DeleteResponse::not_found(err)
+            }
+            Err(e) => {
+                let err = format!("Failed to delete the webhook: {}", e);
+                warn!("{}", err);
+                DeleteResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/graph-theme` to fetch the active graph theme, i.e. the styles of the nodes by the entity label and the styles of the edges by the relation type. It is empty if the built-in styles are used.
+    #[oai(
+        path = "/graph-theme",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchGraphTheme"
+    )]
+    async fn fetch_graph_theme(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GraphThemeResponse {
+        let pool_arc = pool.clone();
+
+        match GraphTheme::get(&pool_arc).await {
+            Ok(theme) => GraphThemeResponse::ok(theme.unwrap_or_default()),
+            Err(e) => {
+                let err = format!("Failed to fetch the graph theme: {}", e);
+                warn!("{}", err);
+                GraphThemeResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/graph-theme` with payload to replace the graph theme, such as `{"nodes": {"Gene": {"color": "#1f77b4", "shape": "graphin-circle"}}, "edges": {"treats": {"color": "#2ca02c", "line_width": 2}}}`. It is applied to all graphs returned by the API, so a deployment can be re-branded without recompiling. Only the admins can change the theme.
+    #[oai(
+        path = "/graph-theme",
+        method = "put",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "putGraphTheme"
+    )]
+    async fn put_graph_theme(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<GraphTheme>,
+        _token: CustomSecurityScheme,
+    ) -> GraphThemeResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return GraphThemeResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("graph theme") {
+            warn!("{}", err.message);
+            return GraphThemeResponse::error(err);
+        }
+
+        let payload = payload.0;
+        if let Err(e) = payload.check() {
+            let err = format!("Failed to validate the graph theme: {}", e);
+            warn!("{}", err);
+            return GraphThemeResponse::error(ApiError::from_error(err, &e));
+        }
+
+        match payload.save(&pool_arc, &_token.0.username).await {
+            Ok(_) => {
+                // The cached graphs have the styles of the previous theme.
+                invalidate_cache(&CacheScope::all()).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                GraphThemeResponse::ok(payload)
+            }
+            Err(e) => {
+                let err = format!("Failed to save the graph theme: {}", e);
+                warn!("{}", err);
+                GraphThemeResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/graph-theme` to remove the graph theme, the built-in styles are used again.
+    #[oai(
+        path = "/graph-theme",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteGraphTheme"
+    )]
+    async fn delete_graph_theme(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return DeleteResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("graph theme") {
+            warn!("{}", err.message);
+            return DeleteResponse::error(err);
+        }
+
+        match GraphTheme::delete(&pool_arc).await {
+            Ok(true) => {
+                invalidate_cache(&CacheScope::all()).await;
+                invalidate_materialized_graphs(&pool_arc).await;
+                DeleteResponse::no_content()
+            }
+            Ok(false) => DeleteResponse::not_found("No graph theme has been saved.".to_string()),
+            Err(e) => {
+                let err = format!("Failed to delete the graph theme: {}", e);
+                warn!("{}", err);
+                DeleteResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types` to fetch the registered relation types, i.e. the human-readable labels, the descriptions, the inverse relation types and the parent relation types. The labels are shown on the edges of all returned graphs.
+    #[oai(
+        path = "/relation-types",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchRelationTypes"
+    )]
+    async fn fetch_relation_types(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<RelationTypeDefinition> {
+        let pool_arc = pool.clone();
+
+        match RelationTypeDefinition::get_records(&pool_arc).await {
+            Ok(definitions) => GetWholeTableResponse::ok(definitions),
+            Err(e) => {
+                let err = format!("Failed to fetch the relation types: {}", e);
+                warn!("{}", err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types/groups` to fetch the registered relation types grouped by their farthest ancestors, so the frontend can show the related relation types together. A parent goes before its children in each group, and the parents which are not registered are labeled by themselves.
+    #[oai(
+        path = "/relation-types/groups",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchRelationTypeGroups"
+    )]
+    async fn fetch_relation_type_groups(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetRelationTypeGroupsResponse {
+        let pool_arc = pool.clone();
+
+        match RelationTypeRegistry::load(&pool_arc).await {
+            Ok(registry) => GetRelationTypeGroupsResponse::ok(registry.groups()),
+            Err(e) => {
+                let err = format!("Failed to fetch the relation types: {}", e);
+                warn!("{}", err);
+                GetRelationTypeGroupsResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types` with payload to register a relation type, such as `{"relation_type": "GNBR::T::Compound:Disease", "label": "treats", "inverse_relation_type": "GNBR::treated_by::Disease:Compound", "parent_relation_type": "BIOMEDGPS::treats::Compound:Disease"}`. The hierarchy must not have loops, and an inverse relation type can only be the inverse of one relation type. Only the admins can change the relation types.
+    #[oai(
+        path = "/relation-types",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postRelationType"
+    )]
+    async fn post_relation_type(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<RelationTypeDefinition>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<RelationTypeDefinition> {
+        let pool_arc = pool.clone();
+        let payload = payload.0;
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("relation types") {
+            warn!("{}", err.message);
+            return PostResponse::error(err);
+        }
+
+        if let Err(e) = check_relation_type(&pool_arc, &payload, None).await {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        match payload.insert(&pool_arc).await {
+            Ok(definition) => {
+                reload_relation_types(&pool_arc).await;
+                PostResponse::created(definition)
+            }
+            Err(e) => {
+                let err = format!("Failed to insert the relation type: {}", e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types/:id` with payload to update a registered relation type. Only the admins can change the relation types.
+    #[oai(
+        path = "/relation-types/:id",
+        method = "put",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "putRelationType"
+    )]
+    async fn put_relation_type(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        payload: Json<RelationTypeDefinition>,
+        _token: CustomSecurityScheme,
+    ) -> PostResponse<RelationTypeDefinition> {
+        let pool_arc = pool.clone();
+        let id = id.0;
+        let payload = payload.0;
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return PostResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("relation types") {
+            warn!("{}", err.message);
+            return PostResponse::error(err);
+        }
+
+        if let Err(e) = check_relation_type(&pool_arc, &payload, Some(id)).await {
+            warn!("{}", e.message);
+            return PostResponse::error(e);
+        }
+
+        match payload.update(&pool_arc, id).await {
+            Ok(definition) => {
+                reload_relation_types(&pool_arc).await;
+                PostResponse::created(definition)
+            }
+            Err(e) => {
+                let err = format!("Failed to update the relation type {}: {}", id, e);
+                warn!("{}", err);
+                PostResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types/:id` to unregister a relation type, the edges of the relation type use the relation type as the label again. Only the admins can change the relation types.
+    #[oai(
+        path = "/relation-types/:id",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteRelationType"
+    )]
+    async fn delete_relation_type(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        id: Path<i64>,
+        _token: CustomSecurityScheme,
+    ) -> DeleteResponse {
+        let pool_arc = pool.clone();
+        let id = id.0;
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return DeleteResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("relation types") {
+            warn!("{}", err.message);
+            return DeleteResponse::error(err);
+        }
+
+        match RelationTypeDefinition::delete(&pool_arc, id).await {
+            Ok(_) => {
+                reload_relation_types(&pool_arc).await;
+                DeleteResponse::no_content()
+            }
+            Err(e) => {
+                let err = format!("Failed to delete the relation type {}: {}", id, e);
+                warn!("{}", err);
+                DeleteResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/relation-types/import` with a list of relation types to register them in one transaction, the registered relation types with the same relation types are replaced. The whole list is checked against the registry before it is saved, and all the registered relation types are returned. The large registries can also be imported by `biomedgps-cli importdb -t relation_type`. Only the admins can change the relation types.
+    #[oai(
+        path = "/relation-types/import",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "importRelationTypes"
+    )]
+    async fn import_relation_types(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<Vec<RelationTypeDefinition>>,
+        _token: CustomSecurityScheme,
+    ) -> ImportRelationTypesResponse {
+        let pool_arc = pool.clone();
+        let payload = payload.0;
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return ImportRelationTypesResponse::forbidden(err);
+        }
+
+        if let Err(err) = check_default_database("relation types") {
+            warn!("{}", err.message);
+            return ImportRelationTypesResponse::error(err);
+        }
+
+        if payload.is_empty() || payload.len() > MAX_IMPORTED_RELATION_TYPES {
+            let err = format!(
+                "The number of the relation types should be between 1 and {}.",
+                MAX_IMPORTED_RELATION_TYPES
+            );
+            warn!("{}", err);
+            return ImportRelationTypesResponse::bad_request(err);
+        }
+
+        for definition in payload.iter() {
+            if let Err(e) = definition.validate() {
+                let err = format!(
+                    "Failed to validate the relation type {}: {}",
+                    definition.relation_type, e
+                );
+                warn!("{}", err);
+                return ImportRelationTypesResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        // Check the list as if it was saved, so the relation types in the list can refer to each other.
+        let mut definitions = match RelationTypeDefinition::get_records(&pool_arc).await {
+            Ok(definitions) => definitions,
+            Err(e) => {
+                let err = format!("Failed to fetch the relation types: {}", e);
+                warn!("{}", err);
+                return ImportRelationTypesResponse::error(ApiError::from_error(err, &*e));
+            }
+        };
+        definitions.retain(|d| !payload.iter().any(|p| p.relation_type == d.relation_type));
+        definitions.extend(payload.iter().cloned());
+        let registry = RelationTypeRegistry::new(definitions);
+        for definition in payload.iter() {
+            if let Err(e) = registry.check(definition) {
+                let err = format!("Invalid relation type {}: {}", definition.relation_type, e);
+                warn!("{}", err);
+                return ImportRelationTypesResponse::error(ApiError::from_error(err, &e));
+            }
+        }
+
+        if let Err(e) = RelationTypeDefinition::upsert_all(&pool_arc, &payload).await {
+            let err = format!("Failed to import the relation types: {}", e);
+            warn!("{}", err);
+            return ImportRelationTypesResponse::error(ApiError::from_error(err, &*e));
+        }
+        reload_relation_types(&pool_arc).await;
+
+        match RelationTypeDefinition::get_records(&pool_arc).await {
+            Ok(definitions) => ImportRelationTypesResponse::ok(definitions),
+            Err(e) => {
+                let err = format!("Failed to fetch the relation types: {}", e);
+                warn!("{}", err);
+                ImportRelationTypesResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+
+    /// Call `/api/v1/admin/config` to fetch the active config of the server, the secrets (such as the database url and the api keys) are masked. It also reports the settings which were changed by the last reload but need a restart. Only the admins can fetch the config.
+    #[oai(
+        path = "/admin/config",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchAdminConfig"
+    )]
+    async fn fetch_admin_config(&self, _token: CustomSecurityScheme) -> ConfigReportResponse {
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return ConfigReportResponse::forbidden(err);
+        }
+
+        match get_config_status() {
+            Some(status) => ConfigReportResponse::ok(ConfigReport::new(&status)),
+            None => ConfigReportResponse::error(ApiError::new(
+                ErrorCode::NotFound,
+                "The config of the server isn't loaded.".to_string(),
+            )),
+        }
+    }
+
+    /// Call `/api/v1/admin/reload-config` to reload the config file and the environment variables without a restart, the command line arguments still take precedence. The reloadable settings (such as the api keys, the rate limits, the query timeout and the payload storage) are applied at once, the graph theme is reloaded and the caches are cleared. The other changed settings (such as the database url) are reported as `restart_required`. An invalid config is rejected and the active config is kept. Only the admins can reload the config.
+    #[oai(
+        path = "/admin/reload-config",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "reloadConfig"
+    )]
+    async fn reload_config(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        rate_limiter: Data<&RateLimit>,
+        _token: CustomSecurityScheme,
+    ) -> ConfigReportResponse {
+        let pool_arc = pool.clone();
+
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return ConfigReportResponse::forbidden(err);
+        }
+
+        let status = match reload_config() {
+            Ok(status) => status,
+            Err(e) => {
+                let err = format!("Failed to reload the config: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                return ConfigReportResponse::error(ApiError::new(ErrorCode::BadRequest, err));
             }
+        };
+
+        let config = &status.config;
+        rate_limiter.set_limits(
+            config.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT),
+            config
+                .strict_rate_limit
+                .unwrap_or(DEFAULT_STRICT_RATE_LIMIT),
+        );
+
+        // The theme and the relation types may be changed by another instance which shares the database, they always come from the default database.
+        let default_pool = get_database_pools()
+            .and_then(|pools| pools.get(DEFAULT_DATABASE))
+            .unwrap_or_else(|| pool_arc.clone());
+        if let Err(e) = load_theme(&default_pool).await {
+            warn!("Failed to reload the graph theme: {}", e);
+        }
+        if let Err(e) = load_relation_types(&default_pool).await {
+            warn!("Failed to reload the relation types: {}", e);
         }
+        invalidate_cache(&CacheScope::all()).await;
+        invalidate_materialized_graphs(&pool_arc).await;
+
+        info!(
+            "The config is reloaded by {}, changed: {:?}, restart required: {:?}",
+            _token.0.username, status.changed, status.restart_required
+        );
+        ConfigReportResponse::ok(ConfigReport::new(&status))
     }
 
-    /// Call `/api/v1/subgraphs` with query params to fetch subgraphs.
+    /// Call `/api/v1/admin/materialized-graphs` to list the materialized expansions with their query keys, dataset versions and hit counts, the most reused ones go first. Only the admins can list them.
     #[oai(
-        path = "/subgraphs",
+        path = "/admin/materialized-graphs",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchSubgraphs"
+        operation_id = "fetchMaterializedGraphs"
     )]
-    async fn fetch_subgraphs(
+    async fn fetch_materialized_graphs(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        query_str: Query<Option<String>>,
         _token: CustomSecurityScheme,
-    ) -> GetRecordsResponse<Subgraph> {
-        let pool_arc = pool.clone();
-        let page = page.0;
-        let page_size = page_size.0;
+    ) -> GetMaterializedGraphsResponse {
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return GetMaterializedGraphsResponse::forbidden(err);
+        }
 
-        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
-            Ok(_) => {}
+        match MaterializedGraph::get_records(&get_primary_pool(&pool)).await {
+            Ok(records) => GetMaterializedGraphsResponse::ok(records),
             Err(e) => {
-                let err = format!("Failed to parse query string: {}", e);
+                let err = format!("Failed to fetch the materialized graphs: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                GetMaterializedGraphsResponse::error(ApiError::from_error(err, &*e))
             }
         }
+    }
 
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
-            None => {
-                warn!("Query string is empty.");
-                "".to_string()
-            }
-        };
-
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
-                    warn!("{}", err);
-                    return GetRecordsResponse::bad_request(err);
-                }
-            }
-        };
+    /// Call `/api/v1/admin/materialized-graphs` with the DELETE method to invalidate the materialized expansions, such as after a curation of the relations. Use `query_hash` and `dataset_version` to only invalidate the matched ones, all of them are invalidated by default. The expired ones are always deleted, and the cached expansions of the server are cleared too. Only the admins can invalidate them.
+    #[oai(
+        path = "/admin/materialized-graphs",
+        method = "delete",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "deleteMaterializedGraphs"
+    )]
+    async fn delete_materialized_graphs(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        query_hash: Query<Option<String>>,
+        dataset_version: Query<Option<String>>,
+        _token: CustomSecurityScheme,
+    ) -> InvalidateMaterializedGraphsResponse {
+        if let Err(err) = _token.0.check_role(Role::Admin) {
+            warn!("{}", err);
+            return InvalidateMaterializedGraphsResponse::forbidden(err);
+        }
 
-        match RecordResponse::<Subgraph>::get_records(
-            &pool_arc,
-            "biomedgps_subgraph",
-            &query,
-            page,
-            page_size,
-            Some("created_time DESC"),
+        match MaterializedGraph::delete(
+            &get_primary_pool(&pool),
+            query_hash.0.as_deref(),
+            dataset_version.0.as_deref(),
         )
         .await
         {
-            Ok(entities) => GetRecordsResponse::ok(entities),
+            Ok(deleted) => {
+                invalidate_cache(&[CacheScope::Expansion]).await;
+                info!(
+                    "{} materialized graphs are invalidated by {}.",
+                    deleted, _token.0.username
+                );
+                InvalidateMaterializedGraphsResponse::ok(MaterializationInvalidation { deleted })
+            }
             Err(e) => {
-                let err = format!("Failed to fetch subgraphs: {}", e);
+                let err = format!("Failed to invalidate the materialized graphs: {}", e);
                 warn!("{}", err);
-                return GetRecordsResponse::bad_request(err);
+                InvalidateMaterializedGraphsResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/subgraphs` with payload to create a subgraph.
+    /// Call `/api/v1/rdf` to export the entities and relations as RDF, such as `/api/v1/rdf?format=ntriples`. The format is turtle (default), ntriples or jsonld.
+    ///
+    /// The entity ids are expanded to IRIs by the built-in prefix map (the unknown prefixes go to identifiers.org), the entities are typed by the BioLink classes and the relations use the BioLink predicates (see `/api/v1/biolink-mappings`). The whole graph is streamed from the database, use `biomedgps-cli export-rdf` with a custom prefix map if you need other IRIs.
     #[oai(
-        path = "/subgraphs",
-        method = "post",
+        path = "/rdf",
+        method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "postSubgraph"
+        operation_id = "exportRdf"
     )]
-    async fn post_subgraph(
+    async fn export_rdf(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        payload: Json<Subgraph>,
+        format: Query<Option<RdfFormat>>,
         _token: CustomSecurityScheme,
-    ) -> PostResponse<Subgraph> {
-        let pool_arc = pool.clone();
-        let mut payload = payload.0;
-        let username = _token.0.username.clone();
+    ) -> GetRdfResponse {
+        let format = format.0.unwrap_or(RdfFormat::Turtle);
+        let rdf = stream_rdf(pool.0.clone(), format, PrefixMap::default());
+        GetRdfResponse::stream(rdf, format)
+    }
 
-        // When we enabled auth mode, we need to use the username from an access_token instead.
-        if username != USERNAME_PLACEHOLDER.to_string() {
-            payload.update_owner(username);
+    /// Call `/api/v1/relation-splits/:split/triples` to export the triples of a dataset split (train, valid or test) for training the knowledge graph embeddings, such as `/api/v1/relation-splits/train/triples`.
+    ///
+    /// The triples are streamed as a tab-separated file without a header, each line is the head, the relation type and the tail (the node ids like `Gene::ENTREZ:7157`), which can be loaded by DGL-KE (`--format raw_udd_hrt`) and PyKEEN (`TriplesFactory.from_path`). The splits are generated by `biomedgps-cli splitdb`, the file is empty if the relations are not split yet.
+    ///
+    /// If `with_group` is true, the data source of each triple (the `resource` column, such as `drkg`) is appended as the fourth column, so the training runs can weight or exclude the triples of some sources, such as `/api/v1/relation-splits/train/triples?with_group=true`. Use `biomedgps-cli export-triples --by-group` to export the triples of each source into separate files.
+    #[oai(
+        path = "/relation-splits/:split/triples",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSplitTriples"
+    )]
+    async fn fetch_split_triples(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        split: Path<RelationSplit>,
+        with_group: Query<Option<bool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetTriplesResponse {
+        let triples = stream_triples(pool.0.clone(), split.0, with_group.0.unwrap_or(false));
+        GetTriplesResponse::stream(triples, split.0)
+    }
+
+    /// Call `/api/v1/relation-splits/:split/negatives` to export the negative triples of a dataset split for training the knowledge graph embeddings, such as `/api/v1/relation-splits/train/negatives?num_negatives=5&corrupt=tail&seed=42`.
+    ///
+    /// The head or the tail (the `corrupt` parameter, both by default) of each triple in the split is replaced by another entity of the same type, and the known triples in all splits are never generated, so the negatives are consistent with the entity types of the relation types. The negatives are streamed in the same format as the triples, `num_negatives` (1 by default) negatives for each triple, and the same seed gives the same negatives on the same data.
+    #[oai(
+        path = "/relation-splits/:split/negatives",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchSplitNegatives"
+    )]
+    async fn fetch_split_negatives(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        split: Path<RelationSplit>,
+        num_negatives: Query<Option<usize>>,
+        corrupt: Query<Option<CorruptionSide>>,
+        seed: Query<Option<u64>>,
+        _token: CustomSecurityScheme,
+    ) -> GetTriplesResponse {
+        let num_negatives = num_negatives.0.unwrap_or(1);
+        if num_negatives == 0 || num_negatives > MAX_NEGATIVES_PER_TRIPLE {
+            let err = format!(
+                "The num_negatives should be between 1 and {}, but got {}.",
+                MAX_NEGATIVES_PER_TRIPLE, num_negatives
+            );
+            warn!("{}", err);
+            return GetTriplesResponse::bad_request(err);
         }
 
-        match payload.validate() {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to validate subgraph: {}", e);
-                warn!("{}", err);
-                return PostResponse::bad_request(err);
-            }
-        };
+        let negatives = stream_negatives(
+            pool.0.clone(),
+            split.0,
+            corrupt.0.unwrap_or(CorruptionSide::Both),
+            num_negatives,
+            seed.0.unwrap_or(DEFAULT_SPLIT_SEED),
+        );
+        GetTriplesResponse::stream_negatives(negatives, split.0)
+    }
 
-        match payload.insert(&pool_arc).await {
-            Ok(kc) => PostResponse::Created(Json(kc)),
+    /// Call `/api/v1/notifications` to fetch the notification feed of the current user, the latest one comes first, such as `/api/v1/notifications?since_id=100&limit=50`.
+    ///
+    /// The feed contains the events for all users (such as the finished imports and the curated knowledges) and the events of the workspaces which the user is a member of. Use `since_id` (the largest id which has been fetched) to poll the new notifications.
+    #[oai(
+        path = "/notifications",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchNotifications"
+    )]
+    async fn fetch_notifications(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        since_id: Query<Option<i64>>,
+        limit: Query<Option<u64>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<Notification> {
+        let pool_arc = pool.clone();
+        let limit = limit.0.unwrap_or(DEFAULT_FEED_SIZE);
+
+        if limit == 0 || limit > get_max_page_size() {
+            let err = format!(
+                "The limit should be between 1 and {}, but got {}.",
+                get_max_page_size(),
+                limit
+            );
+            warn!("{}", err);
+            return GetWholeTableResponse::error(ApiError::new(ErrorCode::ValidationError, err));
+        }
+
+        match Notification::get_records(&pool_arc, &_token.0.username, since_id.0, limit).await {
+            Ok(records) => GetWholeTableResponse::ok(records),
             Err(e) => {
-                let err = format!("Failed to insert curated knowledge: {}", e);
+                let err = format!("Failed to fetch notifications: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/subgraphs/:id` with payload to update a subgraph.
+    /// Call `/api/v1/embedding-ops` with query params to run an operation on the entity embeddings, such as `/api/v1/embedding-ops?operation=similarity&node_ids=Disease::MESH:D001,Disease::MESH:D002`.
+    ///
+    /// The operation is one of similarity (2 node ids, the cosine similarity between them), analogy (3 node ids A, B and C, the nearest entities of A - B + C) and centroid (at least 1 node id, the nearest entities of the mean vector and the mean similarity between the nodes and the mean vector). The nearest entities can be filtered by entity_type. The `model` is the embedding model, the latest registered model is used by default.
     #[oai(
-        path = "/subgraphs/:id",
-        method = "put",
+        path = "/embedding-ops",
+        method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "putSubgraph"
+        operation_id = "fetchEmbeddingOps"
     )]
-    async fn put_subgraph(
+    async fn fetch_embedding_ops(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        id: Path<String>,
-        payload: Json<Subgraph>,
+        operation: Query<EmbeddingOperation>,
+        node_ids: Query<String>,
+        topk: Query<Option<u64>>,
+        entity_type: Query<Option<String>>,
+        model: Query<Option<String>>,
         _token: CustomSecurityScheme,
-    ) -> PostResponse<Subgraph> {
+    ) -> GetEmbeddingOpsResponse {
         let pool_arc = pool.clone();
-        let id = id.0;
-        let mut payload = payload.0;
-        let username = _token.0.username.clone();
 
-        // When we enabled auth mode, we need to use the username from an access_token instead.
-        if username != "user-placeholder".to_string() {
-            payload.update_owner(username);
+        if let Err(e) = NodeIdsQuery::new(&node_ids.0) {
+            let err = format!("Failed to parse node ids: {}", e);
+            warn!("{}", err);
+            return GetEmbeddingOpsResponse::error(ApiError::from_error(err, &e));
         }
 
-        match SubgraphIdQuery::new(&id) {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to parse subgraph id: {}", e);
-                warn!("{}", err);
-                return PostResponse::bad_request(err);
-            }
+        if let Err(e) = SimilarityNodeQuery::new(
+            node_ids.0.split(',').next().unwrap_or_default(),
+            &None,
+            topk.0,
+            &entity_type.0,
+            None,
+        ) {
+            let err = format!("Failed to parse query: {}", e);
+            warn!("{}", err);
+            return GetEmbeddingOpsResponse::error(ApiError::from_error(err, &e));
         }
 
-        match payload.validate() {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to validate subgraph: {}", e);
-                warn!("{}", err);
-                return PostResponse::bad_request(err);
-            }
-        }
+        let node_ids = node_ids
+            .0
+            .split(',')
+            .map(|node_id| node_id.to_string())
+            .collect::<Vec<String>>();
 
-        match payload.update(&pool_arc, &id).await {
-            Ok(kc) => PostResponse::Created(Json(kc)),
+        match EmbeddingOpsResult::run(
+            &pool_arc,
+            operation.0,
+            node_ids,
+            topk.0.unwrap_or(10),
+            entity_type.0.as_deref(),
+            model.0.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => GetEmbeddingOpsResponse::ok(result),
             Err(e) => {
-                let err = format!("Failed to update subgraph: {}", e);
+                let err = format!("Failed to run the embedding operation: {}", e);
                 warn!("{}", err);
-                return PostResponse::bad_request(err);
+                GetEmbeddingOpsResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/subgraphs/:id` with payload to create subgraph.
+    /// Call `/api/v1/batch-similarities` with a json body to compute the cosine similarities between a set of nodes (at most 500) and the anchors (at most 10) in one pass, such as all nodes of a subgraph against a query node.
+    ///
+    /// The similarities are grouped by the anchors in the order of the anchor ids, and sorted by the score in each group. All anchors must have the embeddings, the nodes without the embeddings are returned as the missing node ids. The `model` is the embedding model, the latest registered model is used by default.
     #[oai(
-        path = "/subgraphs/:id",
-        method = "delete",
+        path = "/batch-similarities",
+        method = "post",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "deleteSubgraph"
+        operation_id = "postBatchSimilarities"
     )]
-    async fn delete_subgraph(
+    async fn post_batch_similarities(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        id: Path<String>,
+        payload: Json<BatchSimilarityRequest>,
         _token: CustomSecurityScheme,
-    ) -> DeleteResponse {
+    ) -> GetBatchSimilarityResponse {
         let pool_arc = pool.clone();
-        let id = id.0;
 
-        match SubgraphIdQuery::new(&id) {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to validate subgraph id: {}", e);
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the batch similarity request: {}", e);
+            warn!("{}", err);
+            return GetBatchSimilarityResponse::error(ApiError::from_error(err, &e));
+        }
+
+        for node_ids in [&payload.anchor_ids, &payload.node_ids] {
+            if let Err(e) = NodeIdsQuery::new(&node_ids.join(",")) {
+                let err = format!("Failed to parse node ids: {}", e);
                 warn!("{}", err);
-                return DeleteResponse::bad_request(err);
+                return GetBatchSimilarityResponse::error(ApiError::from_error(err, &e));
             }
         }
 
-        match Subgraph::delete(&pool_arc, &id).await {
-            Ok(_) => DeleteResponse::NoContent,
+        match BatchSimilarity::run(
+            &pool_arc,
+            payload.anchor_ids,
+            payload.node_ids,
+            payload.model.as_deref(),
+        )
+        .await
+        {
+            Ok(result) => GetBatchSimilarityResponse::ok(result),
             Err(e) => {
-                let err = format!("Failed to delete a subgraph: {}", e);
+                let err = format!("Failed to compute the batch similarities: {}", e);
                 warn!("{}", err);
-                DeleteResponse::not_found(err)
+                GetBatchSimilarityResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/nodes` with query params to fetch nodes.
+    /// Call `/api/v1/genes/resolve` with a json body to resolve the gene and protein identifiers (such as a gene list pasted from a paper) to the entities, the types of the identifiers can be mixed, such as the symbols, the ENTREZ ids, the UniProt accessions, the Ensembl ids and the RefSeq accessions.
+    ///
+    /// The results are in the order of the identifiers, each result has the recognized identifier type and the matched entities with the match types and the confidences, the best match goes first. The accessions are matched by the entity ids and the xrefs, the symbols by the names and the synonyms. The unresolved identifiers have no matches.
     #[oai(
-        path = "/nodes",
-        method = "get",
+        path = "/genes/resolve",
+        method = "post",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchNodes"
+        operation_id = "postGenesResolve"
     )]
-    async fn fetch_nodes(
+    async fn post_genes_resolve(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        node_ids: Query<String>,
+        payload: Json<GeneResolutionRequest>,
         _token: CustomSecurityScheme,
-    ) -> GetGraphResponse {
+    ) -> GetGeneResolutionResponse {
         let pool_arc = pool.clone();
-        let node_ids = node_ids.0;
 
-        match NodeIdsQuery::new(&node_ids) {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to validate node ids: {}", e);
-                warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
-            }
-        };
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the gene resolution request: {}", e);
+            warn!("{}", err);
+            return GetGeneResolutionResponse::error(ApiError::from_error(err, &e));
+        }
 
-        let mut graph = Graph::new();
+        let identifiers = split_identifiers(&payload.identifiers);
+        if identifiers.is_empty() || identifiers.len() > MAX_IDENTIFIERS {
+            let err = format!(
+                "The number of identifiers should be between 1 and {}, but got {}.",
+                MAX_IDENTIFIERS,
+                identifiers.len()
+            );
+            warn!("{}", err);
+            return GetGeneResolutionResponse::bad_request(err);
+        }
 
-        if node_ids == "" {
-            return GetGraphResponse::ok(graph);
+        let labels = payload.labels.unwrap_or_else(|| {
+            DEFAULT_GENE_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect()
+        });
+        if let Some(label) = labels
+            .iter()
+            .find(|label| !ENTITY_LABEL_REGEX.is_match(label))
+        {
+            let err = format!("Invalid entity label: {}", label);
+            warn!("{}", err);
+            return GetGeneResolutionResponse::bad_request(err);
         }
 
-        let node_ids: Vec<&str> = node_ids.split(",").collect();
-        match graph.fetch_nodes_by_ids(&pool_arc, &node_ids).await {
-            Ok(graph) => GetGraphResponse::ok(graph.to_owned().get_graph(None).unwrap()),
+        match GeneResolution::resolve(&pool_arc, &identifiers, &labels, payload.taxid.as_deref())
+            .await
+        {
+            Ok(results) => GetGeneResolutionResponse::ok(results),
             Err(e) => {
-                let err = format!("Failed to fetch nodes: {}", e);
+                let err = format!("Failed to resolve the genes: {}", e);
                 warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+                GetGeneResolutionResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/auto-connect-nodes` with query params to fetch edges which connect the input nodes.
+    /// Call `/api/v1/compounds/structure-search` with a json body to search the compounds by the structure of a query molecule (a SMILES), the structures are imported by `biomedgps-cli importdb -t compound_structure`.
+    ///
+    /// The `substructure` mode returns the compounds which contain the query molecule, the `similarity` mode returns the compounds whose Tanimoto similarities (of the path fingerprints) are at least `min_similarity`. The compounds are sorted by the similarity, the compounds whose SMILES cannot be parsed are skipped and counted.
     #[oai(
-        path = "/auto-connect-nodes",
-        method = "get",
+        path = "/compounds/structure-search",
+        method = "post",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchEdgesAutoConnectNodes"
+        operation_id = "postStructureSearch"
     )]
-    async fn fetch_edges_auto_connect_nodes(
+    async fn post_structure_search(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        node_ids: Query<String>,
+        payload: Json<StructureSearchRequest>,
         _token: CustomSecurityScheme,
-    ) -> GetGraphResponse {
+    ) -> GetStructureSearchResponse {
         let pool_arc = pool.clone();
-        let node_ids = node_ids.0;
 
-        match NodeIdsQuery::new(&node_ids) {
-            Ok(_) => {}
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the structure search request: {}", e);
+            warn!("{}", err);
+            return GetStructureSearchResponse::error(ApiError::from_error(err, &e));
+        }
+
+        match StructureSearchResult::search(
+            &pool_arc,
+            &payload.smiles,
+            payload.mode,
+            payload.min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY),
+            payload.entity_type.as_deref(),
+            payload.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+        .await
+        {
+            Ok(result) => GetStructureSearchResponse::ok(result),
             Err(e) => {
-                let err = format!("Failed to validate node ids: {}", e);
+                let err = format!("Failed to search the compounds by structure: {}", e);
                 warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+                GetStructureSearchResponse::error(ApiError::from_error(err, &*e))
             }
-        };
+        }
+    }
 
-        let mut graph = Graph::new();
+    /// Call `/api/v1/relations/exists` with a json body to check whether the triples (at most 1000, each is a source id, a relation type and a target id) exist in the knowledge graph, such as before curating them.
+    ///
+    /// The results are in the order of the triples, each result has a flag and the ids of the matched relations. All triples are checked by one query.
+    #[oai(
+        path = "/relations/exists",
+        method = "post",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "postRelationsExists"
+    )]
+    async fn post_relations_exists(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        payload: Json<RelationExistenceRequest>,
+        _token: CustomSecurityScheme,
+    ) -> GetRelationExistenceResponse {
+        let pool_arc = pool.clone();
 
-        if node_ids == "" {
-            return GetGraphResponse::ok(graph);
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the relation existence request: {}", e);
+            warn!("{}", err);
+            return GetRelationExistenceResponse::error(ApiError::from_error(err, &e));
+        }
+
+        for triple in payload.triples.iter() {
+            if let Err(e) = triple.validate() {
+                let err = format!("Failed to validate the triple {:?}: {}", triple, e);
+                warn!("{}", err);
+                return GetRelationExistenceResponse::error(ApiError::from_error(err, &e));
+            }
         }
 
-        let node_ids: Vec<&str> = node_ids.split(",").collect();
-        match graph.auto_connect_nodes(&pool_arc, &node_ids).await {
-            Ok(graph) => GetGraphResponse::ok(graph.to_owned().get_graph(None).unwrap()),
+        match RelationExistence::check(&pool_arc, &payload.triples).await {
+            Ok(results) => GetRelationExistenceResponse::ok(results),
             Err(e) => {
-                let err = format!("Failed to fetch nodes: {}", e);
+                let err = format!("Failed to check the relations: {}", e);
                 warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+                GetRelationExistenceResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/one-step-linked-nodes` with query params to fetch linked nodes with one step.
+    /// Call `/api/v1/enrichment` with a json body to find the gene sets (such as the pathways and the diseases) which are over-represented in a gene list (at most 5000 genes).
+    ///
+    /// Each gene set of the `sources` (see `/api/v1/gene-set-sources`, all sources by default) whose size is between `min_set_size` and `max_set_size` (5 and 500 by default) is tested by the hypergeometric test, and the p-values are corrected by the Benjamini-Hochberg method. The gene sets whose adjusted p-values are not larger than `adjusted_p_value_cutoff` (0.05 by default) are returned, the smallest p-values go first. The background is all genes of the tested gene sets unless `background_size` is specified, and the genes which are not in the background are returned as the unknown genes.
     #[oai(
-        path = "/one-step-linked-nodes",
-        method = "get",
+        path = "/enrichment",
+        method = "post",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchOneStepLinkedNodes"
+        operation_id = "postEnrichment"
     )]
-    async fn fetch_one_step_linked_nodes(
+    async fn post_enrichment(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        page: Query<Option<u64>>,
-        page_size: Query<Option<u64>>,
-        query_str: Query<Option<String>>,
+        payload: Json<EnrichmentRequest>,
         _token: CustomSecurityScheme,
-    ) -> GetGraphResponse {
+    ) -> GetEnrichmentResponse {
         let pool_arc = pool.clone();
-        let page = page.0;
-        let page_size = page_size.0;
 
-        match PaginationQuery::new(page.clone(), page_size.clone(), query_str.0.clone()) {
-            Ok(_) => {}
-            Err(e) => {
-                let err = format!("Failed to parse query string: {}", e);
-                warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
-            }
-        };
+        let payload = payload.0;
+        if let Err(e) = payload.validate() {
+            let err = format!("Failed to validate the enrichment request: {}", e);
+            warn!("{}", err);
+            return GetEnrichmentResponse::error(ApiError::from_error(err, &e));
+        }
 
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
-            None => {
-                warn!("Query string is empty.");
-                "".to_string()
-            }
+        let options = EnrichmentOptions {
+            sources: payload.sources.unwrap_or_default(),
+            min_set_size: payload.min_set_size.unwrap_or(DEFAULT_MIN_SET_SIZE),
+            max_set_size: payload.max_set_size.unwrap_or(DEFAULT_MAX_SET_SIZE),
+            background_size: payload.background_size,
+            adjusted_p_value_cutoff: payload
+                .adjusted_p_value_cutoff
+                .unwrap_or(DEFAULT_ADJUSTED_P_VALUE_CUTOFF),
         };
 
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
-                    warn!("{}", err);
-                    return GetGraphResponse::bad_request(err);
-                }
+        match EnrichmentResult::run(&pool_arc, &payload.genes, &options).await {
+            Ok(result) => GetEnrichmentResponse::ok(result),
+            Err(e) => {
+                let err = format!("Failed to run the enrichment analysis: {}", e);
+                warn!("{}", err);
+                GetEnrichmentResponse::error(ApiError::from_error(err, &*e))
             }
-        };
+        }
+    }
 
-        let mut graph = Graph::new();
-        match graph
-            .fetch_linked_nodes(&pool_arc, &query, page, page_size, None)
-            .await
+    /// Call `/api/v1/gene-set-sources` to fetch the sources of the gene sets for the enrichment analysis, with the number of the gene sets and the genes of each source. The gene sets are imported by `biomedgps-cli import-gene-sets`.
+    #[oai(
+        path = "/gene-set-sources",
+        method = "get",
+        tag = "ApiTags::KnowledgeGraph",
+        operation_id = "fetchGeneSetSources"
+    )]
+    async fn fetch_gene_set_sources(
+        &self,
+        pool: Data<&Arc<sqlx::PgPool>>,
+        _token: CustomSecurityScheme,
+    ) -> GetWholeTableResponse<GeneSetSource> {
+        let pool_arc = pool.clone();
+
+        match cached(&CacheScope::Metadata.key("gene-set-sources"), || {
+            GeneSet::get_sources(&pool_arc)
+        })
+        .await
         {
-            Ok(graph) => GetGraphResponse::ok(graph.to_owned().get_graph(None).unwrap()),
+            Ok(sources) => GetWholeTableResponse::ok(sources),
             Err(e) => {
-                let err = format!("Failed to fetch linked nodes: {}", e);
+                let err = format!("Failed to fetch the gene set sources: {}", e);
                 warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+                GetWholeTableResponse::error(ApiError::from_error(err, &*e))
             }
         }
     }
 
-    /// Call `/api/v1/similarity-nodes` with query params to fetch similarity nodes.
+    /// Call `/api/v1/rank-entities` with query params to rank all entities of the target type for an anchor entity by the embedding model, such as `/api/v1/rank-entities?anchor_id=Disease::MONDO:0005147&target_type=Compound&relation_type=treats&topk=100` for repurposing the compounds to a disease.
+    ///
+    /// The `relation_type` is a full relation type (such as `DRUGBANK::treats::Compound:Disease`) or the name in the middle of the relation types (such as `treats`), the relation types of the model between the target type and the anchor type are matched in both directions, and the best score of them is used. The entities which are already connected with the anchor by the matched relation types are excluded unless `exclude_known` is false, each entity has the known flag and all relation types which connect it with the anchor as the known evidences. If `explain` is true, the top ranked entities (at most 20) are explained by the top `max_paths` (5 by default) paths within `max_hops` (2 or 3, 3 by default) hops between the anchor and the entity, such as `Compound -> Gene -> Disease`, each explanation is a small graph. The `model` is the embedding model, the latest registered model is used by default, and its algorithm (such as TransE_l2, DistMult, ComplEx and RotatE) decides the score function.
     #[oai(
-        path = "/similarity-nodes",
+        path = "/rank-entities",
         method = "get",
         tag = "ApiTags::KnowledgeGraph",
-        operation_id = "fetchSimilarityNodes"
+        operation_id = "fetchRankedEntities"
     )]
-    async fn fetch_similarity_nodes(
+    async fn fetch_ranked_entities(
         &self,
         pool: Data<&Arc<sqlx::PgPool>>,
-        node_id: Query<String>,
-        query_str: Query<Option<String>>,
+        anchor_id: Query<String>,
+        target_type: Query<String>,
+        relation_type: Query<String>,
         topk: Query<Option<u64>>,
+        exclude_known: Query<Option<bool>>,
+        model: Query<Option<String>>,
+        explain: Query<Option<bool>>,
+        max_hops: Query<Option<usize>>,
+        max_paths: Query<Option<usize>>,
         _token: CustomSecurityScheme,
-    ) -> GetGraphResponse {
+    ) -> GetEntityRankingResponse {
         let pool_arc = pool.clone();
 
-        match SimilarityNodeQuery::new(&node_id.0, &query_str.0, topk.0) {
-            Ok(query) => query,
-            Err(e) => {
-                let err = format!("Failed to parse query string: {}", e);
-                warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
-            }
-        };
+        if let Err(e) = NodeIdsQuery::new(&anchor_id.0) {
+            let err = format!("Failed to parse anchor id: {}", e);
+            warn!("{}", err);
+            return GetEntityRankingResponse::error(ApiError::from_error(err, &e));
+        }
 
-        let query_str = match query_str.0 {
-            Some(query_str) => query_str,
-            None => {
-                warn!("Query string is empty.");
-                "".to_string()
-            }
-        };
+        if anchor_id.0.contains(',') {
+            let err = format!("Only one anchor id is allowed, but got {}.", anchor_id.0);
+            warn!("{}", err);
+            return GetEntityRankingResponse::bad_request(err);
+        }
 
-        let topk = topk.0;
+        if !ENTITY_LABEL_REGEX.is_match(&target_type.0) {
+            let err = format!(
+                "Invalid target type: {}, it should be an entity type such as Compound.",
+                target_type.0
+            );
+            warn!("{}", err);
+            return GetEntityRankingResponse::bad_request(err);
+        }
 
-        let query = if query_str == "" {
-            None
-        } else {
-            debug!("Query string: {}", &query_str);
-            // Parse query string as json
-            match serde_json::from_str(&query_str) {
-                Ok(query) => Some(query),
-                Err(e) => {
-                    let err = format!("Failed to parse query string: {}", e);
-                    warn!("{}", err);
-                    return GetGraphResponse::bad_request(err);
-                }
+        let topk = topk.0.unwrap_or(100);
+        if topk == 0 || topk > get_max_page_size() {
+            let err = format!(
+                "The topk should be between 1 and {}, but got {}.",
+                get_max_page_size(),
+                topk
+            );
+            warn!("{}", err);
+            return GetEntityRankingResponse::bad_request(err);
+        }
+
+        let max_hops = max_hops.0.unwrap_or(MAX_PATH_HOPS);
+        if !(2..=MAX_PATH_HOPS).contains(&max_hops) {
+            let err = format!(
+                "The max_hops should be between 2 and {}, but got {}.",
+                MAX_PATH_HOPS, max_hops
+            );
+            warn!("{}", err);
+            return GetEntityRankingResponse::bad_request(err);
+        }
+
+        let max_paths = max_paths.0.unwrap_or(DEFAULT_MAX_PATHS);
+        if max_paths == 0 || max_paths as u64 > get_max_page_size() {
+            let err = format!(
+                "The max_paths should be between 1 and {}, but got {}.",
+                get_max_page_size(),
+                max_paths
+            );
+            warn!("{}", err);
+            return GetEntityRankingResponse::bad_request(err);
+        }
+
+        let exclude_known = exclude_known.0.unwrap_or(true);
+        let explain = explain.0.unwrap_or(false);
+        let cache_key = CacheScope::Expansion.key(&format!(
+            "rank-entities:{}:{}:{}:{}:{}:{}:{}",
+            anchor_id.0,
+            target_type.0,
+            relation_type.0,
+            topk,
+            exclude_known,
+            model.0.as_deref().unwrap_or_default(),
+            if explain {
+                format!("explain-{}-{}", max_hops, max_paths)
+            } else {
+                "".to_string()
             }
-        };
+        ));
+        match cached(&cache_key, || async {
+            let mut ranking = EntityRanking::run(
+                &pool_arc,
+                &anchor_id.0,
+                &target_type.0,
+                &relation_type.0,
+                topk as usize,
+                exclude_known,
+                model.0.as_deref(),
+            )
+            .await?;
 
-        let mut graph = Graph::new();
-        match graph
-            .fetch_similarity_nodes(&pool_arc, &node_id, &query, topk)
-            .await
+            if explain {
+                ranking.explain(&pool_arc, max_hops, max_paths).await?;
+            }
+            Ok::<_, anyhow::Error>(ranking)
+        })
+        .await
         {
-            Ok(graph) => GetGraphResponse::ok(graph.to_owned().get_graph(None).unwrap()),
+            Ok(ranking) => GetEntityRankingResponse::ok(ranking),
             Err(e) => {
-                let err = format!("Failed to fetch similarity nodes: {}", e);
+                let err = format!("Failed to rank the entities: {}", e);
                 warn!("{}", err);
-                return GetGraphResponse::bad_request(err);
+                GetEntityRankingResponse::error(ApiError::from_error(err, &*e))
+            }
+        }
+    }
+}
+
+/// Check whether the user has the required role in the workspace, the admins can access all workspaces.
+pub(crate) async fn check_workspace_role(
+    pool: &sqlx::PgPool,
+    user: &User,
+    workspace_id: i64,
+    required_role: WorkspaceRole,
+) -> Result<(), ApiError> {
+    if user.is_admin() {
+        return Ok(());
+    }
+
+    match WorkspaceMember::get_role(pool, workspace_id, &user.username).await {
+        Ok(Some(role)) if role >= required_role => Ok(()),
+        Ok(Some(role)) => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            format!(
+                "Permission denied, the {} role of the workspace {} is required but {} has the {} role.",
+                required_role.as_str(),
+                workspace_id,
+                user.username,
+                role.as_str()
+            ),
+        )),
+        Ok(None) => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            format!(
+                "{} is not a member of the workspace {}.",
+                user.username, workspace_id
+            ),
+        )),
+        Err(e) => Err(ApiError::from_error(
+            format!("Failed to fetch the workspace role: {}", e),
+            &*e,
+        )),
+    }
+}
+
+/// The ids of the workspaces which the user is a member of, None for the admins because they can read the records of all workspaces.
+async fn member_workspace_ids(
+    pool: &sqlx::PgPool,
+    user: &User,
+) -> Result<Option<Vec<i64>>, ApiError> {
+    if user.is_admin() {
+        return Ok(None);
+    }
+
+    match Workspace::get_records(pool, &user.username, false).await {
+        Ok(workspaces) => Ok(Some(workspaces.iter().map(|w| w.id).collect())),
+        Err(e) => Err(ApiError::from_error(
+            format!("Failed to fetch the workspaces: {}", e),
+            &*e,
+        )),
+    }
+}
+
+/// Keep the personal records and the records of the workspaces which the user is a member of, it is used when the records are listed without a workspace_id. The admins can list the records of all workspaces.
+pub(crate) async fn merge_member_workspaces(
+    pool: &sqlx::PgPool,
+    user: &User,
+    query: Option<ComposeQuery>,
+) -> Result<Option<ComposeQuery>, ApiError> {
+    match member_workspace_ids(pool, user).await? {
+        Some(workspace_ids) => Ok(Some(Workspace::merge_member_query(&workspace_ids, query))),
+        None => Ok(query),
+    }
+}
+
+/// Fetch the ancestors (upward) or the descendants of an entity for the ontology endpoints, they are cached with the entities.
+async fn fetch_ontology_lineage(
+    pool: &Arc<sqlx::PgPool>,
+    label: &str,
+    id: &str,
+    max_depth: Option<usize>,
+    upward: bool,
+) -> GetWholeTableResponse<OntologyNode> {
+    let max_depth = max_depth.unwrap_or(DEFAULT_ONTOLOGY_DEPTH);
+    if max_depth == 0 || max_depth > MAX_ONTOLOGY_DEPTH {
+        let err = format!(
+            "Invalid max_depth: {}, it should be between 1 and {}.",
+            max_depth, MAX_ONTOLOGY_DEPTH
+        );
+        warn!("{}", err);
+        return GetWholeTableResponse::bad_request(err);
+    }
+
+    let direction = if upward { "ancestors" } else { "descendants" };
+    let key = format!("{}:{}:{}", Node::format_id(label, id), direction, max_depth);
+    let nodes = cached(&CacheScope::Entity.key(&key), || async {
+        if upward {
+            OntologyNode::get_ancestors(pool, label, id, max_depth).await
+        } else {
+            OntologyNode::get_descendants(pool, label, id, max_depth).await
+        }
+    })
+    .await;
+
+    match nodes {
+        Ok(nodes) => GetWholeTableResponse::ok(nodes),
+        Err(e) => {
+            let err = format!(
+                "Failed to fetch the {} of {}::{}: {}",
+                direction, label, id, e
+            );
+            warn!("{}", err);
+            GetWholeTableResponse::error(ApiError::from_error(err, &*e))
+        }
+    }
+}
+
+/// Fetch the controlled vocabularies from the cache or the metadata tables. The validation is skipped if they cannot be fetched, so the errors are only logged.
+async fn fetch_graph_schema(pool: &sqlx::PgPool) -> Option<GraphSchema> {
+    match cached(&CacheScope::Metadata.key("schema"), || {
+        GraphSchema::get(pool)
+    })
+    .await
+    {
+        Ok(schema) => Some(schema),
+        Err(e) => {
+            warn!(
+                "Failed to fetch schema, the vocabularies are not validated: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Check the fields, operators and nesting depth of the query filters against the table, see [`CheckData::check_query`]. The errors of all query items are in the details, such as `[{"path": "items[0]", "field": "nmae", "message": "..."}]`.
+fn check_query_fields<S: CheckData>(query: &ComposeQuery) -> Result<(), ApiError> {
+    S::check_query(query).map_err(|errors| {
+        let err = format!(
+            "Failed to validate query string: {}",
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        );
+        ApiError::new(ErrorCode::ValidationError, err).with_details(serde_json::json!(errors))
+    })
+}
+
+/// Parse the query of a saved query and check it against the target table, see [`check_query_fields`].
+fn check_saved_query(saved_query: &SavedQuery) -> Result<(), ApiError> {
+    let query = saved_query
+        .get_query()
+        .map_err(|e| ApiError::from_error(format!("Failed to parse the query: {}", e), &*e))?;
+    let table = saved_query
+        .get_table()
+        .map_err(|e| ApiError::from_error(format!("Failed to parse the table: {}", e), &e))?;
+
+    match table {
+        TableName::Entity => check_query_fields::<Entity>(&query),
+        TableName::Relation => check_query_fields::<Relation>(&query),
+        TableName::Entity2D => check_query_fields::<Entity2D>(&query),
+        TableName::KnowledgeCuration => check_query_fields::<KnowledgeCuration>(&query),
+        TableName::Subgraph => check_query_fields::<Subgraph>(&query),
+        TableName::EntityEmbedding => check_query_fields::<EntityEmbedding>(&query),
+    }
+}
+
+/// Check the labels and relation types in the query filters against the controlled vocabularies.
+async fn check_query_vocabulary(pool: &sqlx::PgPool, query: &ComposeQuery) -> Result<(), ApiError> {
+    match fetch_graph_schema(pool).await {
+        Some(schema) => schema.check_query(query).map_err(|e| {
+            ApiError::from_error(format!("Failed to validate query string: {}", e), &e)
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Expand the entity ids in the query filters by their descendants in the imported ontologies, for the `expand_descendants` parameter.
+async fn expand_query_descendants(
+    pool: &sqlx::PgPool,
+    query: &ComposeQuery,
+    max_depth: Option<usize>,
+) -> Result<ComposeQuery, ApiError> {
+    let max_depth = max_depth.unwrap_or(DEFAULT_ONTOLOGY_DEPTH);
+    expand_descendants(pool, query, max_depth)
+        .await
+        .map_err(|e| {
+            ApiError::from_error(
+                format!("Failed to expand the query by the descendants: {}", e),
+                &*e,
+            )
+        })
+}
+
+/// Parse the locales of the entity names by the `locale` param and the `Accept-Language` header, see [`parse_locales`].
+fn parse_request_locales(
+    locale: &Option<String>,
+    accept_language: &Option<String>,
+) -> Result<Vec<String>, ApiError> {
+    parse_locales(locale.as_deref(), accept_language.as_deref())
+        .map_err(|e| ApiError::from_error(format!("Failed to parse the locale: {}", e), &e))
+}
+
+/// Translate the names of the nodes by the locales. The default names are kept if the translations cannot be fetched, so the errors are only logged.
+async fn translate_graph(pool: &sqlx::PgPool, locales: &[String], graph: &mut Graph) {
+    if locales.is_empty() {
+        return;
+    }
+
+    if let Err(e) = graph.translate_names(pool, locales).await {
+        warn!("Failed to translate the names of the nodes: {}", e);
+    }
+}
+
+/// Merge the equivalent nodes of the graph by the id mapping, see [`Graph::merge_equivalent_nodes`]. The graph is returned unmerged if the mapping cannot be fetched, so the errors are only logged.
+async fn merge_graph_nodes(pool: &sqlx::PgPool, graph: &mut Graph) {
+    if let Err(e) = graph.merge_equivalent_nodes(pool).await {
+        warn!("Failed to merge the equivalent nodes: {}", e);
+    }
+}
+
+/// Attach the ids and the open flags of the relations to the edges, see [`Graph::fetch_flag_counts`]. The edges are returned without the flags if they cannot be fetched, so the errors are only logged.
+async fn count_graph_flags(pool: &sqlx::PgPool, graph: &mut Graph) {
+    if let Err(e) = graph.fetch_flag_counts(pool).await {
+        warn!("Failed to fetch the flags of the edges: {}", e);
+    }
+}
+
+/// Translate the names of the entities by the locales, same as [`translate_graph`].
+async fn translate_entities(pool: &sqlx::PgPool, locales: &[String], entities: &mut [Entity]) {
+    if locales.is_empty() || entities.is_empty() {
+        return;
+    }
+
+    let keys = entities
+        .iter()
+        .map(|entity| (entity.label.clone(), entity.id.clone()))
+        .collect::<Vec<(String, String)>>();
+    match EntityTranslation::get_names(pool, locales, &keys).await {
+        Ok(names) => {
+            for (entity, key) in entities.iter_mut().zip(keys.iter()) {
+                if let Some(name) = names.get(key) {
+                    entity.name = name.clone();
+                }
             }
         }
+        Err(e) => warn!("Failed to translate the names of the entities: {}", e),
+    }
+}
+
+/// Check the relation type and the entity types of a curated knowledge against the controlled vocabularies.
+async fn check_curation_vocabulary(
+    pool: &sqlx::PgPool,
+    curation: &KnowledgeCuration,
+) -> Result<(), ApiError> {
+    match fetch_graph_schema(pool).await {
+        Some(schema) => schema
+            .check_relation(
+                &curation.relation_type,
+                &curation.source_type,
+                &curation.target_type,
+            )
+            .map_err(|e| ApiError::from_error(format!("Failed to validate payload: {}", e), &e)),
+        None => Ok(()),
+    }
+}
+
+/// Validate a new or updated relation type and check it against the registered relation types, the relation type with the id is replaced by the payload when it is updated.
+async fn check_relation_type(
+    pool: &sqlx::PgPool,
+    definition: &RelationTypeDefinition,
+    id: Option<i64>,
+) -> Result<(), ApiError> {
+    if let Err(e) = definition.validate() {
+        let err = format!("Failed to validate payload: {}", e);
+        return Err(ApiError::from_error(err, &e));
+    }
+
+    let mut definitions = RelationTypeDefinition::get_records(pool)
+        .await
+        .map_err(|e| {
+            let err = format!("Failed to fetch the relation types: {}", e);
+            ApiError::from_error(err, &*e)
+        })?;
+    definitions.retain(|d| Some(d.id) != id && d.relation_type != definition.relation_type);
+    definitions.push(definition.clone());
+    RelationTypeRegistry::new(definitions)
+        .check(definition)
+        .map_err(|e| ApiError::from_error(format!("Invalid relation type: {}", e), &e))
+}
+
+/// The graph theme and the relation types are process-wide, they are loaded from the default database, so they can only be changed in the default database.
+fn check_default_database(setting: &str) -> Result<(), ApiError> {
+    match current_database() {
+        Some(name) if name != DEFAULT_DATABASE => Err(ApiError::new(
+            ErrorCode::BadRequest,
+            format!(
+                "The {} can only be changed in the default database, not in the database {}.",
+                setting, name
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reload the relation type registry after the relation types are changed, and drop the cached graphs which have the previous edge labels.
+async fn reload_relation_types(pool: &sqlx::PgPool) {
+    if let Err(e) = load_relation_types(pool).await {
+        warn!("Failed to reload the relation types: {}", e);
     }
+    invalidate_cache(&CacheScope::all()).await;
+    invalidate_materialized_graphs(pool).await;
 }
 
 #[cfg(test)]
@@ -1241,6 +6573,22 @@ mod tests {
         app
     }
 
+    #[test]
+    fn test_records_response_spec() {
+        let service = OpenApiService::new(BiomedgpsApi, "BioMedGPS", "v0.1.0");
+        let spec: serde_json::Value = serde_json::from_str(&service.spec()).unwrap();
+
+        // The JSON, NDJSON and CSV records are the content types of one 200 response.
+        let content = &spec["paths"]["/api/v1/entities"]["get"]["responses"]["200"]["content"];
+        for content_type in ["application/json", "application/x-ndjson", "text/csv"] {
+            assert!(content.get(content_type).is_some(), "{}", content_type);
+        }
+        let schema = content["application/json"]["schema"]["$ref"]
+            .as_str()
+            .unwrap();
+        assert!(schema.contains("RecordsPayload"), "{}", schema);
+    }
+
     #[tokio::test]
     async fn test_fetch_entities() {
         let app = init_app().await;
@@ -1321,6 +6669,130 @@ mod tests {
         assert!(entity_records.records.len() == 0);
     }
 
+    #[tokio::test]
+    async fn test_list_workspace_records_as_non_member() {
+        let pool = setup_test_db().await;
+        let workspace = Workspace {
+            id: 0,
+            name: "test-workspace-isolation".to_string(),
+            description: None,
+            owner: "test-workspace-owner".to_string(),
+            created_at: chrono::Utc::now(),
+        }
+        .insert(&pool)
+        .await
+        .unwrap();
+
+        let saved_query = SavedQuery {
+            id: 0,
+            name: "test-workspace-isolation".to_string(),
+            description: None,
+            target_table: "entity".to_string(),
+            query: r#"{"operator": "=", "field": "id", "value": "DOID:2022"}"#.to_string(),
+            order_by: None,
+            owner: "test-workspace-owner".to_string(),
+            shared: true,
+            workspace_id: Some(workspace.id),
+            created_at: chrono::Utc::now(),
+        }
+        .insert(&pool)
+        .await
+        .unwrap();
+
+        let non_member = User {
+            username: "test-non-member".to_string(),
+            organizations: vec![-1],
+            projects: vec![-1],
+            role: Role::Curator,
+        };
+
+        // The non-member lists the records without the workspace_id.
+        let saved_queries = SavedQuery::get_records(&pool, &non_member.username, None, false)
+            .await
+            .unwrap();
+        assert!(saved_queries.iter().all(|q| q.id != saved_query.id));
+
+        let query = merge_member_workspaces(&pool, &non_member, None)
+            .await
+            .unwrap();
+        let subgraphs = RecordResponse::<Subgraph>::get_records_with_count(
+            &pool,
+            TableName::Subgraph,
+            &query,
+            Some(1),
+            Some(100),
+            Some("id ASC"),
+            CountStrategy::Exact,
+        )
+        .await
+        .unwrap();
+        assert!(subgraphs.records.iter().all(|s| s.workspace_id.is_none()));
+
+        // The owner is a member, so the shared saved query is listed.
+        let saved_queries = SavedQuery::get_records(&pool, &workspace.owner, None, false)
+            .await
+            .unwrap();
+        assert!(saved_queries.iter().any(|q| q.id == saved_query.id));
+
+        sqlx::query("DELETE FROM biomedgps_saved_query WHERE id = $1")
+            .bind(saved_query.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM biomedgps_workspace_member WHERE workspace_id = $1")
+            .bind(workspace.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM biomedgps_workspace WHERE id = $1")
+            .bind(workspace.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_dataset_version_diff_with_invalid_query() {
+        let app = init_app().await;
+        let cli = TestClient::new(app);
+
+        for query_json_str in [
+            r#"{"operator": "=", "field": "id = id OR 1", "value": "1"}"#,
+            r#"{"operator": "= 1 OR id =", "field": "id", "value": "1"}"#,
+        ] {
+            let query_str = kv2urlstr("query_str", &query_json_str.to_string());
+            let resp = cli
+                .get(format!(
+                    "/api/v1/dataset-versions/diff?from=v1&{}",
+                    query_str
+                ))
+                .send()
+                .await;
+            resp.assert_status(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_default_database() {
+        use crate::model::database::DATABASE;
+
+        assert!(check_default_database("graph theme").is_ok());
+        let result = DATABASE
+            .scope(DEFAULT_DATABASE.to_string(), async {
+                check_default_database("graph theme")
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let err = DATABASE
+            .scope("staging".to_string(), async {
+                check_default_database("relation types")
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::BadRequest);
+    }
+
     #[tokio::test]
     async fn test_fetch_similarity_nodes() {
         let app = init_app().await;
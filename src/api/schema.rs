@@ -1,24 +1,173 @@
 use std::collections::HashMap;
 
-use crate::model::core::{RecordResponse, Statistics, RelationCount};
-use crate::model::core::{JSON_REGEX, SUBGRAPH_UUID_REGEX};
-use crate::model::graph::Graph;
-use crate::model::graph::{COMPOSED_ENTITIES_REGEX, COMPOSED_ENTITY_REGEX};
+use crate::api::middleware::current_request_id;
+use crate::config::ConfigStatus;
+use crate::model::annotation::EntityAnnotations;
+use crate::model::core::{
+    AggregateRecord, DatasetVersionDiff, DeletionResult, Entity2D, Entity2DMap, EntityDetail,
+    ProjectedRecordResponse, RecordFormat, RecordResponse, RelationCount, RelationEvidence,
+    RelationExistence, RelationTriple, SavedQueryResult, Statistics, Subgraph,
+};
+use crate::model::core::{ENTITY_LABEL_REGEX, JSON_REGEX, SUBGRAPH_UUID_REGEX};
+use crate::model::database::DatabaseInfo;
+use crate::model::embedding::{BatchSimilarity, EmbeddingOpsResult, EntityRanking};
+use crate::model::enrichment::EnrichmentResult;
+use crate::model::flag::{FlagReason, FlagStatus};
+use crate::model::gene::GeneResolution;
+use crate::model::graph::ValidationError as GraphValidationError;
+use crate::model::graph::{validate_node_id, Graph, GraphPayloadDiff, NodeId};
+use crate::model::materialize::{MaterializationInvalidation, MaterializedGraph};
+use crate::model::publication::Publication;
+use crate::model::rdf::RdfFormat;
+use crate::model::relation_type::{RelationTypeDefinition, RelationTypeGroup};
+use crate::model::scheduler::MaintenanceTask;
+use crate::model::split::RelationSplit;
+use crate::model::structure::{
+    validate_smiles, StructureSearchMode, StructureSearchResult, MAX_SEARCH_LIMIT,
+};
+use crate::model::theme::GraphTheme;
+use crate::model::util::get_max_page_size;
+use crate::model::vocabulary::GraphSchema;
+use crate::model::workspace::WorkspaceRole;
+use chrono::{DateTime, Utc};
+use futures::Stream;
 use log::{debug, info, warn};
-use poem_openapi::Object;
-use poem_openapi::{payload::Json, ApiResponse, Tags};
+use poem::http::StatusCode;
+use poem::Body;
+use poem_openapi::payload::{Binary, Json};
+use poem_openapi::{ApiResponse, ResponseContent, Tags};
+use poem_openapi::{Enum, Object, Union};
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use validator::Validate;
-use validator::ValidationErrors;
+use validator::{ValidationError, ValidationErrors};
 
 #[derive(Tags)]
 pub enum ApiTags {
     KnowledgeGraph,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
-pub struct ErrorMessage {
-    msg: String,
+/// The machine-readable code of a failed request, the clients can decide how to handle the error by the code instead of the message.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request is invalid, such as a malformed query string.
+    BadRequest,
+    /// The request doesn't pass the validation, such as an invalid id or an out-of-range value.
+    ValidationError,
+    Forbidden,
+    NotFound,
+    /// The request conflicts with the existing records, such as a duplicated key.
+    Conflict,
+    /// The request must be conditional, such as an update without the If-Match header.
+    PreconditionRequired,
+    TooManyRequests,
+    /// The database fails to execute a valid request.
+    DatabaseError,
+    /// An upstream service (such as PubMed or the LLM service) fails.
+    UpstreamError,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest | ErrorCode::ValidationError => StatusCode::BAD_REQUEST,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::DatabaseError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::UpstreamError => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Decide the code by the error which causes the failure (or its sources). The unknown errors are treated as bad requests.
+    pub fn from_error(error: &(dyn Error + 'static)) -> ErrorCode {
+        let mut source = Some(error);
+        while let Some(e) = source {
+            if let Some(e) = e.downcast_ref::<sqlx::Error>() {
+                return match e {
+                    sqlx::Error::RowNotFound => ErrorCode::NotFound,
+                    // More details on https://www.postgresql.org/docs/current/errcodes-appendix.html
+                    sqlx::Error::Database(db_error) => match db_error.code() {
+                        // Integrity constraint violation, such as unique_violation.
+                        Some(code) if code.starts_with("23") => ErrorCode::Conflict,
+                        // Data exception, such as invalid_text_representation.
+                        Some(code) if code.starts_with("22") => ErrorCode::ValidationError,
+                        // Syntax error or access rule violation, the sql is built from the query string.
+                        Some(code) if code.starts_with("42") => ErrorCode::BadRequest,
+                        _ => ErrorCode::DatabaseError,
+                    },
+                    _ => ErrorCode::DatabaseError,
+                };
+            }
+
+            if e.is::<ValidationErrors>()
+                || e.is::<crate::model::core::ValidationError>()
+                || e.is::<crate::model::graph::ValidationError>()
+            {
+                return ErrorCode::ValidationError;
+            }
+
+            if e.is::<reqwest::Error>() {
+                return ErrorCode::UpstreamError;
+            }
+
+            if e.is::<neo4rs::Error>() {
+                return ErrorCode::DatabaseError;
+            }
+
+            source = e.source();
+        }
+
+        ErrorCode::BadRequest
+    }
+}
+
+/// The error of a failed request, such as `{"code": "not_found", "message": "...", "request_id": "..."}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The details of the error, such as the invalid ids.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[oai(skip_serializing_if_is_none)]
+    pub details: Option<serde_json::Value>,
+    /// The id of the request, it is also in the logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[oai(skip_serializing_if_is_none)]
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: String) -> Self {
+        let request_id = current_request_id();
+        debug!(
+            "[{}] {:?}: {}",
+            request_id.as_deref().unwrap_or("-"),
+            code,
+            message
+        );
+
+        ApiError {
+            code,
+            message,
+            details: None,
+            request_id,
+        }
+    }
+
+    /// Create an error whose code is decided by the error which causes the failure, see [`ErrorCode::from_error`].
+    pub fn from_error(message: String, error: &(dyn Error + 'static)) -> Self {
+        Self::new(ErrorCode::from_error(error), message)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
 }
 
 #[derive(ApiResponse)]
@@ -27,10 +176,13 @@ pub enum GetGraphResponse {
     Ok(Json<Graph>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
 impl GetGraphResponse {
@@ -38,12 +190,20 @@ impl GetGraphResponse {
         Self::Ok(Json(graph))
     }
 
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
     }
 }
 
@@ -53,10 +213,13 @@ pub enum GetEntityColorMapResponse {
     Ok(Json<HashMap<String, String>>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
 impl GetEntityColorMapResponse {
@@ -64,12 +227,277 @@ impl GetEntityColorMapResponse {
         Self::Ok(Json(h))
     }
 
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+/// The response of the graph theme, it is the empty theme if no theme has been saved.
+#[derive(ApiResponse)]
+pub enum GraphThemeResponse {
+    #[oai(status = 200)]
+    Ok(Json<GraphTheme>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GraphThemeResponse {
+    pub fn ok(theme: GraphTheme) -> Self {
+        Self::Ok(Json(theme))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+}
+
+/// The response of the imported relation types, it contains all the registered relation types after the import.
+#[derive(ApiResponse)]
+pub enum ImportRelationTypesResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<RelationTypeDefinition>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors, such as validation error (422) and database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl ImportRelationTypesResponse {
+    pub fn ok(definitions: Vec<RelationTypeDefinition>) -> Self {
+        Self::Ok(Json(definitions))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+}
+
+/// The response of the groups of the registered relation types.
+#[derive(ApiResponse)]
+pub enum GetRelationTypeGroupsResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<RelationTypeGroup>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetRelationTypeGroupsResponse {
+    pub fn ok(groups: Vec<RelationTypeGroup>) -> Self {
+        Self::Ok(Json(groups))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+}
+
+/// The response of the databases which are served by the server.
+#[derive(ApiResponse)]
+pub enum GetDatabasesResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<DatabaseInfo>>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetDatabasesResponse {
+    pub fn ok(databases: Vec<DatabaseInfo>) -> Self {
+        Self::Ok(Json(databases))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        Self::Error(err.code.status(), Json(err))
+    }
+}
+
+/// A setting of the server, the secrets (such as jwt_secret_key) and the passwords in the urls are masked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct ConfigSetting {
+    pub name: String,
+    pub value: String,
+}
+
+/// The active settings of the server, see [`crate::config::ConfigStatus`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct ConfigReport {
+    /// The settings which are set, the others use the default values.
+    pub settings: Vec<ConfigSetting>,
+    /// The settings which were changed by the last reload.
+    pub changed: Vec<String>,
+    /// The changed settings which only take effect after a restart, such as database_url.
+    pub restart_required: Vec<String>,
+    #[oai(skip_serializing_if_is_none)]
+    pub reloaded_at: Option<DateTime<Utc>>,
+}
+
+impl ConfigReport {
+    pub fn new(status: &ConfigStatus) -> Self {
+        ConfigReport {
+            settings: status
+                .config
+                .summary()
+                .into_iter()
+                .map(|(name, value)| ConfigSetting { name, value })
+                .collect(),
+            changed: status.changed.clone(),
+            restart_required: status.restart_required.clone(),
+            reloaded_at: status.reloaded_at,
+        }
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum ConfigReportResponse {
+    #[oai(status = 200)]
+    Ok(Json<ConfigReport>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors.
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl ConfigReportResponse {
+    pub fn ok(report: ConfigReport) -> Self {
+        Self::Ok(Json(report))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetMaterializedGraphsResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<MaterializedGraph>>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetMaterializedGraphsResponse {
+    pub fn ok(records: Vec<MaterializedGraph>) -> Self {
+        Self::Ok(Json(records))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum InvalidateMaterializedGraphsResponse {
+    #[oai(status = 200)]
+    Ok(Json<MaterializationInvalidation>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl InvalidateMaterializedGraphsResponse {
+    pub fn ok(invalidation: MaterializationInvalidation) -> Self {
+        Self::Ok(Json(invalidation))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetMaintenanceTasksResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<MaintenanceTask>>),
+}
+
+impl GetMaintenanceTasksResponse {
+    pub fn ok(tasks: Vec<MaintenanceTask>) -> Self {
+        Self::Ok(Json(tasks))
     }
 }
 
@@ -79,10 +507,13 @@ pub enum GetRelationCountResponse {
     Ok(Json<Vec<RelationCount>>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
 impl GetRelationCountResponse {
@@ -90,206 +521,1112 @@ impl GetRelationCountResponse {
         Self::Ok(Json(relation_counts))
     }
 
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
     }
 }
 
-
 #[derive(ApiResponse)]
 pub enum GetStatisticsResponse {
     #[oai(status = 200)]
     Ok(Json<Statistics>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetStatisticsResponse {
+    pub fn ok(statistics: Statistics) -> Self {
+        Self::Ok(Json(statistics))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetEntityDetailResponse {
+    #[oai(status = 200)]
+    Ok(Json<EntityDetail>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEntityDetailResponse {
+    pub fn ok(entity_detail: EntityDetail) -> Self {
+        Self::Ok(Json(entity_detail))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetEntityAnnotationsResponse {
+    #[oai(status = 200)]
+    Ok(Json<EntityAnnotations>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEntityAnnotationsResponse {
+    pub fn ok(annotations: EntityAnnotations) -> Self {
+        Self::Ok(Json(annotations))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetRelationEvidenceResponse {
+    #[oai(status = 200)]
+    Ok(Json<RelationEvidence>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetRelationEvidenceResponse {
+    pub fn ok(relation_evidence: RelationEvidence) -> Self {
+        Self::Ok(Json(relation_evidence))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetPublicationResponse {
+    #[oai(status = 200)]
+    Ok(Json<Publication>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetPublicationResponse {
+    pub fn ok(publication: Publication) -> Self {
+        Self::Ok(Json(publication))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum RunSavedQueryResponse {
+    #[oai(status = 200)]
+    Ok(Json<SavedQueryResult>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl RunSavedQueryResponse {
+    pub fn ok(result: SavedQueryResult) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetAggregateResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<AggregateRecord>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetAggregateResponse {
+    pub fn ok(records: Vec<AggregateRecord>) -> Self {
+        Self::Ok(Json(records))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetEmbeddingOpsResponse {
+    #[oai(status = 200)]
+    Ok(Json<EmbeddingOpsResult>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEmbeddingOpsResponse {
+    pub fn ok(result: EmbeddingOpsResult) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetBatchSimilarityResponse {
+    #[oai(status = 200)]
+    Ok(Json<BatchSimilarity>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetBatchSimilarityResponse {
+    pub fn ok(result: BatchSimilarity) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetStructureSearchResponse {
+    #[oai(status = 200)]
+    Ok(Json<StructureSearchResult>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetStructureSearchResponse {
+    pub fn ok(result: StructureSearchResult) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetGeneResolutionResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<GeneResolution>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetGeneResolutionResponse {
+    pub fn ok(results: Vec<GeneResolution>) -> Self {
+        Self::Ok(Json(results))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+/// The graph which is built from a list of genes or compounds, with the resolutions of the identifiers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct GraphFromList {
+    pub graph: Graph,
+    /// The resolution of each identifier, the best matches are the nodes of the graph.
+    pub resolutions: Vec<GeneResolution>,
+    /// The identifiers which are not resolved to any node.
+    pub unresolved: Vec<String>,
+    /// The id of the saved subgraph, it is only set if `subgraph_name` is given.
+    #[oai(skip_serializing_if_is_none)]
+    pub subgraph_id: Option<String>,
+}
+
+#[derive(ApiResponse)]
+pub enum GetGraphFromListResponse {
+    #[oai(status = 200)]
+    Ok(Json<GraphFromList>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as forbidden (403), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetGraphFromListResponse {
+    pub fn ok(result: GraphFromList) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetRelationExistenceResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<RelationExistence>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetRelationExistenceResponse {
+    pub fn ok(results: Vec<RelationExistence>) -> Self {
+        Self::Ok(Json(results))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetEnrichmentResponse {
+    #[oai(status = 200)]
+    Ok(Json<EnrichmentResult>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEnrichmentResponse {
+    pub fn ok(result: EnrichmentResult) -> Self {
+        Self::Ok(Json(result))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetEntityRankingResponse {
+    #[oai(status = 200)]
+    Ok(Json<EntityRanking>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEntityRankingResponse {
+    pub fn ok(ranking: EntityRanking) -> Self {
+        Self::Ok(Json(ranking))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetWholeTableResponse<
+    T: Serialize
+        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync
+        + poem_openapi::types::Type
+        + poem_openapi::types::ParseFromJSON
+        + poem_openapi::types::ToJSON,
+> {
+    #[oai(status = 200)]
+    Ok(Json<Vec<T>>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl<
+        T: Serialize
+            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+            + std::fmt::Debug
+            + std::marker::Unpin
+            + Send
+            + Sync
+            + poem_openapi::types::Type
+            + poem_openapi::types::ParseFromJSON
+            + poem_openapi::types::ToJSON,
+    > GetWholeTableResponse<T>
+{
+    pub fn ok(vec_t: Vec<T>) -> Self {
+        Self::Ok(Json(vec_t))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+/// The records of a page, or only the columns in the `fields` parameter, which is only supported by some endpoints.
+#[derive(Union)]
+#[oai(one_of)]
+pub enum RecordsPayload<
+    S: Serialize
+        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync
+        + poem_openapi::types::Type
+        + poem_openapi::types::ParseFromJSON
+        + poem_openapi::types::ToJSON,
+> {
+    Full(RecordResponse<S>),
+    Projected(ProjectedRecordResponse),
+}
+
+/// The records as JSON, or all the matched records as NDJSON or CSV, which are only returned by the endpoints which support the `format` parameter. The responses are keyed by the status in the OpenAPI document, so they are the content types of one response.
+#[derive(ResponseContent)]
+pub enum RecordsContent<
+    S: Serialize
+        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync
+        + poem_openapi::types::Type
+        + poem_openapi::types::ParseFromJSON
+        + poem_openapi::types::ToJSON,
+> {
+    Json(Json<RecordsPayload<S>>),
+    #[oai(content_type = "application/x-ndjson")]
+    Ndjson(Binary<Body>),
+    #[oai(content_type = "text/csv")]
+    Csv(Binary<Body>),
+}
+
+#[derive(ApiResponse)]
+pub enum GetRecordsResponse<
+    S: Serialize
+        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync
+        + poem_openapi::types::Type
+        + poem_openapi::types::ParseFromJSON
+        + poem_openapi::types::ToJSON,
+> {
+    #[oai(status = 200)]
+    Ok(RecordsContent<S>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl<
+        S: Serialize
+            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+            + std::fmt::Debug
+            + std::marker::Unpin
+            + Send
+            + Sync
+            + poem_openapi::types::Type
+            + poem_openapi::types::ParseFromJSON
+            + poem_openapi::types::ToJSON,
+    > GetRecordsResponse<S>
+{
+    pub fn ok(record_response: RecordResponse<S>) -> Self {
+        Self::Ok(RecordsContent::Json(Json(RecordsPayload::Full(
+            record_response,
+        ))))
+    }
+
+    pub fn projected(record_response: ProjectedRecordResponse) -> Self {
+        Self::Ok(RecordsContent::Json(Json(RecordsPayload::Projected(
+            record_response,
+        ))))
+    }
+
+    /// Stream the records in the body, the records are not buffered in the server.
+    pub fn stream(
+        records: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static,
+        format: RecordFormat,
+    ) -> Self {
+        let body = Binary(Body::from_bytes_stream(records));
+        match format {
+            RecordFormat::Csv => Self::Ok(RecordsContent::Csv(body)),
+            // The streamed JSON records are one per line.
+            RecordFormat::Ndjson | RecordFormat::Json => Self::Ok(RecordsContent::Ndjson(body)),
+        }
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetTriplesResponse {
+    /// The head, relation and tail of each triple separated by tabs, one triple per line without a header.
+    #[oai(status = 200)]
+    Ok(
+        Binary<Body>,
+        #[oai(header = "Content-Type")] String,
+        #[oai(header = "Content-Disposition")] String,
+    ),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+}
+
+impl GetTriplesResponse {
+    /// Stream the triples of the split as a file named by the split, such as `train.tsv`.
+    pub fn stream(
+        triples: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static,
+        split: RelationSplit,
+    ) -> Self {
+        Self::stream_file(triples, &format!("{}.tsv", split.as_str()))
+    }
+
+    /// Stream the negatives of the split as a file such as `train_negatives.tsv`.
+    pub fn stream_negatives(
+        negatives: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static,
+        split: RelationSplit,
+    ) -> Self {
+        Self::stream_file(negatives, &format!("{}_negatives.tsv", split.as_str()))
+    }
+
+    fn stream_file(
+        triples: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static,
+        filename: &str,
+    ) -> Self {
+        Self::Ok(
+            Binary(Body::from_bytes_stream(triples)),
+            "text/tab-separated-values".to_string(),
+            format!("attachment; filename=\"{}\"", filename),
+        )
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::BadRequest(Json(ApiError::new(ErrorCode::BadRequest, msg)))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum GetRdfResponse {
+    /// The entities and relations in Turtle, N-Triples or JSON-LD.
+    #[oai(status = 200)]
+    Ok(
+        Binary<Body>,
+        #[oai(header = "Content-Type")] String,
+        #[oai(header = "Content-Disposition")] String,
+    ),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+}
+
+impl GetRdfResponse {
+    /// Stream the graph as a file named by the format, such as `biomedgps.ttl`.
+    pub fn stream(
+        rdf: impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static,
+        format: RdfFormat,
+    ) -> Self {
+        Self::Ok(
+            Binary(Body::from_bytes_stream(rdf)),
+            format.content_type().to_string(),
+            format!("attachment; filename=\"biomedgps.{}\"", format.extension()),
+        )
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::BadRequest(Json(ApiError::new(ErrorCode::BadRequest, msg)))
+    }
+}
+
+/// The records of the entity2d table, only the columns in the `fields` parameter if it is specified, or the entities/clusters in the viewport of the 2D map if the `max_points` parameter is specified.
+#[derive(Union)]
+#[oai(one_of)]
+pub enum Entity2DPayload {
+    Full(RecordResponse<Entity2D>),
+    Projected(ProjectedRecordResponse),
+    Map(Entity2DMap),
+}
+
+#[derive(ApiResponse)]
+pub enum GetEntity2DResponse {
+    #[oai(status = 200)]
+    Ok(Json<Entity2DPayload>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl GetEntity2DResponse {
+    pub fn ok(record_response: RecordResponse<Entity2D>) -> Self {
+        Self::Ok(Json(Entity2DPayload::Full(record_response)))
+    }
+
+    pub fn projected(record_response: ProjectedRecordResponse) -> Self {
+        Self::Ok(Json(Entity2DPayload::Projected(record_response)))
+    }
+
+    pub fn map(map: Entity2DMap) -> Self {
+        Self::Ok(Json(Entity2DPayload::Map(map)))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum PostResponse<
+    S: Serialize
+        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync
+        + poem_openapi::types::Type
+        + poem_openapi::types::ParseFromJSON
+        + poem_openapi::types::ToJSON,
+> {
+    #[oai(status = 201)]
+    Created(Json<S>),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl<
+        S: Serialize
+            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+            + std::fmt::Debug
+            + std::marker::Unpin
+            + Send
+            + Sync
+            + poem_openapi::types::Type
+            + poem_openapi::types::ParseFromJSON
+            + poem_openapi::types::ToJSON,
+    > PostResponse<S>
+{
+    pub fn created(s: S) -> Self {
+        Self::Created(Json(s))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn bad_request(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+}
+
+/// The response of a subgraph update, the ETag header is the revision of the returned subgraph.
+#[derive(ApiResponse)]
+pub enum PutSubgraphResponse {
+    #[oai(status = 201)]
+    Created(Json<Subgraph>, #[oai(header = "ETag")] String),
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    #[oai(status = 404)]
+    NotFound(Json<ApiError>),
+
+    /// The subgraph has been updated by others since the revision in the If-Match header, the current subgraph is returned, so the client can merge the changes and retry with the new ETag.
+    #[oai(status = 409)]
+    Conflict(Json<Subgraph>, #[oai(header = "ETag")] String),
+
+    /// The If-Match header is missing.
+    #[oai(status = 428)]
+    PreconditionRequired(Json<ApiError>),
+
+    /// The other errors, such as database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
+}
+
+impl PutSubgraphResponse {
+    pub fn created(subgraph: Subgraph) -> Self {
+        let etag = subgraph.get_etag();
+        Self::Created(Json(subgraph), etag)
+    }
+
+    pub fn conflict(subgraph: Subgraph) -> Self {
+        let etag = subgraph.get_etag();
+        Self::Conflict(Json(subgraph), etag)
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            StatusCode::PRECONDITION_REQUIRED => Self::PreconditionRequired(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
+    }
+
+    pub fn not_found(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
+    }
+
+    pub fn precondition_required(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::PreconditionRequired, msg))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum DeleteResponse {
+    #[oai(status = 204)]
+    NoContent,
+
+    #[oai(status = 400)]
+    BadRequest(Json<ApiError>),
+
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
-impl GetStatisticsResponse {
-    pub fn ok(statistics: Statistics) -> Self {
-        Self::Ok(Json(statistics))
+impl DeleteResponse {
+    pub fn no_content() -> Self {
+        Self::NoContent
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
     }
 
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
+    }
+
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
     }
 }
 
 #[derive(ApiResponse)]
-pub enum GetWholeTableResponse<
-    T: Serialize
-        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-        + std::fmt::Debug
-        + std::marker::Unpin
-        + Send
-        + Sync
-        + poem_openapi::types::Type
-        + poem_openapi::types::ParseFromJSON
-        + poem_openapi::types::ToJSON,
-> {
+pub enum GetSubgraphDiffResponse {
     #[oai(status = 200)]
-    Ok(Json<Vec<T>>),
+    Ok(Json<GraphPayloadDiff>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
-impl<
-        T: Serialize
-            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-            + std::fmt::Debug
-            + std::marker::Unpin
-            + Send
-            + Sync
-            + poem_openapi::types::Type
-            + poem_openapi::types::ParseFromJSON
-            + poem_openapi::types::ToJSON,
-    > GetWholeTableResponse<T>
-{
-    pub fn ok(vec_t: Vec<T>) -> Self {
-        Self::Ok(Json(vec_t))
+impl GetSubgraphDiffResponse {
+    pub fn ok(diff: GraphPayloadDiff) -> Self {
+        Self::Ok(Json(diff))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
     }
 
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
     }
 }
 
 #[derive(ApiResponse)]
-pub enum GetRecordsResponse<
-    S: Serialize
-        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-        + std::fmt::Debug
-        + std::marker::Unpin
-        + Send
-        + Sync
-        + poem_openapi::types::Type
-        + poem_openapi::types::ParseFromJSON
-        + poem_openapi::types::ToJSON,
-> {
+pub enum GetDatasetVersionDiffResponse {
     #[oai(status = 200)]
-    Ok(Json<RecordResponse<S>>),
+    Ok(Json<DatasetVersionDiff>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
     #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    NotFound(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
-impl<
-        S: Serialize
-            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-            + std::fmt::Debug
-            + std::marker::Unpin
-            + Send
-            + Sync
-            + poem_openapi::types::Type
-            + poem_openapi::types::ParseFromJSON
-            + poem_openapi::types::ToJSON,
-    > GetRecordsResponse<S>
-{
-    pub fn ok(record_response: RecordResponse<S>) -> Self {
-        Self::Ok(Json(record_response))
+impl GetDatasetVersionDiffResponse {
+    pub fn ok(diff: DatasetVersionDiff) -> Self {
+        Self::Ok(Json(diff))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::NOT_FOUND => Self::NotFound(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
     }
 
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
     pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::NotFound, msg))
     }
 }
 
 #[derive(ApiResponse)]
-pub enum PostResponse<
-    S: Serialize
-        + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-        + std::fmt::Debug
-        + std::marker::Unpin
-        + Send
-        + Sync
-        + poem_openapi::types::Type
-        + poem_openapi::types::ParseFromJSON
-        + poem_openapi::types::ToJSON,
-> {
-    #[oai(status = 201)]
-    Created(Json<S>),
+pub enum GetGraphSchemaResponse {
+    #[oai(status = 200)]
+    Ok(Json<GraphSchema>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
-    #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    /// The other errors, such as database error (500).
+    Error(StatusCode, Json<ApiError>),
 }
 
-impl<
-        S: Serialize
-            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-            + std::fmt::Debug
-            + std::marker::Unpin
-            + Send
-            + Sync
-            + poem_openapi::types::Type
-            + poem_openapi::types::ParseFromJSON
-            + poem_openapi::types::ToJSON,
-    > PostResponse<S>
-{
-    pub fn created(s: S) -> Self {
-        Self::Created(Json(s))
-    }
-
-    pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+impl GetGraphSchemaResponse {
+    pub fn ok(schema: GraphSchema) -> Self {
+        Self::Ok(Json(schema))
     }
 
-    pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
     }
 }
 
 #[derive(ApiResponse)]
-pub enum DeleteResponse {
-    #[oai(status = 204)]
-    NoContent,
+pub enum BulkDeleteResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<DeletionResult>>),
 
     #[oai(status = 400)]
-    BadRequest(Json<ErrorMessage>),
+    BadRequest(Json<ApiError>),
 
-    #[oai(status = 404)]
-    NotFound(Json<ErrorMessage>),
+    #[oai(status = 403)]
+    Forbidden(Json<ApiError>),
+
+    /// The other errors, such as conflict (409), database error (500) and upstream error (502).
+    Error(StatusCode, Json<ApiError>),
 }
 
-impl DeleteResponse {
-    pub fn no_content() -> Self {
-        Self::NoContent
+impl BulkDeleteResponse {
+    pub fn ok(results: Vec<DeletionResult>) -> Self {
+        Self::Ok(Json(results))
+    }
+
+    pub fn error(err: ApiError) -> Self {
+        match err.code.status() {
+            StatusCode::BAD_REQUEST => Self::BadRequest(Json(err)),
+            StatusCode::FORBIDDEN => Self::Forbidden(Json(err)),
+            status => Self::Error(status, Json(err)),
+        }
     }
 
     pub fn bad_request(msg: String) -> Self {
-        Self::BadRequest(Json(ErrorMessage { msg }))
+        Self::error(ApiError::new(ErrorCode::BadRequest, msg))
     }
 
-    pub fn not_found(msg: String) -> Self {
-        Self::NotFound(Json(ErrorMessage { msg }))
+    pub fn forbidden(msg: String) -> Self {
+        Self::error(ApiError::new(ErrorCode::Forbidden, msg))
     }
 }
 
@@ -321,10 +1658,7 @@ impl SubgraphIdQuery {
 #[derive(Debug, Deserialize, Validate)]
 pub struct NodeIdQuery {
     /// The ID of the object.
-    #[validate(regex(
-        path = "COMPOSED_ENTITY_REGEX",
-        message = "Invalid node id, it must be composed of entity type, ::, and entity id. e.g. Disease::MESH:D001"
-    ))]
+    #[validate(custom = "validate_node_id")]
     pub node_id: String,
 }
 
@@ -343,22 +1677,17 @@ impl NodeIdQuery {
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize)]
 pub struct NodeIdsQuery {
-    /// The ID of the object.
-    #[validate(regex(
-        path = "COMPOSED_ENTITIES_REGEX",
-        message = "Invalid node ids, each node id in it must be composed of entity type, ::, and entity id. There is a comma between each node id. e.g. Disease::MESH:D001,Disease::MESH:D002"
-    ))]
-    pub node_ids: String,
+    /// The comma-separated node ids, such as `Disease::MESH:D001,Disease::MESH:D002`.
+    pub node_ids: Vec<NodeId>,
 }
 
 impl NodeIdsQuery {
-    pub fn new(node_ids: &str) -> Result<Self, ValidationErrors> {
-        let node_ids = node_ids.to_string();
-        let query = Self { node_ids };
-        match query.validate() {
-            Ok(_) => Ok(query),
+    /// Parse the comma-separated node ids, the error tells which ids are malformed and why, see [`NodeId::parse_list`].
+    pub fn new(node_ids: &str) -> Result<Self, GraphValidationError> {
+        match NodeId::parse_list(node_ids) {
+            Ok(node_ids) => Ok(Self { node_ids }),
             Err(e) => {
                 let err = format!("Invalid query: {}", e);
                 warn!("{}", err);
@@ -370,12 +1699,8 @@ impl NodeIdsQuery {
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct SimilarityNodeQuery {
-    /// The ID of the object.
-    #[validate(regex(
-        path = "COMPOSED_ENTITY_REGEX",
-        message = "Invalid node id, it must be composed of entity type, ::, and entity id. e.g. Disease::MESH:D001"
-    ))]
-    pub node_id: String,
+    /// The ID of the object, it is parsed before the other fields are validated.
+    pub node_id: NodeId,
 
     #[validate(regex(
         path = "JSON_REGEX",
@@ -389,6 +1714,20 @@ pub struct SimilarityNodeQuery {
         message = "Invalid threshold, it must be between 0 and 100"
     ))]
     pub topk: Option<u64>,
+
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Invalid entity type, its length must be between 1 and 64"
+    ))]
+    pub entity_type: Option<String>,
+
+    #[validate(range(
+        min = 0.0,
+        max = 1.0,
+        message = "Invalid min score, it must be between 0 and 1"
+    ))]
+    pub min_score: Option<f64>,
 }
 
 impl SimilarityNodeQuery {
@@ -396,11 +1735,27 @@ impl SimilarityNodeQuery {
         node_id: &str,
         query_str: &Option<String>,
         topk: Option<u64>,
+        entity_type: &Option<String>,
+        min_score: Option<f64>,
     ) -> Result<Self, ValidationErrors> {
+        let node_id = match node_id.parse::<NodeId>() {
+            Ok(node_id) => node_id,
+            Err(e) => {
+                let mut errors = ValidationErrors::new();
+                let mut err = ValidationError::new("node_id");
+                err.message = Some(std::borrow::Cow::from(e.to_string()));
+                errors.add("node_id", err);
+                warn!("Invalid query: {}", errors);
+                return Err(errors);
+            }
+        };
+
         let query = Self {
-            node_id: node_id.to_string(),
+            node_id,
             query_str: query_str.clone(),
             topk,
+            entity_type: entity_type.clone(),
+            min_score,
         };
 
         match query.validate() {
@@ -414,6 +1769,27 @@ impl SimilarityNodeQuery {
     }
 }
 
+/// Check whether the page size exceeds the max page size (see `set_max_page_size`), the request is rejected instead of being clamped silently.
+fn check_max_page_size(page_size: Option<u64>) -> Result<(), ValidationErrors> {
+    let max_page_size = get_max_page_size();
+    match page_size {
+        Some(page_size) if page_size > max_page_size => {
+            let mut error = ValidationError::new("max_page_size");
+            error.message = Some(
+                format!(
+                    "Invalid page size {}, it must be less than or equal to {}",
+                    page_size, max_page_size
+                )
+                .into(),
+            );
+            let mut errors = ValidationErrors::new();
+            errors.add("page_size", error);
+            Err(errors)
+        }
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct Pagination {
     #[validate(range(min = 1, message = "Invalid page number, it must be greater than 0"))]
@@ -424,29 +1800,21 @@ pub struct Pagination {
 }
 
 impl Pagination {
+    /// Check the page and the page size, the missing values are set to the default values (page 1 and 10 records per page).
     pub fn new(page: Option<u64>, page_size: Option<u64>) -> Result<Self, ValidationErrors> {
-        let pagination = match (page, page_size) {
-            (Some(page), Some(page_size)) => {
-                let p = Self {
-                    page: Some(page),
-                    page_size: Some(page_size),
-                };
-                match p.validate() {
-                    Ok(_) => p,
-                    Err(e) => {
-                        let err = format!("Invalid pagination: {}", e);
-                        warn!("{}", err);
-                        return Err(e);
-                    }
-                }
-            }
-            _ => Self {
-                page: Some(1),
-                page_size: Some(10),
-            },
+        let p = Self {
+            page: Some(page.unwrap_or(1)),
+            page_size: Some(page_size.unwrap_or(10)),
         };
 
-        Ok(pagination)
+        match p.validate().and_then(|_| check_max_page_size(p.page_size)) {
+            Ok(_) => Ok(p),
+            Err(e) => {
+                let err = format!("Invalid pagination: {}", e);
+                warn!("{}", err);
+                Err(e)
+            }
+        }
     }
 }
 
@@ -466,34 +1834,404 @@ pub struct PaginationQuery {
 }
 
 impl PaginationQuery {
+    /// Check the page, the page size and the query string, the missing page and page size are set to the default values (page 1 and 10 records per page).
     pub fn new(
         page: Option<u64>,
         page_size: Option<u64>,
         query_str: Option<String>,
     ) -> Result<Self, ValidationErrors> {
-        let pagination = match (page, page_size, query_str) {
-            (Some(page), Some(page_size), Some(query_str)) => {
-                let p = Self {
-                    page: Some(page),
-                    page_size: Some(page_size),
-                    query_str: Some(query_str),
-                };
-                match p.validate() {
-                    Ok(_) => p,
-                    Err(e) => {
-                        let err = format!("Invalid pagination query: {}", e);
-                        warn!("{}", err);
-                        return Err(e);
-                    }
-                }
-            }
-            _ => Self {
-                page: Some(1),
-                page_size: Some(10),
-                query_str: None,
-            },
+        let p = Self {
+            page: Some(page.unwrap_or(1)),
+            page_size: Some(page_size.unwrap_or(10)),
+            query_str,
         };
 
-        Ok(pagination)
+        match p.validate().and_then(|_| check_max_page_size(p.page_size)) {
+            Ok(_) => Ok(p),
+            Err(e) => {
+                let err = format!("Invalid pagination query: {}", e);
+                warn!("{}", err);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A read-only cypher query which will be executed against the neo4j database.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct GraphQuery {
+    /// A read-only cypher query, such as `MATCH (n:Gene)-[r]->(m) RETURN n, r, m LIMIT 10`. The nodes, relationships and paths in the returned columns will be converted to a graph.
+    #[validate(length(min = 1, message = "The query cannot be empty."))]
+    pub query: String,
+
+    /// The max number of rows to read. The default value and the upper limit is 1000.
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "Invalid max rows, it must be between 1 and 1000"
+    ))]
+    pub max_rows: Option<u64>,
+}
+
+/// Merge a duplicated entity into the surviving entity, both entities must have the same label.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct EntityMergeRequest {
+    /// The ID of the duplicated entity which will be removed. It is not checked by the entity id pattern, because the malformed ids (such as the ones with spaces) are also duplicates.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of source_id should be between 1 and 64."
+    ))]
+    pub source_id: String,
+
+    /// The ID of the surviving entity.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of target_id should be between 1 and 64."
+    ))]
+    pub target_id: String,
+
+    /// The label of the entities, such as Gene, Compound, Disease, etc.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of label should be between 1 and 64."
+    ))]
+    pub label: String,
+}
+
+/// Merge several subgraphs into a new subgraph, the nodes and edges are the union of the subgraphs.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct SubgraphMergeRequest {
+    /// The IDs of the subgraphs to merge, the first subgraph wins when a node or an edge is in several subgraphs.
+    #[validate(length(
+        min = 2,
+        max = 100,
+        message = "The number of subgraph ids should be between 2 and 100."
+    ))]
+    pub subgraph_ids: Vec<String>,
+
+    /// The name of the new subgraph.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of name should be between 1 and 64."
+    ))]
+    pub name: String,
+
+    /// The description of the new subgraph.
+    pub description: Option<String>,
+}
+
+/// Compute the cosine similarities between a set of nodes and the anchors, such as all nodes of a subgraph against a query node.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct BatchSimilarityRequest {
+    /// The node ids of the anchors, such as `Disease::MONDO:0005147`.
+    #[validate(length(
+        min = 1,
+        max = 10,
+        message = "The number of anchor ids should be between 1 and 10."
+    ))]
+    pub anchor_ids: Vec<String>,
+
+    /// The node ids to compare with the anchors.
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "The number of node ids should be between 1 and 500."
+    ))]
+    pub node_ids: Vec<String>,
+
+    /// The embedding model, the latest registered model is used by default.
+    pub model: Option<String>,
+}
+
+/// Resolve the gene and protein identifiers (such as the ones pasted from a paper) to the entities.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct GeneResolutionRequest {
+    /// The identifiers, the types can be mixed, such as `TP53`, `7157`, `P04637`, `ENSG00000141510` and `NM_000546.6`. Each item may contain several identifiers separated by the whitespaces, the commas or the semicolons, at most 1000 identifiers are resolved.
+    #[validate(length(
+        min = 1,
+        max = 1000,
+        message = "The number of identifiers should be between 1 and 1000."
+    ))]
+    pub identifiers: Vec<String>,
+
+    /// The entity types to search, Gene and Protein by default.
+    #[oai(skip_serializing_if_is_none)]
+    pub labels: Option<Vec<String>>,
+
+    /// Only match the entities of the species, such as `9606` for human.
+    #[validate(length(
+        min = 1,
+        max = 16,
+        message = "The length of taxid should be between 1 and 16."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub taxid: Option<String>,
+}
+
+/// Build a graph from a list of genes or compounds (such as the genes in a paper), the identifiers are resolved like `/api/v1/genes/resolve`.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct GraphFromListRequest {
+    /// The identifiers, such as `TP53`, `P04637` and `DrugBank:DB00945`. Each item may contain several identifiers separated by the whitespaces, the commas or the semicolons, at most 500 identifiers are used.
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "The number of identifiers should be between 1 and 500."
+    ))]
+    pub identifiers: Vec<String>,
+
+    /// The entity types to resolve the identifiers to, Gene, Protein and Compound by default.
+    #[oai(skip_serializing_if_is_none)]
+    pub labels: Option<Vec<String>>,
+
+    /// Only resolve the identifiers to the entities of the species, such as `9606` for human.
+    #[validate(length(
+        min = 1,
+        max = 16,
+        message = "The length of taxid should be between 1 and 16."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub taxid: Option<String>,
+
+    /// The matches whose confidences are lower than it are not used as the nodes, between 0 and 1. The default is 0.5, so the ambiguous synonyms are skipped.
+    #[validate(range(
+        min = 0.0,
+        max = 1.0,
+        message = "The min confidence should be between 0 and 1."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub min_confidence: Option<f64>,
+
+    /// Expand the nodes to their neighbours of the type by one hop, such as Compound or Disease.
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The expand_to must match the ^[A-Za-z]+$ pattern."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub expand_to: Option<String>,
+
+    /// The max number of the edges to the neighbours, between 1 and 1000. The default is 100.
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "The expand_limit should be between 1 and 1000."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub expand_limit: Option<usize>,
+
+    /// Skip the edges of the hub nodes whose global degree is greater than it, same as `/api/v1/auto-connect-nodes`.
+    #[oai(skip_serializing_if_is_none)]
+    pub max_node_degree: Option<u64>,
+
+    /// Skip the edges of the nodes with the labels, comma-separated, such as `Anatomy`.
+    #[oai(skip_serializing_if_is_none)]
+    pub exclude_labels: Option<String>,
+
+    /// Save the graph as a subgraph with the name, the curator role is required.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of subgraph_name should be between 1 and 64."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub subgraph_name: Option<String>,
+
+    /// The description of the saved subgraph.
+    #[oai(skip_serializing_if_is_none)]
+    pub subgraph_description: Option<String>,
+}
+
+/// Search the compounds by a query molecule, the compounds which contain it or are similar to it.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct StructureSearchRequest {
+    /// The SMILES of the query molecule, such as `c1ccccc1C(=O)O`.
+    #[validate(length(
+        min = 1,
+        max = 2048,
+        message = "The length of smiles should be between 1 and 2048."
+    ))]
+    #[validate(custom = "validate_smiles")]
+    pub smiles: String,
+
+    /// substructure or similarity.
+    pub mode: StructureSearchMode,
+
+    /// The min Tanimoto similarity of the similarity search, between 0 and 1. The default is 0.7.
+    #[validate(range(
+        min = 0.0,
+        max = 1.0,
+        message = "The min similarity should be between 0 and 1."
+    ))]
+    pub min_similarity: Option<f64>,
+
+    /// Only search the compounds of the entity type, such as Compound. All structures are searched by default.
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub entity_type: Option<String>,
+
+    /// The max number of the returned compounds, between 1 and 500. The default is 50.
+    #[validate(range(
+        min = 1,
+        max = "MAX_SEARCH_LIMIT",
+        message = "The limit should be between 1 and 500."
+    ))]
+    pub limit: Option<usize>,
+}
+
+/// Check whether the triples (at most 1000) exist in the knowledge graph.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct RelationExistenceRequest {
+    #[validate(length(
+        min = 1,
+        max = 1000,
+        message = "The number of triples should be between 1 and 1000."
+    ))]
+    pub triples: Vec<RelationTriple>,
+}
+
+/// Roll up the entities (such as the diseases in a query result) to their ancestors in an ontology which is imported by `biomedgps-cli import-ontology`.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct OntologyRollupRequest {
+    /// The label of the entities, such as Disease.
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The label must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub label: String,
+
+    /// The ids of the entities, such as `DOID:9352`.
+    #[validate(length(
+        min = 1,
+        max = 1000,
+        message = "The number of ids should be between 1 and 1000."
+    ))]
+    pub ids: Vec<String>,
+
+    /// The max number of the is_a relations between an entity and its ancestors, between 1 and 30. The default is 10.
+    pub max_depth: Option<usize>,
+
+    /// The max number of the returned ancestors, the ones which cover more entities go first. The default is 100.
+    pub limit: Option<usize>,
+}
+
+/// Test which gene sets (such as the pathways and the diseases) are over-represented in a gene list.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct EnrichmentRequest {
+    /// The genes, they should be the same kind of ids as the genes of the gene sets, such as `ENTREZ:7157`.
+    #[validate(length(
+        min = 1,
+        max = 5000,
+        message = "The number of genes should be between 1 and 5000."
+    ))]
+    pub genes: Vec<String>,
+
+    /// Only test the gene sets of the sources (see `/api/v1/gene-set-sources`), all sources are tested by default.
+    pub sources: Option<Vec<String>>,
+
+    /// The gene sets which are smaller than it are skipped. The default is 5.
+    pub min_set_size: Option<usize>,
+
+    /// The gene sets which are larger than it are skipped. The default is 500.
+    pub max_set_size: Option<usize>,
+
+    /// The number of the genes in the background, such as 20000 for all protein-coding genes. It is the number of the genes in the tested gene sets by default.
+    pub background_size: Option<usize>,
+
+    /// Only return the gene sets whose adjusted p-values are not larger than it. The default is 0.05.
+    #[validate(range(
+        min = 0.0,
+        max = 1.0,
+        message = "The adjusted p-value cutoff should be between 0 and 1."
+    ))]
+    pub adjusted_p_value_cutoff: Option<f64>,
+}
+
+/// Invite a user to a workspace, or change the role of a member.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct WorkspaceMemberRequest {
+    /// The username of the user, it is the username in the access token.
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of username should be between 1 and 64."
+    ))]
+    pub username: String,
+
+    /// The role of the member, one of viewer, editor and owner.
+    pub role: WorkspaceRole,
+}
+
+/// Flag a relation which is wrong, outdated or duplicated.
+#[derive(Debug, Clone, Serialize, Deserialize, Object, Validate)]
+pub struct RelationFlagRequest {
+    /// One of wrong, outdated and duplicate.
+    pub reason: FlagReason,
+
+    /// Why the relation is flagged, such as the publication which contradicts it.
+    #[validate(length(
+        max = 2048,
+        message = "The length of comment should be less than or equal to 2048."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub comment: Option<String>,
+}
+
+/// Accept or reject a flag of a relation.
+#[derive(Debug, Clone, Serialize, Deserialize, Object)]
+pub struct RelationFlagResolution {
+    /// accepted or rejected.
+    pub status: FlagStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let err = sqlx::Error::RowNotFound;
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::NotFound);
+        let err = sqlx::Error::PoolTimedOut;
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::DatabaseError);
+
+        // The anyhow errors are checked by their sources.
+        let err: anyhow::Error = sqlx::Error::PoolTimedOut.into();
+        assert_eq!(ErrorCode::from_error(&*err), ErrorCode::DatabaseError);
+        assert_eq!(
+            ErrorCode::DatabaseError.status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let err = SubgraphIdQuery::new("not-a-uuid").unwrap_err();
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::ValidationError);
+
+        let err = anyhow::anyhow!("Unknown error");
+        assert_eq!(ErrorCode::from_error(&*err), ErrorCode::BadRequest);
+
+        let err = ApiError::new(ErrorCode::NotFound, "Not found".to_string());
+        assert_eq!(err.request_id, None);
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({"code": "not_found", "message": "Not found"})
+        );
+    }
+
+    #[test]
+    fn test_pagination() {
+        let p = Pagination::new(None, Some(20)).unwrap();
+        assert_eq!((p.page, p.page_size), (Some(1), Some(20)));
+
+        assert!(Pagination::new(Some(0), None).is_err());
+        assert!(PaginationQuery::new(Some(1), Some(0), None).is_err());
+
+        // The page size is rejected instead of being clamped.
+        let err = Pagination::new(Some(1), Some(get_max_page_size() + 1)).unwrap_err();
+        assert!(err.field_errors().contains_key("page_size"));
+        assert_eq!(ErrorCode::from_error(&err), ErrorCode::ValidationError);
     }
 }
@@ -3,9 +3,11 @@
 //! BioMedGPS library for knowledge graph construction and analysis.
 pub mod algorithm;
 pub mod api;
+pub mod config;
 pub mod model;
 pub mod pgvector;
 pub mod query_builder;
+pub mod telemetry;
 
 use log::{debug, error, info, warn, LevelFilter};
 use log4rs;
@@ -18,25 +20,46 @@ use std::fs::Permissions;
 use std::os::unix::fs::PermissionsExt;
 use std::vec;
 
+use crate::model::biolink::BiolinkMapping;
 use crate::model::core::{
-    CheckData, Entity, Entity2D, EntityEmbedding, KnowledgeCuration, Relation, RelationEmbedding,
-    Subgraph,
+    CheckData, DatasetVersion, EmbeddingModel, Entity, Entity2D, EntityAttribute, EntityEmbedding,
+    ImportJob, KnowledgeCuration, Relation, RelationEmbedding, Subgraph,
 };
+use crate::model::idmapping::EntityIdMapping;
+use crate::model::notification::{publish_event, EventType, CLI_ACTOR};
+use crate::model::relation_type::RelationTypeDefinition;
+use crate::model::remote::sha256_file;
+use crate::model::structure::CompoundStructure;
+use crate::model::translation::EntityTranslation;
 use crate::model::util::{
-    drop_table, get_delimiter, import_file_in_loop, show_errors, update_entity_metadata,
-    update_relation_metadata,
+    drop_table, get_delimiter, import_file_in_batches, show_errors, update_degree_statistics,
+    update_entity_degree, update_entity_metadata, update_relation_metadata, ImportProgress,
+    DEFAULT_IMPORT_BATCH_SIZE,
 };
+use crate::telemetry::TelemetryConfig;
 
 use serde_json::Value;
 use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
+use tokio::task::JoinSet;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 use url::form_urlencoded;
 
+// The interval (in seconds) of logging the import progress if the progress bar is not used.
+const PROGRESS_LOG_INTERVAL: u64 = 30;
+
 const MIGRATIONS: include_dir::Dir = include_dir::include_dir!("migrations");
 
 pub async fn run_migrations(database_url: &str) -> sqlx::Result<()> {
@@ -76,6 +99,77 @@ pub async fn run_migrations(database_url: &str) -> sqlx::Result<()> {
     Ok(())
 }
 
+/// The settings of the database connection pool. All timeouts are in seconds and 0 means no timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolSettings {
+    /// The max number of connections in the pool.
+    pub max_connections: u32,
+    /// The max time to wait for a connection from the pool.
+    pub acquire_timeout: u64,
+    /// The max time a connection can be idle before it is closed.
+    pub idle_timeout: u64,
+    /// The max time a statement can run in the database, it is applied by the `statement_timeout` option of postgresql.
+    pub statement_timeout: u64,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings {
+            max_connections: 5,
+            acquire_timeout: 30,
+            idle_timeout: 600,
+            statement_timeout: 0,
+        }
+    }
+}
+
+/// Connect to the database with the pool settings.
+pub async fn connect_db(
+    database_url: &str,
+    settings: &PoolSettings,
+) -> Result<sqlx::PgPool, sqlx::Error> {
+    let (pool_options, connect_options) = make_pool_options(database_url, settings)?;
+    pool_options.connect_with(connect_options).await
+}
+
+/// Make a pool with the pool settings without connecting to the database, the connections are opened when they are needed. It is used for the databases which may be down when the server starts, such as the read replica.
+pub fn connect_db_lazy(
+    database_url: &str,
+    settings: &PoolSettings,
+) -> Result<sqlx::PgPool, sqlx::Error> {
+    let (pool_options, connect_options) = make_pool_options(database_url, settings)?;
+    Ok(pool_options.connect_lazy_with(connect_options))
+}
+
+fn make_pool_options(
+    database_url: &str,
+    settings: &PoolSettings,
+) -> Result<(PgPoolOptions, PgConnectOptions), sqlx::Error> {
+    let mut connect_options = PgConnectOptions::from_str(database_url)?;
+    if settings.statement_timeout > 0 {
+        connect_options = connect_options.options([(
+            "statement_timeout",
+            format!("{}s", settings.statement_timeout),
+        )]);
+    }
+
+    let idle_timeout = if settings.idle_timeout > 0 {
+        Some(Duration::from_secs(settings.idle_timeout))
+    } else {
+        None
+    };
+
+    let mut pool_options = PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .idle_timeout(idle_timeout);
+
+    if settings.acquire_timeout > 0 {
+        pool_options = pool_options.acquire_timeout(Duration::from_secs(settings.acquire_timeout));
+    }
+
+    Ok((pool_options, connect_options))
+}
+
 pub async fn check_curated_knowledges(pool: &sqlx::PgPool, file: &PathBuf, delimiter: u8) {
     // Get all source_id and source_type pairs from the biomedgps_knowledge_curation table and keep them in a HashMap. The key is the source_id and source_type pair, the value is a list of numbers which are the row numbers that have the same source_id and source_type.
     let mut curated_knowledges: HashMap<(String, String), Vec<i64>> = HashMap::new();
@@ -162,25 +256,116 @@ pub async fn check_curated_knowledges(pool: &sqlx::PgPool, file: &PathBuf, delim
     }
 }
 
+/// The options of importing the data files, see `biomedgps-cli importdb --help` for details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    /// Delete the existing records of the table before importing, nothing is deleted or imported if any of the files is invalid.
+    pub drop: bool,
+    /// Don't check the related tables, such as the knowledge_curation table for the entity table.
+    pub skip_check: bool,
+    /// Show all validation errors instead of the first 3 errors.
+    pub show_all_errors: bool,
+    /// The number of rows which are committed in one transaction.
+    pub batch_size: usize,
+    /// The id of an interrupted import job to resume, the table and the file path are read from the job.
+    pub resume: Option<i64>,
+    /// The number of files which are imported concurrently when the file path is a directory.
+    pub jobs: usize,
+    /// Draw a progress bar on stderr instead of logging the progress periodically, it is used when stderr is a terminal.
+    pub progress_bar: bool,
+    /// The release tag of the dataset, the imported files and their checksums are recorded under it. The import date (such as 20231107) is used if it is not specified.
+    pub release_tag: Option<String>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            drop: false,
+            skip_check: false,
+            show_all_errors: false,
+            batch_size: DEFAULT_IMPORT_BATCH_SIZE,
+            resume: None,
+            jobs: 1,
+            progress_bar: false,
+            release_tag: None,
+        }
+    }
+}
+
+/// Import the data file(s) into a table. The embedding_model is only used by the entity_embedding and relation_embedding tables, the embeddings are tagged with the model name.
+///
+/// The entity, relation, entity2d, knowledge_curation, subgraph, entity_attribute, entity_translation, entity_id_mapping, compound_structure, biolink_mapping and relation_type files are imported batch by batch, each file is tracked by an import job (see [`ImportJob`](model/core/struct.ImportJob.html)) which can be resumed by `options.resume` if the import is interrupted.
 pub async fn import_data(
     database_url: &str,
     filepath: &Option<String>,
     table: &str,
-    drop: bool,
-    skip_check: bool,
-    show_all_errors: bool,
+    options: &ImportOptions,
+    embedding_model: &EmbeddingModel,
 ) {
+    let mut options = options.clone();
+    let release_tag = options
+        .release_tag
+        .get_or_insert_with(|| chrono::Local::now().format("%Y%m%d").to_string());
+    // It is saved as the db_version of the subgraphs, which is limited to 36 characters.
+    if release_tag.is_empty() || release_tag.len() > 36 {
+        error!(
+            "Invalid release tag: {}, it should be 1-36 characters.",
+            release_tag
+        );
+        return;
+    }
+    let options = &options;
+
+    let drop = options.drop;
+    let show_all_errors = options.show_all_errors;
+    // Each concurrent import needs its own connection.
     let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(std::cmp::max(10, options.jobs as u32 + 1))
         .connect(&database_url)
         .await
         .unwrap();
 
+    // The table and the file path of a resumed import come from the job.
+    let mut resumed_job = match options.resume {
+        Some(job_id) => match ImportJob::get_record(&pool, job_id).await {
+            Ok(job) if job.is_resumable() => {
+                info!(
+                    "Resuming the import job {} ({} into {}), {} rows have been committed.",
+                    job.id, job.filepath, job.table_name, job.committed_rows
+                );
+                Some(job)
+            }
+            Ok(job) => {
+                info!("The import job {} has been completed.", job.id);
+                return;
+            }
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+    let resumed_source = resumed_job
+        .as_ref()
+        .map(|job| (job.table_name.clone(), Some(job.filepath.clone())));
+    let (table, filepath, drop) = match &resumed_source {
+        Some((table, filepath)) => (table.as_str(), filepath, false),
+        None => (table, filepath, drop),
+    };
+
     if table == "relation_metadata" {
         update_relation_metadata(&pool, true).await.unwrap();
         return;
     } else if table == "entity_metadata" {
         update_entity_metadata(&pool, true).await.unwrap();
         return;
+    } else if table == "degree_statistics" {
+        update_degree_statistics(&pool, true).await.unwrap();
+        return;
+    } else if table == "entity_degree" {
+        update_entity_degree(&pool, true).await.unwrap();
+        return;
     }
 
     let filepath = match filepath {
@@ -215,7 +400,14 @@ pub async fn import_data(
                 info!("The data file {} is valid.", file.display());
             }
 
-            EntityEmbedding::import_entity_embeddings(&pool, &file, delimiter, drop).await
+            EntityEmbedding::import_entity_embeddings(
+                &pool,
+                &file,
+                delimiter,
+                drop,
+                embedding_model,
+            )
+            .await
         } else {
             let errors = RelationEmbedding::check_csv_is_valid(&file);
             if errors.len() > 0 {
@@ -223,10 +415,18 @@ pub async fn import_data(
                 return;
             };
 
-            RelationEmbedding::import_relation_embeddings(&pool, &file, delimiter, drop).await
+            RelationEmbedding::import_relation_embeddings(
+                &pool,
+                &file,
+                delimiter,
+                drop,
+                embedding_model,
+            )
+            .await
         } {
             Ok(_) => {
                 info!("Import embeddings into {} table successfully.", table);
+                record_dataset_file(&pool, options, table, &file).await;
                 return;
             }
             Err(e) => {
@@ -254,218 +454,591 @@ pub async fn import_data(
         }
 
         if files.is_empty() {
-            error!("No valid files found. Only tsv/csv/txt files are supported.");
+            error!("No valid files found. Only tsv/csv/txt files (may be compressed by gzip or zstd, such as data.tsv.gz) and parquet files are supported.");
             std::process::exit(1);
         }
 
-        for file in files {
-            let filename = file.to_str().unwrap();
-            info!("Importing {} into {}...", filename, table);
-
-            let validation_errors = if table == "entity" {
-                Entity::check_csv_is_valid(&file)
-            } else if table == "entity2d" {
-                Entity2D::check_csv_is_valid(&file)
-            } else if table == "relation" {
-                Relation::check_csv_is_valid(&file)
-            } else if table == "knowledge_curation" {
-                KnowledgeCuration::check_csv_is_valid(&file)
-            } else if table == "subgraph" {
-                Subgraph::check_csv_is_valid(&file)
-            } else {
-                error!("Invalid table name: {}", table);
-                vec![]
-            };
-
-            if validation_errors.len() > 0 {
-                error!("Invalid file: {}", filename);
-                show_errors(&validation_errors, show_all_errors);
-                warn!("Skipping {}...\n\n", filename);
-                continue;
-            } else {
-                info!("{} is valid.", filename);
+        let table_name = match get_table_name(table) {
+            Some((table_name, _)) => table_name,
+            None => {
+                error!("Unsupported table name: {}", table);
+                return;
             }
+        };
+
+        let jobs = options.jobs.max(1);
+        let total = files.len();
+        if jobs > 1 && total > 1 {
+            info!("Importing {} files with {} jobs...", total, jobs);
+        }
 
-            let delimiter = match get_delimiter(&file) {
-                Ok(d) => d,
-                Err(_) => {
-                    error!("Invalid filename: {}, no extension found.", filename);
-                    continue;
+        let started = Instant::now();
+        let progress = Arc::new(ImportProgress::new(total));
+        let reporter = {
+            let progress = progress.clone();
+            let progress_bar = options.progress_bar;
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(if progress_bar {
+                    1
+                } else {
+                    PROGRESS_LOG_INTERVAL
+                }));
+                // The first tick completes immediately.
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    progress.report(progress_bar);
                 }
-            };
+            })
+        };
 
-            let expected_columns = if table == "entity" {
-                Entity::get_column_names(&file)
-            } else if table == "entity2d" {
-                Entity2D::get_column_names(&file)
-            } else if table == "relation" {
-                Relation::get_column_names(&file)
-            } else if table == "knowledge_curation" {
-                KnowledgeCuration::get_column_names(&file)
-            } else if table == "subgraph" {
-                Subgraph::get_column_names(&file)
-            } else {
-                error!("Invalid table name: {}", table);
-                Ok(vec![])
-            };
+        // All files are validated before the table is dropped, so an invalid file never empties the table.
+        let mut files = files.into_iter();
+        let mut tasks = JoinSet::new();
+        let mut validated_files = vec![];
+        let mut summaries = vec![];
+        let mut aborted = 0;
+        loop {
+            while tasks.len() < jobs {
+                let file = match files.next() {
+                    Some(file) => file,
+                    None => break,
+                };
 
-            let expected_columns = match expected_columns {
-                Ok(v) => v,
-                Err(e) => {
-                    error!(
-                        "Fn: get_column_names, Invalid file: {}, reason: {}",
-                        filename, e
-                    );
-                    continue;
+                let pool = pool.clone();
+                let table = table.to_string();
+                let options = options.clone();
+                let resumed_job = resumed_job.take();
+                let progress = progress.clone();
+                tasks.spawn(async move {
+                    validate_table_file(&pool, file, &table, &options, resumed_job, &progress).await
+                });
+            }
+
+            match tasks.join_next().await {
+                Some(Ok(Ok(validated))) => validated_files.push(validated),
+                Some(Ok(Err(summary))) => summaries.push(summary),
+                Some(Err(e)) => {
+                    error!("The validation task is aborted: {}", e);
+                    aborted += 1;
                 }
-            };
+                None => break,
+            }
+        }
 
-            debug!(
-                "Expected columns which will be imported: {:?}",
-                expected_columns
-            );
+        // The table is dropped once before importing the files, so the files which are imported concurrently don't delete the rows of each other. Nothing is dropped or imported if any file is invalid, otherwise the table would only keep the rows of the valid files.
+        if drop {
+            if !summaries.is_empty() || aborted > 0 {
+                reporter.abort();
+                if options.progress_bar {
+                    progress.report(true);
+                    eprintln!();
+                }
 
-            // Selecting process must be done after getting expected columns. because the temporary table is created based on the expected columns and it don't have extension. The get_column_names will fail if the file don't have extension.
-            let pardir = file.parent().unwrap();
-            let temp_file = tempfile::NamedTempFile::new_in(pardir).unwrap();
-            let temp_filepath = PathBuf::from(temp_file.path().to_str().unwrap());
-            let permissions = Permissions::from_mode(0o755); // 0o755 is the octal representation of 755
-            log::info!("Setting permissions to 755 for the temp file.");
-            File::open(&temp_filepath)
-                .expect("Failed to open the file")
-                .set_permissions(permissions)
-                .expect("Failed to set file permissions");
-            debug!("Data file: {:?}, Temp file: {:?}", file, temp_filepath);
-            let results = if table == "entity" {
-                Entity::select_expected_columns(&file, &temp_filepath)
-            } else if table == "entity2d" {
-                Entity2D::select_expected_columns(&file, &temp_filepath)
-            } else if table == "relation" {
-                Relation::select_expected_columns(&file, &temp_filepath)
-            } else if table == "knowledge_curation" {
-                KnowledgeCuration::select_expected_columns(&file, &temp_filepath)
-            } else if table == "subgraph" {
-                Subgraph::select_expected_columns(&file, &temp_filepath)
-            } else {
-                error!("Invalid table name: {}", table);
-                continue;
-            };
+                show_import_summary(&mut summaries, started.elapsed());
+                error!(
+                    "{} of {} files are invalid, the {} table is not dropped and no file is imported. Fix the invalid files or import the valid files without --drop.",
+                    summaries.len() + aborted,
+                    total,
+                    table_name
+                );
+                std::process::exit(1);
+            }
+
+            drop_table(&pool, table_name).await;
+        };
+
+        let mut validated_files = validated_files.into_iter();
+        let mut tasks = JoinSet::new();
+        loop {
+            while tasks.len() < jobs {
+                let validated = match validated_files.next() {
+                    Some(validated) => validated,
+                    None => break,
+                };
+
+                let pool = pool.clone();
+                let table = table.to_string();
+                let options = options.clone();
+                let progress = progress.clone();
+                tasks.spawn(async move {
+                    import_table_file(&pool, validated, &table, &options, &progress).await
+                });
+            }
 
-            let file = match results {
-                Ok(_) => temp_filepath,
-                Err(e) => {
-                    error!(
-                        "Fn: select_expected_columns, Invalid file: {}, reason: {}",
-                        filename, e
+            match tasks.join_next().await {
+                Some(Ok(summary)) => {
+                    summaries.push(summary);
+                    info!(
+                        "{}/{} files have been processed, {} rows have been committed.",
+                        summaries.len(),
+                        total,
+                        summaries.iter().map(|s| s.committed_rows).sum::<i64>()
                     );
-                    continue;
                 }
-            };
+                Some(Err(e)) => error!("The import task is aborted: {}", e),
+                None => break,
+            }
+        }
 
-            match table {
-                "entity" => {
-                    if !skip_check {
-                        if file.exists() {
-                            // To ensure ids in the biomedgps_knowledge_curation table are in the data file, elsewise we cannot use the biomedgps_knowledge_curation table correctly.
-                            check_curated_knowledges(&pool, &file, delimiter).await;
-                        } else {
-                            error!("The file {} doesn't exist.", file.display());
-                            return;
-                        }
-                    }
+        reporter.abort();
+        if options.progress_bar {
+            progress.report(true);
+            eprintln!();
+        }
 
-                    let table_name = "biomedgps_entity";
-                    if drop {
-                        drop_table(&pool, table_name).await;
-                    };
-
-                    import_file_in_loop(
-                        &pool,
-                        &file,
-                        table_name,
-                        &expected_columns,
-                        &Entity::unique_fields(),
-                        delimiter,
-                    )
-                    .await
-                    .expect("Failed to import data into the biomedgps_entity table.");
-                }
-                "relation" => {
-                    let table_name = "biomedgps_relation";
-                    if drop {
-                        drop_table(&pool, table_name).await;
-                    };
-
-                    import_file_in_loop(
-                        &pool,
-                        &file,
-                        table_name,
-                        &expected_columns,
-                        &Relation::unique_fields(),
-                        delimiter,
-                    )
-                    .await
-                    .expect("Failed to import data into the biomedgps_relation table.");
-                }
-                "entity2d" => {
-                    let table_name = "biomedgps_entity2d";
-                    if drop {
-                        drop_table(&pool, table_name).await;
-                    };
-
-                    import_file_in_loop(
-                        &pool,
-                        &file,
-                        table_name,
-                        &expected_columns,
-                        &Entity2D::unique_fields(),
-                        delimiter,
-                    )
-                    .await
-                    .expect("Failed to import data into the biomedgps_entity2d table.");
-                }
-                "knowledge_curation" => {
-                    let table_name = "biomedgps_knowledge_curation";
-                    if drop {
-                        drop_table(&pool, table_name).await;
-                    };
-
-                    import_file_in_loop(
-                        &pool,
-                        &file,
-                        table_name,
-                        &expected_columns,
-                        &KnowledgeCuration::unique_fields(),
-                        delimiter,
-                    )
-                    .await
-                    .expect("Failed to import data into the biomedgps_knowledge_curation table.");
-                }
-                "subgraph" => {
-                    let table_name = "biomedgps_subgraph";
-                    if drop {
-                        drop_table(&pool, table_name).await;
-                    };
-
-                    import_file_in_loop(
-                        &pool,
-                        &file,
-                        table_name,
-                        &expected_columns,
-                        &Subgraph::unique_fields(),
-                        delimiter,
-                    )
-                    .await
-                    .expect("Failed to import data into the biomedgps_subgraph table.");
-                }
-                _ => {
-                    error!("Unsupported table name: {}", table);
-                    return;
-                }
-            };
+        show_import_summary(&mut summaries, started.elapsed());
+    }
+}
+
+/// The table name and the unique fields of a table which can be imported by [`import_data`](fn.import_data.html), except the embedding tables.
+fn get_table_name(table: &str) -> Option<(&'static str, Vec<String>)> {
+    match table {
+        "entity" => Some(("biomedgps_entity", Entity::unique_fields())),
+        "relation" => Some(("biomedgps_relation", Relation::unique_fields())),
+        "entity2d" => Some(("biomedgps_entity2d", Entity2D::unique_fields())),
+        "knowledge_curation" => Some((
+            "biomedgps_knowledge_curation",
+            KnowledgeCuration::unique_fields(),
+        )),
+        "subgraph" => Some(("biomedgps_subgraph", Subgraph::unique_fields())),
+        "entity_attribute" => Some((
+            "biomedgps_entity_attribute",
+            EntityAttribute::unique_fields(),
+        )),
+        "biolink_mapping" => Some((
+            "biomedgps_biolink_mapping",
+            BiolinkMapping::unique_fields(),
+        )),
+        "entity_translation" => Some((
+            "biomedgps_entity_translation",
+            EntityTranslation::unique_fields(),
+        )),
+        "entity_id_mapping" => Some((
+            "biomedgps_entity_id_mapping",
+            EntityIdMapping::unique_fields(),
+        )),
+        "compound_structure" => Some((
+            "biomedgps_compound_structure",
+            CompoundStructure::unique_fields(),
+        )),
+        "relation_type" => Some((
+            "biomedgps_relation_type",
+            RelationTypeDefinition::unique_fields(),
+        )),
+        _ => None,
+    }
+}
+
+/// The result of importing a data file, they are summarized after all files are imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportFileSummary {
+    pub filepath: String,
+    /// The import job of the file, it is None if the file is invalid.
+    pub job_id: Option<i64>,
+    pub committed_rows: i64,
+    pub elapsed: Duration,
+    /// The error message if the file is invalid or failed to import.
+    pub error: Option<String>,
+}
+
+fn show_import_summary(summaries: &mut [ImportFileSummary], elapsed: Duration) {
+    summaries.sort_by(|a, b| a.filepath.cmp(&b.filepath));
+    let failed = summaries.iter().filter(|s| s.error.is_some()).count();
+    info!(
+        "Imported {} files in {:.1}s, {} succeeded, {} failed, {} rows have been committed.",
+        summaries.len(),
+        elapsed.as_secs_f64(),
+        summaries.len() - failed,
+        failed,
+        summaries.iter().map(|s| s.committed_rows).sum::<i64>()
+    );
+
+    for summary in summaries.iter() {
+        let job = match summary.job_id {
+            Some(id) => format!("job {}", id),
+            None => "no job".to_string(),
+        };
 
+        match &summary.error {
+            None => info!(
+                "[OK] {} ({}): {} rows in {:.1}s.",
+                summary.filepath,
+                job,
+                summary.committed_rows,
+                summary.elapsed.as_secs_f64()
+            ),
+            Some(e) => error!(
+                "[FAILED] {} ({}): {} rows have been committed, {}",
+                summary.filepath, job, summary.committed_rows, e
+            ),
+        }
+    }
+
+    if failed > 0 {
+        warn!("Run `importdb --resume <job_id>` to resume the failed imports which have an import job.");
+    }
+}
+
+/// The data file which only contains the expected columns, it is generated from the original data file and removed after the import.
+struct PreparedFile {
+    temp_file: tempfile::NamedTempFile,
+    delimiter: u8,
+    expected_columns: Vec<String>,
+}
+
+/// Validate a data file and select the expected columns into a temporary file. The error contains the reason and the number of the validation errors.
+fn prepare_table_file(
+    file: &PathBuf,
+    table: &str,
+    show_all_errors: bool,
+) -> Result<PreparedFile, (String, usize)> {
+    let filename = file.to_str().unwrap();
+    info!("Importing {} into {}...", filename, table);
+
+    let validation_errors = if table == "entity" {
+        Entity::check_csv_is_valid(file)
+    } else if table == "entity2d" {
+        Entity2D::check_csv_is_valid(file)
+    } else if table == "relation" {
+        Relation::check_csv_is_valid(file)
+    } else if table == "knowledge_curation" {
+        KnowledgeCuration::check_csv_is_valid(file)
+    } else if table == "subgraph" {
+        Subgraph::check_csv_is_valid(file)
+    } else if table == "entity_attribute" {
+        EntityAttribute::check_csv_is_valid(file)
+    } else if table == "biolink_mapping" {
+        BiolinkMapping::check_csv_is_valid(file)
+    } else if table == "entity_translation" {
+        EntityTranslation::check_csv_is_valid(file)
+    } else if table == "entity_id_mapping" {
+        EntityIdMapping::check_csv_is_valid(file)
+    } else if table == "compound_structure" {
+        CompoundStructure::check_csv_is_valid(file)
+    } else if table == "relation_type" {
+        RelationTypeDefinition::check_csv_is_valid(file)
+    } else {
+        return Err((format!("Invalid table name: {}", table), 0));
+    };
+
+    if !validation_errors.is_empty() {
+        error!("Invalid file: {}", filename);
+        show_errors(&validation_errors, show_all_errors);
+        warn!("Skipping {}...\n\n", filename);
+        return Err((
+            format!("found {} validation errors.", validation_errors.len()),
+            validation_errors.len(),
+        ));
+    } else {
+        info!("{} is valid.", filename);
+    }
+
+    let delimiter = match get_delimiter(file) {
+        Ok(d) => d,
+        Err(_) => {
+            return Err((
+                format!("Invalid filename: {}, no extension found.", filename),
+                0,
+            ));
+        }
+    };
+
+    let expected_columns = if table == "entity" {
+        Entity::get_column_names(file)
+    } else if table == "entity2d" {
+        Entity2D::get_column_names(file)
+    } else if table == "relation" {
+        Relation::get_column_names(file)
+    } else if table == "knowledge_curation" {
+        KnowledgeCuration::get_column_names(file)
+    } else if table == "entity_attribute" {
+        EntityAttribute::get_column_names(file)
+    } else if table == "biolink_mapping" {
+        BiolinkMapping::get_column_names(file)
+    } else if table == "entity_translation" {
+        EntityTranslation::get_column_names(file)
+    } else if table == "entity_id_mapping" {
+        EntityIdMapping::get_column_names(file)
+    } else if table == "compound_structure" {
+        CompoundStructure::get_column_names(file)
+    } else if table == "relation_type" {
+        RelationTypeDefinition::get_column_names(file)
+    } else {
+        Subgraph::get_column_names(file)
+    };
+
+    let expected_columns = match expected_columns {
+        Ok(v) => v,
+        Err(e) => {
+            return Err((
+                format!(
+                    "Fn: get_column_names, Invalid file: {}, reason: {}",
+                    filename, e
+                ),
+                0,
+            ));
+        }
+    };
+
+    debug!(
+        "Expected columns which will be imported: {:?}",
+        expected_columns
+    );
+
+    // Selecting process must be done after getting expected columns. because the temporary table is created based on the expected columns and it don't have extension. The get_column_names will fail if the file don't have extension.
+    let pardir = file.parent().unwrap();
+    let temp_file = tempfile::NamedTempFile::new_in(pardir).unwrap();
+    let temp_filepath = PathBuf::from(temp_file.path().to_str().unwrap());
+    let permissions = Permissions::from_mode(0o755); // 0o755 is the octal representation of 755
+    log::info!("Setting permissions to 755 for the temp file.");
+    File::open(&temp_filepath)
+        .expect("Failed to open the file")
+        .set_permissions(permissions)
+        .expect("Failed to set file permissions");
+    debug!("Data file: {:?}, Temp file: {:?}", file, temp_filepath);
+    let results = if table == "entity" {
+        Entity::select_expected_columns(file, &temp_filepath)
+    } else if table == "entity2d" {
+        Entity2D::select_expected_columns(file, &temp_filepath)
+    } else if table == "relation" {
+        Relation::select_expected_columns(file, &temp_filepath)
+    } else if table == "knowledge_curation" {
+        KnowledgeCuration::select_expected_columns(file, &temp_filepath)
+    } else if table == "entity_attribute" {
+        EntityAttribute::select_expected_columns(file, &temp_filepath)
+    } else if table == "biolink_mapping" {
+        BiolinkMapping::select_expected_columns(file, &temp_filepath)
+    } else if table == "entity_translation" {
+        EntityTranslation::select_expected_columns(file, &temp_filepath)
+    } else if table == "entity_id_mapping" {
+        EntityIdMapping::select_expected_columns(file, &temp_filepath)
+    } else if table == "compound_structure" {
+        CompoundStructure::select_expected_columns(file, &temp_filepath)
+    } else if table == "relation_type" {
+        RelationTypeDefinition::select_expected_columns(file, &temp_filepath)
+    } else {
+        Subgraph::select_expected_columns(file, &temp_filepath)
+    };
+
+    match results {
+        Ok(_) => Ok(PreparedFile {
+            temp_file,
+            delimiter,
+            expected_columns,
+        }),
+        Err(e) => Err((
+            format!(
+                "Fn: select_expected_columns, Invalid file: {}, reason: {}",
+                filename, e
+            ),
+            0,
+        )),
+    }
+}
+
+/// A data file which passed the validation, it is imported by [`import_table_file`](fn.import_table_file.html) after all files are validated.
+struct ValidatedFile {
+    file: PathBuf,
+    job: ImportJob,
+    prepared: PreparedFile,
+    started: Instant,
+}
+
+/// Validate a data file of a table and track it by an import job, the resumed job is used instead of creating a new one if it is specified. The validation runs on a blocking thread, so the files can be validated concurrently. The summary of the file is returned if it is invalid.
+async fn validate_table_file(
+    pool: &sqlx::PgPool,
+    file: PathBuf,
+    table: &str,
+    options: &ImportOptions,
+    resumed_job: Option<ImportJob>,
+    progress: &ImportProgress,
+) -> Result<ValidatedFile, ImportFileSummary> {
+    let started = Instant::now();
+    let filename = file.display().to_string();
+
+    // The job is created before the validation, so the invalid files are also recorded.
+    let job = match resumed_job {
+        Some(job) => job,
+        None => match ImportJob::create(pool, table, &filename, options.batch_size).await {
+            Ok(job) => {
+                info!(
+                    "Created the import job {} for {}, run `importdb --resume {}` to resume it if the import is interrupted.",
+                    job.id, filename, job.id
+                );
+                job
+            }
+            Err(e) => {
+                progress.finished_files.fetch_add(1, Ordering::Relaxed);
+                return Err(ImportFileSummary {
+                    filepath: filename,
+                    job_id: None,
+                    committed_rows: 0,
+                    elapsed: started.elapsed(),
+                    error: Some(format!("Failed to create the import job: {}", e)),
+                });
+            }
+        },
+    };
+
+    let prepared = {
+        let file = file.clone();
+        let table = table.to_string();
+        let show_all_errors = options.show_all_errors;
+        tokio::task::spawn_blocking(move || prepare_table_file(&file, &table, show_all_errors))
+            .await
+            .unwrap_or_else(|e| Err((e.to_string(), 0)))
+    };
+
+    match prepared {
+        Ok(prepared) => {
+            if table == "entity" && !options.skip_check {
+                // To ensure ids in the biomedgps_knowledge_curation table are in the data file, elsewise we cannot use the biomedgps_knowledge_curation table correctly.
+                let temp_filepath = prepared.temp_file.path().to_path_buf();
+                check_curated_knowledges(pool, &temp_filepath, prepared.delimiter).await;
+            }
+
+            Ok(ValidatedFile {
+                file,
+                job,
+                prepared,
+                started,
+            })
+        }
+        Err(e) => {
+            let summary =
+                finish_table_file(pool, &file, table, options, job, started, Err(e), progress)
+                    .await;
+            Err(summary)
+        }
+    }
+}
+
+/// Import a validated data file into a table batch by batch, the committed rows are tracked by the import job of the file.
+async fn import_table_file(
+    pool: &sqlx::PgPool,
+    validated: ValidatedFile,
+    table: &str,
+    options: &ImportOptions,
+    progress: &ImportProgress,
+) -> ImportFileSummary {
+    let ValidatedFile {
+        file,
+        mut job,
+        prepared,
+        started,
+    } = validated;
+
+    // The table name is checked before the files are imported.
+    let (table_name, unique_fields) = get_table_name(table).unwrap_or_default();
+    // The relations are stamped with the release tag, so the new relations of a release can be found by the dataset_version.
+    let stamp_columns = match (table, &options.release_tag) {
+        ("relation", Some(release_tag)) => {
+            vec![("dataset_version".to_string(), release_tag.clone())]
+        }
+        _ => vec![],
+    };
+    let result = import_file_in_batches(
+        pool,
+        prepared.temp_file.path(),
+        table_name,
+        &prepared.expected_columns,
+        &unique_fields,
+        &stamp_columns,
+        prepared.delimiter,
+        &mut job,
+        progress,
+    )
+    .await
+    .map_err(|e| (e.to_string(), 1));
+
+    finish_table_file(pool, &file, table, options, job, started, result, progress).await
+}
+
+/// Finish the import job of a data file with the result of its validation or import, and summarize the file.
+#[allow(clippy::too_many_arguments)]
+async fn finish_table_file(
+    pool: &sqlx::PgPool,
+    file: &Path,
+    table: &str,
+    options: &ImportOptions,
+    mut job: ImportJob,
+    started: Instant,
+    result: Result<(), (String, usize)>,
+    progress: &ImportProgress,
+) -> ImportFileSummary {
+    let filename = file.display().to_string();
+    let (message, errors) = match result {
+        Ok(_) => (None, 0),
+        Err((message, errors)) => (Some(message), errors),
+    };
+    progress.errors.fetch_add(errors as u64, Ordering::Relaxed);
+    progress.finished_files.fetch_add(1, Ordering::Relaxed);
+
+    if let Err(e) = job.finish(pool, message.clone(), errors).await {
+        warn!("Failed to update the import job {}: {}", job.id, e);
+    }
+
+    match &message {
+        Some(message) => error!(
+            "Failed to import {} into the {} table: {}. {} rows have been committed, run `importdb --resume {}` to resume the import.",
+            filename, table, message, job.committed_rows, job.id
+        ),
+        None => {
             info!("{} imported.\n\n", filename);
+            record_dataset_file(pool, options, table, file).await;
+        }
+    }
+
+    let event_type = match &message {
+        Some(_) => EventType::ImportFailed,
+        None => EventType::ImportCompleted,
+    };
+    publish_event(pool, event_type, CLI_ACTOR, None, serde_json::json!(job)).await;
+
+    ImportFileSummary {
+        filepath: filename,
+        job_id: Some(job.id),
+        committed_rows: job.committed_rows,
+        elapsed: started.elapsed(),
+        error: message,
+    }
+}
+
+/// Record an imported data file and its checksum under the release tag of the import, a failure is only logged because the data has been imported.
+async fn record_dataset_file(
+    pool: &sqlx::PgPool,
+    options: &ImportOptions,
+    table: &str,
+    file: &Path,
+) {
+    let release_tag = match &options.release_tag {
+        Some(release_tag) => release_tag,
+        None => return,
+    };
+
+    let sha256 = {
+        let file = file.to_path_buf();
+        tokio::task::spawn_blocking(move || sha256_file(&file))
+            .await
+            .unwrap_or_else(|e| Err(anyhow::anyhow!(e)))
+    };
+
+    let filepath = file.display().to_string();
+    match sha256 {
+        Ok(sha256) => {
+            match DatasetVersion::record_file(pool, release_tag, table, &filepath, &sha256).await {
+                Ok(_) => info!(
+                    "Recorded {} (sha256: {}) in the dataset version {}.",
+                    filepath, sha256, release_tag
+                ),
+                Err(e) => warn!(
+                    "Failed to record {} in the dataset version {}: {}",
+                    filepath, release_tag, e
+                ),
+            }
         }
+        Err(e) => warn!("Failed to compute the checksum of {}: {}", filepath, e),
     }
 }
 
@@ -525,6 +1098,85 @@ pub fn kv2urlstr(key: &str, value: &str) -> String {
     return encoded;
 }
 
+/// The format of the logs, the json logs contain the fields of the current request (such as request_id, method, route and user), so they can be collected and queried by a log system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Invalid log format: {}, it must be text or json.",
+                s
+            )),
+        }
+    }
+}
+
+/// Initialize the logger with the format. The text logs are written by log4rs (see [`init_logger`]), and the json logs are written by tracing-subscriber, the records of the log crate are also converted into the json logs.
+///
+/// If the telemetry is set, the tracing spans are also exported by OTLP (see [`crate::telemetry`]). The tracing events (such as the completed requests) are written by tracing-subscriber in the text format then, because they are not forwarded to log4rs when a subscriber is installed.
+pub fn init_logger_with_format(
+    tag_name: &str,
+    level: LevelFilter,
+    format: LogFormat,
+    telemetry: Option<&TelemetryConfig>,
+) -> Result<(), String> {
+    let max_level = match level {
+        LevelFilter::Off => tracing_subscriber::filter::LevelFilter::OFF,
+        LevelFilter::Error => tracing_subscriber::filter::LevelFilter::ERROR,
+        LevelFilter::Warn => tracing_subscriber::filter::LevelFilter::WARN,
+        LevelFilter::Info => tracing_subscriber::filter::LevelFilter::INFO,
+        LevelFilter::Debug => tracing_subscriber::filter::LevelFilter::DEBUG,
+        LevelFilter::Trace => tracing_subscriber::filter::LevelFilter::TRACE,
+    };
+
+    // The debug spans (such as the elapsed time of a query) are only for the logs.
+    let otel_layer = match telemetry {
+        Some(telemetry) => Some(
+            tracing_opentelemetry::layer()
+                .with_tracer(telemetry.install_tracer()?)
+                .with_filter(tracing_subscriber::filter::LevelFilter::INFO),
+        ),
+        None => None,
+    };
+
+    match format {
+        LogFormat::Text => {
+            init_logger(tag_name, level)?;
+            match otel_layer {
+                // The log4rs logger has been set, so the log records must not be converted.
+                Some(otel_layer) => tracing::subscriber::set_global_default(
+                    tracing_subscriber::registry()
+                        .with(otel_layer)
+                        .with(tracing_subscriber::fmt::layer().with_filter(max_level)),
+                )
+                .map_err(|e| format!("couldn't initialize the tracing. Reason: {}", e)),
+                None => Ok(()),
+            }
+        }
+        LogFormat::Json => tracing_subscriber::registry()
+            .with(otel_layer)
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false)
+                    .with_filter(max_level),
+            )
+            .try_init()
+            .map_err(|e| format!("couldn't initialize the json logger. Reason: {}", e)),
+    }
+}
+
 pub fn init_logger(tag_name: &str, level: LevelFilter) -> Result<log4rs::Handle, String> {
     let stdout = ConsoleAppender::builder()
         .encoder(Box::new(PatternEncoder::new(
@@ -0,0 +1,412 @@
+//! Annotation module enriches the entities with the live information from the external APIs, such as the gene summaries from MyGene.info, the compound structures from PubChem and the disease definitions from MONDO (by the OLS API of EBI), so the node detail panels can show them.
+//!
+//! Each source is an adapter which decides whether it applies to an entity (by the entity type and the prefix of the entity id or its xrefs), requests the external API and normalizes the response into a small json object. The sources are requested concurrently, and the annotations are cached in the biomedgps_entity_annotation table for ANNOTATION_CACHE_TTL_DAYS days (default: 30). A stale annotation is still returned (marked as stale) if the external API is unavailable.
+
+use crate::model::core::Entity;
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use futures::future::join_all;
+use log::{debug, warn};
+use poem_openapi::{Enum, Object};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+pub const MYGENE_URL: &str = "https://mygene.info/v3/gene";
+pub const PUBCHEM_URL: &str = "https://pubchem.ncbi.nlm.nih.gov/rest/pug/compound";
+pub const OLS_MONDO_URL: &str = "https://www.ebi.ac.uk/ols4/api/ontologies/mondo/terms";
+
+/// The default number of days to keep an annotation before it is fetched again.
+pub const DEFAULT_CACHE_TTL_DAYS: i64 = 30;
+
+const REQUEST_TIMEOUT: u64 = 10;
+// The separator of the xrefs of an entity, same as the ontology module.
+const XREF_SEPARATOR: &str = "|";
+const MYGENE_FIELDS: &str = "symbol,name,summary,alias,type_of_gene";
+const PUBCHEM_PROPERTIES: &str =
+    "MolecularFormula,MolecularWeight,CanonicalSMILES,IsomericSMILES,InChIKey,IUPACName";
+const COMPOUND_LABELS: [&str; 3] = ["Compound", "Chemical", "Drug"];
+
+/// The external APIs which annotate the entities.
+///
+/// * `Mygene` - The gene summaries from MyGene.info, for the genes with an ENTREZ (or NCBIGene) id or xref.
+/// * `Pubchem` - The compound structures (such as the formula, SMILES and InChIKey) from PubChem, for the compounds with a PUBCHEM.COMPOUND id or xref, otherwise the compounds are looked up by the name.
+/// * `Mondo` - The disease definitions and synonyms from MONDO, for the diseases with a MONDO id or xref.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationSource {
+    Mygene,
+    Pubchem,
+    Mondo,
+}
+
+impl AnnotationSource {
+    pub fn all() -> Vec<AnnotationSource> {
+        vec![
+            AnnotationSource::Mygene,
+            AnnotationSource::Pubchem,
+            AnnotationSource::Mondo,
+        ]
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            AnnotationSource::Mygene => "mygene",
+            AnnotationSource::Pubchem => "pubchem",
+            AnnotationSource::Mondo => "mondo",
+        }
+    }
+
+    /// The key to look up the entity in the source, such as `7157` for MyGene.info, `cid/2244` or `name/aspirin` for PubChem and `MONDO:0004979` for MONDO. It is None if the source doesn't apply to the entity.
+    ///
+    /// ```
+    /// use biomedgps::model::annotation::AnnotationSource;
+    /// use biomedgps::model::core::Entity;
+    ///
+    /// let mut entity = Entity { idx: 0, id: "MESH:D001249".to_string(), name: "Asthma".to_string(), label: "Disease".to_string(), resource: "".to_string(), description: None, taxid: None, synonyms: None, pmids: None, xrefs: Some("DOID:2841|MONDO:0004979".to_string()) };
+    /// assert_eq!(AnnotationSource::Mondo.lookup_key(&entity), Some("MONDO:0004979".to_string()));
+    /// assert_eq!(AnnotationSource::Mygene.lookup_key(&entity), None);
+    ///
+    /// entity.label = "Compound".to_string();
+    /// assert_eq!(AnnotationSource::Pubchem.lookup_key(&entity), Some("name/Asthma".to_string()));
+    /// ```
+    pub fn lookup_key(&self, entity: &Entity) -> Option<String> {
+        match self {
+            AnnotationSource::Mygene if entity.label == "Gene" => {
+                find_curie(entity, &["ENTREZ", "NCBIGENE"])
+                    .filter(|id| id.chars().all(|c| c.is_ascii_digit()))
+            }
+            AnnotationSource::Pubchem if COMPOUND_LABELS.contains(&entity.label.as_str()) => {
+                match find_curie(entity, &["PUBCHEM.COMPOUND", "PUBCHEM", "CID"])
+                    .filter(|id| id.chars().all(|c| c.is_ascii_digit()))
+                {
+                    Some(cid) => Some(format!("cid/{}", cid)),
+                    None if !entity.name.trim().is_empty() => {
+                        Some(format!("name/{}", entity.name.trim()))
+                    }
+                    None => None,
+                }
+            }
+            AnnotationSource::Mondo if entity.label == "Disease" => {
+                find_curie(entity, &["MONDO"]).map(|id| format!("MONDO:{}", id))
+            }
+            _ => None,
+        }
+    }
+
+    fn url(&self, key: &str) -> Result<Url, anyhow::Error> {
+        let url = match self {
+            AnnotationSource::Mygene => {
+                let mut url = Url::parse(MYGENE_URL)?;
+                push_segments(&mut url, &[key])?;
+                url.query_pairs_mut().append_pair("fields", MYGENE_FIELDS);
+                url
+            }
+            AnnotationSource::Pubchem => {
+                // The key is `cid/<cid>` or `name/<name>`, the name is encoded as one segment.
+                let (namespace, value) = key.split_once('/').unwrap_or(("name", key));
+                let mut url = Url::parse(PUBCHEM_URL)?;
+                push_segments(
+                    &mut url,
+                    &[namespace, value, "property", PUBCHEM_PROPERTIES, "JSON"],
+                )?;
+                url
+            }
+            AnnotationSource::Mondo => {
+                let mut url = Url::parse(OLS_MONDO_URL)?;
+                url.query_pairs_mut().append_pair("obo_id", key);
+                url
+            }
+        };
+
+        AnyOk(url)
+    }
+
+    /// Normalize the response of the source into a small json object, it is None if the entity is not found in the response.
+    pub fn parse(&self, response: &Value) -> Option<Value> {
+        match self {
+            AnnotationSource::Mygene => {
+                let symbol = response.get("symbol")?.as_str()?;
+                // The alias is a string if the gene has only one alias.
+                let aliases = match response.get("alias") {
+                    Some(Value::String(alias)) => vec![alias.clone()],
+                    Some(Value::Array(aliases)) => aliases
+                        .iter()
+                        .filter_map(|alias| alias.as_str().map(|alias| alias.to_string()))
+                        .collect(),
+                    _ => vec![],
+                };
+                Some(json!({
+                    "symbol": symbol,
+                    "name": response.get("name"),
+                    "summary": response.get("summary"),
+                    "type_of_gene": response.get("type_of_gene"),
+                    "aliases": aliases,
+                }))
+            }
+            AnnotationSource::Pubchem => {
+                let properties = response
+                    .pointer("/PropertyTable/Properties")?
+                    .as_array()?
+                    .first()?;
+                Some(json!({
+                    "cid": properties.get("CID")?,
+                    "formula": properties.get("MolecularFormula"),
+                    "weight": properties.get("MolecularWeight"),
+                    "canonical_smiles": properties.get("CanonicalSMILES"),
+                    "isomeric_smiles": properties.get("IsomericSMILES"),
+                    "inchikey": properties.get("InChIKey"),
+                    "iupac_name": properties.get("IUPACName"),
+                }))
+            }
+            AnnotationSource::Mondo => {
+                let term = response.pointer("/_embedded/terms")?.as_array()?.first()?;
+                let definition = match term.get("description") {
+                    Some(Value::Array(descriptions)) => descriptions.first().cloned(),
+                    Some(description) => Some(description.clone()),
+                    None => None,
+                };
+                Some(json!({
+                    "mondo_id": term.get("obo_id")?,
+                    "label": term.get("label"),
+                    "definition": definition,
+                    "synonyms": term.get("synonyms").cloned().unwrap_or(json!([])),
+                }))
+            }
+        }
+    }
+
+    /// Request the source by the lookup key, it returns None if the entity is not found in the source.
+    pub async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+    ) -> Result<Option<Value>, anyhow::Error> {
+        let url = self.url(key)?;
+        debug!("Fetch the {} annotation from {}.", self.as_str(), url);
+        let response = client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return AnyOk(None);
+        }
+
+        let response = response.error_for_status()?.json::<Value>().await?;
+        AnyOk(self.parse(&response))
+    }
+}
+
+fn push_segments(url: &mut Url, segments: &[&str]) -> Result<(), anyhow::Error> {
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("The url {} cannot be a base.", url))?
+        .extend(segments);
+    AnyOk(())
+}
+
+/// Find the local id of the entity id or the first xref with one of the prefixes (case-insensitive), such as `7157` of `ENTREZ:7157`.
+fn find_curie(entity: &Entity, prefixes: &[&str]) -> Option<String> {
+    let xrefs = entity.xrefs.as_deref().unwrap_or_default();
+    std::iter::once(entity.id.as_str())
+        .chain(xrefs.split(XREF_SEPARATOR))
+        .filter_map(|curie| curie.trim().split_once(':'))
+        .find(|(prefix, id)| {
+            !id.is_empty() && prefixes.iter().any(|p| p.eq_ignore_ascii_case(prefix))
+        })
+        .map(|(_, id)| id.to_string())
+}
+
+/// The number of days to keep an annotation, it comes from the environment variable ANNOTATION_CACHE_TTL_DAYS.
+fn cache_ttl() -> ChronoDuration {
+    let days = std::env::var("ANNOTATION_CACHE_TTL_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_CACHE_TTL_DAYS);
+    ChronoDuration::days(days)
+}
+
+/// An annotation of an entity from an external API.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct EntityAnnotation {
+    pub entity_id: String,
+    pub entity_type: String,
+    /// mygene, pubchem or mondo.
+    pub source: String,
+    /// The annotation which is normalized from the response of the source, such as `{"symbol": "TP53", "summary": "..."}` for MyGene.info.
+    pub data: Value,
+    #[serde(with = "ts_seconds")]
+    pub fetched_at: DateTime<Utc>,
+    /// The annotation is older than the cache ttl, it is returned because the source is unavailable.
+    #[sqlx(default)]
+    pub stale: bool,
+}
+
+/// A source which failed to annotate the entity.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct AnnotationFailure {
+    pub source: String,
+    pub message: String,
+}
+
+/// The annotations of an entity from all sources which apply to it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EntityAnnotations {
+    pub entity_id: String,
+    pub entity_type: String,
+    pub annotations: Vec<EntityAnnotation>,
+    /// The sources which failed, the annotations of the other sources are still returned.
+    pub failures: Vec<AnnotationFailure>,
+}
+
+impl EntityAnnotations {
+    /// Fetch the annotations of an entity from the sources which apply to it. The cached annotations are used until they expire, and all sources are requested again if refresh is true.
+    pub async fn fetch(
+        pool: &sqlx::PgPool,
+        entity: &Entity,
+        refresh: bool,
+    ) -> Result<EntityAnnotations, anyhow::Error> {
+        let mut result = EntityAnnotations {
+            entity_id: entity.id.clone(),
+            entity_type: entity.label.clone(),
+            annotations: vec![],
+            failures: vec![],
+        };
+
+        let sources = AnnotationSource::all()
+            .into_iter()
+            .filter_map(|source| source.lookup_key(entity).map(|key| (source, key)))
+            .collect::<Vec<(AnnotationSource, String)>>();
+        if sources.is_empty() {
+            return AnyOk(result);
+        }
+
+        let cached = sqlx::query_as::<_, EntityAnnotation>(
+            "SELECT * FROM biomedgps_entity_annotation WHERE entity_type = $1 AND entity_id = $2",
+        )
+        .bind(&entity.label)
+        .bind(&entity.id)
+        .fetch_all(pool)
+        .await?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT))
+            .build()?;
+        let expired_at = Utc::now() - cache_ttl();
+        let outcomes = join_all(sources.iter().map(|(source, key)| {
+            let cached = cached
+                .iter()
+                .find(|annotation| annotation.source == source.as_str())
+                .cloned();
+            let client = &client;
+            async move {
+                if let Some(annotation) = &cached {
+                    if !refresh && annotation.fetched_at > expired_at {
+                        return (source, Ok(Some(annotation.clone())));
+                    }
+                }
+
+                match source.fetch(client, key).await {
+                    Ok(Some(data)) => (source, Self::save(pool, entity, source, &data).await),
+                    Ok(None) => (source, AnyOk(None)),
+                    Err(e) => match cached {
+                        Some(mut annotation) => {
+                            warn!(
+                                "Failed to fetch the {} annotation of {}, the cached one is returned: {}",
+                                source.as_str(),
+                                entity.id,
+                                e
+                            );
+                            annotation.stale = true;
+                            (source, AnyOk(Some(annotation)))
+                        }
+                        None => (source, Err(e)),
+                    },
+                }
+            }
+        }))
+        .await;
+
+        for (source, outcome) in outcomes {
+            match outcome {
+                Ok(Some(annotation)) => result.annotations.push(annotation),
+                Ok(None) => debug!("No {} annotation for {}.", source.as_str(), entity.id),
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch the {} annotation of {}: {}",
+                        source.as_str(),
+                        entity.id,
+                        e
+                    );
+                    result.failures.push(AnnotationFailure {
+                        source: source.as_str().to_string(),
+                        message: e.to_string(),
+                    })
+                }
+            }
+        }
+
+        AnyOk(result)
+    }
+
+    async fn save(
+        pool: &sqlx::PgPool,
+        entity: &Entity,
+        source: &AnnotationSource,
+        data: &Value,
+    ) -> Result<Option<EntityAnnotation>, anyhow::Error> {
+        let annotation = sqlx::query_as::<_, EntityAnnotation>(
+            "INSERT INTO biomedgps_entity_annotation (entity_id, entity_type, source, data, fetched_at)
+             VALUES ($1, $2, $3, $4, now())
+             ON CONFLICT (entity_id, entity_type, source) DO UPDATE SET data = EXCLUDED.data, fetched_at = EXCLUDED.fetched_at
+             RETURNING *",
+        )
+        .bind(&entity.id)
+        .bind(&entity.label)
+        .bind(source.as_str())
+        .bind(data)
+        .fetch_one(pool)
+        .await?;
+
+        AnyOk(Some(annotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotations() {
+        let response = json!({"_id": "7157", "symbol": "TP53", "name": "tumor protein p53", "alias": "BCC7", "summary": "This gene encodes a tumor suppressor protein."});
+        let data = AnnotationSource::Mygene.parse(&response).unwrap();
+        assert_eq!(data["symbol"], "TP53");
+        assert_eq!(data["aliases"], json!(["BCC7"]));
+        assert!(AnnotationSource::Mygene
+            .parse(&json!({"success": false}))
+            .is_none());
+
+        let response = json!({"PropertyTable": {"Properties": [{"CID": 2244, "MolecularFormula": "C9H8O4", "InChIKey": "BSYNRYMUTXBXSQ-UHFFFAOYSA-N"}]}});
+        let data = AnnotationSource::Pubchem.parse(&response).unwrap();
+        assert_eq!(data["cid"], 2244);
+        assert_eq!(data["inchikey"], "BSYNRYMUTXBXSQ-UHFFFAOYSA-N");
+
+        let response = json!({"_embedded": {"terms": [{"obo_id": "MONDO:0004979", "label": "asthma", "description": ["A bronchial disease."], "synonyms": ["asthma"]}]}});
+        let data = AnnotationSource::Mondo.parse(&response).unwrap();
+        assert_eq!(data["definition"], "A bronchial disease.");
+        assert!(AnnotationSource::Mondo
+            .parse(&json!({"page": {"totalElements": 0}}))
+            .is_none());
+    }
+
+    #[test]
+    fn test_annotation_urls() {
+        let url = AnnotationSource::Pubchem.url("name/vitamin c/e").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://pubchem.ncbi.nlm.nih.gov/rest/pug/compound/name/vitamin%20c%2Fe/property/MolecularFormula,MolecularWeight,CanonicalSMILES,IsomericSMILES,InChIKey,IUPACName/JSON"
+        );
+        let url = AnnotationSource::Mondo.url("MONDO:0004979").unwrap();
+        assert_eq!(url.query(), Some("obo_id=MONDO%3A0004979"));
+    }
+}
@@ -0,0 +1,328 @@
+//! BioLink module maps the entity labels and relation types of the knowledge graph to the [BioLink model](https://biolink.github.io/biolink-model/) classes and predicates, so the exported graphs can be merged with the other knowledge graphs (such as the Translator ones).
+//!
+//! The mappings are resolved in order: the mappings in the `biomedgps_biolink_mapping` table (imported by `biomedgps-cli importdb -t biolink_mapping`), the built-in mappings of the common labels and relation keys, and the fallbacks (`biolink:NamedThing` and `biolink:related_to`). A relation type (such as `DRUGBANK::treats::Compound:Disease`) is looked up by itself first and then by its relation key (such as `treats`), so one mapping covers the same relation of all resources.
+
+use crate::model::core::CheckData;
+use crate::model::vocabulary::GraphSchema;
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use poem_openapi::Object;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, path::PathBuf};
+use validator::Validate;
+
+lazy_static! {
+    pub static ref BIOLINK_KIND_REGEX: Regex = Regex::new(r"^(category|predicate)$").unwrap();
+    pub static ref BIOLINK_ID_REGEX: Regex = Regex::new(r"^biolink:[A-Za-z_]+$").unwrap();
+}
+
+/// The kind of the mappings from the entity labels to the BioLink classes.
+pub const CATEGORY: &str = "category";
+/// The kind of the mappings from the relation types to the BioLink predicates.
+pub const PREDICATE: &str = "predicate";
+/// The BioLink class of the entities whose label is not mapped.
+pub const DEFAULT_CATEGORY: &str = "biolink:NamedThing";
+/// The BioLink predicate of the relations whose relation type is not mapped.
+pub const DEFAULT_PREDICATE: &str = "biolink:related_to";
+
+/// The mapping comes from the `biomedgps_biolink_mapping` table.
+pub const MAPPED_BY_CONFIG: &str = "config";
+/// The mapping is one of the built-in mappings.
+pub const MAPPED_BY_BUILTIN: &str = "builtin";
+/// No mapping is found, the default class or predicate is used.
+pub const MAPPED_BY_FALLBACK: &str = "fallback";
+
+/// The built-in mappings of the common entity labels.
+const BUILTIN_CATEGORIES: [(&str, &str); 14] = [
+    ("Gene", "biolink:Gene"),
+    ("Protein", "biolink:Protein"),
+    ("Compound", "biolink:ChemicalEntity"),
+    ("Metabolite", "biolink:SmallMolecule"),
+    ("Disease", "biolink:Disease"),
+    ("Symptom", "biolink:PhenotypicFeature"),
+    ("SideEffect", "biolink:PhenotypicFeature"),
+    ("Anatomy", "biolink:AnatomicalEntity"),
+    ("Pathway", "biolink:Pathway"),
+    ("BiologicalProcess", "biolink:BiologicalProcess"),
+    ("MolecularFunction", "biolink:MolecularActivity"),
+    ("CellularComponent", "biolink:CellularComponent"),
+    ("PharmacologicClass", "biolink:ChemicalRole"),
+    ("Publication", "biolink:Publication"),
+];
+
+/// The built-in mappings of the common relation keys, i.e. the middle part of the relation types.
+const BUILTIN_PREDICATES: [(&str, &str); 16] = [
+    ("treats", "biolink:treats"),
+    ("CtD", "biolink:treats"),
+    ("T", "biolink:treats"),
+    ("causes", "biolink:causes"),
+    ("CcSE", "biolink:causes"),
+    ("J", "biolink:contributes_to"),
+    ("binds", "biolink:directly_physically_interacts_with"),
+    ("CbG", "biolink:directly_physically_interacts_with"),
+    ("interacts", "biolink:interacts_with"),
+    ("INTERACTS_WITH", "biolink:interacts_with"),
+    ("GiG", "biolink:interacts_with"),
+    ("associates", "biolink:associated_with"),
+    ("DaG", "biolink:associated_with"),
+    ("resembles", "biolink:similar_to"),
+    ("participates", "biolink:participates_in"),
+    ("expresses", "biolink:expresses"),
+];
+
+/// A mapping from an entity label or a relation type (or a relation key) to a BioLink class or predicate.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct BiolinkMapping {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    /// category (an entity label to a BioLink class) or predicate (a relation type or a relation key to a BioLink predicate).
+    #[validate(regex(
+        path = "BIOLINK_KIND_REGEX",
+        message = "The kind must be one of category and predicate."
+    ))]
+    pub kind: String,
+
+    /// An entity label (such as `Gene`), a relation type (such as `DRUGBANK::treats::Compound:Disease`) or a relation key (such as `treats`).
+    #[validate(length(
+        max = 255,
+        min = 1,
+        message = "The length of term should be between 1 and 255."
+    ))]
+    pub term: String,
+
+    /// The CURIE of the BioLink class or predicate, such as `biolink:Gene` and `biolink:treats`.
+    #[validate(regex(
+        path = "BIOLINK_ID_REGEX",
+        message = "The biolink_id must match the pattern `^biolink:[A-Za-z_]+$`. Such as `biolink:treats`."
+    ))]
+    pub biolink_id: String,
+}
+
+impl CheckData for BiolinkMapping {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<BiolinkMapping>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["kind".to_string(), "term".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "kind".to_string(),
+            "term".to_string(),
+            "biolink_id".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        Self::fields()
+    }
+}
+
+/// The resolved BioLink class of an entity label or the predicate of a relation type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow)]
+pub struct BiolinkTerm {
+    /// category or predicate.
+    pub kind: String,
+    /// The entity label or the relation type.
+    pub term: String,
+    pub biolink_id: String,
+    /// config, builtin or fallback, the fallbacks are the terms which need a mapping.
+    pub mapped_by: String,
+}
+
+/// Resolve the BioLink classes and predicates by the configured and the built-in mappings.
+#[derive(Debug, Clone, Default)]
+pub struct BiolinkMapper {
+    mappings: Vec<BiolinkMapping>,
+}
+
+impl BiolinkMapper {
+    pub fn new(mappings: Vec<BiolinkMapping>) -> Self {
+        BiolinkMapper { mappings }
+    }
+
+    /// Load the configured mappings from the `biomedgps_biolink_mapping` table.
+    pub async fn load(pool: &sqlx::PgPool) -> Result<Self, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_biolink_mapping ORDER BY kind, term";
+        let mappings = sqlx::query_as::<_, BiolinkMapping>(sql_str)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(BiolinkMapper::new(mappings))
+    }
+
+    fn find_config(&self, kind: &str, term: &str) -> Option<&str> {
+        self.mappings
+            .iter()
+            .find(|m| m.kind == kind && m.term == term)
+            .map(|m| m.biolink_id.as_str())
+    }
+
+    fn make_term(kind: &str, term: &str, biolink_id: &str, mapped_by: &str) -> BiolinkTerm {
+        BiolinkTerm {
+            kind: kind.to_string(),
+            term: term.to_string(),
+            biolink_id: biolink_id.to_string(),
+            mapped_by: mapped_by.to_string(),
+        }
+    }
+
+    /// Resolve the BioLink class of an entity label.
+    ///
+    /// ```
+    /// use biomedgps::model::biolink::BiolinkMapper;
+    ///
+    /// let mapper = BiolinkMapper::default();
+    /// assert_eq!(mapper.category("Compound").biolink_id, "biolink:ChemicalEntity");
+    /// assert_eq!(mapper.category("Unknown").biolink_id, "biolink:NamedThing");
+    /// ```
+    pub fn category(&self, label: &str) -> BiolinkTerm {
+        if let Some(biolink_id) = self.find_config(CATEGORY, label) {
+            return Self::make_term(CATEGORY, label, biolink_id, MAPPED_BY_CONFIG);
+        }
+
+        match BUILTIN_CATEGORIES.iter().find(|(l, _)| *l == label) {
+            Some((_, biolink_id)) => {
+                Self::make_term(CATEGORY, label, biolink_id, MAPPED_BY_BUILTIN)
+            }
+            None => Self::make_term(CATEGORY, label, DEFAULT_CATEGORY, MAPPED_BY_FALLBACK),
+        }
+    }
+
+    /// Resolve the BioLink predicate of a relation type, it is looked up by the relation type and then by its relation key.
+    ///
+    /// ```
+    /// use biomedgps::model::biolink::BiolinkMapper;
+    ///
+    /// let mapper = BiolinkMapper::default();
+    /// assert_eq!(mapper.predicate("DRUGBANK::treats::Compound:Disease").biolink_id, "biolink:treats");
+    /// assert_eq!(mapper.predicate("GNBR::Md::Compound:Disease").biolink_id, "biolink:related_to");
+    /// ```
+    pub fn predicate(&self, relation_type: &str) -> BiolinkTerm {
+        let relation_key = get_relation_key(relation_type);
+        if let Some(biolink_id) = self
+            .find_config(PREDICATE, relation_type)
+            .or_else(|| self.find_config(PREDICATE, relation_key))
+        {
+            return Self::make_term(PREDICATE, relation_type, biolink_id, MAPPED_BY_CONFIG);
+        }
+
+        match BUILTIN_PREDICATES.iter().find(|(k, _)| *k == relation_key) {
+            Some((_, biolink_id)) => {
+                Self::make_term(PREDICATE, relation_type, biolink_id, MAPPED_BY_BUILTIN)
+            }
+            None => Self::make_term(
+                PREDICATE,
+                relation_type,
+                DEFAULT_PREDICATE,
+                MAPPED_BY_FALLBACK,
+            ),
+        }
+    }
+
+    /// Resolve all the labels and relation types of the knowledge graph, the categories go first.
+    pub fn resolve(&self, schema: &GraphSchema) -> Vec<BiolinkTerm> {
+        let mut terms = schema
+            .labels
+            .iter()
+            .map(|label| self.category(label))
+            .collect::<Vec<BiolinkTerm>>();
+        terms.extend(
+            schema
+                .relation_type_names()
+                .iter()
+                .map(|relation_type| self.predicate(relation_type)),
+        );
+
+        terms
+    }
+
+    /// Resolve all the labels and relation types in the entity and relation metadata by the configured and the built-in mappings.
+    pub async fn get_terms(pool: &sqlx::PgPool) -> Result<Vec<BiolinkTerm>, anyhow::Error> {
+        let schema = GraphSchema::get(pool).await?;
+        let mapper = BiolinkMapper::load(pool).await?;
+
+        AnyOk(mapper.resolve(&schema))
+    }
+}
+
+/// The relation key is the middle part of a relation type, such as `treats` in `DRUGBANK::treats::Compound:Disease`. The relation type is returned as it is if it has no resource and entity types.
+pub fn get_relation_key(relation_type: &str) -> &str {
+    let parts = relation_type.split("::").collect::<Vec<&str>>();
+    if parts.len() == 3 {
+        parts[1]
+    } else {
+        relation_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biolink_mapper() {
+        let mapping = |kind: &str, term: &str, biolink_id: &str| BiolinkMapping {
+            id: 0,
+            kind: kind.to_string(),
+            term: term.to_string(),
+            biolink_id: biolink_id.to_string(),
+        };
+        let mapper = BiolinkMapper::new(vec![
+            mapping(CATEGORY, "Compound", "biolink:Drug"),
+            mapping(PREDICATE, "Md", "biolink:diagnoses"),
+            mapping(
+                PREDICATE,
+                "GNBR::T::Compound:Disease",
+                "biolink:ameliorates",
+            ),
+        ]);
+
+        // The configured mappings override the built-in ones.
+        let term = mapper.category("Compound");
+        assert_eq!(term.biolink_id, "biolink:Drug");
+        assert_eq!(term.mapped_by, MAPPED_BY_CONFIG);
+        assert_eq!(mapper.category("Gene").mapped_by, MAPPED_BY_BUILTIN);
+
+        // The relation type goes before the relation key.
+        assert_eq!(
+            mapper.predicate("GNBR::T::Compound:Disease").biolink_id,
+            "biolink:ameliorates"
+        );
+        assert_eq!(
+            mapper
+                .predicate("Hetionet::CtD::Compound:Disease")
+                .biolink_id,
+            "biolink:treats"
+        );
+        assert_eq!(
+            mapper.predicate("GNBR::Md::Compound:Disease").biolink_id,
+            "biolink:diagnoses"
+        );
+        let term = mapper.predicate("GNBR::Sa::Compound:Disease");
+        assert_eq!(term.biolink_id, DEFAULT_PREDICATE);
+        assert_eq!(term.mapped_by, MAPPED_BY_FALLBACK);
+
+        assert_eq!(get_relation_key("treats"), "treats");
+        assert!(BiolinkMapping {
+            id: 0,
+            kind: "class".to_string(),
+            term: "Gene".to_string(),
+            biolink_id: "biolink:Gene".to_string(),
+        }
+        .validate()
+        .is_err());
+    }
+}
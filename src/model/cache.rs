@@ -0,0 +1,325 @@
+//! Cache module is used to cache the results of the hot read endpoints, such as the entity/relation metadata, the statistics, the entity details and the node expansions. They are requested constantly but only change after an import or a curation.
+//!
+//! The results are serialized as JSON and kept in an in-process LRU cache by default. If the redis_url is set, they are kept in Redis instead, so that several server instances share the cache and the cli can invalidate it after an import. The in-process cache cannot be invalidated by the cli, so the cached results may be stale for at most the cache ttl after an import.
+
+use crate::model::database::{current_database, DEFAULT_DATABASE};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use lru::LruCache;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// The default seconds to keep a cached result.
+pub const DEFAULT_CACHE_TTL: u64 = 600;
+/// The default max number of the cached results in the in-process cache.
+pub const DEFAULT_CACHE_CAPACITY: usize = 10000;
+
+const REDIS_KEY_PREFIX: &str = "biomedgps:cache";
+
+/// The group of the cached results, a group is invalidated as a whole when its data are changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheScope {
+    /// The entity/relation metadata and the statistics, they are changed by an import or `biomedgps-cli statdb`.
+    Metadata,
+    /// The entity details, they are changed by an import, a curation or an entity merge.
+    Entity,
+    /// The node expansions (such as the one-step linked nodes), they are changed by an import, a curation or an entity merge.
+    Expansion,
+}
+
+impl CacheScope {
+    pub fn all() -> Vec<CacheScope> {
+        vec![
+            CacheScope::Metadata,
+            CacheScope::Entity,
+            CacheScope::Expansion,
+        ]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            CacheScope::Metadata => "metadata",
+            CacheScope::Entity => "entity",
+            CacheScope::Expansion => "expansion",
+        }
+    }
+
+    /// Make the cache key of a result in the scope, the long ids (such as the query strings) are hashed. The results of the named databases (see [`current_database`]) are kept apart from the default database.
+    pub fn key(&self, id: &str) -> String {
+        let scope = match current_database() {
+            Some(database) if database != DEFAULT_DATABASE => {
+                format!("{}:@{}", self.name(), database)
+            }
+            _ => self.name().to_string(),
+        };
+
+        if id.len() > 128 {
+            format!("{}:{:x}", scope, Sha256::digest(id.as_bytes()))
+        } else {
+            format!("{}:{}", scope, id)
+        }
+    }
+}
+
+enum CacheBackend {
+    Memory(Mutex<LruCache<String, (String, Instant)>>),
+    Redis(ConnectionManager),
+}
+
+pub struct Cache {
+    backend: CacheBackend,
+    ttl: Duration,
+}
+
+impl Cache {
+    /// Make an in-process LRU cache which keeps at most `capacity` results.
+    pub fn memory(capacity: usize, ttl: u64) -> Self {
+        let capacity = NonZeroUsize::new(capacity)
+            .unwrap_or(NonZeroUsize::new(DEFAULT_CACHE_CAPACITY).unwrap());
+        Cache {
+            backend: CacheBackend::Memory(Mutex::new(LruCache::new(capacity))),
+            ttl: Duration::from_secs(ttl),
+        }
+    }
+
+    /// Connect to the Redis server, such as redis://:password@localhost:6379/0.
+    pub async fn redis(redis_url: &str, ttl: u64) -> Result<Self, anyhow::Error> {
+        let client = redis::Client::open(redis_url)?;
+        let manager = ConnectionManager::new(client).await?;
+        Ok(Cache {
+            backend: CacheBackend::Redis(manager),
+            ttl: Duration::from_secs(ttl),
+        })
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<String>, anyhow::Error> {
+        match &self.backend {
+            CacheBackend::Memory(cache) => {
+                let mut cache = cache.lock().unwrap();
+                let expired = match cache.get(key) {
+                    Some((value, cached_at)) if cached_at.elapsed() < self.ttl => {
+                        return Ok(Some(value.clone()));
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+
+                if expired {
+                    cache.pop(key);
+                }
+                Ok(None)
+            }
+            CacheBackend::Redis(manager) => {
+                let mut conn = manager.clone();
+                let value: Option<String> = conn.get(redis_key(key)).await?;
+                Ok(value)
+            }
+        }
+    }
+
+    pub async fn set(&self, key: &str, value: String) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            CacheBackend::Memory(cache) => {
+                cache
+                    .lock()
+                    .unwrap()
+                    .put(key.to_string(), (value, Instant::now()));
+                Ok(())
+            }
+            CacheBackend::Redis(manager) => {
+                let mut conn = manager.clone();
+                conn.set_ex::<_, _, ()>(redis_key(key), value, self.ttl.as_secs() as usize)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove all the cached results of the scope, return the number of the removed results.
+    pub async fn invalidate(&self, scope: CacheScope) -> Result<usize, anyhow::Error> {
+        let prefix = format!("{}:", scope.name());
+        match &self.backend {
+            CacheBackend::Memory(cache) => {
+                let mut cache = cache.lock().unwrap();
+                let keys = cache
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(&prefix))
+                    .map(|(key, _)| key.clone())
+                    .collect::<Vec<String>>();
+                for key in keys.iter() {
+                    cache.pop(key);
+                }
+                Ok(keys.len())
+            }
+            CacheBackend::Redis(manager) => {
+                let mut conn = manager.clone();
+                let mut keys: Vec<String> = vec![];
+                {
+                    let mut iter = conn
+                        .scan_match::<_, String>(format!("{}*", redis_key(&prefix)))
+                        .await?;
+                    while let Some(key) = iter.next_item().await {
+                        keys.push(key);
+                    }
+                }
+
+                if !keys.is_empty() {
+                    conn.del::<_, ()>(keys.clone()).await?;
+                }
+                Ok(keys.len())
+            }
+        }
+    }
+}
+
+fn redis_key(key: &str) -> String {
+    format!("{}:{}", REDIS_KEY_PREFIX, key)
+}
+
+lazy_static! {
+    static ref CACHE: RwLock<Option<Arc<Cache>>> = RwLock::new(None);
+}
+
+/// Initialize the cache, it should be called once when the server (or the cli) starts. It uses Redis if the redis_url is set, otherwise the in-process cache. The cache is disabled if the ttl is 0.
+pub async fn init_cache(
+    redis_url: Option<&str>,
+    capacity: usize,
+    ttl: u64,
+) -> Result<(), anyhow::Error> {
+    let cache = if ttl == 0 {
+        info!("The cache is disabled.");
+        None
+    } else {
+        match redis_url {
+            Some(redis_url) => {
+                info!("Use the Redis cache, the ttl is {}s.", ttl);
+                Some(Arc::new(Cache::redis(redis_url, ttl).await?))
+            }
+            None => {
+                info!(
+                    "Use the in-process cache, the capacity is {} and the ttl is {}s.",
+                    capacity, ttl
+                );
+                Some(Arc::new(Cache::memory(capacity, ttl)))
+            }
+        }
+    };
+
+    *CACHE.write().unwrap() = cache;
+    Ok(())
+}
+
+fn get_cache() -> Option<Arc<Cache>> {
+    CACHE.read().unwrap().clone()
+}
+
+/// Return the cached result of the key, otherwise compute it by `f` and cache it if it succeeds. The errors of the cache are logged and ignored, so the result is always computed when the cache is unavailable.
+pub async fn cached<T, E, F, Fut>(key: &str, f: F) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    cached_with_refresh(key, false, f).await
+}
+
+/// Same as [`cached`], but the cached result is skipped and replaced by the computed one if refresh is true.
+pub async fn cached_with_refresh<T, E, F, Fut>(key: &str, refresh: bool, f: F) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let cache = match get_cache() {
+        Some(cache) => cache,
+        None => return f().await,
+    };
+
+    if refresh {
+        debug!("Refresh the cached result of {}.", key);
+    } else {
+        match cache.get(key).await {
+            Ok(Some(value)) => match serde_json::from_str::<T>(&value) {
+                Ok(value) => {
+                    debug!("Use the cached result of {}.", key);
+                    return Ok(value);
+                }
+                Err(e) => warn!("Failed to parse the cached result of {}: {}", key, e),
+            },
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read the cached result of {}: {}", key, e),
+        };
+    }
+
+    let value = f().await?;
+    match serde_json::to_string(&value) {
+        Ok(json) => {
+            if let Err(e) = cache.set(key, json).await {
+                warn!("Failed to cache the result of {}: {}", key, e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize the result of {}: {}", key, e),
+    };
+
+    Ok(value)
+}
+
+/// Remove the cached results of the scopes, it is called after the data are changed, such as an import or a curation.
+pub async fn invalidate_cache(scopes: &[CacheScope]) {
+    let cache = match get_cache() {
+        Some(cache) => cache,
+        None => return,
+    };
+
+    for scope in scopes {
+        match cache.invalidate(*scope).await {
+            Ok(count) => debug!("Invalidate {} cached results of {:?}.", count, scope),
+            Err(e) => warn!(
+                "Failed to invalidate the cached results of {:?}: {}",
+                scope, e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_cache() {
+        let cache = Cache::memory(2, 60);
+        let entity_key = CacheScope::Entity.key("Disease::MESH:D001755");
+        let metadata_key = CacheScope::Metadata.key("entity");
+
+        cache.set(&entity_key, "1".to_string()).await.unwrap();
+        cache.set(&metadata_key, "2".to_string()).await.unwrap();
+        assert_eq!(cache.get(&entity_key).await.unwrap(), Some("1".to_string()));
+
+        assert_eq!(cache.invalidate(CacheScope::Entity).await.unwrap(), 1);
+        assert_eq!(cache.get(&entity_key).await.unwrap(), None);
+        assert_eq!(
+            cache.get(&metadata_key).await.unwrap(),
+            Some("2".to_string())
+        );
+
+        // The least recently used result is evicted when the cache is full.
+        cache.set("expansion:a", "3".to_string()).await.unwrap();
+        cache.set("expansion:b", "4".to_string()).await.unwrap();
+        assert_eq!(cache.get(&metadata_key).await.unwrap(), None);
+
+        let cache = Cache::memory(2, 0);
+        cache.set(&entity_key, "1".to_string()).await.unwrap();
+        assert_eq!(cache.get(&entity_key).await.unwrap(), None);
+
+        let key = CacheScope::Expansion.key(&"x".repeat(200));
+        assert_eq!(key.len(), "expansion:".len() + 64);
+    }
+}
@@ -0,0 +1,317 @@
+//! Collab module is used to edit a shared subgraph by several curators at the same time. The clients connect to the `/ws/subgraphs/:id` websocket, send the node and edge operations and their cursors, and receive the ones of the others.
+//!
+//! Each subgraph has a room which keeps the connected users and a broadcast channel. The operations are applied to the latest payload of the subgraph and saved by [`Subgraph::update`] with the revision check, they are applied again to the newer payload if the subgraph is updated by others in the meantime, so the operations are merged instead of being overwritten. The saved revision is broadcast with the operations, so the clients know which revision their local payload is.
+
+use crate::model::core::Subgraph;
+use crate::model::graph::{
+    GraphPayload, GraphPayloadEdge, GraphPayloadNode, ValidationError, COMPOSED_ENTITY_REGEX,
+};
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// The number of the messages which a slow client can lag behind, it gets a snapshot of the subgraph if it lags more.
+pub const ROOM_CAPACITY: usize = 256;
+/// The max number of the operations in one message.
+pub const MAX_OPERATIONS: usize = 500;
+// The max number of the retries when the subgraph is updated by others at the same time.
+const MAX_UPDATE_RETRIES: usize = 5;
+
+lazy_static! {
+    static ref ROOMS: Mutex<HashMap<String, Room>> = Mutex::new(HashMap::new());
+}
+
+/// An operation on the payload of a subgraph, such as `{"action": "add_node", "node": {"id": "Gene::ENTREZ:7157"}}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum GraphOperation {
+    /// Add a node, or replace the node with the same id (such as moving it).
+    AddNode { node: GraphPayloadNode },
+    /// Remove a node and its edges.
+    RemoveNode { id: String },
+    /// Add an edge between two existing nodes, or replace the edge with the same source, target and reltype.
+    AddEdge { edge: GraphPayloadEdge },
+    /// Remove an edge by its source, target and reltype.
+    RemoveEdge {
+        source: String,
+        target: String,
+        reltype: String,
+    },
+}
+
+/// A message from a client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Apply the operations to the subgraph, they are saved together.
+    Operations { operations: Vec<GraphOperation> },
+    /// The cursor of the user, such as `{"x": 10.5, "y": 20, "node_id": "Gene::ENTREZ:7157"}`. It is broadcast as it is and never saved.
+    Cursor { cursor: serde_json::Value },
+}
+
+/// A message to the clients.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The whole subgraph, it is sent when a client joins, lags behind or the subgraph is updated by the API.
+    Snapshot { subgraph: Subgraph },
+    /// The operations which are saved as the revision, the sender gets them too as the acknowledgement.
+    Operations {
+        username: String,
+        operations: Vec<GraphOperation>,
+        revision: i64,
+    },
+    /// The users who are editing the subgraph.
+    Presence { users: Vec<String> },
+    Cursor {
+        username: String,
+        cursor: serde_json::Value,
+    },
+    /// An error which is only sent to the client which caused it, such as an invalid operation.
+    Error { message: String },
+}
+
+impl ServerMessage {
+    pub fn to_text(&self) -> String {
+        // The messages only contain the strings, numbers and json values, so the serialization never fails.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// The connected users of a subgraph and the channel to broadcast the messages to them. A user may connect several times, such as in two browser tabs.
+struct Room {
+    sender: broadcast::Sender<String>,
+    users: BTreeMap<String, usize>,
+}
+
+impl Room {
+    fn get_users(&self) -> Vec<String> {
+        self.users.keys().cloned().collect()
+    }
+}
+
+/// Join the room of a subgraph, it returns the receiver of the room and the users in the room (including the user).
+pub fn join_room(subgraph_id: &str, username: &str) -> (broadcast::Receiver<String>, Vec<String>) {
+    let mut rooms = ROOMS.lock().unwrap();
+    let room = rooms
+        .entry(subgraph_id.to_string())
+        .or_insert_with(|| Room {
+            sender: broadcast::channel(ROOM_CAPACITY).0,
+            users: BTreeMap::new(),
+        });
+    *room.users.entry(username.to_string()).or_insert(0) += 1;
+
+    (room.sender.subscribe(), room.get_users())
+}
+
+/// Leave the room of a subgraph, it returns the users who are still in the room. The room is closed when the last user leaves.
+pub fn leave_room(subgraph_id: &str, username: &str) -> Vec<String> {
+    let mut rooms = ROOMS.lock().unwrap();
+    let room = match rooms.get_mut(subgraph_id) {
+        Some(room) => room,
+        None => return vec![],
+    };
+
+    if let Some(count) = room.users.get_mut(username) {
+        *count -= 1;
+        if *count == 0 {
+            room.users.remove(username);
+        }
+    }
+
+    let users = room.get_users();
+    if users.is_empty() {
+        rooms.remove(subgraph_id);
+    }
+    users
+}
+
+/// Broadcast a message to all clients of a subgraph, it does nothing if nobody is editing the subgraph.
+pub fn broadcast(subgraph_id: &str, message: &ServerMessage) {
+    let rooms = ROOMS.lock().unwrap();
+    if let Some(room) = rooms.get(subgraph_id) {
+        // It fails only when there is no receiver, i.e. all clients are leaving.
+        if room.sender.send(message.to_text()).is_err() {
+            debug!("No client is editing the subgraph {}.", subgraph_id);
+        }
+    }
+}
+
+impl GraphOperation {
+    /// Apply the operation to the payload, the payload is still valid after it (see [`GraphPayload::validate`]).
+    pub fn apply(&self, payload: &mut GraphPayload) -> Result<(), ValidationError> {
+        match self {
+            GraphOperation::AddNode { node } => {
+                if !COMPOSED_ENTITY_REGEX.is_match(&node.id) {
+                    return Err(ValidationError::new(
+                        &format!(
+                            "Invalid node id: {}, it should be a composed id, such as Gene::ENTREZ:7157.",
+                            node.id
+                        ),
+                        vec![node.id.clone()],
+                    ));
+                }
+
+                match payload.nodes.iter_mut().find(|n| n.id == node.id) {
+                    Some(existing) => *existing = node.clone(),
+                    None => payload.nodes.push(node.clone()),
+                }
+            }
+            GraphOperation::RemoveNode { id } => {
+                payload.nodes.retain(|node| &node.id != id);
+                payload
+                    .edges
+                    .retain(|edge| &edge.source != id && &edge.target != id);
+            }
+            GraphOperation::AddEdge { edge } => {
+                let missed_ids = [&edge.source, &edge.target]
+                    .into_iter()
+                    .filter(|id| !payload.nodes.iter().any(|node| &&node.id == id))
+                    .cloned()
+                    .collect::<Vec<String>>();
+                if !missed_ids.is_empty() {
+                    return Err(ValidationError::new(
+                        &format!(
+                            "The source and target of the edge should be in the nodes. Missed node ids: {}",
+                            missed_ids.join(", ")
+                        ),
+                        missed_ids,
+                    ));
+                }
+
+                match payload.edges.iter_mut().find(|e| e.key() == edge.key()) {
+                    Some(existing) => *existing = edge.clone(),
+                    None => payload.edges.push(edge.clone()),
+                }
+            }
+            GraphOperation::RemoveEdge {
+                source,
+                target,
+                reltype,
+            } => {
+                let key = (source.clone(), reltype.clone(), target.clone());
+                payload.edges.retain(|edge| edge.key() != key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Apply the operations to the latest payload of the subgraph and save it, see the module docs. It returns None if the subgraph doesn't exist or it has been deleted.
+pub async fn apply_operations(
+    pool: &sqlx::PgPool,
+    subgraph_id: &str,
+    operations: &[GraphOperation],
+) -> Result<Option<Subgraph>, anyhow::Error> {
+    if operations.is_empty() || operations.len() > MAX_OPERATIONS {
+        return Err(ValidationError::new(
+            &format!(
+                "The number of operations should be between 1 and {}.",
+                MAX_OPERATIONS
+            ),
+            vec![],
+        )
+        .into());
+    }
+
+    for _ in 0..MAX_UPDATE_RETRIES {
+        let mut subgraph = match Subgraph::get_record(pool, subgraph_id).await? {
+            Some(subgraph) => subgraph,
+            None => return AnyOk(None),
+        };
+
+        let mut payload = GraphPayload::parse(&subgraph.payload)?;
+        for operation in operations.iter() {
+            operation.apply(&mut payload)?;
+        }
+        subgraph.payload = serde_json::to_string(&payload)?;
+
+        match subgraph
+            .update(pool, subgraph_id, Some(subgraph.revision))
+            .await?
+        {
+            Some(updated) => return AnyOk(Some(updated)),
+            None => {
+                warn!(
+                    "The subgraph {} is updated by others at the same time, apply the operations again.",
+                    subgraph_id
+                );
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "The subgraph {} is updated too frequently, please try again later.",
+        subgraph_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_operations() {
+        let mut payload = GraphPayload::parse(
+            r#"{"nodes": [{"id": "Gene::ENTREZ:7157"}, {"id": "Disease::MESH:D001249"}], "edges": [], "layout": "force"}"#,
+        )
+        .unwrap();
+
+        let operations: Vec<GraphOperation> = serde_json::from_str(
+            r#"[
+                {"action": "add_node", "node": {"id": "Compound::DrugBank:DB00001", "x": 10}},
+                {"action": "add_edge", "edge": {"source": "Compound::DrugBank:DB00001", "target": "Gene::ENTREZ:7157", "reltype": "binds"}},
+                {"action": "add_edge", "edge": {"source": "Gene::ENTREZ:7157", "target": "Disease::MESH:D001249", "reltype": "associates"}},
+                {"action": "add_node", "node": {"id": "Compound::DrugBank:DB00001", "x": 20}},
+                {"action": "remove_node", "id": "Disease::MESH:D001249"}
+            ]"#,
+        )
+        .unwrap();
+        for operation in operations.iter() {
+            operation.apply(&mut payload).unwrap();
+        }
+
+        assert!(payload.validate().is_ok());
+        assert_eq!(payload.nodes.len(), 2);
+        assert_eq!(payload.nodes[1].extra["x"], 20);
+        // The edges of the removed node are removed too.
+        assert_eq!(payload.edges.len(), 1);
+        assert_eq!(payload.extra["layout"], "force");
+
+        let operation = GraphOperation::AddEdge {
+            edge: serde_json::from_str(
+                r#"{"source": "Gene::ENTREZ:7157", "target": "Disease::MESH:D001249", "reltype": "associates"}"#,
+            )
+            .unwrap(),
+        };
+        assert!(operation.apply(&mut payload).is_err());
+    }
+
+    #[test]
+    fn test_rooms() {
+        let (_receiver, users) = join_room("test-subgraph", "alice");
+        assert_eq!(users, vec!["alice"]);
+        let (mut receiver, users) = join_room("test-subgraph", "bob");
+        assert_eq!(users, vec!["alice", "bob"]);
+        let (_receiver, _) = join_room("test-subgraph", "bob");
+
+        broadcast("test-subgraph", &ServerMessage::Presence { users });
+        let message: ServerMessage = serde_json::from_str(&receiver.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            message,
+            ServerMessage::Presence {
+                users: vec!["alice".to_string(), "bob".to_string()]
+            }
+        );
+
+        assert_eq!(leave_room("test-subgraph", "bob"), vec!["alice", "bob"]);
+        assert_eq!(leave_room("test-subgraph", "bob"), vec!["alice"]);
+        assert!(leave_room("test-subgraph", "alice").is_empty());
+        assert!(!ROOMS.lock().unwrap().contains_key("test-subgraph"));
+    }
+}
@@ -1,18 +1,37 @@
 //! The database schema for the application. These are the models that will be used to interact with the database.
 
-use super::util::{drop_table, get_delimiter, parse_csv_error};
+use super::util::{
+    get_count_cache_ttl, get_delimiter, open_csv_reader, parse_csv_error, with_query_timeout,
+    with_statement_timeout,
+};
+use crate::algorithm::reduction::{reduce, ReductionMethod, ReductionOptions};
+use crate::model::database::current_database;
+use crate::model::graph::{Graph, GraphPayload, GraphPayloadDiff, COMPOSED_ENTITY_REGEX};
+use crate::model::publication::{parse_pmids, PublicationSummary};
+use crate::model::storage::{load_payload, store_payload, PayloadRef};
+use crate::model::translation::EntityTranslation;
 use crate::model::util::match_color;
 use crate::pgvector::Vector;
-use crate::query_builder::sql_builder::{ComposeQuery, QueryItem};
+use crate::query_builder::sql_builder::{
+    bind_query_values, bind_values, AggregateQuery, ColumnTypes, ComposeQuery, ComposeQueryItem,
+    QueryError, QueryItem, Value,
+};
 use anyhow::Ok as AnyOk;
-use chrono::serde::ts_seconds;
+use chrono::serde::{ts_seconds, ts_seconds_option};
 use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
 use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
-use poem_openapi::Object;
+use poem_openapi::{Enum, Object};
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{error::Error, fmt, option::Option, path::PathBuf};
+use tokio::sync::mpsc;
 use validator::Validate;
 
 const ENTITY_NAME_MAX_LENGTH: u64 = 255;
@@ -26,6 +45,12 @@ lazy_static! {
     pub static ref EMBEDDING_REGEX: Regex = Regex::new(r"^(?:-?\d+(?:\.\d+)?\|)*-?\d+(?:\.\d+)?$").unwrap();
     pub static ref SUBGRAPH_UUID_REGEX: Regex = Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$").unwrap();
     pub static ref JSON_REGEX: Regex = Regex::new(r"^(\{.*\}|\[.*\])$").expect("Failed to compile regex");
+    pub static ref RELATION_SPLIT_REGEX: Regex = Regex::new(r"^(train|valid|test)$").unwrap();
+    pub static ref SAVED_QUERY_TABLE_REGEX: Regex = Regex::new(r"^(entity|relation|entity2d|knowledge_curation|subgraph)$").unwrap();
+    // score DESC, id ASC
+    pub static ref ORDER_BY_REGEX: Regex = Regex::new(r"^[a-z_][a-z0-9_]*( (?i:ASC|DESC))?(, ?[a-z_][a-z0-9_]*( (?i:ASC|DESC))?)*$").unwrap();
+    // biomedgps_entity
+    pub static ref IDENTIFIER_REGEX: Regex = Regex::new(r"^[a-z_][a-z0-9_]{0,62}$").unwrap();
 }
 
 #[derive(Debug)]
@@ -82,10 +107,7 @@ pub trait CheckData {
 
         debug!("The delimiter is: {:?}", delimiter as char);
         // Build the CSV reader
-        let mut reader = match csv::ReaderBuilder::new()
-            .delimiter(delimiter)
-            .from_path(filepath)
-        {
+        let mut reader = match open_csv_reader(filepath, delimiter) {
             Ok(r) => r,
             Err(e) => {
                 validation_errors.push(Box::new(ValidationError::new(&format!(
@@ -136,6 +158,117 @@ pub trait CheckData {
 
     fn unique_fields() -> Vec<String>;
 
+    /// The columns which can be selected by the `fields` query parameter, they are the fields and the generated columns (such as the primary key).
+    fn selectable_fields() -> Vec<String> {
+        Self::fields()
+    }
+
+    /// The columns which can be used in the query string, they are the selectable fields by default.
+    fn queryable_fields() -> Vec<String> {
+        Self::selectable_fields()
+    }
+
+    /// The jsonb columns, their keys can be used in the query string too, such as `attributes.tissue`.
+    fn json_fields() -> Vec<String> {
+        vec![]
+    }
+
+    /// Check the fields, operators and nesting depth of the query string against the columns before the sql is built, see [`ComposeQuery::check`].
+    ///
+    /// ```
+    /// use biomedgps::model::core::{CheckData, Entity, Relation};
+    /// use biomedgps::query_builder::sql_builder::ComposeQuery;
+    ///
+    /// let query: ComposeQuery = serde_json::from_str(r#"{"field": "attributes.tissue", "value": "liver", "operator": "="}"#).unwrap();
+    /// assert!(Relation::check_query(&query).is_ok());
+    /// assert!(Entity::check_query(&query).is_err());
+    /// ```
+    fn check_query(query: &ComposeQuery) -> Result<(), Vec<QueryError>> {
+        query.check(&Self::queryable_fields(), &Self::json_fields())
+    }
+
+    /// Parse the `fields` query parameter, such as `id,name,label`, into a list of columns. An unknown column will cause an error, because the columns are used to build the sql string directly.
+    fn parse_selected_fields(fields: &str) -> Result<Vec<String>, ValidationError> {
+        let allowed_fields = Self::selectable_fields();
+        let mut selected_fields: Vec<String> = vec![];
+        for field in fields
+            .split(',')
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+        {
+            if !allowed_fields.contains(&field.to_string()) {
+                return Err(ValidationError::new(&format!(
+                    "Invalid field: {}, the available fields are {}",
+                    field,
+                    allowed_fields.join(", ")
+                )));
+            }
+
+            if !selected_fields.contains(&field.to_string()) {
+                selected_fields.push(field.to_string());
+            }
+        }
+
+        if selected_fields.is_empty() {
+            return Err(ValidationError::new(
+                "The fields parameter cannot be empty.",
+            ));
+        }
+
+        Ok(selected_fields)
+    }
+
+    /// The columns which can be used to sort the records by the `order_by` query parameter. The sorting is disabled by default.
+    fn sortable_fields() -> Vec<String> {
+        vec![]
+    }
+
+    /// Parse the `order_by` query parameter, such as `name DESC, id`, into an order by clause. The columns must be in the `sortable_fields` and the direction must be ASC or DESC (ASC by default), because the clause is used to build the sql string directly.
+    ///
+    /// ```
+    /// use biomedgps::model::core::{CheckData, Entity};
+    ///
+    /// assert_eq!(Entity::parse_order_by("name desc, id").unwrap(), "name DESC, id ASC");
+    /// assert!(Entity::parse_order_by("name; DROP TABLE biomedgps_entity").is_err());
+    /// assert!(Entity::parse_order_by("description ASC").is_err());
+    /// ```
+    fn parse_order_by(order_by: &str) -> Result<String, ValidationError> {
+        let sortable_fields = Self::sortable_fields();
+        let mut clauses: Vec<String> = vec![];
+        for item in order_by.split(',').map(|item| item.trim()) {
+            let parts = item.split_whitespace().collect::<Vec<&str>>();
+            let (field, direction) = match parts.as_slice() {
+                [field] => (field.to_string(), "ASC".to_string()),
+                [field, direction] => (field.to_string(), direction.to_uppercase()),
+                _ => {
+                    return Err(ValidationError::new(&format!(
+                        "Invalid order_by: {}, it must be a list of fields with an optional ASC or DESC, such as `name DESC, id ASC`.",
+                        order_by
+                    )))
+                }
+            };
+
+            if !sortable_fields.contains(&field) {
+                return Err(ValidationError::new(&format!(
+                    "Invalid order_by field: {}, the sortable fields are {}",
+                    field,
+                    sortable_fields.join(", ")
+                )));
+            }
+
+            if direction != "ASC" && direction != "DESC" {
+                return Err(ValidationError::new(&format!(
+                    "Invalid order_by direction: {}, it must be ASC or DESC.",
+                    direction
+                )));
+            }
+
+            clauses.push(format!("{} {}", field, direction));
+        }
+
+        Ok(clauses.join(", "))
+    }
+
     /// Select the columns to keep
     /// Return the path of the output file which is a temporary file
     fn select_expected_columns(
@@ -144,9 +277,7 @@ pub trait CheckData {
     ) -> Result<(), Box<dyn Error>> {
         let delimiter = get_delimiter(in_filepath)?;
         debug!("The delimiter is: {:?}", delimiter as char);
-        let mut reader = csv::ReaderBuilder::new()
-            .delimiter(delimiter)
-            .from_path(in_filepath)?;
+        let mut reader = open_csv_reader(in_filepath, delimiter)?;
 
         let headers = reader.headers()?.clone();
         debug!("The headers are: {:?}", headers);
@@ -197,9 +328,7 @@ pub trait CheckData {
 
     fn get_column_names(filepath: &PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
         let delimiter = get_delimiter(filepath)?;
-        let mut reader = csv::ReaderBuilder::new()
-            .delimiter(delimiter)
-            .from_path(filepath)?;
+        let mut reader = open_csv_reader(filepath, delimiter)?;
 
         let headers = reader.headers()?;
         let mut column_names = Vec::new();
@@ -218,6 +347,357 @@ pub trait CheckData {
     }
 }
 
+/// The tables whose records are soft deleted, a deleted record is kept with its deletion time until it is purged by `biomedgps-cli purgedb`.
+pub const SOFT_DELETE_TABLES: [&str; 2] = ["biomedgps_knowledge_curation", "biomedgps_subgraph"];
+
+/// Quote an identifier (such as a table name) for the sql. Only the lowercase identifiers are allowed, so the quoted identifier never needs escaping.
+///
+/// ```
+/// use biomedgps::model::core::quote_identifier;
+///
+/// assert_eq!(quote_identifier("biomedgps_entity").unwrap(), r#""biomedgps_entity""#);
+/// assert!(quote_identifier("biomedgps_entity; DROP TABLE biomedgps_entity").is_err());
+/// assert!(quote_identifier(r#"biomedgps_entity""#).is_err());
+/// ```
+pub fn quote_identifier(name: &str) -> Result<String, ValidationError> {
+    if !IDENTIFIER_REGEX.is_match(name) {
+        return Err(ValidationError::new(&format!(
+            "Invalid identifier: {}, it must match the pattern `^[a-z_][a-z0-9_]*$` and be at most 63 characters.",
+            name
+        )));
+    }
+
+    Ok(format!("\"{}\"", name))
+}
+
+/// The tables which can be queried by the query DSL. The functions which build the sql from a query (such as `RecordResponse::get_records`) take a table name instead of a string, so only the known tables can ever be queried even if a table comes from the user input.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TableName {
+    Entity,
+    Relation,
+    KnowledgeCuration,
+    Entity2D,
+    Subgraph,
+    EntityEmbedding,
+}
+
+impl TableName {
+    pub const ALL: [TableName; 6] = [
+        TableName::Entity,
+        TableName::Relation,
+        TableName::KnowledgeCuration,
+        TableName::Entity2D,
+        TableName::Subgraph,
+        TableName::EntityEmbedding,
+    ];
+
+    /// The name of the table in the database, such as biomedgps_entity.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableName::Entity => "biomedgps_entity",
+            TableName::Relation => "biomedgps_relation",
+            TableName::KnowledgeCuration => "biomedgps_knowledge_curation",
+            TableName::Entity2D => "biomedgps_entity2d",
+            TableName::Subgraph => "biomedgps_subgraph",
+            TableName::EntityEmbedding => "biomedgps_entity_embedding",
+        }
+    }
+
+    /// The name without the prefix, such as entity, it is used by the aggregate endpoint and the saved queries.
+    pub fn short_name(&self) -> &'static str {
+        self.as_str().trim_start_matches("biomedgps_")
+    }
+
+    /// Parse the name without the prefix, such as `knowledge_curation`.
+    ///
+    /// ```
+    /// use biomedgps::model::core::{quote_identifier, TableName};
+    ///
+    /// assert_eq!(TableName::from_short_name("knowledge_curation").unwrap(), TableName::KnowledgeCuration);
+    /// assert!(TableName::from_short_name("biomedgps_entity").is_err());
+    /// assert!(TableName::from_short_name("entity; DROP TABLE biomedgps_entity").is_err());
+    ///
+    /// for table in TableName::ALL {
+    ///     assert_eq!(TableName::from_short_name(table.short_name()).unwrap(), table);
+    ///     assert_eq!(quote_identifier(table.as_str()).unwrap(), table.quoted());
+    /// }
+    /// ```
+    pub fn from_short_name(name: &str) -> Result<TableName, ValidationError> {
+        TableName::ALL
+            .into_iter()
+            .find(|table| table.short_name() == name)
+            .ok_or_else(|| {
+                ValidationError::new(&format!(
+                    "Invalid table: {}, it must be one of {}.",
+                    name,
+                    TableName::ALL
+                        .iter()
+                        .map(|table| table.short_name())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ))
+            })
+    }
+
+    /// The quoted name which is formatted into the sql, such as `"biomedgps_entity"`.
+    pub fn quoted(&self) -> String {
+        format!("\"{}\"", self.as_str())
+    }
+
+    /// Whether the records of the table are soft deleted, see `SOFT_DELETE_TABLES`.
+    pub fn is_soft_deleted(&self) -> bool {
+        SOFT_DELETE_TABLES.contains(&self.as_str())
+    }
+}
+
+impl std::fmt::Display for TableName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.quoted())
+    }
+}
+
+/// Exclude the soft-deleted records from the where clause if the table supports the soft deletion.
+///
+/// ```
+/// use biomedgps::model::core::{exclude_deleted_records, TableName};
+///
+/// assert_eq!(exclude_deleted_records(TableName::Subgraph, "owner = 'a'".to_string()), "(owner = 'a') AND deleted_at IS NULL");
+/// assert_eq!(exclude_deleted_records(TableName::Relation, "1=1".to_string()), "1=1");
+/// ```
+pub fn exclude_deleted_records(table: TableName, query_str: String) -> String {
+    if table.is_soft_deleted() {
+        format!("({}) AND deleted_at IS NULL", query_str)
+    } else {
+        query_str
+    }
+}
+
+lazy_static! {
+    // The named databases are migrated by the same migrations, so the types are cached per table.
+    static ref COLUMN_TYPES: Mutex<HashMap<String, ColumnTypes>> = Mutex::new(HashMap::new());
+}
+
+/// The types of the columns of a table, they are used to cast the bound strings (see `ColumnTypes`) and cached because they only change with the migrations.
+pub(crate) async fn get_column_types(
+    pool: &sqlx::PgPool,
+    table: TableName,
+) -> Result<ColumnTypes, anyhow::Error> {
+    let cached = COLUMN_TYPES.lock().unwrap().get(table.as_str()).cloned();
+    if let Some(column_types) = cached {
+        return AnyOk(column_types);
+    }
+
+    let columns = sqlx::query_as::<_, (String, String)>(
+        "SELECT column_name::TEXT, udt_name::TEXT FROM information_schema.columns
+         WHERE table_schema = 'public' AND table_name = $1",
+    )
+    .bind(table.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    let column_types = columns.into_iter().collect::<ColumnTypes>();
+    COLUMN_TYPES
+        .lock()
+        .unwrap()
+        .insert(table.as_str().to_string(), column_types.clone());
+    AnyOk(column_types)
+}
+
+/// Build the where, order by and pagination clauses for fetching the records of a table. The values of the where clause are the params, they are bound by `bind_values` in the same order.
+pub(crate) async fn make_record_clauses(
+    pool: &sqlx::PgPool,
+    table: TableName,
+    query: &Option<ComposeQuery>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+    order_by: Option<&str>,
+) -> Result<(String, Vec<Value>, String, String), anyhow::Error> {
+    let (mut query_str, params) = match query {
+        Some(query) => query.format_with_params(&get_column_types(pool, table).await?),
+        None => ("".to_string(), vec![]),
+    };
+
+    if query_str.is_empty() {
+        query_str = "1=1".to_string();
+    };
+
+    let order_by_str = if order_by.is_none() {
+        "".to_string()
+    } else {
+        format!("ORDER BY {}", order_by.unwrap())
+    };
+
+    let pagination_str = if page.is_none() && page_size.is_none() {
+        "LIMIT 10 OFFSET 0".to_string()
+    } else {
+        let page = match page {
+            Some(page) => page,
+            None => 1,
+        };
+
+        let page_size = match page_size {
+            Some(page_size) => page_size,
+            None => 10,
+        };
+
+        let limit = page_size;
+        let offset = (page - 1) * page_size;
+
+        format!("LIMIT {} OFFSET {}", limit, offset)
+    };
+
+    AnyOk((query_str, params, order_by_str, pagination_str))
+}
+
+/// How to count the total number of the records of a list request. The exact count is slow on the large tables (such as the relation table), so the list endpoints can use an estimated or a cached count instead.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CountStrategy {
+    /// Run `SELECT COUNT(*)` for each request.
+    Exact,
+    /// Use the statistics of the table (`pg_class.reltuples`) or the row estimate of the query plan, it may be inaccurate if the table is not analyzed recently.
+    Estimated,
+    /// Use the exact count which is cached for a while (see `set_count_cache_ttl`), the cache is keyed on the database, the table and the query.
+    Cached,
+}
+
+// The max number of the cached counts, the expired ones are removed when it is exceeded.
+const MAX_COUNT_CACHE_ENTRIES: usize = 10000;
+
+lazy_static! {
+    static ref COUNT_CACHE: Mutex<HashMap<(String, u64), (u64, Instant)>> =
+        Mutex::new(HashMap::new());
+}
+
+async fn count_exact(
+    pool: &sqlx::PgPool,
+    table: TableName,
+    query_str: &str,
+    params: &[Value],
+) -> Result<u64, anyhow::Error> {
+    let sql_str = format!("SELECT COUNT(*) FROM {} WHERE {}", table, query_str);
+    let total = with_statement_timeout(
+        &sql_str,
+        bind_values(sqlx::query_as::<_, (i64,)>(sql_str.as_str()), params).fetch_one(pool),
+    )
+    .await?;
+    AnyOk(total.0 as u64)
+}
+
+/// Estimate the number of the records, None if the table has no statistics.
+async fn count_estimated(
+    pool: &sqlx::PgPool,
+    table: TableName,
+    query_str: &str,
+    params: &[Value],
+) -> Result<Option<u64>, anyhow::Error> {
+    if query_str == "1=1" {
+        let sql_str = "SELECT reltuples::BIGINT FROM pg_class WHERE oid = to_regclass($1)";
+        let total = with_query_timeout(
+            sqlx::query_as::<_, (i64,)>(sql_str)
+                .bind(table.as_str())
+                .fetch_optional(pool),
+        )
+        .await?;
+
+        // The reltuples is -1 if the table has never been analyzed.
+        return AnyOk(total.map(|t| t.0).filter(|t| *t >= 0).map(|t| t as u64));
+    }
+
+    // The first line of the plan is like `Seq Scan on biomedgps_relation  (cost=0.00..1.04 rows=2 width=0)`.
+    let sql_str = format!("EXPLAIN SELECT 1 FROM {} WHERE {}", table, query_str);
+    let plan = with_query_timeout(
+        bind_values(sqlx::query_as::<_, (String,)>(sql_str.as_str()), params).fetch_all(pool),
+    )
+    .await?;
+    AnyOk(plan.first().and_then(|line| {
+        line.0
+            .split_whitespace()
+            .find_map(|item| item.strip_prefix("rows="))
+            .and_then(|rows| rows.parse::<u64>().ok())
+    }))
+}
+
+async fn count_cached(
+    pool: &sqlx::PgPool,
+    table: TableName,
+    query_str: &str,
+    params: &[Value],
+) -> Result<u64, anyhow::Error> {
+    // The counts of the named databases are kept apart.
+    let mut hasher = DefaultHasher::new();
+    current_database().hash(&mut hasher);
+    query_str.hash(&mut hasher);
+    // The floats can't be hashed, so the params are hashed as their debug strings.
+    format!("{:?}", params).hash(&mut hasher);
+    let key = (table.as_str().to_string(), hasher.finish());
+    let ttl = Duration::from_secs(get_count_cache_ttl());
+
+    if let Some((total, cached_at)) = COUNT_CACHE.lock().unwrap().get(&key) {
+        if cached_at.elapsed() < ttl {
+            debug!("Use the cached count of {}: {}", table.as_str(), total);
+            return AnyOk(*total);
+        }
+    }
+
+    let total = count_exact(pool, table, query_str, params).await?;
+    let mut cache = COUNT_CACHE.lock().unwrap();
+    if cache.len() >= MAX_COUNT_CACHE_ENTRIES {
+        cache.retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+        if cache.len() >= MAX_COUNT_CACHE_ENTRIES {
+            cache.clear();
+        }
+    }
+    cache.insert(key, (total, Instant::now()));
+
+    AnyOk(total)
+}
+
+/// Remove all cached counts, it returns the number of the removed counts.
+pub fn clear_count_cache() -> usize {
+    let mut cache = COUNT_CACHE.lock().unwrap();
+    let count = cache.len();
+    cache.clear();
+    count
+}
+
+/// Count the records which match the where clause with the strategy, the estimated count falls back to the exact count if the table has no statistics. The params of the where clause are bound by `bind_values`.
+pub async fn count_records(
+    pool: &sqlx::PgPool,
+    table: TableName,
+    query_str: &str,
+    params: &[Value],
+    strategy: CountStrategy,
+) -> Result<u64, anyhow::Error> {
+    match strategy {
+        CountStrategy::Exact => count_exact(pool, table, query_str, params).await,
+        CountStrategy::Estimated => match count_estimated(pool, table, query_str, params).await? {
+            Some(total) => AnyOk(total),
+            None => count_exact(pool, table, query_str, params).await,
+        },
+        CountStrategy::Cached => count_cached(pool, table, query_str, params).await,
+    }
+}
+
+/// Compute the paging metadata of a list response, i.e. the total number of pages and whether there is a next page.
+///
+/// ```
+/// use biomedgps::model::core::paging_metadata;
+///
+/// assert_eq!(paging_metadata(0, 1, 10), (0, false));
+/// assert_eq!(paging_metadata(25, 2, 10), (3, true));
+/// assert_eq!(paging_metadata(30, 3, 10), (3, false));
+/// ```
+pub fn paging_metadata(total: u64, page: u64, page_size: u64) -> (u64, bool) {
+    if page_size == 0 {
+        return (0, false);
+    }
+
+    let total_pages = total.div_ceil(page_size);
+    (total_pages, page < total_pages)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
 pub struct RecordResponse<S>
 where
@@ -239,6 +719,10 @@ where
     pub page: u64,
     /// default 10
     pub page_size: u64,
+    /// the total number of pages
+    pub total_pages: u64,
+    /// whether there is a next page
+    pub has_next: bool,
 }
 
 impl<
@@ -255,71 +739,320 @@ impl<
 {
     pub async fn get_records(
         pool: &sqlx::PgPool,
-        table_name: &str,
+        table: TableName,
         query: &Option<ComposeQuery>,
         page: Option<u64>,
         page_size: Option<u64>,
         order_by: Option<&str>,
     ) -> Result<RecordResponse<S>, anyhow::Error> {
-        let mut query_str = match query {
-            Some(ComposeQuery::QueryItem(item)) => item.format(),
-            Some(ComposeQuery::ComposeQueryItem(item)) => item.format(),
-            None => "".to_string(),
-        };
+        Self::get_records_with_count(
+            pool,
+            table,
+            query,
+            page,
+            page_size,
+            order_by,
+            CountStrategy::Exact,
+        )
+        .await
+    }
 
-        if query_str.is_empty() {
-            query_str = "1=1".to_string();
-        };
+    /// Same as `get_records`, but the total number of the records is counted with the strategy.
+    pub async fn get_records_with_count(
+        pool: &sqlx::PgPool,
+        table: TableName,
+        query: &Option<ComposeQuery>,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+        count_strategy: CountStrategy,
+    ) -> Result<RecordResponse<S>, anyhow::Error> {
+        let (query_str, params, order_by_str, pagination_str) =
+            make_record_clauses(pool, table, query, page, page_size, order_by).await?;
+        let query_str = exclude_deleted_records(table, query_str);
 
-        let order_by_str = if order_by.is_none() {
-            "".to_string()
-        } else {
-            format!("ORDER BY {}", order_by.unwrap())
-        };
+        let sql_str = format!(
+            "SELECT * FROM {} WHERE {} {} {}",
+            table, query_str, order_by_str, pagination_str
+        );
 
-        let pagination_str = if page.is_none() && page_size.is_none() {
-            "LIMIT 10 OFFSET 0".to_string()
-        } else {
-            let page = match page {
-                Some(page) => page,
-                None => 1,
-            };
+        let records = with_statement_timeout(
+            &sql_str,
+            bind_values(sqlx::query_as::<_, S>(sql_str.as_str()), &params).fetch_all(pool),
+        )
+        .await?;
 
-            let page_size = match page_size {
-                Some(page_size) => page_size,
-                None => 10,
-            };
+        let total = count_records(pool, table, &query_str, &params, count_strategy).await?;
+        let page = page.unwrap_or(1);
+        let page_size = page_size.unwrap_or(10);
+        let (total_pages, has_next) = paging_metadata(total, page, page_size);
 
-            let limit = page_size;
-            let offset = (page - 1) * page_size;
+        AnyOk(RecordResponse {
+            records: records,
+            total,
+            page,
+            page_size,
+            total_pages,
+            has_next,
+        })
+    }
+}
 
-            format!("LIMIT {} OFFSET {}", limit, offset)
-        };
+/// The records which only contain the selected columns, the other columns are null.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct ProjectedRecordResponse {
+    /// data
+    pub records: Vec<serde_json::Value>,
+    /// total num
+    pub total: u64,
+    /// current page index
+    pub page: u64,
+    /// default 10
+    pub page_size: u64,
+    /// the total number of pages
+    pub total_pages: u64,
+    /// whether there is a next page
+    pub has_next: bool,
+}
+
+impl ProjectedRecordResponse {
+    /// Fetch the selected columns of the records, the fields must be checked by `CheckData::parse_selected_fields` before calling this function. The total number of the records is counted with the strategy.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_records<S: CheckData>(
+        pool: &sqlx::PgPool,
+        table: TableName,
+        fields: &Vec<String>,
+        query: &Option<ComposeQuery>,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+        count_strategy: CountStrategy,
+    ) -> Result<ProjectedRecordResponse, anyhow::Error> {
+        let (query_str, params, order_by_str, pagination_str) =
+            make_record_clauses(pool, table, query, page, page_size, order_by).await?;
+        let query_str = exclude_deleted_records(table, query_str);
 
+        // We don't enable the json feature of sqlx, so we convert the row to a json string in the database.
         let sql_str = format!(
-            "SELECT * FROM {} WHERE {} {} {}",
-            table_name, query_str, order_by_str, pagination_str
+            "SELECT row_to_json(t)::TEXT FROM (SELECT {} FROM {} WHERE {} {} {}) t",
+            fields.join(", "),
+            table,
+            query_str,
+            order_by_str,
+            pagination_str
         );
 
-        let records = sqlx::query_as::<_, S>(sql_str.as_str())
-            .fetch_all(pool)
-            .await?;
-
-        let sql_str = format!("SELECT COUNT(*) FROM {} WHERE {}", table_name, query_str);
+        let rows = with_statement_timeout(
+            &sql_str,
+            bind_values(sqlx::query_as::<_, (String,)>(sql_str.as_str()), &params).fetch_all(pool),
+        )
+        .await?;
+
+        let mut records = vec![];
+        for row in rows {
+            let mut record: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&row.0)?;
+            for field in S::selectable_fields() {
+                record.entry(field).or_insert(serde_json::Value::Null);
+            }
+            records.push(serde_json::Value::Object(record));
+        }
 
-        let total = sqlx::query_as::<_, (i64,)>(sql_str.as_str())
-            .fetch_one(pool)
-            .await?;
+        let total = count_records(pool, table, &query_str, &params, count_strategy).await?;
+        let page = page.unwrap_or(1);
+        let page_size = page_size.unwrap_or(10);
+        let (total_pages, has_next) = paging_metadata(total, page, page_size);
 
-        AnyOk(RecordResponse {
+        AnyOk(ProjectedRecordResponse {
             records: records,
-            total: total.0 as u64,
-            page: page.unwrap_or(1),
-            page_size: page_size.unwrap_or(10),
+            total,
+            page,
+            page_size,
+            total_pages,
+            has_next,
         })
     }
 }
 
+/// The format of the records of a list endpoint (such as `/api/v1/relations`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    /// The paged JSON response.
+    Json,
+    /// All the matched records as newline-delimited JSON, one record per line.
+    Ndjson,
+    /// All the matched records as CSV, the first line is the header.
+    Csv,
+}
+
+impl RecordFormat {
+    /// Decide the format by the `format` query parameter, otherwise by the Accept header, such as `application/x-ndjson` or `text/csv`.
+    ///
+    /// ```
+    /// use biomedgps::model::core::RecordFormat;
+    ///
+    /// assert_eq!(RecordFormat::negotiate(None, Some("application/x-ndjson")), RecordFormat::Ndjson);
+    /// assert_eq!(RecordFormat::negotiate(Some(RecordFormat::Csv), Some("application/json")), RecordFormat::Csv);
+    /// assert_eq!(RecordFormat::negotiate(None, Some("*/*")), RecordFormat::Json);
+    /// ```
+    pub fn negotiate(format: Option<RecordFormat>, accept: Option<&str>) -> RecordFormat {
+        if let Some(format) = format {
+            return format;
+        }
+
+        let accept = accept.unwrap_or("");
+        let accepts = |content_type: &str| {
+            accept
+                .split(',')
+                .any(|item| item.split(';').next().unwrap_or("").trim() == content_type)
+        };
+
+        if accepts("application/x-ndjson") {
+            RecordFormat::Ndjson
+        } else if accepts("text/csv") {
+            RecordFormat::Csv
+        } else {
+            RecordFormat::Json
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RecordFormat::Json => "application/json",
+            RecordFormat::Ndjson => "application/x-ndjson",
+            RecordFormat::Csv => "text/csv",
+        }
+    }
+}
+
+// The max number of the formatted records which are waiting to be sent. The cursor is paused when the client is slower than the database, so the records are not buffered in the server.
+const STREAM_CHANNEL_SIZE: usize = 256;
+
+fn format_csv_line(values: &[String]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(values)?;
+    AnyOk(writer.into_inner()?)
+}
+
+/// Format a record (a json object which is made by `row_to_json`) as a NDJSON or CSV line. The CSV columns are in the order of the columns, the null values are empty and the nested values are json strings.
+fn format_record_line(
+    record: &str,
+    columns: &[String],
+    format: RecordFormat,
+) -> Result<Vec<u8>, anyhow::Error> {
+    match format {
+        RecordFormat::Csv => {
+            let record: serde_json::Map<String, serde_json::Value> = serde_json::from_str(record)?;
+            let values = columns
+                .iter()
+                .map(|column| match record.get(column) {
+                    None | Some(serde_json::Value::Null) => "".to_string(),
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect::<Vec<String>>();
+            format_csv_line(&values)
+        }
+        _ => {
+            let mut line = record.as_bytes().to_vec();
+            line.push(b'\n');
+            AnyOk(line)
+        }
+    }
+}
+
+/// Stream all the records which match the query (without pagination) from a database cursor, they are formatted line by line as NDJSON or CSV. Only the selected columns are returned if the fields are specified, the fields must be checked by `CheckData::parse_selected_fields`.
+///
+/// The records are read in a background task and sent through a bounded channel, the task stops when the stream is dropped (such as the client disconnects). If the query fails after the first record, the stream ends with an error, so the client gets a truncated response instead of a complete one.
+pub fn stream_records<S: CheckData>(
+    pool: Arc<sqlx::PgPool>,
+    table: TableName,
+    fields: Option<Vec<String>>,
+    query: &Option<ComposeQuery>,
+    order_by: Option<&str>,
+    format: RecordFormat,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static {
+    let query = query.clone();
+    let order_by = order_by.map(|order_by| order_by.to_string());
+    let columns = fields.unwrap_or_else(S::selectable_fields);
+    let table_name = table.as_str();
+
+    let (sender, receiver) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        let to_io_error = |e: anyhow::Error| std::io::Error::new(std::io::ErrorKind::Other, e);
+
+        let (query_str, params, order_by_str, _) = match make_record_clauses(
+            &pool,
+            table,
+            &query,
+            None,
+            None,
+            order_by.as_deref(),
+        )
+        .await
+        {
+            Ok(clauses) => clauses,
+            Err(e) => {
+                error!("Failed to stream the records of {}: {}", table_name, e);
+                let _ = sender.send(Err(to_io_error(e))).await;
+                return;
+            }
+        };
+        let query_str = exclude_deleted_records(table, query_str);
+        // We don't enable the json feature of sqlx, so we convert the row to a json string in the database.
+        let sql_str = format!(
+            "SELECT row_to_json(t)::TEXT FROM (SELECT {} FROM {} WHERE {} {}) t",
+            columns.join(", "),
+            table,
+            query_str,
+            order_by_str
+        );
+
+        if format == RecordFormat::Csv {
+            let header = format_csv_line(&columns).map_err(to_io_error);
+            if sender.send(header).await.is_err() {
+                return;
+            }
+        }
+
+        let mut total = 0;
+        let mut rows =
+            bind_values(sqlx::query_as::<_, (String,)>(sql_str.as_str()), &params).fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            let line = match row {
+                Ok(row) => format_record_line(&row.0, &columns, format),
+                Err(e) => Err(anyhow::Error::from(e)),
+            };
+
+            if let Err(e) = &line {
+                error!("Failed to stream the records of {}: {}", table_name, e);
+            }
+
+            let failed = line.is_err();
+            if sender.send(line.map_err(to_io_error)).await.is_err() {
+                debug!(
+                    "The client stopped receiving the records of {} after {} records.",
+                    table_name, total
+                );
+                return;
+            }
+
+            if failed {
+                return;
+            }
+            total += 1;
+        }
+
+        info!("Streamed {} records of {}.", total, table_name);
+    });
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
 pub struct Entity {
     // Ignore this field when deserialize from json
@@ -398,123 +1131,89 @@ impl CheckData for Entity {
             "xrefs".to_string(),
         ]
     }
-}
 
-fn text2vector<'de, D>(deserializer: D) -> Result<Vector, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    match s
-        .split('|')
-        .map(|s| s.parse().map_err(serde::de::Error::custom))
-        .collect::<Result<Vec<f32>, D::Error>>()
-    {
-        // More details on https://github.com/pgvector/pgvector-rust#sqlx
-        Ok(vec) => Ok(Vector::from(vec)),
-        Err(e) => Err(e),
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["idx".to_string()];
+        fields.extend(Self::fields());
+        fields
     }
-}
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct EmbeddingRecordResponse<S>
-where
-    S: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-        + std::fmt::Debug
-        + std::marker::Unpin
-        + Send
-        + Sync,
-{
-    /// data
-    pub records: Vec<S>,
-    /// total num
-    pub total: u64,
-    /// current page index
-    pub page: u64,
-    /// default 10
-    pub page_size: u64,
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "name".to_string(),
+            "label".to_string(),
+            "resource".to_string(),
+            "taxid".to_string(),
+        ]
+    }
 }
 
-impl<
-        S: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
-            + std::fmt::Debug
-            + std::marker::Unpin
-            + Send
-            + Sync,
-    > EmbeddingRecordResponse<S>
-{
-    pub async fn get_records(
+impl Entity {
+    /// Fetch an entity by its label and id, return None if the entity doesn't exist.
+    pub async fn get_entity(
         pool: &sqlx::PgPool,
-        table_name: &str,
-        query: &Option<ComposeQuery>,
-        page: Option<u64>,
-        page_size: Option<u64>,
-        order_by: Option<&str>,
-    ) -> Result<EmbeddingRecordResponse<S>, anyhow::Error> {
-        let mut query_str = match query {
-            Some(ComposeQuery::QueryItem(item)) => item.format(),
-            Some(ComposeQuery::ComposeQueryItem(item)) => item.format(),
-            None => "".to_string(),
-        };
-
-        if query_str.is_empty() {
-            query_str = "1=1".to_string();
-        };
-
-        let order_by_str = if order_by.is_none() {
-            "".to_string()
-        } else {
-            format!("ORDER BY {}", order_by.unwrap())
-        };
-
-        let pagination_str = if page.is_none() && page_size.is_none() {
-            "LIMIT 10 OFFSET 0".to_string()
-        } else {
-            let page = match page {
-                Some(page) => page,
-                None => 1,
-            };
-
-            let page_size = match page_size {
-                Some(page_size) => page_size,
-                None => 10,
-            };
-
-            let limit = page_size;
-            let offset = (page - 1) * page_size;
+        label: &str,
+        id: &str,
+    ) -> Result<Option<Entity>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_entity WHERE label = $1 AND id = $2";
+        let entity = sqlx::query_as::<_, Entity>(sql_str)
+            .bind(label)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
 
-            format!("LIMIT {} OFFSET {}", limit, offset)
-        };
+        AnyOk(entity)
+    }
+}
 
-        let sql_str = format!(
-            "SELECT * FROM {} WHERE {} {} {}",
-            table_name, query_str, order_by_str, pagination_str
-        );
+/// The value types of the entity attributes, the value is stored as text and parsed by the value type.
+pub const ATTRIBUTE_VALUE_TYPES: [&str; 4] = ["string", "integer", "float", "boolean"];
 
-        let records = sqlx::query_as::<_, S>(sql_str.as_str())
-            .fetch_all(pool)
-            .await?;
+lazy_static! {
+    pub static ref ATTRIBUTE_KEY_REGEX: Regex = Regex::new(r"^[a-z][a-z0-9_]*$").unwrap();
+    // BSYNRYMUTXBXSQ-UHFFFAOYSA-N
+    static ref INCHIKEY_REGEX: Regex = Regex::new(r"^[A-Z]{14}-[A-Z]{10}-[A-Z]$").unwrap();
+    // E11 or E11.9
+    static ref ICD10_REGEX: Regex = Regex::new(r"^[A-Z][0-9]{2}(\.[0-9A-Z]{1,4})?$").unwrap();
+}
 
-        let sql_str = format!("SELECT COUNT(*) FROM {} WHERE {}", table_name, query_str);
+/// Get the value type and the pattern of a well-known attribute, the values of the well-known attributes are checked when they are imported.
+fn get_known_attribute(key: &str) -> Option<(&'static str, Option<&'static Regex>)> {
+    match key {
+        "symbol" | "smiles" | "chromosome" | "icd11" => Some(("string", None)),
+        "inchikey" => Some(("string", Some(&*INCHIKEY_REGEX))),
+        "icd10" => Some(("string", Some(&*ICD10_REGEX))),
+        "molecular_weight" => Some(("float", None)),
+        _ => None,
+    }
+}
 
-        let total = sqlx::query_as::<_, (i64,)>(sql_str.as_str())
-            .fetch_one(pool)
-            .await?;
+fn default_value_type() -> String {
+    "string".to_string()
+}
 
-        AnyOk(EmbeddingRecordResponse {
-            records: records,
-            total: total.0 as u64,
-            page: page.unwrap_or(1),
-            page_size: page_size.unwrap_or(10),
-        })
+fn validate_entity_attribute(
+    attribute: &EntityAttribute,
+) -> Result<(), validator::ValidationError> {
+    match attribute.check_value() {
+        Ok(_) => Ok(()),
+        Err(msg) => {
+            let mut err = validator::ValidationError::new("value");
+            err.message = Some(std::borrow::Cow::from(msg));
+            Err(err)
+        }
     }
 }
 
-/// A struct for entity embedding, it is used for import entity embeddings into database from csv file.
-/// Only for internal use, not for api.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::FromRow, Validate)]
-pub struct EntityEmbedding {
-    pub embedding_id: i64,
+/// A type-specific attribute of an entity, such as the symbol of a gene, the SMILES and InChIKey of a compound or the ICD-10 code of a disease. An entity may have several values of the same key, such as several ICD-10 codes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+#[validate(schema(function = "validate_entity_attribute"))]
+pub struct EntityAttribute {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
 
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
@@ -523,234 +1222,338 @@ pub struct EntityEmbedding {
     ))]
     #[validate(regex(
         path = "ENTITY_ID_REGEX",
-        message = "The entity id should match ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$. Such as 'MESH:D00001'."
+        message = "The entity_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `ENTREZ:7157`."
     ))]
     pub entity_id: String,
 
-    #[validate(length(
-        max = "ENTITY_NAME_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of entity_name should be between 1 and 64."
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must be a valid entity label, such as `Gene`."
     ))]
-    pub entity_name: String,
-
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
         message = "The length of entity_type should be between 1 and 64."
     ))]
+    pub entity_type: String,
+
+    /// The attribute key in snake case, such as symbol, smiles, inchikey and icd10.
     #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The entity type should match ^[A-Za-z]+$. Such as Disease."
+        path = "ATTRIBUTE_KEY_REGEX",
+        message = "The key must be in snake case, such as `inchikey`."
     ))]
-    pub entity_type: String,
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of key should be between 1 and 64."
+    ))]
+    pub key: String,
 
-    #[serde(deserialize_with = "text2vector")]
-    pub embedding: Vector,
+    #[validate(length(min = 1, message = "The value cannot be empty."))]
+    pub value: String,
+
+    /// One of string, integer, float and boolean, the default is string.
+    #[serde(default = "default_value_type")]
+    pub value_type: String,
 }
 
-impl EntityEmbedding {
-    pub fn new(
-        embedding_id: i64,
-        entity_id: &str,
-        entity_name: &str,
-        entity_type: &str,
-        embedding: &Vec<f32>,
-    ) -> EntityEmbedding {
-        EntityEmbedding {
-            embedding_id: embedding_id,
-            entity_id: entity_id.to_string(),
-            entity_name: entity_name.to_string(),
-            entity_type: entity_type.to_string(),
-            embedding: Vector::from(embedding.clone()),
+impl EntityAttribute {
+    /// Check whether the value can be parsed by the value type, and whether the value of a well-known attribute (such as inchikey) has the expected type and pattern.
+    ///
+    /// ```
+    /// use biomedgps::model::core::EntityAttribute;
+    ///
+    /// let mut attribute = EntityAttribute {
+    ///     id: 0,
+    ///     entity_id: "MESH:D008687".to_string(),
+    ///     entity_type: "Compound".to_string(),
+    ///     key: "inchikey".to_string(),
+    ///     value: "XZWYZXLIPXDOLR-UHFFFAOYSA-N".to_string(),
+    ///     value_type: "string".to_string(),
+    /// };
+    /// assert!(attribute.check_value().is_ok());
+    ///
+    /// attribute.value = "metformin".to_string();
+    /// assert!(attribute.check_value().is_err());
+    ///
+    /// attribute.key = "molecular_weight".to_string();
+    /// attribute.value = "129.16".to_string();
+    /// attribute.value_type = "float".to_string();
+    /// assert!(attribute.check_value().is_ok());
+    /// ```
+    pub fn check_value(&self) -> Result<(), String> {
+        if !ATTRIBUTE_VALUE_TYPES.contains(&self.value_type.as_str()) {
+            return Err(format!(
+                "Invalid value_type: {}, it should be one of {}.",
+                self.value_type,
+                ATTRIBUTE_VALUE_TYPES.join(", ")
+            ));
         }
-    }
 
-    pub async fn import_entity_embeddings(
-        pool: &sqlx::PgPool,
-        filepath: &PathBuf,
-        delimiter: u8,
-        drop: bool,
-    ) -> Result<(), Box<dyn Error>> {
-        if drop {
-            drop_table(&pool, "biomedgps_entity_embedding").await;
+        let is_valid = match self.value_type.as_str() {
+            "integer" => self.value.parse::<i64>().is_ok(),
+            "float" => self.value.parse::<f64>().is_ok(),
+            "boolean" => self.value == "true" || self.value == "false",
+            _ => true,
         };
+        if !is_valid {
+            return Err(format!(
+                "The value {} of {} is not a valid {}.",
+                self.value, self.key, self.value_type
+            ));
+        }
 
-        // Build the CSV reader
-        let mut reader = match csv::ReaderBuilder::new()
-            .delimiter(delimiter)
-            .from_path(filepath)
-        {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(Box::new(e));
+        if let Some((value_type, pattern)) = get_known_attribute(&self.key) {
+            if self.value_type != value_type {
+                return Err(format!(
+                    "The value_type of {} should be {}, but got {}.",
+                    self.key, value_type, self.value_type
+                ));
             }
-        };
-
-        for result in reader.deserialize() {
-            let record: EntityEmbedding = match result {
-                Ok(r) => r,
-                Err(e) => {
-                    let error_msg = parse_csv_error(&e);
-                    return Err(Box::new(ValidationError::new(&error_msg)));
-                }
-            };
-
-            let sql_str = "INSERT INTO biomedgps_entity_embedding (embedding_id, entity_id, entity_type, entity_name, embedding) VALUES ($1, $2, $3, $4, $5)";
-
-            let query = sqlx::query(&sql_str)
-                .bind(record.embedding_id)
-                .bind(record.entity_id)
-                .bind(record.entity_type)
-                .bind(record.entity_name)
-                .bind(record.embedding);
 
-            match query.execute(pool).await {
-                Ok(_) => {}
-                Err(e) => {
-                    return Err(Box::new(e));
+            if let Some(pattern) = pattern {
+                if !pattern.is_match(&self.value) {
+                    return Err(format!(
+                        "The value {} of {} doesn't match the pattern {}.",
+                        self.value,
+                        self.key,
+                        pattern.as_str()
+                    ));
                 }
-            };
+            }
         }
 
         Ok(())
     }
+
+    /// Fetch the attributes of an entity, they are sorted by the key.
+    pub async fn get_records_by_entity(
+        pool: &sqlx::PgPool,
+        entity_type: &str,
+        entity_id: &str,
+    ) -> Result<Vec<EntityAttribute>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_entity_attribute WHERE entity_type = $1 AND entity_id = $2 ORDER BY key, id";
+        let records = sqlx::query_as::<_, EntityAttribute>(sql_str)
+            .bind(entity_type)
+            .bind(entity_id)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
 }
 
-impl CheckData for EntityEmbedding {
+impl CheckData for EntityAttribute {
     fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<EntityEmbedding>(filepath)
+        Self::check_csv_is_valid_default::<EntityAttribute>(filepath)
     }
 
     fn unique_fields() -> Vec<String> {
-        vec!["entity_id".to_string(), "entity_type".to_string()]
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "key".to_string(),
+            "value".to_string(),
+        ]
     }
 
     fn fields() -> Vec<String> {
         vec![
-            "embedding_id".to_string(),
             "entity_id".to_string(),
             "entity_type".to_string(),
-            "entity_name".to_string(),
-            "embedding".to_string(),
+            "key".to_string(),
+            "value".to_string(),
+            "value_type".to_string(),
         ]
     }
-}
 
-#[derive(Debug, Clone, Deserialize, PartialEq, sqlx::FromRow, Validate)]
-pub struct RelationEmbedding {
-    pub embedding_id: i64,
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of relation_type should be between 1 and 64."
-    ))]
-    pub relation_type: String,
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "key".to_string(),
+        ]
+    }
+}
 
-    #[serde(deserialize_with = "text2vector")]
-    pub embedding: Vector,
+fn text2vector<'de, D>(deserializer: D) -> Result<Vector, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    match s
+        .split('|')
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect::<Result<Vec<f32>, D::Error>>()
+    {
+        // More details on https://github.com/pgvector/pgvector-rust#sqlx
+        Ok(vec) => Ok(Vector::from(vec)),
+        Err(e) => Err(e),
+    }
 }
 
-impl RelationEmbedding {
-    pub async fn import_relation_embeddings(
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct EmbeddingRecordResponse<S>
+where
+    S: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+        + std::fmt::Debug
+        + std::marker::Unpin
+        + Send
+        + Sync,
+{
+    /// data
+    pub records: Vec<S>,
+    /// total num
+    pub total: u64,
+    /// current page index
+    pub page: u64,
+    /// default 10
+    pub page_size: u64,
+}
+
+impl<
+        S: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+            + std::fmt::Debug
+            + std::marker::Unpin
+            + Send
+            + Sync,
+    > EmbeddingRecordResponse<S>
+{
+    pub async fn get_records(
         pool: &sqlx::PgPool,
-        filepath: &PathBuf,
-        delimiter: u8,
-        drop: bool,
-    ) -> Result<(), Box<dyn Error>> {
-        if drop {
-            drop_table(&pool, "biomedgps_relation_embedding").await;
-        };
+        table: TableName,
+        query: &Option<ComposeQuery>,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+    ) -> Result<EmbeddingRecordResponse<S>, anyhow::Error> {
+        let (query_str, params, order_by_str, pagination_str) =
+            make_record_clauses(pool, table, query, page, page_size, order_by).await?;
 
-        // Build the CSV reader
-        let mut reader = match csv::ReaderBuilder::new()
-            .delimiter(delimiter)
-            .from_path(filepath)
-        {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(Box::new(e));
-            }
-        };
+        let sql_str = format!(
+            "SELECT * FROM {} WHERE {} {} {}",
+            table, query_str, order_by_str, pagination_str
+        );
 
-        for result in reader.deserialize() {
-            let record: RelationEmbedding = match result {
-                Ok(r) => r,
-                Err(e) => {
-                    let error_msg = parse_csv_error(&e);
-                    return Err(Box::new(ValidationError::new(&error_msg)));
-                }
-            };
+        let records = with_statement_timeout(
+            &sql_str,
+            bind_values(sqlx::query_as::<_, S>(sql_str.as_str()), &params).fetch_all(pool),
+        )
+        .await?;
 
-            let sql_str = "INSERT INTO biomedgps_relation_embedding (embedding_id, relation_type, embedding) VALUES ($1, $2, $3)";
+        let sql_str = format!("SELECT COUNT(*) FROM {} WHERE {}", table, query_str);
 
-            let query = sqlx::query(&sql_str)
-                .bind(record.embedding_id)
-                .bind(record.relation_type)
-                .bind(record.embedding);
+        let total = with_query_timeout(
+            bind_values(sqlx::query_as::<_, (i64,)>(sql_str.as_str()), &params).fetch_one(pool),
+        )
+        .await?;
 
-            match query.execute(pool).await {
-                Ok(_) => {}
-                Err(e) => {
-                    return Err(Box::new(e));
-                }
-            };
-        }
-
-        Ok(())
+        AnyOk(EmbeddingRecordResponse {
+            records: records,
+            total: total.0 as u64,
+            page: page.unwrap_or(1),
+            page_size: page_size.unwrap_or(10),
+        })
     }
 }
 
-impl CheckData for RelationEmbedding {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<RelationEmbedding>(filepath)
-    }
+/// The max number of the mismatched lines in the error message of the dimension check.
+const MAX_DIMENSION_ERRORS: usize = 10;
+
+/// Check whether all embeddings in a csv file have the same dimension, the expected dimension is the dimension of the first embedding if it is None.
+///
+/// # Returns
+///
+/// * `Ok(usize)` - The dimension of the embeddings.
+/// * `Err(ValidationError)` - The line numbers (the header is line 1) of the mismatched or invalid embeddings.
+pub fn check_embedding_dimensions<S>(
+    filepath: &PathBuf,
+    delimiter: u8,
+    expected_dimension: Option<usize>,
+    get_dimension: impl Fn(&S) -> usize,
+) -> Result<usize, ValidationError>
+where
+    S: for<'de> serde::Deserialize<'de>,
+{
+    let mut reader = match open_csv_reader(filepath, delimiter) {
+        Ok(r) => r,
+        Err(e) => return Err(ValidationError::new(&parse_csv_error(&e))),
+    };
+
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return Err(ValidationError::new(&parse_csv_error(&e))),
+    };
+
+    let mut expected_dimension = expected_dimension;
+    let mut errors: Vec<String> = vec![];
+    let mut num_errors = 0;
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => return Err(ValidationError::new(&parse_csv_error(&e))),
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        let message = match record.deserialize::<S>(Some(&headers)) {
+            Ok(embedding) => {
+                let dimension = get_dimension(&embedding);
+                match expected_dimension {
+                    Some(expected) if expected != dimension => Some(format!(
+                        "Line {}: the dimension of the embedding is {}, but {} is expected.",
+                        line, dimension, expected
+                    )),
+                    Some(_) => None,
+                    None => {
+                        expected_dimension = Some(dimension);
+                        None
+                    }
+                }
+            }
+            Err(e) => Some(format!("Line {}: {}", line, parse_csv_error(&e))),
+        };
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "relation_type".to_string(),
-            "source_id".to_string(),
-            "source_type".to_string(),
-            "target_id".to_string(),
-            "target_type".to_string(),
-        ]
+        if let Some(message) = message {
+            num_errors += 1;
+            if errors.len() < MAX_DIMENSION_ERRORS {
+                errors.push(message);
+            }
+        }
     }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "embedding_id".to_string(),
-            "relation_type".to_string(),
-            "source_id".to_string(),
-            "source_type".to_string(),
-            "target_id".to_string(),
-            "target_type".to_string(),
-            "embedding".to_string(),
-        ]
+    if num_errors > 0 {
+        if num_errors > errors.len() {
+            errors.push(format!("... and {} more lines.", num_errors - errors.len()));
+        }
+        return Err(ValidationError::new(&format!(
+            "Found {} invalid embeddings in {}:\n{}",
+            num_errors,
+            filepath.display(),
+            errors.join("\n")
+        )));
     }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
-pub struct Statistics {
-    entity_stat: Vec<EntityMetadata>,
-    relation_stat: Vec<RelationMetadata>,
-}
 
-impl Statistics {
-    pub fn new(
-        entity_stat: Vec<EntityMetadata>,
-        relation_stat: Vec<RelationMetadata>,
-    ) -> Statistics {
-        Statistics {
-            entity_stat: entity_stat,
-            relation_stat: relation_stat,
-        }
+    match expected_dimension {
+        Some(dimension) => Ok(dimension),
+        None => Err(ValidationError::new(&format!(
+            "No embeddings found in {}.",
+            filepath.display()
+        ))),
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
-pub struct EntityMetadata {
-    // Ignore this field when deserialize from json
+/// The name of the embedding model which the embeddings imported before the model registry belong to.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "default";
+
+/// A registered embedding model, the entity and relation embeddings are tagged by the model name, so we can keep multiple versions of the embeddings in one database.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
+pub struct EmbeddingModel {
     #[serde(skip_deserializing)]
     #[oai(read_only)]
     pub id: i64,
@@ -758,442 +1561,2667 @@ pub struct EntityMetadata {
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of resource should be between 1 and 64."
+        message = "The length of model_name should be between 1 and 64."
     ))]
-    pub resource: String,
+    pub model_name: String,
 
-    #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The entity type should match ^[A-Za-z]+$. Such as Disease."
-    ))]
+    /// Such as TransE, DistMult, etc.
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of entity_type should be between 1 and 64."
+        message = "The length of algorithm should be between 1 and 64."
     ))]
-    pub entity_type: String,
+    pub algorithm: String,
 
-    pub entity_count: i64,
+    pub dimension: i32,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub trained_at: Option<DateTime<Utc>>,
+
+    /// The evaluation metrics, a json string such as `{"mrr": 0.35, "hits@10": 0.52}`.
+    #[validate(regex(
+        path = "JSON_REGEX",
+        message = "The metrics must be a valid json string."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub metrics: Option<String>,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    #[serde(skip_deserializing)]
+    #[serde(with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
 }
 
-impl CheckData for EntityMetadata {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<EntityMetadata>(filepath)
+impl EmbeddingModel {
+    /// Create a model which is not registered yet, the dimension is detected when the embeddings are imported.
+    pub fn new(
+        model_name: &str,
+        algorithm: &str,
+        trained_at: Option<DateTime<Utc>>,
+        metrics: Option<String>,
+        description: Option<String>,
+    ) -> Self {
+        EmbeddingModel {
+            id: 0,
+            model_name: model_name.to_string(),
+            algorithm: algorithm.to_string(),
+            dimension: 0,
+            trained_at,
+            metrics,
+            description,
+            created_at: Utc::now(),
+        }
     }
 
-    fn unique_fields() -> Vec<String> {
-        vec!["resource".to_string(), "entity_type".to_string()]
-    }
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<EmbeddingModel>, anyhow::Error> {
+        let records = sqlx::query_as::<_, EmbeddingModel>(
+            "SELECT * FROM biomedgps_embedding_model ORDER BY created_at DESC",
+        )
+        .fetch_all(pool)
+        .await?;
 
-    fn fields() -> Vec<String> {
-        vec![
-            "resource".to_string(),
-            "entity_type".to_string(),
-            "entity_count".to_string(),
-        ]
+        AnyOk(records)
     }
-}
 
-impl EntityMetadata {
-    pub async fn get_entity_metadata(
+    pub async fn get_record(
         pool: &sqlx::PgPool,
-    ) -> Result<Vec<EntityMetadata>, anyhow::Error> {
-        let sql_str = "SELECT * FROM biomedgps_entity_metadata";
-        let entity_metadata = sqlx::query_as::<_, EntityMetadata>(sql_str)
-            .fetch_all(pool)
-            .await?;
-
-        AnyOk(entity_metadata)
+        model_name: &str,
+    ) -> Result<Option<EmbeddingModel>, anyhow::Error> {
+        let record = sqlx::query_as::<_, EmbeddingModel>(
+            "SELECT * FROM biomedgps_embedding_model WHERE model_name = $1",
+        )
+        .bind(model_name)
+        .fetch_optional(pool)
+        .await?;
+
+        AnyOk(record)
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
-pub struct RelationMetadata {
-    // Ignore this field when deserialize from json
-    #[serde(skip_deserializing)]
-    #[oai(read_only)]
-    pub id: i64,
+    /// Get the model by name, or the latest registered model if the name is not specified.
+    pub async fn resolve(
+        pool: &sqlx::PgPool,
+        model_name: Option<&str>,
+    ) -> Result<EmbeddingModel, anyhow::Error> {
+        let record = match model_name {
+            Some(model_name) => Self::get_record(pool, model_name).await?,
+            None => sqlx::query_as::<_, EmbeddingModel>(
+                "SELECT * FROM biomedgps_embedding_model ORDER BY created_at DESC, id DESC LIMIT 1",
+            )
+            .fetch_optional(pool)
+            .await?,
+        };
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of resource should be between 1 and 64."
-    ))]
-    pub resource: String,
+        match record {
+            Some(record) => AnyOk(record),
+            None => Err(anyhow::anyhow!(
+                "Cannot find the embedding model {}, please import the embeddings first.",
+                model_name.unwrap_or("")
+            )),
+        }
+    }
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of relation_type should be between 1 and 64."
-    ))]
-    pub relation_type: String,
+    /// Register the model or update its metadata if it is already registered.
+    pub async fn upsert(&self, pool: &sqlx::PgPool) -> Result<EmbeddingModel, anyhow::Error> {
+        let sql_str = "INSERT INTO biomedgps_embedding_model (model_name, algorithm, dimension, trained_at, metrics, description)
+                       VALUES ($1, $2, $3, $4, $5, $6)
+                       ON CONFLICT (model_name) DO UPDATE
+                       SET algorithm = $2, dimension = $3,
+                           trained_at = COALESCE($4, biomedgps_embedding_model.trained_at),
+                           metrics = COALESCE($5, biomedgps_embedding_model.metrics),
+                           description = COALESCE($6, biomedgps_embedding_model.description)
+                       RETURNING *";
+        let record = sqlx::query_as::<_, EmbeddingModel>(sql_str)
+            .bind(&self.model_name)
+            .bind(&self.algorithm)
+            .bind(self.dimension)
+            .bind(self.trained_at)
+            .bind(&self.metrics)
+            .bind(&self.description)
+            .fetch_one(pool)
+            .await?;
 
-    pub relation_count: i64,
+        AnyOk(record)
+    }
 
-    #[validate(regex = "ENTITY_LABEL_REGEX")]
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of start_entity_type should be between 1 and 64."
-    ))]
-    pub start_entity_type: String,
+    /// Replace the evaluation metrics of the model, such as the metrics computed by `biomedgps-cli evaluate-embeddings`.
+    pub async fn update_metrics(
+        &self,
+        pool: &sqlx::PgPool,
+        metrics: &str,
+    ) -> Result<EmbeddingModel, anyhow::Error> {
+        let record = sqlx::query_as::<_, EmbeddingModel>(
+            "UPDATE biomedgps_embedding_model SET metrics = $1 WHERE model_name = $2 RETURNING *",
+        )
+        .bind(metrics)
+        .bind(&self.model_name)
+        .fetch_one(pool)
+        .await?;
+
+        AnyOk(record)
+    }
 
-    #[validate(regex = "ENTITY_LABEL_REGEX")]
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of end_entity_type should be between 1 and 64."
-    ))]
-    pub end_entity_type: String,
-}
+    /// Check the dimensions of the embeddings in the file, register (or update) the model with the dimension and delete the existing embeddings of the model if drop is true. It must be called before importing the embeddings.
+    ///
+    /// The expected dimension is the registered dimension of the model. If the model is not registered or drop is true (the embeddings of the model will be replaced), the dimension of the first embedding is expected.
+    pub async fn prepare_import<S>(
+        &mut self,
+        pool: &sqlx::PgPool,
+        filepath: &PathBuf,
+        delimiter: u8,
+        drop: bool,
+        table_name: &str,
+        get_dimension: impl Fn(&S) -> usize,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        S: for<'de> serde::Deserialize<'de>,
+    {
+        let expected_dimension = if drop {
+            None
+        } else {
+            Self::get_record(pool, &self.model_name)
+                .await?
+                .map(|model| model.dimension as usize)
+        };
 
-impl CheckData for RelationMetadata {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<RelationMetadata>(filepath)
-    }
+        let dimension = check_embedding_dimensions::<S>(
+            filepath,
+            delimiter,
+            expected_dimension,
+            get_dimension,
+        )?;
+
+        info!(
+            "The dimension of the embeddings in {} is {}, they belong to the model {}.",
+            filepath.display(),
+            dimension,
+            self.model_name
+        );
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "resource".to_string(),
-            "relation_type".to_string(),
-            "start_entity_type".to_string(),
-            "end_entity_type".to_string(),
-        ]
-    }
+        self.dimension = dimension as i32;
+        *self = self.upsert(pool).await?;
+        if drop {
+            self.delete_embeddings(pool, table_name).await?;
+        }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "resource".to_string(),
-            "relation_type".to_string(),
-            "relation_count".to_string(),
-            "start_entity_type".to_string(),
-            "end_entity_type".to_string(),
-        ]
+        Ok(())
     }
-}
 
-impl RelationMetadata {
-    pub async fn get_relation_metadata(
+    /// Delete the embeddings of the model in a table, it is used to replace the embeddings of a model.
+    pub async fn delete_embeddings(
+        &self,
         pool: &sqlx::PgPool,
-    ) -> Result<Vec<RelationMetadata>, anyhow::Error> {
-        let sql_str = "SELECT * FROM biomedgps_relation_metadata";
-        let relation_metadata = sqlx::query_as::<_, RelationMetadata>(sql_str)
-            .fetch_all(pool)
+        table_name: &str,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(&format!("DELETE FROM {} WHERE model_name = $1", table_name))
+            .bind(&self.model_name)
+            .execute(pool)
             .await?;
 
-        AnyOk(relation_metadata)
+        AnyOk(())
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Object, PartialEq, Eq)]
-pub struct Payload {
-    pub project_id: String,
-    pub organization_id: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
-pub struct KnowledgeCuration {
-    // Ignore this field when deserialize from json
-    #[serde(skip_deserializing)]
-    #[oai(read_only)]
-    pub id: i64,
+/// A struct for entity embedding, it is used for import entity embeddings into database from csv file.
+/// Only for internal use, not for api.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::FromRow, Validate)]
+pub struct EntityEmbedding {
+    pub embedding_id: i64,
 
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of resource must be between 1 and 64."
-    ))]
-    pub relation_type: String,
-
-    #[validate(length(
-        max = "ENTITY_NAME_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
+        message = "The length of entity_id should be between 1 and 64."
     ))]
-    pub source_name: String,
-
     #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The source_type must be a valid entity type. The regex pattern is `^[A-Za-z]+$`, such as `Gene`."
+        path = "ENTITY_ID_REGEX",
+        message = "The entity id should match ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$. Such as 'MESH:D00001'."
     ))]
+    pub entity_id: String,
+
     #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
+        max = "ENTITY_NAME_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_type must be between 1 and 64."
+        message = "The length of entity_name should be between 1 and 64."
     ))]
-    pub source_type: String,
+    pub entity_name: String,
 
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_id must be between 1 and 64."
+        message = "The length of entity_type should be between 1 and 64."
     ))]
     #[validate(regex(
-        path = "ENTITY_ID_REGEX",
-        message = "The source_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `UniProtKB:P12345`."
-    ))]
-    pub source_id: String,
-
-    #[validate(length(
-        max = "ENTITY_NAME_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of target_name must be between 1 and 64."
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity type should match ^[A-Za-z]+$. Such as Disease."
     ))]
-    pub target_name: String,
+    pub entity_type: String,
+
+    #[serde(deserialize_with = "text2vector")]
+    pub embedding: Vector,
+}
+
+impl EntityEmbedding {
+    pub fn new(
+        embedding_id: i64,
+        entity_id: &str,
+        entity_name: &str,
+        entity_type: &str,
+        embedding: &Vec<f32>,
+    ) -> EntityEmbedding {
+        EntityEmbedding {
+            embedding_id: embedding_id,
+            entity_id: entity_id.to_string(),
+            entity_name: entity_name.to_string(),
+            entity_type: entity_type.to_string(),
+            embedding: Vector::from(embedding.clone()),
+        }
+    }
+
+    /// Import the entity embeddings of a model, the file is rejected if the dimensions of the embeddings don't match the model (see [`EmbeddingModel::prepare_import`](struct.EmbeddingModel.html#method.prepare_import)). If drop is true, only the existing embeddings of the model are deleted.
+    pub async fn import_entity_embeddings(
+        pool: &sqlx::PgPool,
+        filepath: &PathBuf,
+        delimiter: u8,
+        drop: bool,
+        model: &EmbeddingModel,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut model = model.clone();
+        model
+            .prepare_import::<EntityEmbedding>(
+                pool,
+                filepath,
+                delimiter,
+                drop,
+                "biomedgps_entity_embedding",
+                |record| record.embedding.to_vec().len(),
+            )
+            .await?;
+
+        // Build the CSV reader
+        let mut reader = match open_csv_reader(filepath, delimiter) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Box::new(e));
+            }
+        };
+
+        for result in reader.deserialize() {
+            let record: EntityEmbedding = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    let error_msg = parse_csv_error(&e);
+                    return Err(Box::new(ValidationError::new(&error_msg)));
+                }
+            };
+
+            let sql_str = "INSERT INTO biomedgps_entity_embedding (embedding_id, entity_id, entity_type, entity_name, embedding, model_name) VALUES ($1, $2, $3, $4, $5, $6)";
+
+            let query = sqlx::query(&sql_str)
+                .bind(record.embedding_id)
+                .bind(record.entity_id)
+                .bind(record.entity_type)
+                .bind(record.entity_name)
+                .bind(record.embedding)
+                .bind(&model.model_name);
+
+            match query.execute(pool).await {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(Box::new(e));
+                }
+            };
+        }
+
+        Ok(())
+    }
+}
+
+impl CheckData for EntityEmbedding {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<EntityEmbedding>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["entity_id".to_string(), "entity_type".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "embedding_id".to_string(),
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "entity_name".to_string(),
+            "embedding".to_string(),
+        ]
+    }
+
+    fn queryable_fields() -> Vec<String> {
+        // The embedding vectors cannot be compared with the values in the query string.
+        vec![
+            "embedding_id".to_string(),
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "entity_name".to_string(),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, sqlx::FromRow, Validate)]
+pub struct RelationEmbedding {
+    pub embedding_id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of relation_type should be between 1 and 64."
+    ))]
+    pub relation_type: String,
+
+    #[serde(deserialize_with = "text2vector")]
+    pub embedding: Vector,
+}
+
+impl RelationEmbedding {
+    /// Import the relation embeddings of a model, same as [`EntityEmbedding::import_entity_embeddings`](struct.EntityEmbedding.html#method.import_entity_embeddings).
+    pub async fn import_relation_embeddings(
+        pool: &sqlx::PgPool,
+        filepath: &PathBuf,
+        delimiter: u8,
+        drop: bool,
+        model: &EmbeddingModel,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut model = model.clone();
+        model
+            .prepare_import::<RelationEmbedding>(
+                pool,
+                filepath,
+                delimiter,
+                drop,
+                "biomedgps_relation_embedding",
+                |record| record.embedding.to_vec().len(),
+            )
+            .await?;
+
+        // Build the CSV reader
+        let mut reader = match open_csv_reader(filepath, delimiter) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Box::new(e));
+            }
+        };
+
+        for result in reader.deserialize() {
+            let record: RelationEmbedding = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    let error_msg = parse_csv_error(&e);
+                    return Err(Box::new(ValidationError::new(&error_msg)));
+                }
+            };
+
+            let sql_str = "INSERT INTO biomedgps_relation_embedding (embedding_id, relation_type, embedding, model_name) VALUES ($1, $2, $3, $4)";
+
+            let query = sqlx::query(&sql_str)
+                .bind(record.embedding_id)
+                .bind(record.relation_type)
+                .bind(record.embedding)
+                .bind(&model.model_name);
+
+            match query.execute(pool).await {
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(Box::new(e));
+                }
+            };
+        }
+
+        Ok(())
+    }
+}
+
+impl CheckData for RelationEmbedding {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<RelationEmbedding>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "source_id".to_string(),
+            "source_type".to_string(),
+            "target_id".to_string(),
+            "target_type".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "embedding_id".to_string(),
+            "relation_type".to_string(),
+            "source_id".to_string(),
+            "source_type".to_string(),
+            "target_id".to_string(),
+            "target_type".to_string(),
+            "embedding".to_string(),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct Statistics {
+    entity_stat: Vec<EntityMetadata>,
+    relation_stat: Vec<RelationMetadata>,
+    degree_stat: Vec<DegreeStatistics>,
+    metadata: DatabaseMetadata,
+}
+
+impl Statistics {
+    pub fn new(
+        entity_stat: Vec<EntityMetadata>,
+        relation_stat: Vec<RelationMetadata>,
+        degree_stat: Vec<DegreeStatistics>,
+        metadata: DatabaseMetadata,
+    ) -> Statistics {
+        Statistics {
+            entity_stat: entity_stat,
+            relation_stat: relation_stat,
+            degree_stat: degree_stat,
+            metadata: metadata,
+        }
+    }
+}
+
+/// Everything about one entity, it is used to render the detail panel of a node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EntityDetail {
+    pub entity: Entity,
+    /// The metadata of the entity type, one record per resource.
+    pub metadata: Vec<EntityMetadata>,
+    /// The degree of the entity per relation type.
+    pub degrees: Vec<RelationCount>,
+    /// The 2D coordinates of the entity, one record per embedding.
+    pub entity2d: Vec<Entity2D>,
+    pub curated_knowledges: Vec<KnowledgeCuration>,
+    /// The type-specific attributes of the entity, such as the symbol of a gene or the InChIKey of a compound.
+    pub attributes: Vec<EntityAttribute>,
+    /// The names of the entity in other languages, such as Chinese.
+    pub translations: Vec<EntityTranslation>,
+    /// The top-K similar nodes and the similarity edges, only available when the topk parameter is specified.
+    #[oai(skip_serializing_if_is_none)]
+    pub similar_nodes: Option<Graph>,
+}
+
+/// The version information of the running service and the database schema.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct DatabaseMetadata {
+    /// The version of the biomedgps service.
+    pub app_version: String,
+    /// The latest migration version which has been applied to the database.
+    #[oai(skip_serializing_if_is_none)]
+    pub schema_version: Option<i64>,
+    /// The active dataset version, i.e. the release of the knowledge graph which was imported last.
+    #[oai(skip_serializing_if_is_none)]
+    pub dataset_version: Option<DatasetVersion>,
+}
+
+impl DatabaseMetadata {
+    pub async fn get_database_metadata(
+        pool: &sqlx::PgPool,
+    ) -> Result<DatabaseMetadata, anyhow::Error> {
+        let sql_str = "SELECT MAX(version) FROM _sqlx_migrations WHERE success = true";
+        let schema_version = sqlx::query_as::<_, (Option<i64>,)>(sql_str)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(DatabaseMetadata {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: schema_version.0,
+            dataset_version: DatasetVersion::get_active(pool).await?,
+        })
+    }
+}
+
+/// The degree distribution summary of one entity type. It is computed from the relation table by the `update_degree_statistics` function, so we don't need to count the degrees on demand.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct DegreeStatistics {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    pub entity_type: String,
+
+    pub entity_count: i64,
+
+    pub min_degree: i64,
+
+    pub max_degree: i64,
+
+    pub mean_degree: f64,
+
+    pub median_degree: f64,
+
+    pub p90_degree: f64,
+}
+
+impl DegreeStatistics {
+    pub async fn get_degree_statistics(
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<DegreeStatistics>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_degree_statistics ORDER BY entity_type";
+        let degree_statistics = sqlx::query_as::<_, DegreeStatistics>(sql_str)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(degree_statistics)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct EntityMetadata {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of resource should be between 1 and 64."
+    ))]
+    pub resource: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity type should match ^[A-Za-z]+$. Such as Disease."
+    ))]
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of entity_type should be between 1 and 64."
+    ))]
+    pub entity_type: String,
+
+    pub entity_count: i64,
+}
+
+impl CheckData for EntityMetadata {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<EntityMetadata>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["resource".to_string(), "entity_type".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "resource".to_string(),
+            "entity_type".to_string(),
+            "entity_count".to_string(),
+        ]
+    }
+}
+
+impl EntityMetadata {
+    pub async fn get_entity_metadata(
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<EntityMetadata>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_entity_metadata";
+        let entity_metadata = sqlx::query_as::<_, EntityMetadata>(sql_str)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(entity_metadata)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct RelationMetadata {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of resource should be between 1 and 64."
+    ))]
+    pub resource: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of relation_type should be between 1 and 64."
+    ))]
+    pub relation_type: String,
+
+    pub relation_count: i64,
+
+    #[validate(regex = "ENTITY_LABEL_REGEX")]
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of start_entity_type should be between 1 and 64."
+    ))]
+    pub start_entity_type: String,
+
+    #[validate(regex = "ENTITY_LABEL_REGEX")]
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of end_entity_type should be between 1 and 64."
+    ))]
+    pub end_entity_type: String,
+}
+
+impl CheckData for RelationMetadata {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<RelationMetadata>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "resource".to_string(),
+            "relation_type".to_string(),
+            "start_entity_type".to_string(),
+            "end_entity_type".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "resource".to_string(),
+            "relation_type".to_string(),
+            "relation_count".to_string(),
+            "start_entity_type".to_string(),
+            "end_entity_type".to_string(),
+        ]
+    }
+}
+
+impl RelationMetadata {
+    pub async fn get_relation_metadata(
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<RelationMetadata>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_relation_metadata";
+        let relation_metadata = sqlx::query_as::<_, RelationMetadata>(sql_str)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(relation_metadata)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Object, PartialEq, Eq)]
+pub struct Payload {
+    pub project_id: String,
+    pub organization_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct KnowledgeCuration {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of resource must be between 1 and 64."
+    ))]
+    pub relation_type: String,
+
+    #[validate(length(
+        max = "ENTITY_NAME_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    pub source_name: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The source_type must be a valid entity type. The regex pattern is `^[A-Za-z]+$`, such as `Gene`."
+    ))]
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_type must be between 1 and 64."
+    ))]
+    pub source_type: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_id must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The source_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `UniProtKB:P12345`."
+    ))]
+    pub source_id: String,
+
+    #[validate(length(
+        max = "ENTITY_NAME_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of target_name must be between 1 and 64."
+    ))]
+    pub target_name: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The target_type must be a valid entity label. The regex pattern is `^[A-Za-z]+$`, such as `Gene`."
+    ))]
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of target_type must be between 1 and 64."
+    ))]
+    pub target_type: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of target_id must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The target_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `UniProtKB:P12345`."
+    ))]
+    pub target_id: String,
+
+    pub key_sentence: String,
+
+    #[serde(skip_deserializing)]
+    #[serde(with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of curator must be between 1 and 64."
+    ))]
+    pub curator: String,
+
+    #[validate(range(min = 1, message = "pmid must be greater than 0"))]
+    pub pmid: i64,
+
+    // The payload field is a jsonb field which contains the project_id and organization_id.
+    pub payload: Option<serde_json::Value>,
+
+    /// The workspace which the curated knowledge belongs to, it is a personal record if it is empty.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub workspace_id: Option<i64>,
+
+    /// The time when the curated knowledge was deleted, it can be restored until it is purged.
+    #[serde(default, skip_deserializing, with = "ts_seconds_option")]
+    #[sqlx(default)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl KnowledgeCuration {
+    pub fn update_curator(&mut self, curator: String) -> &Self {
+        self.curator = curator;
+        self
+    }
+
+    pub fn to_relation(&self) -> Relation {
+        Relation {
+            id: self.id,
+            relation_type: self.relation_type.clone(),
+            source_type: self.source_type.clone(),
+            source_id: self.source_id.clone(),
+            target_type: self.target_type.clone(),
+            target_id: self.target_id.clone(),
+            key_sentence: Some(self.key_sentence.clone()),
+            resource: self.curator.clone(),
+            pmids: Some(format!("{}", self.pmid)),
+            score: None,
+            directed: true,
+            attributes: None,
+            split: None,
+            created_at: Some(self.created_at),
+            dataset_version: None,
+        }
+    }
+
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<KnowledgeCuration>, anyhow::Error> {
+        let columns = <KnowledgeCuration as CheckData>::fields().join(",");
+        let sql_str = format!("SELECT id,created_at,payload,workspace_id,{columns} FROM biomedgps_knowledge_curation WHERE deleted_at IS NULL");
+        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+
+    /// Fetch all curated knowledges which use the node as the source or target node.
+    pub async fn get_records_by_node(
+        pool: &sqlx::PgPool,
+        node_type: &str,
+        node_id: &str,
+    ) -> Result<Vec<KnowledgeCuration>, anyhow::Error> {
+        let columns = <KnowledgeCuration as CheckData>::fields().join(",");
+        let sql_str = format!(
+            "SELECT id,created_at,payload,workspace_id,{columns} FROM biomedgps_knowledge_curation 
+             WHERE ((source_type = $1 AND source_id = $2) OR (target_type = $1 AND target_id = $2)) AND deleted_at IS NULL
+             ORDER BY created_at DESC"
+        );
+        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
+            .bind(node_type)
+            .bind(node_id)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+
+    /// Fetch all curated knowledges which have the same relation type, source node and target node as the relation. If the workspace_ids is set, only the personal curated knowledges and the ones in the workspaces are returned.
+    pub async fn get_records_by_relation(
+        pool: &sqlx::PgPool,
+        relation: &Relation,
+        workspace_ids: Option<&[i64]>,
+    ) -> Result<Vec<KnowledgeCuration>, anyhow::Error> {
+        let columns = <KnowledgeCuration as CheckData>::fields().join(",");
+        let sql_str = format!(
+            "SELECT id,created_at,payload,workspace_id,{columns} FROM biomedgps_knowledge_curation 
+             WHERE relation_type = $1 AND source_type = $2 AND source_id = $3 AND target_type = $4 AND target_id = $5 AND deleted_at IS NULL
+             AND ($6::BIGINT[] IS NULL OR workspace_id IS NULL OR workspace_id = ANY($6))
+             ORDER BY created_at DESC"
+        );
+        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
+            .bind(&relation.relation_type)
+            .bind(&relation.source_type)
+            .bind(&relation.source_id)
+            .bind(&relation.target_type)
+            .bind(&relation.target_id)
+            .bind(workspace_ids)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+
+    /// Fetch the curated knowledges of the curator, the project or the organization. If the workspace_id is set, only the curated knowledges in the workspace are returned.
+    pub async fn get_records_by_owner(
+        pool: &sqlx::PgPool,
+        curator: &str,
+        project_id: i32,
+        organization_id: i32,
+        workspace_id: Option<i64>,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+    ) -> Result<RecordResponse<KnowledgeCuration>, anyhow::Error> {
+        let project_id_qstr = if project_id >= 0 {
+            format!("payload->>'project_id' = '{}'", project_id)
+        } else {
+            format!("payload->>'project_id' IS NOT NULL")
+        };
+
+        let organization_id_qstr = if organization_id >= 0 {
+            format!("payload->>'organization_id' = '{}'", organization_id)
+        } else {
+            format!("payload->>'organization_id' IS NOT NULL")
+        };
+
+        let curator_qstr = if project_id < 0 && organization_id < 0 {
+            format!("curator = '{}'", curator)
+        } else {
+            format!("curator IS NOT NULL")
+        };
+
+        let mut where_str = format!(
+            "{} AND {} AND {} AND deleted_at IS NULL",
+            curator_qstr, project_id_qstr, organization_id_qstr
+        );
+
+        if let Some(workspace_id) = workspace_id {
+            where_str = format!("{} AND workspace_id = {}", where_str, workspace_id);
+        }
+
+        let page = match page {
+            Some(page) => page,
+            None => 1,
+        };
+
+        let page_size = match page_size {
+            Some(page_size) => page_size,
+            None => 10,
+        };
+
+        let limit = page_size;
+        let offset = (page - 1) * page_size;
+
+        let order_by_str = if order_by.is_none() {
+            "".to_string()
+        } else {
+            format!("ORDER BY {}", order_by.unwrap())
+        };
+
+        let sql_str = format!(
+            "SELECT * FROM biomedgps_knowledge_curation WHERE {} {} LIMIT {} OFFSET {}",
+            where_str, order_by_str, limit, offset
+        );
+
+        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
+            .fetch_all(pool)
+            .await?;
+
+        let sql_str = format!(
+            "SELECT COUNT(*) FROM biomedgps_knowledge_curation WHERE {}",
+            where_str
+        );
+
+        let total = sqlx::query_as::<_, (i64,)>(sql_str.as_str())
+            .fetch_one(pool)
+            .await?;
+
+        let total = total.0 as u64;
+        let (total_pages, has_next) = paging_metadata(total, page, page_size);
+
+        AnyOk(RecordResponse {
+            records: records,
+            total,
+            page,
+            page_size,
+            total_pages,
+            has_next,
+        })
+    }
+
+    fn get_value(key: &str, json: &serde_json::Value) -> Result<String, anyhow::Error> {
+        match json[key].as_str() {
+            Some(value) => Ok(value.to_string()),
+            None => Err(anyhow::anyhow!("The {} field is missing.", key)),
+        }
+    }
+
+    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<KnowledgeCuration, anyhow::Error> {
+        let sql_str = "INSERT INTO biomedgps_knowledge_curation (relation_type, source_name, source_type, source_id, target_name, target_type, target_id, key_sentence, curator, pmid, payload, workspace_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING *";
+        let payload = match &self.payload {
+            Some(payload) => sqlx::types::Json(Payload {
+                project_id: KnowledgeCuration::get_value("project_id", payload)?,
+                organization_id: KnowledgeCuration::get_value("organization_id", payload)?,
+            }),
+            None => sqlx::types::Json(Payload {
+                project_id: "0".to_string(),
+                organization_id: "0".to_string(),
+            }),
+        };
+
+        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+            .bind(&self.relation_type)
+            .bind(&self.source_name)
+            .bind(&self.source_type)
+            .bind(&self.source_id)
+            .bind(&self.target_name)
+            .bind(&self.target_type)
+            .bind(&self.target_id)
+            .bind(&self.key_sentence)
+            .bind(&self.curator)
+            .bind(&self.pmid)
+            .bind(&payload)
+            .bind(self.workspace_id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(knowledge_curation)
+    }
+
+    pub async fn update(
+        &self,
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<KnowledgeCuration, anyhow::Error> {
+        let sql_str = "UPDATE biomedgps_knowledge_curation SET relation_type = $1, source_name = $2, source_type = $3, source_id = $4, target_name = $5, target_type = $6, target_id = $7, key_sentence = $8, created_at = now(), pmid = $9, workspace_id = $10 WHERE id = $11 AND deleted_at IS NULL RETURNING *";
+        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+            .bind(&self.relation_type)
+            .bind(&self.source_name)
+            .bind(&self.source_type)
+            .bind(&self.source_id)
+            .bind(&self.target_name)
+            .bind(&self.target_type)
+            .bind(&self.target_id)
+            .bind(&self.key_sentence)
+            .bind(&self.pmid)
+            .bind(self.workspace_id)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(knowledge_curation)
+    }
+
+    /// Fetch a curated knowledge by id, return None if it doesn't exist or it has been deleted.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<Option<KnowledgeCuration>, anyhow::Error> {
+        let columns = <KnowledgeCuration as CheckData>::fields().join(",");
+        let sql_str = format!(
+            "SELECT id,created_at,payload,workspace_id,{columns} FROM biomedgps_knowledge_curation WHERE id = $1 AND deleted_at IS NULL"
+        );
+        let record = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Fetch a deleted curated knowledge by id, return None if it doesn't exist, it isn't deleted or it has been purged.
+    pub async fn get_deleted_record(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<Option<KnowledgeCuration>, anyhow::Error> {
+        let sql_str =
+            "SELECT * FROM biomedgps_knowledge_curation WHERE id = $1 AND deleted_at IS NOT NULL";
+        let record = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Soft delete a curated knowledge, it is hidden from all queries but it can be restored until it is purged.
+    pub async fn delete(pool: &sqlx::PgPool, id: i64) -> Result<KnowledgeCuration, anyhow::Error> {
+        let sql_str = "UPDATE biomedgps_knowledge_curation SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL RETURNING *";
+        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(knowledge_curation)
+    }
+
+    /// Restore a deleted curated knowledge.
+    pub async fn restore(pool: &sqlx::PgPool, id: i64) -> Result<KnowledgeCuration, anyhow::Error> {
+        let sql_str = "UPDATE biomedgps_knowledge_curation SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *";
+        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(knowledge_curation)
+    }
+}
+
+/// The result of deleting one record in a bulk deletion.
+///
+/// * `status` - One of `deleted`, `not_found`, `forbidden` and `failed`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct DeletionResult {
+    pub id: i64,
+    pub status: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub msg: Option<String>,
+}
+
+impl DeletionResult {
+    pub fn new(id: i64, status: &str, msg: Option<String>) -> Self {
+        DeletionResult {
+            id,
+            status: status.to_string(),
+            msg,
+        }
+    }
+}
+
+impl CheckData for KnowledgeCuration {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<KnowledgeCuration>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "source_type".to_string(),
+            "source_id".to_string(),
+            "target_type".to_string(),
+            "target_id".to_string(),
+            "curator".to_string(),
+            "pmid".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "source_name".to_string(),
+            "source_type".to_string(),
+            "source_id".to_string(),
+            "target_name".to_string(),
+            "target_type".to_string(),
+            "target_id".to_string(),
+            "key_sentence".to_string(),
+            "curator".to_string(),
+            "pmid".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields.push("created_at".to_string());
+        fields.push("payload".to_string());
+        fields.push("workspace_id".to_string());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "relation_type".to_string(),
+            "source_name".to_string(),
+            "source_type".to_string(),
+            "source_id".to_string(),
+            "target_name".to_string(),
+            "target_type".to_string(),
+            "target_id".to_string(),
+            "curator".to_string(),
+            "pmid".to_string(),
+            "created_at".to_string(),
+        ]
+    }
+
+    fn json_fields() -> Vec<String> {
+        vec!["payload".to_string()]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
+pub struct Relation {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of relation_type must be between 1 and 64."
+    ))]
+    pub relation_type: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The source_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
+    ))]
+    pub source_id: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The source_type must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub source_type: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The source_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
+    ))]
+    pub target_id: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The target_type must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub target_type: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub score: Option<f64>,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub key_sentence: Option<String>,
+
+    pub resource: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub pmids: Option<String>,
+
+    /// Whether the relation goes from the source to the target, it is false for the symmetric relations such as a protein-protein interaction. The default is true.
+    #[serde(default = "default_directed")]
+    #[oai(default = "default_directed")]
+    pub directed: bool,
+
+    /// The qualifiers of the relation, such as `{"tissue": "liver", "dosage": "10mg", "direction": "up", "confidence": 0.8}`. It is a json object in the data file.
+    #[serde(default, deserialize_with = "text2attributes")]
+    #[validate(custom = "validate_relation_attributes")]
+    #[oai(skip_serializing_if_is_none)]
+    pub attributes: Option<serde_json::Value>,
+
+    /// The dataset split of the relation, one of train, valid and test. It is generated by `biomedgps-cli splitdb`.
+    #[validate(regex(
+        path = "RELATION_SPLIT_REGEX",
+        message = "The split must be one of train, valid and test."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub split: Option<String>,
+
+    /// The time when the relation was imported, it is filled by the database.
+    #[serde(skip_deserializing, with = "ts_seconds_option")]
+    #[sqlx(default)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// The release tag of the dataset which the relation was imported from, such as 2023Q4. It is stamped by `biomedgps-cli importdb --release-tag`, and it is empty for the relations imported before the provenance was recorded.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub dataset_version: Option<String>,
+}
+
+fn default_directed() -> bool {
+    true
+}
+
+/// Parse the attributes column of a data file, it is a json string in the csv/tsv file.
+fn text2attributes<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(serde_json::Value::String(s)) => serde_json::from_str(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        other => Ok(other),
+    }
+}
+
+/// The attributes must be a json object whose keys are in snake case and whose values are strings, numbers, booleans or arrays of them, so they can be filtered by `attributes.<key>` in the query.
+///
+/// ```
+/// use biomedgps::model::core::validate_relation_attributes;
+/// use serde_json::json;
+///
+/// assert!(validate_relation_attributes(&json!({"tissue": "liver", "confidence": 0.8})).is_ok());
+/// assert!(validate_relation_attributes(&json!({"Tissue": "liver"})).is_err());
+/// assert!(validate_relation_attributes(&json!({"dosage": {"value": 10}})).is_err());
+/// assert!(validate_relation_attributes(&json!(["liver"])).is_err());
+/// ```
+pub fn validate_relation_attributes(
+    attributes: &serde_json::Value,
+) -> Result<(), validator::ValidationError> {
+    let is_scalar = |value: &serde_json::Value| {
+        matches!(
+            value,
+            serde_json::Value::String(_)
+                | serde_json::Value::Number(_)
+                | serde_json::Value::Bool(_)
+        )
+    };
+
+    let error = |msg: String| {
+        let mut err = validator::ValidationError::new("attributes");
+        err.message = Some(std::borrow::Cow::from(msg));
+        err
+    };
+
+    let attributes = match attributes.as_object() {
+        Some(attributes) => attributes,
+        None => return Err(error("The attributes must be a json object.".to_string())),
+    };
+
+    for (key, value) in attributes {
+        if !ATTRIBUTE_KEY_REGEX.is_match(key) || key.len() > DEFAULT_MAX_LENGTH as usize {
+            return Err(error(format!(
+                "Invalid attribute key: {}, it must be in snake case, such as `tissue`.",
+                key
+            )));
+        }
+
+        let is_valid = match value {
+            serde_json::Value::Array(items) => items.iter().all(is_scalar),
+            _ => is_scalar(value),
+        };
+        if !is_valid {
+            return Err(error(format!(
+                "Invalid value of the attribute {}, it must be a string, a number, a boolean or an array of them.",
+                key
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+impl Relation {
+    /// Fetch a relation by id, return None if it doesn't exist.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<Option<Relation>, anyhow::Error> {
+        let columns = <Relation as CheckData>::selectable_fields().join(",");
+        let sql_str = format!("SELECT {columns} FROM biomedgps_relation WHERE id = $1");
+        let record = sqlx::query_as::<_, Relation>(sql_str.as_str())
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+}
+
+/// The evidence behind a relation, it aggregates the key sentences, the pmids and the resources of the relation and the matched curated knowledges.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct RelationEvidence {
+    pub relation: Relation,
+    /// The distinct key sentences, the key sentence of the relation comes first.
+    pub key_sentences: Vec<String>,
+    /// The distinct pmids, the pmids of the relation come first.
+    pub pmids: Vec<i64>,
+    /// The resources which provide the relation, such as `drkg` or `curator:<username>` for the curated knowledges.
+    pub resources: Vec<String>,
+    /// The curated knowledges which have the same relation type, source node and target node as the relation.
+    pub curated_knowledges: Vec<KnowledgeCuration>,
+    /// The title, journal and year of the pmids, only available when the PubMed enrichment is enabled.
+    #[oai(skip_serializing_if_is_none)]
+    pub publications: Option<Vec<PublicationSummary>>,
+}
+
+impl RelationEvidence {
+    pub fn new(relation: Relation, curated_knowledges: Vec<KnowledgeCuration>) -> Self {
+        let mut key_sentences: Vec<String> = vec![];
+        let mut pmids: Vec<i64> = vec![];
+        let mut resources: Vec<String> = vec![];
+
+        let mut add_key_sentence = |sentence: &str| {
+            let sentence = sentence.trim();
+            if !sentence.is_empty() && !key_sentences.iter().any(|s| s == sentence) {
+                key_sentences.push(sentence.to_string());
+            }
+        };
+
+        if let Some(key_sentence) = &relation.key_sentence {
+            add_key_sentence(key_sentence);
+        }
+
+        for record in curated_knowledges.iter() {
+            add_key_sentence(&record.key_sentence);
+        }
+
+        let mut relation_pmids = relation
+            .pmids
+            .as_ref()
+            .map(|pmids| parse_pmids(pmids))
+            .unwrap_or_default();
+        relation_pmids.extend(curated_knowledges.iter().map(|record| record.pmid));
+        for pmid in relation_pmids {
+            if pmid > 0 && !pmids.contains(&pmid) {
+                pmids.push(pmid);
+            }
+        }
+
+        let mut relation_resources = relation
+            .resource
+            .split('|')
+            .map(|resource| resource.trim().to_string())
+            .collect::<Vec<String>>();
+        relation_resources.extend(
+            curated_knowledges
+                .iter()
+                .map(|record| format!("curator:{}", record.curator)),
+        );
+        for resource in relation_resources {
+            if !resource.is_empty() && !resources.contains(&resource) {
+                resources.push(resource);
+            }
+        }
+
+        RelationEvidence {
+            relation,
+            key_sentences,
+            pmids,
+            resources,
+            curated_knowledges,
+            publications: None,
+        }
+    }
+}
+
+/// A candidate triple, such as a triple which is going to be curated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, Validate)]
+pub struct RelationTriple {
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The source_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `DrugBank:DB00945`."
+    ))]
+    pub source_id: String,
+
+    /// The relation type, such as `DRUGBANK::treats::Compound:Disease`.
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of relation_type should be between 1 and 64."
+    ))]
+    pub relation_type: String,
 
     #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The target_type must be a valid entity label. The regex pattern is `^[A-Za-z]+$`, such as `Gene`."
+        path = "ENTITY_ID_REGEX",
+        message = "The target_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `MESH:D001249`."
+    ))]
+    pub target_id: String,
+}
+
+/// Whether a candidate triple exists in the knowledge graph, and the ids of the matched relations. A triple may match several relations whose source or target types are different.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct RelationExistence {
+    pub source_id: String,
+    pub relation_type: String,
+    pub target_id: String,
+    pub exists: bool,
+    pub relation_ids: Vec<i64>,
+}
+
+impl RelationExistence {
+    /// Check which of the triples exist in the relation table, the results are in the order of the triples. The triples are joined with the relations in one query, so a long list costs one round trip.
+    pub async fn check(
+        pool: &sqlx::PgPool,
+        triples: &[RelationTriple],
+    ) -> Result<Vec<RelationExistence>, anyhow::Error> {
+        if triples.is_empty() {
+            return AnyOk(vec![]);
+        }
+
+        let source_ids = triples.iter().map(|t| t.source_id.clone()).collect::<Vec<String>>();
+        let relation_types = triples
+            .iter()
+            .map(|t| t.relation_type.clone())
+            .collect::<Vec<String>>();
+        let target_ids = triples.iter().map(|t| t.target_id.clone()).collect::<Vec<String>>();
+
+        // The ordinality keeps the duplicated triples apart, so each triple gets its own result.
+        let sql_str = "SELECT t.idx, COALESCE(ARRAY_AGG(r.id ORDER BY r.id) FILTER (WHERE r.id IS NOT NULL), '{}') AS relation_ids
+                       FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[]) WITH ORDINALITY AS t(source_id, relation_type, target_id, idx)
+                       LEFT JOIN biomedgps_relation r
+                       ON r.relation_type = t.relation_type AND r.source_id = t.source_id AND r.target_id = t.target_id
+                       GROUP BY t.idx";
+        let rows = with_query_timeout(
+            sqlx::query_as::<_, (i64, Vec<i64>)>(sql_str)
+                .bind(&source_ids)
+                .bind(&relation_types)
+                .bind(&target_ids)
+                .fetch_all(pool),
+        )
+        .await?;
+        let matched = rows.into_iter().collect::<HashMap<i64, Vec<i64>>>();
+
+        let results = triples
+            .iter()
+            .enumerate()
+            .map(|(i, triple)| {
+                let relation_ids = matched.get(&(i as i64 + 1)).cloned().unwrap_or_default();
+                RelationExistence {
+                    source_id: triple.source_id.clone(),
+                    relation_type: triple.relation_type.clone(),
+                    target_id: triple.target_id.clone(),
+                    exists: !relation_ids.is_empty(),
+                    relation_ids,
+                }
+            })
+            .collect();
+
+        AnyOk(results)
+    }
+}
+
+impl CheckData for Relation {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<Relation>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "source_id".to_string(),
+            "source_type".to_string(),
+            "target_id".to_string(),
+            "target_type".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "source_id".to_string(),
+            "source_type".to_string(),
+            "target_id".to_string(),
+            "target_type".to_string(),
+            "score".to_string(),
+            "key_sentence".to_string(),
+            "resource".to_string(),
+            "pmids".to_string(),
+            "directed".to_string(),
+            "attributes".to_string(),
+            "split".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        // The provenance is not in the data file, it is filled when the relation is imported.
+        fields.extend(vec!["created_at".to_string(), "dataset_version".to_string()]);
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "relation_type".to_string(),
+            "source_id".to_string(),
+            "source_type".to_string(),
+            "target_id".to_string(),
+            "target_type".to_string(),
+            "score".to_string(),
+            "resource".to_string(),
+            "created_at".to_string(),
+            "dataset_version".to_string(),
+        ]
+    }
+
+    fn json_fields() -> Vec<String> {
+        vec!["attributes".to_string()]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
+pub struct RelationCount {
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of relation_type must be between 1 and 64."
     ))]
+    pub relation_type: String,
+
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of target_type must be between 1 and 64."
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The target_type must match the ^[A-Za-z]+$ pattern."
     ))]
     pub target_type: String,
 
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of target_id must be between 1 and 64."
+        message = "The length of source_name must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The source_type must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub source_type: String,
+
+    pub resource: String,
+
+    pub ncount: i64,
+}
+
+impl RelationCount {
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        query: &Option<ComposeQuery>,
+    ) -> Result<Vec<RelationCount>, anyhow::Error> {
+        let (mut query_str, params) = match query {
+            Some(query) => {
+                query.format_with_params(&get_column_types(pool, TableName::Relation).await?)
+            }
+            None => ("".to_string(), vec![]),
+        };
+
+        if query_str.is_empty() {
+            query_str = "1=1".to_string();
+        };
+
+        let sql_str = format!(
+            "SELECT relation_type, source_type, target_type, resource, COUNT(*) as ncount FROM biomedgps_relation WHERE {} GROUP BY relation_type, source_type, target_type, resource",
+            query_str
+        );
+
+        let records = bind_values(
+            sqlx::query_as::<_, RelationCount>(sql_str.as_str()),
+            &params,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    /// Count the relations which use the node as the source or target node, it is the degree of the node per relation type.
+    pub async fn get_records_by_node(
+        pool: &sqlx::PgPool,
+        node_type: &str,
+        node_id: &str,
+    ) -> Result<Vec<RelationCount>, anyhow::Error> {
+        let sql_str = "SELECT relation_type, source_type, target_type, resource, COUNT(*) as ncount FROM biomedgps_relation 
+                       WHERE (source_type = $1 AND source_id = $2) OR (target_type = $1 AND target_id = $2)
+                       GROUP BY relation_type, source_type, target_type, resource
+                       ORDER BY ncount DESC";
+
+        let records = sqlx::query_as::<_, RelationCount>(sql_str)
+            .bind(node_type)
+            .bind(node_id)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
+pub struct Entity2D {
+    pub embedding_id: i64,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of entity_id must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The entity_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
+    ))]
+    pub entity_id: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of entity_type must be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must match the ^[A-Za-z]+$ pattern."
+    ))]
+    pub entity_type: String,
+
+    #[validate(length(
+        max = "ENTITY_NAME_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of entity_name must be between 1 and 255."
+    ))]
+    pub entity_name: String,
+
+    pub umap_x: f64,
+
+    pub umap_y: f64,
+
+    pub tsne_x: f64,
+
+    pub tsne_y: f64,
+}
+
+impl Entity2D {
+    /// Fetch the 2D coordinates of an entity, there might be several records if the entity has multiple embeddings.
+    pub async fn get_records_by_entity(
+        pool: &sqlx::PgPool,
+        entity_type: &str,
+        entity_id: &str,
+    ) -> Result<Vec<Entity2D>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_entity2d WHERE entity_type = $1 AND entity_id = $2";
+        let records = sqlx::query_as::<_, Entity2D>(sql_str)
+            .bind(entity_type)
+            .bind(entity_id)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+
+    /// Compute the 2D coordinates of all entities from the embeddings of a model and replace the records in the `biomedgps_entity2d` table.
+    ///
+    /// The umap method only updates the umap_x/umap_y columns and the tsne method only updates the tsne_x/tsne_y columns, the other columns keep the existing values (or the computed values for the new entities). The pca method updates both, so it can be used when no UMAP/t-SNE coordinates are available. It returns the number of the records.
+    pub async fn compute_from_embeddings(
+        pool: &sqlx::PgPool,
+        model: &EmbeddingModel,
+        method: ReductionMethod,
+        options: &ReductionOptions,
+    ) -> Result<usize, anyhow::Error> {
+        let sql_str = "SELECT embedding_id, entity_id, entity_name, entity_type, embedding
+                       FROM biomedgps_entity_embedding WHERE model_name = $1 ORDER BY embedding_id";
+        let embeddings = sqlx::query_as::<_, EntityEmbedding>(sql_str)
+            .bind(&model.model_name)
+            .fetch_all(pool)
+            .await?;
+
+        if embeddings.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot find any entity embeddings of the model {}.",
+                model.model_name
+            ));
+        }
+
+        info!(
+            "Compute the 2D coordinates of {} entities by {}, it may take a while.",
+            embeddings.len(),
+            method
+        );
+        let data = embeddings
+            .iter()
+            .map(|e| e.embedding.to_vec())
+            .collect::<Vec<Vec<f32>>>();
+        let reduce_options = options.clone();
+        let coordinates =
+            tokio::task::spawn_blocking(move || reduce(&data, method, &reduce_options)).await??;
+
+        let mut tx = pool.begin().await?;
+        let existing = sqlx::query_as::<_, Entity2D>("SELECT * FROM biomedgps_entity2d")
+            .fetch_all(&mut tx)
+            .await?
+            .into_iter()
+            .map(|r| ((r.entity_type.clone(), r.entity_id.clone()), r))
+            .collect::<HashMap<(String, String), Entity2D>>();
+
+        let records = embeddings
+            .into_iter()
+            .zip(coordinates)
+            .map(|(e, (x, y))| {
+                let old = existing.get(&(e.entity_type.clone(), e.entity_id.clone()));
+                let (umap, tsne) = match (method, old) {
+                    (ReductionMethod::Umap, Some(old)) => ((x, y), (old.tsne_x, old.tsne_y)),
+                    (ReductionMethod::Tsne, Some(old)) => ((old.umap_x, old.umap_y), (x, y)),
+                    _ => ((x, y), (x, y)),
+                };
+
+                Entity2D {
+                    embedding_id: e.embedding_id,
+                    entity_id: e.entity_id,
+                    entity_type: e.entity_type,
+                    entity_name: e.entity_name,
+                    umap_x: umap.0,
+                    umap_y: umap.1,
+                    tsne_x: tsne.0,
+                    tsne_y: tsne.1,
+                }
+            })
+            .collect::<Vec<Entity2D>>();
+
+        sqlx::query("DELETE FROM biomedgps_entity2d")
+            .execute(&mut tx)
+            .await?;
+
+        let sql_str = "INSERT INTO biomedgps_entity2d (embedding_id, entity_id, entity_type, entity_name, umap_x, umap_y, tsne_x, tsne_y)
+                       SELECT * FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::FLOAT[], $6::FLOAT[], $7::FLOAT[], $8::FLOAT[])";
+        for chunk in records.chunks(10000) {
+            let texts = |f: fn(&Entity2D) -> &String| {
+                chunk.iter().map(|r| f(r).clone()).collect::<Vec<String>>()
+            };
+            let floats = |f: fn(&Entity2D) -> f64| chunk.iter().map(f).collect::<Vec<f64>>();
+
+            sqlx::query(sql_str)
+                .bind(chunk.iter().map(|r| r.embedding_id).collect::<Vec<i64>>())
+                .bind(texts(|r| &r.entity_id))
+                .bind(texts(|r| &r.entity_type))
+                .bind(texts(|r| &r.entity_name))
+                .bind(floats(|r| r.umap_x))
+                .bind(floats(|r| r.umap_y))
+                .bind(floats(|r| r.tsne_x))
+                .bind(floats(|r| r.tsne_y))
+                .execute(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        AnyOk(records.len())
+    }
+}
+
+impl CheckData for Entity2D {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<Entity2D>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "embedding_id".to_string(),
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "embedding_id".to_string(),
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "entity_name".to_string(),
+            "umap_x".to_string(),
+            "umap_y".to_string(),
+            "tsne_x".to_string(),
+            "tsne_y".to_string(),
+        ]
+    }
+}
+
+/// The coordinates of the 2D map, they are the umap_x/umap_y or tsne_x/tsne_y columns of the `biomedgps_entity2d` table.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Coordinate2D {
+    Umap,
+    Tsne,
+}
+
+impl Coordinate2D {
+    pub fn columns(&self) -> (&'static str, &'static str) {
+        match self {
+            Coordinate2D::Umap => ("umap_x", "umap_y"),
+            Coordinate2D::Tsne => ("tsne_x", "tsne_y"),
+        }
+    }
+}
+
+/// The visible area of the 2D map, all bounds are optional and the entity types are used to filter the entities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity2DViewport {
+    pub coordinate: Coordinate2D,
+    pub x_min: Option<f64>,
+    pub x_max: Option<f64>,
+    pub y_min: Option<f64>,
+    pub y_max: Option<f64>,
+    pub entity_types: Vec<String>,
+}
+
+impl Entity2DViewport {
+    /// Parse the viewport, the entity types are separated by commas, such as `Gene,Disease`.
+    pub fn new(
+        coordinate: Coordinate2D,
+        x_min: Option<f64>,
+        x_max: Option<f64>,
+        y_min: Option<f64>,
+        y_max: Option<f64>,
+        entity_types: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        for (name, min, max) in [("x", x_min, x_max), ("y", y_min, y_max)] {
+            if min.iter().chain(max.iter()).any(|v| !v.is_finite()) {
+                return Err(ValidationError::new(&format!(
+                    "The bounds of {} must be finite numbers.",
+                    name
+                )));
+            }
+
+            if let (Some(min), Some(max)) = (min, max) {
+                if min >= max {
+                    return Err(ValidationError::new(&format!(
+                        "The {}_min must be less than the {}_max.",
+                        name, name
+                    )));
+                }
+            }
+        }
+
+        let entity_types = entity_types
+            .unwrap_or("")
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<String>>();
+        if let Some(entity_type) = entity_types
+            .iter()
+            .find(|v| !ENTITY_LABEL_REGEX.is_match(v))
+        {
+            return Err(ValidationError::new(&format!(
+                "Invalid entity type: {}, it must match the ^[A-Za-z]+$ pattern.",
+                entity_type
+            )));
+        }
+
+        Ok(Entity2DViewport {
+            coordinate,
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            entity_types,
+        })
+    }
+
+    /// Combine the range predicates and the entity types of the viewport with the query.
+    ///
+    /// ```
+    /// use biomedgps::model::core::{Coordinate2D, Entity2DViewport};
+    /// use biomedgps::query_builder::sql_builder::ComposeQuery;
+    ///
+    /// let viewport = Entity2DViewport::new(Coordinate2D::Umap, Some(-1.0), Some(1.0), None, None, Some("Gene")).unwrap();
+    /// match viewport.merge_query(None) {
+    ///     Some(ComposeQuery::ComposeQueryItem(item)) => {
+    ///         assert_eq!(item.format(), "umap_x >= -1 and umap_x <= 1 and entity_type in ('Gene')");
+    ///     }
+    ///     _ => panic!("The viewport should be converted to a compose query."),
+    /// }
+    ///
+    /// assert!(Entity2DViewport::new(Coordinate2D::Umap, Some(1.0), Some(-1.0), None, None, None).is_err());
+    /// ```
+    pub fn merge_query(&self, query: Option<ComposeQuery>) -> Option<ComposeQuery> {
+        let (x, y) = self.coordinate.columns();
+        let mut items = vec![
+            (x, ">=", self.x_min),
+            (x, "<=", self.x_max),
+            (y, ">=", self.y_min),
+            (y, "<=", self.y_max),
+        ]
+        .into_iter()
+        .filter_map(|(field, operator, value)| {
+            value.map(|v| {
+                ComposeQuery::QueryItem(QueryItem::new(
+                    field.to_string(),
+                    Value::Float(v),
+                    operator.to_string(),
+                ))
+            })
+        })
+        .collect::<Vec<ComposeQuery>>();
+
+        if !self.entity_types.is_empty() {
+            items.push(ComposeQuery::QueryItem(QueryItem::new(
+                "entity_type".to_string(),
+                Value::ArrayString(self.entity_types.clone()),
+                "in".to_string(),
+            )));
+        }
+
+        if items.is_empty() {
+            return query;
+        }
+
+        let mut merged = ComposeQueryItem::new("and");
+        if let Some(query) = query {
+            merged.add_item(query);
+        }
+        for item in items {
+            merged.add_item(item);
+        }
+
+        Some(ComposeQuery::ComposeQueryItem(merged))
+    }
+}
+
+/// The entities in a cell of the 2D map, x and y are the centroid of the entities.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct Entity2DCluster {
+    pub entity_type: String,
+    pub x: f64,
+    pub y: f64,
+    pub count: i64,
+}
+
+/// The entities in the viewport of the 2D map. If there are more entities than the limit, the viewport is split into bins x bins cells and the clusters (one per cell and entity type) are returned instead of the entities.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct Entity2DMap {
+    pub coordinate: Coordinate2D,
+    pub total: u64,
+    pub clustered: bool,
+    #[oai(skip_serializing_if_is_none)]
+    pub cell_width: Option<f64>,
+    #[oai(skip_serializing_if_is_none)]
+    pub cell_height: Option<f64>,
+    pub records: Vec<Entity2D>,
+    pub clusters: Vec<Entity2DCluster>,
+}
+
+/// The default number of the bins on each axis of the 2D map.
+pub const DEFAULT_MAP_BINS: u64 = 100;
+/// The max number of the bins on each axis of the 2D map.
+pub const MAX_MAP_BINS: u64 = 500;
+/// The max number of the entities which are returned without clustering.
+pub const MAX_MAP_POINTS: u64 = 50000;
+
+impl Entity2DMap {
+    pub async fn get_map(
+        pool: &sqlx::PgPool,
+        viewport: &Entity2DViewport,
+        query: &Option<ComposeQuery>,
+        max_points: u64,
+        bins: u64,
+    ) -> Result<Entity2DMap, anyhow::Error> {
+        let (x, y) = viewport.coordinate.columns();
+        let (where_str, params, _, _) = make_record_clauses(
+            pool,
+            TableName::Entity2D,
+            &viewport.merge_query(query.clone()),
+            None,
+            None,
+            None,
+        )
+        .await?;
+
+        let sql_str = format!(
+            "SELECT COUNT(*), MIN({x}), MAX({x}), MIN({y}), MAX({y}) FROM biomedgps_entity2d WHERE {where_str}"
+        );
+        debug!("sql_str: {}", sql_str);
+        let (total, min_x, max_x, min_y, max_y) = with_query_timeout(
+            bind_values(
+                sqlx::query_as::<_, (i64, Option<f64>, Option<f64>, Option<f64>, Option<f64>)>(
+                    sql_str.as_str(),
+                ),
+                &params,
+            )
+            .fetch_one(pool),
+        )
+        .await?;
+
+        let mut map = Entity2DMap {
+            coordinate: viewport.coordinate,
+            total: total as u64,
+            clustered: false,
+            cell_width: None,
+            cell_height: None,
+            records: vec![],
+            clusters: vec![],
+        };
+
+        if total as u64 <= max_points {
+            let sql_str = format!(
+                "SELECT * FROM biomedgps_entity2d WHERE {where_str} ORDER BY embedding_id ASC"
+            );
+            map.records = with_query_timeout(
+                bind_values(sqlx::query_as::<_, Entity2D>(sql_str.as_str()), &params)
+                    .fetch_all(pool),
+            )
+            .await?;
+            return AnyOk(map);
+        }
+
+        // The cells cover the viewport, or the bounding box of the entities if the viewport is not bounded.
+        let cell_size = |min: Option<f64>, max: Option<f64>| {
+            let (min, max) = (min.unwrap_or(0.0), max.unwrap_or(0.0));
+            let size = (max - min) / bins as f64;
+            (min, if size > 0.0 { size } else { 1.0 })
+        };
+        let (x0, cell_width) = cell_size(viewport.x_min.or(min_x), viewport.x_max.or(max_x));
+        let (y0, cell_height) = cell_size(viewport.y_min.or(min_y), viewport.y_max.or(max_y));
+
+        // The cell params follow the params of the where clause.
+        let n = params.len();
+        let sql_str = format!(
+            "SELECT entity_type, AVG(x) AS x, AVG(y) AS y, COUNT(*) AS count FROM (
+                SELECT entity_type, {x} AS x, {y} AS y,
+                       LEAST(FLOOR(({x} - ${}) / ${}), ${}) AS cell_x,
+                       LEAST(FLOOR(({y} - ${}) / ${}), ${}) AS cell_y
+                FROM biomedgps_entity2d WHERE {where_str}
+             ) AS t
+             GROUP BY entity_type, cell_x, cell_y
+             ORDER BY count DESC",
+            n + 1,
+            n + 2,
+            n + 5,
+            n + 3,
+            n + 4,
+            n + 5
+        );
+        debug!("sql_str: {}", sql_str);
+        map.clusters = with_query_timeout(
+            bind_values(
+                sqlx::query_as::<_, Entity2DCluster>(sql_str.as_str()),
+                &params,
+            )
+            .bind(x0)
+            .bind(cell_width)
+            .bind(y0)
+            .bind(cell_height)
+            .bind((bins - 1) as f64)
+            .fetch_all(pool),
+        )
+        .await?;
+        map.clustered = true;
+        map.cell_width = Some(cell_width);
+        map.cell_height = Some(cell_height);
+
+        AnyOk(map)
+    }
+}
+
+// UUID Pattern: https://stackoverflow.com/questions/136505/searching-for-uuids-in-text-with-regex
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, Validate)]
+pub struct Subgraph {
+    #[oai(read_only)]
+    pub id: String,
+
+    #[validate(length(
+        max = "DEFAULT_MAX_LENGTH",
+        min = "DEFAULT_MIN_LENGTH",
+        message = "The length of name must be between 1 and 64."
     ))]
+    pub name: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    /// The graph json string, e.g. {"nodes": [], "edges": []}. It is checked by `GraphPayload::parse` before saving, and stored as jsonb.
     #[validate(regex(
-        path = "ENTITY_ID_REGEX",
-        message = "The target_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `UniProtKB:P12345`."
+        path = "JSON_REGEX",
+        message = "The payload must be a valid json string."
     ))]
-    pub target_id: String,
-
-    pub key_sentence: String,
+    pub payload: String,
 
     #[serde(skip_deserializing)]
     #[serde(with = "ts_seconds")]
     #[oai(read_only)]
-    pub created_at: DateTime<Utc>,
+    pub created_time: DateTime<Utc>,
 
     #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of curator must be between 1 and 64."
+        min = 1,
+        max = 36,
+        message = "The owner length should be between 1 and 36"
     ))]
-    pub curator: String,
+    pub owner: String,
 
-    #[validate(range(min = 1, message = "pmid must be greater than 0"))]
-    pub pmid: i64,
+    #[validate(length(
+        min = 1,
+        max = 36,
+        message = "The version length should be between 1 and 36"
+    ))]
+    pub version: String,
 
-    // The payload field is a jsonb field which contains the project_id and organization_id.
-    pub payload: Option<serde_json::Value>,
+    #[validate(length(
+        min = 1,
+        max = 36,
+        message = "The db_version length should be between 1 and 36"
+    ))]
+    pub db_version: String,
+
+    #[validate(regex(
+        path = "SUBGRAPH_UUID_REGEX",
+        message = "The parent must match the ^[a-f0-9]{8}-[a-f0-9]{4}-[a-f0-9]{4}-[a-f0-9]{4}-[a-f0-9]{12}$ pattern."
+    ))]
+    pub parent: Option<String>, // parent subgraph id, it is same as id if it is a root subgraph (no parent), otherwise it is the parent subgraph id
+
+    /// The workspace which the subgraph belongs to, it is a personal subgraph if it is empty.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub workspace_id: Option<i64>,
+
+    /// The time when the subgraph was deleted, it can be restored until it is purged.
+    #[serde(default, skip_deserializing, with = "ts_seconds_option")]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// The revision of the subgraph, it starts from 1 and it is bumped by every update. Send it in the If-Match header when updating the subgraph, see [`Subgraph::parse_if_match`].
+    #[serde(default = "default_revision", skip_deserializing)]
+    #[oai(read_only)]
+    pub revision: i64,
 }
 
-impl KnowledgeCuration {
-    pub fn to_relation(&self) -> Relation {
-        Relation {
-            id: self.id,
-            relation_type: self.relation_type.clone(),
-            source_type: self.source_type.clone(),
-            source_id: self.source_id.clone(),
-            target_type: self.target_type.clone(),
-            target_id: self.target_id.clone(),
-            key_sentence: Some(self.key_sentence.clone()),
-            resource: self.curator.clone(),
-            pmids: Some(format!("{}", self.pmid)),
-            score: None,
-        }
+fn default_revision() -> i64 {
+    1
+}
+
+// The payload is a jsonb column, but it is a json string in the API, so we cannot derive the FromRow trait.
+impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for Subgraph {
+    fn from_row(row: &'r sqlx::postgres::PgRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        let payload: serde_json::Value = row.try_get("payload")?;
+
+        Ok(Subgraph {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            description: row.try_get("description")?,
+            payload: payload.to_string(),
+            created_time: row.try_get("created_time")?,
+            owner: row.try_get("owner")?,
+            version: row.try_get("version")?,
+            db_version: row.try_get("db_version")?,
+            parent: row.try_get("parent")?,
+            workspace_id: row.try_get("workspace_id")?,
+            deleted_at: row.try_get("deleted_at")?,
+            revision: row.try_get("revision")?,
+        })
     }
+}
 
-    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<KnowledgeCuration>, anyhow::Error> {
-        let columns = <KnowledgeCuration as CheckData>::fields().join(",");
-        let sql_str = format!("SELECT id,created_at,payload,{columns} FROM biomedgps_knowledge_curation");
-        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
-            .fetch_all(pool)
-            .await?;
+impl CheckData for Subgraph {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<Subgraph>(filepath)
+    }
 
-        AnyOk(records)
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "owner".to_string(),
+            "version".to_string(),
+            "db_version".to_string(),
+            "parent".to_string(),
+        ]
     }
 
-    pub async fn get_records_by_owner(
-        pool: &sqlx::PgPool,
-        curator: &str,
-        project_id: i32,
-        organization_id: i32,
-        page: Option<u64>,
-        page_size: Option<u64>,
-        order_by: Option<&str>,
-    ) -> Result<RecordResponse<KnowledgeCuration>, anyhow::Error> {
-        let project_id_qstr = if project_id >= 0 {
-            format!("payload->>'project_id' = '{}'", project_id)
-        } else {
-            format!("payload->>'project_id' IS NOT NULL")
-        };
+    fn fields() -> Vec<String> {
+        vec![
+            "name".to_string(),
+            "description".to_string(),
+            "payload".to_string(),
+            "owner".to_string(),
+            "version".to_string(),
+            "db_version".to_string(),
+            "parent".to_string(),
+        ]
+    }
 
-        let organization_id_qstr = if organization_id >= 0 {
-            format!("payload->>'organization_id' = '{}'", organization_id)
-        } else {
-            format!("payload->>'organization_id' IS NOT NULL")
-        };
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields.push("created_time".to_string());
+        fields.push("workspace_id".to_string());
+        fields.push("revision".to_string());
+        fields
+    }
 
-        let curator_qstr = if project_id < 0 && organization_id < 0 {
-            format!("curator = '{}'", curator)
-        } else {
-            format!("curator IS NOT NULL")
-        };
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "name".to_string(),
+            "owner".to_string(),
+            "version".to_string(),
+            "db_version".to_string(),
+            "created_time".to_string(),
+            "revision".to_string(),
+        ]
+    }
 
-        let where_str = format!(
-            "{} AND {} AND {}",
-            curator_qstr, project_id_qstr, organization_id_qstr
-        );
+    fn json_fields() -> Vec<String> {
+        vec!["payload".to_string()]
+    }
+}
 
-        let page = match page {
-            Some(page) => page,
-            None => 1,
+impl Subgraph {
+    pub fn update_owner(&mut self, username: String) -> &Self {
+        self.owner = username;
+        return self;
+    }
+
+    /// Record the release of the dataset which the subgraph is built from.
+    pub fn update_db_version(&mut self, db_version: String) -> &Self {
+        self.db_version = db_version;
+        self
+    }
+
+    /// Merge a condition into the query to keep the subgraphs which contain the node. The node id must be a composed id, such as `Gene::ENTREZ:7157`.
+    ///
+    /// ```
+    /// use biomedgps::model::core::Subgraph;
+    ///
+    /// let query = Subgraph::merge_node_query("Gene::ENTREZ:7157", None).unwrap();
+    /// match query {
+    ///     biomedgps::query_builder::sql_builder::ComposeQuery::QueryItem(item) => {
+    ///         assert_eq!(item.format(), r#"payload -> 'nodes' @> '[{"id":"Gene::ENTREZ:7157"}]'"#);
+    ///     }
+    ///     _ => panic!("The node id should be converted to a query item."),
+    /// }
+    ///
+    /// assert!(Subgraph::merge_node_query("Gene::ENTREZ:7157' OR 1=1", None).is_err());
+    /// ```
+    pub fn merge_node_query(
+        node_id: &str,
+        query: Option<ComposeQuery>,
+    ) -> Result<ComposeQuery, ValidationError> {
+        if !COMPOSED_ENTITY_REGEX.is_match(node_id) {
+            return Err(ValidationError::new(&format!(
+                "Invalid node id: {}, it should be a composed id, such as Gene::ENTREZ:7157.",
+                node_id
+            )));
+        }
+
+        // The jsonb containment can use the gin index on the nodes of the payload.
+        let node_query = ComposeQuery::QueryItem(QueryItem::new(
+            "payload -> 'nodes'".to_string(),
+            Value::String(serde_json::json!([{ "id": node_id }]).to_string()),
+            "@>".to_string(),
+        ));
+
+        match query {
+            Some(query) => {
+                let mut merged = ComposeQueryItem::new("and");
+                merged.add_item(query).add_item(node_query);
+                Ok(ComposeQuery::ComposeQueryItem(merged))
+            }
+            None => Ok(node_query),
+        }
+    }
+
+    /// Parse the payload of the subgraph as a graph, see [`GraphPayload::parse`].
+    pub fn get_graph_payload(&self) -> Result<GraphPayload, ValidationError> {
+        GraphPayload::parse(&self.payload).map_err(|e| {
+            ValidationError::new(&format!(
+                "The payload of the subgraph {} is not a valid graph: {}",
+                self.id, e
+            ))
+        })
+    }
+
+    /// Merge the subgraphs into a new subgraph (not saved), the payload is the union of the payloads, see [`GraphPayload::union`]. The version and db_version are inherited from the first subgraph.
+    pub fn merge(
+        subgraphs: &[Subgraph],
+        name: &str,
+        description: Option<String>,
+        owner: &str,
+    ) -> Result<Subgraph, ValidationError> {
+        let first = match subgraphs.first() {
+            Some(first) => first,
+            None => return Err(ValidationError::new("There is no subgraph to merge.")),
         };
 
-        let page_size = match page_size {
-            Some(page_size) => page_size,
-            None => 10,
+        let payloads = subgraphs
+            .iter()
+            .map(|subgraph| subgraph.get_graph_payload())
+            .collect::<Result<Vec<GraphPayload>, ValidationError>>()?;
+        let payload = match serde_json::to_string(&GraphPayload::union(&payloads)) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return Err(ValidationError::new(&format!(
+                    "Failed to serialize the merged payload: {}",
+                    e
+                )))
+            }
         };
 
-        let limit = page_size;
-        let offset = (page - 1) * page_size;
+        Ok(Subgraph {
+            id: "".to_string(),
+            name: name.to_string(),
+            description,
+            payload,
+            created_time: Utc::now(),
+            owner: owner.to_string(),
+            version: first.version.clone(),
+            db_version: first.db_version.clone(),
+            parent: None,
+            workspace_id: first.workspace_id,
+            deleted_at: None,
+            revision: default_revision(),
+        })
+    }
 
-        let order_by_str = if order_by.is_none() {
-            "".to_string()
-        } else {
-            format!("ORDER BY {}", order_by.unwrap())
+    /// Make a new subgraph (not saved) from a graph which is built by the server, such as the graph of a gene list. The version is the version of the server, and the db_version is the dataset version of the graph.
+    pub fn from_graph(
+        graph: &Graph,
+        name: &str,
+        description: Option<String>,
+        owner: &str,
+    ) -> Result<Subgraph, ValidationError> {
+        let payload = match serde_json::to_string(graph) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return Err(ValidationError::new(&format!(
+                    "Failed to serialize the graph: {}",
+                    e
+                )))
+            }
         };
 
-        let sql_str = format!(
-            "SELECT * FROM biomedgps_knowledge_curation WHERE {} {} LIMIT {} OFFSET {}",
-            where_str, order_by_str, limit, offset
-        );
+        if let Err(e) = GraphPayload::parse(&payload) {
+            return Err(ValidationError::new(&format!(
+                "The graph is not a valid subgraph payload: {}",
+                e
+            )));
+        }
 
-        let records = sqlx::query_as::<_, KnowledgeCuration>(sql_str.as_str())
-            .fetch_all(pool)
+        Ok(Subgraph {
+            id: "".to_string(),
+            name: name.to_string(),
+            description,
+            payload,
+            created_time: Utc::now(),
+            owner: owner.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            db_version: graph.get_dataset_version().unwrap_or("unknown").to_string(),
+            parent: None,
+            workspace_id: None,
+            deleted_at: None,
+            revision: default_revision(),
+        })
+    }
+
+    /// The fields of the subgraph which are sent in a `subgraph.shared` event, the payload is left out because it may be large.
+    pub fn get_share_summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "name": self.name,
+            "description": self.description,
+            "owner": self.owner,
+            "workspace_id": self.workspace_id,
+        })
+    }
+
+    /// Compare the payload with the one of another subgraph, see [`GraphPayload::diff`].
+    pub fn diff(&self, other: &Subgraph) -> Result<GraphPayloadDiff, ValidationError> {
+        Ok(self.get_graph_payload()?.diff(&other.get_graph_payload()?))
+    }
+
+    /// Fetch a subgraph by id, return None if it doesn't exist or it has been deleted.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        id: &str,
+    ) -> Result<Option<Subgraph>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_subgraph WHERE id = $1 AND deleted_at IS NULL";
+        let record = sqlx::query_as::<_, Subgraph>(sql_str)
+            .bind(id)
+            .fetch_optional(pool)
             .await?;
 
-        let sql_str = format!(
-            "SELECT COUNT(*) FROM biomedgps_knowledge_curation WHERE {}",
-            where_str
-        );
+        match record {
+            Some(record) => AnyOk(Some(record.resolve_payload().await?)),
+            None => AnyOk(None),
+        }
+    }
+
+    /// Resolve the payload if it has been offloaded to the object storage, see [`crate::model::storage`].
+    pub async fn resolve_payload(mut self) -> Result<Subgraph, anyhow::Error> {
+        self.payload = load_payload(&self.payload).await?;
+        AnyOk(self)
+    }
+
+    /// Resolve the payloads of the subgraphs concurrently, see [`Subgraph::resolve_payload`].
+    pub async fn resolve_payloads(records: Vec<Subgraph>) -> Result<Vec<Subgraph>, anyhow::Error> {
+        futures::future::try_join_all(records.into_iter().map(|record| record.resolve_payload()))
+            .await
+    }
+
+    /// Resolve the payloads of the projected subgraphs (see [`ProjectedRecordResponse`]), the payload is kept as json in the projected records.
+    pub async fn resolve_projected_payloads(
+        records: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        futures::future::try_join_all(records.into_iter().map(|mut record| async move {
+            if let Some(reference) = record.get("payload").and_then(PayloadRef::from_value) {
+                let payload = load_payload(&serde_json::to_string(&reference)?).await?;
+                record["payload"] = serde_json::from_str(&payload)?;
+            }
+            AnyOk(record)
+        }))
+        .await
+    }
+
+    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<Subgraph, anyhow::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let parent = if self.parent.is_none() {
+            id.clone()
+        } else {
+            self.parent.clone().unwrap()
+        };
 
-        let total = sqlx::query_as::<_, (i64,)>(sql_str.as_str())
+        let payload = store_payload(&id, &self.payload).await?;
+        let sql_str = "INSERT INTO biomedgps_subgraph (id, name, description, payload, owner, version, db_version, parent, workspace_id) VALUES ($1, $2, $3, $4::JSONB, $5, $6, $7, $8, $9) RETURNING *";
+        let mut subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
+            .bind(id)
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(payload)
+            .bind(&self.owner)
+            .bind(&self.version)
+            .bind(&self.db_version)
+            .bind(parent)
+            .bind(self.workspace_id)
             .fetch_one(pool)
             .await?;
 
-        AnyOk(RecordResponse {
-            records: records,
-            total: total.0 as u64,
-            page: page,
-            page_size: page_size,
-        })
+        // The returned payload may be a reference, but the saved payload is the same as ours.
+        subgraph.payload = self.payload.clone();
+        AnyOk(subgraph)
     }
 
-    fn get_value(key: &str, json: &serde_json::Value) -> Result<String, anyhow::Error> {
-        match json[key].as_str() {
-            Some(value) => Ok(value.to_string()),
-            None => Err(anyhow::anyhow!("The {} field is missing.", key)),
+    /// Parse the revision in the If-Match header, such as `"3"` (the ETag of the subgraph) or `3`. The `*` matches any revision, so it returns None to overwrite the subgraph without the check.
+    ///
+    /// ```
+    /// use biomedgps::model::core::Subgraph;
+    ///
+    /// assert_eq!(Subgraph::parse_if_match("\"3\"").unwrap(), Some(3));
+    /// assert_eq!(Subgraph::parse_if_match(" W/\"3\" ").unwrap(), Some(3));
+    /// assert_eq!(Subgraph::parse_if_match("*").unwrap(), None);
+    /// assert!(Subgraph::parse_if_match("\"abc\"").is_err());
+    /// ```
+    pub fn parse_if_match(value: &str) -> Result<Option<i64>, ValidationError> {
+        let value = value.trim();
+        if value == "*" {
+            return Ok(None);
         }
-    }
-
-    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<KnowledgeCuration, anyhow::Error> {
-        let sql_str = "INSERT INTO biomedgps_knowledge_curation (relation_type, source_name, source_type, source_id, target_name, target_type, target_id, key_sentence, curator, pmid, payload) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING *";
-        let payload = match &self.payload {
-            Some(payload) => sqlx::types::Json(Payload {
-                project_id: KnowledgeCuration::get_value("project_id", payload)?,
-                organization_id: KnowledgeCuration::get_value("organization_id", payload)?,
-            }),
-            None => sqlx::types::Json(Payload {
-                project_id: "0".to_string(),
-                organization_id: "0".to_string(),
-            }),
-        };
 
-        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
-            .bind(&self.relation_type)
-            .bind(&self.source_name)
-            .bind(&self.source_type)
-            .bind(&self.source_id)
-            .bind(&self.target_name)
-            .bind(&self.target_type)
-            .bind(&self.target_id)
-            .bind(&self.key_sentence)
-            .bind(&self.curator)
-            .bind(&self.pmid)
-            .bind(&payload)
-            .fetch_one(pool)
-            .await?;
+        let tag = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+        match tag.parse::<i64>() {
+            Ok(revision) if revision > 0 => Ok(Some(revision)),
+            _ => Err(ValidationError::new(&format!(
+                "Invalid If-Match header: {}, it should be the ETag of the subgraph, such as \"3\".",
+                value
+            ))),
+        }
+    }
 
-        AnyOk(knowledge_curation)
+    /// The ETag of the subgraph, it is the quoted revision, such as `"3"`.
+    pub fn get_etag(&self) -> String {
+        format!("\"{}\"", self.revision)
     }
 
+    /// Update the subgraph and bump its revision. If the revision is set, the subgraph is only updated when its revision is still the same, so the changes of others are not overwritten. It returns None if the subgraph doesn't exist, it has been deleted or its revision has changed.
     pub async fn update(
         &self,
         pool: &sqlx::PgPool,
-        id: i64,
-    ) -> Result<KnowledgeCuration, anyhow::Error> {
-        let sql_str = "UPDATE biomedgps_knowledge_curation SET relation_type = $1, source_name = $2, source_type = $3, source_id = $4, target_name = $5, target_type = $6, target_id = $7, key_sentence = $8, created_at = now(), pmid = $9 WHERE id = $10 RETURNING *";
-        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
-            .bind(&self.relation_type)
-            .bind(&self.source_name)
-            .bind(&self.source_type)
-            .bind(&self.source_id)
-            .bind(&self.target_name)
-            .bind(&self.target_type)
-            .bind(&self.target_id)
-            .bind(&self.key_sentence)
-            .bind(&self.pmid)
+        id: &str,
+        revision: Option<i64>,
+    ) -> Result<Option<Subgraph>, anyhow::Error> {
+        let payload = store_payload(id, &self.payload).await?;
+        let sql_str = "UPDATE biomedgps_subgraph SET name = $1, description = $2, payload = $3::JSONB, db_version = $4, workspace_id = $5, revision = revision + 1 WHERE id = $6 AND deleted_at IS NULL AND ($7::BIGINT IS NULL OR revision = $7) RETURNING *";
+        let subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(payload)
+            .bind(&self.db_version)
+            .bind(self.workspace_id)
             .bind(id)
-            .fetch_one(pool)
+            .bind(revision)
+            .fetch_optional(pool)
             .await?;
 
-        AnyOk(knowledge_curation)
+        AnyOk(subgraph.map(|mut subgraph| {
+            subgraph.payload = self.payload.clone();
+            subgraph
+        }))
     }
 
-    pub async fn delete(pool: &sqlx::PgPool, id: i64) -> Result<KnowledgeCuration, anyhow::Error> {
-        let sql_str = "DELETE FROM biomedgps_knowledge_curation WHERE id = $1 RETURNING *";
-        let knowledge_curation = sqlx::query_as::<_, KnowledgeCuration>(sql_str)
+    /// Fetch a deleted subgraph by id, return None if it doesn't exist, it isn't deleted or it has been purged.
+    pub async fn get_deleted_record(
+        pool: &sqlx::PgPool,
+        id: &str,
+    ) -> Result<Option<Subgraph>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_subgraph WHERE id = $1 AND deleted_at IS NOT NULL";
+        let record = sqlx::query_as::<_, Subgraph>(sql_str)
             .bind(id)
-            .fetch_one(pool)
+            .fetch_optional(pool)
             .await?;
 
-        AnyOk(knowledge_curation)
+        match record {
+            Some(record) => AnyOk(Some(record.resolve_payload().await?)),
+            None => AnyOk(None),
+        }
     }
-}
 
-impl CheckData for KnowledgeCuration {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<KnowledgeCuration>(filepath)
-    }
+    /// Soft delete a subgraph, it is hidden from all queries but it can be restored until it is purged. The child subgraphs of a root subgraph are deleted together, as the hard deletion cascades to them.
+    pub async fn delete(pool: &sqlx::PgPool, id: &str) -> Result<Subgraph, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let sql_str = "UPDATE biomedgps_subgraph SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL RETURNING *";
+        let subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
+            .bind(id)
+            .fetch_one(&mut tx)
+            .await?;
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "relation_type".to_string(),
-            "source_type".to_string(),
-            "source_id".to_string(),
-            "target_type".to_string(),
-            "target_id".to_string(),
-            "curator".to_string(),
-            "pmid".to_string(),
-        ]
+        // The children share the deletion time, so they are restored together with the root subgraph.
+        sqlx::query(
+            "UPDATE biomedgps_subgraph SET deleted_at = $1 WHERE parent = $2 AND id <> $2 AND deleted_at IS NULL",
+        )
+        .bind(subgraph.deleted_at)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        subgraph.resolve_payload().await
     }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "relation_type".to_string(),
-            "source_name".to_string(),
-            "source_type".to_string(),
-            "source_id".to_string(),
-            "target_name".to_string(),
-            "target_type".to_string(),
-            "target_id".to_string(),
-            "key_sentence".to_string(),
-            "curator".to_string(),
-            "pmid".to_string(),
-        ]
+    /// Restore a deleted subgraph and the child subgraphs which were deleted together with it.
+    pub async fn restore(pool: &sqlx::PgPool, id: &str) -> Result<Subgraph, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let deleted = sqlx::query_as::<_, Subgraph>(
+            "SELECT * FROM biomedgps_subgraph WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE biomedgps_subgraph SET deleted_at = NULL WHERE parent = $1 AND id <> $1 AND deleted_at = $2",
+        )
+        .bind(id)
+        .bind(deleted.deleted_at)
+        .execute(&mut tx)
+        .await?;
+
+        let subgraph = sqlx::query_as::<_, Subgraph>(
+            "UPDATE biomedgps_subgraph SET deleted_at = NULL WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        subgraph.resolve_payload().await
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
-pub struct Relation {
+/// A named query definition of the query DSL, it can be shared with other users and executed by reference.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct SavedQuery {
     // Ignore this field when deserialize from json
     #[serde(skip_deserializing)]
     #[oai(read_only)]
@@ -1202,363 +4230,729 @@ pub struct Relation {
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of relation_type must be between 1 and 64."
+        message = "The length of name must be between 1 and 64."
     ))]
-    pub relation_type: String,
+    pub name: String,
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    /// One of entity, relation, entity2d, knowledge_curation and subgraph.
     #[validate(regex(
-        path = "ENTITY_ID_REGEX",
-        message = "The source_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
+        path = "SAVED_QUERY_TABLE_REGEX",
+        message = "The target_table must be one of entity, relation, entity2d, knowledge_curation and subgraph."
     ))]
-    pub source_id: String,
+    pub target_table: String,
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
+    /// The ComposeQuery json string, such as `{"operator": "=", "field": "id", "value": "DOID:2022"}`.
     #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The source_type must match the ^[A-Za-z]+$ pattern."
+        path = "JSON_REGEX",
+        message = "The query must be a valid json string."
     ))]
-    pub source_type: String,
+    pub query: String,
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
+    /// The default sort, such as `score DESC, id ASC`.
     #[validate(regex(
-        path = "ENTITY_ID_REGEX",
-        message = "The source_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
+        path = "ORDER_BY_REGEX",
+        message = "The order_by must be a list of fields with an optional ASC or DESC, such as `score DESC, id ASC`."
     ))]
-    pub target_id: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub order_by: Option<String>,
 
     #[validate(length(
         max = "DEFAULT_MAX_LENGTH",
         min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
-    #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The target_type must match the ^[A-Za-z]+$ pattern."
+        message = "The length of owner must be between 1 and 64."
     ))]
-    pub target_type: String,
+    pub owner: String,
 
-    #[oai(skip_serializing_if_is_none)]
-    pub score: Option<f64>,
+    /// Whether other users can list and execute the saved query.
+    #[serde(default)]
+    #[oai(default)]
+    pub shared: bool,
 
+    /// The workspace which the saved query belongs to, all members of the workspace can list and execute it.
+    #[serde(default)]
+    #[sqlx(default)]
     #[oai(skip_serializing_if_is_none)]
-    pub key_sentence: Option<String>,
-
-    pub resource: String,
+    pub workspace_id: Option<i64>,
 
-    #[oai(skip_serializing_if_is_none)]
-    pub pmids: Option<String>,
+    #[serde(skip_deserializing)]
+    #[serde(with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
 }
 
-impl CheckData for Relation {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<Relation>(filepath)
+impl SavedQuery {
+    pub fn update_owner(&mut self, username: String) -> &Self {
+        self.owner = username;
+        self
     }
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "relation_type".to_string(),
-            "source_id".to_string(),
-            "source_type".to_string(),
-            "target_id".to_string(),
-            "target_type".to_string(),
-        ]
+    /// The target table, such as `TableName::Entity` for entity.
+    pub fn get_table(&self) -> Result<TableName, ValidationError> {
+        TableName::from_short_name(&self.target_table)
     }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "relation_type".to_string(),
-            "source_id".to_string(),
-            "source_type".to_string(),
-            "target_id".to_string(),
-            "target_type".to_string(),
-            "score".to_string(),
-            "key_sentence".to_string(),
-            "resource".to_string(),
-            "pmids".to_string(),
-        ]
+    /// Parse the query field as a ComposeQuery.
+    pub fn get_query(&self) -> Result<ComposeQuery, anyhow::Error> {
+        AnyOk(serde_json::from_str(&self.query)?)
+    }
+
+    /// Fetch the saved queries which are owned by the user or shared by other users, the ones in the workspaces which the user is not a member of are excluded unless `all` is true (for the admins). If the workspace_id is set, all saved queries in the workspace are returned instead.
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        owner: &str,
+        workspace_id: Option<i64>,
+        all: bool,
+    ) -> Result<Vec<SavedQuery>, anyhow::Error> {
+        let records = match workspace_id {
+            Some(workspace_id) => {
+                sqlx::query_as::<_, SavedQuery>(
+                    "SELECT * FROM biomedgps_saved_query WHERE workspace_id = $1 ORDER BY created_at DESC",
+                )
+                .bind(workspace_id)
+                .fetch_all(pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, SavedQuery>(
+                    "SELECT * FROM biomedgps_saved_query WHERE (owner = $1 OR shared = TRUE)
+                     AND ($2 OR workspace_id IS NULL OR workspace_id IN (SELECT workspace_id FROM biomedgps_workspace_member WHERE username = $1))
+                     ORDER BY created_at DESC",
+                )
+                .bind(owner)
+                .bind(all)
+                .fetch_all(pool)
+                .await?
+            }
+        };
+
+        AnyOk(records)
+    }
+
+    /// Fetch a saved query by id, return None if it doesn't exist.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<Option<SavedQuery>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_saved_query WHERE id = $1";
+        let record = sqlx::query_as::<_, SavedQuery>(sql_str)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<SavedQuery, anyhow::Error> {
+        let sql_str = "INSERT INTO biomedgps_saved_query (name, description, target_table, query, order_by, owner, shared, workspace_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *";
+        let saved_query = sqlx::query_as::<_, SavedQuery>(sql_str)
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(&self.target_table)
+            .bind(&self.query)
+            .bind(&self.order_by)
+            .bind(&self.owner)
+            .bind(self.shared)
+            .bind(self.workspace_id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(saved_query)
+    }
+
+    pub async fn update(&self, pool: &sqlx::PgPool, id: i64) -> Result<SavedQuery, anyhow::Error> {
+        let sql_str = "UPDATE biomedgps_saved_query SET name = $1, description = $2, target_table = $3, query = $4, order_by = $5, shared = $6, workspace_id = $7 WHERE id = $8 RETURNING *";
+        let saved_query = sqlx::query_as::<_, SavedQuery>(sql_str)
+            .bind(&self.name)
+            .bind(&self.description)
+            .bind(&self.target_table)
+            .bind(&self.query)
+            .bind(&self.order_by)
+            .bind(self.shared)
+            .bind(self.workspace_id)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(saved_query)
+    }
+
+    pub async fn delete(pool: &sqlx::PgPool, id: i64) -> Result<SavedQuery, anyhow::Error> {
+        let sql_str = "DELETE FROM biomedgps_saved_query WHERE id = $1 RETURNING *";
+        let saved_query = sqlx::query_as::<_, SavedQuery>(sql_str)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(saved_query)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
-pub struct RelationCount {
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of relation_type must be between 1 and 64."
-    ))]
-    pub relation_type: String,
+/// The records which are returned by executing a saved query, the records are the rows of the target table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct SavedQueryResult {
+    pub saved_query: SavedQuery,
+    pub records: Vec<serde_json::Value>,
+    pub total: u64,
+    pub page: u64,
+    pub page_size: u64,
+    pub total_pages: u64,
+    pub has_next: bool,
+}
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
-    #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The target_type must match the ^[A-Za-z]+$ pattern."
-    ))]
-    pub target_type: String,
+impl SavedQueryResult {
+    async fn run_on_table<S>(
+        pool: &sqlx::PgPool,
+        saved_query: SavedQuery,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+    ) -> Result<Self, anyhow::Error>
+    where
+        S: Serialize
+            + for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow>
+            + std::fmt::Debug
+            + std::marker::Unpin
+            + Send
+            + Sync
+            + poem_openapi::types::Type
+            + poem_openapi::types::ParseFromJSON
+            + poem_openapi::types::ToJSON,
+    {
+        let query = Some(saved_query.get_query()?);
+        let table = saved_query.get_table()?;
+        let records =
+            RecordResponse::<S>::get_records(pool, table, &query, page, page_size, order_by)
+                .await?;
+
+        AnyOk(SavedQueryResult {
+            saved_query,
+            records: records
+                .records
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<serde_json::Value>, serde_json::Error>>()?,
+            total: records.total,
+            page: records.page,
+            page_size: records.page_size,
+            total_pages: records.total_pages,
+            has_next: records.has_next,
+        })
+    }
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of source_name must be between 1 and 64."
-    ))]
-    #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The source_type must match the ^[A-Za-z]+$ pattern."
-    ))]
-    pub source_type: String,
+    /// Execute the saved query on its target table.
+    pub async fn run(
+        pool: &sqlx::PgPool,
+        saved_query: SavedQuery,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        order_by: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        match saved_query.get_table()? {
+            TableName::Entity => {
+                Self::run_on_table::<Entity>(pool, saved_query, page, page_size, order_by).await
+            }
+            TableName::Relation => {
+                Self::run_on_table::<Relation>(pool, saved_query, page, page_size, order_by).await
+            }
+            TableName::Entity2D => {
+                Self::run_on_table::<Entity2D>(pool, saved_query, page, page_size, order_by).await
+            }
+            TableName::KnowledgeCuration => {
+                Self::run_on_table::<KnowledgeCuration>(
+                    pool,
+                    saved_query,
+                    page,
+                    page_size,
+                    order_by,
+                )
+                .await
+            }
+            TableName::Subgraph => {
+                Self::run_on_table::<Subgraph>(pool, saved_query, page, page_size, order_by).await
+            }
+            TableName::EntityEmbedding => Err(anyhow::anyhow!(
+                "Unsupported target table: {}",
+                saved_query.target_table
+            )),
+        }
+    }
+}
 
-    pub resource: String,
+/// The default max number of groups which are returned by an aggregation query.
+pub const DEFAULT_MAX_GROUPS: u64 = 1000;
+
+/// Get the fields which can be used in the aggregation mode of a table.
+///
+/// # Returns
+///
+/// * `Some((groupable_fields, numeric_fields))` - The fields which can be used in the GROUP BY clause and the fields which can be aggregated by sum and avg.
+/// * `None` - The table doesn't support the aggregation mode.
+pub fn get_aggregate_fields(table: &str) -> Option<(Vec<&'static str>, Vec<&'static str>)> {
+    match table {
+        "entity" => Some((vec!["label", "resource"], vec![])),
+        "relation" => Some((
+            vec![
+                "relation_type",
+                "source_type",
+                "target_type",
+                "resource",
+                "split",
+                "dataset_version",
+            ],
+            vec!["score"],
+        )),
+        "knowledge_curation" => Some((
+            vec!["relation_type", "source_type", "target_type", "curator"],
+            vec![],
+        )),
+        "subgraph" => Some((vec!["owner", "version", "db_version"], vec![])),
+        _ => None,
+    }
+}
 
-    pub ncount: i64,
+/// One group of an aggregation query, such as `{"group": {"relation_type": "Treats"}, "value": 100}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct AggregateRecord {
+    /// The values of the group-by fields.
+    pub group: HashMap<String, Option<String>>,
+    /// The aggregated value, it may be null when all values of the field are null.
+    pub value: Option<f64>,
 }
 
-impl RelationCount {
+impl AggregateRecord {
     pub async fn get_records(
         pool: &sqlx::PgPool,
+        table: TableName,
+        aggregate_query: &AggregateQuery,
         query: &Option<ComposeQuery>,
-    ) -> Result<Vec<RelationCount>, anyhow::Error> {
-        let mut query_str = match query {
-            Some(ComposeQuery::QueryItem(item)) => item.format(),
-            Some(ComposeQuery::ComposeQueryItem(item)) => item.format(),
-            None => "".to_string(),
+        limit: u64,
+    ) -> Result<Vec<AggregateRecord>, anyhow::Error> {
+        use sqlx::Row;
+
+        let (mut query_str, params) = match query {
+            Some(query) => query.format_with_params(&get_column_types(pool, table).await?),
+            None => ("".to_string(), vec![]),
         };
 
         if query_str.is_empty() {
             query_str = "1=1".to_string();
         };
+        let query_str = exclude_deleted_records(table, query_str);
+
+        let sql_str = aggregate_query.format(&table.quoted(), &query_str, limit);
+        debug!("Aggregation query: {}", sql_str);
+        let rows = with_query_timeout(
+            bind_query_values(sqlx::query(sql_str.as_str()), &params).fetch_all(pool),
+        )
+        .await?;
+
+        let mut records = vec![];
+        for row in rows.iter() {
+            let mut group = HashMap::new();
+            for field in aggregate_query.group_by.iter() {
+                group.insert(
+                    field.clone(),
+                    row.try_get::<Option<String>, _>(field.as_str())?,
+                );
+            }
 
-        let sql_str = format!(
-            "SELECT relation_type, source_type, target_type, resource, COUNT(*) as ncount FROM biomedgps_relation WHERE {} GROUP BY relation_type, source_type, target_type, resource",
-            query_str
-        );
-
-        let records = sqlx::query_as::<_, RelationCount>(sql_str.as_str())
-            .fetch_all(pool)
-            .await?;
+            records.push(AggregateRecord {
+                group,
+                value: row.try_get::<Option<f64>, _>("value")?,
+            });
+        }
 
         AnyOk(records)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
-pub struct Entity2D {
-    pub embedding_id: i64,
-
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of entity_id must be between 1 and 64."
-    ))]
-    #[validate(regex(
-        path = "ENTITY_ID_REGEX",
-        message = "The entity_id must match the ^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$ pattern. eg: UniProtKB:P12345"
-    ))]
-    pub entity_id: String,
-
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of entity_type must be between 1 and 64."
-    ))]
-    #[validate(regex(
-        path = "ENTITY_LABEL_REGEX",
-        message = "The entity_type must match the ^[A-Za-z]+$ pattern."
-    ))]
-    pub entity_type: String,
-
-    #[validate(length(
-        max = "ENTITY_NAME_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of entity_name must be between 1 and 255."
-    ))]
-    pub entity_name: String,
+/// The status of an import job, only the running and failed jobs can be resumed.
+pub const IMPORT_JOB_RUNNING: &str = "running";
+pub const IMPORT_JOB_COMPLETED: &str = "completed";
+pub const IMPORT_JOB_FAILED: &str = "failed";
 
-    pub umap_x: f64,
+/// An import job records the checkpoint of importing a data file, the rows are committed batch by batch and the checkpoint is updated in the same transaction. So an interrupted import can be resumed by `importdb --resume <job_id>` from the last committed batch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct ImportJob {
+    pub id: i64,
+    /// The table which the data is imported into, such as entity, relation, entity2d, knowledge_curation and subgraph.
+    pub table_name: String,
+    pub filepath: String,
+    /// running, completed or failed.
+    pub status: String,
+    pub batch_size: i64,
+    /// The byte offset of the next row in the normalized data file.
+    pub file_offset: i64,
+    /// The number of rows which have been read and committed.
+    pub committed_rows: i64,
+    /// The number of rows which are inserted, the rows which already exist in the table are skipped.
+    pub rows_inserted: i64,
+    /// The number of the validation errors and the failed batches.
+    pub errors: i64,
+    /// The throughput of the last run of the import job.
+    pub rows_per_second: f64,
 
-    pub umap_y: f64,
+    #[oai(skip_serializing_if_is_none)]
+    pub message: Option<String>,
 
-    pub tsne_x: f64,
+    #[serde(with = "ts_seconds")]
+    pub created_at: DateTime<Utc>,
 
-    pub tsne_y: f64,
+    #[serde(with = "ts_seconds")]
+    pub updated_at: DateTime<Utc>,
 }
 
-impl CheckData for Entity2D {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<Entity2D>(filepath)
+impl ImportJob {
+    /// Create a running import job for a data file.
+    pub async fn create(
+        pool: &sqlx::PgPool,
+        table_name: &str,
+        filepath: &str,
+        batch_size: usize,
+    ) -> Result<ImportJob, anyhow::Error> {
+        let record = sqlx::query_as::<_, ImportJob>(
+            "INSERT INTO biomedgps_import_job (table_name, filepath, status, batch_size)
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(table_name)
+        .bind(filepath)
+        .bind(IMPORT_JOB_RUNNING)
+        .bind(batch_size as i64)
+        .fetch_one(pool)
+        .await?;
+
+        AnyOk(record)
     }
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "embedding_id".to_string(),
-            "entity_id".to_string(),
-            "entity_type".to_string(),
-        ]
+    pub async fn get_record(pool: &sqlx::PgPool, id: i64) -> Result<ImportJob, anyhow::Error> {
+        let record =
+            sqlx::query_as::<_, ImportJob>("SELECT * FROM biomedgps_import_job WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool)
+                .await?;
+
+        match record {
+            Some(record) => AnyOk(record),
+            None => Err(anyhow::anyhow!("Cannot find the import job {}.", id)),
+        }
     }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "embedding_id".to_string(),
-            "entity_id".to_string(),
-            "entity_type".to_string(),
-            "entity_name".to_string(),
-            "umap_x".to_string(),
-            "umap_y".to_string(),
-            "tsne_x".to_string(),
-            "tsne_y".to_string(),
-        ]
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<ImportJob>, anyhow::Error> {
+        let records = sqlx::query_as::<_, ImportJob>(
+            "SELECT * FROM biomedgps_import_job ORDER BY created_at DESC, id DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
     }
-}
 
-// UUID Pattern: https://stackoverflow.com/questions/136505/searching-for-uuids-in-text-with-regex
+    /// Whether the import job can be resumed, the completed jobs are not resumable.
+    pub fn is_resumable(&self) -> bool {
+        self.status != IMPORT_JOB_COMPLETED
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow, Validate)]
-pub struct Subgraph {
-    #[oai(read_only)]
-    pub id: String,
+    /// Save the checkpoint and the progress after a batch is imported, it must be executed in the transaction of the batch.
+    pub async fn checkpoint(
+        &mut self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        file_offset: u64,
+        batch_rows: usize,
+        inserted_rows: u64,
+        rows_per_second: f64,
+    ) -> Result<(), anyhow::Error> {
+        let record = sqlx::query_as::<_, ImportJob>(
+            "UPDATE biomedgps_import_job
+             SET file_offset = $2, committed_rows = committed_rows + $3, rows_inserted = rows_inserted + $4,
+                 rows_per_second = $5, status = $6, message = NULL, updated_at = now()
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .bind(file_offset as i64)
+        .bind(batch_rows as i64)
+        .bind(inserted_rows as i64)
+        .bind(rows_per_second)
+        .bind(IMPORT_JOB_RUNNING)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        *self = record;
+        AnyOk(())
+    }
 
-    #[validate(length(
-        max = "DEFAULT_MAX_LENGTH",
-        min = "DEFAULT_MIN_LENGTH",
-        message = "The length of name must be between 1 and 64."
-    ))]
-    pub name: String,
+    /// Mark the import job as completed or failed (with the error message and the number of errors).
+    pub async fn finish(
+        &mut self,
+        pool: &sqlx::PgPool,
+        message: Option<String>,
+        errors: usize,
+    ) -> Result<(), anyhow::Error> {
+        let status = if message.is_none() {
+            IMPORT_JOB_COMPLETED
+        } else {
+            IMPORT_JOB_FAILED
+        };
 
-    #[oai(skip_serializing_if_is_none)]
-    pub description: Option<String>,
+        let record = sqlx::query_as::<_, ImportJob>(
+            "UPDATE biomedgps_import_job SET status = $2, message = $3, errors = errors + $4, updated_at = now()
+             WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .bind(status)
+        .bind(message)
+        .bind(errors as i64)
+        .fetch_one(pool)
+        .await?;
+
+        *self = record;
+        AnyOk(())
+    }
+}
 
-    #[validate(regex(
-        path = "JSON_REGEX",
-        message = "The payload must be a valid json string."
-    ))]
-    pub payload: String, // json string, e.g. {"nodes": [], "edges": []}. how to validate json string?
+/// A release of the knowledge graph which has been imported. The release with the latest import time is the active dataset version, it is stamped on the graph and statistics responses and saved as the db_version of the subgraphs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow)]
+pub struct DatasetVersion {
+    pub id: i64,
+    /// The release tag of the dataset, such as v20231101.
+    pub release_tag: String,
 
-    #[serde(skip_deserializing)]
+    /// The time when the release was imported for the first time.
     #[serde(with = "ts_seconds")]
-    #[oai(read_only)]
-    pub created_time: DateTime<Utc>,
+    pub imported_at: DateTime<Utc>,
 
-    #[validate(length(
-        min = 1,
-        max = 36,
-        message = "The owner length should be between 1 and 36"
-    ))]
-    pub owner: String,
+    /// The time when the last file of the release was imported.
+    #[serde(with = "ts_seconds")]
+    pub updated_at: DateTime<Utc>,
+}
 
-    #[validate(length(
-        min = 1,
-        max = 36,
-        message = "The version length should be between 1 and 36"
-    ))]
-    pub version: String,
+impl DatasetVersion {
+    /// Record a data file which has been imported, the release is created if it doesn't exist.
+    pub async fn record_file(
+        pool: &sqlx::PgPool,
+        release_tag: &str,
+        table_name: &str,
+        filepath: &str,
+        sha256: &str,
+    ) -> Result<DatasetVersion, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let record = sqlx::query_as::<_, DatasetVersion>(
+            "INSERT INTO biomedgps_dataset_version (release_tag) VALUES ($1)
+             ON CONFLICT (release_tag) DO UPDATE SET updated_at = now() RETURNING *",
+        )
+        .bind(release_tag)
+        .fetch_one(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO biomedgps_dataset_file (release_tag, table_name, filepath, sha256)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(release_tag)
+        .bind(table_name)
+        .bind(filepath)
+        .bind(sha256)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        AnyOk(record)
+    }
 
-    #[validate(length(
-        min = 1,
-        max = 36,
-        message = "The db_version length should be between 1 and 36"
-    ))]
-    pub db_version: String,
+    /// Get the active dataset version, i.e. the release which was imported last.
+    pub async fn get_active(pool: &sqlx::PgPool) -> Result<Option<DatasetVersion>, anyhow::Error> {
+        let record = sqlx::query_as::<_, DatasetVersion>(
+            "SELECT * FROM biomedgps_dataset_version ORDER BY imported_at DESC, id DESC LIMIT 1",
+        )
+        .fetch_optional(pool)
+        .await?;
 
-    #[validate(regex(
-        path = "SUBGRAPH_UUID_REGEX",
-        message = "The parent must match the ^[a-f0-9]{8}-[a-f0-9]{4}-[a-f0-9]{4}-[a-f0-9]{4}-[a-f0-9]{12}$ pattern."
-    ))]
-    pub parent: Option<String>, // parent subgraph id, it is same as id if it is a root subgraph (no parent), otherwise it is the parent subgraph id
-}
+        AnyOk(record)
+    }
 
-impl CheckData for Subgraph {
-    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
-        Self::check_csv_is_valid_default::<Subgraph>(filepath)
+    /// Get the release tag of the active dataset version. It is only used to stamp the responses, so the error is logged and ignored.
+    pub async fn get_active_tag(pool: &sqlx::PgPool) -> Option<String> {
+        match DatasetVersion::get_active(pool).await {
+            Ok(record) => record.map(|r| r.release_tag),
+            Err(e) => {
+                warn!("Failed to get the active dataset version: {}", e);
+                None
+            }
+        }
     }
 
-    fn unique_fields() -> Vec<String> {
-        vec![
-            "id".to_string(),
-            "owner".to_string(),
-            "version".to_string(),
-            "db_version".to_string(),
-            "parent".to_string(),
-        ]
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<DatasetVersion>, anyhow::Error> {
+        let records = sqlx::query_as::<_, DatasetVersion>(
+            "SELECT * FROM biomedgps_dataset_version ORDER BY imported_at DESC, id DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
     }
 
-    fn fields() -> Vec<String> {
-        vec![
-            "name".to_string(),
-            "description".to_string(),
-            "payload".to_string(),
-            "owner".to_string(),
-            "version".to_string(),
-            "db_version".to_string(),
-            "parent".to_string(),
-        ]
+    /// Fetch a dataset version by the release tag, return None if it doesn't exist.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        release_tag: &str,
+    ) -> Result<Option<DatasetVersion>, anyhow::Error> {
+        let record = sqlx::query_as::<_, DatasetVersion>(
+            "SELECT * FROM biomedgps_dataset_version WHERE release_tag = $1",
+        )
+        .bind(release_tag)
+        .fetch_optional(pool)
+        .await?;
+
+        AnyOk(record)
     }
 }
 
-impl Subgraph {
-    pub fn update_owner(&mut self, username: String) -> &Self {
-        self.owner = username;
-        return self;
-    }
+/// The number of new relations of a relation type in a dataset version diff.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow)]
+pub struct RelationTypeCount {
+    pub relation_type: String,
+    pub ncount: i64,
+}
 
-    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<Subgraph, anyhow::Error> {
-        let id = uuid::Uuid::new_v4().to_string();
-        let parent = if self.parent.is_none() {
-            id.clone()
-        } else {
-            self.parent.clone().unwrap()
-        };
+/// The relations which are new since a dataset version, i.e. the relations stamped with the releases which were imported after the from version and up to the to version. The removed relations are not reported, because an import never deletes the existing relations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct DatasetVersionDiff {
+    pub from_version: String,
+    pub to_version: String,
+    /// The releases after the from version and up to the to version, in the import order.
+    pub versions: Vec<String>,
+    /// The number of new relations of each relation type, the largest first.
+    pub relation_type_counts: Vec<RelationTypeCount>,
+    /// The new relations of the current page, they are ordered by id.
+    pub records: Vec<Relation>,
+    pub total: u64,
+    pub page: u64,
+    pub page_size: u64,
+    pub total_pages: u64,
+    pub has_next: bool,
+}
 
-        let sql_str = "INSERT INTO biomedgps_subgraph (id, name, description, payload, owner, version, db_version, parent) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *";
-        let subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
-            .bind(id)
-            .bind(&self.name)
-            .bind(&self.description)
-            .bind(&self.payload)
-            .bind(&self.owner)
-            .bind(&self.version)
-            .bind(&self.db_version)
-            .bind(parent)
-            .fetch_one(pool)
-            .await?;
+impl DatasetVersionDiff {
+    /// Compare two dataset versions, there are no new relations if the to version was imported before the from version. The query is used to filter the new relations, such as `{"field": "relation_type", "operator": "=", "value": "Treats"}`.
+    pub async fn get(
+        pool: &sqlx::PgPool,
+        from: &DatasetVersion,
+        to: &DatasetVersion,
+        query: &Option<ComposeQuery>,
+        page: Option<u64>,
+        page_size: Option<u64>,
+    ) -> Result<DatasetVersionDiff, anyhow::Error> {
+        let versions = with_query_timeout(
+            sqlx::query_as::<_, (String,)>(
+                "SELECT release_tag FROM biomedgps_dataset_version
+                 WHERE (imported_at, id) > ($1, $2) AND (imported_at, id) <= ($3, $4)
+                 ORDER BY imported_at, id",
+            )
+            .bind(from.imported_at)
+            .bind(from.id)
+            .bind(to.imported_at)
+            .bind(to.id)
+            .fetch_all(pool),
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.0)
+        .collect::<Vec<String>>();
+
+        let (query_str, params, order_by_str, pagination_str) = make_record_clauses(
+            pool,
+            TableName::Relation,
+            query,
+            page,
+            page_size,
+            Some("id ASC"),
+        )
+        .await?;
+        // The versions follow the params of the where clause.
+        let where_str = format!(
+            "dataset_version = ANY(${}) AND ({})",
+            params.len() + 1,
+            query_str
+        );
 
-        AnyOk(subgraph)
+        let relation_type_counts = with_query_timeout(
+            bind_values(
+                sqlx::query_as::<_, RelationTypeCount>(&format!(
+                    "SELECT relation_type, COUNT(*) AS ncount FROM biomedgps_relation WHERE {}
+                     GROUP BY relation_type ORDER BY ncount DESC, relation_type",
+                    where_str
+                )),
+                &params,
+            )
+            .bind(&versions)
+            .fetch_all(pool),
+        )
+        .await?;
+
+        let columns = <Relation as CheckData>::selectable_fields().join(",");
+        let records = with_query_timeout(
+            bind_values(
+                sqlx::query_as::<_, Relation>(&format!(
+                    "SELECT {} FROM biomedgps_relation WHERE {} {} {}",
+                    columns, where_str, order_by_str, pagination_str
+                )),
+                &params,
+            )
+            .bind(&versions)
+            .fetch_all(pool),
+        )
+        .await?;
+
+        let total = relation_type_counts
+            .iter()
+            .map(|count| count.ncount as u64)
+            .sum::<u64>();
+        let page = page.unwrap_or(1);
+        let page_size = page_size.unwrap_or(10);
+        let (total_pages, has_next) = paging_metadata(total, page, page_size);
+
+        AnyOk(DatasetVersionDiff {
+            from_version: from.release_tag.clone(),
+            to_version: to.release_tag.clone(),
+            versions,
+            relation_type_counts,
+            records,
+            total,
+            page,
+            page_size,
+            total_pages,
+            has_next,
+        })
     }
+}
 
-    pub async fn update(&self, pool: &sqlx::PgPool, id: &str) -> Result<Subgraph, anyhow::Error> {
-        let sql_str = "UPDATE biomedgps_subgraph SET name = $1, description = $2, payload = $3, WHERE id = $4 RETURNING *";
-        let subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
-            .bind(&self.name)
-            .bind(&self.description)
-            .bind(&self.payload)
-            .bind(id)
-            .fetch_one(pool)
-            .await?;
+/// A data file of a release and its checksum.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow)]
+pub struct DatasetFile {
+    pub id: i64,
+    pub release_tag: String,
+    pub table_name: String,
+    pub filepath: String,
+    pub sha256: String,
 
-        AnyOk(subgraph)
-    }
+    #[serde(with = "ts_seconds")]
+    pub imported_at: DateTime<Utc>,
+}
 
-    pub async fn delete(pool: &sqlx::PgPool, id: &str) -> Result<Subgraph, anyhow::Error> {
-        let sql_str = "DELETE FROM biomedgps_subgraph WHERE id = $1 RETURNING *";
-        let subgraph = sqlx::query_as::<_, Subgraph>(sql_str)
-            .bind(id)
-            .fetch_one(pool)
-            .await?;
+impl DatasetFile {
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        release_tag: &str,
+    ) -> Result<Vec<DatasetFile>, anyhow::Error> {
+        let records = sqlx::query_as::<_, DatasetFile>(
+            "SELECT * FROM biomedgps_dataset_file WHERE release_tag = $1 ORDER BY imported_at, id",
+        )
+        .bind(release_tag)
+        .fetch_all(pool)
+        .await?;
 
-        AnyOk(subgraph)
+        AnyOk(records)
     }
 }
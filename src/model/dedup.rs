@@ -0,0 +1,405 @@
+//! Dedup module is used to detect the duplicated entities and merge them. The entity table accumulates near-duplicates from different resources, such as the same CURIE in different cases (MESH:D000001 and mesh:D000001) or the same name with different IDs.
+//!
+//! The candidates are detected by the normalized IDs and the trigram similarity of the names (the pg_trgm extension is enabled by the searching migration). A merge removes the duplicated entity, rewrites its relations, embeddings and curations to the surviving entity, and records the removed entity in the biomedgps_entity_merge table.
+
+use crate::model::core::Entity;
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The ids are the same after they are trimmed and uppercased.
+pub const DUPLICATE_BY_ID: &str = "id";
+/// The names are similar, see [`DEFAULT_MIN_SIMILARITY`].
+pub const DUPLICATE_BY_NAME: &str = "name";
+/// The minimum trigram similarity of the names of two duplicated entities.
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.9;
+
+/// A pair of entities with the same label which may be duplicates.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::FromRow)]
+pub struct DuplicateCandidate {
+    pub label: String,
+    pub entity_id: String,
+    pub entity_name: String,
+    pub entity_resource: String,
+    pub duplicate_id: String,
+    pub duplicate_name: String,
+    pub duplicate_resource: String,
+    /// id or name, see [`DUPLICATE_BY_ID`] and [`DUPLICATE_BY_NAME`].
+    pub reason: String,
+    /// The trigram similarity of the names, it is 1.0 for the duplicates which are detected by the ids.
+    pub similarity: f64,
+}
+
+/// Find the candidate duplicates of the entities, only the entities with the same label are compared. The label is used to limit the comparison to one entity type, because comparing the names is expensive on a large entity table.
+pub async fn find_duplicates(
+    pool: &sqlx::PgPool,
+    label: Option<&str>,
+    min_similarity: f64,
+) -> Result<Vec<DuplicateCandidate>, anyhow::Error> {
+    if !(0.0..=1.0).contains(&min_similarity) {
+        return Err(anyhow::anyhow!(
+            "Invalid min similarity: {}, it should be between 0 and 1.",
+            min_similarity
+        ));
+    }
+
+    let columns = "a.label, a.id AS entity_id, a.name AS entity_name, a.resource AS entity_resource,
+                   b.id AS duplicate_id, b.name AS duplicate_name, b.resource AS duplicate_resource";
+
+    let mut tx = pool.begin().await?;
+    let id_query = format!(
+        "SELECT {columns}, $2 AS reason, 1.0::FLOAT8 AS similarity
+         FROM biomedgps_entity a
+         JOIN biomedgps_entity b ON a.label = b.label AND a.id < b.id AND UPPER(TRIM(a.id)) = UPPER(TRIM(b.id))
+         WHERE $1::TEXT IS NULL OR a.label = $1"
+    );
+    debug!("Finding the duplicates by ids: {}", id_query);
+    let mut candidates = sqlx::query_as::<_, DuplicateCandidate>(&id_query)
+        .bind(label)
+        .bind(DUPLICATE_BY_ID)
+        .fetch_all(&mut tx)
+        .await?;
+    info!("Found {} duplicates by ids.", candidates.len());
+
+    // The % operator uses the trigram index on the name column, its threshold is only set in the transaction.
+    sqlx::query("SELECT set_config('pg_trgm.similarity_threshold', $1, true)")
+        .bind(min_similarity.to_string())
+        .execute(&mut tx)
+        .await?;
+    let name_query = format!(
+        "SELECT {columns}, $2 AS reason, similarity(a.name, b.name)::FLOAT8 AS similarity
+         FROM biomedgps_entity a
+         JOIN biomedgps_entity b ON a.label = b.label AND a.id < b.id AND a.name % b.name
+         WHERE ($1::TEXT IS NULL OR a.label = $1) AND UPPER(TRIM(a.id)) <> UPPER(TRIM(b.id))
+         ORDER BY a.label, similarity DESC, a.id, b.id"
+    );
+    debug!("Finding the duplicates by names: {}", name_query);
+    let by_name = sqlx::query_as::<_, DuplicateCandidate>(&name_query)
+        .bind(label)
+        .bind(DUPLICATE_BY_NAME)
+        .fetch_all(&mut tx)
+        .await?;
+    info!("Found {} duplicates by names.", by_name.len());
+    tx.rollback().await?;
+
+    candidates.extend(by_name);
+    AnyOk(candidates)
+}
+
+/// Write the candidate duplicates into a tsv file, so they can be reviewed before merging.
+pub fn write_duplicates(
+    candidates: &[DuplicateCandidate],
+    filepath: &Path,
+) -> Result<(), anyhow::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_path(filepath)?;
+    for candidate in candidates {
+        writer.serialize(candidate)?;
+    }
+    writer.flush()?;
+
+    AnyOk(())
+}
+
+/// A merge of a duplicated entity into the surviving entity.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct EntityMerge {
+    pub id: i64,
+    /// The duplicated entity which is removed.
+    pub source_id: String,
+    pub source_type: String,
+    /// The surviving entity.
+    pub target_id: String,
+    pub target_type: String,
+    /// The removed entity in json format, it can be used to restore the entity.
+    pub source_entity: String,
+    /// The number of the relations which are rewritten to the surviving entity.
+    pub rewritten_relations: i64,
+    /// The number of the entity embeddings and entity2d records which are rewritten to the surviving entity.
+    pub rewritten_embeddings: i64,
+    /// The number of the curated knowledges which are rewritten to the surviving entity.
+    pub rewritten_curations: i64,
+    /// The number of the rows which are deleted because the surviving entity already has the same records.
+    pub deleted_rows: i64,
+    pub merged_by: String,
+
+    #[serde(with = "ts_seconds")]
+    pub merged_at: DateTime<Utc>,
+}
+
+/// The rows which reference an entity, they are rewritten to the surviving entity when the entity is merged.
+struct EntityReference {
+    table_name: &'static str,
+    id_column: &'static str,
+    type_column: &'static str,
+    /// The column which keeps the entity name, it is rewritten with the id.
+    name_column: Option<&'static str>,
+    /// The other columns of the unique key, a row is deleted instead of rewritten if the surviving entity already has the same row.
+    unique_columns: &'static [&'static str],
+}
+
+fn get_entity_references() -> Vec<EntityReference> {
+    let curation_columns: &'static [&'static str] = &[
+        "relation_type",
+        "source_id",
+        "source_name",
+        "source_type",
+        "target_id",
+        "target_name",
+        "target_type",
+        "curator",
+        "pmid",
+    ];
+
+    vec![
+        EntityReference {
+            table_name: "biomedgps_relation",
+            id_column: "source_id",
+            type_column: "source_type",
+            name_column: None,
+            unique_columns: &["relation_type", "target_type", "target_id"],
+        },
+        EntityReference {
+            table_name: "biomedgps_relation",
+            id_column: "target_id",
+            type_column: "target_type",
+            name_column: None,
+            unique_columns: &["relation_type", "source_type", "source_id"],
+        },
+        EntityReference {
+            table_name: "biomedgps_entity_embedding",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: Some("entity_name"),
+            unique_columns: &["model_name"],
+        },
+        EntityReference {
+            table_name: "biomedgps_entity2d",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: Some("entity_name"),
+            unique_columns: &[],
+        },
+        EntityReference {
+            table_name: "biomedgps_knowledge_curation",
+            id_column: "source_id",
+            type_column: "source_type",
+            name_column: Some("source_name"),
+            unique_columns: curation_columns,
+        },
+        EntityReference {
+            table_name: "biomedgps_knowledge_curation",
+            id_column: "target_id",
+            type_column: "target_type",
+            name_column: Some("target_name"),
+            unique_columns: curation_columns,
+        },
+        EntityReference {
+            table_name: "biomedgps_entity_attribute",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: None,
+            unique_columns: &["key", "value"],
+        },
+        EntityReference {
+            table_name: "biomedgps_entity_translation",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: None,
+            unique_columns: &["locale"],
+        },
+        EntityReference {
+            table_name: "biomedgps_entity_id_mapping",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: None,
+            unique_columns: &[],
+        },
+        EntityReference {
+            table_name: "biomedgps_entity_id_mapping",
+            id_column: "canonical_id",
+            type_column: "entity_type",
+            name_column: None,
+            unique_columns: &["entity_id"],
+        },
+        EntityReference {
+            table_name: "biomedgps_compound_structure",
+            id_column: "entity_id",
+            type_column: "entity_type",
+            name_column: None,
+            unique_columns: &[],
+        },
+    ]
+}
+
+impl EntityReference {
+    /// Delete the rows of the duplicated entity which the surviving entity already has. The parameters are the duplicated id, the label, the surviving id and the surviving name.
+    fn delete_query(&self) -> String {
+        let mut conditions = vec![
+            format!("o.{} = $3", self.id_column),
+            format!("o.{} = $2", self.type_column),
+        ];
+        if let Some(name_column) = self.name_column {
+            if self.unique_columns.contains(&name_column) {
+                conditions.push(format!("o.{} = $4", name_column));
+            }
+        }
+        for column in self.unique_columns.iter() {
+            if *column != self.id_column && Some(*column) != self.name_column {
+                conditions.push(format!("o.{} = t.{}", column, column));
+            }
+        }
+
+        format!(
+            "DELETE FROM {table} AS t WHERE t.{id} = $1 AND t.{label} = $2
+             AND EXISTS (SELECT 1 FROM {table} AS o WHERE {conditions})",
+            table = self.table_name,
+            id = self.id_column,
+            label = self.type_column,
+            conditions = conditions.join(" AND ")
+        )
+    }
+
+    /// Rewrite the rows of the duplicated entity to the surviving entity.
+    fn update_query(&self) -> String {
+        let mut assignments = vec![format!("{} = $3", self.id_column)];
+        if let Some(name_column) = self.name_column {
+            assignments.push(format!("{} = $4", name_column));
+        }
+
+        format!(
+            "UPDATE {table} SET {assignments} WHERE {id} = $1 AND {label} = $2",
+            table = self.table_name,
+            assignments = assignments.join(", "),
+            id = self.id_column,
+            label = self.type_column
+        )
+    }
+}
+
+impl EntityMerge {
+    /// Merge the duplicated entity (source) into the surviving entity (target). Only the entities with the same label can be merged, because the relation types contain the labels.
+    ///
+    /// All changes are made in one transaction. The statistics tables are not refreshed, run `biomedgps-cli statdb` after merging.
+    pub async fn merge(
+        pool: &sqlx::PgPool,
+        source_id: &str,
+        target_id: &str,
+        label: &str,
+        merged_by: &str,
+    ) -> Result<EntityMerge, anyhow::Error> {
+        if source_id == target_id {
+            return Err(anyhow::anyhow!(
+                "Cannot merge the entity {} into itself.",
+                source_id
+            ));
+        }
+
+        let mut tx = pool.begin().await?;
+        let get_entity = |id: &str| {
+            sqlx::query_as::<_, Entity>(
+                "SELECT * FROM biomedgps_entity WHERE id = $1 AND label = $2 FOR UPDATE",
+            )
+            .bind(id.to_string())
+            .bind(label.to_string())
+        };
+
+        let source = match get_entity(source_id).fetch_optional(&mut tx).await? {
+            Some(entity) => entity,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Cannot find the entity {} ({}).",
+                    source_id,
+                    label
+                ))
+            }
+        };
+        let target = match get_entity(target_id).fetch_optional(&mut tx).await? {
+            Some(entity) => entity,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Cannot find the entity {} ({}).",
+                    target_id,
+                    label
+                ))
+            }
+        };
+
+        let mut rewritten = [0_i64; 3];
+        let mut deleted_rows = 0;
+        for reference in get_entity_references() {
+            for (query_str, is_delete) in [
+                (reference.delete_query(), true),
+                (reference.update_query(), false),
+            ] {
+                debug!("Merging the entity: {}", query_str);
+                let rows = sqlx::query(&query_str)
+                    .bind(&source.id)
+                    .bind(&source.label)
+                    .bind(&target.id)
+                    .bind(&target.name)
+                    .execute(&mut tx)
+                    .await?
+                    .rows_affected() as i64;
+
+                if is_delete {
+                    deleted_rows += rows;
+                } else {
+                    let index = match reference.table_name {
+                        "biomedgps_relation" => 0,
+                        "biomedgps_knowledge_curation" => 2,
+                        _ => 1,
+                    };
+                    rewritten[index] += rows;
+                }
+            }
+        }
+
+        sqlx::query("DELETE FROM biomedgps_entity WHERE idx = $1")
+            .bind(source.idx)
+            .execute(&mut tx)
+            .await?;
+
+        let record = sqlx::query_as::<_, EntityMerge>(
+            "INSERT INTO biomedgps_entity_merge
+             (source_id, source_type, target_id, target_type, source_entity, rewritten_relations,
+              rewritten_embeddings, rewritten_curations, deleted_rows, merged_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *",
+        )
+        .bind(&source.id)
+        .bind(&source.label)
+        .bind(&target.id)
+        .bind(&target.label)
+        .bind(serde_json::to_string(&source)?)
+        .bind(rewritten[0])
+        .bind(rewritten[1])
+        .bind(rewritten[2])
+        .bind(deleted_rows)
+        .bind(merged_by)
+        .fetch_one(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        info!(
+            "Merged the entity {} into {} ({}).",
+            source.id, target.id, label
+        );
+
+        AnyOk(record)
+    }
+
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<EntityMerge>, anyhow::Error> {
+        let records = sqlx::query_as::<_, EntityMerge>(
+            "SELECT * FROM biomedgps_entity_merge ORDER BY merged_at DESC, id DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+}
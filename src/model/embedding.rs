@@ -0,0 +1,760 @@
+//! Embedding module is used to compute the similarity between the entities from their embeddings in the `biomedgps_entity_embedding` table, such as the pairwise similarity, the analogy and the centroid similarity. It also ranks the candidate entities for an anchor entity by the score function of the embedding model, such as the compounds for a disease.
+
+use crate::algorithm::kge::ScoreFunction;
+use crate::model::core::EmbeddingModel;
+use crate::model::graph::{Graph, COMPOSED_ENTITY_DELIMITER};
+use crate::model::path::fetch_evidence_graph;
+use crate::model::util::with_query_timeout;
+use crate::pgvector::Vector;
+use log::debug;
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// The operations on the embeddings.
+///
+/// * `Similarity` - The cosine similarity between two entities.
+/// * `Analogy` - A - B + C, and then find the nearest entities of the result vector. Such as `Gene::A - Disease::B + Disease::C` means which genes play the role for the disease C that the gene A plays for the disease B.
+/// * `Centroid` - The mean vector of a set of entities, and then find the nearest entities of the mean vector.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingOperation {
+    Similarity,
+    Analogy,
+    Centroid,
+}
+
+impl EmbeddingOperation {
+    /// Check whether the number of the node ids matches the operation.
+    pub fn check_node_ids(&self, node_ids: &[String]) -> Result<(), String> {
+        let valid = match self {
+            EmbeddingOperation::Similarity => node_ids.len() == 2,
+            EmbeddingOperation::Analogy => node_ids.len() == 3,
+            EmbeddingOperation::Centroid => !node_ids.is_empty(),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "The {:?} operation needs {} node ids, but {} node ids are given.",
+                self,
+                match self {
+                    EmbeddingOperation::Similarity => "2",
+                    EmbeddingOperation::Analogy => "3",
+                    EmbeddingOperation::Centroid => "at least 1",
+                },
+                node_ids.len()
+            ))
+        }
+    }
+}
+
+/// The max number of the ranked entities which are explained by the paths.
+pub const MAX_EXPLAINED_ENTITIES: usize = 20;
+/// The max number of the anchors of a batch similarity computation.
+pub const MAX_BATCH_ANCHORS: usize = 10;
+/// The max number of the nodes of a batch similarity computation.
+pub const MAX_BATCH_NODES: usize = 500;
+
+/// An entity which is similar to the queried vector, the score is the cosine similarity.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct SimilarEntity {
+    pub node_id: String,
+    pub entity_name: String,
+    pub score: f64,
+}
+
+/// The result of an embedding operation.
+///
+/// * `score` - The cosine similarity between two entities for the similarity operation, the mean cosine similarity between the entities and their centroid for the centroid operation, None for the analogy operation.
+/// * `entities` - The nearest entities of the result vector, it is empty for the similarity operation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EmbeddingOpsResult {
+    pub operation: EmbeddingOperation,
+    pub model_name: String,
+    pub node_ids: Vec<String>,
+    #[oai(skip_serializing_if_is_none)]
+    pub score: Option<f64>,
+    pub entities: Vec<SimilarEntity>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct NodeEmbedding {
+    node_id: String,
+    embedding: Vector,
+}
+
+/// Compute the cosine similarity between two vectors, it returns None if the lengths of the vectors are different or one of the vectors is a zero vector.
+///
+/// ```
+/// use biomedgps::model::embedding::cosine_similarity;
+///
+/// assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), Some(1.0));
+/// assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), Some(0.0));
+/// assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]), None);
+/// ```
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+
+    let mut dot = 0.0;
+    let mut norm_a = 0.0;
+    let mut norm_b = 0.0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        let (x, y) = (*x as f64, *y as f64);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        None
+    } else {
+        Some(dot / (norm_a.sqrt() * norm_b.sqrt()))
+    }
+}
+
+/// Compute the mean vector of the vectors, all vectors must have the same length.
+pub fn centroid(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dimension = vectors.first()?.len();
+    if vectors.iter().any(|v| v.len() != dimension) {
+        return None;
+    }
+
+    let mut mean = vec![0.0; dimension];
+    for vector in vectors {
+        for (i, v) in vector.iter().enumerate() {
+            mean[i] += v / vectors.len() as f32;
+        }
+    }
+
+    Some(mean)
+}
+
+/// Compute a - b + c, all vectors must have the same length.
+pub fn analogy(a: &[f32], b: &[f32], c: &[f32]) -> Option<Vec<f32>> {
+    if a.len() != b.len() || a.len() != c.len() {
+        return None;
+    }
+
+    Some(
+        a.iter()
+            .zip(b.iter())
+            .zip(c.iter())
+            .map(|((a, b), c)| a - b + c)
+            .collect(),
+    )
+}
+
+/// Fetch the embeddings of the nodes from an embedding model, the node id is composed of the entity type and the entity id, such as `Gene::ENTREZ:123`.
+///
+/// It returns an error if any node doesn't have an embedding.
+pub async fn fetch_embeddings(
+    pool: &sqlx::PgPool,
+    node_ids: &[String],
+    model_name: &str,
+) -> Result<HashMap<String, Vec<f32>>, anyhow::Error> {
+    let sql_str = format!(
+        "SELECT COALESCE(entity_type, '') || '{}' || COALESCE(entity_id, '') AS node_id, embedding
+         FROM biomedgps_entity_embedding
+         WHERE COALESCE(entity_type, '') || '{}' || COALESCE(entity_id, '') = ANY($1) AND model_name = $2",
+        COMPOSED_ENTITY_DELIMITER, COMPOSED_ENTITY_DELIMITER
+    );
+
+    let records = with_query_timeout(
+        sqlx::query_as::<_, NodeEmbedding>(sql_str.as_str())
+            .bind(node_ids)
+            .bind(model_name)
+            .fetch_all(pool),
+    )
+    .await?;
+
+    let embeddings = records
+        .into_iter()
+        .map(|record| (record.node_id, record.embedding.to_vec()))
+        .collect::<HashMap<String, Vec<f32>>>();
+
+    let missed_node_ids = node_ids
+        .iter()
+        .filter(|node_id| !embeddings.contains_key(*node_id))
+        .map(|node_id| node_id.as_str())
+        .collect::<Vec<&str>>();
+    if !missed_node_ids.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Cannot find the embeddings of the nodes: {}",
+            missed_node_ids.join(", ")
+        ));
+    }
+
+    Ok(embeddings)
+}
+
+/// Find the nearest entities of a vector in an embedding model by the cosine distance, the excluded nodes are not returned.
+pub async fn fetch_nearest_entities(
+    pool: &sqlx::PgPool,
+    vector: Vec<f32>,
+    topk: u64,
+    entity_type: Option<&str>,
+    excluded_node_ids: &[String],
+    model_name: &str,
+) -> Result<Vec<SimilarEntity>, anyhow::Error> {
+    let sql_str = format!(
+        "SELECT node_id, entity_name, score FROM (
+            SELECT COALESCE(entity_type, '') || '{}' || COALESCE(entity_id, '') AS node_id,
+                   entity_name,
+                   1 - (embedding <=> $1) AS score
+            FROM biomedgps_entity_embedding
+            WHERE ($2::TEXT IS NULL OR entity_type = $2) AND model_name = $4
+         ) AS t
+         WHERE NOT node_id = ANY($3) AND score IS NOT NULL
+         ORDER BY score DESC
+         LIMIT {}",
+        COMPOSED_ENTITY_DELIMITER, topk
+    );
+
+    debug!(
+        "sql_str: {} with arguments $2: `{:?}`, $3: `{:?}`, $4: `{}`",
+        sql_str, entity_type, excluded_node_ids, model_name
+    );
+
+    let entities = with_query_timeout(
+        sqlx::query_as::<_, SimilarEntity>(sql_str.as_str())
+            .bind(Vector::from(vector))
+            .bind(entity_type)
+            .bind(excluded_node_ids)
+            .bind(model_name)
+            .fetch_all(pool),
+    )
+    .await?;
+
+    Ok(entities)
+}
+
+impl EmbeddingOpsResult {
+    /// Run an embedding operation on the nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The database connection pool.
+    /// * `operation` - The embedding operation.
+    /// * `node_ids` - The node ids, 2 for the similarity operation, 3 (A, B, C) for the analogy operation and at least 1 for the centroid operation.
+    /// * `topk` - The number of the nearest entities to return.
+    /// * `entity_type` - Only return the nearest entities with the entity type.
+    /// * `model_name` - The embedding model, the latest registered model is used if it is None.
+    pub async fn run(
+        pool: &sqlx::PgPool,
+        operation: EmbeddingOperation,
+        node_ids: Vec<String>,
+        topk: u64,
+        entity_type: Option<&str>,
+        model_name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if let Err(err) = operation.check_node_ids(&node_ids) {
+            return Err(anyhow::anyhow!(err));
+        }
+
+        let model_name = EmbeddingModel::resolve(pool, model_name).await?.model_name;
+        let embeddings = fetch_embeddings(pool, &node_ids, &model_name).await?;
+        let vectors = node_ids
+            .iter()
+            .map(|node_id| embeddings[node_id].clone())
+            .collect::<Vec<Vec<f32>>>();
+        let dimension_error = || anyhow::anyhow!("The dimensions of the embeddings are different.");
+
+        let (score, entities) = match operation {
+            EmbeddingOperation::Similarity => {
+                let score = cosine_similarity(&vectors[0], &vectors[1]).ok_or_else(|| {
+                    anyhow::anyhow!("Cannot compute the cosine similarity of the embeddings.")
+                })?;
+                (Some(score), vec![])
+            }
+            EmbeddingOperation::Analogy => {
+                let vector =
+                    analogy(&vectors[0], &vectors[1], &vectors[2]).ok_or_else(dimension_error)?;
+                let entities =
+                    fetch_nearest_entities(pool, vector, topk, entity_type, &node_ids, &model_name)
+                        .await?;
+                (None, entities)
+            }
+            EmbeddingOperation::Centroid => {
+                let vector = centroid(&vectors).ok_or_else(dimension_error)?;
+                let scores = vectors
+                    .iter()
+                    .filter_map(|v| cosine_similarity(v, &vector))
+                    .collect::<Vec<f64>>();
+                let score = if scores.is_empty() {
+                    None
+                } else {
+                    Some(scores.iter().sum::<f64>() / scores.len() as f64)
+                };
+                let entities =
+                    fetch_nearest_entities(pool, vector, topk, entity_type, &node_ids, &model_name)
+                        .await?;
+                (score, entities)
+            }
+        };
+
+        Ok(EmbeddingOpsResult {
+            operation,
+            model_name,
+            node_ids,
+            score,
+            entities,
+        })
+    }
+}
+
+/// The cosine similarity between a node and an anchor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct NodeSimilarity {
+    pub anchor_id: String,
+    pub node_id: String,
+    pub score: f64,
+}
+
+/// The similarities between a set of nodes and the anchors.
+///
+/// * `similarities` - The similarities are grouped by the anchors in the order of the anchor ids, and sorted by the score in each group.
+/// * `missing_node_ids` - The nodes which don't have the embeddings in the model (or have the zero vectors), they are skipped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct BatchSimilarity {
+    pub model_name: String,
+    pub anchor_ids: Vec<String>,
+    pub missing_node_ids: Vec<String>,
+    pub similarities: Vec<NodeSimilarity>,
+}
+
+impl BatchSimilarity {
+    /// Compute the cosine similarities between every node and every anchor in one query, such as all nodes of a subgraph against a query node. The duplicated node ids are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The database connection pool.
+    /// * `anchor_ids` - The node ids of the anchors, at most 10 (see [`MAX_BATCH_ANCHORS`]), all of them must have the embeddings.
+    /// * `node_ids` - The node ids to compare with the anchors, at most 500 (see [`MAX_BATCH_NODES`]).
+    /// * `model_name` - The embedding model, the latest registered model is used if it is None.
+    pub async fn run(
+        pool: &sqlx::PgPool,
+        anchor_ids: Vec<String>,
+        node_ids: Vec<String>,
+        model_name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        let dedup = |ids: Vec<String>| -> Vec<String> {
+            let mut seen = HashSet::new();
+            ids.into_iter()
+                .filter(|id| seen.insert(id.clone()))
+                .collect()
+        };
+        let anchor_ids = dedup(anchor_ids);
+        let node_ids = dedup(node_ids);
+
+        if anchor_ids.is_empty() || anchor_ids.len() > MAX_BATCH_ANCHORS {
+            return Err(anyhow::anyhow!(
+                "The number of the anchor ids should be between 1 and {}, but got {}.",
+                MAX_BATCH_ANCHORS,
+                anchor_ids.len()
+            ));
+        }
+
+        if node_ids.is_empty() || node_ids.len() > MAX_BATCH_NODES {
+            return Err(anyhow::anyhow!(
+                "The number of the node ids should be between 1 and {}, but got {}.",
+                MAX_BATCH_NODES,
+                node_ids.len()
+            ));
+        }
+
+        let model_name = EmbeddingModel::resolve(pool, model_name).await?.model_name;
+        let mut anchors = fetch_embeddings(pool, &anchor_ids, &model_name).await?;
+        let anchor_vectors = anchor_ids
+            .iter()
+            .map(|anchor_id| Vector::from(anchors.remove(anchor_id).unwrap_or_default()))
+            .collect::<Vec<Vector>>();
+
+        // The anchors are joined as an array, so all similarities are computed in one pass over the embeddings of the nodes.
+        let sql_str = format!(
+            "SELECT anchor_id, node_id, score FROM (
+                SELECT a.anchor_id, e.node_id, a.idx, 1 - (e.embedding <=> a.embedding) AS score
+                FROM (
+                    SELECT COALESCE(entity_type, '') || '{}' || COALESCE(entity_id, '') AS node_id, embedding
+                    FROM biomedgps_entity_embedding
+                    WHERE COALESCE(entity_type, '') || '{}' || COALESCE(entity_id, '') = ANY($1) AND model_name = $2
+                ) AS e
+                CROSS JOIN UNNEST($3::TEXT[], $4::VECTOR[]) WITH ORDINALITY AS a(anchor_id, embedding, idx)
+             ) AS t
+             WHERE score IS NOT NULL AND score <> 'NaN'::FLOAT8
+             ORDER BY idx ASC, score DESC, node_id ASC",
+            COMPOSED_ENTITY_DELIMITER, COMPOSED_ENTITY_DELIMITER
+        );
+
+        debug!(
+            "sql_str: {} with arguments $1: `{:?}`, $2: `{}`, $3: `{:?}`",
+            sql_str, node_ids, model_name, anchor_ids
+        );
+
+        let similarities = with_query_timeout(
+            sqlx::query_as::<_, NodeSimilarity>(sql_str.as_str())
+                .bind(&node_ids)
+                .bind(&model_name)
+                .bind(&anchor_ids)
+                .bind(&anchor_vectors)
+                .fetch_all(pool),
+        )
+        .await?;
+
+        let found = similarities
+            .iter()
+            .map(|similarity| similarity.node_id.as_str())
+            .collect::<HashSet<&str>>();
+        let missing_node_ids = node_ids
+            .into_iter()
+            .filter(|node_id| !found.contains(node_id.as_str()))
+            .collect::<Vec<String>>();
+
+        Ok(BatchSimilarity {
+            model_name,
+            anchor_ids,
+            missing_node_ids,
+            similarities,
+        })
+    }
+}
+
+/// Find the relation types of the model which match the queried relation type between the anchor type and the target type, and whether the anchor is the head of each relation type. The relation types are like `DRUGBANK::treats::Compound:Disease`, the queried relation type can be a full relation type or the name in the middle (such as `treats`, case-insensitive).
+///
+/// ```
+/// use biomedgps::model::embedding::match_relation_types;
+///
+/// let relation_types = vec![
+///     "DRUGBANK::treats::Compound:Disease".to_string(),
+///     "GNBR::T::Compound:Disease".to_string(),
+///     "Hetionet::CtD::Compound:Disease".to_string(),
+/// ];
+/// assert_eq!(
+///     match_relation_types(&relation_types, "Treats", "Disease", "Compound"),
+///     vec![("DRUGBANK::treats::Compound:Disease".to_string(), false)]
+/// );
+/// assert_eq!(
+///     match_relation_types(&relation_types, "GNBR::T::Compound:Disease", "Compound", "Disease"),
+///     vec![("GNBR::T::Compound:Disease".to_string(), true)]
+/// );
+/// assert!(match_relation_types(&relation_types, "treats", "Gene", "Compound").is_empty());
+/// ```
+pub fn match_relation_types(
+    relation_types: &[String],
+    relation_type: &str,
+    anchor_type: &str,
+    target_type: &str,
+) -> Vec<(String, bool)> {
+    let mut matched = vec![];
+    for candidate in relation_types {
+        let parts = candidate
+            .split(COMPOSED_ENTITY_DELIMITER)
+            .collect::<Vec<&str>>();
+        let (name, types) = match parts.as_slice() {
+            [_, name, types] => (*name, *types),
+            _ => continue,
+        };
+
+        if candidate != relation_type && !name.eq_ignore_ascii_case(relation_type) {
+            continue;
+        }
+
+        match types.split_once(':') {
+            // The head and the tail have the same type, such as Gene:Gene, the anchor is ranked as the head.
+            Some((head_type, tail_type))
+                if head_type == anchor_type && tail_type == target_type =>
+            {
+                matched.push((candidate.clone(), true))
+            }
+            Some((head_type, tail_type))
+                if head_type == target_type && tail_type == anchor_type =>
+            {
+                matched.push((candidate.clone(), false))
+            }
+            _ => {}
+        }
+    }
+
+    matched
+}
+
+/// A candidate entity which is ranked by the score function of the embedding model.
+///
+/// * `relation_type` - The relation type which gives the best score, when the queried relation type matches several relation types (such as the `treats` of several databases).
+/// * `known` - Whether the entity is already connected with the anchor by the matched relation types in the knowledge graph.
+/// * `linked_relation_types` - All relation types which connect the entity with the anchor in the knowledge graph, they are the known evidences of the entity.
+/// * `explanation` - The top multi-hop paths between the anchor and the entity as a graph, only in the explanation mode (see [`EntityRanking::explain`]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct RankedEntity {
+    pub rank: usize,
+    pub node_id: String,
+    pub entity_id: String,
+    pub entity_type: String,
+    pub entity_name: String,
+    pub score: f64,
+    pub relation_type: String,
+    pub known: bool,
+    pub linked_relation_types: Vec<String>,
+    #[oai(skip_serializing_if_is_none)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Graph>,
+}
+
+/// The candidate entities of the target type which are ranked for the anchor entity, such as the compounds for a disease.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EntityRanking {
+    pub model_name: String,
+    pub algorithm: String,
+    pub anchor_id: String,
+    pub target_type: String,
+    /// The relation types of the model which match the queried relation type.
+    pub relation_types: Vec<String>,
+    /// The number of the candidate entities which have the embeddings.
+    pub num_candidates: usize,
+    /// The number of the candidate entities which are already connected with the anchor by the matched relation types.
+    pub num_known: usize,
+    pub entities: Vec<RankedEntity>,
+}
+
+impl EntityRanking {
+    /// Rank all entities of the target type for the anchor by the score function of the embedding model (see [`ScoreFunction`](../../algorithm/kge/enum.ScoreFunction.html)), the entities which are already connected with the anchor by the matched relation types are excluded if exclude_known is true.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The database connection pool.
+    /// * `anchor_id` - The node id of the anchor entity, such as `Disease::MONDO:0005147`.
+    /// * `target_type` - The entity type of the candidates, such as `Compound`.
+    /// * `relation_type` - A full relation type or the name in the middle of the relation types, such as `treats` (see [`match_relation_types`]).
+    /// * `topk` - The number of the top ranked entities to return.
+    /// * `exclude_known` - Exclude the entities which are already connected with the anchor by the matched relation types.
+    /// * `model_name` - The embedding model, the latest registered model is used if it is None.
+    pub async fn run(
+        pool: &sqlx::PgPool,
+        anchor_id: &str,
+        target_type: &str,
+        relation_type: &str,
+        topk: usize,
+        exclude_known: bool,
+        model_name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        let model = EmbeddingModel::resolve(pool, model_name).await?;
+        let score_function = model.algorithm.parse::<ScoreFunction>().map_err(|e| {
+            anyhow::anyhow!(
+                "Cannot rank the entities by the embedding model {}: {}",
+                model.model_name,
+                e
+            )
+        })?;
+
+        let anchor_type = anchor_id
+            .split(COMPOSED_ENTITY_DELIMITER)
+            .next()
+            .unwrap_or_default();
+        let anchor_entity_id = anchor_id
+            .split_once(COMPOSED_ENTITY_DELIMITER)
+            .map(|(_, entity_id)| entity_id)
+            .unwrap_or_default();
+        let anchor = fetch_embeddings(pool, &[anchor_id.to_string()], &model.model_name)
+            .await?
+            .remove(anchor_id)
+            .unwrap_or_default();
+
+        let relations = with_query_timeout(
+            sqlx::query_as::<_, (String, Vector)>(
+                "SELECT relation_type, embedding FROM biomedgps_relation_embedding WHERE model_name = $1",
+            )
+            .bind(&model.model_name)
+            .fetch_all(pool),
+        )
+        .await?
+        .into_iter()
+        .map(|(relation_type, embedding)| (relation_type, embedding.to_vec()))
+        .collect::<HashMap<String, Vec<f32>>>();
+        let relation_types = relations.keys().cloned().collect::<Vec<String>>();
+        let matched =
+            match_relation_types(&relation_types, relation_type, anchor_type, target_type);
+        if matched.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The embedding model {} doesn't have the relation type {} between {} and {}.",
+                model.model_name,
+                relation_type,
+                anchor_type,
+                target_type
+            ));
+        }
+
+        for (relation_type, _) in matched.iter() {
+            score_function
+                .check_dimensions(anchor.len(), relations[relation_type].len())
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        let candidates = with_query_timeout(
+            sqlx::query_as::<_, (String, String, Vector)>(
+                "SELECT entity_id, entity_name, embedding FROM biomedgps_entity_embedding
+                 WHERE model_name = $1 AND entity_type = $2",
+            )
+            .bind(&model.model_name)
+            .bind(target_type)
+            .fetch_all(pool),
+        )
+        .await?;
+
+        // The relation types which connect the candidates with the anchor in both directions.
+        let mut linked: HashMap<String, Vec<String>> = HashMap::new();
+        let linked_rows = with_query_timeout(
+            sqlx::query_as::<_, (String, String)>(
+                "SELECT CASE WHEN source_type = $1 AND source_id = $2 THEN target_id ELSE source_id END, relation_type
+                 FROM biomedgps_relation
+                 WHERE (source_type = $1 AND source_id = $2 AND target_type = $3)
+                    OR (target_type = $1 AND target_id = $2 AND source_type = $3)",
+            )
+            .bind(anchor_type)
+            .bind(anchor_entity_id)
+            .bind(target_type)
+            .fetch_all(pool),
+        )
+        .await?;
+        for (entity_id, relation_type) in linked_rows {
+            linked.entry(entity_id).or_default().push(relation_type);
+        }
+
+        let num_candidates = candidates.len();
+        let mut num_known = 0;
+        let mut ranked: Vec<RankedEntity> = vec![];
+        for (entity_id, entity_name, embedding) in candidates {
+            let embedding = embedding.to_vec();
+            let node_id = format!("{}{}{}", target_type, COMPOSED_ENTITY_DELIMITER, entity_id);
+            if node_id == anchor_id {
+                continue;
+            }
+
+            let (score, best_relation_type) = matched
+                .iter()
+                .map(|(relation_type, anchor_is_head)| {
+                    let relation = &relations[relation_type];
+                    let score = if *anchor_is_head {
+                        score_function.score(&anchor, relation, &embedding)
+                    } else {
+                        score_function.score(&embedding, relation, &anchor)
+                    };
+                    (score, relation_type)
+                })
+                .fold((f32::NEG_INFINITY, &matched[0].0), |best, current| {
+                    if current.0 > best.0 {
+                        current
+                    } else {
+                        best
+                    }
+                });
+
+            let mut linked_relation_types = linked.remove(&entity_id).unwrap_or_default();
+            linked_relation_types.sort();
+            linked_relation_types.dedup();
+            let known = linked_relation_types.iter().any(|linked| {
+                matched
+                    .iter()
+                    .any(|(relation_type, _)| relation_type == linked)
+            });
+            if known {
+                num_known += 1;
+                if exclude_known {
+                    continue;
+                }
+            }
+
+            ranked.push(RankedEntity {
+                rank: 0,
+                node_id,
+                entity_id,
+                entity_type: target_type.to_string(),
+                entity_name,
+                score: score as f64,
+                relation_type: best_relation_type.clone(),
+                known,
+                linked_relation_types,
+                explanation: None,
+            });
+        }
+
+        ranked.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        ranked.truncate(topk);
+        for (i, entity) in ranked.iter_mut().enumerate() {
+            entity.rank = i + 1;
+        }
+
+        debug!(
+            "Ranked {} {} entities for {} by {}, {} of them are known.",
+            num_candidates, target_type, anchor_id, model.model_name, num_known
+        );
+
+        Ok(EntityRanking {
+            model_name: model.model_name,
+            algorithm: score_function.to_string(),
+            anchor_id: anchor_id.to_string(),
+            target_type: target_type.to_string(),
+            relation_types: matched
+                .into_iter()
+                .map(|(relation_type, _)| relation_type)
+                .collect(),
+            num_candidates,
+            num_known,
+            entities: ranked,
+        })
+    }
+
+    /// Explain the top ranked entities by the multi-hop paths between the anchor and each entity (see [`find_evidence_paths`](../path/fn.find_evidence_paths.html)), at most `MAX_EXPLAINED_ENTITIES` entities are explained because each one needs several queries.
+    pub async fn explain(
+        &mut self,
+        pool: &sqlx::PgPool,
+        max_hops: usize,
+        max_paths: usize,
+    ) -> Result<&Self, anyhow::Error> {
+        for entity in self.entities.iter_mut().take(MAX_EXPLAINED_ENTITIES) {
+            let graph =
+                fetch_evidence_graph(pool, &self.anchor_id, &entity.node_id, max_hops, max_paths)
+                    .await?;
+            entity.explanation = Some(graph);
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_operations() {
+        assert_eq!(
+            centroid(&[vec![1.0, 2.0], vec![3.0, 4.0]]),
+            Some(vec![2.0, 3.0])
+        );
+        assert_eq!(centroid(&[vec![1.0, 2.0], vec![3.0]]), None);
+        assert_eq!(centroid(&[]), None);
+
+        assert_eq!(
+            analogy(&[1.0, 1.0], &[1.0, 0.0], &[0.0, 1.0]),
+            Some(vec![0.0, 2.0])
+        );
+        assert_eq!(analogy(&[1.0], &[1.0, 0.0], &[0.0, 1.0]), None);
+
+        let op = EmbeddingOperation::Analogy;
+        assert!(op
+            .check_node_ids(&["a".to_string(), "b".to_string()])
+            .is_err());
+        assert!(EmbeddingOperation::Centroid
+            .check_node_ids(&["a".to_string()])
+            .is_ok());
+    }
+}
@@ -0,0 +1,544 @@
+//! Enrichment module finds the gene sets (such as the pathways and the diseases) which are over-represented in a gene list, by the hypergeometric test of the overlap and the Benjamini-Hochberg correction of the p-values.
+//!
+//! The gene sets are stored in the `biomedgps_gene_set` table by `biomedgps-cli import-gene-sets`, they are read from a [GMT](https://software.broadinstitute.org/cancer/software/gsea/wiki/index.php/Data_formats#GMT:_Gene_Matrix_Transposed_file_format_.28.2A.gmt.29) file (such as the MSigDB collections) or derived from the relations of the knowledge graph, e.g. the genes which are associated with each disease. The genes of a gene set should be the same kind of ids as the submitted gene lists, such as `ENTREZ:7157`.
+
+use crate::model::core::ValidationError;
+use crate::model::util::with_query_timeout;
+use anyhow::Ok as AnyOk;
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use log::{debug, info};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::Path;
+
+/// The max number of the genes in a submitted gene list.
+pub const MAX_GENE_LIST_SIZE: usize = 5000;
+/// The gene sets which are smaller than it are skipped by default, the small sets are easy to be over-represented by chance.
+pub const DEFAULT_MIN_SET_SIZE: usize = 5;
+/// The gene sets which are larger than it are skipped by default, the large sets are too broad to be informative.
+pub const DEFAULT_MAX_SET_SIZE: usize = 500;
+/// The default cutoff of the adjusted p-values.
+pub const DEFAULT_ADJUSTED_P_VALUE_CUTOFF: f64 = 0.05;
+
+/// A gene set, such as a pathway in a GMT file or the genes of a disease in the knowledge graph.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct GeneSet {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    pub name: String,
+    pub source: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    pub genes: Vec<String>,
+
+    #[serde(skip_deserializing, with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// The gene sets of a source and how many genes they have.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct GeneSetSource {
+    pub source: String,
+    pub num_of_sets: i64,
+    pub num_of_genes: i64,
+}
+
+/// A gene set which overlaps with the gene list, the p-value is the probability of the overlap (or a larger one) by chance, and the adjusted p-value is corrected by the Benjamini-Hochberg method over all the tested gene sets.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EnrichmentRecord {
+    pub gene_set_id: i64,
+    pub name: String,
+    pub source: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    pub set_size: usize,
+    pub overlap: usize,
+    pub overlap_genes: Vec<String>,
+    /// The overlap divided by the expected overlap by chance.
+    pub fold_enrichment: f64,
+    pub p_value: f64,
+    pub adjusted_p_value: f64,
+}
+
+/// The enrichment of a gene list, the records are sorted by the p-values.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct EnrichmentResult {
+    /// The genes of the list which are in the background, only they are tested.
+    pub num_of_genes: usize,
+    /// The genes of the list which are not in any gene set of the background.
+    pub unknown_genes: Vec<String>,
+    /// The number of the genes in the background, i.e. all genes of the tested gene sets unless it is specified.
+    pub background_size: usize,
+    /// The number of the tested gene sets, the p-values are corrected over them.
+    pub num_of_tested_sets: usize,
+    pub records: Vec<EnrichmentRecord>,
+}
+
+/// The options of an enrichment analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnrichmentOptions {
+    /// Only test the gene sets of the sources, all sources are tested if it is empty.
+    pub sources: Vec<String>,
+    pub min_set_size: usize,
+    pub max_set_size: usize,
+    /// The number of the genes in the background, such as 20000 for all protein-coding genes. It must not be smaller than the number of the genes in the tested gene sets.
+    pub background_size: Option<usize>,
+    pub adjusted_p_value_cutoff: f64,
+}
+
+impl Default for EnrichmentOptions {
+    fn default() -> Self {
+        EnrichmentOptions {
+            sources: vec![],
+            min_set_size: DEFAULT_MIN_SET_SIZE,
+            max_set_size: DEFAULT_MAX_SET_SIZE,
+            background_size: None,
+            adjusted_p_value_cutoff: DEFAULT_ADJUSTED_P_VALUE_CUTOFF,
+        }
+    }
+}
+
+/// Parse the gene sets in the GMT format, each line is a gene set with the name, the description and the genes separated by tabs. The genes without a prefix (such as `7157`) get the prefix (such as `ENTREZ:7157`) if it is specified.
+///
+/// ```
+/// use biomedgps::model::enrichment::parse_gmt;
+///
+/// let gmt = "HALLMARK_APOPTOSIS\thttp://www.gsea-msigdb.org\t7157\t596\t596\n\nEMPTY_SET\tna\n";
+/// let gene_sets = parse_gmt(gmt.as_bytes(), "MSigDB_Hallmark", Some("ENTREZ")).unwrap();
+/// assert_eq!(gene_sets.len(), 1);
+/// assert_eq!(gene_sets[0].genes, vec!["ENTREZ:7157", "ENTREZ:596"]);
+/// ```
+pub fn parse_gmt<R: BufRead>(
+    reader: R,
+    source: &str,
+    prefix: Option<&str>,
+) -> Result<Vec<GeneSet>, anyhow::Error> {
+    let mut gene_sets = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut columns = line.split('\t');
+        let name = columns.next().unwrap_or_default().trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The gene set name is empty at line {}.",
+                i + 1
+            ));
+        }
+        let description = columns
+            .next()
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty() && *d != "na");
+
+        let mut seen = HashSet::new();
+        let genes = columns
+            .map(|gene| gene.trim())
+            .filter(|gene| !gene.is_empty())
+            .map(|gene| match prefix {
+                Some(prefix) if !gene.contains(':') => format!("{}:{}", prefix, gene),
+                _ => gene.to_string(),
+            })
+            .filter(|gene| seen.insert(gene.clone()))
+            .collect::<Vec<String>>();
+        // A gene set without genes is useless for the enrichment analysis.
+        if genes.is_empty() {
+            continue;
+        }
+
+        gene_sets.push(GeneSet {
+            id: 0,
+            name: name.to_string(),
+            source: source.to_string(),
+            description: description.map(|d| d.to_string()),
+            genes,
+            created_at: Utc::now(),
+        });
+    }
+
+    AnyOk(gene_sets)
+}
+
+impl GeneSet {
+    /// Import the gene sets of a GMT file into the database, the gene sets with the same name and source are replaced. The existing gene sets of the source are deleted first if drop is true.
+    pub async fn import_gmt(
+        pool: &sqlx::PgPool,
+        filepath: &Path,
+        source: &str,
+        prefix: Option<&str>,
+        drop: bool,
+    ) -> Result<usize, anyhow::Error> {
+        let reader = std::io::BufReader::new(std::fs::File::open(filepath)?);
+        let gene_sets = parse_gmt(reader, source, prefix)?;
+
+        let mut tx = pool.begin().await?;
+        if drop {
+            sqlx::query("DELETE FROM biomedgps_gene_set WHERE source = $1")
+                .bind(source)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        for gene_set in gene_sets.iter() {
+            sqlx::query(
+                "INSERT INTO biomedgps_gene_set (name, source, description, genes) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (name, source) DO UPDATE SET description = EXCLUDED.description, genes = EXCLUDED.genes, created_at = now()",
+            )
+            .bind(&gene_set.name)
+            .bind(&gene_set.source)
+            .bind(&gene_set.description)
+            .bind(&gene_set.genes)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        info!(
+            "Imported {} gene sets from {} as {}.",
+            gene_sets.len(),
+            filepath.display(),
+            source
+        );
+        AnyOk(gene_sets.len())
+    }
+
+    /// Derive the gene sets from the relations of a relation type between genes and the other entities (such as `GNBR::J::Gene:Disease`), each gene set is the genes which are connected with an entity, it is named as `Label::ID` (such as `Disease::MESH:D001249`) and its source is the relation type. The existing gene sets of the relation type are replaced.
+    pub async fn derive_from_relations(
+        pool: &sqlx::PgPool,
+        relation_type: &str,
+        min_set_size: usize,
+    ) -> Result<usize, anyhow::Error> {
+        // The genes may be on either end of the relations.
+        let sql_str = "
+            WITH gene_entity AS (
+                SELECT source_id AS gene, target_type AS label, target_id AS id FROM biomedgps_relation
+                WHERE relation_type = $1 AND source_type = 'Gene' AND target_type <> 'Gene'
+                UNION
+                SELECT target_id AS gene, source_type AS label, source_id AS id FROM biomedgps_relation
+                WHERE relation_type = $1 AND target_type = 'Gene' AND source_type <> 'Gene'
+            )
+            INSERT INTO biomedgps_gene_set (name, source, description, genes)
+            SELECT g.label || '::' || g.id, $1, MAX(e.name), ARRAY_AGG(DISTINCT g.gene)
+            FROM gene_entity g LEFT JOIN biomedgps_entity e ON e.id = g.id AND e.label = g.label
+            GROUP BY g.label, g.id
+            HAVING COUNT(DISTINCT g.gene) >= $2";
+        debug!("Deriving the gene sets of {}: {}", relation_type, sql_str);
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM biomedgps_gene_set WHERE source = $1")
+            .bind(relation_type)
+            .execute(&mut tx)
+            .await?;
+        let result = sqlx::query(sql_str)
+            .bind(relation_type)
+            .bind(min_set_size as i64)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        info!(
+            "Derived {} gene sets from the relations of {}.",
+            result.rows_affected(),
+            relation_type
+        );
+        AnyOk(result.rows_affected() as usize)
+    }
+
+    /// Fetch the sources of the gene sets with the number of the gene sets and the distinct genes.
+    pub async fn get_sources(pool: &sqlx::PgPool) -> Result<Vec<GeneSetSource>, anyhow::Error> {
+        let sources = sqlx::query_as::<_, GeneSetSource>(
+            "SELECT source, COUNT(DISTINCT id) AS num_of_sets, COUNT(DISTINCT gene) AS num_of_genes
+             FROM biomedgps_gene_set, UNNEST(genes) AS gene
+             GROUP BY source ORDER BY source",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(sources)
+    }
+
+    /// Fetch the gene sets of the sources (all sources if it is empty) whose sizes are in the range.
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        sources: &[String],
+        min_set_size: usize,
+        max_set_size: usize,
+    ) -> Result<Vec<GeneSet>, anyhow::Error> {
+        let gene_sets = with_query_timeout(
+            sqlx::query_as::<_, GeneSet>(
+                "SELECT * FROM biomedgps_gene_set
+                 WHERE (CARDINALITY($1::TEXT[]) = 0 OR source = ANY($1))
+                   AND CARDINALITY(genes) BETWEEN $2 AND $3
+                 ORDER BY id",
+            )
+            .bind(sources)
+            .bind(min_set_size as i32)
+            .bind(max_set_size as i32)
+            .fetch_all(pool),
+        )
+        .await?;
+
+        AnyOk(gene_sets)
+    }
+}
+
+impl EnrichmentResult {
+    /// Run the enrichment analysis of a gene list against the gene sets in the database, see [`enrich`](#method.enrich).
+    pub async fn run(
+        pool: &sqlx::PgPool,
+        genes: &[String],
+        options: &EnrichmentOptions,
+    ) -> Result<EnrichmentResult, anyhow::Error> {
+        if genes.is_empty() || genes.len() > MAX_GENE_LIST_SIZE {
+            return Err(ValidationError::new(&format!(
+                "The number of genes should be between 1 and {}.",
+                MAX_GENE_LIST_SIZE
+            ))
+            .into());
+        }
+        if options.min_set_size > options.max_set_size {
+            return Err(ValidationError::new(&format!(
+                "The min set size {} is larger than the max set size {}.",
+                options.min_set_size, options.max_set_size
+            ))
+            .into());
+        }
+
+        let gene_sets = GeneSet::get_records(
+            pool,
+            &options.sources,
+            options.min_set_size,
+            options.max_set_size,
+        )
+        .await?;
+
+        EnrichmentResult::enrich(genes, &gene_sets, options)
+    }
+
+    /// Test the over-representation of the gene list in each gene set by the hypergeometric test. The background is all genes of the gene sets unless its size is specified, the genes of the list which are not in the background are reported as the unknown genes and not tested.
+    pub fn enrich(
+        genes: &[String],
+        gene_sets: &[GeneSet],
+        options: &EnrichmentOptions,
+    ) -> Result<EnrichmentResult, anyhow::Error> {
+        let background = gene_sets
+            .iter()
+            .flat_map(|gene_set| gene_set.genes.iter())
+            .collect::<HashSet<&String>>();
+        let background_size = match options.background_size {
+            Some(size) if size < background.len() => {
+                return Err(ValidationError::new(&format!(
+                    "The background size {} is smaller than the number of the genes in the gene sets ({}).",
+                    size,
+                    background.len()
+                ))
+                .into());
+            }
+            Some(size) => size,
+            None => background.len(),
+        };
+
+        let mut seen = HashSet::new();
+        let (known, unknown): (Vec<&String>, Vec<&String>) = genes
+            .iter()
+            .filter(|gene| seen.insert(gene.as_str()))
+            .partition(|gene| background.contains(gene));
+        let gene_list = known.into_iter().collect::<HashSet<&String>>();
+
+        let hypergeometric = Hypergeometric::new(background_size);
+        let mut records = vec![];
+        for gene_set in gene_sets.iter() {
+            let overlap_genes = gene_set
+                .genes
+                .iter()
+                .filter(|gene| gene_list.contains(gene))
+                .cloned()
+                .collect::<Vec<String>>();
+            let overlap = overlap_genes.len();
+            let set_size = gene_set.genes.len();
+            let expected = gene_list.len() as f64 * set_size as f64 / background_size.max(1) as f64;
+
+            records.push(EnrichmentRecord {
+                gene_set_id: gene_set.id,
+                name: gene_set.name.clone(),
+                source: gene_set.source.clone(),
+                description: gene_set.description.clone(),
+                set_size,
+                overlap,
+                overlap_genes,
+                fold_enrichment: if expected > 0.0 {
+                    overlap as f64 / expected
+                } else {
+                    0.0
+                },
+                p_value: hypergeometric.upper_tail(overlap, set_size, gene_list.len()),
+                adjusted_p_value: 1.0,
+            });
+        }
+
+        let num_of_tested_sets = records.len();
+        let adjusted = benjamini_hochberg(&records.iter().map(|r| r.p_value).collect::<Vec<f64>>());
+        for (record, adjusted_p_value) in records.iter_mut().zip(adjusted) {
+            record.adjusted_p_value = adjusted_p_value;
+        }
+
+        records.retain(|r| r.overlap > 0 && r.adjusted_p_value <= options.adjusted_p_value_cutoff);
+        records.sort_by(|a, b| {
+            a.p_value
+                .total_cmp(&b.p_value)
+                .then(b.overlap.cmp(&a.overlap))
+                .then(a.gene_set_id.cmp(&b.gene_set_id))
+        });
+
+        AnyOk(EnrichmentResult {
+            num_of_genes: gene_list.len(),
+            unknown_genes: unknown.into_iter().cloned().collect(),
+            background_size,
+            num_of_tested_sets,
+            records,
+        })
+    }
+}
+
+/// The hypergeometric distribution of a background with N genes, the log factorials are computed once.
+struct Hypergeometric {
+    ln_factorials: Vec<f64>,
+}
+
+impl Hypergeometric {
+    fn new(background_size: usize) -> Self {
+        let mut ln_factorials = Vec::with_capacity(background_size + 1);
+        ln_factorials.push(0.0);
+        for i in 1..=background_size {
+            ln_factorials.push(ln_factorials[i - 1] + (i as f64).ln());
+        }
+
+        Hypergeometric { ln_factorials }
+    }
+
+    fn ln_choose(&self, n: usize, k: usize) -> f64 {
+        self.ln_factorials[n] - self.ln_factorials[k] - self.ln_factorials[n - k]
+    }
+
+    /// P(X >= overlap) where X is the overlap between a gene set of size K and a random gene list of size n, drawn from the background of size N.
+    fn upper_tail(&self, overlap: usize, set_size: usize, list_size: usize) -> f64 {
+        let total = self.ln_factorials.len() - 1;
+        let max_overlap = set_size.min(list_size);
+        let min_overlap = (set_size + list_size).saturating_sub(total);
+        if overlap <= min_overlap {
+            return 1.0;
+        }
+
+        let ln_total = self.ln_choose(total, list_size);
+        let p_value = (overlap..=max_overlap)
+            .map(|k| {
+                (self.ln_choose(set_size, k) + self.ln_choose(total - set_size, list_size - k)
+                    - ln_total)
+                    .exp()
+            })
+            .sum::<f64>();
+
+        p_value.min(1.0)
+    }
+}
+
+/// Adjust the p-values by the Benjamini-Hochberg method, the adjusted p-values are in the same order as the p-values.
+///
+/// ```
+/// use biomedgps::model::enrichment::benjamini_hochberg;
+///
+/// let adjusted = benjamini_hochberg(&[0.01, 0.04, 0.03, 0.2])
+///     .iter()
+///     .map(|p| (p * 1e4).round() / 1e4)
+///     .collect::<Vec<f64>>();
+/// assert_eq!(adjusted, vec![0.04, 0.0533, 0.0533, 0.2]);
+/// ```
+pub fn benjamini_hochberg(p_values: &[f64]) -> Vec<f64> {
+    let n = p_values.len();
+    let mut order = (0..n).collect::<Vec<usize>>();
+    order.sort_by(|a, b| p_values[*b].total_cmp(&p_values[*a]));
+
+    // Walk from the largest p-value to the smallest one, so the adjusted p-values are monotone.
+    let mut adjusted = vec![1.0; n];
+    let mut min_adjusted: f64 = 1.0;
+    for (i, index) in order.into_iter().enumerate() {
+        let rank = n - i;
+        min_adjusted = min_adjusted.min(p_values[index] * n as f64 / rank as f64);
+        adjusted[index] = min_adjusted;
+    }
+
+    adjusted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gene_set(id: i64, name: &str, genes: &[&str]) -> GeneSet {
+        GeneSet {
+            id,
+            name: name.to_string(),
+            source: "test".to_string(),
+            description: None,
+            genes: genes.iter().map(|g| g.to_string()).collect(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_hypergeometric() {
+        // The classic example: 50 balls with 5 white ones, draw 10 balls and get 4 or more white ones.
+        let hypergeometric = Hypergeometric::new(50);
+        let p_value = hypergeometric.upper_tail(4, 5, 10);
+        assert!((p_value - 0.0040835205).abs() < 1e-9);
+        assert_eq!(hypergeometric.upper_tail(0, 5, 10), 1.0);
+    }
+
+    #[test]
+    fn test_enrich() {
+        let background = (0..100)
+            .map(|i| format!("ENTREZ:{}", i))
+            .collect::<Vec<String>>();
+        let background_refs = background.iter().map(|g| g.as_str()).collect::<Vec<&str>>();
+        let gene_sets = vec![
+            gene_set(1, "PATHWAY_A", &background_refs[0..10]),
+            gene_set(2, "PATHWAY_B", &background_refs[10..60]),
+            gene_set(3, "PATHWAY_C", &background_refs[60..100]),
+        ];
+
+        let mut genes = background[0..8].to_vec();
+        genes.push("ENTREZ:8".to_string());
+        genes.push("ENTREZ:8".to_string());
+        genes.push("ENTREZ:65".to_string());
+        genes.push("ENTREZ:10000".to_string());
+
+        let result =
+            EnrichmentResult::enrich(&genes, &gene_sets, &EnrichmentOptions::default()).unwrap();
+        assert_eq!(result.num_of_genes, 10);
+        assert_eq!(result.unknown_genes, vec!["ENTREZ:10000"]);
+        assert_eq!(result.background_size, 100);
+        assert_eq!(result.num_of_tested_sets, 3);
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.records[0].name, "PATHWAY_A");
+        assert_eq!(result.records[0].overlap, 9);
+        assert!((result.records[0].fold_enrichment - 9.0).abs() < 1e-9);
+
+        let options = EnrichmentOptions {
+            background_size: Some(50),
+            ..Default::default()
+        };
+        assert!(EnrichmentResult::enrich(&genes, &gene_sets, &options).is_err());
+    }
+}
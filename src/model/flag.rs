@@ -0,0 +1,263 @@
+//! Flag module keeps the flags of the relations which are reported by the users as wrong, outdated or duplicated, such as an edge which is spotted in a rendered graph.
+//!
+//! A relation is flagged by `POST /api/v1/relations/:id/flags`, the admins triage the flags (see `/api/v1/relation-flags`) by accepting or rejecting them. The flags link back to the flagged relation and its provenance (the resource, the pmids and the dataset version), and the graph endpoints attach the number of the open flags to the edges (see [`Graph::fetch_flag_counts`](crate::model::graph::Graph::fetch_flag_counts)).
+
+use crate::model::core::{paging_metadata, RecordResponse};
+use anyhow::Ok as AnyOk;
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Utc};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Why a relation is flagged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FlagReason {
+    Wrong,
+    Outdated,
+    Duplicate,
+}
+
+impl FlagReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FlagReason::Wrong => "wrong",
+            FlagReason::Outdated => "outdated",
+            FlagReason::Duplicate => "duplicate",
+        }
+    }
+}
+
+/// The status of a flag, a flag is open until an admin accepts or rejects it. Only the open flags are counted on the edges.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FlagStatus {
+    Open,
+    Accepted,
+    Rejected,
+}
+
+impl FlagStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FlagStatus::Open => "open",
+            FlagStatus::Accepted => "accepted",
+            FlagStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// A flag of a relation, the fields of the relation are the provenance of the flagged relation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct RelationFlag {
+    pub id: i64,
+    pub relation_id: i64,
+    /// wrong, outdated or duplicate.
+    pub reason: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub comment: Option<String>,
+    pub flagged_by: String,
+    /// open, accepted or rejected.
+    pub status: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub resolved_by: Option<String>,
+
+    #[serde(with = "ts_seconds_option")]
+    #[oai(skip_serializing_if_is_none)]
+    pub resolved_at: Option<DateTime<Utc>>,
+
+    #[serde(with = "ts_seconds")]
+    pub created_at: DateTime<Utc>,
+
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub relation_type: Option<String>,
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub source_id: Option<String>,
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub source_type: Option<String>,
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub target_id: Option<String>,
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub target_type: Option<String>,
+    /// The resource which the relation comes from.
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub resource: Option<String>,
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub pmids: Option<String>,
+    /// The release tag of the dataset which the relation was imported from.
+    #[sqlx(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub dataset_version: Option<String>,
+}
+
+/// A relation is identified by the relation type, the source type, the source id, the target type and the target id.
+pub type RelationKey = (String, String, String, String, String);
+
+// The flags with the provenance of the flagged relations, the flags are aliased as `f`.
+const FLAG_SELECT: &str = "SELECT f.*, r.relation_type, r.source_id, r.source_type, r.target_id, r.target_type, r.resource, r.pmids, r.dataset_version
+                           FROM flags f JOIN biomedgps_relation r ON r.id = f.relation_id";
+
+impl RelationFlag {
+    /// Flag a relation, a user can only open one flag with the same reason for a relation, so the duplicated flag is a conflict.
+    pub async fn insert(
+        pool: &sqlx::PgPool,
+        relation_id: i64,
+        reason: FlagReason,
+        comment: Option<&str>,
+        flagged_by: &str,
+    ) -> Result<RelationFlag, anyhow::Error> {
+        let sql_str = format!(
+            "WITH flags AS (
+                 INSERT INTO biomedgps_relation_flag (relation_id, reason, comment, flagged_by)
+                 VALUES ($1, $2, $3, $4) RETURNING *
+             ) {}",
+            FLAG_SELECT
+        );
+        let record = sqlx::query_as::<_, RelationFlag>(&sql_str)
+            .bind(relation_id)
+            .bind(reason.as_str())
+            .bind(comment)
+            .bind(flagged_by)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Fetch the flags for the triage, such as the open flags, the latest one comes first. All flags are returned if the status is None.
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        status: Option<FlagStatus>,
+        relation_id: Option<i64>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<RecordResponse<RelationFlag>, anyhow::Error> {
+        let status = status.map(|status| status.as_str().to_string());
+        let where_str =
+            "($1::TEXT IS NULL OR f.status = $1) AND ($2::BIGINT IS NULL OR f.relation_id = $2)";
+        let sql_str = format!(
+            "WITH flags AS (SELECT * FROM biomedgps_relation_flag) {} WHERE {} ORDER BY f.created_at DESC, f.id DESC LIMIT $3 OFFSET $4",
+            FLAG_SELECT, where_str
+        );
+        let records = sqlx::query_as::<_, RelationFlag>(&sql_str)
+            .bind(&status)
+            .bind(relation_id)
+            .bind(page_size as i64)
+            .bind(((page - 1) * page_size) as i64)
+            .fetch_all(pool)
+            .await?;
+
+        let sql_str = format!(
+            "SELECT COUNT(*) FROM biomedgps_relation_flag f WHERE {}",
+            where_str
+        );
+        let total: i64 = sqlx::query_scalar(&sql_str)
+            .bind(&status)
+            .bind(relation_id)
+            .fetch_one(pool)
+            .await?;
+
+        let total = total as u64;
+        let (total_pages, has_next) = paging_metadata(total, page, page_size);
+        AnyOk(RecordResponse {
+            records,
+            total,
+            page,
+            page_size,
+            total_pages,
+            has_next,
+        })
+    }
+
+    /// Accept or reject an open flag (the status must not be open), it returns None if the flag doesn't exist or has been triaged.
+    pub async fn resolve(
+        pool: &sqlx::PgPool,
+        id: i64,
+        status: FlagStatus,
+        resolved_by: &str,
+    ) -> Result<Option<RelationFlag>, anyhow::Error> {
+        let sql_str = format!(
+            "WITH flags AS (
+                 UPDATE biomedgps_relation_flag SET status = $1, resolved_by = $2, resolved_at = now()
+                 WHERE id = $3 AND status = '{}' RETURNING *
+             ) {}",
+            FlagStatus::Open.as_str(),
+            FLAG_SELECT
+        );
+        let record = sqlx::query_as::<_, RelationFlag>(&sql_str)
+            .bind(status.as_str())
+            .bind(resolved_by)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Count the open flags of the relations which are identified by the relation type, the source and the target, such as the edges of a graph. The ids and the counts of the existing relations are returned, the count is 0 if a relation isn't flagged.
+    pub async fn count_open_flags(
+        pool: &sqlx::PgPool,
+        relations: &[RelationKey],
+    ) -> Result<HashMap<RelationKey, (i64, i64)>, anyhow::Error> {
+        if relations.is_empty() {
+            return AnyOk(HashMap::new());
+        }
+
+        let mut columns: [Vec<String>; 5] = Default::default();
+        for relation in relations.iter() {
+            columns[0].push(relation.0.clone());
+            columns[1].push(relation.1.clone());
+            columns[2].push(relation.2.clone());
+            columns[3].push(relation.3.clone());
+            columns[4].push(relation.4.clone());
+        }
+
+        let sql_str = format!(
+            "SELECT r.relation_type, r.source_type, r.source_id, r.target_type, r.target_id, r.id,
+                    (SELECT COUNT(*) FROM biomedgps_relation_flag f WHERE f.relation_id = r.id AND f.status = '{}')
+             FROM biomedgps_relation r
+             JOIN UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[]) AS e(relation_type, source_type, source_id, target_type, target_id)
+             ON r.relation_type = e.relation_type AND r.source_type = e.source_type AND r.source_id = e.source_id
+                AND r.target_type = e.target_type AND r.target_id = e.target_id",
+            FlagStatus::Open.as_str()
+        );
+        let rows =
+            sqlx::query_as::<_, (String, String, String, String, String, i64, i64)>(&sql_str)
+                .bind(&columns[0])
+                .bind(&columns[1])
+                .bind(&columns[2])
+                .bind(&columns[3])
+                .bind(&columns[4])
+                .fetch_all(pool)
+                .await?;
+
+        AnyOk(
+            rows.into_iter()
+                .map(
+                    |(relation_type, source_type, source_id, target_type, target_id, id, count)| {
+                        (
+                            (
+                                relation_type,
+                                source_type,
+                                source_id,
+                                target_type,
+                                target_id,
+                            ),
+                            (id, count),
+                        )
+                    },
+                )
+                .collect(),
+        )
+    }
+}
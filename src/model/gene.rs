@@ -0,0 +1,474 @@
+//! Gene module resolves the gene and protein identifiers which are pasted from the papers (such as `TP53`, `P04637`, `ENSG00000141510` and `NM_000546.6`) to the entities in the knowledge graph.
+//!
+//! The type of each identifier is recognized by its pattern (see [`IdentifierType::detect`]). The ENTREZ and HGNC ids are matched against the entity ids, the UniProt accessions, the Ensembl ids and the RefSeq accessions against the ids and the cross references (the `xrefs` column of the entities), the other prefixed ids (such as `DrugBank:DB00945` of a compound) against the ids and the xrefs as they are, and the symbols against the names and the synonyms. All identifiers are resolved by one query which is accelerated by the trigram indexes of the xrefs and the synonyms.
+
+use crate::model::core::{Entity, ENTITY_ID_REGEX};
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use poem_openapi::{Enum, Object};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+lazy_static! {
+    static ref ENTREZ_REGEX: Regex = Regex::new(r"^(?i:ENTREZ|NCBIGene|GeneID):([0-9]+)$|^([0-9]+)$").unwrap();
+    static ref HGNC_REGEX: Regex = Regex::new(r"^(?i:HGNC):([0-9]+)$").unwrap();
+    // P04637, A0A024R1R8 and the isoforms, such as P04637-2.
+    static ref UNIPROT_REGEX: Regex = Regex::new(
+        r"^(?:(?i:UniProtKB|UniProt):)?((?:[OPQ][0-9][A-Z0-9]{3}[0-9]|[A-NR-Z][0-9](?:[A-Z][A-Z0-9]{2}[0-9]){1,2})(?:-[0-9]+)?)$"
+    )
+    .unwrap();
+    // ENSG00000141510, ENSMUSG00000059552 and the versions, such as ENST00000269305.9.
+    static ref ENSEMBL_REGEX: Regex =
+        Regex::new(r"^(?:(?i:ENSEMBL):)?(ENS[A-Z]*[GTP][0-9]{11})(?:\.[0-9]+)?$").unwrap();
+    // NM_000546.6, NP_000537 and the other RefSeq accessions of the genes, the transcripts and the proteins.
+    static ref REFSEQ_REGEX: Regex =
+        Regex::new(r"^(?:(?i:RefSeq):)?((?:NM|NR|NP|XM|XR|XP|NG|NC)_[0-9]+)(?:\.[0-9]+)?$").unwrap();
+    static ref SYMBOL_REGEX: Regex = Regex::new(r"^[A-Za-z][A-Za-z0-9\-\.@/]*$").unwrap();
+}
+
+/// The max number of the identifiers which are resolved by one request.
+pub const MAX_IDENTIFIERS: usize = 1000;
+/// The entity types which are searched by default.
+pub const DEFAULT_GENE_LABELS: [&str; 2] = ["Gene", "Protein"];
+/// The entity types which a gene or compound list is resolved to by default, see `/api/v1/graphs/from-list`.
+pub const DEFAULT_LIST_LABELS: [&str; 3] = ["Gene", "Protein", "Compound"];
+/// The matches whose confidences are lower than it are not used as the nodes of a graph by default, so the ambiguous synonyms are skipped.
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+// The separator of the synonyms and the xrefs of an entity.
+const LIST_SEPARATOR: char = '|';
+
+/// The type of a gene or protein identifier.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierType {
+    Entrez,
+    Hgnc,
+    Uniprot,
+    Ensembl,
+    Refseq,
+    /// The other prefixed ids, such as `DrugBank:DB00945` and `MESH:D001241`.
+    Curie,
+    Symbol,
+    /// The identifiers which are not recognized, such as the ones with spaces.
+    Unknown,
+}
+
+impl IdentifierType {
+    /// The prefixes of the identifiers in the entity ids and the xrefs, the first one is used to compose the id.
+    fn prefixes(&self) -> &'static [&'static str] {
+        match self {
+            IdentifierType::Entrez => &["ENTREZ", "NCBIGene", "GeneID"],
+            IdentifierType::Hgnc => &["HGNC"],
+            IdentifierType::Uniprot => &["UniProtKB", "UniProt"],
+            IdentifierType::Ensembl => &["ENSEMBL"],
+            IdentifierType::Refseq => &["RefSeq"],
+            IdentifierType::Curie | IdentifierType::Symbol | IdentifierType::Unknown => &[],
+        }
+    }
+
+    /// Recognize the type of an identifier and normalize it, the prefix, the version and the isoform of an accession are removed, such as `NM_000546` for `RefSeq:NM_000546.6`.
+    ///
+    /// ```
+    /// use biomedgps::model::gene::IdentifierType;
+    ///
+    /// assert_eq!(IdentifierType::detect("7157"), (IdentifierType::Entrez, "7157".to_string()));
+    /// assert_eq!(IdentifierType::detect("NCBIGene:7157"), (IdentifierType::Entrez, "7157".to_string()));
+    /// assert_eq!(IdentifierType::detect("P04637-2"), (IdentifierType::Uniprot, "P04637".to_string()));
+    /// assert_eq!(IdentifierType::detect("ENST00000269305.9"), (IdentifierType::Ensembl, "ENST00000269305".to_string()));
+    /// assert_eq!(IdentifierType::detect("NM_000546.6"), (IdentifierType::Refseq, "NM_000546".to_string()));
+    /// assert_eq!(IdentifierType::detect("TP53"), (IdentifierType::Symbol, "TP53".to_string()));
+    /// ```
+    pub fn detect(identifier: &str) -> (IdentifierType, String) {
+        let identifier = identifier.trim();
+        if let Some(captures) = ENTREZ_REGEX.captures(identifier) {
+            let id = captures.get(1).or(captures.get(2)).unwrap().as_str();
+            return (IdentifierType::Entrez, id.to_string());
+        }
+
+        let accessions = [
+            (IdentifierType::Hgnc, &*HGNC_REGEX),
+            (IdentifierType::Ensembl, &*ENSEMBL_REGEX),
+            (IdentifierType::Refseq, &*REFSEQ_REGEX),
+            (IdentifierType::Uniprot, &*UNIPROT_REGEX),
+        ];
+        for (identifier_type, regex) in accessions.iter() {
+            if let Some(captures) = regex.captures(identifier) {
+                let mut accession = captures[1].to_string();
+                // The isoforms are resolved to the canonical accession.
+                if *identifier_type == IdentifierType::Uniprot {
+                    accession = accession.split('-').next().unwrap().to_string();
+                }
+                return (*identifier_type, accession);
+            }
+        }
+
+        if ENTITY_ID_REGEX.is_match(identifier) {
+            (IdentifierType::Curie, identifier.to_string())
+        } else if SYMBOL_REGEX.is_match(identifier) {
+            (IdentifierType::Symbol, identifier.to_string())
+        } else {
+            (IdentifierType::Unknown, identifier.to_string())
+        }
+    }
+}
+
+/// How an identifier matches an entity, the confidence of each match type is in [`MatchType::confidence`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// The identifier is the id of the entity.
+    Id,
+    /// The identifier is a cross reference of the entity.
+    Xref,
+    /// The identifier is the name of the entity.
+    Symbol,
+    /// The identifier is the name of the entity, but the case is different.
+    #[oai(rename = "symbol_ignore_case")]
+    #[serde(rename = "symbol_ignore_case")]
+    SymbolIgnoreCase,
+    /// The identifier is a synonym of the entity, such as a previous symbol.
+    Synonym,
+}
+
+impl MatchType {
+    /// The confidence of a match, the ids are more reliable than the symbols because a symbol may be an alias of another gene.
+    pub fn confidence(&self) -> f64 {
+        match self {
+            MatchType::Id => 1.0,
+            MatchType::Xref => 0.95,
+            MatchType::Symbol => 0.9,
+            MatchType::SymbolIgnoreCase => 0.8,
+            MatchType::Synonym => 0.6,
+        }
+    }
+}
+
+/// An entity which is matched by an identifier.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct GeneMatch {
+    pub entity_id: String,
+    pub entity_type: String,
+    pub name: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub taxid: Option<String>,
+    pub match_type: MatchType,
+    /// The confidence of the match, it is lowered if the identifier matches several entities with the same match type.
+    pub confidence: f64,
+}
+
+/// The entities which are matched by an identifier, the best match goes first. The matches are empty if the identifier is not resolved.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct GeneResolution {
+    /// The identifier in the request.
+    pub identifier: String,
+    pub identifier_type: IdentifierType,
+    pub matches: Vec<GeneMatch>,
+}
+
+/// Split the pasted identifiers by the whitespaces, the commas and the semicolons, the duplicated identifiers are removed.
+///
+/// ```
+/// use biomedgps::model::gene::split_identifiers;
+///
+/// let identifiers = vec!["TP53, BRCA1\nENSG00000141510".to_string(), "TP53; ".to_string()];
+/// assert_eq!(split_identifiers(&identifiers), vec!["TP53", "BRCA1", "ENSG00000141510"]);
+/// ```
+pub fn split_identifiers(identifiers: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    identifiers
+        .iter()
+        .flat_map(|identifier| {
+            identifier.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        })
+        .filter(|identifier| !identifier.is_empty() && seen.insert(identifier.to_string()))
+        .map(|identifier| identifier.to_string())
+        .collect()
+}
+
+/// Whether an id (such as `UniProtKB:P04637`) is the normalized identifier of the type, the prefix is compared without the case.
+fn is_same_id(id: &str, identifier_type: IdentifierType, accession: &str) -> bool {
+    if identifier_type == IdentifierType::Curie {
+        return match (id.split_once(':'), accession.split_once(':')) {
+            (Some((prefix, local)), Some((other_prefix, other_local))) => {
+                prefix.eq_ignore_ascii_case(other_prefix) && local == other_local
+            }
+            _ => false,
+        };
+    }
+
+    let (_, normalized) = IdentifierType::detect(id);
+    match id.split_once(':') {
+        Some((prefix, _)) => {
+            identifier_type
+                .prefixes()
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(prefix))
+                && normalized == accession
+        }
+        None => false,
+    }
+}
+
+/// Match an identifier against an entity, the best match type is returned.
+fn match_entity(
+    identifier: &str,
+    identifier_type: IdentifierType,
+    accession: &str,
+    entity: &Entity,
+) -> Option<MatchType> {
+    let split = |value: &Option<String>| -> Vec<String> {
+        value
+            .as_deref()
+            .unwrap_or_default()
+            .split(LIST_SEPARATOR)
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    };
+
+    match identifier_type {
+        IdentifierType::Symbol => {
+            if entity.name == identifier {
+                Some(MatchType::Symbol)
+            } else if entity.name.eq_ignore_ascii_case(identifier) {
+                Some(MatchType::SymbolIgnoreCase)
+            } else if split(&entity.synonyms)
+                .iter()
+                .any(|synonym| synonym.eq_ignore_ascii_case(identifier))
+            {
+                Some(MatchType::Synonym)
+            } else {
+                None
+            }
+        }
+        IdentifierType::Unknown => None,
+        _ => {
+            if is_same_id(&entity.id, identifier_type, accession) {
+                Some(MatchType::Id)
+            } else if split(&entity.xrefs)
+                .iter()
+                .any(|xref| is_same_id(xref, identifier_type, accession))
+            {
+                Some(MatchType::Xref)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl GeneResolution {
+    /// Resolve the identifiers to the entities of the labels (the genes and the proteins by default), the entities of other species are skipped if the taxid is given, such as `9606` for human. The results are in the order of the identifiers.
+    pub async fn resolve(
+        pool: &sqlx::PgPool,
+        identifiers: &[String],
+        labels: &[String],
+        taxid: Option<&str>,
+    ) -> Result<Vec<GeneResolution>, anyhow::Error> {
+        let detected = identifiers
+            .iter()
+            .map(|identifier| {
+                let (identifier_type, accession) = IdentifierType::detect(identifier);
+                (identifier.to_string(), identifier_type, accession)
+            })
+            .collect::<Vec<(String, IdentifierType, String)>>();
+
+        // The candidates are fetched by the composed ids, the names (ILIKE without wildcards ignores the case) and the patterns of the xrefs and the synonyms, they are checked by match_entity.
+        let mut ids = vec![];
+        let mut names = vec![];
+        let mut xref_patterns = vec![];
+        let mut synonym_patterns = vec![];
+        for (identifier, identifier_type, accession) in detected.iter() {
+            match identifier_type {
+                IdentifierType::Symbol => {
+                    names.push(escape_like(identifier));
+                    synonym_patterns.push(format!("%{}%", escape_like(identifier)));
+                }
+                IdentifierType::Unknown => {}
+                IdentifierType::Curie => {
+                    ids.push(accession.to_string());
+                    xref_patterns.push(format!("%{}%", escape_like(accession)));
+                }
+                _ => {
+                    for prefix in identifier_type.prefixes() {
+                        ids.push(format!("{}:{}", prefix, accession));
+                    }
+                    xref_patterns.push(format!("%{}%", escape_like(accession)));
+                }
+            }
+        }
+
+        let sql_str = "SELECT * FROM biomedgps_entity
+                       WHERE label = ANY($1) AND ($2::TEXT IS NULL OR taxid = $2)
+                       AND (id = ANY($3) OR name ILIKE ANY($4) OR xrefs ILIKE ANY($5) OR synonyms ILIKE ANY($6))";
+        let candidates = sqlx::query_as::<_, Entity>(sql_str)
+            .bind(labels)
+            .bind(taxid)
+            .bind(&ids)
+            .bind(&names)
+            .bind(&xref_patterns)
+            .bind(&synonym_patterns)
+            .fetch_all(pool)
+            .await?;
+
+        let results = detected
+            .into_iter()
+            .map(|(identifier, identifier_type, accession)| {
+                let mut matches = candidates
+                    .iter()
+                    .filter_map(|entity| {
+                        match_entity(&identifier, identifier_type, &accession, entity).map(
+                            |match_type| GeneMatch {
+                                entity_id: entity.id.clone(),
+                                entity_type: entity.label.clone(),
+                                name: entity.name.clone(),
+                                taxid: entity.taxid.clone(),
+                                match_type,
+                                confidence: match_type.confidence(),
+                            },
+                        )
+                    })
+                    .collect::<Vec<GeneMatch>>();
+                rank_matches(&mut matches);
+
+                GeneResolution {
+                    identifier,
+                    identifier_type,
+                    matches,
+                }
+            })
+            .collect();
+
+        AnyOk(results)
+    }
+}
+
+/// Lower the confidences of the ambiguous matches and sort the matches by the confidence. The gene and the protein of the same symbol are not ambiguous, so the matches are counted per entity type.
+fn rank_matches(matches: &mut [GeneMatch]) {
+    let mut counts: HashMap<(String, MatchType), usize> = HashMap::new();
+    for m in matches.iter() {
+        *counts
+            .entry((m.entity_type.clone(), m.match_type))
+            .or_default() += 1;
+    }
+    for m in matches.iter_mut() {
+        m.confidence /= counts[&(m.entity_type.clone(), m.match_type)] as f64;
+    }
+
+    matches.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.entity_id.cmp(&b.entity_id))
+    });
+}
+
+// Escape the wildcards of a LIKE pattern.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(id: &str, name: &str, synonyms: Option<&str>, xrefs: Option<&str>) -> Entity {
+        Entity {
+            idx: 0,
+            id: id.to_string(),
+            name: name.to_string(),
+            label: "Gene".to_string(),
+            resource: "test".to_string(),
+            description: None,
+            taxid: Some("9606".to_string()),
+            synonyms: synonyms.map(|s| s.to_string()),
+            pmids: None,
+            xrefs: xrefs.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_match_entity() {
+        let tp53 = entity(
+            "ENTREZ:7157",
+            "TP53",
+            Some("P53|LFS1"),
+            Some("UniProtKB:P04637|ENSEMBL:ENSG00000141510|RefSeq:NM_000546.6"),
+        );
+        let check = |identifier: &str| {
+            let (identifier_type, accession) = IdentifierType::detect(identifier);
+            match_entity(identifier, identifier_type, &accession, &tp53)
+        };
+
+        assert_eq!(check("7157"), Some(MatchType::Id));
+        assert_eq!(check("uniprot:P04637-1"), Some(MatchType::Xref));
+        assert_eq!(check("ENSG00000141510.17"), Some(MatchType::Xref));
+        assert_eq!(check("NM_000546"), Some(MatchType::Xref));
+        assert_eq!(check("TP53"), Some(MatchType::Symbol));
+        assert_eq!(check("tp53"), Some(MatchType::SymbolIgnoreCase));
+        assert_eq!(check("LFS1"), Some(MatchType::Synonym));
+        // A substring of a xref is not a match.
+        assert_eq!(check("ENSG0000014151"), None);
+        assert_eq!(check("P0463"), None);
+        assert_eq!(check("71570"), None);
+
+        let aspirin = Entity {
+            label: "Compound".to_string(),
+            ..entity("DrugBank:DB00945", "aspirin", None, Some("MESH:D001241"))
+        };
+        let (identifier_type, accession) = IdentifierType::detect("mesh:D001241");
+        assert_eq!(identifier_type, IdentifierType::Curie);
+        assert_eq!(
+            match_entity("mesh:D001241", identifier_type, &accession, &aspirin),
+            Some(MatchType::Xref)
+        );
+        assert_eq!(
+            match_entity(
+                "DrugBank:DB00945",
+                IdentifierType::Curie,
+                "DrugBank:DB00945",
+                &aspirin
+            ),
+            Some(MatchType::Id)
+        );
+    }
+
+    #[test]
+    fn test_rank_matches() {
+        let gene_match = |entity_id: &str, entity_type: &str, match_type: MatchType| GeneMatch {
+            entity_id: entity_id.to_string(),
+            entity_type: entity_type.to_string(),
+            name: "".to_string(),
+            taxid: None,
+            match_type,
+            confidence: match_type.confidence(),
+        };
+        let mut matches = vec![
+            gene_match("ENTREZ:2", "Gene", MatchType::Synonym),
+            gene_match("ENTREZ:3", "Gene", MatchType::Synonym),
+            gene_match("UniProtKB:P1", "Protein", MatchType::Symbol),
+            gene_match("ENTREZ:1", "Gene", MatchType::Symbol),
+        ];
+        rank_matches(&mut matches);
+
+        let ranked = matches
+            .iter()
+            .map(|m| (m.entity_id.as_str(), m.confidence))
+            .collect::<Vec<(&str, f64)>>();
+        assert_eq!(
+            ranked,
+            vec![
+                ("ENTREZ:1", 0.9),
+                ("UniProtKB:P1", 0.9),
+                ("ENTREZ:2", 0.3),
+                ("ENTREZ:3", 0.3)
+            ]
+        );
+    }
+}
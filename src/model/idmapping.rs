@@ -0,0 +1,114 @@
+//! ID mapping module maps the ids of the same entity in different resources to a canonical id, such as `DOID:2841` and `MONDO:0004979` to `MESH:D001249`, so the graph endpoints can merge the equivalent nodes into one node (see [`Graph::merge_equivalent_nodes`](crate::model::graph::Graph::merge_equivalent_nodes)).
+//!
+//! The mappings are imported by `biomedgps-cli importdb -t entity_id_mapping` from a file with the entity_id, entity_type and canonical_id columns. The canonical id doesn't need a mapping to itself, and the ids of an entity are only mapped within the same entity type.
+
+use crate::model::core::{CheckData, ENTITY_ID_REGEX, ENTITY_LABEL_REGEX};
+use anyhow::Ok as AnyOk;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::{error::Error, path::PathBuf};
+use validator::Validate;
+
+/// The canonical id of an entity id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct EntityIdMapping {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_id should be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The entity_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `ENTREZ:7157`."
+    ))]
+    pub entity_id: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must be a valid entity label, such as `Gene`."
+    ))]
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_type should be between 1 and 64."
+    ))]
+    pub entity_type: String,
+
+    /// The id of the canonical entity with the same type, such as `MESH:D001249` for `DOID:2841`.
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of canonical_id should be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The canonical_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `ENTREZ:7157`."
+    ))]
+    pub canonical_id: String,
+}
+
+impl CheckData for EntityIdMapping {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<EntityIdMapping>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["entity_id".to_string(), "entity_type".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "canonical_id".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        Self::fields()
+    }
+}
+
+impl EntityIdMapping {
+    /// Fetch the canonical ids of the entities (the pairs of the entity type and the entity id), the entities without a mapping are not in the result.
+    pub async fn get_canonical_ids(
+        pool: &sqlx::PgPool,
+        entities: &[(String, String)],
+    ) -> Result<HashMap<(String, String), String>, anyhow::Error> {
+        if entities.is_empty() {
+            return AnyOk(HashMap::new());
+        }
+
+        let (entity_types, entity_ids): (Vec<String>, Vec<String>) =
+            entities.iter().cloned().unzip();
+        let sql_str = "SELECT m.entity_type, m.entity_id, m.canonical_id
+                       FROM biomedgps_entity_id_mapping m
+                       JOIN UNNEST($1::TEXT[], $2::TEXT[]) AS e(entity_type, entity_id)
+                       ON m.entity_type = e.entity_type AND m.entity_id = e.entity_id";
+        let rows = sqlx::query_as::<_, (String, String, String)>(sql_str)
+            .bind(&entity_types)
+            .bind(&entity_ids)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(
+            rows.into_iter()
+                .map(|(entity_type, entity_id, canonical_id)| {
+                    ((entity_type, entity_id), canonical_id)
+                })
+                .collect(),
+        )
+    }
+}
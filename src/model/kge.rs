@@ -0,0 +1,862 @@
+//! KGE module is used to import the embeddings from the native outputs of the knowledge graph embedding tools, so they don't need to be converted into the csv files by hand, and to evaluate the imported embeddings on the test split of the relations.
+//!
+//! * `dglke` - The `entities.tsv` and `relations.tsv` (id and name, without a header) of the dataset, and the `*_entity.npy` and `*_relation.npy` files of the saved model.
+//! * `pykeen` - The `training_triples/entity_to_id.tsv.gz` and `training_triples/relation_to_id.tsv.gz` of a pipeline result directory, and the `entity_embeddings.npy` and `relation_embeddings.npy` files in the same directory. The `trained_model.pkl` is a pickled torch module which cannot be read without torch, so the embeddings need to be saved by numpy, such as `np.save("entity_embeddings.npy", model.entity_representations[0](indices=None).detach().cpu().numpy())`.
+//! * `csv` - The `entity_embeddings.tsv` and `relation_embeddings.tsv` files which are same with the entity_embedding and relation_embedding files of `biomedgps-cli importdb`.
+//!
+//! The entities are named like `Gene::ENTREZ:7157` in the native outputs (same with the triples exported by `/api/v1/relation-splits/{split}/triples`), the entity names are read from the entity table. The native outputs are converted into the csv files first, and then they are imported by the same way as `biomedgps-cli importdb`.
+//!
+//! The evaluation ranks the head and the tail of each test triple among the candidate entities by the score function of the model (see [`ScoreFunction`](../../algorithm/kge/enum.ScoreFunction.html)), and computes the MRR, MR and Hits@K which are saved into the metrics of the model.
+
+use crate::algorithm::kge::ScoreFunction;
+use crate::model::core::{EmbeddingModel, EntityEmbedding, RelationEmbedding};
+use crate::model::graph::COMPOSED_ENTITY_DELIMITER;
+use crate::model::split::RelationSplit;
+use crate::model::util::open_data_file;
+use crate::pgvector::Vector;
+use anyhow::Ok as AnyOk;
+use log::{debug, info, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const ENTITY_EMBEDDING_FILE: &str = "entity_embeddings.tsv";
+pub const RELATION_EMBEDDING_FILE: &str = "relation_embeddings.tsv";
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingFormat {
+    Dglke,
+    Pykeen,
+    Csv,
+}
+
+impl FromStr for EmbeddingFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dglke" => Ok(EmbeddingFormat::Dglke),
+            "pykeen" => Ok(EmbeddingFormat::Pykeen),
+            "csv" => Ok(EmbeddingFormat::Csv),
+            _ => Err(format!(
+                "Invalid embedding format: {}, it must be dglke, pykeen or csv.",
+                s
+            )),
+        }
+    }
+}
+
+/// A 2D array which is read from a npy file, the values are in the row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f32>,
+}
+
+impl NpyMatrix {
+    pub fn row(&self, index: usize) -> &[f32] {
+        &self.data[index * self.cols..(index + 1) * self.cols]
+    }
+}
+
+/// Get the value of a key in the header of a npy file, such as `{'descr': '<f4', 'fortran_order': False, 'shape': (100, 400), }`.
+fn get_npy_header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}':", key))? + key.len() + 3;
+    let value = header[start..].trim_start();
+    let end = if value.starts_with('(') {
+        value.find(')')? + 1
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        quoted.find('\'')? + 2
+    } else {
+        value.find(',').unwrap_or(value.len())
+    };
+
+    Some(value[..end].trim().trim_matches('\''))
+}
+
+/// Parse a npy file of a 2D float array (float32, float64, complex64 or complex128, little-endian). The complex embeddings (such as ComplEx and RotatE) have double columns, the real parts are followed by the imaginary parts in each row, which is same as the npy files of DGL-KE.
+pub fn parse_npy(bytes: &[u8]) -> Result<NpyMatrix, anyhow::Error> {
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(anyhow::anyhow!("It is not a npy file."));
+    }
+
+    let (header_len, offset) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            12,
+        ),
+        version => return Err(anyhow::anyhow!("Unsupported npy version: {}.", version)),
+    };
+
+    let header = bytes
+        .get(offset..offset + header_len)
+        .ok_or_else(|| anyhow::anyhow!("The header of the npy file is truncated."))?;
+    let header = std::str::from_utf8(header)?;
+
+    let descr = get_npy_header_value(header, "descr")
+        .ok_or_else(|| anyhow::anyhow!("No descr in the npy header: {}", header))?;
+    let (item_size, is_complex) = match descr {
+        "<f4" => (4, false),
+        "<f8" => (8, false),
+        "<c8" => (4, true),
+        "<c16" => (8, true),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unsupported dtype: {}, only the little-endian float32, float64, complex64 and complex128 arrays are supported.",
+                descr
+            ))
+        }
+    };
+
+    let fortran_order = get_npy_header_value(header, "fortran_order") == Some("True");
+    let shape = get_npy_header_value(header, "shape")
+        .ok_or_else(|| anyhow::anyhow!("No shape in the npy header: {}", header))?
+        .trim_matches(|c: char| c == '(' || c == ')')
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()?;
+    let (rows, cols) = match shape.as_slice() {
+        [rows, cols] => (*rows, if is_complex { cols * 2 } else { *cols }),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "The embeddings must be a 2D array, but the shape is {:?}.",
+                shape
+            ))
+        }
+    };
+
+    let body = &bytes[offset + header_len..];
+    if body.len() != rows * cols * item_size {
+        return Err(anyhow::anyhow!(
+            "The npy file is truncated, expected {} bytes of data, but got {}.",
+            rows * cols * item_size,
+            body.len()
+        ));
+    }
+
+    let values = body
+        .chunks_exact(item_size)
+        .map(|v| match item_size {
+            4 => f32::from_le_bytes([v[0], v[1], v[2], v[3]]),
+            _ => f64::from_le_bytes([v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]]) as f32,
+        })
+        .collect::<Vec<f32>>();
+
+    let data = if fortran_order && !is_complex {
+        let mut data = vec![0.0; rows * cols];
+        for (i, value) in values.into_iter().enumerate() {
+            data[(i % rows) * cols + i / rows] = value;
+        }
+        data
+    } else if fortran_order {
+        return Err(anyhow::anyhow!(
+            "The complex arrays in the fortran order are not supported."
+        ));
+    } else if is_complex {
+        // The real and imaginary parts are interleaved in the npy file.
+        let half = cols / 2;
+        let mut data = vec![0.0; rows * cols];
+        for (i, value) in values.into_iter().enumerate() {
+            let (row, col) = (i / cols, i % cols);
+            data[row * cols + (col % 2) * half + col / 2] = value;
+        }
+        data
+    } else {
+        values
+    };
+
+    AnyOk(NpyMatrix { rows, cols, data })
+}
+
+pub fn read_npy(filepath: &Path) -> Result<NpyMatrix, anyhow::Error> {
+    let bytes = std::fs::read(filepath)?;
+    parse_npy(&bytes).map_err(|e| anyhow::anyhow!("Invalid npy file {}: {}", filepath.display(), e))
+}
+
+/// Read a mapping file of the ids and the names, each line is an id and a name separated by a tab. The file may be compressed by gzip or zstd.
+pub fn read_id_mapping(
+    filepath: &Path,
+    has_header: bool,
+) -> Result<Vec<(usize, String)>, anyhow::Error> {
+    let reader = open_data_file(filepath)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", filepath.display(), e.to_string()))?;
+
+    let mut mapping = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if (has_header && i == 0) || line.trim().is_empty() {
+            continue;
+        }
+
+        let (id, name) = line.split_once('\t').ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid line {} of {}, it should be an id and a name separated by a tab.",
+                i + 1,
+                filepath.display()
+            )
+        })?;
+        let id = id.trim().parse::<usize>().map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid id {} at line {} of {}: {}",
+                id,
+                i + 1,
+                filepath.display(),
+                e
+            )
+        })?;
+        mapping.push((id, name.trim().to_string()));
+    }
+
+    AnyOk(mapping)
+}
+
+/// Pair the names with the rows of the embeddings by the ids of the mapping.
+fn pair_embeddings(
+    mapping: Vec<(usize, String)>,
+    matrix: &NpyMatrix,
+    filepath: &Path,
+) -> Result<Vec<(usize, String, Vec<f32>)>, anyhow::Error> {
+    if mapping.len() != matrix.rows {
+        warn!(
+            "There are {} names but {} embeddings in {}.",
+            mapping.len(),
+            matrix.rows,
+            filepath.display()
+        );
+    }
+
+    mapping
+        .into_iter()
+        .map(|(id, name)| {
+            if id >= matrix.rows {
+                return Err(anyhow::anyhow!(
+                    "The id {} of {} is out of the {} embeddings in {}.",
+                    id,
+                    name,
+                    matrix.rows,
+                    filepath.display()
+                ));
+            }
+            AnyOk((id, name, matrix.row(id).to_vec()))
+        })
+        .collect()
+}
+
+/// Find the file whose name ends with the suffix in the directory, such as `_entity.npy` of the DGL-KE model.
+fn find_file_by_suffix(dir: &Path, suffix: &str) -> Result<PathBuf, anyhow::Error> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.ends_with(suffix))
+                .unwrap_or(false)
+        })
+        .collect::<Vec<PathBuf>>();
+
+    match files.len() {
+        1 => AnyOk(files.remove(0)),
+        0 => Err(anyhow::anyhow!("No *{} file in {}.", suffix, dir.display())),
+        _ => Err(anyhow::anyhow!(
+            "Found {} *{} files in {}, please keep only one model in the directory.",
+            files.len(),
+            suffix,
+            dir.display()
+        )),
+    }
+}
+
+/// The embeddings which are read from the native outputs, the names are the node ids (such as `Gene::ENTREZ:7157`) and the relation types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NativeEmbeddings {
+    pub entities: Vec<(usize, String, Vec<f32>)>,
+    pub relations: Vec<(usize, String, Vec<f32>)>,
+}
+
+impl NativeEmbeddings {
+    /// Read the DGL-KE outputs, the dataset directory contains the entities.tsv and relations.tsv, and the model directory contains the npy files.
+    pub fn read_dglke(
+        model_dir: &Path,
+        dataset_dir: &Path,
+    ) -> Result<NativeEmbeddings, anyhow::Error> {
+        let entity_file = find_file_by_suffix(model_dir, "_entity.npy")?;
+        let relation_file = find_file_by_suffix(model_dir, "_relation.npy")?;
+        let entities = read_id_mapping(&dataset_dir.join("entities.tsv"), false)?;
+        let relations = read_id_mapping(&dataset_dir.join("relations.tsv"), false)?;
+
+        AnyOk(NativeEmbeddings {
+            entities: pair_embeddings(entities, &read_npy(&entity_file)?, &entity_file)?,
+            relations: pair_embeddings(relations, &read_npy(&relation_file)?, &relation_file)?,
+        })
+    }
+
+    /// Read the PyKEEN outputs, the directory is the result directory of a pipeline which contains the training_triples directory and the npy files.
+    pub fn read_pykeen(result_dir: &Path) -> Result<NativeEmbeddings, anyhow::Error> {
+        let triples_dir = result_dir.join("training_triples");
+        let entity_file = result_dir.join("entity_embeddings.npy");
+        let relation_file = result_dir.join("relation_embeddings.npy");
+        let entities = read_id_mapping(&triples_dir.join("entity_to_id.tsv.gz"), true)?;
+        let relations = read_id_mapping(&triples_dir.join("relation_to_id.tsv.gz"), true)?;
+
+        AnyOk(NativeEmbeddings {
+            entities: pair_embeddings(entities, &read_npy(&entity_file)?, &entity_file)?,
+            relations: pair_embeddings(relations, &read_npy(&relation_file)?, &relation_file)?,
+        })
+    }
+
+    /// Write the embeddings into the entity_embeddings.tsv and relation_embeddings.tsv files in the output directory. The entity names are read from the entity table, and the entities which are not in the entity table are skipped.
+    pub async fn write_csv_files(
+        &self,
+        pool: &sqlx::PgPool,
+        output_dir: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let mut node_ids: Vec<(String, String)> = vec![];
+        for (_, node_id, _) in self.entities.iter() {
+            match node_id.split_once("::") {
+                Some((entity_type, entity_id)) => {
+                    node_ids.push((entity_id.to_string(), entity_type.to_string()))
+                }
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid entity: {}, it should be like Gene::ENTREZ:7157.",
+                        node_id
+                    ))
+                }
+            }
+        }
+
+        let names: HashMap<(String, String), String> =
+            sqlx::query_as::<_, (String, String, String)>(
+                "SELECT id, label, name FROM biomedgps_entity
+                 WHERE (id, label) IN (SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[]))",
+            )
+            .bind(
+                node_ids
+                    .iter()
+                    .map(|(id, _)| id.clone())
+                    .collect::<Vec<String>>(),
+            )
+            .bind(
+                node_ids
+                    .iter()
+                    .map(|(_, label)| label.clone())
+                    .collect::<Vec<String>>(),
+            )
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(id, label, name)| ((id, label), name))
+            .collect();
+
+        let format_embedding = |embedding: &[f32]| {
+            embedding
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join("|")
+        };
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_path(output_dir.join(ENTITY_EMBEDDING_FILE))?;
+        writer.write_record([
+            "embedding_id",
+            "entity_id",
+            "entity_type",
+            "entity_name",
+            "embedding",
+        ])?;
+        let mut skipped = 0;
+        for ((embedding_id, _, embedding), (entity_id, entity_type)) in
+            self.entities.iter().zip(node_ids.iter())
+        {
+            let name = match names.get(&(entity_id.clone(), entity_type.clone())) {
+                Some(name) => name,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            writer.write_record([
+                embedding_id.to_string(),
+                entity_id.clone(),
+                entity_type.clone(),
+                name.clone(),
+                format_embedding(embedding),
+            ])?;
+        }
+        writer.flush()?;
+
+        if skipped > 0 {
+            warn!(
+                "Skipped the embeddings of {} entities which are not in the entity table.",
+                skipped
+            );
+        }
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_path(output_dir.join(RELATION_EMBEDDING_FILE))?;
+        writer.write_record(["embedding_id", "relation_type", "embedding"])?;
+        for (embedding_id, relation_type, embedding) in self.relations.iter() {
+            writer.write_record([
+                embedding_id.to_string(),
+                relation_type.clone(),
+                format_embedding(embedding),
+            ])?;
+        }
+        writer.flush()?;
+
+        AnyOk(())
+    }
+}
+
+/// Import the entity_embeddings.tsv and relation_embeddings.tsv files in the directory, a missing file is skipped. If drop is true, the existing embeddings of the model are replaced.
+pub async fn import_csv_files(
+    pool: &sqlx::PgPool,
+    dir: &Path,
+    drop: bool,
+    model: &EmbeddingModel,
+) -> Result<(), anyhow::Error> {
+    let entity_file = dir.join(ENTITY_EMBEDDING_FILE);
+    let relation_file = dir.join(RELATION_EMBEDDING_FILE);
+    if !entity_file.exists() && !relation_file.exists() {
+        return Err(anyhow::anyhow!(
+            "Neither {} nor {} is in {}.",
+            ENTITY_EMBEDDING_FILE,
+            RELATION_EMBEDDING_FILE,
+            dir.display()
+        ));
+    }
+
+    if entity_file.exists() {
+        info!(
+            "Importing the entity embeddings from {}...",
+            entity_file.display()
+        );
+        EntityEmbedding::import_entity_embeddings(pool, &entity_file, b'\t', drop, model)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to import the entity embeddings: {}", e))?;
+    }
+
+    if relation_file.exists() {
+        info!(
+            "Importing the relation embeddings from {}...",
+            relation_file.display()
+        );
+        RelationEmbedding::import_relation_embeddings(pool, &relation_file, b'\t', drop, model)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to import the relation embeddings: {}", e))?;
+    }
+
+    AnyOk(())
+}
+
+/// Import the embeddings of a model from the native outputs (see the module docs). The dataset_dir is only for the DGL-KE outputs, it is same with the input directory by default. The converted csv files are written into the output_dir if it is set, otherwise into a temporary directory which is deleted after import.
+pub async fn import_embeddings(
+    pool: &sqlx::PgPool,
+    format: EmbeddingFormat,
+    input: &Path,
+    dataset_dir: Option<&Path>,
+    output_dir: Option<&Path>,
+    drop: bool,
+    model: &EmbeddingModel,
+) -> Result<(), anyhow::Error> {
+    let native = match format {
+        EmbeddingFormat::Csv => return import_csv_files(pool, input, drop, model).await,
+        EmbeddingFormat::Dglke => {
+            NativeEmbeddings::read_dglke(input, dataset_dir.unwrap_or(input))?
+        }
+        EmbeddingFormat::Pykeen => NativeEmbeddings::read_pykeen(input)?,
+    };
+    info!(
+        "Read {} entity embeddings and {} relation embeddings from {}.",
+        native.entities.len(),
+        native.relations.len(),
+        input.display()
+    );
+
+    let temp_dir = tempfile::tempdir()?;
+    let output_dir = match output_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            dir
+        }
+        None => temp_dir.path(),
+    };
+
+    native.write_csv_files(pool, output_dir).await?;
+    import_csv_files(pool, output_dir, drop, model).await
+}
+
+/// The options of evaluating the embeddings of a model on the test split.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationOptions {
+    /// Skip the other true triples (in all splits) when ranking a test triple, which is the filtered setting of DGL-KE and PyKEEN.
+    pub filtered: bool,
+    /// Only rank the entities whose type is same with the true entity, such as the diseases for the tail of a `treats` triple.
+    pub type_constrained: bool,
+    /// Evaluate a random sample of the test triples, all test triples are evaluated if it is None.
+    pub max_triples: Option<usize>,
+    /// The seed of the random generator which samples the test triples.
+    pub seed: u64,
+}
+
+/// The ranking metrics of the test triples, both the head and the tail of each test triple are ranked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvaluationMetrics {
+    pub mrr: f64,
+    pub mr: f64,
+    #[serde(rename = "hits@1")]
+    pub hits_at_1: f64,
+    #[serde(rename = "hits@3")]
+    pub hits_at_3: f64,
+    #[serde(rename = "hits@10")]
+    pub hits_at_10: f64,
+    pub num_test_triples: usize,
+    /// The test triples whose entities or relation type don't have embeddings, they are not ranked.
+    pub num_skipped_triples: usize,
+    pub filtered: bool,
+    pub type_constrained: bool,
+}
+
+impl EvaluationMetrics {
+    /// Compute the metrics from the ranks of the heads and the tails, the ranks start from 1.
+    ///
+    /// ```
+    /// use biomedgps::model::kge::EvaluationMetrics;
+    ///
+    /// let metrics = EvaluationMetrics::from_ranks(&[1.0, 2.0, 4.0, 20.0], 0);
+    /// assert_eq!(metrics.num_test_triples, 2);
+    /// assert_eq!(metrics.hits_at_1, 0.25);
+    /// assert_eq!(metrics.hits_at_10, 0.75);
+    /// assert_eq!(metrics.mr, 6.75);
+    /// ```
+    pub fn from_ranks(ranks: &[f64], num_skipped_triples: usize) -> EvaluationMetrics {
+        let total = ranks.len().max(1) as f64;
+        let hits = |k: f64| ranks.iter().filter(|rank| **rank <= k).count() as f64 / total;
+
+        EvaluationMetrics {
+            mrr: ranks.iter().map(|rank| 1.0 / rank).sum::<f64>() / total,
+            mr: ranks.iter().sum::<f64>() / total,
+            hits_at_1: hits(1.0),
+            hits_at_3: hits(3.0),
+            hits_at_10: hits(10.0),
+            num_test_triples: ranks.len() / 2,
+            num_skipped_triples,
+            filtered: true,
+            type_constrained: false,
+        }
+    }
+
+    /// Merge the metrics into the existing metrics of the model (a json object), the other keys of the existing metrics are kept.
+    ///
+    /// ```
+    /// use biomedgps::model::kge::EvaluationMetrics;
+    ///
+    /// let metrics = EvaluationMetrics::from_ranks(&[1.0, 1.0], 0);
+    /// let merged = metrics.merge_into(Some(r#"{"loss": 0.1, "mrr": 0.2}"#)).unwrap();
+    /// let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+    /// assert_eq!(merged["loss"], 0.1);
+    /// assert_eq!(merged["mrr"], 1.0);
+    /// ```
+    pub fn merge_into(&self, existing: Option<&str>) -> Result<String, anyhow::Error> {
+        let mut merged = match existing {
+            Some(existing) => match serde_json::from_str::<serde_json::Value>(existing) {
+                Ok(serde_json::Value::Object(map)) => map,
+                _ => {
+                    warn!("The existing metrics are not a json object, they are replaced.");
+                    serde_json::Map::new()
+                }
+            },
+            None => serde_json::Map::new(),
+        };
+
+        if let serde_json::Value::Object(metrics) = serde_json::to_value(self)? {
+            merged.extend(metrics);
+        }
+
+        AnyOk(serde_json::to_string(&merged)?)
+    }
+}
+
+/// Compute the rank of the true entity from the scores of the other candidates, the ties are ranked in the middle, so a model which gives the same score to all entities is not rewarded.
+///
+/// ```
+/// use biomedgps::model::kge::compute_rank;
+///
+/// assert_eq!(compute_rank(0.5, vec![0.1, 0.2].into_iter()), 1.0);
+/// assert_eq!(compute_rank(0.5, vec![0.9, 0.5, 0.2].into_iter()), 2.5);
+/// ```
+pub fn compute_rank(true_score: f32, scores: impl Iterator<Item = f32>) -> f64 {
+    let (mut greater, mut equal) = (0, 0);
+    for score in scores {
+        if score > true_score {
+            greater += 1;
+        } else if score == true_score {
+            equal += 1;
+        }
+    }
+
+    1.0 + greater as f64 + equal as f64 / 2.0
+}
+
+/// Evaluate the embeddings of a model on the test split of the relations (see `biomedgps-cli splitdb`), the head and the tail of each test triple are replaced by the candidate entities and ranked by the score function. All embeddings of the model are loaded into memory, and each test triple scores all candidates twice, so it is better to set `max_triples` for a large graph.
+pub async fn evaluate_embeddings(
+    pool: &sqlx::PgPool,
+    model: &EmbeddingModel,
+    score_function: ScoreFunction,
+    options: &EvaluationOptions,
+) -> Result<EvaluationMetrics, anyhow::Error> {
+    let entity_rows: Vec<(String, Vector)> = sqlx::query_as(&format!(
+        "SELECT entity_type || '{}' || entity_id, embedding FROM biomedgps_entity_embedding
+         WHERE model_name = $1 ORDER BY embedding_id",
+        COMPOSED_ENTITY_DELIMITER
+    ))
+    .bind(&model.model_name)
+    .fetch_all(pool)
+    .await?;
+    let relation_rows: Vec<(String, Vector)> = sqlx::query_as(
+        "SELECT relation_type, embedding FROM biomedgps_relation_embedding WHERE model_name = $1",
+    )
+    .bind(&model.model_name)
+    .fetch_all(pool)
+    .await?;
+
+    if entity_rows.is_empty() || relation_rows.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The model {} doesn't have the entity or relation embeddings.",
+            model.model_name
+        ));
+    }
+
+    let node_ids = entity_rows
+        .iter()
+        .map(|(node_id, _)| node_id.clone())
+        .collect::<Vec<String>>();
+    let entities = entity_rows
+        .into_iter()
+        .map(|(_, embedding)| embedding.to_vec())
+        .collect::<Vec<Vec<f32>>>();
+    let relations = relation_rows
+        .into_iter()
+        .map(|(relation_type, embedding)| (relation_type, embedding.to_vec()))
+        .collect::<HashMap<String, Vec<f32>>>();
+    let relation_dimension = relations.values().next().map(|v| v.len()).unwrap_or(0);
+    score_function
+        .check_dimensions(entities[0].len(), relation_dimension)
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    let node_index = node_ids
+        .iter()
+        .enumerate()
+        .map(|(i, node_id)| (node_id.as_str(), i))
+        .collect::<HashMap<&str, usize>>();
+    let entity_type = |i: usize| {
+        node_ids[i]
+            .split(COMPOSED_ENTITY_DELIMITER)
+            .next()
+            .unwrap_or_default()
+    };
+    let all_candidates = (0..entities.len()).collect::<Vec<usize>>();
+    let mut typed_candidates: HashMap<&str, Vec<usize>> = HashMap::new();
+    for i in 0..entities.len() {
+        typed_candidates.entry(entity_type(i)).or_default().push(i);
+    }
+
+    // The other triples are only needed to filter the true entities in the filtered setting.
+    let sql_str = format!(
+        "SELECT source_type || '{0}' || source_id, relation_type, target_type || '{0}' || target_id, split
+         FROM biomedgps_relation {1} ORDER BY id",
+        COMPOSED_ENTITY_DELIMITER,
+        if options.filtered { "" } else { "WHERE split = 'test'" }
+    );
+    let triples: Vec<(String, String, String, Option<String>)> =
+        sqlx::query_as(&sql_str).fetch_all(pool).await?;
+
+    let mut test_triples: Vec<(usize, &str, usize)> = vec![];
+    let mut known_tails: HashMap<(usize, &str), HashSet<usize>> = HashMap::new();
+    let mut known_heads: HashMap<(&str, usize), HashSet<usize>> = HashMap::new();
+    let mut num_skipped = 0;
+    for (head, relation_type, tail, split) in triples.iter() {
+        let is_test = split.as_deref() == Some(RelationSplit::Test.as_str());
+        match (
+            node_index.get(head.as_str()),
+            relations.contains_key(relation_type),
+            node_index.get(tail.as_str()),
+        ) {
+            (Some(head), true, Some(tail)) => {
+                if options.filtered {
+                    let relation_type = relation_type.as_str();
+                    known_tails
+                        .entry((*head, relation_type))
+                        .or_default()
+                        .insert(*tail);
+                    known_heads
+                        .entry((relation_type, *tail))
+                        .or_default()
+                        .insert(*head);
+                }
+
+                if is_test {
+                    test_triples.push((*head, relation_type.as_str(), *tail));
+                }
+            }
+            _ => {
+                if is_test {
+                    num_skipped += 1;
+                }
+            }
+        }
+    }
+
+    if test_triples.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No test triple has the embeddings of the model {}, please split the relations by `biomedgps-cli splitdb` first.",
+            model.model_name
+        ));
+    }
+
+    if let Some(max_triples) = options.max_triples {
+        if test_triples.len() > max_triples {
+            test_triples.shuffle(&mut StdRng::seed_from_u64(options.seed));
+            test_triples.truncate(max_triples);
+        }
+    }
+    info!(
+        "Evaluating {} test triples with {} candidate entities ({} test triples are skipped).",
+        test_triples.len(),
+        entities.len(),
+        num_skipped
+    );
+
+    let no_entities = HashSet::new();
+    let mut ranks: Vec<f64> = Vec::with_capacity(test_triples.len() * 2);
+    for (i, (head, relation_type, tail)) in test_triples.iter().enumerate() {
+        let relation = &relations[*relation_type];
+        let true_score = score_function.score(&entities[*head], relation, &entities[*tail]);
+        let candidates = |entity: usize| {
+            if options.type_constrained {
+                typed_candidates
+                    .get(entity_type(entity))
+                    .unwrap_or(&all_candidates)
+            } else {
+                &all_candidates
+            }
+        };
+
+        let excluded = known_tails
+            .get(&(*head, *relation_type))
+            .unwrap_or(&no_entities);
+        ranks.push(compute_rank(
+            true_score,
+            candidates(*tail)
+                .iter()
+                .filter(|c| **c != *tail && !excluded.contains(*c))
+                .map(|c| score_function.score(&entities[*head], relation, &entities[*c])),
+        ));
+
+        let excluded = known_heads
+            .get(&(*relation_type, *tail))
+            .unwrap_or(&no_entities);
+        ranks.push(compute_rank(
+            true_score,
+            candidates(*head)
+                .iter()
+                .filter(|c| **c != *head && !excluded.contains(*c))
+                .map(|c| score_function.score(&entities[*c], relation, &entities[*tail])),
+        ));
+
+        if (i + 1) % 1000 == 0 {
+            debug!(
+                "Evaluated {} of {} test triples.",
+                i + 1,
+                test_triples.len()
+            );
+        }
+    }
+
+    AnyOk(EvaluationMetrics {
+        filtered: options.filtered,
+        type_constrained: options.type_constrained,
+        ..EvaluationMetrics::from_ranks(&ranks, num_skipped)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_npy(descr: &str, shape: &str, fortran_order: bool, body: &[u8]) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+            descr,
+            if fortran_order { "True" } else { "False" },
+            shape
+        );
+        // The header is padded with spaces and ends with a newline, so the data is aligned to 64 bytes.
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut bytes = NPY_MAGIC.to_vec();
+        bytes.extend([1, 0]);
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_npy() {
+        let values: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let body = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        let matrix = parse_npy(&make_npy("<f4", "(2, 3)", false, &body)).unwrap();
+        assert_eq!((matrix.rows, matrix.cols), (2, 3));
+        assert_eq!(matrix.row(1), &[4.0, 5.0, 6.0]);
+
+        // The values of a fortran-order array are stored column by column.
+        let matrix = parse_npy(&make_npy("<f4", "(3, 2)", true, &body)).unwrap();
+        assert_eq!(matrix.row(0), &[1.0, 4.0]);
+        assert_eq!(matrix.row(2), &[3.0, 6.0]);
+
+        let body = values
+            .iter()
+            .flat_map(|v| (*v as f64).to_le_bytes())
+            .collect::<Vec<u8>>();
+        let matrix = parse_npy(&make_npy("<c16", "(1, 3)", false, &body)).unwrap();
+        assert_eq!((matrix.rows, matrix.cols), (1, 6));
+        assert_eq!(matrix.row(0), &[1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+
+        assert!(parse_npy(&make_npy("<i8", "(2, 3)", false, &body)).is_err());
+        assert!(parse_npy(&make_npy("<f4", "(6,)", false, &body[..24])).is_err());
+        assert!(parse_npy(&make_npy("<f4", "(2, 4)", false, &body[..24])).is_err());
+        assert!(parse_npy(b"not a npy file").is_err());
+    }
+
+    #[test]
+    fn test_embedding_format() {
+        assert_eq!(
+            "DGLKE".parse::<EmbeddingFormat>(),
+            Ok(EmbeddingFormat::Dglke)
+        );
+        assert!("word2vec".parse::<EmbeddingFormat>().is_err());
+    }
+}
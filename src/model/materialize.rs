@@ -0,0 +1,235 @@
+//! Materialize module keeps the graphs of the expensive expansions (such as the neighborhoods of the popular diseases which are requested by every attendee of a workshop) in the biomedgps_materialized_graph table, so the same expansion is computed once per dataset version and reused by all server instances until it expires.
+//!
+//! A graph is keyed by the sha256 hash of the query key and the release tag of the active dataset version, so a new dataset version never reuses the graphs of the previous one. The graphs are kept for `materialization_ttl_hours` hours (default: 24, 0 turns the materialization off).
+//!
+//! The graphs are read from the pool of the request (which may be the read replica), and their hits are collected in the server and written to the primary database in batches (see [`record_hit`]).
+
+use crate::model::database::{current_database, DEFAULT_DATABASE};
+use crate::model::graph::Graph;
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The default number of hours to keep a materialized graph before it is computed again.
+pub const DEFAULT_MATERIALIZATION_TTL_HOURS: u64 = 24;
+
+// The number of hours to keep a materialized graph, 0 means the materialization is turned off.
+static MATERIALIZATION_TTL_HOURS: AtomicU64 = AtomicU64::new(DEFAULT_MATERIALIZATION_TTL_HOURS);
+
+/// Set the number of hours to keep a materialized graph. It should be called once when the server starts.
+pub fn set_materialization_ttl_hours(hours: u64) {
+    MATERIALIZATION_TTL_HOURS.store(hours, Ordering::Relaxed);
+}
+
+/// The time to keep a materialized graph, None means the materialization is turned off.
+pub fn materialization_ttl() -> Option<ChronoDuration> {
+    match MATERIALIZATION_TTL_HOURS.load(Ordering::Relaxed) {
+        0 => None,
+        hours => Some(ChronoDuration::hours(hours as i64)),
+    }
+}
+
+/// The seconds to collect the hits of the materialized graphs before they are written in one statement.
+const HIT_FLUSH_INTERVAL: u64 = 60;
+
+// The hit counts keyed by the query hash and the dataset version.
+type PendingHits = HashMap<(String, String), i64>;
+
+lazy_static! {
+    // The hits which are not written yet and when the first of them was collected, keyed by the database.
+    static ref PENDING_HITS: Mutex<HashMap<String, (Instant, PendingHits)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Count a hit of the materialized graph of the query key in the dataset version. The hits are collected in the server and written to the primary database in the background once they are older than a minute, so a hit doesn't write the database on the request path. The hit counts may lag behind until the next hit of the database.
+pub fn record_hit(primary_pool: Arc<sqlx::PgPool>, query_key: &str, dataset_version: Option<&str>) {
+    let database = current_database().unwrap_or_else(|| DEFAULT_DATABASE.to_string());
+    let hits = {
+        let mut pending = PENDING_HITS.lock().unwrap();
+        let (collected_at, hits) = pending
+            .entry(database.clone())
+            .or_insert_with(|| (Instant::now(), HashMap::new()));
+        *hits
+            .entry((
+                query_hash(query_key),
+                dataset_version.unwrap_or_default().to_string(),
+            ))
+            .or_insert(0) += 1;
+
+        if collected_at.elapsed() < Duration::from_secs(HIT_FLUSH_INTERVAL) {
+            return;
+        }
+
+        match pending.remove(&database) {
+            Some((_, hits)) => hits,
+            None => return,
+        }
+    };
+
+    tokio::spawn(async move {
+        match MaterializedGraph::add_hits(&primary_pool, hits).await {
+            Ok(updated) => debug!("The hits of {} materialized graphs are written.", updated),
+            Err(e) => warn!("Failed to write the hits of the materialized graphs: {}", e),
+        }
+    });
+}
+
+/// The sha256 hash of a query key, such as the cache key of an expansion.
+///
+/// # Example
+///
+/// ```
+/// use biomedgps::model::materialize::query_hash;
+///
+/// let hash = query_hash("one-step:0:50:{}");
+/// assert_eq!(hash.len(), 64);
+/// assert_eq!(hash, query_hash("one-step:0:50:{}"));
+/// assert_ne!(hash, query_hash("one-step:1:50:{}"));
+/// ```
+pub fn query_hash(query_key: &str) -> String {
+    format!("{:x}", Sha256::digest(query_key.as_bytes()))
+}
+
+/// A materialized graph, the payload is not listed because it may be large.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct MaterializedGraph {
+    pub id: i64,
+    pub query_hash: String,
+    /// The query key of the expansion, such as `one-step:0:50:{"operator": "=", ...}`.
+    pub query_key: String,
+    /// The release tag of the dataset version which the graph is computed from, it is empty if no dataset version is active.
+    pub dataset_version: String,
+    /// How many times the graph is reused, the recent hits are written in batches (see [`record_hit`]).
+    pub hit_count: i64,
+    #[serde(with = "ts_seconds")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The number of the materialized graphs which are invalidated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct MaterializationInvalidation {
+    pub deleted: u64,
+}
+
+impl MaterializedGraph {
+    /// Get the graph of the query key in the dataset version if it isn't expired. It only reads the database, so the pool may be the read replica, the hit is counted by [`record_hit`].
+    pub async fn get_graph(
+        pool: &sqlx::PgPool,
+        query_key: &str,
+        dataset_version: Option<&str>,
+    ) -> Result<Option<Graph>, anyhow::Error> {
+        let payload: Option<serde_json::Value> = sqlx::query_scalar(
+            "SELECT payload FROM biomedgps_materialized_graph
+             WHERE query_hash = $1 AND dataset_version = $2 AND expires_at > now()",
+        )
+        .bind(query_hash(query_key))
+        .bind(dataset_version.unwrap_or_default())
+        .fetch_optional(pool)
+        .await?;
+
+        match payload {
+            Some(payload) => AnyOk(Some(serde_json::from_value::<Graph>(payload)?)),
+            None => AnyOk(None),
+        }
+    }
+
+    /// Keep the graph of the query key in the dataset version for the ttl, it replaces the previous graph.
+    pub async fn save_graph(
+        pool: &sqlx::PgPool,
+        query_key: &str,
+        dataset_version: Option<&str>,
+        graph: &Graph,
+        ttl: ChronoDuration,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO biomedgps_materialized_graph (query_hash, query_key, dataset_version, payload, hit_count, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, 0, now(), $5)
+             ON CONFLICT (query_hash, dataset_version) DO UPDATE
+             SET payload = EXCLUDED.payload, hit_count = 0, created_at = EXCLUDED.created_at, expires_at = EXCLUDED.expires_at",
+        )
+        .bind(query_hash(query_key))
+        .bind(query_key)
+        .bind(dataset_version.unwrap_or_default())
+        .bind(serde_json::to_value(graph)?)
+        .bind(Utc::now() + ttl)
+        .execute(pool)
+        .await?;
+
+        AnyOk(())
+    }
+
+    /// Add the collected hits (keyed by the query hash and the dataset version) to the hit counts in one statement, it returns the number of the updated graphs.
+    async fn add_hits(pool: &sqlx::PgPool, hits: PendingHits) -> Result<u64, anyhow::Error> {
+        let mut query_hashes = vec![];
+        let mut dataset_versions = vec![];
+        let mut counts = vec![];
+        for ((query_hash, dataset_version), count) in hits {
+            query_hashes.push(query_hash);
+            dataset_versions.push(dataset_version);
+            counts.push(count);
+        }
+
+        let result = sqlx::query(
+            "UPDATE biomedgps_materialized_graph AS g SET hit_count = g.hit_count + h.hits
+             FROM UNNEST($1::TEXT[], $2::TEXT[], $3::BIGINT[]) AS h(query_hash, dataset_version, hits)
+             WHERE g.query_hash = h.query_hash AND g.dataset_version = h.dataset_version",
+        )
+        .bind(query_hashes)
+        .bind(dataset_versions)
+        .bind(counts)
+        .execute(pool)
+        .await?;
+
+        AnyOk(result.rows_affected())
+    }
+
+    /// List the materialized graphs, the most reused ones go first.
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<MaterializedGraph>, anyhow::Error> {
+        let records = sqlx::query_as::<_, MaterializedGraph>(
+            "SELECT id, query_hash, query_key, dataset_version, hit_count, created_at, expires_at
+             FROM biomedgps_materialized_graph ORDER BY hit_count DESC, id",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    /// Delete the materialized graphs of the query hash and the dataset version, all of them are deleted if both are None. The expired graphs are always deleted.
+    pub async fn delete(
+        pool: &sqlx::PgPool,
+        query_hash: Option<&str>,
+        dataset_version: Option<&str>,
+    ) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query(
+            "DELETE FROM biomedgps_materialized_graph
+             WHERE expires_at <= now()
+                OR (($1::TEXT IS NULL OR query_hash = $1) AND ($2::TEXT IS NULL OR dataset_version = $2))",
+        )
+        .bind(query_hash)
+        .bind(dataset_version)
+        .execute(pool)
+        .await?;
+
+        AnyOk(result.rows_affected())
+    }
+}
+
+/// Delete all materialized graphs after the data which they are computed from is changed, such as a curation or an import. The failure is only logged, because the graphs expire anyway.
+pub async fn invalidate_materialized_graphs(pool: &sqlx::PgPool) {
+    match MaterializedGraph::delete(pool, None, None).await {
+        Ok(deleted) => debug!("{} materialized graphs are invalidated.", deleted),
+        Err(e) => warn!("Failed to invalidate the materialized graphs: {}", e),
+    }
+}
@@ -2,4 +2,36 @@
 
 pub mod core;
 pub mod util;
-pub mod graph;
\ No newline at end of file
+pub mod graph;
+pub mod neo4j;
+pub mod publication;
+pub mod embedding;
+pub mod remote;
+pub mod snapshot;
+pub mod dedup;
+pub mod cache;
+pub mod workspace;
+pub mod notification;
+pub mod split;
+pub mod kge;
+pub mod path;
+pub mod vocabulary;
+pub mod biolink;
+pub mod rdf;
+pub mod ontology;
+pub mod enrichment;
+pub mod collab;
+pub mod theme;
+pub mod translation;
+pub mod pgml;
+pub mod storage;
+pub mod scheduler;
+pub mod flag;
+pub mod idmapping;
+pub mod annotation;
+pub mod structure;
+pub mod gene;
+pub mod qc;
+pub mod relation_type;
+pub mod database;
+pub mod materialize;
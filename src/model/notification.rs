@@ -0,0 +1,559 @@
+//! Notification module is used to tell the users and the external pipelines about the data events, such as a new curated knowledge, a finished import or a subgraph which is shared with a workspace.
+//!
+//! Each event is saved in the biomedgps_notification table as the in-app notification feed, and it is sent to the registered webhooks as a POST request. The request body is signed with the secret of the webhook (HMAC-SHA256), the signature is in the `X-Biomedgps-Signature` header as `sha256=<hex>`. A failed delivery is retried with an exponential backoff.
+
+use crate::model::core::ValidationError;
+use crate::model::workspace::WorkspaceMember;
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use validator::Validate;
+
+/// The max number of the attempts to deliver an event to a webhook.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+const DELIVERY_TIMEOUT: u64 = 10;
+
+/// The actor of the events which are triggered by `biomedgps-cli`, such as the imports.
+pub const CLI_ACTOR: &str = "biomedgps-cli";
+/// The default number of the notifications in a page of the feed.
+pub const DEFAULT_FEED_SIZE: u64 = 50;
+
+/// Subscribe to all event types.
+pub const ALL_EVENT_TYPES: &str = "*";
+
+pub const SIGNATURE_HEADER: &str = "X-Biomedgps-Signature";
+pub const EVENT_HEADER: &str = "X-Biomedgps-Event";
+pub const DELIVERY_HEADER: &str = "X-Biomedgps-Delivery";
+
+/// The type of a data event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    CurationCreated,
+    CurationUpdated,
+    CurationDeleted,
+    /// A deleted curated knowledge is restored before it is purged.
+    CurationRestored,
+    ImportCompleted,
+    ImportFailed,
+    /// A subgraph is saved into a workspace.
+    SubgraphShared,
+}
+
+impl EventType {
+    pub fn all() -> Vec<EventType> {
+        vec![
+            EventType::CurationCreated,
+            EventType::CurationUpdated,
+            EventType::CurationDeleted,
+            EventType::CurationRestored,
+            EventType::ImportCompleted,
+            EventType::ImportFailed,
+            EventType::SubgraphShared,
+        ]
+    }
+
+    /// Parse the name of the event type, it returns None if the event type is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use biomedgps::model::notification::EventType;
+    ///
+    /// assert_eq!(EventType::from_name("import.completed"), Some(EventType::ImportCompleted));
+    /// assert_eq!(EventType::from_name("import.started"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<EventType> {
+        EventType::all()
+            .into_iter()
+            .find(|event_type| event_type.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventType::CurationCreated => "curation.created",
+            EventType::CurationUpdated => "curation.updated",
+            EventType::CurationDeleted => "curation.deleted",
+            EventType::CurationRestored => "curation.restored",
+            EventType::ImportCompleted => "import.completed",
+            EventType::ImportFailed => "import.failed",
+            EventType::SubgraphShared => "subgraph.shared",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct Webhook {
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    /// The http(s) url which receives the events.
+    #[validate(length(
+        min = 1,
+        max = 1024,
+        message = "The length of url should be between 1 and 1024."
+    ))]
+    pub url: String,
+
+    /// The secret to sign the request body, it is never returned.
+    #[validate(length(
+        min = 16,
+        max = 255,
+        message = "The length of secret should be between 16 and 255."
+    ))]
+    #[oai(write_only)]
+    pub secret: String,
+
+    /// The subscribed event types, such as `curation.created` and `import.completed`, or `*` for all events.
+    #[validate(length(min = 1, message = "At least one event type is required."))]
+    pub event_types: Vec<String>,
+
+    #[serde(default)]
+    #[oai(default)]
+    pub owner: String,
+
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub active: bool,
+
+    #[serde(skip_deserializing)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub last_status_code: Option<i32>,
+
+    #[serde(skip_deserializing)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub last_error: Option<String>,
+
+    #[serde(skip_deserializing)]
+    #[oai(read_only, skip_serializing_if_is_none)]
+    pub last_delivered_at: Option<DateTime<Utc>>,
+
+    #[serde(skip_deserializing)]
+    #[serde(with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn update_owner(&mut self, owner: String) -> &Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Check the url and the event types, the fields are also checked by `validate`.
+    pub fn check(&self) -> Result<(), ValidationError> {
+        match url::Url::parse(&self.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => {
+                return Err(ValidationError::new(&format!(
+                    "Invalid url: {}, it should be a http(s) url.",
+                    self.url
+                )))
+            }
+        };
+
+        for event_type in self.event_types.iter() {
+            if event_type != ALL_EVENT_TYPES && EventType::from_name(event_type).is_none() {
+                return Err(ValidationError::new(&format!(
+                    "Unknown event type: {}, it should be one of {} or {}.",
+                    event_type,
+                    EventType::all()
+                        .iter()
+                        .map(|event_type| event_type.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", "),
+                    ALL_EVENT_TYPES
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_subscribed(&self, event_type: EventType) -> bool {
+        self.event_types
+            .iter()
+            .any(|name| name == ALL_EVENT_TYPES || name == event_type.as_str())
+    }
+
+    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<Webhook, anyhow::Error> {
+        let record = sqlx::query_as::<_, Webhook>(
+            "INSERT INTO biomedgps_webhook (url, secret, event_types, owner) VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(&self.url)
+        .bind(&self.secret)
+        .bind(&self.event_types)
+        .bind(&self.owner)
+        .fetch_one(pool)
+        .await?;
+
+        AnyOk(record)
+    }
+
+    pub async fn get_records(pool: &sqlx::PgPool) -> Result<Vec<Webhook>, anyhow::Error> {
+        let records =
+            sqlx::query_as::<_, Webhook>("SELECT * FROM biomedgps_webhook ORDER BY id ASC")
+                .fetch_all(pool)
+                .await?;
+
+        AnyOk(records)
+    }
+
+    /// Fetch the active webhooks which subscribe to the event type.
+    pub async fn get_subscribers(
+        pool: &sqlx::PgPool,
+        event_type: EventType,
+    ) -> Result<Vec<Webhook>, anyhow::Error> {
+        let records = sqlx::query_as::<_, Webhook>(
+            "SELECT * FROM biomedgps_webhook WHERE active = TRUE AND ($1 = ANY(event_types) OR $2 = ANY(event_types))",
+        )
+        .bind(event_type.as_str())
+        .bind(ALL_EVENT_TYPES)
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    /// Delete a webhook by id, return None if it doesn't exist.
+    pub async fn delete(pool: &sqlx::PgPool, id: i64) -> Result<Option<Webhook>, anyhow::Error> {
+        let record =
+            sqlx::query_as::<_, Webhook>("DELETE FROM biomedgps_webhook WHERE id = $1 RETURNING *")
+                .bind(id)
+                .fetch_optional(pool)
+                .await?;
+
+        AnyOk(record)
+    }
+
+    async fn record_delivery(
+        &self,
+        pool: &sqlx::PgPool,
+        status_code: Option<i32>,
+        error: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE biomedgps_webhook SET last_status_code = $2, last_error = $3, last_delivered_at = now() WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(status_code)
+        .bind(error)
+        .execute(pool)
+        .await?;
+
+        AnyOk(())
+    }
+
+    /// Send the event to the webhook, the request is retried at most `MAX_DELIVERY_ATTEMPTS` times when it fails without a response or with a 429/5xx response. The result of the last attempt is saved on the webhook.
+    pub async fn deliver(
+        &self,
+        pool: &sqlx::PgPool,
+        client: &reqwest::Client,
+        event_type: EventType,
+        delivery_id: &str,
+        body: &str,
+    ) -> Result<(), anyhow::Error> {
+        let signature = sign_payload(&self.secret, body);
+        let mut attempt = 0;
+        let (status_code, error) = loop {
+            attempt += 1;
+            let response = client
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, event_type.as_str())
+                .header(DELIVERY_HEADER, delivery_id)
+                .body(body.to_string())
+                .send()
+                .await;
+
+            let (status_code, error, retryable) = match response {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16() as i32), None, false)
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    (
+                        Some(status.as_u16() as i32),
+                        Some(format!("The webhook responded with {}.", status)),
+                        status.is_server_error() || status.as_u16() == 429,
+                    )
+                }
+                Err(e) => (None, Some(e.to_string()), true),
+            };
+
+            if !retryable || attempt >= MAX_DELIVERY_ATTEMPTS {
+                break (status_code, error);
+            }
+
+            warn!(
+                "Failed to deliver the event {} to the webhook {} (attempt {}): {}",
+                delivery_id,
+                self.id,
+                attempt,
+                error.as_deref().unwrap_or_default()
+            );
+            tokio::time::sleep(Duration::from_secs(2u64.pow(attempt - 1))).await;
+        };
+
+        self.record_delivery(pool, status_code, error.clone())
+            .await?;
+        match error {
+            Some(error) => Err(anyhow::anyhow!(error)),
+            None => AnyOk(()),
+        }
+    }
+}
+
+/// Sign the request body with the secret of the webhook, the receiver can verify the request by computing the same signature.
+///
+/// ```
+/// use biomedgps::model::notification::sign_payload;
+///
+/// let signature = sign_payload("It's a Secret to Everybody", "Hello, World!");
+/// assert_eq!(
+///     signature,
+///     "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+/// );
+/// ```
+pub fn sign_payload(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// An event in the notification feed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct Notification {
+    pub id: i64,
+    /// The event type, such as `curation.created`.
+    pub event_type: String,
+    /// The user who triggered the event.
+    pub actor: String,
+    /// The recipient of the notification, it is visible to all users if it is empty.
+    #[oai(skip_serializing_if_is_none)]
+    pub username: Option<String>,
+    #[oai(skip_serializing_if_is_none)]
+    pub workspace_id: Option<i64>,
+    /// The data of the event, such as the curated knowledge or the import job.
+    pub payload: serde_json::Value,
+
+    #[serde(with = "ts_seconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    /// Fetch the notifications of the user (including the ones for all users), the latest one comes first. Use `since_id` to fetch only the new notifications.
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        username: &str,
+        since_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<Notification>, anyhow::Error> {
+        let records = sqlx::query_as::<_, Notification>(
+            "SELECT * FROM biomedgps_notification
+             WHERE (username = $1 OR username IS NULL) AND id > $2
+             ORDER BY id DESC LIMIT $3",
+        )
+        .bind(username)
+        .bind(since_id.unwrap_or(0))
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    /// Save the event for the recipients, or for all users if there is no recipient.
+    async fn insert(
+        pool: &sqlx::PgPool,
+        event_type: EventType,
+        actor: &str,
+        recipients: &[String],
+        workspace_id: Option<i64>,
+        payload: &serde_json::Value,
+    ) -> Result<Vec<Notification>, anyhow::Error> {
+        let recipients: Vec<Option<&str>> = if recipients.is_empty() {
+            vec![None]
+        } else {
+            recipients.iter().map(|r| Some(r.as_str())).collect()
+        };
+
+        let mut tx = pool.begin().await?;
+        let mut records = vec![];
+        for recipient in recipients {
+            let record = sqlx::query_as::<_, Notification>(
+                "INSERT INTO biomedgps_notification (event_type, actor, username, workspace_id, payload) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+            )
+            .bind(event_type.as_str())
+            .bind(actor)
+            .bind(recipient)
+            .bind(workspace_id)
+            .bind(payload)
+            .fetch_one(&mut tx)
+            .await?;
+            records.push(record);
+        }
+        tx.commit().await?;
+
+        AnyOk(records)
+    }
+}
+
+/// Save the event in the notification feed and send it to the subscribed webhooks. The events of a workspace are only visible to the other members of the workspace, the other events are visible to all users.
+///
+/// It waits until all deliveries are finished, use [`spawn_event`] in the request handlers. The errors are logged and ignored, because the data has been changed anyway.
+pub async fn publish_event(
+    pool: &sqlx::PgPool,
+    event_type: EventType,
+    actor: &str,
+    workspace_id: Option<i64>,
+    data: serde_json::Value,
+) {
+    let recipients = match workspace_id {
+        Some(workspace_id) => match WorkspaceMember::get_records(pool, workspace_id).await {
+            Ok(members) => members
+                .into_iter()
+                .map(|member| member.username)
+                .filter(|username| username != actor)
+                .collect::<Vec<String>>(),
+            Err(e) => {
+                warn!(
+                    "Failed to fetch the members of the workspace {}: {}",
+                    workspace_id, e
+                );
+                vec![]
+            }
+        },
+        None => vec![],
+    };
+
+    // Nobody else is in the workspace, so there is nothing to show in the feed.
+    if workspace_id.is_none() || !recipients.is_empty() {
+        if let Err(e) =
+            Notification::insert(pool, event_type, actor, &recipients, workspace_id, &data).await
+        {
+            warn!("Failed to save the {} event: {}", event_type.as_str(), e);
+        }
+    }
+
+    let webhooks = match Webhook::get_subscribers(pool, event_type).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            warn!("Failed to fetch the webhooks: {}", e);
+            return;
+        }
+    };
+
+    if webhooks.is_empty() {
+        debug!(
+            "No webhook subscribes to the {} event.",
+            event_type.as_str()
+        );
+        return;
+    }
+
+    // The receivers can use the delivery id to skip the retried deliveries which they have handled.
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let body = serde_json::json!({
+        "id": delivery_id,
+        "event": event_type.as_str(),
+        "actor": actor,
+        "workspace_id": workspace_id,
+        "created_at": Utc::now().timestamp(),
+        "data": data,
+    })
+    .to_string();
+
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(DELIVERY_TIMEOUT))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to build the http client for the webhooks: {}", e);
+            return;
+        }
+    };
+
+    let deliveries = webhooks
+        .iter()
+        .map(|webhook| webhook.deliver(pool, &client, event_type, &delivery_id, &body));
+    for (webhook, result) in webhooks
+        .iter()
+        .zip(futures::future::join_all(deliveries).await)
+    {
+        match result {
+            Ok(_) => info!(
+                "Delivered the {} event {} to the webhook {}.",
+                event_type.as_str(),
+                delivery_id,
+                webhook.id
+            ),
+            Err(e) => warn!(
+                "Failed to deliver the {} event {} to the webhook {}: {}",
+                event_type.as_str(),
+                delivery_id,
+                webhook.id,
+                e
+            ),
+        }
+    }
+}
+
+/// Publish the event in the background, see [`publish_event`].
+pub fn spawn_event(
+    pool: Arc<sqlx::PgPool>,
+    event_type: EventType,
+    actor: &str,
+    workspace_id: Option<i64>,
+    data: serde_json::Value,
+) {
+    let actor = actor.to_string();
+    tokio::spawn(async move {
+        publish_event(&pool, event_type, &actor, workspace_id, data).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_check() {
+        let mut webhook = Webhook {
+            id: 0,
+            url: "https://example.com/hooks/biomedgps".to_string(),
+            secret: "a-secret-of-the-webhook".to_string(),
+            event_types: vec!["curation.created".to_string(), "import.failed".to_string()],
+            owner: "admin".to_string(),
+            active: true,
+            last_status_code: None,
+            last_error: None,
+            last_delivered_at: None,
+            created_at: Utc::now(),
+        };
+        assert!(webhook.check().is_ok());
+        assert!(webhook.is_subscribed(EventType::ImportFailed));
+        assert!(!webhook.is_subscribed(EventType::ImportCompleted));
+
+        webhook.event_types = vec![ALL_EVENT_TYPES.to_string()];
+        assert!(webhook.check().is_ok());
+        assert!(webhook.is_subscribed(EventType::SubgraphShared));
+
+        webhook.event_types = vec!["curation.approved".to_string()];
+        assert!(webhook.check().is_err());
+
+        webhook.event_types = vec![ALL_EVENT_TYPES.to_string()];
+        webhook.url = "ftp://example.com".to_string();
+        assert!(webhook.check().is_err());
+    }
+}
@@ -0,0 +1,686 @@
+//! Ontology module is used to import the ontologies (such as the Disease Ontology and the Human Phenotype Ontology) in the [OBO graphs](https://github.com/geneontology/obographs) JSON format, and to query the is_a hierarchies of the imported terms.
+//!
+//! The terms are imported as entities and the subclass edges are imported as relations whose relation key is `is_a` (such as `DOID::is_a::Disease:Disease`), the source is the child term and the target is the parent term. The ontology is converted into the entity.tsv and relation.tsv files first, and then they are imported by the same way as `biomedgps-cli importdb`, so the files are validated and the imports can be resumed.
+//!
+//! The ancestors and descendants are found by the recursive queries on the is_a relations, they are used to roll up the query results to the broader terms, such as counting the diseases of a drug by their top-level categories. The descendants are also used to expand the queries on a parent term, so the relations of its subclasses are matched too.
+
+use crate::model::core::{ValidationError, ENTITY_ID_REGEX};
+use crate::model::util::with_query_timeout;
+use crate::query_builder::sql_builder::{ComposeQuery, ComposeQueryItem, QueryItem, Value};
+use anyhow::Ok as AnyOk;
+use log::debug;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The relation key of the subclass relations, see [`is_a_relation_type`].
+pub const IS_A: &str = "is_a";
+/// The default max depth of the ancestors and descendants.
+pub const DEFAULT_ONTOLOGY_DEPTH: usize = 10;
+/// The max depth of the ancestors and descendants, the deepest terms of the common ontologies are less than 20 levels deep.
+pub const MAX_ONTOLOGY_DEPTH: usize = 30;
+/// The max number of the entities which are rolled up in one request.
+pub const MAX_ROLLUP_IDS: usize = 1000;
+/// The default number of the terms which the entities are rolled up to.
+pub const DEFAULT_ROLLUP_LIMIT: usize = 100;
+/// The max number of the entity ids after a query is expanded by the descendants, use a smaller depth if it is exceeded.
+pub const MAX_EXPANDED_IDS: usize = 10000;
+/// The fields of a query which hold an entity id, they are expanded by [`expand_descendants`].
+pub const ENTITY_ID_FIELDS: [&str; 3] = ["id", "source_id", "target_id"];
+/// The entity file which is converted from an ontology.
+pub const ONTOLOGY_ENTITY_FILE: &str = "entity.tsv";
+/// The relation file which is converted from an ontology.
+pub const ONTOLOGY_RELATION_FILE: &str = "relation.tsv";
+
+// The predicates of the subclass edges, the obographs use is_a for rdfs:subClassOf.
+const SUBCLASS_PREDICATES: [&str; 3] = [
+    "is_a",
+    "rdfs:subClassOf",
+    "http://www.w3.org/2000/01/rdf-schema#subClassOf",
+];
+// The separator of the synonyms and xrefs of an entity.
+const LIST_SEPARATOR: &str = "|";
+
+#[derive(Debug, Deserialize)]
+struct ObographDocument {
+    #[serde(default)]
+    graphs: Vec<Obograph>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Obograph {
+    #[serde(default)]
+    nodes: Vec<ObographNode>,
+    #[serde(default)]
+    edges: Vec<ObographEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObographNode {
+    id: String,
+    lbl: Option<String>,
+    #[serde(rename = "type")]
+    node_type: Option<String>,
+    meta: Option<ObographMeta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ObographMeta {
+    definition: Option<ObographValue>,
+    #[serde(default)]
+    synonyms: Vec<ObographValue>,
+    #[serde(default)]
+    xrefs: Vec<ObographValue>,
+    #[serde(default)]
+    deprecated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObographValue {
+    val: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObographEdge {
+    sub: String,
+    pred: String,
+    obj: String,
+}
+
+/// A term of an ontology, the id is a CURIE such as `DOID:9352`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OntologyTerm {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub synonyms: Vec<String>,
+    pub xrefs: Vec<String>,
+}
+
+/// The terms and the is_a edges (child, parent) of an ontology.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Ontology {
+    pub terms: Vec<OntologyTerm>,
+    pub is_a: Vec<(String, String)>,
+}
+
+/// Convert an IRI of an OBO term to a CURIE, such as `http://purl.obolibrary.org/obo/DOID_9352` to `DOID:9352`. The CURIEs are kept, and it returns None if the result is not a valid entity id.
+///
+/// ```
+/// use biomedgps::model::ontology::iri_to_curie;
+///
+/// assert_eq!(iri_to_curie("http://purl.obolibrary.org/obo/DOID_9352"), Some("DOID:9352".to_string()));
+/// assert_eq!(iri_to_curie("http://www.orpha.net/ORDO/Orphanet_558"), Some("Orphanet:558".to_string()));
+/// assert_eq!(iri_to_curie("HP:0000118"), Some("HP:0000118".to_string()));
+/// assert_eq!(iri_to_curie("http://purl.obolibrary.org/obo/doid#has_symptom"), None);
+/// ```
+pub fn iri_to_curie(iri: &str) -> Option<String> {
+    let curie = if iri.starts_with("http://") || iri.starts_with("https://") {
+        let local = iri.rsplit(['/', '#']).next().unwrap_or_default();
+        let (prefix, id) = local.split_once('_')?;
+        format!("{}:{}", prefix, id)
+    } else {
+        iri.to_string()
+    };
+
+    if ENTITY_ID_REGEX.is_match(&curie) {
+        Some(curie)
+    } else {
+        None
+    }
+}
+
+/// The relation type of the is_a relations of an ontology, such as `DOID::is_a::Disease:Disease`.
+pub fn is_a_relation_type(resource: &str, label: &str) -> String {
+    format!("{}::{}::{}:{}", resource, IS_A, label, label)
+}
+
+impl Ontology {
+    /// Read an ontology from an obographs JSON file, the terms of all graphs are merged. The deprecated terms, the properties and the terms whose id cannot be converted to a CURIE are skipped, and only the terms with the prefix are kept if it is specified (the ontologies often import the terms of the other ontologies). The is_a edges are kept if both ends are kept.
+    pub fn read_obographs(filepath: &Path, prefix: Option<&str>) -> Result<Self, anyhow::Error> {
+        let reader = std::io::BufReader::new(std::fs::File::open(filepath)?);
+        let document: ObographDocument = serde_json::from_reader(reader)?;
+        AnyOk(Ontology::from_obographs(document, prefix))
+    }
+
+    /// Parse an ontology from an obographs JSON string, see [`read_obographs`](#method.read_obographs).
+    ///
+    /// ```
+    /// use biomedgps::model::ontology::Ontology;
+    ///
+    /// let json = r#"{"graphs": [{
+    ///     "nodes": [
+    ///         {"id": "http://purl.obolibrary.org/obo/DOID_4", "lbl": "disease", "type": "CLASS"},
+    ///         {"id": "http://purl.obolibrary.org/obo/DOID_162", "lbl": "cancer", "type": "CLASS"}
+    ///     ],
+    ///     "edges": [{"sub": "http://purl.obolibrary.org/obo/DOID_162", "pred": "is_a", "obj": "http://purl.obolibrary.org/obo/DOID_4"}]
+    /// }]}"#;
+    /// let ontology = Ontology::parse_obographs(json, None).unwrap();
+    /// assert_eq!(ontology.terms.len(), 2);
+    /// assert_eq!(ontology.is_a, vec![("DOID:162".to_string(), "DOID:4".to_string())]);
+    /// ```
+    pub fn parse_obographs(json: &str, prefix: Option<&str>) -> Result<Self, anyhow::Error> {
+        let document: ObographDocument = serde_json::from_str(json)?;
+        AnyOk(Ontology::from_obographs(document, prefix))
+    }
+
+    fn from_obographs(document: ObographDocument, prefix: Option<&str>) -> Self {
+        let mut terms: Vec<OntologyTerm> = vec![];
+        let mut term_ids: HashSet<String> = HashSet::new();
+        let mut is_a: Vec<(String, String)> = vec![];
+        let mut edges: HashSet<(String, String)> = HashSet::new();
+
+        let has_prefix = |curie: &str| match prefix {
+            Some(prefix) => curie.split(':').next() == Some(prefix),
+            None => true,
+        };
+
+        for graph in document.graphs.iter() {
+            for node in graph.nodes.iter() {
+                if node.node_type.as_deref().unwrap_or("CLASS") != "CLASS" {
+                    continue;
+                }
+
+                let id = match iri_to_curie(&node.id) {
+                    Some(id) if has_prefix(&id) => id,
+                    _ => continue,
+                };
+
+                let default_meta = ObographMeta::default();
+                let meta = node.meta.as_ref().unwrap_or(&default_meta);
+                if meta.deprecated || term_ids.contains(&id) {
+                    continue;
+                }
+
+                let mut synonyms = meta
+                    .synonyms
+                    .iter()
+                    .map(|s| s.val.clone())
+                    .collect::<Vec<String>>();
+                synonyms.dedup();
+
+                term_ids.insert(id.clone());
+                terms.push(OntologyTerm {
+                    name: node.lbl.clone().unwrap_or_else(|| id.clone()),
+                    description: meta.definition.as_ref().map(|d| d.val.clone()),
+                    synonyms,
+                    xrefs: meta.xrefs.iter().map(|x| x.val.clone()).collect(),
+                    id,
+                });
+            }
+        }
+
+        for graph in document.graphs.iter() {
+            for edge in graph.edges.iter() {
+                if !SUBCLASS_PREDICATES.contains(&edge.pred.as_str()) {
+                    continue;
+                }
+
+                if let (Some(child), Some(parent)) =
+                    (iri_to_curie(&edge.sub), iri_to_curie(&edge.obj))
+                {
+                    if child != parent
+                        && term_ids.contains(&child)
+                        && term_ids.contains(&parent)
+                        && edges.insert((child.clone(), parent.clone()))
+                    {
+                        is_a.push((child, parent));
+                    }
+                }
+            }
+        }
+
+        Ontology { terms, is_a }
+    }
+
+    /// Write the terms and the is_a edges into the entity.tsv and relation.tsv files in the directory, they can be imported by `biomedgps-cli importdb`. The list values (synonyms and xrefs) are joined by `|`.
+    pub fn write_files(
+        &self,
+        dir: &Path,
+        label: &str,
+        resource: &str,
+    ) -> Result<(PathBuf, PathBuf), anyhow::Error> {
+        let entity_file = dir.join(ONTOLOGY_ENTITY_FILE);
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_path(&entity_file)?;
+        writer.write_record([
+            "id",
+            "name",
+            "label",
+            "resource",
+            "description",
+            "synonyms",
+            "xrefs",
+        ])?;
+        for term in self.terms.iter() {
+            writer.write_record([
+                term.id.as_str(),
+                term.name.as_str(),
+                label,
+                resource,
+                term.description.as_deref().unwrap_or_default(),
+                term.synonyms.join(LIST_SEPARATOR).as_str(),
+                term.xrefs.join(LIST_SEPARATOR).as_str(),
+            ])?;
+        }
+        writer.flush()?;
+
+        let relation_file = dir.join(ONTOLOGY_RELATION_FILE);
+        let relation_type = is_a_relation_type(resource, label);
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_path(&relation_file)?;
+        writer.write_record([
+            "relation_type",
+            "source_id",
+            "source_type",
+            "target_id",
+            "target_type",
+            "resource",
+        ])?;
+        for (child, parent) in self.is_a.iter() {
+            writer.write_record([
+                relation_type.as_str(),
+                child.as_str(),
+                label,
+                parent.as_str(),
+                label,
+                resource,
+            ])?;
+        }
+        writer.flush()?;
+
+        debug!(
+            "Wrote {} terms into {} and {} is_a edges into {}.",
+            self.terms.len(),
+            entity_file.display(),
+            self.is_a.len(),
+            relation_file.display()
+        );
+        AnyOk((entity_file, relation_file))
+    }
+
+    /// The most common prefix of the terms, it is used as the resource of the imported entities if the resource is not specified.
+    pub fn main_prefix(&self) -> Option<String> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for term in self.terms.iter() {
+            let prefix = term.id.split(':').next().unwrap_or_default();
+            *counts.entry(prefix).or_insert(0) += 1;
+        }
+
+        counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+            .map(|(prefix, _)| prefix.to_string())
+    }
+}
+
+/// An ancestor or a descendant of a term, the depth is the number of the is_a edges between them (the shortest one if there are several paths).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct OntologyNode {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+    pub depth: i32,
+}
+
+/// A term which the entities are rolled up to, the members are the entities which are the term itself or its descendants.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct OntologyRollup {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+    pub count: i64,
+    pub member_ids: Vec<String>,
+}
+
+// Walk the is_a relations from the entities, upward from the child to the parent or downward from the parent to the child. The lineage contains the start entities with the depth 0, and the relations of all labels are walked if the label ($1) is null.
+fn lineage_query(upward: bool) -> String {
+    let (from, to) = if upward {
+        ("source", "target")
+    } else {
+        ("target", "source")
+    };
+
+    format!(
+        "WITH RECURSIVE lineage(member_id, id, depth) AS (
+             SELECT m, m, 0 FROM UNNEST($2::TEXT[]) AS m
+             UNION
+             SELECT l.member_id, r.{to}_id, l.depth + 1
+             FROM lineage l
+             JOIN biomedgps_relation r ON r.{from}_id = l.id
+                 AND ($1::TEXT IS NULL OR (r.{from}_type = $1 AND r.{to}_type = $1))
+                 AND split_part(r.relation_type, '::', 2) = '{is_a}'
+             WHERE l.depth < $3
+         )",
+        from = from,
+        to = to,
+        is_a = IS_A
+    )
+}
+
+fn check_depth(max_depth: usize) -> Result<(), anyhow::Error> {
+    if max_depth == 0 || max_depth > MAX_ONTOLOGY_DEPTH {
+        return Err(ValidationError::new(&format!(
+            "Invalid max depth: {}, it should be between 1 and {}.",
+            max_depth, MAX_ONTOLOGY_DEPTH
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+impl OntologyNode {
+    async fn get_lineage(
+        pool: &sqlx::PgPool,
+        label: &str,
+        id: &str,
+        max_depth: usize,
+        upward: bool,
+    ) -> Result<Vec<OntologyNode>, anyhow::Error> {
+        check_depth(max_depth)?;
+        let sql_str = format!(
+            "{}
+             SELECT e.id, e.name, e.label, MIN(l.depth)::INT AS depth
+             FROM lineage l JOIN biomedgps_entity e ON e.id = l.id AND e.label = $1
+             WHERE l.depth > 0
+             GROUP BY e.id, e.name, e.label
+             ORDER BY depth, e.id",
+            lineage_query(upward)
+        );
+        debug!("Fetching the lineage of {}::{}: {}", label, id, sql_str);
+
+        let nodes = with_query_timeout(
+            sqlx::query_as::<_, OntologyNode>(&sql_str)
+                .bind(label)
+                .bind(vec![id.to_string()])
+                .bind(max_depth as i32)
+                .fetch_all(pool),
+        )
+        .await?;
+
+        AnyOk(nodes)
+    }
+
+    /// Fetch the ancestors of an entity by the is_a relations, the parents go first.
+    pub async fn get_ancestors(
+        pool: &sqlx::PgPool,
+        label: &str,
+        id: &str,
+        max_depth: usize,
+    ) -> Result<Vec<OntologyNode>, anyhow::Error> {
+        OntologyNode::get_lineage(pool, label, id, max_depth, true).await
+    }
+
+    /// Fetch the descendants of an entity by the is_a relations, the children go first.
+    pub async fn get_descendants(
+        pool: &sqlx::PgPool,
+        label: &str,
+        id: &str,
+        max_depth: usize,
+    ) -> Result<Vec<OntologyNode>, anyhow::Error> {
+        OntologyNode::get_lineage(pool, label, id, max_depth, false).await
+    }
+}
+
+impl OntologyRollup {
+    /// Roll up the entities (such as the diseases in a query result) to themselves and their ancestors within the max depth. The terms which cover more entities go first, and at most `limit` terms are returned.
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        label: &str,
+        ids: &[String],
+        max_depth: usize,
+        limit: usize,
+    ) -> Result<Vec<OntologyRollup>, anyhow::Error> {
+        check_depth(max_depth)?;
+        if ids.len() > MAX_ROLLUP_IDS {
+            return Err(ValidationError::new(&format!(
+                "Too many entities: {}, at most {} entities can be rolled up.",
+                ids.len(),
+                MAX_ROLLUP_IDS
+            ))
+            .into());
+        }
+
+        let sql_str = format!(
+            "{}
+             SELECT e.id, e.name, e.label, COUNT(DISTINCT l.member_id) AS count,
+                    ARRAY_AGG(DISTINCT l.member_id) AS member_ids
+             FROM lineage l JOIN biomedgps_entity e ON e.id = l.id AND e.label = $1
+             GROUP BY e.id, e.name, e.label
+             ORDER BY count DESC, e.id
+             LIMIT $4",
+            lineage_query(true)
+        );
+        debug!("Rolling up {} {} entities: {}", ids.len(), label, sql_str);
+
+        let records = with_query_timeout(
+            sqlx::query_as::<_, OntologyRollup>(&sql_str)
+                .bind(label)
+                .bind(ids)
+                .bind(max_depth as i32)
+                .bind(limit as i64)
+                .fetch_all(pool),
+        )
+        .await?;
+
+        AnyOk(records)
+    }
+}
+
+/// Expand the entity ids in the query filters by their descendants within the max depth, so a query on a parent term (such as autoimmune disease) also matches the relations of its subclasses. Only the exact matches (`=` and `in`) of the id fields (see [`ENTITY_ID_FIELDS`]) are expanded, and they become `in` filters.
+pub async fn expand_descendants(
+    pool: &sqlx::PgPool,
+    query: &ComposeQuery,
+    max_depth: usize,
+) -> Result<ComposeQuery, anyhow::Error> {
+    check_depth(max_depth)?;
+    let mut ids = vec![];
+    collect_query_ids(query, &mut ids);
+    if ids.is_empty() {
+        return AnyOk(query.clone());
+    }
+    ids.sort();
+    ids.dedup();
+
+    let sql_str = format!(
+        "{}
+         SELECT member_id, ARRAY_AGG(DISTINCT id) AS ids FROM lineage GROUP BY member_id",
+        lineage_query(false)
+    );
+    debug!(
+        "Expanding {} entity ids by the descendants: {}",
+        ids.len(),
+        sql_str
+    );
+
+    let rows = with_query_timeout(
+        sqlx::query_as::<_, (String, Vec<String>)>(&sql_str)
+            .bind(None::<String>)
+            .bind(&ids)
+            .bind(max_depth as i32)
+            .fetch_all(pool),
+    )
+    .await?;
+
+    let total = rows.iter().map(|(_, ids)| ids.len()).sum::<usize>();
+    if total > MAX_EXPANDED_IDS {
+        return Err(ValidationError::new(&format!(
+            "Too many entities after the expansion: {}, at most {} entities are allowed, please use a smaller max depth.",
+            total,
+            MAX_EXPANDED_IDS
+        )).into());
+    }
+
+    let descendants = rows.into_iter().collect::<HashMap<String, Vec<String>>>();
+    AnyOk(expand_query_ids(query, &descendants))
+}
+
+fn is_expandable(item: &QueryItem) -> bool {
+    ENTITY_ID_FIELDS.contains(&item.field.as_str())
+        && ["=", "in"].contains(&item.operator.to_lowercase().as_str())
+}
+
+fn collect_query_ids(query: &ComposeQuery, ids: &mut Vec<String>) {
+    match query {
+        ComposeQuery::QueryItem(item) if is_expandable(item) => match &item.value {
+            Value::String(id) => ids.push(id.clone()),
+            Value::ArrayString(values) => ids.extend(values.iter().cloned()),
+            _ => {}
+        },
+        ComposeQuery::QueryItem(_) => {}
+        ComposeQuery::ComposeQueryItem(item) => {
+            for query in item.items.iter() {
+                collect_query_ids(query, ids);
+            }
+        }
+    }
+}
+
+// Replace the ids in the query filters by themselves and their descendants, the ids without descendants are kept as they are.
+fn expand_query_ids(
+    query: &ComposeQuery,
+    descendants: &HashMap<String, Vec<String>>,
+) -> ComposeQuery {
+    match query {
+        ComposeQuery::QueryItem(item) if is_expandable(item) => {
+            let ids = match &item.value {
+                Value::String(id) => vec![id.clone()],
+                Value::ArrayString(values) => values.clone(),
+                _ => return query.clone(),
+            };
+
+            let mut expanded: Vec<String> = vec![];
+            for id in ids.iter() {
+                expanded.push(id.clone());
+                if let Some(children) = descendants.get(id) {
+                    expanded.extend(children.iter().cloned());
+                }
+            }
+            let mut seen = HashSet::new();
+            expanded.retain(|id| seen.insert(id.clone()));
+
+            ComposeQuery::QueryItem(QueryItem::new(
+                item.field.clone(),
+                Value::ArrayString(expanded),
+                "in".to_string(),
+            ))
+        }
+        ComposeQuery::QueryItem(_) => query.clone(),
+        ComposeQuery::ComposeQueryItem(item) => {
+            let mut expanded = ComposeQueryItem::new(&item.operator);
+            expanded.items = item
+                .items
+                .iter()
+                .map(|query| expand_query_ids(query, descendants))
+                .collect();
+            ComposeQuery::ComposeQueryItem(expanded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_obographs() {
+        let json = r#"{"graphs": [{
+            "nodes": [
+                {"id": "http://purl.obolibrary.org/obo/HP_0000118", "lbl": "Phenotypic abnormality", "type": "CLASS",
+                 "meta": {"definition": {"val": "A phenotypic abnormality."}, "synonyms": [{"val": "Organ abnormality"}]}},
+                {"id": "http://purl.obolibrary.org/obo/HP_0000707", "lbl": "Abnormality of the nervous system", "type": "CLASS",
+                 "meta": {"xrefs": [{"val": "UMLS:C0497552"}]}},
+                {"id": "http://purl.obolibrary.org/obo/HP_0000001", "lbl": "obsolete term", "type": "CLASS", "meta": {"deprecated": true}},
+                {"id": "http://purl.obolibrary.org/obo/UBERON_0001016", "lbl": "nervous system", "type": "CLASS"},
+                {"id": "http://purl.obolibrary.org/obo/hp#has_onset", "lbl": "has onset", "type": "PROPERTY"}
+            ],
+            "edges": [
+                {"sub": "http://purl.obolibrary.org/obo/HP_0000707", "pred": "is_a", "obj": "http://purl.obolibrary.org/obo/HP_0000118"},
+                {"sub": "http://purl.obolibrary.org/obo/HP_0000707", "pred": "is_a", "obj": "http://purl.obolibrary.org/obo/HP_0000118"},
+                {"sub": "http://purl.obolibrary.org/obo/HP_0000001", "pred": "is_a", "obj": "http://purl.obolibrary.org/obo/HP_0000118"},
+                {"sub": "http://purl.obolibrary.org/obo/HP_0000707", "pred": "http://purl.obolibrary.org/obo/BFO_0000050", "obj": "http://purl.obolibrary.org/obo/UBERON_0001016"}
+            ]
+        }]}"#;
+
+        let ontology = Ontology::parse_obographs(json, Some("HP")).unwrap();
+        assert_eq!(
+            ontology
+                .terms
+                .iter()
+                .map(|t| t.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["HP:0000118", "HP:0000707"]
+        );
+        assert_eq!(ontology.terms[0].synonyms, vec!["Organ abnormality"]);
+        assert_eq!(ontology.terms[1].xrefs, vec!["UMLS:C0497552"]);
+        // The duplicated edge and the edge of the deprecated term are skipped.
+        assert_eq!(
+            ontology.is_a,
+            vec![("HP:0000707".to_string(), "HP:0000118".to_string())]
+        );
+        assert_eq!(ontology.main_prefix(), Some("HP".to_string()));
+
+        // The terms of the other ontologies are kept without the prefix.
+        let ontology = Ontology::parse_obographs(json, None).unwrap();
+        assert_eq!(ontology.terms.len(), 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        let (entity_file, relation_file) =
+            ontology.write_files(dir.path(), "Phenotype", "HP").unwrap();
+        let relations = std::fs::read_to_string(relation_file).unwrap();
+        assert_eq!(
+            relations.lines().nth(1),
+            Some("HP::is_a::Phenotype:Phenotype\tHP:0000707\tPhenotype\tHP:0000118\tPhenotype\tHP")
+        );
+        assert_eq!(
+            std::fs::read_to_string(entity_file)
+                .unwrap()
+                .lines()
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_expand_query_ids() {
+        let source = QueryItem::new(
+            "source_id".to_string(),
+            Value::String("DOID:417".to_string()),
+            "=".to_string(),
+        );
+        // The negative filters are not expanded.
+        let target = QueryItem::new(
+            "target_id".to_string(),
+            Value::String("DOID:4".to_string()),
+            "!=".to_string(),
+        );
+        let mut query = ComposeQueryItem::new("and");
+        query.items.push(ComposeQuery::QueryItem(source));
+        query.items.push(ComposeQuery::QueryItem(target.clone()));
+        let query = ComposeQuery::ComposeQueryItem(query);
+
+        let mut ids = vec![];
+        collect_query_ids(&query, &mut ids);
+        assert_eq!(ids, vec!["DOID:417"]);
+
+        // The lineage contains the entity itself.
+        let descendants = HashMap::from([(
+            "DOID:417".to_string(),
+            vec!["DOID:417".to_string(), "DOID:9074".to_string()],
+        )]);
+        let mut expected = ComposeQueryItem::new("and");
+        expected.items.push(ComposeQuery::QueryItem(QueryItem::new(
+            "source_id".to_string(),
+            Value::ArrayString(vec!["DOID:417".to_string(), "DOID:9074".to_string()]),
+            "in".to_string(),
+        )));
+        expected.items.push(ComposeQuery::QueryItem(target));
+        assert_eq!(
+            expand_query_ids(&query, &descendants),
+            ComposeQuery::ComposeQueryItem(expected)
+        );
+    }
+}
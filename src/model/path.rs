@@ -0,0 +1,312 @@
+//! Path module is used to explain a predicted edge by the multi-hop paths between its two nodes in the `biomedgps_relation` table, such as `Compound -> Gene -> Disease` for a predicted `treats` edge. The paths are returned as a small graph, so they can be rendered as the evidences of the prediction.
+//!
+//! The paths are ranked by the degree-weighted path score (such as the DWPC of Hetionet), each intermediate node contributes `degree ^ -0.5`, so the paths through the specific nodes rank higher than the paths through the hubs. The direct edges between the two nodes are not returned, because they are the edge to explain.
+
+use crate::model::core::Relation;
+use crate::model::graph::{Edge, Graph, Node, NodeId};
+use crate::model::util::with_query_timeout;
+use anyhow::Ok as AnyOk;
+use log::debug;
+use std::collections::{HashMap, HashSet};
+
+/// The default number of the paths to explain an edge.
+pub const DEFAULT_MAX_PATHS: usize = 5;
+/// The max number of the hops of a path, the 3-hop paths are much more than the 2-hop paths.
+pub const MAX_PATH_HOPS: usize = 3;
+
+// The max number of the edges of each end which are used to find the paths, the edges with higher scores are used first.
+const MAX_PATH_EDGES: i64 = 10000;
+// The max number of the neighbours of each end which are used to find the 3-hop paths.
+const MAX_PATH_NEIGHBOURS: usize = 1000;
+// The damping exponent of the degree-weighted path score.
+const PATH_DAMPING: f64 = 0.5;
+
+/// A path between two nodes, the relations are in the order of the path and their directions are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvidencePath {
+    pub node_ids: Vec<String>,
+    pub relations: Vec<Relation>,
+    pub score: f64,
+}
+
+/// Compute the degree-weighted path score from the degrees of the intermediate nodes.
+///
+/// ```
+/// use biomedgps::model::path::path_score;
+///
+/// assert_eq!(path_score(&[4]), 0.5);
+/// assert_eq!(path_score(&[4, 16]), 0.125);
+/// // A path through a hub scores lower.
+/// assert!(path_score(&[10000]) < path_score(&[100]));
+/// ```
+pub fn path_score(degrees: &[i64]) -> f64 {
+    degrees
+        .iter()
+        .map(|degree| (*degree).max(1) as f64)
+        .map(|degree| degree.powf(-PATH_DAMPING))
+        .product()
+}
+
+fn get_source_node_id(relation: &Relation) -> String {
+    Node::format_id(&relation.source_type, &relation.source_id)
+}
+
+fn get_target_node_id(relation: &Relation) -> String {
+    Node::format_id(&relation.target_type, &relation.target_id)
+}
+
+/// Fetch the edges of a node in both directions, and keep the best scored edge for each neighbour. The edges to the excluded node (the other end of the predicted edge) are skipped.
+async fn fetch_neighbours(
+    pool: &sqlx::PgPool,
+    node_id: &str,
+    excluded_node_id: &str,
+) -> Result<Vec<(String, Relation)>, anyhow::Error> {
+    let (node_type, entity_id) = Node::parse_id(node_id);
+    let relations = with_query_timeout(
+        sqlx::query_as::<_, Relation>(
+            "SELECT * FROM biomedgps_relation
+             WHERE (source_type = $1 AND source_id = $2) OR (target_type = $1 AND target_id = $2)
+             ORDER BY score DESC NULLS LAST, id ASC LIMIT $3",
+        )
+        .bind(&node_type)
+        .bind(&entity_id)
+        .bind(MAX_PATH_EDGES)
+        .fetch_all(pool),
+    )
+    .await?;
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut neighbours: Vec<(String, Relation)> = vec![];
+    for relation in relations {
+        let source = get_source_node_id(&relation);
+        let neighbour = if source == node_id {
+            get_target_node_id(&relation)
+        } else {
+            source
+        };
+
+        // The relations are sorted by the score, so the first one of a neighbour is the best one.
+        if neighbour == node_id || neighbour == excluded_node_id || !seen.insert(neighbour.clone())
+        {
+            continue;
+        }
+        neighbours.push((neighbour, relation));
+    }
+
+    AnyOk(neighbours)
+}
+
+/// Fetch the relations between two sets of nodes in both directions.
+async fn fetch_relations_between(
+    pool: &sqlx::PgPool,
+    node_ids: &[&String],
+    other_node_ids: &[&String],
+) -> Result<Vec<Relation>, anyhow::Error> {
+    let split = |node_ids: &[&String]| -> (Vec<String>, Vec<String>) {
+        node_ids
+            .iter()
+            .map(|node_id| Node::parse_id(node_id))
+            .unzip()
+    };
+    let (types, ids) = split(node_ids);
+    let (other_types, other_ids) = split(other_node_ids);
+
+    let relations = with_query_timeout(
+        sqlx::query_as::<_, Relation>(
+            "SELECT r.* FROM biomedgps_relation r
+             JOIN UNNEST($1::TEXT[], $2::TEXT[]) AS a(t, i) ON r.source_type = a.t AND r.source_id = a.i
+             JOIN UNNEST($3::TEXT[], $4::TEXT[]) AS b(t, i) ON r.target_type = b.t AND r.target_id = b.i
+             UNION ALL
+             SELECT r.* FROM biomedgps_relation r
+             JOIN UNNEST($3::TEXT[], $4::TEXT[]) AS b(t, i) ON r.source_type = b.t AND r.source_id = b.i
+             JOIN UNNEST($1::TEXT[], $2::TEXT[]) AS a(t, i) ON r.target_type = a.t AND r.target_id = a.i",
+        )
+        .bind(&types)
+        .bind(&ids)
+        .bind(&other_types)
+        .bind(&other_ids)
+        .fetch_all(pool),
+    )
+    .await?;
+
+    AnyOk(relations)
+}
+
+/// Count the edges of the nodes in both directions.
+async fn fetch_degrees(
+    pool: &sqlx::PgPool,
+    node_ids: &[&String],
+) -> Result<HashMap<String, i64>, anyhow::Error> {
+    let (types, ids): (Vec<String>, Vec<String>) = node_ids
+        .iter()
+        .map(|node_id| Node::parse_id(node_id))
+        .unzip();
+
+    let degrees = with_query_timeout(
+        sqlx::query_as::<_, (String, i64)>(
+            "SELECT a.t || '::' || a.i,
+                    (SELECT COUNT(*) FROM biomedgps_relation WHERE source_type = a.t AND source_id = a.i)
+                    + (SELECT COUNT(*) FROM biomedgps_relation WHERE target_type = a.t AND target_id = a.i)
+             FROM UNNEST($1::TEXT[], $2::TEXT[]) AS a(t, i)",
+        )
+        .bind(&types)
+        .bind(&ids)
+        .fetch_all(pool),
+    )
+    .await?;
+
+    AnyOk(degrees.into_iter().collect())
+}
+
+/// Find the top paths between two nodes within the max hops (2 or 3), ranked by the degree-weighted path score. Only the best scored relation is used between two adjacent nodes of a path.
+pub async fn find_evidence_paths(
+    pool: &sqlx::PgPool,
+    source_id: &str,
+    target_id: &str,
+    max_hops: usize,
+    max_paths: usize,
+) -> Result<Vec<EvidencePath>, anyhow::Error> {
+    let source_neighbours = fetch_neighbours(pool, source_id, target_id).await?;
+    let target_neighbours = fetch_neighbours(pool, target_id, source_id).await?;
+    let target_relations = target_neighbours
+        .iter()
+        .map(|(node_id, relation)| (node_id.as_str(), relation))
+        .collect::<HashMap<&str, &Relation>>();
+
+    let mut paths: Vec<(Vec<String>, Vec<Relation>)> = vec![];
+    for (middle, first) in source_neighbours.iter() {
+        if let Some(second) = target_relations.get(middle.as_str()) {
+            paths.push((
+                vec![source_id.to_string(), middle.clone(), target_id.to_string()],
+                vec![first.clone(), (*second).clone()],
+            ));
+        }
+    }
+
+    if max_hops >= 3 {
+        let source_relations = source_neighbours
+            .iter()
+            .map(|(node_id, relation)| (node_id.as_str(), relation))
+            .collect::<HashMap<&str, &Relation>>();
+        let source_nodes = source_neighbours
+            .iter()
+            .take(MAX_PATH_NEIGHBOURS)
+            .map(|(node_id, _)| node_id)
+            .collect::<Vec<&String>>();
+        let target_nodes = target_neighbours
+            .iter()
+            .take(MAX_PATH_NEIGHBOURS)
+            .map(|(node_id, _)| node_id)
+            .collect::<Vec<&String>>();
+
+        let mut seen: HashSet<(String, String)> = HashSet::new();
+        for relation in fetch_relations_between(pool, &source_nodes, &target_nodes).await? {
+            let (source, target) = (get_source_node_id(&relation), get_target_node_id(&relation));
+            // The middle relation may go from the target side to the source side.
+            let (first_middle, second_middle) = if source_relations.contains_key(source.as_str())
+                && target_relations.contains_key(target.as_str())
+            {
+                (source, target)
+            } else {
+                (target, source)
+            };
+
+            if first_middle == second_middle
+                || !seen.insert((first_middle.clone(), second_middle.clone()))
+            {
+                continue;
+            }
+
+            if let (Some(first), Some(third)) = (
+                source_relations.get(first_middle.as_str()),
+                target_relations.get(second_middle.as_str()),
+            ) {
+                paths.push((
+                    vec![
+                        source_id.to_string(),
+                        first_middle,
+                        second_middle,
+                        target_id.to_string(),
+                    ],
+                    vec![(*first).clone(), relation, (*third).clone()],
+                ));
+            }
+        }
+    }
+
+    let middle_nodes = paths
+        .iter()
+        .flat_map(|(node_ids, _)| node_ids[1..node_ids.len() - 1].iter())
+        .collect::<HashSet<&String>>()
+        .into_iter()
+        .collect::<Vec<&String>>();
+    let degrees = fetch_degrees(pool, &middle_nodes).await?;
+
+    let mut paths = paths
+        .into_iter()
+        .map(|(node_ids, relations)| {
+            let path_degrees = node_ids[1..node_ids.len() - 1]
+                .iter()
+                .map(|node_id| degrees.get(node_id).cloned().unwrap_or(1))
+                .collect::<Vec<i64>>();
+            EvidencePath {
+                score: path_score(&path_degrees),
+                node_ids,
+                relations,
+            }
+        })
+        .collect::<Vec<EvidencePath>>();
+
+    paths.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.node_ids.cmp(&b.node_ids))
+    });
+    debug!(
+        "Found {} paths between {} and {}.",
+        paths.len(),
+        source_id,
+        target_id
+    );
+    paths.truncate(max_paths);
+
+    AnyOk(paths)
+}
+
+/// Find the top paths between two nodes (see [`find_evidence_paths`]) and convert them into a graph.
+pub async fn fetch_evidence_graph(
+    pool: &sqlx::PgPool,
+    source_id: &str,
+    target_id: &str,
+    max_hops: usize,
+    max_paths: usize,
+) -> Result<Graph, anyhow::Error> {
+    let paths = find_evidence_paths(pool, source_id, target_id, max_hops, max_paths).await?;
+
+    let mut graph = Graph::new();
+    let mut node_ids: Vec<&str> = vec![source_id, target_id];
+    for path in paths.iter() {
+        node_ids.extend(path.node_ids.iter().map(|node_id| node_id.as_str()));
+        for relation in path.relations.iter() {
+            graph.add_edge(Edge::from_relation(relation));
+        }
+    }
+    node_ids.sort_unstable();
+    node_ids.dedup();
+    let node_ids = node_ids
+        .iter()
+        .map(|node_id| node_id.parse::<NodeId>())
+        .collect::<Result<Vec<NodeId>, _>>()?;
+
+    graph
+        .fetch_nodes_by_ids(pool, &node_ids)
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    AnyOk(
+        graph
+            .get_graph(None)
+            .map_err(|e| anyhow::anyhow!("{}", e))?,
+    )
+}
@@ -0,0 +1,324 @@
+//! PGML module is used to train the knowledge graph embeddings in the database (such as a PostgresML server), so the triples don't need to be exported and the embeddings don't need to be imported by hand.
+//!
+//! The training triples (with the group and split columns, see [`crate::model::split`]) are materialized into the `biomedgps_kge_training_triples` table, then a PL/Python function which trains the embeddings by torch is created and called. The PostgresML image ships PL/Python and torch, other servers need the `plpython3u` extension and torch in the python environment of the database. The function reports the loss of each epoch as a notice, which is logged by the client, and returns the embeddings which are registered as a model by the same way as `biomedgps-cli import-embeddings`.
+//!
+//! Only TransE and DistMult are supported, the negatives are sampled uniformly by replacing the head or the tail of a training triple, and the embeddings are trained by the margin ranking loss.
+
+use crate::algorithm::kge::ScoreFunction;
+use crate::model::core::EmbeddingModel;
+use crate::model::kge::{import_csv_files, NativeEmbeddings};
+use anyhow::Ok as AnyOk;
+use log::{info, warn};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The table which keeps the materialized training triples.
+pub const TRAINING_TABLE: &str = "biomedgps_kge_training_triples";
+/// The interval of logging the elapsed time of the training.
+pub const PROGRESS_INTERVAL: u64 = 30;
+
+const CREATE_TRAINING_TABLE: &str = r#"CREATE UNLOGGED TABLE IF NOT EXISTS biomedgps_kge_training_triples (
+    id BIGINT PRIMARY KEY,
+    head TEXT NOT NULL,
+    relation_type TEXT NOT NULL,
+    tail TEXT NOT NULL,
+    "group" TEXT NOT NULL,
+    split TEXT NOT NULL
+)"#;
+
+// The training function, it returns the embeddings of the entities (the node ids like Gene::ENTREZ:7157) and the relation types.
+const CREATE_TRAINING_FUNCTION: &str = r#"CREATE OR REPLACE FUNCTION biomedgps_train_kge(
+    algorithm TEXT, dimension INTEGER, epochs INTEGER, batch_size INTEGER,
+    learning_rate REAL, negatives INTEGER, margin REAL, seed BIGINT
+) RETURNS TABLE (kind TEXT, name TEXT, embedding REAL[]) AS $$
+import time
+
+try:
+    import torch
+except ImportError:
+    plpy.error("torch is not installed in the python environment of the database, please use the PostgresML image or install torch.")
+
+torch.manual_seed(seed)
+entities = {}
+relations = {}
+heads, rels, tails = [], [], []
+for row in plpy.cursor("SELECT head, relation_type, tail FROM biomedgps_kge_training_triples WHERE split = 'train' ORDER BY id"):
+    heads.append(entities.setdefault(row["head"], len(entities)))
+    rels.append(relations.setdefault(row["relation_type"], len(relations)))
+    tails.append(entities.setdefault(row["tail"], len(entities)))
+
+if not heads:
+    plpy.error("No training triples, please split the relations by biomedgps-cli splitdb first.")
+plpy.notice("Training %s on %d triples of %d entities and %d relation types." % (algorithm, len(heads), len(entities), len(relations)))
+
+h, r, t = torch.tensor(heads), torch.tensor(rels), torch.tensor(tails)
+ent = torch.nn.Embedding(len(entities), dimension)
+rel = torch.nn.Embedding(len(relations), dimension)
+bound = 6 / dimension ** 0.5
+torch.nn.init.uniform_(ent.weight, -bound, bound)
+torch.nn.init.uniform_(rel.weight, -bound, bound)
+optimizer = torch.optim.Adam(list(ent.parameters()) + list(rel.parameters()), lr=learning_rate)
+
+def score(hv, rv, tv):
+    if algorithm == "TransE_l1":
+        return -(hv + rv - tv).norm(p=1, dim=-1)
+    if algorithm == "TransE_l2":
+        return -(hv + rv - tv).norm(p=2, dim=-1)
+    return (hv * rv * tv).sum(dim=-1)
+
+total = len(heads)
+for epoch in range(epochs):
+    started = time.time()
+    epoch_loss = 0.0
+    order = torch.randperm(total)
+    for start in range(0, total, batch_size):
+        index = order[start:start + batch_size]
+        bh, br, bt = h[index], r[index], t[index]
+        positive = score(ent(bh), rel(br), ent(bt))
+        size = (len(index), negatives)
+        corrupted = torch.randint(0, len(entities), size)
+        corrupt_head = torch.rand(size) < 0.5
+        nh = torch.where(corrupt_head, corrupted, bh.unsqueeze(1))
+        nt = torch.where(corrupt_head, bt.unsqueeze(1), corrupted)
+        negative = score(ent(nh), rel(br).unsqueeze(1), ent(nt))
+        loss = torch.relu(margin - positive.unsqueeze(1) + negative).mean()
+        optimizer.zero_grad()
+        loss.backward()
+        optimizer.step()
+        epoch_loss += loss.item() * len(index)
+
+    if algorithm.startswith("TransE"):
+        with torch.no_grad():
+            ent.weight.div_(ent.weight.norm(dim=1, keepdim=True).clamp(min=1.0))
+    plpy.notice("Epoch %d/%d, loss %.6f, %.1fs" % (epoch + 1, epochs, epoch_loss / total, time.time() - started))
+
+entity_weights = ent.weight.detach().tolist()
+relation_weights = rel.weight.detach().tolist()
+for name, index in entities.items():
+    yield ("entity", name, entity_weights[index])
+for name, index in relations.items():
+    yield ("relation", name, relation_weights[index])
+$$ LANGUAGE plpython3u"#;
+
+/// The backend which trains the embeddings, only PostgresML is supported now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingBackend {
+    PostgresML,
+}
+
+impl FromStr for TrainingBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "postgresml" | "pgml" => Ok(TrainingBackend::PostgresML),
+            _ => Err(format!(
+                "Invalid training backend: {}, it must be postgresml.",
+                s
+            )),
+        }
+    }
+}
+
+/// The options of training the embeddings in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingOptions {
+    pub algorithm: ScoreFunction,
+    pub dimension: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f32,
+    /// The number of the negatives of each training triple.
+    pub negatives: usize,
+    pub margin: f32,
+    pub seed: u64,
+    /// The groups (the `resource` column of the relations, such as drkg) whose triples are not used.
+    pub exclude_groups: Vec<String>,
+}
+
+impl Default for TrainingOptions {
+    fn default() -> Self {
+        TrainingOptions {
+            algorithm: ScoreFunction::TransE { l1: false },
+            dimension: 400,
+            epochs: 100,
+            batch_size: 1024,
+            learning_rate: 0.001,
+            negatives: 64,
+            margin: 1.0,
+            seed: 42,
+            exclude_groups: vec![],
+        }
+    }
+}
+
+impl TrainingOptions {
+    /// Check whether the options are supported by the training function.
+    ///
+    /// ```
+    /// use biomedgps::algorithm::kge::ScoreFunction;
+    /// use biomedgps::model::pgml::TrainingOptions;
+    ///
+    /// assert!(TrainingOptions::default().check().is_ok());
+    ///
+    /// let options = TrainingOptions { algorithm: ScoreFunction::RotatE, ..Default::default() };
+    /// assert!(options.check().is_err());
+    ///
+    /// let options = TrainingOptions { epochs: 0, ..Default::default() };
+    /// assert!(options.check().is_err());
+    /// ```
+    pub fn check(&self) -> Result<(), String> {
+        match self.algorithm {
+            ScoreFunction::TransE { .. } | ScoreFunction::DistMult => {}
+            _ => {
+                return Err(format!(
+                    "Unsupported algorithm: {}, only TransE_l1, TransE_l2 and DistMult can be trained in the database.",
+                    self.algorithm
+                ))
+            }
+        }
+
+        if self.dimension == 0 || self.epochs == 0 || self.batch_size == 0 || self.negatives == 0 {
+            return Err(
+                "The dimension, epochs, batch size and negatives should be greater than 0."
+                    .to_string(),
+            );
+        }
+
+        if self.learning_rate <= 0.0 || self.margin <= 0.0 {
+            return Err("The learning rate and margin should be greater than 0.".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The description of the trained model, such as `Trained by PostgresML: dimension=400, epochs=100, ...`.
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "Trained by PostgresML: dimension={}, epochs={}, batch_size={}, learning_rate={}, negatives={}, margin={}, seed={}",
+            self.dimension,
+            self.epochs,
+            self.batch_size,
+            self.learning_rate,
+            self.negatives,
+            self.margin,
+            self.seed
+        );
+        if !self.exclude_groups.is_empty() {
+            description.push_str(&format!(
+                ", exclude_groups={}",
+                self.exclude_groups.join(",")
+            ));
+        }
+        description
+    }
+}
+
+/// Materialize the training triples into the training table (see the module docs), the triples which are not split or in the excluded groups are skipped. It returns the number of the triples.
+pub async fn materialize_training_triples(
+    pool: &sqlx::PgPool,
+    exclude_groups: &[String],
+) -> Result<u64, anyhow::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(CREATE_TRAINING_TABLE).execute(&mut tx).await?;
+    sqlx::query(&format!("TRUNCATE {}", TRAINING_TABLE))
+        .execute(&mut tx)
+        .await?;
+    let inserted = sqlx::query(
+        r#"INSERT INTO biomedgps_kge_training_triples (id, head, relation_type, tail, "group", split)
+           SELECT id, source_type || '::' || source_id, relation_type, target_type || '::' || target_id, resource, split
+           FROM biomedgps_relation WHERE split IS NOT NULL AND NOT (resource = ANY($1))"#,
+    )
+    .bind(exclude_groups)
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    AnyOk(inserted.rows_affected())
+}
+
+/// Train the embeddings in the database and register them as the model (see the module docs). If drop is true, the existing embeddings of the model are replaced.
+pub async fn train_embeddings(
+    pool: &sqlx::PgPool,
+    backend: TrainingBackend,
+    options: &TrainingOptions,
+    model: &EmbeddingModel,
+    drop: bool,
+) -> Result<(), anyhow::Error> {
+    options.check().map_err(|e| anyhow::anyhow!(e))?;
+    match backend {
+        TrainingBackend::PostgresML => {
+            let pgml: Option<(String,)> =
+                sqlx::query_as("SELECT extversion FROM pg_extension WHERE extname = 'pgml'")
+                    .fetch_optional(pool)
+                    .await?;
+            match pgml {
+                Some((version,)) => info!("Training on PostgresML {}.", version),
+                None => warn!(
+                    "The pgml extension is not installed, the training needs plpython3u and torch in the database."
+                ),
+            }
+        }
+    }
+
+    let total = materialize_training_triples(pool, &options.exclude_groups).await?;
+    info!("Materialized {} triples into {}.", total, TRAINING_TABLE);
+
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS plpython3u")
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to enable plpython3u: {}", e))?;
+    sqlx::query(CREATE_TRAINING_FUNCTION).execute(pool).await?;
+
+    // The notices of the training function (such as the loss of each epoch) are logged by sqlx.
+    let started = Instant::now();
+    let training = sqlx::query_as::<_, (String, String, Vec<f32>)>(
+        "SELECT * FROM biomedgps_train_kge($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(options.algorithm.to_string())
+    .bind(options.dimension as i32)
+    .bind(options.epochs as i32)
+    .bind(options.batch_size as i32)
+    .bind(options.learning_rate)
+    .bind(options.negatives as i32)
+    .bind(options.margin)
+    .bind(options.seed as i64)
+    .fetch_all(pool);
+    tokio::pin!(training);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(PROGRESS_INTERVAL));
+    // The first tick completes immediately.
+    interval.tick().await;
+    let rows = loop {
+        tokio::select! {
+            rows = &mut training => break rows?,
+            _ = interval.tick() => info!(
+                "Training {} for {} seconds...",
+                model.model_name,
+                started.elapsed().as_secs()
+            ),
+        }
+    };
+
+    let mut native = NativeEmbeddings::default();
+    for (kind, name, embedding) in rows {
+        match kind.as_str() {
+            "entity" => native
+                .entities
+                .push((native.entities.len(), name, embedding)),
+            _ => native
+                .relations
+                .push((native.relations.len(), name, embedding)),
+        }
+    }
+    info!(
+        "Trained {} entity embeddings and {} relation embeddings in {} seconds.",
+        native.entities.len(),
+        native.relations.len(),
+        started.elapsed().as_secs()
+    );
+
+    let temp_dir = tempfile::tempdir()?;
+    native.write_csv_files(pool, temp_dir.path()).await?;
+    import_csv_files(pool, temp_dir.path(), drop, model).await
+}
@@ -0,0 +1,616 @@
+//! Publication module is used to fetch the metadata of the publications (such as title, journal, year and abstract) from the PubMed E-utilities.
+//!
+//! There are two ways to use the metadata:
+//!
+//! * The summaries (title, journal and year) are cached on disk (one json file per pmid), so we don't need to request the PubMed E-utilities again. The cache directory can be set by the environment variable PUBMED_CACHE_DIR, the default is `<TMPDIR>/biomedgps/pubmed`.
+//! * The full metadata (with the abstract) is stored in the biomedgps_publication table. A background fetcher resolves the pmids of the new curated knowledges.
+//!
+//! All requests of the server (the background fetcher, the maintenance task and the on-demand fetches of the API) share one throttle, so they respect the rate limits of the E-utilities together.
+//!
+//! You can set the environment variable NCBI_API_KEY to get a higher rate limit.
+
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{debug, error, info, warn};
+use poem_openapi::Object;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+pub const ESUMMARY_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esummary.fcgi";
+pub const EFETCH_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi";
+
+// The E-utilities allow at most 200 ids per esummary request when using GET.
+const MAX_IDS_PER_REQUEST: usize = 200;
+const REQUEST_TIMEOUT: u64 = 30;
+
+lazy_static! {
+    static ref ARTICLE_REGEX: Regex = Regex::new(r"(?s)<PubmedArticle>.*?</PubmedArticle>").unwrap();
+    static ref PMID_REGEX: Regex = Regex::new(r"<PMID[^>]*>(\d+)</PMID>").unwrap();
+    static ref ARTICLE_TITLE_REGEX: Regex = Regex::new(r"(?s)<ArticleTitle[^>]*>(.*?)</ArticleTitle>").unwrap();
+    static ref JOURNAL_TITLE_REGEX: Regex = Regex::new(r"(?s)<Journal>.*?<Title>(.*?)</Title>").unwrap();
+    // The PubDate contains a Year or a MedlineDate, such as `2019 Aug-Sep`.
+    static ref PUB_YEAR_REGEX: Regex = Regex::new(r"(?s)<PubDate>.*?(?:<Year>|<MedlineDate>)(\d{4})").unwrap();
+    static ref ABSTRACT_TEXT_REGEX: Regex = Regex::new(r#"(?s)<AbstractText(?:[^>]*?Label="([^"]*)")?[^>]*>(.*?)</AbstractText>"#).unwrap();
+    static ref XML_TAG_REGEX: Regex = Regex::new(r"<[^>]+>").unwrap();
+    static ref FETCHER: Mutex<Option<UnboundedSender<i64>>> = Mutex::new(None);
+    // When the E-utilities were requested last time.
+    static ref LAST_REQUEST: tokio::sync::Mutex<Option<Instant>> = tokio::sync::Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct PublicationSummary {
+    pub pmid: i64,
+    pub title: String,
+    pub journal: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub year: Option<i32>,
+}
+
+/// Parse the pmids from a string, the pmids may be separated by `|`, `,`, `;` or whitespace. The invalid pmids are ignored and the duplicated pmids are removed.
+///
+/// # Examples
+///
+/// ```
+/// use biomedgps::model::publication::parse_pmids;
+///
+/// assert_eq!(parse_pmids("123|456, 789;123"), vec![123, 456, 789]);
+/// assert_eq!(parse_pmids("PMID:abc"), Vec::<i64>::new());
+/// ```
+pub fn parse_pmids(pmids: &str) -> Vec<i64> {
+    let mut results: Vec<i64> = vec![];
+    for pmid in pmids.split(|c: char| c == '|' || c == ',' || c == ';' || c.is_whitespace()) {
+        if let Ok(pmid) = pmid.trim().parse::<i64>() {
+            if pmid > 0 && !results.contains(&pmid) {
+                results.push(pmid);
+            }
+        }
+    }
+
+    results
+}
+
+/// Parse the response of the esummary api (retmode=json), the pmids which are not in the response are ignored.
+pub fn parse_esummary(response: &serde_json::Value) -> Vec<PublicationSummary> {
+    let result = match response.get("result") {
+        Some(result) => result,
+        None => return vec![],
+    };
+
+    let uids = match result.get("uids").and_then(|uids| uids.as_array()) {
+        Some(uids) => uids,
+        None => return vec![],
+    };
+
+    uids.iter()
+        .filter_map(|uid| uid.as_str())
+        .filter_map(|uid| {
+            let pmid = uid.parse::<i64>().ok()?;
+            let record = result.get(uid)?;
+            // The record of an invalid pmid only contains the error field.
+            if record.get("error").is_some() {
+                return None;
+            }
+
+            let get_str = |key: &str| {
+                record
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string()
+            };
+
+            let journal = match get_str("fulljournalname") {
+                journal if journal.is_empty() => get_str("source"),
+                journal => journal,
+            };
+
+            // The pubdate looks like `2020 Jan 5` or `2020`.
+            let year = get_str("pubdate")
+                .split_whitespace()
+                .next()
+                .and_then(|year| year.parse::<i32>().ok());
+
+            Some(PublicationSummary {
+                pmid,
+                title: get_str("title"),
+                journal,
+                year,
+            })
+        })
+        .collect()
+}
+
+/// An on-disk cache for the publication summaries.
+pub struct PublicationCache {
+    dir: PathBuf,
+}
+
+impl PublicationCache {
+    pub fn new(dir: PathBuf) -> Self {
+        PublicationCache { dir }
+    }
+
+    /// The cache directory is read from the environment variable PUBMED_CACHE_DIR.
+    pub fn from_env() -> Self {
+        let dir = match std::env::var("PUBMED_CACHE_DIR") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => std::env::temp_dir().join("biomedgps").join("pubmed"),
+        };
+
+        PublicationCache::new(dir)
+    }
+
+    fn filepath(&self, pmid: i64) -> PathBuf {
+        self.dir.join(format!("{}.json", pmid))
+    }
+
+    pub fn get(&self, pmid: i64) -> Option<PublicationSummary> {
+        let content = std::fs::read_to_string(self.filepath(pmid)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn put(&self, publication: &PublicationSummary) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string(publication)?;
+        std::fs::write(self.filepath(publication.pmid), content)?;
+        AnyOk(())
+    }
+}
+
+/// Fetch the summaries of the publications from the PubMed E-utilities, the cached summaries are returned directly.
+///
+/// The results keep the order of the pmids, and the pmids which cannot be found in PubMed are ignored.
+pub async fn fetch_publication_summaries(
+    pmids: &[i64],
+) -> Result<Vec<PublicationSummary>, anyhow::Error> {
+    let cache = PublicationCache::from_env();
+    let mut publications: Vec<PublicationSummary> = vec![];
+    let missing_pmids = pmids
+        .iter()
+        .filter(|pmid| match cache.get(**pmid) {
+            Some(publication) => {
+                publications.push(publication);
+                false
+            }
+            None => true,
+        })
+        .map(|pmid| pmid.to_string())
+        .collect::<Vec<String>>();
+
+    if !missing_pmids.is_empty() {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT))
+            .build()?;
+        let api_key = std::env::var("NCBI_API_KEY").ok().filter(|v| !v.is_empty());
+
+        for chunk in missing_pmids.chunks(MAX_IDS_PER_REQUEST) {
+            wait_for_request_slot().await;
+            let mut params = vec![
+                ("db", "pubmed".to_string()),
+                ("retmode", "json".to_string()),
+                ("id", chunk.join(",")),
+            ];
+            if let Some(api_key) = &api_key {
+                params.push(("api_key", api_key.clone()));
+            }
+
+            debug!("Fetch the publication summaries of {:?}.", chunk);
+            let response = client
+                .get(ESUMMARY_URL)
+                .query(&params)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<serde_json::Value>()
+                .await?;
+
+            for publication in parse_esummary(&response) {
+                if let Err(e) = cache.put(&publication) {
+                    warn!(
+                        "Failed to cache the publication {}: {}",
+                        publication.pmid, e
+                    );
+                }
+                publications.push(publication);
+            }
+        }
+    }
+
+    publications.sort_by_key(|publication| {
+        pmids
+            .iter()
+            .position(|pmid| *pmid == publication.pmid)
+            .unwrap_or(usize::MAX)
+    });
+
+    AnyOk(publications)
+}
+
+/// The metadata of a publication which is stored in the biomedgps_publication table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct Publication {
+    pub pmid: i64,
+    pub title: String,
+    pub journal: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub year: Option<i32>,
+    #[oai(skip_serializing_if_is_none)]
+    pub abstract_text: Option<String>,
+    #[serde(with = "ts_seconds")]
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Remove the inline tags (such as `<i>`) and unescape the xml entities.
+fn clean_xml_text(text: &str) -> String {
+    let text = XML_TAG_REGEX.replace_all(text, "");
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Parse the response of the efetch api (retmode=xml), one publication per PubmedArticle.
+pub fn parse_efetch(xml: &str) -> Vec<Publication> {
+    let fetched_at = Utc::now();
+    ARTICLE_REGEX
+        .find_iter(xml)
+        .filter_map(|article| {
+            let article = article.as_str();
+            let pmid = PMID_REGEX.captures(article)?[1].parse::<i64>().ok()?;
+            let get_text = |regex: &Regex| {
+                regex
+                    .captures(article)
+                    .map(|caps| clean_xml_text(&caps[1]))
+                    .unwrap_or_default()
+            };
+
+            // The structured abstract has several sections, such as BACKGROUND and METHODS.
+            let abstract_text = ABSTRACT_TEXT_REGEX
+                .captures_iter(article)
+                .map(|caps| match caps.get(1) {
+                    Some(label) => format!("{}: {}", label.as_str(), clean_xml_text(&caps[2])),
+                    None => clean_xml_text(&caps[2]),
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            Some(Publication {
+                pmid,
+                title: get_text(&ARTICLE_TITLE_REGEX),
+                journal: get_text(&JOURNAL_TITLE_REGEX),
+                year: PUB_YEAR_REGEX
+                    .captures(article)
+                    .and_then(|caps| caps[1].parse::<i32>().ok()),
+                abstract_text: if abstract_text.is_empty() {
+                    None
+                } else {
+                    Some(abstract_text)
+                },
+                fetched_at,
+            })
+        })
+        .collect()
+}
+
+fn get_ncbi_api_key() -> Option<String> {
+    std::env::var("NCBI_API_KEY").ok().filter(|v| !v.is_empty())
+}
+
+/// The E-utilities allow 3 requests per second without an api key and 10 requests per second with an api key.
+fn get_request_interval() -> Duration {
+    match get_ncbi_api_key() {
+        Some(_) => Duration::from_millis(100),
+        None => Duration::from_millis(340),
+    }
+}
+
+/// Wait until the E-utilities can be requested again, the concurrent callers are queued, so the requests are never sent faster than the rate limit.
+async fn wait_for_request_slot() {
+    let mut last_request = LAST_REQUEST.lock().await;
+    if let Some(last_request) = *last_request {
+        let interval = get_request_interval();
+        let elapsed = last_request.elapsed();
+        if elapsed < interval {
+            tokio::time::sleep(interval - elapsed).await;
+        }
+    }
+    *last_request = Some(Instant::now());
+}
+
+/// Fetch the full metadata of the publications from the PubMed E-utilities, the pmids which cannot be found in PubMed are ignored. The requests wait for the shared throttle of the E-utilities.
+pub async fn fetch_publications(pmids: &[i64]) -> Result<Vec<Publication>, anyhow::Error> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT))
+        .build()?;
+    let api_key = get_ncbi_api_key();
+    let mut publications: Vec<Publication> = vec![];
+
+    for chunk in pmids.chunks(MAX_IDS_PER_REQUEST) {
+        wait_for_request_slot().await;
+
+        let ids = chunk
+            .iter()
+            .map(|pmid| pmid.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let mut params = vec![
+            ("db", "pubmed".to_string()),
+            ("retmode", "xml".to_string()),
+            ("id", ids),
+        ];
+        if let Some(api_key) = &api_key {
+            params.push(("api_key", api_key.clone()));
+        }
+
+        debug!("Fetch the publications of {:?}.", chunk);
+        let response = client
+            .get(EFETCH_URL)
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        publications.extend(parse_efetch(&response));
+    }
+
+    AnyOk(publications)
+}
+
+impl Publication {
+    /// Fetch a publication by pmid, return None if it doesn't exist.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        pmid: i64,
+    ) -> Result<Option<Publication>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_publication WHERE pmid = $1";
+        let record = sqlx::query_as::<_, Publication>(sql_str)
+            .bind(pmid)
+            .fetch_optional(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Find the pmids which are not in the biomedgps_publication table.
+    pub async fn get_missing_pmids(
+        pool: &sqlx::PgPool,
+        pmids: &[i64],
+    ) -> Result<Vec<i64>, anyhow::Error> {
+        let sql_str = "SELECT pmid FROM biomedgps_publication WHERE pmid = ANY($1)";
+        let existing_pmids: Vec<i64> = sqlx::query_scalar(sql_str)
+            .bind(pmids)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(
+            pmids
+                .iter()
+                .filter(|pmid| !existing_pmids.contains(pmid))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Insert the publication or update it if the pmid already exists.
+    pub async fn upsert(&self, pool: &sqlx::PgPool) -> Result<Publication, anyhow::Error> {
+        let sql_str = "INSERT INTO biomedgps_publication (pmid, title, journal, year, abstract_text, fetched_at)
+                       VALUES ($1, $2, $3, $4, $5, $6)
+                       ON CONFLICT (pmid) DO UPDATE SET title = $2, journal = $3, year = $4, abstract_text = $5, fetched_at = $6
+                       RETURNING *";
+        let publication = sqlx::query_as::<_, Publication>(sql_str)
+            .bind(self.pmid)
+            .bind(&self.title)
+            .bind(&self.journal)
+            .bind(self.year)
+            .bind(&self.abstract_text)
+            .bind(self.fetched_at)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(publication)
+    }
+
+    /// Fetch the publications which are not in the database from PubMed and save them.
+    pub async fn sync_records(pool: &sqlx::PgPool, pmids: &[i64]) -> Result<usize, anyhow::Error> {
+        let missing_pmids = Publication::get_missing_pmids(pool, pmids).await?;
+        if missing_pmids.is_empty() {
+            return AnyOk(0);
+        }
+
+        let publications = fetch_publications(&missing_pmids).await?;
+        for publication in publications.iter() {
+            publication.upsert(pool).await?;
+        }
+
+        AnyOk(publications.len())
+    }
+
+    /// Find the pmids of the relations and the curated knowledges which are not in the biomedgps_publication table, at most `limit` pmids are returned.
+    pub async fn get_unresolved_pmids(
+        pool: &sqlx::PgPool,
+        limit: usize,
+    ) -> Result<Vec<i64>, anyhow::Error> {
+        // The pmids of a relation are separated by `|`, `,`, `;` or whitespace, same as `parse_pmids`.
+        let sql_str = "SELECT t.pmid FROM (
+                           SELECT pmid FROM biomedgps_knowledge_curation WHERE deleted_at IS NULL
+                           UNION
+                           SELECT CAST(item AS BIGINT) FROM biomedgps_relation,
+                           regexp_split_to_table(pmids, '[|,;[:space:]]+') AS item
+                           WHERE item ~ '^[0-9]{1,18}$'
+                       ) AS t(pmid)
+                       WHERE t.pmid > 0 AND NOT EXISTS (SELECT 1 FROM biomedgps_publication p WHERE p.pmid = t.pmid)
+                       ORDER BY t.pmid LIMIT $1";
+        let pmids: Vec<i64> = sqlx::query_scalar(sql_str)
+            .bind(limit as i64)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(pmids)
+    }
+
+    /// Fetch the publications of the unresolved pmids (see [`Publication::get_unresolved_pmids`]) from PubMed and save them, the requests wait for the shared throttle of the E-utilities.
+    pub async fn sync_unresolved_records(
+        pool: &sqlx::PgPool,
+        limit: usize,
+    ) -> Result<usize, anyhow::Error> {
+        let pmids = Publication::get_unresolved_pmids(pool, limit).await?;
+        let mut count = 0;
+        for chunk in pmids.chunks(MAX_IDS_PER_REQUEST) {
+            count += Publication::sync_records(pool, chunk).await?;
+        }
+
+        AnyOk(count)
+    }
+}
+
+/// Start the background fetcher which resolves the enqueued pmids and saves the publications into the database. The pmids are fetched in batches and the requests wait for the shared throttle of the E-utilities.
+pub fn start_publication_fetcher(pool: Arc<sqlx::PgPool>) {
+    let (sender, mut receiver) = unbounded_channel::<i64>();
+    *FETCHER.lock().unwrap() = Some(sender);
+
+    tokio::spawn(async move {
+        info!("The publication fetcher is started.");
+        while let Some(pmid) = receiver.recv().await {
+            let mut pmids = vec![pmid];
+            while pmids.len() < MAX_IDS_PER_REQUEST {
+                match receiver.try_recv() {
+                    Ok(pmid) if !pmids.contains(&pmid) => pmids.push(pmid),
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+
+            match Publication::sync_records(&pool, &pmids).await {
+                Ok(count) => debug!("Fetched {} publications of {:?}.", count, pmids),
+                Err(e) => error!("Failed to fetch the publications of {:?}: {}", pmids, e),
+            }
+        }
+    });
+}
+
+/// Enqueue the pmids for the background fetcher, it does nothing if the fetcher is not started.
+pub fn enqueue_publications(pmids: &[i64]) {
+    match FETCHER.lock().unwrap().as_ref() {
+        Some(sender) => {
+            for pmid in pmids.iter().filter(|pmid| **pmid > 0) {
+                if let Err(e) = sender.send(*pmid) {
+                    warn!("Failed to enqueue the publication {}: {}", pmid, e);
+                }
+            }
+        }
+        None => debug!("The publication fetcher is not started, skip {:?}.", pmids),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_throttle() {
+        let started_at = Instant::now();
+        for _ in 0..3 {
+            wait_for_request_slot().await;
+        }
+        // The first request may go at once, the others wait for the interval.
+        assert!(started_at.elapsed() >= get_request_interval() * 2);
+    }
+
+    #[test]
+    fn test_parse_esummary() {
+        let response = serde_json::json!({
+            "result": {
+                "uids": ["31452104", "1"],
+                "31452104": {
+                    "uid": "31452104",
+                    "pubdate": "2019 Aug 26",
+                    "source": "Nat Commun",
+                    "fulljournalname": "Nature communications",
+                    "title": "A title."
+                },
+                "1": {
+                    "uid": "1",
+                    "error": "cannot get document summary"
+                }
+            }
+        });
+
+        let publications = parse_esummary(&response);
+        assert_eq!(publications.len(), 1);
+        assert_eq!(publications[0].pmid, 31452104);
+        assert_eq!(publications[0].journal, "Nature communications");
+        assert_eq!(publications[0].year, Some(2019));
+        assert!(parse_esummary(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_publication_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PublicationCache::new(dir.path().join("pubmed"));
+        let publication = PublicationSummary {
+            pmid: 123,
+            title: "A title.".to_string(),
+            journal: "A journal".to_string(),
+            year: None,
+        };
+
+        assert!(cache.get(123).is_none());
+        cache.put(&publication).unwrap();
+        assert_eq!(cache.get(123), Some(publication));
+    }
+
+    #[test]
+    fn test_parse_efetch() {
+        let xml = r#"<?xml version="1.0" ?>
+<PubmedArticleSet>
+<PubmedArticle>
+  <MedlineCitation Status="MEDLINE" Owner="NLM">
+    <PMID Version="1">31452104</PMID>
+    <Article PubModel="Electronic">
+      <Journal>
+        <JournalIssue CitedMedium="Internet">
+          <PubDate><Year>2019</Year><Month>Aug</Month></PubDate>
+        </JournalIssue>
+        <Title>Nature communications</Title>
+      </Journal>
+      <ArticleTitle>A <i>title</i> &amp; more.</ArticleTitle>
+      <Abstract>
+        <AbstractText Label="BACKGROUND" NlmCategory="BACKGROUND">Some background.</AbstractText>
+        <AbstractText Label="RESULTS" NlmCategory="RESULTS">Some results.</AbstractText>
+      </Abstract>
+    </Article>
+  </MedlineCitation>
+</PubmedArticle>
+<PubmedArticle>
+  <MedlineCitation><PMID Version="1">123</PMID>
+    <Article><Journal><JournalIssue><PubDate><MedlineDate>1998 Dec-1999 Jan</MedlineDate></PubDate></JournalIssue><Title>J</Title></Journal>
+    <ArticleTitle>No abstract.</ArticleTitle></Article>
+  </MedlineCitation>
+</PubmedArticle>
+</PubmedArticleSet>"#;
+
+        let publications = parse_efetch(xml);
+        assert_eq!(publications.len(), 2);
+        assert_eq!(publications[0].pmid, 31452104);
+        assert_eq!(publications[0].title, "A title & more.");
+        assert_eq!(publications[0].journal, "Nature communications");
+        assert_eq!(publications[0].year, Some(2019));
+        assert_eq!(
+            publications[0].abstract_text,
+            Some("BACKGROUND: Some background.\nRESULTS: Some results.".to_string())
+        );
+        assert_eq!(publications[1].year, Some(1998));
+        assert_eq!(publications[1].abstract_text, None);
+    }
+}
@@ -0,0 +1,825 @@
+//! QC module checks the quality of the knowledge graph before a release, the issues which are hand-checked before (such as the relations of the missing entities and the relation types whose entity types are wrong) are collected into a structured report, which can be written as JSON (for the release scripts) or HTML (for reviewing in a browser).
+//!
+//! The report contains the following sections:
+//!
+//! * Dangling relations - the relations whose source or target entities don't exist, they can be deleted by `biomedgps-cli cleandb`.
+//! * Isolated entities - the entities which are not in any relation.
+//! * Relation type violations - the relations whose entity types are not the start and end entity types of their relation types, such as a `Gene:Disease` relation of the `DRUGBANK::treats::Compound:Disease` relation type.
+//! * Duplicate triples - the relations which are only different in the case of the ids or the relation types (the exact duplicates are rejected by the unique constraint of the relation table), and the relations which are imported in both directions.
+//! * Score distributions - the distribution of the scores of each resource, so a resource whose scores are missing or in another range can be found.
+//! * ID prefixes - the prefixes of the entity ids of each label, the prefixes which are only different in case from another prefix (such as `Mesh` and `MESH`) or which are rarely used by the label are flagged, and the ids which don't match the id pattern are sampled.
+
+use crate::model::core::ENTITY_ID_REGEX;
+use crate::model::util::missing_entity;
+use anyhow::Ok as AnyOk;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The default number of the sampled rows of each section.
+pub const DEFAULT_SAMPLE_SIZE: usize = 20;
+/// The prefixes whose fractions of a label are lower than it are flagged as rare prefixes.
+pub const RARE_PREFIX_FRACTION: f64 = 0.01;
+
+/// The format of a QC report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QcReportFormat {
+    Json,
+    Html,
+}
+
+impl FromStr for QcReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(QcReportFormat::Json),
+            "html" => Ok(QcReportFormat::Html),
+            _ => Err(format!("Invalid format: {}, it must be json or html.", s)),
+        }
+    }
+}
+
+/// A relation which is sampled by a section of the report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QcRelation {
+    pub id: i64,
+    pub relation_type: String,
+    pub source_id: String,
+    pub source_type: String,
+    pub target_id: String,
+    pub target_type: String,
+    pub resource: String,
+}
+
+/// An entity which is sampled by a section of the report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QcEntity {
+    pub id: String,
+    pub label: String,
+    pub name: String,
+    pub resource: String,
+}
+
+/// The relations whose source or target entities don't exist.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct DanglingRelations {
+    pub count: i64,
+    pub samples: Vec<QcRelation>,
+}
+
+/// The entities which are not in any relation, they are counted by label.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IsolatedEntities {
+    pub count: i64,
+    pub counts_by_label: HashMap<String, i64>,
+    pub samples: Vec<QcEntity>,
+}
+
+/// A group of the relations whose entity types are not the start and end entity types of their relation type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationTypeViolation {
+    pub relation_type: String,
+    /// The start and end entity types of the relation type, they are parsed from the last segment of the relation type, such as `Compound:Disease` of `DRUGBANK::treats::Compound:Disease`. They are None if the relation type doesn't end with the entity types.
+    pub expected_start_type: Option<String>,
+    pub expected_end_type: Option<String>,
+    pub source_type: String,
+    pub target_type: String,
+    pub count: i64,
+}
+
+/// The kind of a duplicate triple.
+///
+/// * `Case` - The relations are only different in the case of the ids or the relation types, such as `MESH:D001` and `mesh:D001`.
+/// * `Reversed` - The relations have the same relation type and the swapped source and target entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    Case,
+    Reversed,
+}
+
+/// A group of the relations which are the same triple.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateTriple {
+    pub kind: DuplicateKind,
+    pub relation_type: String,
+    pub source_id: String,
+    pub source_type: String,
+    pub target_id: String,
+    pub target_type: String,
+    /// The number of the relations of the triple.
+    pub count: i64,
+}
+
+/// The duplicate triples, the counts are the numbers of the relations which are redundant (i.e. the relations of a triple except one).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct DuplicateTriples {
+    pub case_count: i64,
+    pub reversed_count: i64,
+    pub samples: Vec<DuplicateTriple>,
+}
+
+/// The distribution of the scores of a resource, the statistics are None if no relation of the resource has a score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScoreDistribution {
+    pub resource: String,
+    pub relation_count: i64,
+    pub scored_count: i64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub p25: Option<f64>,
+    pub median: Option<f64>,
+    pub p75: Option<f64>,
+}
+
+/// The number of the entities of a label whose ids have the prefix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdPrefixCount {
+    pub label: String,
+    pub prefix: String,
+    pub count: i64,
+    /// The fraction of the entities of the label.
+    pub fraction: f64,
+    /// Why the prefix is flagged, it is None if the prefix looks consistent.
+    pub issue: Option<String>,
+}
+
+/// The prefixes of the entity ids and the ids which don't match the id pattern.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IdPrefixes {
+    pub prefixes: Vec<IdPrefixCount>,
+    pub invalid_id_count: i64,
+    pub invalid_id_samples: Vec<QcEntity>,
+}
+
+/// The QC report of the knowledge graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QcReport {
+    pub generated_at: String,
+    pub entity_count: i64,
+    pub relation_count: i64,
+    pub dangling_relations: DanglingRelations,
+    pub isolated_entities: IsolatedEntities,
+    pub relation_type_violations: Vec<RelationTypeViolation>,
+    pub duplicate_triples: DuplicateTriples,
+    pub score_distributions: Vec<ScoreDistribution>,
+    pub id_prefixes: IdPrefixes,
+}
+
+/// Parse the start and end entity types from the last segment of a relation type, such as `("Compound", "Disease")` of `DRUGBANK::treats::Compound:Disease`.
+///
+/// ```
+/// use biomedgps::model::qc::parse_relation_entity_types;
+///
+/// assert_eq!(
+///     parse_relation_entity_types("DRUGBANK::treats::Compound:Disease"),
+///     Some(("Compound".to_string(), "Disease".to_string()))
+/// );
+/// assert_eq!(parse_relation_entity_types("treats"), None);
+/// ```
+pub fn parse_relation_entity_types(relation_type: &str) -> Option<(String, String)> {
+    let (_, types) = relation_type.rsplit_once("::")?;
+    let (start_type, end_type) = types.split_once(':')?;
+    if start_type.is_empty() || end_type.is_empty() || end_type.contains(':') {
+        return None;
+    }
+
+    Some((start_type.to_string(), end_type.to_string()))
+}
+
+/// Find the relation types whose relations have other entity types than the entity types in their names. The groups are the (relation_type, source_type, target_type) groups of the relations with their counts.
+pub fn find_relation_type_violations(
+    groups: Vec<(String, String, String, i64)>,
+) -> Vec<RelationTypeViolation> {
+    let mut violations = groups
+        .into_iter()
+        .filter_map(|(relation_type, source_type, target_type, count)| {
+            let expected = parse_relation_entity_types(&relation_type);
+            match &expected {
+                Some((start_type, end_type))
+                    if *start_type == source_type && *end_type == target_type =>
+                {
+                    None
+                }
+                _ => Some(RelationTypeViolation {
+                    relation_type,
+                    expected_start_type: expected.as_ref().map(|(s, _)| s.clone()),
+                    expected_end_type: expected.map(|(_, e)| e),
+                    source_type,
+                    target_type,
+                    count,
+                }),
+            }
+        })
+        .collect::<Vec<RelationTypeViolation>>();
+
+    violations.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.relation_type.cmp(&b.relation_type))
+    });
+    violations
+}
+
+/// Compute the fractions of the (label, prefix, count) rows and flag the prefixes which are only different in case from a more common prefix of the same label, or whose fractions are lower than [`RARE_PREFIX_FRACTION`].
+pub fn check_id_prefixes(rows: Vec<(String, String, i64)>) -> Vec<IdPrefixCount> {
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    // The most common spelling of each prefix of a label, such as MESH for MESH, Mesh and mesh.
+    let mut major_spellings: HashMap<(String, String), (String, i64)> = HashMap::new();
+    for (label, prefix, count) in rows.iter() {
+        *totals.entry(label.clone()).or_insert(0) += count;
+        let key = (label.clone(), prefix.to_uppercase());
+        let major = major_spellings
+            .entry(key)
+            .or_insert_with(|| (prefix.clone(), *count));
+        if *count > major.1 || (*count == major.1 && *prefix < major.0) {
+            *major = (prefix.clone(), *count);
+        }
+    }
+
+    let mut prefixes = rows
+        .into_iter()
+        .map(|(label, prefix, count)| {
+            let total = totals.get(&label).copied().unwrap_or(0).max(1);
+            let fraction = count as f64 / total as f64;
+            let major = &major_spellings[&(label.clone(), prefix.to_uppercase())].0;
+            let issue = if *major != prefix {
+                Some(format!("Same as the prefix {} in a different case", major))
+            } else if fraction < RARE_PREFIX_FRACTION {
+                Some(format!(
+                    "Used by less than {}% of the {} entities",
+                    RARE_PREFIX_FRACTION * 100.0,
+                    label
+                ))
+            } else {
+                None
+            };
+
+            IdPrefixCount {
+                label,
+                prefix,
+                count,
+                fraction,
+                issue,
+            }
+        })
+        .collect::<Vec<IdPrefixCount>>();
+
+    prefixes.sort_by(|a, b| a.label.cmp(&b.label).then_with(|| b.count.cmp(&a.count)));
+    prefixes
+}
+
+/// Escape the special characters of a text in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_score(value: Option<f64>) -> String {
+    match value {
+        Some(v) => format!("{:.4}", v),
+        None => "-".to_string(),
+    }
+}
+
+/// Render an HTML table, the cells are escaped.
+fn html_table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    if rows.is_empty() {
+        return "<p class=\"ok\">No issues found.</p>\n".to_string();
+    }
+
+    let mut html = String::from("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(html, "<th>{}</th>", escape_html(header));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape_html(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn relation_rows(relations: &[QcRelation]) -> Vec<Vec<String>> {
+    relations
+        .iter()
+        .map(|r| {
+            vec![
+                r.id.to_string(),
+                r.relation_type.clone(),
+                format!("{}::{}", r.source_type, r.source_id),
+                format!("{}::{}", r.target_type, r.target_id),
+                r.resource.clone(),
+            ]
+        })
+        .collect()
+}
+
+fn entity_rows(entities: &[QcEntity]) -> Vec<Vec<String>> {
+    entities
+        .iter()
+        .map(|e| {
+            vec![
+                e.id.clone(),
+                e.label.clone(),
+                e.name.clone(),
+                e.resource.clone(),
+            ]
+        })
+        .collect()
+}
+
+impl QcReport {
+    /// Check the entity and relation tables and collect the issues, at most sample_size rows are sampled for each section. It scans the whole tables several times, so it may take minutes on a large graph.
+    pub async fn generate(
+        pool: &sqlx::PgPool,
+        sample_size: usize,
+    ) -> Result<QcReport, anyhow::Error> {
+        let limit = sample_size as i64;
+
+        let (entity_count,) = sqlx::query_as::<_, (i64,)>("SELECT count(*) FROM biomedgps_entity")
+            .fetch_one(pool)
+            .await?;
+        let (relation_count,) =
+            sqlx::query_as::<_, (i64,)>("SELECT count(*) FROM biomedgps_relation")
+                .fetch_one(pool)
+                .await?;
+
+        info!("Checking the dangling relations ...");
+        let dangling_condition = format!(
+            "{} OR {}",
+            missing_entity("source_id", "source_type"),
+            missing_entity("target_id", "target_type")
+        );
+        let (dangling_count,) = sqlx::query_as::<_, (i64,)>(&format!(
+            "SELECT count(*) FROM biomedgps_relation AS t WHERE {}",
+            dangling_condition
+        ))
+        .fetch_one(pool)
+        .await?;
+        let dangling_samples = sqlx::query_as::<_, QcRelation>(&format!(
+            "SELECT id, relation_type, source_id, source_type, target_id, target_type, resource
+             FROM biomedgps_relation AS t WHERE {} ORDER BY id LIMIT $1",
+            dangling_condition
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        info!("Checking the isolated entities ...");
+        let isolated_condition = "
+            NOT EXISTS (SELECT 1 FROM biomedgps_relation r WHERE r.source_id = e.id AND r.source_type = e.label)
+            AND NOT EXISTS (SELECT 1 FROM biomedgps_relation r WHERE r.target_id = e.id AND r.target_type = e.label)";
+        let isolated_counts = sqlx::query_as::<_, (String, i64)>(&format!(
+            "SELECT label, count(*) FROM biomedgps_entity AS e WHERE {} GROUP BY label",
+            isolated_condition
+        ))
+        .fetch_all(pool)
+        .await?;
+        let isolated_samples = sqlx::query_as::<_, QcEntity>(&format!(
+            "SELECT id, label, name, resource FROM biomedgps_entity AS e WHERE {} ORDER BY label, id LIMIT $1",
+            isolated_condition
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        info!("Checking the entity types of the relation types ...");
+        let groups = sqlx::query_as::<_, (String, String, String, i64)>(
+            "SELECT relation_type, source_type, target_type, count(*)
+             FROM biomedgps_relation GROUP BY relation_type, source_type, target_type",
+        )
+        .fetch_all(pool)
+        .await?;
+        let relation_type_violations = find_relation_type_violations(groups);
+
+        info!("Checking the duplicate triples ...");
+        let duplicate_triples = Self::check_duplicates(pool, limit).await?;
+
+        info!("Computing the score distributions ...");
+        let score_distributions = sqlx::query_as::<_, ScoreDistribution>(
+            "SELECT resource, count(*) AS relation_count, count(score) AS scored_count,
+                    min(score) AS min, max(score) AS max, avg(score) AS mean, stddev_pop(score) AS stddev,
+                    percentile_cont(0.25) WITHIN GROUP (ORDER BY score) AS p25,
+                    percentile_cont(0.5) WITHIN GROUP (ORDER BY score) AS median,
+                    percentile_cont(0.75) WITHIN GROUP (ORDER BY score) AS p75
+             FROM biomedgps_relation GROUP BY resource ORDER BY resource",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        info!("Checking the id prefixes ...");
+        let prefix_rows = sqlx::query_as::<_, (String, String, i64)>(
+            "SELECT label, split_part(id, ':', 1) AS prefix, count(*)
+             FROM biomedgps_entity GROUP BY label, prefix",
+        )
+        .fetch_all(pool)
+        .await?;
+        // The ENTITY_ID_REGEX is also a valid POSIX regex of postgres.
+        let (invalid_id_count,) =
+            sqlx::query_as::<_, (i64,)>("SELECT count(*) FROM biomedgps_entity WHERE id !~ $1")
+                .bind(ENTITY_ID_REGEX.as_str())
+                .fetch_one(pool)
+                .await?;
+        let invalid_id_samples = sqlx::query_as::<_, QcEntity>(
+            "SELECT id, label, name, resource FROM biomedgps_entity WHERE id !~ $1 ORDER BY label, id LIMIT $2",
+        )
+        .bind(ENTITY_ID_REGEX.as_str())
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(QcReport {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            entity_count,
+            relation_count,
+            dangling_relations: DanglingRelations {
+                count: dangling_count,
+                samples: dangling_samples,
+            },
+            isolated_entities: IsolatedEntities {
+                count: isolated_counts.iter().map(|(_, count)| count).sum(),
+                counts_by_label: isolated_counts.into_iter().collect(),
+                samples: isolated_samples,
+            },
+            relation_type_violations,
+            duplicate_triples,
+            score_distributions,
+            id_prefixes: IdPrefixes {
+                prefixes: check_id_prefixes(prefix_rows),
+                invalid_id_count,
+                invalid_id_samples,
+            },
+        })
+    }
+
+    async fn check_duplicates(
+        pool: &sqlx::PgPool,
+        limit: i64,
+    ) -> Result<DuplicateTriples, anyhow::Error> {
+        // The exact duplicates are rejected by the unique constraint, so the relations are grouped case-insensitively.
+        let case_sql = "
+            SELECT min(relation_type), min(source_id), source_type, min(target_id), target_type, count(*) AS count
+            FROM biomedgps_relation
+            GROUP BY lower(relation_type), lower(source_id), source_type, lower(target_id), target_type
+            HAVING count(*) > 1";
+        let (case_count,) = sqlx::query_as::<_, (Option<i64>,)>(&format!(
+            "SELECT sum(count - 1)::BIGINT FROM ({}) AS d",
+            case_sql
+        ))
+        .fetch_one(pool)
+        .await?;
+        let case_samples = sqlx::query_as::<_, (String, String, String, String, String, i64)>(
+            &format!("{} ORDER BY count DESC LIMIT $1", case_sql),
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        // The joins are covered by the unique index of the relation table.
+        let reversed_sql = "
+            SELECT a.relation_type, a.source_id, a.source_type, a.target_id, a.target_type
+            FROM biomedgps_relation a
+            JOIN biomedgps_relation b
+              ON b.relation_type = a.relation_type
+             AND b.source_id = a.target_id AND b.source_type = a.target_type
+             AND b.target_id = a.source_id AND b.target_type = a.source_type
+            WHERE a.id < b.id";
+        let (reversed_count,) =
+            sqlx::query_as::<_, (i64,)>(&format!("SELECT count(*) FROM ({}) AS d", reversed_sql))
+                .fetch_one(pool)
+                .await?;
+        let reversed_samples = sqlx::query_as::<_, (String, String, String, String, String)>(
+            &format!("{} ORDER BY a.id LIMIT $1", reversed_sql),
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        debug!(
+            "Found {} case and {} reversed duplicates.",
+            case_count.unwrap_or(0),
+            reversed_count
+        );
+
+        let samples = case_samples
+            .into_iter()
+            .map(
+                |(relation_type, source_id, source_type, target_id, target_type, count)| {
+                    DuplicateTriple {
+                        kind: DuplicateKind::Case,
+                        relation_type,
+                        source_id,
+                        source_type,
+                        target_id,
+                        target_type,
+                        count,
+                    }
+                },
+            )
+            .chain(reversed_samples.into_iter().map(
+                |(relation_type, source_id, source_type, target_id, target_type)| DuplicateTriple {
+                    kind: DuplicateKind::Reversed,
+                    relation_type,
+                    source_id,
+                    source_type,
+                    target_id,
+                    target_type,
+                    count: 2,
+                },
+            ))
+            .collect();
+
+        AnyOk(DuplicateTriples {
+            case_count: case_count.unwrap_or(0),
+            reversed_count,
+            samples,
+        })
+    }
+
+    /// The number of the issues of all sections. The isolated entities and the rare id prefixes are included, because they are often the symptoms of a partial import or a wrong id mapping.
+    pub fn issue_count(&self) -> i64 {
+        self.dangling_relations.count
+            + self.isolated_entities.count
+            + self
+                .relation_type_violations
+                .iter()
+                .map(|v| v.count)
+                .sum::<i64>()
+            + self.duplicate_triples.case_count
+            + self.duplicate_triples.reversed_count
+            + self
+                .id_prefixes
+                .prefixes
+                .iter()
+                .filter(|p| p.issue.is_some())
+                .count() as i64
+            + self.id_prefixes.invalid_id_count
+    }
+
+    /// Render the report as a self-contained HTML page.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>BioMedGPS QC Report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1em; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+             th { background: #f0f0f0; }\n\
+             .ok { color: #2e7d32; }\n\
+             .issue { color: #c62828; }\n\
+             </style>\n</head>\n<body>\n<h1>BioMedGPS QC Report</h1>\n",
+        );
+        let _ = writeln!(
+            html,
+            "<p>Generated at {}. {} entities, {} relations, <span class=\"{}\">{} issues</span>.</p>",
+            escape_html(&self.generated_at),
+            self.entity_count,
+            self.relation_count,
+            if self.issue_count() > 0 { "issue" } else { "ok" },
+            self.issue_count()
+        );
+
+        let _ = writeln!(
+            html,
+            "<h2>Dangling relations ({})</h2>",
+            self.dangling_relations.count
+        );
+        html.push_str(&html_table(
+            &["ID", "Relation Type", "Source", "Target", "Resource"],
+            relation_rows(&self.dangling_relations.samples),
+        ));
+
+        let _ = writeln!(
+            html,
+            "<h2>Isolated entities ({})</h2>",
+            self.isolated_entities.count
+        );
+        let mut isolated_counts = self
+            .isolated_entities
+            .counts_by_label
+            .iter()
+            .collect::<Vec<(&String, &i64)>>();
+        isolated_counts.sort();
+        html.push_str(&html_table(
+            &["Label", "Count"],
+            isolated_counts
+                .into_iter()
+                .map(|(label, count)| vec![label.clone(), count.to_string()])
+                .collect(),
+        ));
+        if !self.isolated_entities.samples.is_empty() {
+            html.push_str(&html_table(
+                &["ID", "Label", "Name", "Resource"],
+                entity_rows(&self.isolated_entities.samples),
+            ));
+        }
+
+        let _ = writeln!(
+            html,
+            "<h2>Relation type violations ({})</h2>",
+            self.relation_type_violations.len()
+        );
+        html.push_str(&html_table(
+            &[
+                "Relation Type",
+                "Expected Types",
+                "Source Type",
+                "Target Type",
+                "Count",
+            ],
+            self.relation_type_violations
+                .iter()
+                .map(|v| {
+                    vec![
+                        v.relation_type.clone(),
+                        match (&v.expected_start_type, &v.expected_end_type) {
+                            (Some(s), Some(e)) => format!("{}:{}", s, e),
+                            _ => "Unknown".to_string(),
+                        },
+                        v.source_type.clone(),
+                        v.target_type.clone(),
+                        v.count.to_string(),
+                    ]
+                })
+                .collect(),
+        ));
+
+        let _ = writeln!(
+            html,
+            "<h2>Duplicate triples ({} in case, {} reversed)</h2>",
+            self.duplicate_triples.case_count, self.duplicate_triples.reversed_count
+        );
+        html.push_str(&html_table(
+            &["Kind", "Relation Type", "Source", "Target", "Count"],
+            self.duplicate_triples
+                .samples
+                .iter()
+                .map(|d| {
+                    vec![
+                        match d.kind {
+                            DuplicateKind::Case => "case".to_string(),
+                            DuplicateKind::Reversed => "reversed".to_string(),
+                        },
+                        d.relation_type.clone(),
+                        format!("{}::{}", d.source_type, d.source_id),
+                        format!("{}::{}", d.target_type, d.target_id),
+                        d.count.to_string(),
+                    ]
+                })
+                .collect(),
+        ));
+
+        html.push_str("<h2>Score distributions</h2>\n");
+        html.push_str(&html_table(
+            &[
+                "Resource",
+                "Relations",
+                "Scored",
+                "Min",
+                "P25",
+                "Median",
+                "Mean",
+                "P75",
+                "Max",
+                "Stddev",
+            ],
+            self.score_distributions
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.resource.clone(),
+                        s.relation_count.to_string(),
+                        s.scored_count.to_string(),
+                        format_score(s.min),
+                        format_score(s.p25),
+                        format_score(s.median),
+                        format_score(s.mean),
+                        format_score(s.p75),
+                        format_score(s.max),
+                        format_score(s.stddev),
+                    ]
+                })
+                .collect(),
+        ));
+
+        let _ = writeln!(
+            html,
+            "<h2>ID prefixes ({} invalid ids)</h2>",
+            self.id_prefixes.invalid_id_count
+        );
+        html.push_str(&html_table(
+            &["Label", "Prefix", "Count", "Fraction", "Issue"],
+            self.id_prefixes
+                .prefixes
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.label.clone(),
+                        p.prefix.clone(),
+                        p.count.to_string(),
+                        format!("{:.2}%", p.fraction * 100.0),
+                        p.issue.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        ));
+        if !self.id_prefixes.invalid_id_samples.is_empty() {
+            html.push_str(&html_table(
+                &["ID", "Label", "Name", "Resource"],
+                entity_rows(&self.id_prefixes.invalid_id_samples),
+            ));
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_relation_type_violations() {
+        let violations = find_relation_type_violations(vec![
+            (
+                "DRUGBANK::treats::Compound:Disease".to_string(),
+                "Compound".to_string(),
+                "Disease".to_string(),
+                10,
+            ),
+            (
+                "DRUGBANK::treats::Compound:Disease".to_string(),
+                "Gene".to_string(),
+                "Disease".to_string(),
+                2,
+            ),
+            (
+                "treats".to_string(),
+                "Compound".to_string(),
+                "Disease".to_string(),
+                1,
+            ),
+        ]);
+
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].source_type, "Gene");
+        assert_eq!(
+            violations[0].expected_start_type.as_deref(),
+            Some("Compound")
+        );
+        assert_eq!(violations[1].relation_type, "treats");
+        assert_eq!(violations[1].expected_start_type, None);
+    }
+
+    #[test]
+    fn test_check_id_prefixes() {
+        let prefixes = check_id_prefixes(vec![
+            ("Disease".to_string(), "MESH".to_string(), 900),
+            ("Disease".to_string(), "Mesh".to_string(), 95),
+            ("Disease".to_string(), "DOID".to_string(), 5),
+            ("Gene".to_string(), "ENTREZ".to_string(), 10),
+        ]);
+
+        let issues = prefixes
+            .iter()
+            .map(|p| (p.prefix.as_str(), p.issue.is_some()))
+            .collect::<Vec<(&str, bool)>>();
+        assert_eq!(
+            issues,
+            vec![
+                ("MESH", false),
+                ("Mesh", true),
+                ("DOID", true),
+                ("ENTREZ", false)
+            ]
+        );
+        assert_eq!(prefixes[0].fraction, 0.9);
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">R&D's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;R&amp;D&#39;s&lt;/a&gt;"
+        );
+    }
+}
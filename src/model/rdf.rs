@@ -0,0 +1,427 @@
+//! RDF module exports the entities and relations of the knowledge graph as RDF triples in Turtle, N-Triples or JSON-LD, so the graph can be loaded into a triple store or merged with the other knowledge graphs.
+//!
+//! The entity ids (CURIEs such as `MESH:D008687`) are expanded to IRIs by a prefix map, the entities are typed by the BioLink classes of their labels and the relations use the BioLink predicates of their relation types (see [`BiolinkMapper`](../biolink/struct.BiolinkMapper.html)). The triples are streamed from database cursors, so the graph is never held in memory.
+
+use crate::model::biolink::BiolinkMapper;
+use anyhow::Ok as AnyOk;
+use futures::{Stream, StreamExt};
+use log::{debug, error, info};
+use poem_openapi::Enum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// The IRI of the CURIEs whose prefix is not in the prefix map, the CURIE is appended to it.
+pub const DEFAULT_BASE_IRI: &str = "https://identifiers.org/";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+// The max number of the serialized chunks which are waiting to be sent.
+const STREAM_CHANNEL_SIZE: usize = 256;
+
+/// The built-in prefixes, the BioLink classes and predicates are CURIEs of the biolink prefix.
+const BUILTIN_PREFIXES: [(&str, &str); 16] = [
+    ("biolink", "https://w3id.org/biolink/vocab/"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("MESH", "http://id.nlm.nih.gov/mesh/"),
+    ("ENTREZ", "https://www.ncbi.nlm.nih.gov/gene/"),
+    ("HGNC", "https://identifiers.org/hgnc:"),
+    ("UNIPROT", "http://purl.uniprot.org/uniprot/"),
+    ("DrugBank", "https://go.drugbank.com/drugs/"),
+    ("CHEBI", "http://purl.obolibrary.org/obo/CHEBI_"),
+    ("DOID", "http://purl.obolibrary.org/obo/DOID_"),
+    ("MONDO", "http://purl.obolibrary.org/obo/MONDO_"),
+    ("HP", "http://purl.obolibrary.org/obo/HP_"),
+    ("GO", "http://purl.obolibrary.org/obo/GO_"),
+    ("UBERON", "http://purl.obolibrary.org/obo/UBERON_"),
+    ("UMLS", "https://identifiers.org/umls:"),
+    ("REACTOME", "https://identifiers.org/reactome:"),
+];
+
+/// The RDF serialization of the exported knowledge graph.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RdfFormat {
+    /// Turtle, the BioLink classes and predicates are prefixed names.
+    Turtle,
+    /// N-Triples, one triple per line with the full IRIs.
+    NTriples,
+    /// JSON-LD, one node object per entity and per relation in the `@graph`.
+    JsonLd,
+}
+
+impl FromStr for RdfFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "turtle" | "ttl" => Ok(RdfFormat::Turtle),
+            "ntriples" | "nt" => Ok(RdfFormat::NTriples),
+            "jsonld" => Ok(RdfFormat::JsonLd),
+            _ => Err(format!(
+                "Invalid rdf format: {}, it must be turtle, ntriples or jsonld.",
+                s
+            )),
+        }
+    }
+}
+
+impl RdfFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "text/turtle",
+            RdfFormat::NTriples => "application/n-triples",
+            RdfFormat::JsonLd => "application/ld+json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "ttl",
+            RdfFormat::NTriples => "nt",
+            RdfFormat::JsonLd => "jsonld",
+        }
+    }
+}
+
+/// The map from the CURIE prefixes to the IRIs, it is in the same form as the `@context` of JSON-LD, such as `{"MESH": "http://id.nlm.nih.gov/mesh/"}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixMap {
+    prefixes: BTreeMap<String, String>,
+}
+
+impl Default for PrefixMap {
+    fn default() -> Self {
+        PrefixMap {
+            prefixes: BUILTIN_PREFIXES
+                .iter()
+                .map(|(prefix, iri)| (prefix.to_string(), iri.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl PrefixMap {
+    /// Read the prefixes from a JSON file, they are added to the built-in prefixes and override them.
+    pub fn from_file(filepath: &Path) -> Result<Self, anyhow::Error> {
+        let content = std::fs::read_to_string(filepath)?;
+        let prefixes: BTreeMap<String, String> = serde_json::from_str(&content)?;
+        let mut prefix_map = PrefixMap::default();
+        prefix_map.prefixes.extend(prefixes);
+
+        AnyOk(prefix_map)
+    }
+
+    /// Expand a CURIE to an IRI, the prefix is matched exactly first and then case-insensitively. The characters which are not allowed in an IRI are percent-encoded.
+    ///
+    /// ```
+    /// use biomedgps::model::rdf::PrefixMap;
+    ///
+    /// let prefixes = PrefixMap::default();
+    /// assert_eq!(prefixes.expand("MESH:D008687"), "http://id.nlm.nih.gov/mesh/D008687");
+    /// assert_eq!(prefixes.expand("Mesh:D008687"), "http://id.nlm.nih.gov/mesh/D008687");
+    /// assert_eq!(prefixes.expand("XYZ:1"), "https://identifiers.org/XYZ:1");
+    /// ```
+    pub fn expand(&self, curie: &str) -> String {
+        let iri = match curie.split_once(':') {
+            Some((prefix, local)) => {
+                let base = self.prefixes.get(prefix).or_else(|| {
+                    self.prefixes
+                        .iter()
+                        .find(|(p, _)| p.eq_ignore_ascii_case(prefix))
+                        .map(|(_, iri)| iri)
+                });
+                match base {
+                    Some(base) => format!("{}{}", base, local),
+                    None => format!("{}{}", DEFAULT_BASE_IRI, curie),
+                }
+            }
+            None => format!("{}{}", DEFAULT_BASE_IRI, curie),
+        };
+
+        escape_iri(&iri)
+    }
+
+    fn get(&self, prefix: &str) -> &str {
+        self.prefixes
+            .get(prefix)
+            .map(|iri| iri.as_str())
+            .unwrap_or_default()
+    }
+}
+
+fn escape_iri(iri: &str) -> String {
+    let mut escaped = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        if c <= ' ' || ['<', '>', '"', '{', '}', '|', '^', '`', '\\'].contains(&c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn escape_literal(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+/// Serialize the entities and relations in a format, the header goes first and the footer goes last. The BioLink classes and predicates are CURIEs such as `biolink:Gene`.
+#[derive(Debug, Clone)]
+pub struct RdfWriter {
+    format: RdfFormat,
+    prefixes: PrefixMap,
+    // Whether a node has been written, the JSON-LD nodes are separated by commas.
+    has_nodes: bool,
+}
+
+impl RdfWriter {
+    pub fn new(format: RdfFormat, prefixes: PrefixMap) -> Self {
+        RdfWriter {
+            format,
+            prefixes,
+            has_nodes: false,
+        }
+    }
+
+    pub fn header(&self) -> String {
+        match self.format {
+            RdfFormat::Turtle => {
+                ["biolink", "rdf", "rdfs"]
+                    .iter()
+                    .map(|prefix| {
+                        format!("@prefix {}: <{}> .\n", prefix, self.prefixes.get(prefix))
+                    })
+                    .collect::<String>()
+                    + "\n"
+            }
+            RdfFormat::NTriples => "".to_string(),
+            RdfFormat::JsonLd => {
+                let context = serde_json::json!({
+                    "biolink": self.prefixes.get("biolink"),
+                    "rdfs": self.prefixes.get("rdfs"),
+                });
+                format!("{{\"@context\": {}, \"@graph\": [\n", context)
+            }
+        }
+    }
+
+    pub fn footer(&self) -> String {
+        match self.format {
+            RdfFormat::JsonLd => "\n]}\n".to_string(),
+            _ => "".to_string(),
+        }
+    }
+
+    fn json_node(&mut self, node: serde_json::Value) -> String {
+        let separator = if self.has_nodes { ",\n" } else { "" };
+        self.has_nodes = true;
+        format!("{}{}", separator, node)
+    }
+
+    /// Serialize an entity as its BioLink class and its name.
+    ///
+    /// ```
+    /// use biomedgps::model::rdf::{PrefixMap, RdfFormat, RdfWriter};
+    ///
+    /// let mut writer = RdfWriter::new(RdfFormat::Turtle, PrefixMap::default());
+    /// assert_eq!(
+    ///     writer.entity("MESH:D008687", "Metformin", "biolink:ChemicalEntity"),
+    ///     "<http://id.nlm.nih.gov/mesh/D008687> a biolink:ChemicalEntity ;\n    rdfs:label \"Metformin\" .\n"
+    /// );
+    /// ```
+    pub fn entity(&mut self, id: &str, name: &str, category: &str) -> String {
+        let iri = self.prefixes.expand(id);
+        match self.format {
+            RdfFormat::Turtle => format!(
+                "<{}> a {} ;\n    rdfs:label \"{}\" .\n",
+                iri,
+                category,
+                escape_literal(name)
+            ),
+            RdfFormat::NTriples => format!(
+                "<{iri}> <{}> <{}> .\n<{iri}> <{}> \"{}\" .\n",
+                RDF_TYPE,
+                self.prefixes.expand(category),
+                RDFS_LABEL,
+                escape_literal(name),
+                iri = iri
+            ),
+            RdfFormat::JsonLd => self.json_node(serde_json::json!({
+                "@id": iri,
+                "@type": category,
+                "rdfs:label": name,
+            })),
+        }
+    }
+
+    /// Serialize a relation as a triple of the BioLink predicate.
+    pub fn relation(&mut self, source_id: &str, predicate: &str, target_id: &str) -> String {
+        let source = self.prefixes.expand(source_id);
+        let target = self.prefixes.expand(target_id);
+        match self.format {
+            RdfFormat::Turtle => format!("<{}> {} <{}> .\n", source, predicate, target),
+            RdfFormat::NTriples => format!(
+                "<{}> <{}> <{}> .\n",
+                source,
+                self.prefixes.expand(predicate),
+                target
+            ),
+            RdfFormat::JsonLd => {
+                let mut node = serde_json::Map::new();
+                node.insert("@id".to_string(), serde_json::json!(source));
+                node.insert(predicate.to_string(), serde_json::json!({ "@id": target }));
+                self.json_node(serde_json::Value::Object(node))
+            }
+        }
+    }
+}
+
+/// Send a chunk to the receiver, it returns false if the receiver is closed.
+async fn send_chunk(
+    sender: &mpsc::Sender<Result<Vec<u8>, std::io::Error>>,
+    chunk: Result<String, std::io::Error>,
+) -> bool {
+    sender.send(chunk.map(|c| c.into_bytes())).await.is_ok()
+}
+
+/// Stream the entities and relations of the knowledge graph as RDF, the entities go first and both are sorted by id, so the output is same for the same data.
+pub fn stream_rdf(
+    pool: Arc<sqlx::PgPool>,
+    format: RdfFormat,
+    prefixes: PrefixMap,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static {
+    let (sender, receiver) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        let to_io_error = |e: &dyn std::fmt::Display| {
+            error!("Failed to stream the rdf: {}", e);
+            std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+        };
+
+        let mapper = match BiolinkMapper::load(&pool).await {
+            Ok(mapper) => mapper,
+            Err(e) => {
+                send_chunk(&sender, Err(to_io_error(&e))).await;
+                return;
+            }
+        };
+
+        let mut writer = RdfWriter::new(format, prefixes);
+        if !send_chunk(&sender, Ok(writer.header())).await {
+            return;
+        }
+
+        let mut categories: HashMap<String, String> = HashMap::new();
+        let mut num_entities = 0;
+        let sql_str = "SELECT id, label, name FROM biomedgps_entity ORDER BY id";
+        let mut rows = sqlx::query_as::<_, (String, String, String)>(sql_str).fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            let chunk = match row {
+                Ok((id, label, name)) => {
+                    let category = categories
+                        .entry(label.clone())
+                        .or_insert_with(|| mapper.category(&label).biolink_id);
+                    Ok(writer.entity(&id, &name, category))
+                }
+                Err(e) => Err(to_io_error(&e)),
+            };
+
+            let failed = chunk.is_err();
+            if !send_chunk(&sender, chunk).await || failed {
+                debug!("Stopped streaming the rdf after {} entities.", num_entities);
+                return;
+            }
+            num_entities += 1;
+        }
+        drop(rows);
+
+        let mut predicates: HashMap<String, String> = HashMap::new();
+        let mut num_relations = 0;
+        let sql_str =
+            "SELECT source_id, relation_type, target_id FROM biomedgps_relation ORDER BY id";
+        let mut rows = sqlx::query_as::<_, (String, String, String)>(sql_str).fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            let chunk = match row {
+                Ok((source_id, relation_type, target_id)) => {
+                    let predicate = predicates
+                        .entry(relation_type.clone())
+                        .or_insert_with(|| mapper.predicate(&relation_type).biolink_id);
+                    Ok(writer.relation(&source_id, predicate, &target_id))
+                }
+                Err(e) => Err(to_io_error(&e)),
+            };
+
+            let failed = chunk.is_err();
+            if !send_chunk(&sender, chunk).await || failed {
+                debug!(
+                    "Stopped streaming the rdf after {} entities and {} relations.",
+                    num_entities, num_relations
+                );
+                return;
+            }
+            num_relations += 1;
+        }
+
+        send_chunk(&sender, Ok(writer.footer())).await;
+        info!(
+            "Streamed {} entities and {} relations as {}.",
+            num_entities,
+            num_relations,
+            format.extension()
+        );
+    });
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rdf_writer() {
+        let mut writer = RdfWriter::new(RdfFormat::NTriples, PrefixMap::default());
+        assert_eq!(writer.header(), "");
+        assert_eq!(
+            writer.relation("MESH:D008687", "biolink:treats", "MESH:D003924"),
+            "<http://id.nlm.nih.gov/mesh/D008687> <https://w3id.org/biolink/vocab/treats> <http://id.nlm.nih.gov/mesh/D003924> .\n"
+        );
+        assert!(writer
+            .entity("ENTREZ:7157", "TP53 \"tumor\"\n", "biolink:Gene")
+            .ends_with(
+                "<http://www.w3.org/2000/01/rdf-schema#label> \"TP53 \\\"tumor\\\"\\n\" .\n"
+            ));
+
+        // The JSON-LD nodes are separated by commas, and the whole output is a valid json.
+        let mut writer = RdfWriter::new(RdfFormat::JsonLd, PrefixMap::default());
+        let output = writer.header()
+            + &writer.entity("MESH:D008687", "Metformin", "biolink:ChemicalEntity")
+            + &writer.relation("MESH:D008687", "biolink:treats", "MESH:D003924")
+            + &writer.footer();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["@graph"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            value["@graph"][1]["biolink:treats"]["@id"],
+            "http://id.nlm.nih.gov/mesh/D003924"
+        );
+
+        assert_eq!(
+            PrefixMap::default().expand("MESH:D 1"),
+            "http://id.nlm.nih.gov/mesh/D%201"
+        );
+        assert_eq!("ttl".parse::<RdfFormat>(), Ok(RdfFormat::Turtle));
+        assert!("rdfxml".parse::<RdfFormat>().is_err());
+    }
+}
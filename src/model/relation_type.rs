@@ -0,0 +1,645 @@
+//! Relation type module is a registry of the relation types, the relation types (such as `GNBR::T::Compound:Disease`) are opaque strings, so each of them can be registered with a human-readable label (such as `treats`), a description, the inverse relation type (such as `GNBR::treated_by::Disease:Compound`) and the parent relation type.
+//!
+//! The registry is saved in the `biomedgps_relation_type` table (managed by `/api/v1/relation-types` or imported by `biomedgps-cli importdb -t relation_type`) and kept in memory like the graph theme, so it is used without querying the database:
+//!
+//! * [`Graph::get_graph`](crate::model::graph::Graph::get_graph) renders the labels of the edges by the registered labels.
+//! * The relation queries match the inverse relations (see [`RelationTypeRegistry::with_inverse_relations`]), so a query on `treated_by` also returns the stored `treats` relations with the swapped ends.
+//! * The relation types are grouped by their parents (see [`RelationTypeRegistry::groups`]), so the frontend can show the related predicates together.
+//!
+//! A relation type is looked up by itself only, the registry doesn't fall back to the relation key like the BioLink mappings, because the inverse relation type of a relation key is ambiguous.
+
+use crate::model::core::{CheckData, Relation, ValidationError};
+use crate::query_builder::sql_builder::{ComposeQuery, ComposeQueryItem, QueryItem, Value};
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use log::info;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::{error::Error, path::PathBuf};
+use validator::Validate;
+
+/// The max number of the relation types which are imported by one request.
+pub const MAX_IMPORTED_RELATION_TYPES: usize = 10000;
+
+lazy_static! {
+    static ref RELATION_TYPE_REGISTRY: RwLock<Option<Arc<RelationTypeRegistry>>> =
+        RwLock::new(None);
+}
+
+/// A registered relation type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct RelationTypeDefinition {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    /// The relation type, such as `GNBR::T::Compound:Disease`.
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of relation_type should be between 1 and 64."
+    ))]
+    pub relation_type: String,
+
+    /// The human-readable label which is shown on the edges, such as `treats`.
+    #[validate(length(
+        max = 255,
+        min = 1,
+        message = "The length of label should be between 1 and 255."
+    ))]
+    pub label: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    /// The relation type which goes in the reversed direction, such as `GNBR::treated_by::Disease:Compound`. It is the relation type itself for the symmetric relation types.
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of inverse_relation_type should be between 1 and 64."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub inverse_relation_type: Option<String>,
+
+    /// The more general relation type, such as `BIOMEDGPS::treats::Compound:Disease` for `GNBR::T::Compound:Disease`.
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of parent_relation_type should be between 1 and 64."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub parent_relation_type: Option<String>,
+}
+
+impl CheckData for RelationTypeDefinition {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<RelationTypeDefinition>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["relation_type".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "relation_type".to_string(),
+            "label".to_string(),
+            "description".to_string(),
+            "inverse_relation_type".to_string(),
+            "parent_relation_type".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        vec!["relation_type".to_string(), "label".to_string()]
+    }
+}
+
+impl RelationTypeDefinition {
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<RelationTypeDefinition>, anyhow::Error> {
+        let records = sqlx::query_as::<_, RelationTypeDefinition>(
+            "SELECT * FROM biomedgps_relation_type ORDER BY relation_type",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    pub async fn insert(
+        &self,
+        pool: &sqlx::PgPool,
+    ) -> Result<RelationTypeDefinition, anyhow::Error> {
+        let sql_str = "INSERT INTO biomedgps_relation_type (relation_type, label, description, inverse_relation_type, parent_relation_type) VALUES ($1, $2, $3, $4, $5) RETURNING *";
+        let record = sqlx::query_as::<_, RelationTypeDefinition>(sql_str)
+            .bind(&self.relation_type)
+            .bind(&self.label)
+            .bind(&self.description)
+            .bind(&self.inverse_relation_type)
+            .bind(&self.parent_relation_type)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    pub async fn update(
+        &self,
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<RelationTypeDefinition, anyhow::Error> {
+        let sql_str = "UPDATE biomedgps_relation_type SET relation_type = $1, label = $2, description = $3, inverse_relation_type = $4, parent_relation_type = $5 WHERE id = $6 RETURNING *";
+        let record = sqlx::query_as::<_, RelationTypeDefinition>(sql_str)
+            .bind(&self.relation_type)
+            .bind(&self.label)
+            .bind(&self.description)
+            .bind(&self.inverse_relation_type)
+            .bind(&self.parent_relation_type)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    pub async fn delete(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<RelationTypeDefinition, anyhow::Error> {
+        let sql_str = "DELETE FROM biomedgps_relation_type WHERE id = $1 RETURNING *";
+        let record = sqlx::query_as::<_, RelationTypeDefinition>(sql_str)
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+        AnyOk(record)
+    }
+
+    /// Insert or update the relation types by their relation types in one transaction, it returns the number of the saved relation types.
+    pub async fn upsert_all(
+        pool: &sqlx::PgPool,
+        definitions: &[RelationTypeDefinition],
+    ) -> Result<u64, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let sql_str = "INSERT INTO biomedgps_relation_type (relation_type, label, description, inverse_relation_type, parent_relation_type) VALUES ($1, $2, $3, $4, $5)
+                       ON CONFLICT (relation_type) DO UPDATE SET label = EXCLUDED.label, description = EXCLUDED.description, inverse_relation_type = EXCLUDED.inverse_relation_type, parent_relation_type = EXCLUDED.parent_relation_type";
+        let mut count = 0;
+        for definition in definitions.iter() {
+            count += sqlx::query(sql_str)
+                .bind(&definition.relation_type)
+                .bind(&definition.label)
+                .bind(&definition.description)
+                .bind(&definition.inverse_relation_type)
+                .bind(&definition.parent_relation_type)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+
+        AnyOk(count)
+    }
+}
+
+/// A group of the relation types for the frontend, i.e. a relation type which has no parent and all its descendants.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object)]
+pub struct RelationTypeGroup {
+    /// The root relation type of the group.
+    pub relation_type: String,
+    /// The label of the root relation type, it is the relation type itself if the root isn't registered.
+    pub label: String,
+    /// The descendants of the root relation type, a parent goes before its children.
+    pub relation_types: Vec<String>,
+}
+
+/// A relation query which also matches the inverse relations, see [`RelationTypeRegistry::with_inverse_relations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InverseQuery {
+    pub query: ComposeQuery,
+    /// The stored relation types which are inverted to the queried relation types, such as `treats` to `treated_by`.
+    pub inverted: HashMap<String, String>,
+}
+
+impl InverseQuery {
+    /// Invert the relations which are matched by their inverse relation types, the other relations are kept as they are.
+    pub fn invert_relations(&self, relations: Vec<Relation>) -> Vec<Relation> {
+        relations
+            .into_iter()
+            .map(
+                |relation| match self.inverted.get(&relation.relation_type) {
+                    Some(relation_type) => invert_relation(relation, relation_type),
+                    None => relation,
+                },
+            )
+            .collect()
+    }
+}
+
+/// Swap the ends of the relation and rename it to the inverse relation type.
+pub fn invert_relation(relation: Relation, relation_type: &str) -> Relation {
+    Relation {
+        relation_type: relation_type.to_string(),
+        source_id: relation.target_id,
+        source_type: relation.target_type,
+        target_id: relation.source_id,
+        target_type: relation.source_type,
+        ..relation
+    }
+}
+
+/// The registered relation types, they are keyed by the relation types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelationTypeRegistry {
+    definitions: HashMap<String, RelationTypeDefinition>,
+}
+
+impl RelationTypeRegistry {
+    pub fn new(definitions: Vec<RelationTypeDefinition>) -> Self {
+        RelationTypeRegistry {
+            definitions: definitions
+                .into_iter()
+                .map(|d| (d.relation_type.clone(), d))
+                .collect(),
+        }
+    }
+
+    pub async fn load(pool: &sqlx::PgPool) -> Result<Self, anyhow::Error> {
+        let definitions = RelationTypeDefinition::get_records(pool).await?;
+        AnyOk(RelationTypeRegistry::new(definitions))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    pub fn get(&self, relation_type: &str) -> Option<&RelationTypeDefinition> {
+        self.definitions.get(relation_type)
+    }
+
+    /// The registered label of the relation type.
+    pub fn label(&self, relation_type: &str) -> Option<&str> {
+        self.get(relation_type).map(|d| d.label.as_str())
+    }
+
+    /// The inverse relation type of the relation type, it is also found by the relation types whose inverse is the relation type, so an inverse only needs to be registered once.
+    ///
+    /// ```
+    /// use biomedgps::model::relation_type::{RelationTypeDefinition, RelationTypeRegistry};
+    ///
+    /// let registry = RelationTypeRegistry::new(vec![RelationTypeDefinition {
+    ///     id: 1,
+    ///     relation_type: "GNBR::T::Compound:Disease".to_string(),
+    ///     label: "treats".to_string(),
+    ///     description: None,
+    ///     inverse_relation_type: Some("GNBR::treated_by::Disease:Compound".to_string()),
+    ///     parent_relation_type: None,
+    /// }]);
+    /// assert_eq!(registry.inverse("GNBR::treated_by::Disease:Compound"), Some("GNBR::T::Compound:Disease"));
+    /// ```
+    pub fn inverse(&self, relation_type: &str) -> Option<&str> {
+        if let Some(inverse) = self
+            .get(relation_type)
+            .and_then(|d| d.inverse_relation_type.as_deref())
+        {
+            return Some(inverse);
+        }
+
+        let mut inverses = self
+            .definitions
+            .values()
+            .filter(|d| d.inverse_relation_type.as_deref() == Some(relation_type))
+            .map(|d| d.relation_type.as_str())
+            .collect::<Vec<&str>>();
+        // The registry may be imported without the checks, so the result is kept stable.
+        inverses.sort();
+        inverses.first().copied()
+    }
+
+    /// The parent, the grandparent and so on of the relation type, the loops (which may be imported without the checks) are cut.
+    pub fn ancestors(&self, relation_type: &str) -> Vec<String> {
+        let mut ancestors = vec![];
+        let mut visited = HashSet::from([relation_type.to_string()]);
+        let mut current = relation_type;
+        while let Some(parent) = self
+            .get(current)
+            .and_then(|d| d.parent_relation_type.as_deref())
+        {
+            if !visited.insert(parent.to_string()) {
+                break;
+            }
+            ancestors.push(parent.to_string());
+            current = parent;
+        }
+
+        ancestors
+    }
+
+    /// Check a new or updated relation type against the registry, the parent must not be the relation type or one of its descendants, and the inverse must not be registered as the inverse of another relation type.
+    pub fn check(&self, definition: &RelationTypeDefinition) -> Result<(), ValidationError> {
+        let relation_type = definition.relation_type.as_str();
+        if let Some(parent) = &definition.parent_relation_type {
+            if parent == relation_type || self.ancestors(parent).iter().any(|a| a == relation_type)
+            {
+                return Err(ValidationError::new(&format!(
+                    "The parent relation type {} is {} or one of its descendants, the hierarchy must not have loops.",
+                    parent, relation_type
+                )));
+            }
+        }
+
+        if let Some(inverse) = &definition.inverse_relation_type {
+            if inverse == relation_type {
+                return Ok(());
+            }
+
+            let registered = self
+                .get(inverse)
+                .and_then(|d| d.inverse_relation_type.as_deref());
+            if let Some(registered) = registered.filter(|r| *r != relation_type) {
+                return Err(ValidationError::new(&format!(
+                    "The inverse relation type {} is registered with another inverse relation type {}.",
+                    inverse, registered
+                )));
+            }
+
+            let other = self.definitions.values().find(|d| {
+                d.relation_type != relation_type
+                    && d.inverse_relation_type.as_deref() == Some(inverse.as_str())
+            });
+            if let Some(other) = other {
+                return Err(ValidationError::new(&format!(
+                    "The relation type {} is already the inverse of {}.",
+                    inverse, other.relation_type
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Group the registered relation types by their roots, i.e. the farthest ancestors. The parents which are not registered are also the roots, and a loop in the hierarchy (which may be imported without the checks) is cut, so every registered relation type is in one group.
+    pub fn groups(&self) -> Vec<RelationTypeGroup> {
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut roots: Vec<String> = vec![];
+        for definition in self.definitions.values() {
+            if let Some(parent) = &definition.parent_relation_type {
+                children
+                    .entry(parent.as_str())
+                    .or_default()
+                    .push(definition.relation_type.as_str());
+            }
+            match self.ancestors(&definition.relation_type).pop() {
+                Some(root) => roots.push(root),
+                None => roots.push(definition.relation_type.clone()),
+            }
+        }
+        roots.sort();
+        roots.dedup();
+        for values in children.values_mut() {
+            values.sort();
+        }
+
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut groups = vec![];
+        for root in roots.iter() {
+            if visited.contains(root.as_str()) {
+                continue;
+            }
+
+            // Depth first, so a parent goes before its children.
+            let mut relation_types = vec![];
+            let mut stack = vec![root.as_str()];
+            while let Some(current) = stack.pop() {
+                if !visited.insert(current) {
+                    continue;
+                }
+                relation_types.push(current.to_string());
+                if let Some(values) = children.get(current) {
+                    stack.extend(values.iter().rev());
+                }
+            }
+
+            groups.push(RelationTypeGroup {
+                relation_type: root.clone(),
+                label: self.label(root).unwrap_or(root).to_string(),
+                relation_types,
+            });
+        }
+
+        groups
+    }
+
+    /// Extend the relation query to match the inverse relations, i.e. the stored relations of the inverse relation types with the swapped ends, such as the `treats` relations from a compound to a disease for a query on the `treated_by` relations of the disease. The matched relations should be inverted by [`InverseQuery::invert_relations`].
+    ///
+    /// The queried relation types are the exact matches (`=` and `in`) of the relation_type field at the top level of the query (or in the top-level `and` query), so every relation which is matched by the query itself is of a queried relation type. It returns None if no queried relation type has an inverse relation type which is not queried, the symmetric relation types are not extended.
+    pub fn with_inverse_relations(&self, query: &ComposeQuery) -> Option<InverseQuery> {
+        let items = match query {
+            ComposeQuery::QueryItem(item) => vec![item],
+            ComposeQuery::ComposeQueryItem(item) if item.operator.to_lowercase() == "and" => item
+                .items
+                .iter()
+                .filter_map(|q| match q {
+                    ComposeQuery::QueryItem(item) => Some(item),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let mut queried: Vec<String> = vec![];
+        for item in items {
+            if item.field != "relation_type"
+                || !["=", "in"].contains(&item.operator.to_lowercase().as_str())
+            {
+                continue;
+            }
+            match &item.value {
+                Value::String(value) => queried.push(value.clone()),
+                Value::ArrayString(values) => queried.extend(values.iter().cloned()),
+                _ => {}
+            }
+        }
+
+        let mut inverted: HashMap<String, String> = HashMap::new();
+        for relation_type in queried.iter() {
+            if let Some(inverse) = self.inverse(relation_type) {
+                if inverse != relation_type && !queried.iter().any(|q| q == inverse) {
+                    inverted.insert(inverse.to_string(), relation_type.clone());
+                }
+            }
+        }
+        if inverted.is_empty() {
+            return None;
+        }
+
+        let inverses = inverted
+            .iter()
+            .map(|(stored, queried)| (queried.clone(), stored.clone()))
+            .collect::<HashMap<String, String>>();
+        let mut stored_types = inverted.keys().cloned().collect::<Vec<String>>();
+        stored_types.sort();
+
+        let mut mirror = ComposeQueryItem::new("and");
+        mirror.items = vec![
+            ComposeQuery::QueryItem(QueryItem::new(
+                "relation_type".to_string(),
+                Value::ArrayString(stored_types),
+                "in".to_string(),
+            )),
+            mirror_query(query, &inverses),
+        ];
+
+        let mut extended = ComposeQueryItem::new("or");
+        extended.items = vec![query.clone(), ComposeQuery::ComposeQueryItem(mirror)];
+
+        Some(InverseQuery {
+            query: ComposeQuery::ComposeQueryItem(extended),
+            inverted,
+        })
+    }
+}
+
+/// Swap the source and target fields of the query and replace the relation types by their inverse relation types, so it matches the stored relations whose inverted relations are matched by the query. The relation types which are not in the inverses are kept.
+pub(crate) fn mirror_query(
+    query: &ComposeQuery,
+    inverses: &HashMap<String, String>,
+) -> ComposeQuery {
+    match query {
+        ComposeQuery::QueryItem(item) => {
+            let field = match item.field.as_str() {
+                "source_id" => "target_id",
+                "target_id" => "source_id",
+                "source_type" => "target_type",
+                "target_type" => "source_type",
+                field => field,
+            };
+            let map = |value: &String| inverses.get(value).unwrap_or(value).clone();
+            let value = match &item.value {
+                Value::String(value) if item.field == "relation_type" => Value::String(map(value)),
+                Value::ArrayString(values) if item.field == "relation_type" => {
+                    Value::ArrayString(values.iter().map(map).collect())
+                }
+                value => value.clone(),
+            };
+
+            ComposeQuery::QueryItem(QueryItem {
+                field: field.to_string(),
+                value,
+                operator: item.operator.clone(),
+            })
+        }
+        ComposeQuery::ComposeQueryItem(item) => {
+            let mut mirrored = ComposeQueryItem::new(&item.operator);
+            mirrored.items = item
+                .items
+                .iter()
+                .map(|query| mirror_query(query, inverses))
+                .collect();
+            ComposeQuery::ComposeQueryItem(mirrored)
+        }
+    }
+}
+
+/// Load the registered relation types and make them active, it should be called once when the server starts.
+pub async fn load_relation_types(pool: &sqlx::PgPool) -> Result<(), anyhow::Error> {
+    let registry = RelationTypeRegistry::load(pool).await?;
+    info!("Registered {} relation types.", registry.definitions.len());
+    set_relation_type_registry(Some(registry));
+    AnyOk(())
+}
+
+pub fn set_relation_type_registry(registry: Option<RelationTypeRegistry>) {
+    *RELATION_TYPE_REGISTRY.write().unwrap() = registry.map(Arc::new);
+}
+
+/// Get the active registry, it is None if the registry isn't loaded (such as in the cli).
+pub fn get_relation_type_registry() -> Option<Arc<RelationTypeRegistry>> {
+    RELATION_TYPE_REGISTRY.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(
+        relation_type: &str,
+        inverse: Option<&str>,
+        parent: Option<&str>,
+    ) -> RelationTypeDefinition {
+        RelationTypeDefinition {
+            id: 0,
+            relation_type: relation_type.to_string(),
+            label: relation_type.to_lowercase(),
+            description: None,
+            inverse_relation_type: inverse.map(|s| s.to_string()),
+            parent_relation_type: parent.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_groups_and_check() {
+        let registry = RelationTypeRegistry::new(vec![
+            definition("TREATS", Some("TREATED_BY"), None),
+            definition("GNBR_T", None, Some("TREATS")),
+            definition("HETIONET_CTD", None, Some("TREATS")),
+            definition("PALLIATES", None, Some("GNBR_T")),
+            definition("BINDS", None, Some("INTERACTS")),
+        ]);
+
+        let groups = registry.groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].relation_type, "INTERACTS");
+        assert_eq!(groups[0].label, "INTERACTS");
+        assert_eq!(groups[0].relation_types, vec!["INTERACTS", "BINDS"]);
+        assert_eq!(groups[1].label, "treats");
+        assert_eq!(
+            groups[1].relation_types,
+            vec!["TREATS", "GNBR_T", "PALLIATES", "HETIONET_CTD"]
+        );
+
+        assert_eq!(registry.ancestors("PALLIATES"), vec!["GNBR_T", "TREATS"]);
+        assert!(registry
+            .check(&definition("TREATS", None, Some("PALLIATES")))
+            .is_err());
+        assert!(registry
+            .check(&definition("CURES", Some("TREATED_BY"), None))
+            .is_err());
+        assert!(registry
+            .check(&definition("TREATED_BY", Some("TREATS"), None))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_with_inverse_relations() {
+        let registry =
+            RelationTypeRegistry::new(vec![definition("TREATS", Some("TREATED_BY"), None)]);
+        let query: ComposeQuery = serde_json::from_str(
+            r#"{"operator": "and", "items": [
+                {"field": "relation_type", "operator": "=", "value": "TREATED_BY"},
+                {"field": "source_id", "operator": "=", "value": "MESH:D003920"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let inverse_query = registry.with_inverse_relations(&query).unwrap();
+        assert_eq!(
+            inverse_query.inverted.get("TREATS").map(|s| s.as_str()),
+            Some("TREATED_BY")
+        );
+        let expected: ComposeQuery = serde_json::from_str(
+            r#"{"operator": "or", "items": [
+                {"operator": "and", "items": [
+                    {"field": "relation_type", "operator": "=", "value": "TREATED_BY"},
+                    {"field": "source_id", "operator": "=", "value": "MESH:D003920"}
+                ]},
+                {"operator": "and", "items": [
+                    {"field": "relation_type", "operator": "in", "value": ["TREATS"]},
+                    {"operator": "and", "items": [
+                        {"field": "relation_type", "operator": "=", "value": "TREATS"},
+                        {"field": "target_id", "operator": "=", "value": "MESH:D003920"}
+                    ]}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(inverse_query.query, expected);
+
+        // The inverse relation type is queried too, so the query isn't extended.
+        let query: ComposeQuery = serde_json::from_str(
+            r#"{"field": "relation_type", "operator": "in", "value": ["TREATS", "TREATED_BY"]}"#,
+        )
+        .unwrap();
+        assert!(registry.with_inverse_relations(&query).is_none());
+    }
+}
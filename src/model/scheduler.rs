@@ -0,0 +1,440 @@
+//! Scheduler module runs the maintenance tasks inside the server periodically, such as refreshing the statistics tables, clearing the cached counts, cleaning the orphaned rows and fetching the publications of the pmids which are not resolved yet.
+//!
+//! Each task runs in its own tokio task by a cron-like schedule (see [`Schedule`]), the schedules can be changed or turned off in the `[maintenance]` section of the config file. The tasks are guarded by the postgres advisory locks, so a task runs only once at a time even if several server instances share the database. The status of the last run of each task is kept in memory and returned by `/api/v1/tasks`.
+
+use crate::model::cache::{invalidate_cache, CacheScope};
+use crate::model::core::clear_count_cache;
+use crate::model::materialize::invalidate_materialized_graphs;
+use crate::model::publication::Publication;
+use crate::model::util::{clean_orphans, update_statistics};
+use anyhow::Ok as AnyOk;
+use chrono::{DateTime, Duration as ChronoDuration, Timelike, Utc};
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The status of a maintenance task.
+pub const TASK_RUNNING: &str = "running";
+pub const TASK_COMPLETED: &str = "completed";
+pub const TASK_FAILED: &str = "failed";
+/// The task is skipped because it is running in another server instance.
+pub const TASK_SKIPPED: &str = "skipped";
+
+/// The schedule value which turns a task off.
+pub const SCHEDULE_OFF: &str = "off";
+
+/// The max number of the unresolved pmids which are fetched from PubMed in one run, the rest are fetched in the next runs.
+pub const MAX_PUBLICATIONS_PER_RUN: usize = 2000;
+
+// The advisory lock keys of the tasks are the base plus the index of the task.
+const ADVISORY_LOCK_BASE: i64 = 0x6269_6f6d_0000;
+
+lazy_static! {
+    static ref TASK_STATUS: Mutex<HashMap<MaintenanceJob, MaintenanceTask>> =
+        Mutex::new(HashMap::new());
+}
+
+/// When a maintenance task runs, the times are in UTC. It is parsed from:
+///
+/// * `@every <n><s|m|h|d>`, such as `@every 30m`, the first run is after the interval since the server starts.
+/// * `@hourly` or `<minute> * * * *`, such as `15 * * * *`.
+/// * `@daily` or `<minute> <hour> * * *`, such as `30 2 * * *`.
+///
+/// The other cron expressions (such as the day of the week) are not supported.
+///
+/// ```
+/// use biomedgps::model::scheduler::Schedule;
+/// use chrono::{TimeZone, Utc};
+///
+/// let now = Utc.with_ymd_and_hms(2023, 11, 24, 3, 20, 0).unwrap();
+/// let daily = "30 2 * * *".parse::<Schedule>().unwrap();
+/// assert_eq!(daily.next_run(now), Utc.with_ymd_and_hms(2023, 11, 25, 2, 30, 0).unwrap());
+/// let hourly = "@hourly".parse::<Schedule>().unwrap();
+/// assert_eq!(hourly.next_run(now), Utc.with_ymd_and_hms(2023, 11, 24, 4, 0, 0).unwrap());
+/// let every = "@every 30m".parse::<Schedule>().unwrap();
+/// assert_eq!(every.next_run(now), Utc.with_ymd_and_hms(2023, 11, 24, 3, 50, 0).unwrap());
+/// assert!("0 2 * * 1".parse::<Schedule>().is_err());
+/// assert!("@every 0s".parse::<Schedule>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    Every(Duration),
+    Hourly { minute: u32 },
+    Daily { hour: u32, minute: u32 },
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            format!(
+                "Invalid schedule: {}, {}. Such as `@every 30m`, `@hourly`, `@daily` or `30 2 * * *`.",
+                value, reason
+            )
+        };
+
+        let value = value.trim();
+        match value {
+            "@hourly" => return Ok(Schedule::Hourly { minute: 0 }),
+            "@daily" | "@midnight" => return Ok(Schedule::Daily { hour: 0, minute: 0 }),
+            _ => {}
+        }
+
+        if let Some(interval) = value.strip_prefix("@every") {
+            let interval = interval.trim();
+            let unit_start = interval.char_indices().last().map(|(i, _)| i).unwrap_or(0);
+            let (number, unit) = interval.split_at(unit_start);
+            let seconds = match unit {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86400,
+                _ => return Err(invalid("the unit of the interval must be s, m, h or d")),
+            };
+            return match number.parse::<u64>() {
+                Ok(number) if number > 0 => Ok(Schedule::Every(Duration::from_secs(
+                    number.saturating_mul(seconds),
+                ))),
+                _ => Err(invalid("the interval must be a positive integer")),
+            };
+        }
+
+        let fields = value.split_whitespace().collect::<Vec<&str>>();
+        if fields.len() != 5 {
+            return Err(invalid("a cron expression must have 5 fields"));
+        }
+        if fields[2..].iter().any(|field| *field != "*") {
+            return Err(invalid(
+                "only the minute and the hour of a cron expression are supported",
+            ));
+        }
+
+        let minute = match fields[0].parse::<u32>() {
+            Ok(minute) if minute < 60 => minute,
+            _ => return Err(invalid("the minute must be between 0 and 59")),
+        };
+        match fields[1] {
+            "*" => Ok(Schedule::Hourly { minute }),
+            hour => match hour.parse::<u32>() {
+                Ok(hour) if hour < 24 => Ok(Schedule::Daily { hour, minute }),
+                _ => Err(invalid("the hour must be between 0 and 23 or *")),
+            },
+        }
+    }
+}
+
+impl Schedule {
+    /// The time of the next run after the given time.
+    pub fn next_run(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        // The candidates are truncated to the minute, so a run never starts twice in the same minute.
+        let start_of_minute = after.with_second(0).and_then(|t| t.with_nanosecond(0));
+        match self {
+            Schedule::Every(interval) => {
+                after + ChronoDuration::from_std(*interval).unwrap_or(ChronoDuration::days(1))
+            }
+            Schedule::Hourly { minute } => {
+                let candidate = start_of_minute
+                    .and_then(|t| t.with_minute(*minute))
+                    .unwrap_or(after);
+                if candidate > after {
+                    candidate
+                } else {
+                    candidate + ChronoDuration::hours(1)
+                }
+            }
+            Schedule::Daily { hour, minute } => {
+                let candidate = start_of_minute
+                    .and_then(|t| t.with_minute(*minute))
+                    .and_then(|t| t.with_hour(*hour))
+                    .unwrap_or(after);
+                if candidate > after {
+                    candidate
+                } else {
+                    candidate + ChronoDuration::days(1)
+                }
+            }
+        }
+    }
+}
+
+/// The maintenance tasks which are run by the scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaintenanceJob {
+    /// Recompute the statistics tables, same as `biomedgps-cli statdb`.
+    RefreshStatistics,
+    /// Clear the cached counts of the list endpoints.
+    InvalidateCountCache,
+    /// Delete the orphaned rows, same as `biomedgps-cli cleandb`.
+    CleanOrphans,
+    /// Fetch the publications of the pmids of the relations and the curated knowledges which are not resolved yet.
+    EnrichPublications,
+}
+
+impl MaintenanceJob {
+    pub fn all() -> Vec<MaintenanceJob> {
+        vec![
+            MaintenanceJob::CleanOrphans,
+            MaintenanceJob::RefreshStatistics,
+            MaintenanceJob::InvalidateCountCache,
+            MaintenanceJob::EnrichPublications,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaintenanceJob::RefreshStatistics => "refresh_statistics",
+            MaintenanceJob::InvalidateCountCache => "invalidate_count_cache",
+            MaintenanceJob::CleanOrphans => "clean_orphans",
+            MaintenanceJob::EnrichPublications => "enrich_publications",
+        }
+    }
+
+    /// The default schedule, all tasks run at night and the orphans are cleaned before the statistics are refreshed.
+    pub fn default_schedule(&self) -> &'static str {
+        match self {
+            MaintenanceJob::CleanOrphans => "30 1 * * *",
+            MaintenanceJob::RefreshStatistics => "0 2 * * *",
+            MaintenanceJob::InvalidateCountCache => "30 2 * * *",
+            MaintenanceJob::EnrichPublications => "0 3 * * *",
+        }
+    }
+
+    /// Parse the configured schedule of the task, the default schedule is used if it is not set, and None means the task is turned off.
+    pub fn parse_schedule(&self, value: Option<&str>) -> Result<Option<Schedule>, String> {
+        match value.unwrap_or(self.default_schedule()).trim() {
+            SCHEDULE_OFF => Ok(None),
+            value => value
+                .parse::<Schedule>()
+                .map(Some)
+                .map_err(|e| format!("{} of the maintenance task {}", e, self.name())),
+        }
+    }
+
+    fn lock_key(&self) -> i64 {
+        let index = MaintenanceJob::all()
+            .iter()
+            .position(|job| job == self)
+            .unwrap_or_default();
+        ADVISORY_LOCK_BASE + index as i64
+    }
+
+    /// Run the task and return a message about what is done.
+    pub async fn run(&self, pool: &sqlx::PgPool) -> Result<String, anyhow::Error> {
+        match self {
+            MaintenanceJob::RefreshStatistics => {
+                update_statistics(pool)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+                invalidate_cache(&[CacheScope::Metadata]).await;
+                AnyOk("All statistics tables are refreshed.".to_string())
+            }
+            MaintenanceJob::InvalidateCountCache => {
+                let count = clear_count_cache();
+                AnyOk(format!("{} cached counts are removed.", count))
+            }
+            MaintenanceJob::CleanOrphans => {
+                let total = clean_orphans(pool, false)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+                    .iter()
+                    .map(|report| report.count)
+                    .sum::<u64>();
+                if total > 0 {
+                    invalidate_cache(&CacheScope::all()).await;
+                    invalidate_materialized_graphs(pool).await;
+                }
+                AnyOk(format!("{} orphaned rows are deleted.", total))
+            }
+            MaintenanceJob::EnrichPublications => {
+                let count =
+                    Publication::sync_unresolved_records(pool, MAX_PUBLICATIONS_PER_RUN).await?;
+                AnyOk(format!("{} publications are fetched.", count))
+            }
+        }
+    }
+
+    /// Run the task if it isn't running in another server instance, it returns None if it is skipped.
+    async fn run_exclusively(&self, pool: &sqlx::PgPool) -> Result<Option<String>, anyhow::Error> {
+        // The advisory lock belongs to the session, so it must be released by the same connection.
+        let mut conn = pool.acquire().await?;
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(self.lock_key())
+            .fetch_one(&mut *conn)
+            .await?;
+        if !locked {
+            return AnyOk(None);
+        }
+
+        let result = self.run(pool).await;
+        if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(self.lock_key())
+            .execute(&mut *conn)
+            .await
+        {
+            warn!("Failed to release the lock of {}: {}", self.name(), e);
+        }
+
+        result.map(Some)
+    }
+}
+
+/// The schedule and the last run of a maintenance task.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct MaintenanceTask {
+    pub name: String,
+    /// The schedule, such as `30 2 * * *`, it is `off` if the task is turned off.
+    pub schedule: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[oai(skip_serializing_if_is_none)]
+    pub last_started_at: Option<DateTime<Utc>>,
+    #[oai(skip_serializing_if_is_none)]
+    pub last_finished_at: Option<DateTime<Utc>>,
+    /// running, completed, failed or skipped (it is running in another server instance).
+    #[oai(skip_serializing_if_is_none)]
+    pub last_status: Option<String>,
+    /// What is done by the last run, or the error.
+    #[oai(skip_serializing_if_is_none)]
+    pub last_message: Option<String>,
+}
+
+fn update_status<F: FnOnce(&mut MaintenanceTask)>(job: MaintenanceJob, f: F) {
+    if let Some(task) = TASK_STATUS.lock().unwrap().get_mut(&job) {
+        f(task);
+    }
+}
+
+/// Start the scheduler of the maintenance tasks, the schedules are the configured values of the tasks (see [`MaintenanceJob::parse_schedule`]). It must be called in the tokio runtime.
+pub fn start_scheduler(
+    pool: Arc<sqlx::PgPool>,
+    schedules: Vec<(MaintenanceJob, Option<Schedule>, String)>,
+) {
+    for (job, schedule, schedule_str) in schedules {
+        TASK_STATUS.lock().unwrap().insert(
+            job,
+            MaintenanceTask {
+                name: job.name().to_string(),
+                schedule: schedule_str,
+                next_run_at: None,
+                last_started_at: None,
+                last_finished_at: None,
+                last_status: None,
+                last_message: None,
+            },
+        );
+
+        let schedule = match schedule {
+            Some(schedule) => schedule,
+            None => {
+                info!("The maintenance task {} is turned off.", job.name());
+                continue;
+            }
+        };
+
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            loop {
+                let now = Utc::now();
+                let next_run_at = schedule.next_run(now);
+                update_status(job, |task| task.next_run_at = Some(next_run_at));
+                let wait = (next_run_at - now).to_std().unwrap_or_default();
+                tokio::time::sleep(wait).await;
+
+                info!("Run the maintenance task {}.", job.name());
+                update_status(job, |task| {
+                    task.last_started_at = Some(Utc::now());
+                    task.last_status = Some(TASK_RUNNING.to_string());
+                });
+
+                let (status, message) = match job.run_exclusively(&pool).await {
+                    Ok(Some(message)) => {
+                        info!(
+                            "The maintenance task {} is completed: {}",
+                            job.name(),
+                            message
+                        );
+                        (TASK_COMPLETED, message)
+                    }
+                    Ok(None) => {
+                        info!(
+                            "The maintenance task {} is running in another instance, skip it.",
+                            job.name()
+                        );
+                        (
+                            TASK_SKIPPED,
+                            "The task is running in another server instance.".to_string(),
+                        )
+                    }
+                    Err(e) => {
+                        error!("The maintenance task {} failed: {}", job.name(), e);
+                        (TASK_FAILED, e.to_string())
+                    }
+                };
+
+                update_status(job, |task| {
+                    task.last_finished_at = Some(Utc::now());
+                    task.last_status = Some(status.to_string());
+                    task.last_message = Some(message);
+                });
+            }
+        });
+    }
+}
+
+/// The maintenance tasks and their last runs, it is empty if the scheduler is not started.
+pub fn get_maintenance_tasks() -> Vec<MaintenanceTask> {
+    let status = TASK_STATUS.lock().unwrap();
+    MaintenanceJob::all()
+        .iter()
+        .filter_map(|job| status.get(job).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_schedule() {
+        let now = Utc.with_ymd_and_hms(2023, 11, 24, 2, 30, 0).unwrap();
+
+        // A run at the same minute is not repeated.
+        let daily = "30 2 * * *".parse::<Schedule>().unwrap();
+        assert_eq!(
+            daily.next_run(now),
+            Utc.with_ymd_and_hms(2023, 11, 25, 2, 30, 0).unwrap()
+        );
+        assert_eq!(
+            "@daily".parse::<Schedule>().unwrap().next_run(now),
+            Utc.with_ymd_and_hms(2023, 11, 25, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            "45 * * * *".parse::<Schedule>().unwrap().next_run(now),
+            Utc.with_ymd_and_hms(2023, 11, 24, 2, 45, 0).unwrap()
+        );
+        assert_eq!(
+            "@every 1d".parse::<Schedule>().unwrap(),
+            Schedule::Every(Duration::from_secs(86400))
+        );
+
+        assert!("60 2 * * *".parse::<Schedule>().is_err());
+        assert!("30 24 * * *".parse::<Schedule>().is_err());
+        assert!("@every 5x".parse::<Schedule>().is_err());
+        assert!("@weekly".parse::<Schedule>().is_err());
+
+        let job = MaintenanceJob::RefreshStatistics;
+        assert_eq!(
+            job.parse_schedule(None).unwrap(),
+            Some(Schedule::Daily { hour: 2, minute: 0 })
+        );
+        assert_eq!(job.parse_schedule(Some("off")).unwrap(), None);
+        assert!(job.parse_schedule(Some("daily")).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! Snapshot module is used to dump all biomedgps tables into a snapshot and restore it into a database, so that a database state (such as the one used by a paper submission) can be reproduced.
+//!
+//! A snapshot is a directory which contains a gzipped csv file for each table and a `manifest.json` file. The manifest records the schema version (the latest migration), the columns, the row count and the sha256 checksum of each table, the checksums are verified before a snapshot is restored.
+
+use crate::model::remote::sha256_file;
+use anyhow::Ok as AnyOk;
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// The tables which are restored first, because other tables reference them.
+const PARENT_TABLES: [&str; 4] = [
+    "biomedgps_workspace",
+    "biomedgps_embedding_model",
+    "biomedgps_subgraph",
+    "biomedgps_dataset_version",
+];
+
+/// A table in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotTable {
+    pub table_name: String,
+    pub filename: String,
+    pub columns: Vec<String>,
+    pub row_count: i64,
+    pub sha256: String,
+}
+
+/// The manifest of a snapshot, it is saved as `manifest.json` in the snapshot directory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// The version of the latest migration which is applied to the database.
+    pub schema_version: i64,
+    /// The version of biomedgps which creates the snapshot.
+    pub biomedgps_version: String,
+    pub tables: Vec<SnapshotTable>,
+}
+
+impl SnapshotManifest {
+    pub fn read(snapshot_path: &Path) -> Result<SnapshotManifest, anyhow::Error> {
+        let manifest_path = snapshot_path.join(MANIFEST_FILENAME);
+        let file = File::open(&manifest_path)
+            .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", manifest_path.display(), e))?;
+
+        AnyOk(serde_json::from_reader(file)?)
+    }
+
+    pub fn total_rows(&self) -> i64 {
+        self.tables.iter().map(|t| t.row_count).sum()
+    }
+
+    /// Verify the checksums of the table files.
+    pub fn verify(&self, snapshot_path: &Path) -> Result<(), anyhow::Error> {
+        for table in self.tables.iter() {
+            let filepath = snapshot_path.join(&table.filename);
+            let checksum = sha256_file(&filepath)?;
+            if checksum != table.sha256 {
+                return Err(anyhow::anyhow!(
+                    "The checksum of {} doesn't match, expected: {}, actual: {}.",
+                    filepath.display(),
+                    table.sha256,
+                    checksum
+                ));
+            }
+        }
+
+        AnyOk(())
+    }
+}
+
+/// Get the version of the latest migration which is applied to the database.
+pub async fn get_schema_version(pool: &sqlx::PgPool) -> Result<i64, anyhow::Error> {
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+            .fetch_one(pool)
+            .await?;
+
+    match version {
+        Some(version) => AnyOk(version),
+        None => Err(anyhow::anyhow!(
+            "No migrations found in the database, please run `biomedgps-cli initdb` first."
+        )),
+    }
+}
+
+/// Get the biomedgps tables in the database, the parent tables come first.
+async fn get_tables(pool: &sqlx::PgPool) -> Result<Vec<String>, anyhow::Error> {
+    let mut tables: Vec<String> = sqlx::query_scalar(
+        "SELECT table_name::TEXT FROM information_schema.tables
+         WHERE table_schema = 'public' AND table_type = 'BASE TABLE' AND table_name LIKE 'biomedgps\\_%'
+         ORDER BY table_name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    tables.sort_by_key(|t| {
+        PARENT_TABLES
+            .iter()
+            .position(|p| p == t)
+            .unwrap_or(PARENT_TABLES.len())
+    });
+
+    AnyOk(tables)
+}
+
+async fn get_columns(pool: &sqlx::PgPool, table_name: &str) -> Result<Vec<String>, anyhow::Error> {
+    let columns = sqlx::query_scalar(
+        "SELECT column_name::TEXT FROM information_schema.columns
+         WHERE table_schema = 'public' AND table_name = $1 ORDER BY ordinal_position",
+    )
+    .bind(table_name)
+    .fetch_all(pool)
+    .await?;
+
+    AnyOk(columns)
+}
+
+/// The query to dump a table. The subgraphs are ordered by their depth, so the parent subgraphs are restored before their children.
+fn get_dump_query(table_name: &str, columns: &[String]) -> String {
+    let columns = columns.join(", ");
+    if table_name == "biomedgps_subgraph" {
+        format!(
+            "WITH RECURSIVE tree AS (
+                SELECT id, 0 AS depth FROM biomedgps_subgraph WHERE parent IS NULL
+                UNION ALL
+                SELECT s.id, tree.depth + 1 FROM biomedgps_subgraph s JOIN tree ON s.parent = tree.id
+             )
+             SELECT {} FROM biomedgps_subgraph LEFT JOIN tree USING (id) ORDER BY tree.depth NULLS LAST",
+            columns
+        )
+    } else {
+        format!("SELECT {} FROM {}", columns, table_name)
+    }
+}
+
+/// Dump all biomedgps tables into a snapshot directory (`<snapshot_dir>/<name>`), the tables are dumped in one repeatable read transaction, so the snapshot is consistent.
+pub async fn create_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_dir: &Path,
+    name: Option<&str>,
+) -> Result<SnapshotManifest, anyhow::Error> {
+    let created_at = Utc::now();
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => format!("snapshot_{}", created_at.format("%Y%m%d%H%M%S")),
+    };
+
+    let snapshot_path = snapshot_dir.join(&name);
+    if snapshot_path.exists() {
+        return Err(anyhow::anyhow!(
+            "The snapshot {} already exists.",
+            snapshot_path.display()
+        ));
+    }
+    std::fs::create_dir_all(&snapshot_path)?;
+
+    let schema_version = get_schema_version(pool).await?;
+    let table_names = get_tables(pool).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut tx)
+        .await?;
+
+    let mut tables = vec![];
+    for table_name in table_names {
+        let columns = get_columns(pool, &table_name).await?;
+        let row_count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table_name))
+            .fetch_one(&mut tx)
+            .await?;
+
+        let filename = format!("{}.csv.gz", table_name);
+        let filepath = snapshot_path.join(&filename);
+        info!(
+            "Dumping {} rows of {} into {}...",
+            row_count,
+            table_name,
+            filepath.display()
+        );
+
+        let mut encoder = GzEncoder::new(File::create(&filepath)?, Compression::default());
+        let query_str = format!(
+            "COPY ({}) TO STDOUT WITH (FORMAT csv, HEADER)",
+            get_dump_query(&table_name, &columns)
+        );
+        let mut stream = tx.copy_out_raw(&query_str).await?;
+        while let Some(chunk) = stream.next().await {
+            encoder.write_all(&chunk?)?;
+        }
+        drop(stream);
+        encoder.finish()?;
+
+        tables.push(SnapshotTable {
+            table_name,
+            sha256: sha256_file(&filepath)?,
+            filename,
+            columns,
+            row_count,
+        });
+    }
+    tx.commit().await?;
+
+    let manifest = SnapshotManifest {
+        name,
+        created_at,
+        schema_version,
+        biomedgps_version: env!("CARGO_PKG_VERSION").to_string(),
+        tables,
+    };
+    let manifest_file = File::create(snapshot_path.join(MANIFEST_FILENAME))?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
+
+    AnyOk(manifest)
+}
+
+/// List the snapshots in the snapshot directory, the latest one comes first.
+pub fn list_snapshots(
+    snapshot_dir: &Path,
+) -> Result<Vec<(PathBuf, SnapshotManifest)>, anyhow::Error> {
+    let mut snapshots = vec![];
+    if !snapshot_dir.exists() {
+        return AnyOk(snapshots);
+    }
+
+    for entry in std::fs::read_dir(snapshot_dir)? {
+        let path = entry?.path();
+        if !path.join(MANIFEST_FILENAME).is_file() {
+            continue;
+        }
+
+        match SnapshotManifest::read(&path) {
+            Ok(manifest) => snapshots.push((path, manifest)),
+            Err(e) => warn!("Skip the invalid snapshot {}: {}", path.display(), e),
+        }
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.1.created_at));
+    AnyOk(snapshots)
+}
+
+/// Restore a snapshot into the database, the database must have been migrated (see `run_migrations`). The checksums are verified first, and then the tables in the snapshot are truncated and restored in one transaction.
+///
+/// The database must not contain any entities or relations unless force is true, in that case the existing data of the tables in the snapshot is replaced.
+pub async fn restore_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_path: &Path,
+    force: bool,
+) -> Result<SnapshotManifest, anyhow::Error> {
+    let manifest = SnapshotManifest::read(snapshot_path)?;
+    info!(
+        "Verifying the checksums of the snapshot {}...",
+        manifest.name
+    );
+    manifest.verify(snapshot_path)?;
+
+    let schema_version = get_schema_version(pool).await?;
+    if schema_version < manifest.schema_version {
+        return Err(anyhow::anyhow!(
+            "The schema version of the snapshot ({}) is newer than the database ({}), please upgrade biomedgps and run `biomedgps-cli initdb` first.",
+            manifest.schema_version,
+            schema_version
+        ));
+    } else if schema_version > manifest.schema_version {
+        warn!(
+            "The schema version of the snapshot ({}) is older than the database ({}), the columns which are added later will be filled with their default values.",
+            manifest.schema_version, schema_version
+        );
+    }
+
+    let existing_tables = get_tables(pool).await?;
+    if let Some(table) = manifest
+        .tables
+        .iter()
+        .find(|t| !existing_tables.contains(&t.table_name))
+    {
+        return Err(anyhow::anyhow!(
+            "The table {} doesn't exist in the database.",
+            table.table_name
+        ));
+    }
+
+    if !force {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM biomedgps_entity) + (SELECT COUNT(*) FROM biomedgps_relation)",
+        )
+        .fetch_one(pool)
+        .await?;
+        if count > 0 {
+            return Err(anyhow::anyhow!(
+                "The database is not empty, please restore the snapshot into a fresh database or use the --force option to replace the existing data."
+            ));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    let table_names = manifest
+        .tables
+        .iter()
+        .map(|t| t.table_name.as_str())
+        .collect::<Vec<&str>>();
+    sqlx::query(&format!(
+        "TRUNCATE {} RESTART IDENTITY CASCADE",
+        table_names.join(", ")
+    ))
+    .execute(&mut tx)
+    .await?;
+
+    let mut buffer = vec![0; 1024 * 1024];
+    for table in manifest.tables.iter() {
+        info!(
+            "Restoring {} rows of {}...",
+            table.row_count, table.table_name
+        );
+        let mut decoder = MultiGzDecoder::new(File::open(snapshot_path.join(&table.filename))?);
+        let mut copy = tx
+            .copy_in_raw(&format!(
+                "COPY {} ({}) FROM STDIN WITH (FORMAT csv, HEADER)",
+                table.table_name,
+                table.columns.join(", ")
+            ))
+            .await?;
+
+        loop {
+            let size = match decoder.read(&mut buffer) {
+                Ok(size) => size,
+                Err(e) => {
+                    copy.abort(e.to_string()).await?;
+                    return Err(e.into());
+                }
+            };
+            if size == 0 {
+                break;
+            }
+            copy.send(&buffer[..size]).await?;
+        }
+        copy.finish().await?;
+
+        // Reset the sequences of the serial columns, so the new rows don't conflict with the restored rows.
+        let serial_columns: Vec<String> = sqlx::query_scalar(
+            "SELECT column_name::TEXT FROM information_schema.columns
+             WHERE table_schema = 'public' AND table_name = $1 AND column_default LIKE 'nextval(%'",
+        )
+        .bind(&table.table_name)
+        .fetch_all(&mut tx)
+        .await?;
+        for column in serial_columns {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence('{}', '{}'), COALESCE(MAX({}), 0) + 1, false) FROM {}",
+                table.table_name, column, column, table.table_name
+            ))
+            .execute(&mut tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+
+    AnyOk(manifest)
+}
@@ -0,0 +1,694 @@
+//! Split module is used to split the relations into the train, valid and test sets for training the knowledge graph embeddings (such as DGL-KE and PyKEEN). The split is kept in the `split` column of the relation table, so the same split can be exported again after the data are updated.
+//!
+//! The relations are stratified by the relation type, each relation type is split by the ratios independently, so a rare relation type is also in the valid and test sets. The relations are shuffled by a seeded random generator after they are sorted by id, so the same seed gives the same split on the same data.
+//!
+//! The negative triples for training are generated by replacing the head or the tail of a positive triple with another entity of the same type, the known triples are excluded, so the negatives are consistent with the type constraints of the relation types.
+
+use anyhow::Ok as AnyOk;
+use futures::{Stream, StreamExt};
+use log::{debug, error, info};
+use poem_openapi::Enum;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// The default seed of the random generator which shuffles the relations.
+pub const DEFAULT_SPLIT_SEED: u64 = 42;
+/// The default ratios of the train, valid and test sets.
+pub const DEFAULT_SPLIT_RATIOS: &str = "0.8,0.1,0.1";
+
+/// The max number of the negatives of a positive triple.
+pub const MAX_NEGATIVES_PER_TRIPLE: usize = 100;
+
+// The number of the relations which are updated in one statement.
+const UPDATE_BATCH_SIZE: usize = 10000;
+// The max number of the formatted triples which are waiting to be sent.
+const STREAM_CHANNEL_SIZE: usize = 256;
+// The max attempts to sample a negative which is not a known triple, the negative is skipped after the attempts, such as when the entity type only has a few entities.
+const NEGATIVE_SAMPLING_ATTEMPTS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RelationSplit {
+    Train,
+    Valid,
+    Test,
+}
+
+impl RelationSplit {
+    /// Parse the split which is stored in the database, it returns None if the split is unknown.
+    ///
+    /// ```
+    /// use biomedgps::model::split::RelationSplit;
+    ///
+    /// assert_eq!(RelationSplit::from_name("valid"), Some(RelationSplit::Valid));
+    /// assert_eq!(RelationSplit::from_name("validation"), None);
+    /// ```
+    pub fn from_name(split: &str) -> Option<RelationSplit> {
+        match split {
+            "train" => Some(RelationSplit::Train),
+            "valid" => Some(RelationSplit::Valid),
+            "test" => Some(RelationSplit::Test),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationSplit::Train => "train",
+            RelationSplit::Valid => "valid",
+            RelationSplit::Test => "test",
+        }
+    }
+}
+
+impl FromStr for RelationSplit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RelationSplit::from_name(&s.to_lowercase())
+            .ok_or_else(|| format!("Invalid split: {}, it must be train, valid or test.", s))
+    }
+}
+
+/// Which entity of a positive triple is replaced to generate a negative triple.
+///
+/// * `Head` - Replace the head by another entity of the same type.
+/// * `Tail` - Replace the tail by another entity of the same type.
+/// * `Both` - Replace the head or the tail randomly, which is the default of DGL-KE and PyKEEN.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CorruptionSide {
+    Head,
+    Tail,
+    Both,
+}
+
+/// The ratios of the train, valid and test sets, they sum to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitRatios {
+    pub train: f64,
+    pub valid: f64,
+    pub test: f64,
+}
+
+impl SplitRatios {
+    /// Parse the ratios like `0.8,0.1,0.1`, the train ratio must be greater than 0 and the ratios must sum to 1.
+    ///
+    /// ```
+    /// use biomedgps::model::split::SplitRatios;
+    ///
+    /// let ratios = SplitRatios::parse("0.9, 0.05, 0.05").unwrap();
+    /// assert_eq!(ratios.valid, 0.05);
+    /// assert!(SplitRatios::parse("0.8,0.1").is_err());
+    /// assert!(SplitRatios::parse("0.8,0.3,0.1").is_err());
+    /// ```
+    pub fn parse(ratios: &str) -> Result<SplitRatios, String> {
+        let values = ratios
+            .split(',')
+            .map(|ratio| ratio.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| format!("Invalid ratios: {}, {}", ratios, e))?;
+
+        let (train, valid, test) = match values.as_slice() {
+            [train, valid, test] => (*train, *valid, *test),
+            _ => {
+                return Err(format!(
+                    "Invalid ratios: {}, it should be the ratios of the train, valid and test sets, such as {}.",
+                    ratios, DEFAULT_SPLIT_RATIOS
+                ))
+            }
+        };
+
+        if train <= 0.0 || valid < 0.0 || test < 0.0 || (train + valid + test - 1.0).abs() > 1e-6 {
+            return Err(format!(
+                "Invalid ratios: {}, the train ratio should be greater than 0 and the ratios should sum to 1.",
+                ratios
+            ));
+        }
+
+        Ok(SplitRatios { train, valid, test })
+    }
+}
+
+/// The number of the relations in each split of a relation type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitSummary {
+    pub relation_type: String,
+    pub train: usize,
+    pub valid: usize,
+    pub test: usize,
+}
+
+/// Assign the relations (id and relation type) to the splits, the relations of each relation type are split by the ratios independently. The valid and test sizes are rounded, and the rest relations are in the train set, so a relation type with only one relation is always in the train set.
+pub fn assign_splits(
+    relations: &[(i64, String)],
+    ratios: &SplitRatios,
+    seed: u64,
+) -> (Vec<(i64, RelationSplit)>, Vec<SplitSummary>) {
+    let mut groups: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    for (id, relation_type) in relations {
+        groups.entry(relation_type.as_str()).or_default().push(*id);
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut assignments: Vec<(i64, RelationSplit)> = Vec::with_capacity(relations.len());
+    let mut summaries: Vec<SplitSummary> = vec![];
+    for (relation_type, mut ids) in groups {
+        // The order of the relations in the database is not stable, so we sort them before shuffling.
+        ids.sort_unstable();
+        ids.shuffle(&mut rng);
+
+        let total = ids.len();
+        let valid = ((total as f64 * ratios.valid).round() as usize).min(total - 1);
+        let test = ((total as f64 * ratios.test).round() as usize).min(total - 1 - valid);
+        let train = total - valid - test;
+
+        for (i, id) in ids.into_iter().enumerate() {
+            let split = if i < train {
+                RelationSplit::Train
+            } else if i < train + valid {
+                RelationSplit::Valid
+            } else {
+                RelationSplit::Test
+            };
+            assignments.push((id, split));
+        }
+
+        summaries.push(SplitSummary {
+            relation_type: relation_type.to_string(),
+            train,
+            valid,
+            test,
+        });
+    }
+
+    (assignments, summaries)
+}
+
+/// Split all relations into the train, valid and test sets and save the splits into the `split` column, the existing splits are replaced. If dry_run is true, only the summaries are returned.
+pub async fn generate_splits(
+    pool: &sqlx::PgPool,
+    ratios: &SplitRatios,
+    seed: u64,
+    dry_run: bool,
+) -> Result<Vec<SplitSummary>, anyhow::Error> {
+    let relations: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, relation_type FROM biomedgps_relation")
+            .fetch_all(pool)
+            .await?;
+    info!(
+        "Splitting {} relations with the seed {}.",
+        relations.len(),
+        seed
+    );
+
+    let (assignments, summaries) = assign_splits(&relations, ratios, seed);
+    if dry_run {
+        return AnyOk(summaries);
+    }
+
+    let mut tx = pool.begin().await?;
+    for chunk in assignments.chunks(UPDATE_BATCH_SIZE) {
+        let ids = chunk.iter().map(|(id, _)| *id).collect::<Vec<i64>>();
+        let splits = chunk
+            .iter()
+            .map(|(_, split)| split.as_str().to_string())
+            .collect::<Vec<String>>();
+
+        sqlx::query(
+            "UPDATE biomedgps_relation AS r SET split = v.split
+             FROM UNNEST($1::BIGINT[], $2::TEXT[]) AS v(id, split) WHERE r.id = v.id",
+        )
+        .bind(&ids)
+        .bind(&splits)
+        .execute(&mut tx)
+        .await?;
+        debug!("Saved the splits of {} relations.", chunk.len());
+    }
+    tx.commit().await?;
+
+    AnyOk(summaries)
+}
+
+/// Format a triple as a tab-separated line, the head and tail are the node ids like `Gene::ENTREZ:7157`. Both DGL-KE (`--format raw_udd_hrt`) and PyKEEN (`TriplesFactory.from_path`) read the files of such lines.
+///
+/// ```
+/// use biomedgps::model::split::format_triple;
+///
+/// assert_eq!(
+///     format_triple("Compound::MESH:D008687", "DRUGBANK::treats::Compound:Disease", "Disease::MESH:D003924"),
+///     "Compound::MESH:D008687\tDRUGBANK::treats::Compound:Disease\tDisease::MESH:D003924\n"
+/// );
+/// ```
+pub fn format_triple(head: &str, relation: &str, tail: &str) -> String {
+    // A tab or a newline in the ids would break the columns, they are not allowed by the ENTITY_ID_REGEX, but the relation types are not checked.
+    let clean = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+    format!("{}\t{}\t{}\n", clean(head), clean(relation), clean(tail))
+}
+
+/// Format a triple with its group as a tab-separated line, the group is the data source (the `resource` column, such as `drkg` or `drkg|hetionet`) of the relation. DGL-KE only reads three columns, so the group column should be cut (or used to weight or exclude the triples) before training.
+///
+/// ```
+/// use biomedgps::model::split::format_grouped_triple;
+///
+/// assert_eq!(
+///     format_grouped_triple("Compound::MESH:D008687", "DRUGBANK::treats::Compound:Disease", "Disease::MESH:D003924", "drkg"),
+///     "Compound::MESH:D008687\tDRUGBANK::treats::Compound:Disease\tDisease::MESH:D003924\tdrkg\n"
+/// );
+/// ```
+pub fn format_grouped_triple(head: &str, relation: &str, tail: &str, group: &str) -> String {
+    let mut line = format_triple(head, relation, tail);
+    line.pop();
+    line.push('\t');
+    line.push_str(&group.replace(['\t', '\n', '\r'], " "));
+    line.push('\n');
+    line
+}
+
+/// Get the file name of a group, the characters which are not safe in a file name (such as `|` and `/`) are replaced by `_`, an empty group is named `unknown`.
+///
+/// ```
+/// use biomedgps::model::split::group_filename;
+///
+/// assert_eq!(group_filename("drkg"), "drkg");
+/// assert_eq!(group_filename("drkg|hetionet"), "drkg_hetionet");
+/// assert_eq!(group_filename("../ctd"), ".._ctd");
+/// assert_eq!(group_filename(" "), "unknown");
+/// ```
+pub fn group_filename(group: &str) -> String {
+    let group = group.trim();
+    if group.is_empty() {
+        return "unknown".to_string();
+    }
+
+    group
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Stream the triples of a split from a database cursor, one triple per line without a header (see [`format_triple`]). The triples are sorted by the relation id, so the file is same for the same split. If with_group is true, the data source of each triple is appended as the fourth column (see [`format_grouped_triple`]).
+pub fn stream_triples(
+    pool: Arc<sqlx::PgPool>,
+    split: RelationSplit,
+    with_group: bool,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static {
+    let (sender, receiver) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        let sql_str = "SELECT source_type || '::' || source_id, relation_type, target_type || '::' || target_id, resource
+                       FROM biomedgps_relation WHERE split = $1 ORDER BY id";
+        let mut total = 0;
+        let mut rows = sqlx::query_as::<_, (String, String, String, String)>(sql_str)
+            .bind(split.as_str())
+            .fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            let line = match row {
+                Ok((head, relation, tail, group)) => {
+                    let line = if with_group {
+                        format_grouped_triple(&head, &relation, &tail, &group)
+                    } else {
+                        format_triple(&head, &relation, &tail)
+                    };
+                    Ok(line.into_bytes())
+                }
+                Err(e) => {
+                    error!("Failed to stream the {} triples: {}", split.as_str(), e);
+                    Err(std::io::Error::new(std::io::ErrorKind::Other, e))
+                }
+            };
+
+            let failed = line.is_err();
+            if sender.send(line).await.is_err() {
+                debug!(
+                    "The client stopped receiving the {} triples after {} triples.",
+                    split.as_str(),
+                    total
+                );
+                return;
+            }
+
+            if failed {
+                return;
+            }
+            total += 1;
+        }
+
+        info!("Streamed {} {} triples.", total, split.as_str());
+    });
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+/// Export the triples of a split into the output directory, it returns the number of the triples of each group. If by_group is true, the triples of each group are written into `<output_dir>/<group>/<split>.tsv` without the group column (see [`group_filename`]), so each group directory is a dataset of DGL-KE or PyKEEN. Otherwise all triples are written into `<output_dir>/<split>.tsv` with the group column (see [`format_grouped_triple`]).
+pub async fn export_triples(
+    pool: &sqlx::PgPool,
+    split: RelationSplit,
+    output_dir: &Path,
+    by_group: bool,
+) -> Result<BTreeMap<String, usize>, anyhow::Error> {
+    std::fs::create_dir_all(output_dir)?;
+    let filename = format!("{}.tsv", split.as_str());
+    let mut writers: HashMap<String, BufWriter<File>> = HashMap::new();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    let sql_str = "SELECT source_type || '::' || source_id, relation_type, target_type || '::' || target_id, resource
+                   FROM biomedgps_relation WHERE split = $1 ORDER BY id";
+    let mut rows = sqlx::query_as::<_, (String, String, String, String)>(sql_str)
+        .bind(split.as_str())
+        .fetch(pool);
+    while let Some(row) = rows.next().await {
+        let (head, relation, tail, group) = row?;
+        let (key, line) = if by_group {
+            (
+                group_filename(&group),
+                format_triple(&head, &relation, &tail),
+            )
+        } else {
+            (
+                String::new(),
+                format_grouped_triple(&head, &relation, &tail, &group),
+            )
+        };
+
+        let writer = match writers.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let dir = output_dir.join(entry.key());
+                std::fs::create_dir_all(&dir)?;
+                entry.insert(BufWriter::new(File::create(dir.join(&filename))?))
+            }
+        };
+        writer.write_all(line.as_bytes())?;
+        *counts.entry(group).or_insert(0) += 1;
+    }
+
+    for writer in writers.values_mut() {
+        writer.flush()?;
+    }
+
+    info!(
+        "Exported {} {} triples of {} groups into {:?}.",
+        counts.values().sum::<usize>(),
+        split.as_str(),
+        counts.len(),
+        output_dir
+    );
+    AnyOk(counts)
+}
+
+/// Get the entity type of a node id, such as `Gene` of `Gene::ENTREZ:7157`.
+fn get_node_type(node_id: &str) -> &str {
+    node_id.split("::").next().unwrap_or_default()
+}
+
+/// Generate the type-constrained negative triples by replacing the head or the tail of a positive triple with another entity of the same type, the known triples (in all splits) are never generated as negatives.
+pub struct NegativeSampler {
+    node_ids_by_type: HashMap<String, Vec<String>>,
+    known_triples: HashSet<(String, String, String)>,
+    rng: StdRng,
+}
+
+impl NegativeSampler {
+    /// Make a sampler from the node ids of all entities and all known triples (head, relation type and tail), the same seed gives the same negatives for the same positives.
+    pub fn new(
+        node_ids: Vec<String>,
+        known_triples: HashSet<(String, String, String)>,
+        seed: u64,
+    ) -> Self {
+        let mut node_ids_by_type: HashMap<String, Vec<String>> = HashMap::new();
+        for node_id in node_ids {
+            node_ids_by_type
+                .entry(get_node_type(&node_id).to_string())
+                .or_default()
+                .push(node_id);
+        }
+
+        // The order of the entities in the database is not stable, so we sort them before sampling.
+        for node_ids in node_ids_by_type.values_mut() {
+            node_ids.sort_unstable();
+        }
+
+        NegativeSampler {
+            node_ids_by_type,
+            known_triples,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Sample at most `num_negatives` distinct negatives (head and tail) of a positive triple, fewer negatives are returned if the entity type doesn't have enough entities which make unknown triples.
+    pub fn sample(
+        &mut self,
+        head: &str,
+        relation_type: &str,
+        tail: &str,
+        side: CorruptionSide,
+        num_negatives: usize,
+    ) -> Vec<(String, String)> {
+        let mut negatives: Vec<(String, String)> = Vec::with_capacity(num_negatives);
+        for _ in 0..num_negatives {
+            for _ in 0..NEGATIVE_SAMPLING_ATTEMPTS {
+                let corrupt_head = match side {
+                    CorruptionSide::Head => true,
+                    CorruptionSide::Tail => false,
+                    CorruptionSide::Both => self.rng.gen_bool(0.5),
+                };
+
+                let replaced = if corrupt_head { head } else { tail };
+                let candidates = match self.node_ids_by_type.get(get_node_type(replaced)) {
+                    Some(candidates) if !candidates.is_empty() => candidates,
+                    _ => break,
+                };
+                let candidate = &candidates[self.rng.gen_range(0..candidates.len())];
+
+                let negative = if corrupt_head {
+                    (candidate.clone(), tail.to_string())
+                } else {
+                    (head.to_string(), candidate.clone())
+                };
+                let known = self.known_triples.contains(&(
+                    negative.0.clone(),
+                    relation_type.to_string(),
+                    negative.1.clone(),
+                ));
+
+                if candidate != replaced && !known && !negatives.contains(&negative) {
+                    negatives.push(negative);
+                    break;
+                }
+            }
+        }
+
+        negatives
+    }
+}
+
+/// Stream the negatives of the triples of a split, `num_negatives` negatives are generated for each positive triple (see [`NegativeSampler`]). The lines are same as the triples (see [`format_triple`]), the negatives of a positive triple are adjacent and the positive triples are sorted by the relation id.
+pub fn stream_negatives(
+    pool: Arc<sqlx::PgPool>,
+    split: RelationSplit,
+    side: CorruptionSide,
+    num_negatives: usize,
+    seed: u64,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static {
+    let (sender, receiver) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(STREAM_CHANNEL_SIZE);
+    tokio::spawn(async move {
+        let loaded = async {
+            let node_ids: Vec<String> =
+                sqlx::query_scalar("SELECT label || '::' || id FROM biomedgps_entity")
+                    .fetch_all(&*pool)
+                    .await?;
+            let known_triples = sqlx::query_as::<_, (String, String, String)>(
+                "SELECT source_type || '::' || source_id, relation_type, target_type || '::' || target_id
+                 FROM biomedgps_relation",
+            )
+            .fetch_all(&*pool)
+            .await?
+            .into_iter()
+            .collect::<HashSet<(String, String, String)>>();
+
+            Ok::<_, sqlx::Error>(NegativeSampler::new(node_ids, known_triples, seed))
+        };
+
+        let mut sampler = match loaded.await {
+            Ok(sampler) => sampler,
+            Err(e) => {
+                error!("Failed to load the entities and the triples: {}", e);
+                let _ = sender
+                    .send(Err(std::io::Error::new(std::io::ErrorKind::Other, e)))
+                    .await;
+                return;
+            }
+        };
+
+        let sql_str = "SELECT source_type || '::' || source_id, relation_type, target_type || '::' || target_id
+                       FROM biomedgps_relation WHERE split = $1 ORDER BY id";
+        let mut total = 0;
+        let mut rows = sqlx::query_as::<_, (String, String, String)>(sql_str)
+            .bind(split.as_str())
+            .fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            let lines = match row {
+                Ok((head, relation, tail)) => {
+                    let negatives = sampler.sample(&head, &relation, &tail, side, num_negatives);
+                    total += negatives.len();
+                    Ok(negatives
+                        .iter()
+                        .map(|(head, tail)| format_triple(head, &relation, tail))
+                        .collect::<String>()
+                        .into_bytes())
+                }
+                Err(e) => {
+                    error!("Failed to stream the {} negatives: {}", split.as_str(), e);
+                    Err(std::io::Error::new(std::io::ErrorKind::Other, e))
+                }
+            };
+
+            let failed = lines.is_err();
+            if sender.send(lines).await.is_err() {
+                debug!(
+                    "The client stopped receiving the {} negatives after {} negatives.",
+                    split.as_str(),
+                    total
+                );
+                return;
+            }
+
+            if failed {
+                return;
+            }
+        }
+
+        info!("Streamed {} {} negatives.", total, split.as_str());
+    });
+
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_splits() {
+        let mut relations: Vec<(i64, String)> = (0..100).map(|id| (id, "A".to_string())).collect();
+        relations.extend((100..110).map(|id| (id, "B".to_string())));
+        relations.push((110, "C".to_string()));
+        let ratios = SplitRatios::parse(DEFAULT_SPLIT_RATIOS).unwrap();
+
+        let (assignments, summaries) = assign_splits(&relations, &ratios, DEFAULT_SPLIT_SEED);
+        assert_eq!(assignments.len(), relations.len());
+        assert_eq!(
+            summaries,
+            vec![
+                SplitSummary {
+                    relation_type: "A".to_string(),
+                    train: 80,
+                    valid: 10,
+                    test: 10,
+                },
+                SplitSummary {
+                    relation_type: "B".to_string(),
+                    train: 8,
+                    valid: 1,
+                    test: 1,
+                },
+                SplitSummary {
+                    relation_type: "C".to_string(),
+                    train: 1,
+                    valid: 0,
+                    test: 0,
+                },
+            ]
+        );
+
+        // The same seed gives the same split regardless of the order of the relations.
+        let mut reversed = relations.clone();
+        reversed.reverse();
+        let (mut other, _) = assign_splits(&reversed, &ratios, DEFAULT_SPLIT_SEED);
+        let mut assignments = assignments;
+        assignments.sort();
+        other.sort();
+        assert_eq!(assignments, other);
+    }
+
+    #[test]
+    fn test_negative_sampler() {
+        let node_ids = vec![
+            "Compound::A".to_string(),
+            "Compound::B".to_string(),
+            "Compound::C".to_string(),
+            "Disease::X".to_string(),
+            "Disease::Y".to_string(),
+        ];
+        let known_triples = HashSet::from([
+            (
+                "Compound::A".to_string(),
+                "treats".to_string(),
+                "Disease::X".to_string(),
+            ),
+            (
+                "Compound::B".to_string(),
+                "treats".to_string(),
+                "Disease::X".to_string(),
+            ),
+        ]);
+        let mut sampler = NegativeSampler::new(node_ids, known_triples, DEFAULT_SPLIT_SEED);
+
+        // Only Compound::C makes an unknown triple with the tail, so only one negative is generated.
+        let negatives = sampler.sample(
+            "Compound::A",
+            "treats",
+            "Disease::X",
+            CorruptionSide::Head,
+            3,
+        );
+        assert_eq!(
+            negatives,
+            vec![("Compound::C".to_string(), "Disease::X".to_string())]
+        );
+
+        let negatives = sampler.sample(
+            "Compound::A",
+            "treats",
+            "Disease::X",
+            CorruptionSide::Tail,
+            1,
+        );
+        assert_eq!(
+            negatives,
+            vec![("Compound::A".to_string(), "Disease::Y".to_string())]
+        );
+
+        for (head, tail) in sampler.sample(
+            "Compound::A",
+            "treats",
+            "Disease::X",
+            CorruptionSide::Both,
+            10,
+        ) {
+            assert!(head.starts_with("Compound::") && tail.starts_with("Disease::"));
+        }
+    }
+}
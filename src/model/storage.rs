@@ -0,0 +1,356 @@
+//! Storage module keeps the large payloads of the subgraphs out of the database, so the rows of `biomedgps_subgraph` stay small and the tables can be vacuumed and backed up quickly.
+//!
+//! The storage is configured per deployment by `payload_storage` (env var: PAYLOAD_STORAGE), it is `inline` (default, the payload is kept in the row), a local directory (such as `file:///data/subgraphs`) or an S3 prefix (such as `s3://bucket/subgraphs`, see [`crate::model::remote`] for the credentials and the S3 compatible services, such as MinIO). Only the payloads which are larger than `payload_offload_threshold` bytes are offloaded, the row keeps a reference instead, such as `{"$ref": "s3://bucket/subgraphs/<id>/<sha256>.json", "sha256": "...", "size": 2097152, "nodes": [{"id": "Gene::ENTREZ:7157"}]}`. The ids of the nodes are kept in the reference, so the subgraphs can still be queried by the nodes (see [`crate::model::core::Subgraph::merge_node_query`]).
+//!
+//! The references are resolved by their urls when the subgraphs are read, so the deployment can switch the storage without migrating the existing subgraphs. The objects are named by the checksum and never overwritten, and they are not removed when the subgraphs are updated or purged, use the lifecycle rules of the bucket to expire them if needed.
+
+use crate::model::remote::build_s3_request;
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// The payloads which are larger than 1 MiB are offloaded by default.
+pub const DEFAULT_OFFLOAD_THRESHOLD: usize = 1024 * 1024;
+
+const CONNECT_TIMEOUT: u64 = 30;
+
+/// Where the large payloads are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadStorage {
+    /// The payloads are kept in the rows.
+    Inline,
+    /// The payloads are kept in a local directory, it must be shared by all instances of the server.
+    LocalDir(PathBuf),
+    /// The payloads are kept in an S3 bucket with the prefix (it may be empty).
+    S3 { bucket: String, prefix: String },
+}
+
+impl FromStr for PayloadStorage {
+    type Err = String;
+
+    /// Parse the storage from `inline`, `file:///path/to/dir` or `s3://bucket/prefix`.
+    ///
+    /// ```
+    /// use biomedgps::model::storage::PayloadStorage;
+    /// use std::path::PathBuf;
+    ///
+    /// assert_eq!("inline".parse::<PayloadStorage>().unwrap(), PayloadStorage::Inline);
+    /// assert_eq!("file:///data/subgraphs/".parse::<PayloadStorage>().unwrap(), PayloadStorage::LocalDir(PathBuf::from("/data/subgraphs")));
+    /// assert_eq!(
+    ///     "s3://biomedgps/payloads/subgraphs".parse::<PayloadStorage>().unwrap(),
+    ///     PayloadStorage::S3 { bucket: "biomedgps".to_string(), prefix: "payloads/subgraphs".to_string() }
+    /// );
+    /// assert!("file://data".parse::<PayloadStorage>().is_err());
+    /// assert!("s3://".parse::<PayloadStorage>().is_err());
+    /// assert!("minio".parse::<PayloadStorage>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "inline" {
+            Ok(PayloadStorage::Inline)
+        } else if let Some(path) = s.strip_prefix("file://") {
+            if !path.starts_with('/') {
+                return Err(format!(
+                    "Invalid payload storage: {}, the directory must be an absolute path, such as file:///data/subgraphs.",
+                    s
+                ));
+            }
+
+            let path = path.trim_end_matches('/');
+            Ok(PayloadStorage::LocalDir(PathBuf::from(
+                if path.is_empty() { "/" } else { path },
+            )))
+        } else if let Some(location) = s.strip_prefix("s3://") {
+            let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+            if bucket.is_empty() {
+                return Err(format!(
+                    "Invalid payload storage: {}, the bucket is missing, such as s3://bucket/subgraphs.",
+                    s
+                ));
+            }
+
+            Ok(PayloadStorage::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_matches('/').to_string(),
+            })
+        } else {
+            Err(format!(
+                "Invalid payload storage: {}, it should be inline, file:///path/to/dir or s3://bucket/prefix.",
+                s
+            ))
+        }
+    }
+}
+
+impl PayloadStorage {
+    /// The url of the object with the key, it is None for the inline storage.
+    ///
+    /// ```
+    /// use biomedgps::model::storage::PayloadStorage;
+    ///
+    /// let storage = "s3://biomedgps/payloads".parse::<PayloadStorage>().unwrap();
+    /// assert_eq!(storage.object_url("subgraphs/1/abc.json").unwrap(), "s3://biomedgps/payloads/subgraphs/1/abc.json");
+    /// let storage = "file:///data".parse::<PayloadStorage>().unwrap();
+    /// assert_eq!(storage.object_url("subgraphs/1/abc.json").unwrap(), "file:///data/subgraphs/1/abc.json");
+    /// assert!(PayloadStorage::Inline.object_url("subgraphs/1/abc.json").is_none());
+    /// ```
+    pub fn object_url(&self, key: &str) -> Option<String> {
+        match self {
+            PayloadStorage::Inline => None,
+            PayloadStorage::LocalDir(dir) => Some(format!("file://{}", dir.join(key).display())),
+            PayloadStorage::S3 { bucket, prefix } if prefix.is_empty() => {
+                Some(format!("s3://{}/{}", bucket, key))
+            }
+            PayloadStorage::S3 { bucket, prefix } => {
+                Some(format!("s3://{}/{}/{}", bucket, prefix, key))
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref PAYLOAD_STORAGE: RwLock<PayloadStorage> = RwLock::new(PayloadStorage::Inline);
+}
+
+static OFFLOAD_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_OFFLOAD_THRESHOLD);
+
+/// Set the storage of the payloads and the size (in bytes) above which the payloads are offloaded, it is called once when the server starts.
+pub fn set_payload_storage(storage: PayloadStorage, threshold: usize) {
+    *PAYLOAD_STORAGE.write().unwrap() = storage;
+    OFFLOAD_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+pub fn get_payload_storage() -> (PayloadStorage, usize) {
+    (
+        PAYLOAD_STORAGE.read().unwrap().clone(),
+        OFFLOAD_THRESHOLD.load(Ordering::Relaxed),
+    )
+}
+
+/// The reference which is kept in the row instead of an offloaded payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadRef {
+    #[serde(rename = "$ref")]
+    pub url: String,
+    pub sha256: String,
+    pub size: usize,
+    /// The ids of the nodes, such as `[{"id": "Gene::ENTREZ:7157"}]`.
+    #[serde(default)]
+    pub nodes: Vec<serde_json::Value>,
+}
+
+impl PayloadRef {
+    /// Parse the stored payload as a reference, it returns None if the payload is inline.
+    ///
+    /// ```
+    /// use biomedgps::model::storage::PayloadRef;
+    ///
+    /// let stored = r#"{"$ref": "file:///data/subgraphs/1/abc.json", "sha256": "abc", "size": 10, "nodes": [{"id": "Gene::ENTREZ:7157"}]}"#;
+    /// assert_eq!(PayloadRef::parse(stored).unwrap().url, "file:///data/subgraphs/1/abc.json");
+    /// assert!(PayloadRef::parse(r#"{"nodes": [], "edges": []}"#).is_none());
+    /// assert!(PayloadRef::parse("not a json").is_none());
+    /// ```
+    pub fn parse(stored: &str) -> Option<PayloadRef> {
+        let value = serde_json::from_str::<serde_json::Value>(stored).ok()?;
+        Self::from_value(&value)
+    }
+
+    /// The same as [`PayloadRef::parse`], but for a parsed payload, such as the payload of a projected record.
+    pub fn from_value(value: &serde_json::Value) -> Option<PayloadRef> {
+        if value.get("$ref").is_none() {
+            return None;
+        }
+
+        serde_json::from_value::<PayloadRef>(value.clone()).ok()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn build_client() -> Result<reqwest::Client, anyhow::Error> {
+    AnyOk(
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT))
+            .build()?,
+    )
+}
+
+async fn write_object(url: &str, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    if let Some(path) = url.strip_prefix("file://") {
+        let path = PathBuf::from(path);
+        // The objects are named by the checksum, so an existing object has the same content.
+        if path.exists() {
+            return AnyOk(());
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let part_path = PathBuf::from(format!("{}.part", path.display()));
+        std::fs::write(&part_path, bytes)?;
+        std::fs::rename(&part_path, &path)?;
+        return AnyOk(());
+    }
+
+    let client = build_client()?;
+    let response = build_s3_request(
+        &client,
+        reqwest::Method::PUT,
+        url,
+        None,
+        Some(bytes.to_vec()),
+    )?
+    .send()
+    .await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to upload {}: {}",
+            url,
+            response.status()
+        ));
+    }
+
+    AnyOk(())
+}
+
+async fn read_object(url: &str) -> Result<Vec<u8>, anyhow::Error> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return AnyOk(std::fs::read(path)?);
+    }
+
+    if !url.starts_with("s3://") {
+        return Err(anyhow::anyhow!(
+            "Unsupported payload url: {}, it should be a file:// or s3:// url.",
+            url
+        ));
+    }
+
+    let client = build_client()?;
+    let response = build_s3_request(&client, reqwest::Method::GET, url, None, None)?
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to download {}: {}",
+            url,
+            response.status()
+        ));
+    }
+
+    AnyOk(response.bytes().await?.to_vec())
+}
+
+/// Prepare the payload of a subgraph for saving, it is offloaded to the configured storage if it is larger than the threshold (see [`set_payload_storage`]), and the reference is returned instead. Otherwise the payload is returned as is.
+pub async fn store_payload(subgraph_id: &str, payload: &str) -> Result<String, anyhow::Error> {
+    // Otherwise a crafted payload could make the server read any file or object.
+    if PayloadRef::parse(payload).is_some() {
+        return Err(anyhow::anyhow!(
+            "The payload cannot be a reference, the $ref field is reserved."
+        ));
+    }
+
+    let (storage, threshold) = get_payload_storage();
+    if storage == PayloadStorage::Inline || payload.len() <= threshold {
+        return AnyOk(payload.to_string());
+    }
+
+    let value = serde_json::from_str::<serde_json::Value>(payload)?;
+    let nodes = match value.get("nodes").and_then(|nodes| nodes.as_array()) {
+        Some(nodes) => nodes
+            .iter()
+            .filter_map(|node| node.get("id"))
+            .map(|id| serde_json::json!({ "id": id }))
+            .collect(),
+        None => vec![],
+    };
+
+    let sha256 = to_hex(&Sha256::digest(payload.as_bytes()));
+    let key = format!("subgraphs/{}/{}.json", subgraph_id, sha256);
+    // The storage is not inline here, so the url always exists.
+    let url = storage.object_url(&key).unwrap_or_default();
+    write_object(&url, payload.as_bytes()).await?;
+    debug!(
+        "Offloaded the payload ({} bytes) to {}.",
+        payload.len(),
+        url
+    );
+
+    let reference = PayloadRef {
+        url,
+        sha256,
+        size: payload.len(),
+        nodes,
+    };
+    AnyOk(serde_json::to_string(&reference)?)
+}
+
+/// Resolve the stored payload of a subgraph, the offloaded payload is fetched by its reference and verified by the checksum. The inline payload is returned as is.
+pub async fn load_payload(stored: &str) -> Result<String, anyhow::Error> {
+    let reference = match PayloadRef::parse(stored) {
+        Some(reference) => reference,
+        None => return AnyOk(stored.to_string()),
+    };
+
+    let bytes = read_object(&reference.url).await?;
+    let actual = to_hex(&Sha256::digest(&bytes));
+    if actual != reference.sha256 {
+        return Err(anyhow::anyhow!(
+            "The checksum of the payload {} doesn't match, expected: {}, actual: {}.",
+            reference.url,
+            reference.sha256,
+            actual
+        ));
+    }
+
+    AnyOk(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_payload_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = PayloadStorage::LocalDir(dir.path().to_path_buf());
+        let payload = serde_json::json!({
+            "nodes": [{"id": "Gene::ENTREZ:7157", "label": "TP53"}],
+            "edges": [],
+        })
+        .to_string();
+
+        set_payload_storage(storage.clone(), 10);
+        let stored = store_payload("subgraph-1", &payload).await.unwrap();
+        set_payload_storage(PayloadStorage::Inline, DEFAULT_OFFLOAD_THRESHOLD);
+
+        let reference = PayloadRef::parse(&stored).unwrap();
+        assert!(reference.url.starts_with("file://"));
+        assert_eq!(reference.size, payload.len());
+        assert_eq!(
+            reference.nodes,
+            vec![serde_json::json!({"id": "Gene::ENTREZ:7157"})]
+        );
+
+        // The reference is resolved even if the storage is switched back to inline.
+        assert_eq!(load_payload(&stored).await.unwrap(), payload);
+        assert_eq!(load_payload(&payload).await.unwrap(), payload);
+        assert!(store_payload("subgraph-1", &stored).await.is_err());
+        assert_eq!(
+            store_payload("subgraph-1", &payload).await.unwrap(),
+            payload
+        );
+
+        let path = reference.url.trim_start_matches("file://");
+        std::fs::write(path, "{}").unwrap();
+        assert!(load_payload(&stored).await.is_err());
+    }
+}
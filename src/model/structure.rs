@@ -0,0 +1,771 @@
+//! Structure module keeps the chemical structures (SMILES, InChI and InChIKey) of the compounds and searches the compounds by structure, so the chemists can find the compounds which contain a substructure or are similar to a molecule.
+//!
+//! The structures are imported by `biomedgps-cli importdb -t compound_structure` from a file with the entity_id, entity_type, smiles, inchi and inchikey columns. The search is implemented in Rust instead of a database cartridge (such as RDKit), so it works on any postgresql server:
+//!
+//! * The SMILES are parsed into the molecular graphs of the heavy atoms (see [`Molecule::from_smiles`]), the atoms are compared by the element and the aromaticity, and the bonds by the order.
+//! * The fingerprint of a molecule hashes all linear paths of up to 6 atoms into 1024 bits (see [`Fingerprint`]), the similarity is the Tanimoto coefficient of the fingerprints.
+//! * A substructure search screens the compounds by the fingerprints (all bits of the query must be set) and then matches the query graph against the compound graph atom by atom.
+//!
+//! The structures are scanned for each search, it is fast enough for tens of thousands of compounds.
+
+use crate::model::core::{CheckData, ENTITY_ID_REGEX, ENTITY_LABEL_REGEX};
+use anyhow::Ok as AnyOk;
+use futures::TryStreamExt;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use poem_openapi::{Enum, Object};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::{error::Error, path::PathBuf};
+use validator::Validate;
+
+lazy_static! {
+    // The characters which may appear in a SMILES string, the string is parsed before it is searched.
+    pub static ref SMILES_REGEX: Regex = Regex::new(r"^[A-Za-z0-9@+\-\[\]\(\)=#$:/\\%.*]+$").unwrap();
+    pub static ref INCHIKEY_REGEX: Regex = Regex::new(r"^[A-Z]{14}-[A-Z]{10}-[A-Z]$").unwrap();
+}
+
+/// The number of the bits of a fingerprint.
+pub const FINGERPRINT_BITS: usize = 1024;
+/// The max number of the atoms of the paths which are hashed into a fingerprint.
+pub const MAX_PATH_ATOMS: usize = 6;
+/// The default min Tanimoto similarity of a similarity search.
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.7;
+/// The default and the max number of the compounds which are returned by a search.
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+pub const MAX_SEARCH_LIMIT: usize = 500;
+/// The max number of the atoms of a query molecule.
+pub const MAX_QUERY_ATOMS: usize = 200;
+// The max number of the steps to match a query against a compound, the pathological compounds are skipped.
+const MAX_MATCH_STEPS: usize = 100000;
+
+const ORGANIC_SUBSET: [&str; 10] = ["B", "C", "N", "O", "P", "S", "F", "Cl", "Br", "I"];
+const AROMATIC_SUBSET: [&str; 6] = ["b", "c", "n", "o", "p", "s"];
+const AROMATIC_BRACKET: [&str; 8] = ["b", "c", "n", "o", "p", "s", "se", "as"];
+
+/// The order of a bond, the stereo bonds (`/` and `\`) are single bonds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    Quadruple,
+    Aromatic,
+}
+
+/// A heavy atom, the hydrogens, the charges, the isotopes and the chirality are ignored in the search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Atom {
+    /// The element symbol, such as `C` or `Cl`, the aromatic atoms are capitalized too.
+    pub element: String,
+    pub aromatic: bool,
+}
+
+/// The molecular graph of the heavy atoms which is parsed from a SMILES string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Molecule {
+    pub atoms: Vec<Atom>,
+    /// The neighbours of each atom and the orders of the bonds.
+    pub neighbours: Vec<Vec<(usize, BondOrder)>>,
+}
+
+impl Molecule {
+    fn add_atom(&mut self, atom: Atom) -> usize {
+        self.atoms.push(atom);
+        self.neighbours.push(vec![]);
+        self.atoms.len() - 1
+    }
+
+    fn add_bond(&mut self, a: usize, b: usize, order: Option<BondOrder>) -> Result<(), String> {
+        if a == b || self.bond(a, b).is_some() {
+            return Err(format!(
+                "The atom {} is bonded to the atom {} twice.",
+                a + 1,
+                b + 1
+            ));
+        }
+
+        // The bond between two aromatic atoms is aromatic unless it is specified.
+        let order = order.unwrap_or(if self.atoms[a].aromatic && self.atoms[b].aromatic {
+            BondOrder::Aromatic
+        } else {
+            BondOrder::Single
+        });
+        self.neighbours[a].push((b, order));
+        self.neighbours[b].push((a, order));
+        Ok(())
+    }
+
+    /// The order of the bond between two atoms, it is None if they are not bonded.
+    pub fn bond(&self, a: usize, b: usize) -> Option<BondOrder> {
+        self.neighbours[a]
+            .iter()
+            .find(|(neighbour, _)| *neighbour == b)
+            .map(|(_, order)| *order)
+    }
+
+    /// Parse a SMILES string into the molecular graph of the heavy atoms. The organic subset, the bracket atoms, the branches, the ring closures (including `%nn`) and the disconnected parts (`.`) are supported.
+    ///
+    /// ```
+    /// use biomedgps::model::structure::{BondOrder, Molecule};
+    ///
+    /// // Aspirin
+    /// let molecule = Molecule::from_smiles("CC(=O)Oc1ccccc1C(=O)O").unwrap();
+    /// assert_eq!(molecule.atoms.len(), 13);
+    /// assert_eq!(molecule.bond(1, 2), Some(BondOrder::Double));
+    /// assert_eq!(molecule.bond(4, 9), Some(BondOrder::Aromatic));
+    ///
+    /// assert!(Molecule::from_smiles("C1CC").is_err());
+    /// assert!(Molecule::from_smiles("C(C").is_err());
+    /// assert!(Molecule::from_smiles("Xy").is_err());
+    /// ```
+    pub fn from_smiles(smiles: &str) -> Result<Molecule, String> {
+        let chars = smiles.trim().chars().collect::<Vec<char>>();
+        if chars.is_empty() {
+            return Err("The SMILES is empty.".to_string());
+        }
+
+        let mut molecule = Molecule {
+            atoms: vec![],
+            neighbours: vec![],
+        };
+        let mut previous: Option<usize> = None;
+        let mut branches: Vec<Option<usize>> = vec![];
+        let mut rings: HashMap<u32, (usize, Option<BondOrder>)> = HashMap::new();
+        let mut order: Option<BondOrder> = None;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let position = i + 1;
+            match c {
+                '-' | '/' | '\\' => order = Some(BondOrder::Single),
+                '=' => order = Some(BondOrder::Double),
+                '#' => order = Some(BondOrder::Triple),
+                '$' => order = Some(BondOrder::Quadruple),
+                ':' => order = Some(BondOrder::Aromatic),
+                '.' => {
+                    previous = None;
+                    order = None;
+                }
+                '(' => {
+                    if previous.is_none() {
+                        return Err(format!("The branch at {} has no atom before it.", position));
+                    }
+                    branches.push(previous);
+                }
+                ')' => match branches.pop() {
+                    Some(atom) => {
+                        previous = atom;
+                        order = None;
+                    }
+                    None => return Err(format!("The `)` at {} is not opened.", position)),
+                },
+                '0'..='9' | '%' => {
+                    let atom = previous.ok_or(format!(
+                        "The ring closure at {} has no atom before it.",
+                        position
+                    ))?;
+                    let number = if c == '%' {
+                        let digits = chars.get(i + 1..i + 3).unwrap_or_default();
+                        if digits.len() != 2 || !digits.iter().all(|d| d.is_ascii_digit()) {
+                            return Err(format!(
+                                "The ring closure at {} needs two digits.",
+                                position
+                            ));
+                        }
+                        i += 2;
+                        digits.iter().collect::<String>().parse::<u32>().unwrap()
+                    } else {
+                        c.to_digit(10).unwrap()
+                    };
+
+                    match rings.remove(&number) {
+                        Some((other, other_order)) => {
+                            molecule.add_bond(other, atom, order.or(other_order))?
+                        }
+                        None => {
+                            rings.insert(number, (atom, order));
+                        }
+                    }
+                    order = None;
+                }
+                '[' => {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|c| *c == ']')
+                        .ok_or(format!("The `[` at {} is not closed.", position))?;
+                    let atom =
+                        parse_bracket_atom(&chars[i + 1..i + end].iter().collect::<String>())
+                            .map_err(|e| format!("{} at {}", e, position))?;
+                    let atom = molecule.add_atom(atom);
+                    if let Some(previous) = previous {
+                        molecule.add_bond(previous, atom, order)?;
+                    }
+                    previous = Some(atom);
+                    order = None;
+                    i += end;
+                }
+                '*' => {
+                    return Err(format!(
+                        "The wildcard atom at {} is not supported.",
+                        position
+                    ));
+                }
+                _ if c.is_ascii_alphabetic() => {
+                    // The two-letter elements of the organic subset go first, such as Cl and Br.
+                    let two = chars.get(i..i + 2).map(|s| s.iter().collect::<String>());
+                    let symbol = match two {
+                        Some(two) if ORGANIC_SUBSET.contains(&two.as_str()) => two,
+                        _ => c.to_string(),
+                    };
+                    let atom = if ORGANIC_SUBSET.contains(&symbol.as_str()) {
+                        Atom {
+                            element: symbol.clone(),
+                            aromatic: false,
+                        }
+                    } else if AROMATIC_SUBSET.contains(&symbol.as_str()) {
+                        Atom {
+                            element: symbol.to_uppercase(),
+                            aromatic: true,
+                        }
+                    } else {
+                        return Err(format!("Unknown atom `{}` at {}.", symbol, position));
+                    };
+
+                    let atom_index = molecule.add_atom(atom);
+                    if let Some(previous) = previous {
+                        molecule.add_bond(previous, atom_index, order)?;
+                    }
+                    previous = Some(atom_index);
+                    order = None;
+                    i += symbol.len() - 1;
+                }
+                _ => return Err(format!("Unexpected character `{}` at {}.", c, position)),
+            }
+            i += 1;
+        }
+
+        if !branches.is_empty() {
+            return Err("The `(` is not closed.".to_string());
+        }
+        if let Some(number) = rings.keys().min() {
+            return Err(format!("The ring closure {} is not closed.", number));
+        }
+        if molecule.atoms.is_empty() {
+            return Err("The SMILES has no atom.".to_string());
+        }
+
+        Ok(molecule)
+    }
+
+    /// Hash all linear paths of up to [`MAX_PATH_ATOMS`] atoms into a fingerprint. Each path is hashed in both directions, so a substructure sets a subset of the bits of the molecule.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut fingerprint = Fingerprint::default();
+        let mut path = vec![];
+        let mut visited = vec![false; self.atoms.len()];
+        for start in 0..self.atoms.len() {
+            self.walk(start, &mut path, &mut visited, &mut fingerprint);
+        }
+        fingerprint
+    }
+
+    fn walk(
+        &self,
+        atom: usize,
+        path: &mut Vec<(usize, Option<BondOrder>)>,
+        visited: &mut [bool],
+        fingerprint: &mut Fingerprint,
+    ) {
+        visited[atom] = true;
+        let bond = path
+            .last()
+            .map(|(previous, _)| self.bond(*previous, atom).unwrap());
+        path.push((atom, bond));
+
+        let mut hasher = DefaultHasher::new();
+        for (atom, bond) in path.iter() {
+            bond.hash(&mut hasher);
+            self.atoms[*atom].hash(&mut hasher);
+        }
+        fingerprint.set((hasher.finish() % FINGERPRINT_BITS as u64) as usize);
+
+        if path.len() < MAX_PATH_ATOMS {
+            for (neighbour, _) in self.neighbours[atom].iter() {
+                if !visited[*neighbour] {
+                    self.walk(*neighbour, path, visited, fingerprint);
+                }
+            }
+        }
+
+        path.pop();
+        visited[atom] = false;
+    }
+
+    /// Whether the query is a substructure of the molecule, the atoms must have the same elements and aromaticity, and the bonds must have the same orders. It is None if the match takes too many steps.
+    ///
+    /// ```
+    /// use biomedgps::model::structure::Molecule;
+    ///
+    /// let aspirin = Molecule::from_smiles("CC(=O)Oc1ccccc1C(=O)O").unwrap();
+    /// assert_eq!(aspirin.contains(&Molecule::from_smiles("c1ccccc1").unwrap()), Some(true));
+    /// assert_eq!(aspirin.contains(&Molecule::from_smiles("OC(=O)c1ccccc1").unwrap()), Some(true));
+    /// assert_eq!(aspirin.contains(&Molecule::from_smiles("C1CCCCC1").unwrap()), Some(false));
+    /// ```
+    pub fn contains(&self, query: &Molecule) -> Option<bool> {
+        if query.atoms.len() > self.atoms.len() {
+            return Some(false);
+        }
+
+        // Match the query atoms in the breadth-first order, so each atom (except the first one of each part) has a matched neighbour.
+        let mut order = vec![];
+        let mut seen = vec![false; query.atoms.len()];
+        for start in 0..query.atoms.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut queue = std::collections::VecDeque::from([start]);
+            while let Some(atom) = queue.pop_front() {
+                order.push(atom);
+                for (neighbour, _) in query.neighbours[atom].iter() {
+                    if !seen[*neighbour] {
+                        seen[*neighbour] = true;
+                        queue.push_back(*neighbour);
+                    }
+                }
+            }
+        }
+
+        let mut mapping = vec![usize::MAX; query.atoms.len()];
+        let mut used = vec![false; self.atoms.len()];
+        let mut steps = 0;
+        let matched = self.extend_match(query, &order, 0, &mut mapping, &mut used, &mut steps);
+        if steps > MAX_MATCH_STEPS {
+            None
+        } else {
+            Some(matched)
+        }
+    }
+
+    fn extend_match(
+        &self,
+        query: &Molecule,
+        order: &[usize],
+        depth: usize,
+        mapping: &mut [usize],
+        used: &mut [bool],
+        steps: &mut usize,
+    ) -> bool {
+        if depth == order.len() {
+            return true;
+        }
+
+        let atom = order[depth];
+        for candidate in 0..self.atoms.len() {
+            *steps += 1;
+            if *steps > MAX_MATCH_STEPS {
+                return false;
+            }
+
+            if used[candidate]
+                || self.atoms[candidate] != query.atoms[atom]
+                || self.neighbours[candidate].len() < query.neighbours[atom].len()
+            {
+                continue;
+            }
+
+            // The bonds to the matched neighbours must exist with the same orders.
+            let bonded = query.neighbours[atom].iter().all(|(neighbour, order)| {
+                mapping[*neighbour] == usize::MAX
+                    || self.bond(candidate, mapping[*neighbour]) == Some(*order)
+            });
+            if !bonded {
+                continue;
+            }
+
+            mapping[atom] = candidate;
+            used[candidate] = true;
+            if self.extend_match(query, order, depth + 1, mapping, used, steps) {
+                return true;
+            }
+            mapping[atom] = usize::MAX;
+            used[candidate] = false;
+        }
+
+        false
+    }
+}
+
+/// Check whether a query SMILES can be parsed and is not too large to search, it is used to validate the search requests.
+pub fn validate_smiles(smiles: &str) -> Result<(), validator::ValidationError> {
+    let mut error = validator::ValidationError::new("invalid_smiles");
+    match Molecule::from_smiles(smiles) {
+        Ok(molecule) if molecule.atoms.len() <= MAX_QUERY_ATOMS => return Ok(()),
+        Ok(molecule) => {
+            error.message = Some(std::borrow::Cow::Owned(format!(
+                "The query molecule has {} atoms, but at most {} atoms are allowed.",
+                molecule.atoms.len(),
+                MAX_QUERY_ATOMS
+            )))
+        }
+        Err(e) => error.message = Some(std::borrow::Cow::Owned(format!("Invalid SMILES: {}", e))),
+    }
+    Err(error)
+}
+
+/// Parse the content of a bracket atom, such as `NH4+`, `13CH3` or `C@@H`. Only the element and the aromaticity are kept.
+fn parse_bracket_atom(content: &str) -> Result<Atom, String> {
+    let symbol = content.trim_start_matches(|c: char| c.is_ascii_digit());
+    let mut chars = symbol.chars();
+    let first = chars.next().ok_or("The bracket atom is empty")?;
+    let second = chars.next().filter(|c| c.is_ascii_lowercase());
+
+    if first.is_ascii_lowercase() {
+        // The aromatic atoms, such as `se` and `nH`.
+        let two = second.map(|second| format!("{}{}", first, second));
+        let symbol = match two {
+            Some(two) if AROMATIC_BRACKET.contains(&two.as_str()) => two,
+            _ => first.to_string(),
+        };
+        if !AROMATIC_BRACKET.contains(&symbol.as_str()) {
+            return Err(format!("Unknown aromatic atom `{}`", symbol));
+        }
+        let mut element = symbol.clone();
+        element.replace_range(0..1, &first.to_ascii_uppercase().to_string());
+        return Ok(Atom {
+            element,
+            aromatic: true,
+        });
+    }
+
+    if !first.is_ascii_uppercase() {
+        return Err(format!("Invalid bracket atom `{}`", content));
+    }
+
+    Ok(Atom {
+        element: match second {
+            Some(second) => format!("{}{}", first, second),
+            None => first.to_string(),
+        },
+        aromatic: false,
+    })
+}
+
+/// A hashed path fingerprint of [`FINGERPRINT_BITS`] bits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprint([u64; FINGERPRINT_BITS / 64]);
+
+impl Fingerprint {
+    fn set(&mut self, bit: usize) {
+        self.0[bit / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// Whether all bits of the other fingerprint are set in this one.
+    pub fn contains(&self, other: &Fingerprint) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(a, b)| a & b == *b)
+    }
+
+    /// The Tanimoto coefficient of two fingerprints, it is 0 if both are empty.
+    ///
+    /// ```
+    /// use biomedgps::model::structure::Molecule;
+    ///
+    /// let aspirin = Molecule::from_smiles("CC(=O)Oc1ccccc1C(=O)O").unwrap().fingerprint();
+    /// let salicylic_acid = Molecule::from_smiles("OC(=O)c1ccccc1O").unwrap().fingerprint();
+    /// assert_eq!(aspirin.tanimoto(&aspirin), 1.0);
+    /// assert!(aspirin.tanimoto(&salicylic_acid) > 0.3);
+    /// ```
+    pub fn tanimoto(&self, other: &Fingerprint) -> f64 {
+        let (mut common, mut union) = (0, 0);
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            common += (a & b).count_ones();
+            union += (a | b).count_ones();
+        }
+        if union == 0 {
+            0.0
+        } else {
+            common as f64 / union as f64
+        }
+    }
+}
+
+/// The structure of a compound.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct CompoundStructure {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_id should be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The entity_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `DrugBank:DB00945`."
+    ))]
+    pub entity_id: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must be a valid entity label, such as `Compound`."
+    ))]
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_type should be between 1 and 64."
+    ))]
+    pub entity_type: String,
+
+    /// The SMILES of the compound, such as `CC(=O)Oc1ccccc1C(=O)O`.
+    #[validate(length(
+        max = 4096,
+        min = 1,
+        message = "The length of smiles should be between 1 and 4096."
+    ))]
+    #[validate(regex(
+        path = "SMILES_REGEX",
+        message = "The smiles contains the characters which are not allowed in a SMILES."
+    ))]
+    pub smiles: String,
+
+    /// The standard InChI, such as `InChI=1S/C9H8O4/...`.
+    #[validate(length(
+        max = 8192,
+        min = 7,
+        message = "The length of inchi should be between 7 and 8192."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub inchi: Option<String>,
+
+    /// The InChIKey, such as `BSYNRYMUTXBXSQ-UHFFFAOYSA-N`.
+    #[validate(regex(
+        path = "INCHIKEY_REGEX",
+        message = "The inchikey must be like `BSYNRYMUTXBXSQ-UHFFFAOYSA-N`."
+    ))]
+    #[oai(skip_serializing_if_is_none)]
+    pub inchikey: Option<String>,
+}
+
+impl CheckData for CompoundStructure {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<CompoundStructure>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec!["entity_id".to_string(), "entity_type".to_string()]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "smiles".to_string(),
+            "inchi".to_string(),
+            "inchikey".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "inchikey".to_string(),
+        ]
+    }
+}
+
+/// How to search the compounds by structure.
+///
+/// * `Substructure` - The compounds which contain the query molecule.
+/// * `Similarity` - The compounds whose Tanimoto similarity to the query molecule is at least the min similarity.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum StructureSearchMode {
+    Substructure,
+    Similarity,
+}
+
+/// A compound which is found by a structure search.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object, sqlx::FromRow)]
+pub struct StructureHit {
+    pub entity_id: String,
+    pub entity_type: String,
+    /// The name of the entity, it is empty if the entity is not in the entity table.
+    pub name: String,
+    pub smiles: String,
+    #[oai(skip_serializing_if_is_none)]
+    pub inchikey: Option<String>,
+    /// The Tanimoto similarity between the compound and the query molecule.
+    #[sqlx(default)]
+    pub similarity: f64,
+}
+
+/// The compounds which are found by a structure search, they are sorted by the similarity.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Object)]
+pub struct StructureSearchResult {
+    pub hits: Vec<StructureHit>,
+    /// The number of the scanned compounds.
+    pub scanned: u64,
+    /// The number of the compounds which are skipped because their SMILES cannot be parsed or their match takes too long.
+    pub skipped: u64,
+}
+
+impl StructureSearchResult {
+    /// Search the compounds (of the entity type if it is given) by the query SMILES, at most `limit` compounds with the highest similarities are returned.
+    pub async fn search(
+        pool: &sqlx::PgPool,
+        smiles: &str,
+        mode: StructureSearchMode,
+        min_similarity: f64,
+        entity_type: Option<&str>,
+        limit: usize,
+    ) -> Result<StructureSearchResult, anyhow::Error> {
+        let query = Molecule::from_smiles(smiles)
+            .map_err(|e| anyhow::anyhow!("Invalid SMILES {}: {}", smiles, e))?;
+        if query.atoms.len() > MAX_QUERY_ATOMS {
+            return Err(anyhow::anyhow!(
+                "The query molecule has {} atoms, but at most {} atoms are allowed.",
+                query.atoms.len(),
+                MAX_QUERY_ATOMS
+            ));
+        }
+        let query_fingerprint = query.fingerprint();
+
+        let mut result = StructureSearchResult {
+            hits: vec![],
+            scanned: 0,
+            skipped: 0,
+        };
+        let mut rows = sqlx::query_as::<_, StructureHit>(
+            "SELECT s.entity_id, s.entity_type, COALESCE(e.name, '') AS name, s.smiles, s.inchikey
+             FROM biomedgps_compound_structure s
+             LEFT JOIN biomedgps_entity e ON e.id = s.entity_id AND e.label = s.entity_type
+             WHERE $1::TEXT IS NULL OR s.entity_type = $1",
+        )
+        .bind(entity_type)
+        .fetch(pool);
+
+        while let Some(mut hit) = rows.try_next().await? {
+            result.scanned += 1;
+            let molecule = match Molecule::from_smiles(&hit.smiles) {
+                Ok(molecule) => molecule,
+                Err(e) => {
+                    debug!("Skip the compound {}: {}", hit.entity_id, e);
+                    result.skipped += 1;
+                    continue;
+                }
+            };
+
+            let fingerprint = molecule.fingerprint();
+            hit.similarity = query_fingerprint.tanimoto(&fingerprint);
+            let matched = match mode {
+                StructureSearchMode::Similarity => hit.similarity >= min_similarity,
+                StructureSearchMode::Substructure => {
+                    if !fingerprint.contains(&query_fingerprint) {
+                        false
+                    } else {
+                        match molecule.contains(&query) {
+                            Some(matched) => matched,
+                            None => {
+                                warn!(
+                                    "Skip the compound {}, the substructure match takes too long.",
+                                    hit.entity_id
+                                );
+                                result.skipped += 1;
+                                false
+                            }
+                        }
+                    }
+                }
+            };
+
+            if matched {
+                result.hits.push(hit);
+                // Keep the best hits only, so the memory is bounded by the limit.
+                if result.hits.len() >= limit * 2 {
+                    sort_hits(&mut result.hits);
+                    result.hits.truncate(limit);
+                }
+            }
+        }
+
+        sort_hits(&mut result.hits);
+        result.hits.truncate(limit);
+        AnyOk(result)
+    }
+}
+
+fn sort_hits(hits: &mut [StructureHit]) {
+    hits.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.entity_id.cmp(&b.entity_id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smiles() {
+        // Caffeine, the ring closures and the bracket-free aromatic nitrogens.
+        let caffeine = Molecule::from_smiles("CN1C=NC2=C1C(=O)N(C(=O)N2C)C").unwrap();
+        assert_eq!(caffeine.atoms.len(), 14);
+
+        // The bracket atoms, the charges, the isotopes and the two-digit ring closures.
+        let molecule = Molecule::from_smiles("[NH4+].[13CH3][C@@H](Cl)C%10CCCCC%10").unwrap();
+        assert_eq!(molecule.atoms[0].element, "N");
+        assert_eq!(molecule.atoms[1].element, "C");
+        assert_eq!(molecule.atoms[3].element, "Cl");
+        assert_eq!(molecule.bond(0, 1), None);
+        assert_eq!(molecule.bond(4, 9), Some(BondOrder::Single));
+
+        let pyrrole = Molecule::from_smiles("c1cc[nH]c1").unwrap();
+        assert!(pyrrole.atoms[3].aromatic);
+        assert_eq!(pyrrole.bond(0, 4), Some(BondOrder::Aromatic));
+
+        assert!(Molecule::from_smiles("").is_err());
+        assert!(Molecule::from_smiles("C)").is_err());
+        assert!(Molecule::from_smiles("C[NH4").is_err());
+        assert!(Molecule::from_smiles("C11").is_err());
+    }
+
+    #[test]
+    fn test_substructure_screen() {
+        let aspirin = Molecule::from_smiles("CC(=O)Oc1ccccc1C(=O)O").unwrap();
+        let ester = Molecule::from_smiles("cOC(C)=O").unwrap();
+        // A substructure always sets a subset of the bits.
+        assert!(aspirin.fingerprint().contains(&ester.fingerprint()));
+        assert_eq!(aspirin.contains(&ester), Some(true));
+
+        // The bond orders must match.
+        let ether = Molecule::from_smiles("C=CO").unwrap();
+        assert_eq!(aspirin.contains(&ether), Some(false));
+
+        // Not an induced match, a ring in the molecule contains a chain of the query.
+        let cyclopropane = Molecule::from_smiles("C1CC1").unwrap();
+        assert_eq!(
+            cyclopropane.contains(&Molecule::from_smiles("CCC").unwrap()),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Theme module is used to customize the styles of the graphs without recompiling, such as the color and the shape of the nodes of each entity label and the color, the line width and the dash of the edges of each relation type.
+//!
+//! The theme is saved in the `biomedgps_config` table by the admins (see `/api/v1/graph-theme`) and kept in memory, [`Graph::get_graph`](crate::model::graph::Graph::get_graph) applies it to the nodes and edges of all returned graphs. The labels and the relation types which are not in the theme keep the built-in styles. A relation type (such as `DRUGBANK::treats::Compound:Disease`) is looked up by itself first and then by its relation key (such as `treats`).
+
+use crate::model::biolink::get_relation_key;
+use crate::model::core::{ValidationError, ENTITY_LABEL_REGEX};
+use crate::model::graph::{Edge, EdgeKeyShape, Node};
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use log::info;
+use poem_openapi::Object;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+/// The key of the graph theme in the `biomedgps_config` table.
+pub const GRAPH_THEME_KEY: &str = "graph_theme";
+/// The max number of the entity labels or the relation types in a theme.
+pub const MAX_THEME_ENTRIES: usize = 1000;
+/// The max line width of the edges.
+pub const MAX_LINE_WIDTH: i32 = 20;
+
+lazy_static! {
+    pub static ref THEME_COLOR_REGEX: Regex =
+        Regex::new(r"^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$").unwrap();
+    // The node types of Graphin and G6, such as graphin-circle, rect and diamond.
+    pub static ref THEME_SHAPE_REGEX: Regex = Regex::new(r"^[a-z][a-zA-Z0-9\-]{0,63}$").unwrap();
+    static ref ACTIVE_THEME: RwLock<Option<Arc<GraphTheme>>> = RwLock::new(None);
+}
+
+/// The style of the nodes of an entity label, the empty fields keep the built-in styles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Object)]
+pub struct NodeTheme {
+    /// The fill color of the nodes, such as `#1f77b4`. It is also the border color if the stroke is empty.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub color: Option<String>,
+
+    /// The border color of the nodes.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub stroke: Option<String>,
+
+    /// The node type of Graphin, such as `graphin-circle`.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub shape: Option<String>,
+}
+
+/// The style of the edges of a relation type, the empty fields keep the built-in styles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Object)]
+pub struct EdgeTheme {
+    /// The color of the edges, such as `#999999`.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub color: Option<String>,
+
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub line_width: Option<i32>,
+
+    /// The lengths of the dashes and the gaps, such as `[5, 5]`, `[0, 0]` draws a solid line.
+    #[serde(default)]
+    #[oai(skip_serializing_if_is_none)]
+    pub line_dash: Option<[i32; 2]>,
+}
+
+/// The theme of the graphs, such as `{"nodes": {"Gene": {"color": "#1f77b4"}}, "edges": {"treats": {"color": "#2ca02c", "line_width": 2}}}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Object)]
+pub struct GraphTheme {
+    /// The styles of the nodes by the entity label, such as `Gene`.
+    #[serde(default)]
+    #[oai(default)]
+    pub nodes: BTreeMap<String, NodeTheme>,
+
+    /// The styles of the edges by the relation type (such as `DRUGBANK::treats::Compound:Disease`) or the relation key (such as `treats`).
+    #[serde(default)]
+    #[oai(default)]
+    pub edges: BTreeMap<String, EdgeTheme>,
+}
+
+fn check_color(color: &Option<String>, field: &str) -> Result<(), ValidationError> {
+    match color {
+        Some(color) if !THEME_COLOR_REGEX.is_match(color) => Err(ValidationError::new(&format!(
+            "Invalid {}: {}, it should be a hex color, such as #1f77b4.",
+            field, color
+        ))),
+        _ => Ok(()),
+    }
+}
+
+impl GraphTheme {
+    /// Check the labels, the relation types and the styles of the theme.
+    ///
+    /// ```
+    /// use biomedgps::model::theme::GraphTheme;
+    ///
+    /// let theme: GraphTheme = serde_json::from_str(r##"{"nodes": {"Gene": {"color": "#1f77b4", "shape": "rect"}}, "edges": {"treats": {"line_width": 3}}}"##).unwrap();
+    /// assert!(theme.check().is_ok());
+    ///
+    /// let theme: GraphTheme = serde_json::from_str(r#"{"nodes": {"Gene": {"color": "blue"}}}"#).unwrap();
+    /// assert!(theme.check().is_err());
+    /// ```
+    pub fn check(&self) -> Result<(), ValidationError> {
+        if self.nodes.len() > MAX_THEME_ENTRIES || self.edges.len() > MAX_THEME_ENTRIES {
+            return Err(ValidationError::new(&format!(
+                "The theme can have at most {} entity labels and {} relation types.",
+                MAX_THEME_ENTRIES, MAX_THEME_ENTRIES
+            )));
+        }
+
+        for (label, node) in self.nodes.iter() {
+            if !ENTITY_LABEL_REGEX.is_match(label) {
+                return Err(ValidationError::new(&format!(
+                    "Invalid entity label: {}, it should only contain letters.",
+                    label
+                )));
+            }
+
+            check_color(&node.color, "color")?;
+            check_color(&node.stroke, "stroke")?;
+            if let Some(shape) = &node.shape {
+                if !THEME_SHAPE_REGEX.is_match(shape) {
+                    return Err(ValidationError::new(&format!(
+                        "Invalid shape: {}, it should be a node type of Graphin, such as graphin-circle.",
+                        shape
+                    )));
+                }
+            }
+        }
+
+        for (relation_type, edge) in self.edges.iter() {
+            if relation_type.is_empty() || relation_type.len() > 255 {
+                return Err(ValidationError::new(
+                    "The length of the relation type should be between 1 and 255.",
+                ));
+            }
+
+            check_color(&edge.color, "color")?;
+            if let Some(line_width) = edge.line_width {
+                if line_width < 1 || line_width > MAX_LINE_WIDTH {
+                    return Err(ValidationError::new(&format!(
+                        "The line width of {} should be between 1 and {}.",
+                        relation_type, MAX_LINE_WIDTH
+                    )));
+                }
+            }
+            if let Some(line_dash) = edge.line_dash {
+                if line_dash.iter().any(|length| *length < 0 || *length > 100) {
+                    return Err(ValidationError::new(&format!(
+                        "The line dash of {} should be between 0 and 100.",
+                        relation_type
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the node color of an entity label, it is None if the label has no color in the theme.
+    pub fn get_node_color(&self, label: &str) -> Option<String> {
+        self.nodes.get(label).and_then(|node| node.color.clone())
+    }
+
+    fn get_edge_theme(&self, relation_type: &str) -> Option<&EdgeTheme> {
+        self.edges
+            .get(relation_type)
+            .or_else(|| self.edges.get(get_relation_key(relation_type)))
+    }
+
+    /// Apply the style of the entity label to the node.
+    pub fn apply_node(&self, node: &mut Node) {
+        let theme = match self.nodes.get(&node.nlabel) {
+            Some(theme) => theme,
+            None => return,
+        };
+
+        if let Some(color) = &theme.color {
+            node.style.keyshape.fill = color.clone();
+            node.style.keyshape.stroke = color.clone();
+        }
+        if let Some(stroke) = &theme.stroke {
+            node.style.keyshape.stroke = stroke.clone();
+        }
+        if let Some(shape) = &theme.shape {
+            node.r#type = shape.clone();
+        }
+    }
+
+    /// Apply the style of the relation type to the edge, the arrow of the edge is kept.
+    pub fn apply_edge(&self, edge: &mut Edge) {
+        let theme = match self.get_edge_theme(&edge.reltype) {
+            Some(theme) => theme,
+            None => return,
+        };
+
+        let keyshape = edge.style.keyshape.get_or_insert_with(|| {
+            let mut keyshape = EdgeKeyShape::undirected();
+            keyshape.end_arrow = true;
+            keyshape
+        });
+        if theme.color.is_some() {
+            keyshape.stroke = theme.color.clone();
+        }
+        if theme.line_width.is_some() {
+            keyshape.line_width = theme.line_width;
+        }
+        if theme.line_dash.is_some() {
+            keyshape.line_dash = theme.line_dash;
+        }
+    }
+
+    /// Get the saved theme, it is None if no theme has been saved.
+    pub async fn get(pool: &sqlx::PgPool) -> Result<Option<GraphTheme>, anyhow::Error> {
+        let value = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM biomedgps_config WHERE key = $1",
+        )
+        .bind(GRAPH_THEME_KEY)
+        .fetch_optional(pool)
+        .await?;
+
+        match value {
+            Some(value) => AnyOk(Some(serde_json::from_value(value)?)),
+            None => AnyOk(None),
+        }
+    }
+
+    /// Save the theme and make it active.
+    pub async fn save(&self, pool: &sqlx::PgPool, username: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO biomedgps_config (key, value, updated_by) VALUES ($1, $2, $3)
+             ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(GRAPH_THEME_KEY)
+        .bind(serde_json::to_value(self)?)
+        .bind(username)
+        .execute(pool)
+        .await?;
+
+        set_active_theme(Some(self.clone()));
+        AnyOk(())
+    }
+
+    /// Remove the saved theme, the built-in styles are used again. It returns false if no theme has been saved.
+    pub async fn delete(pool: &sqlx::PgPool) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM biomedgps_config WHERE key = $1")
+            .bind(GRAPH_THEME_KEY)
+            .execute(pool)
+            .await?;
+
+        set_active_theme(None);
+        AnyOk(result.rows_affected() > 0)
+    }
+}
+
+/// Load the saved theme and make it active, it should be called once when the server starts.
+pub async fn load_theme(pool: &sqlx::PgPool) -> Result<(), anyhow::Error> {
+    let theme = GraphTheme::get(pool).await?;
+    match &theme {
+        Some(theme) => info!(
+            "Use the graph theme with {} entity labels and {} relation types.",
+            theme.nodes.len(),
+            theme.edges.len()
+        ),
+        None => info!("No graph theme is saved, use the built-in styles."),
+    };
+
+    set_active_theme(theme);
+    AnyOk(())
+}
+
+pub fn set_active_theme(theme: Option<GraphTheme>) {
+    *ACTIVE_THEME.write().unwrap() = theme.map(Arc::new);
+}
+
+/// Get the active theme, it is None if no theme is saved or loaded (such as in the cli).
+pub fn get_active_theme() -> Option<Arc<GraphTheme>> {
+    ACTIVE_THEME.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::core::{Entity, Relation};
+
+    #[test]
+    fn test_apply_theme() {
+        let theme: GraphTheme = serde_json::from_str(
+            r##"{
+                "nodes": {"Gene": {"color": "#1f77b4", "shape": "rect"}},
+                "edges": {"treats": {"color": "#2ca02c", "line_width": 3}, "STRING::INTERACTS_WITH::Gene:Gene": {"line_dash": [2, 2]}}
+            }"##,
+        )
+        .unwrap();
+        assert!(theme.check().is_ok());
+
+        let entity = Entity {
+            idx: 1,
+            id: "ENTREZ:7157".to_string(),
+            name: "TP53".to_string(),
+            label: "Gene".to_string(),
+            resource: "STRING".to_string(),
+            description: None,
+            taxid: None,
+            synonyms: None,
+            pmids: None,
+            xrefs: None,
+        };
+        let mut node = Node::new(&entity);
+        theme.apply_node(&mut node);
+        assert_eq!(node.style.keyshape.fill, "#1f77b4");
+        assert_eq!(node.style.keyshape.stroke, "#1f77b4");
+        assert_eq!(node.r#type, "rect");
+
+        let relation = Relation {
+            id: 1,
+            relation_type: "DRUGBANK::treats::Compound:Disease".to_string(),
+            source_id: "DrugBank:DB00001".to_string(),
+            source_type: "Compound".to_string(),
+            target_id: "MESH:D001249".to_string(),
+            target_type: "Disease".to_string(),
+            score: None,
+            key_sentence: None,
+            resource: "DRUGBANK".to_string(),
+            pmids: None,
+            directed: true,
+            attributes: None,
+            split: None,
+            created_at: None,
+            dataset_version: None,
+        };
+        let mut edge = Edge::from_relation(&relation);
+        theme.apply_edge(&mut edge);
+        let keyshape = edge.style.keyshape.unwrap();
+        assert_eq!(keyshape.stroke, Some("#2ca02c".to_string()));
+        assert_eq!(keyshape.line_width, Some(3));
+        assert!(keyshape.end_arrow);
+
+        let mut relation = relation;
+        relation.relation_type = "STRING::INTERACTS_WITH::Gene:Gene".to_string();
+        relation.directed = false;
+        let mut edge = Edge::from_relation(&relation);
+        theme.apply_edge(&mut edge);
+        let keyshape = edge.style.keyshape.unwrap();
+        assert_eq!(keyshape.line_dash, Some([2, 2]));
+        assert!(!keyshape.end_arrow);
+    }
+}
@@ -0,0 +1,220 @@
+//! Translation module keeps the names of the entities in other languages, such as the Chinese names of the diseases, so the entity and graph endpoints can return the localized names.
+//!
+//! The translations are imported by `biomedgps-cli importdb -t entity_translation` from a file with the entity_id, entity_type, locale (such as `zh-CN` or `zh`) and name columns. The locale of a request comes from the `locale` param or the `Accept-Language` header, a more specific locale (such as `zh-CN`) is preferred over its language (such as `zh`), and the default names are returned for the entities without a translation.
+
+use crate::model::core::{CheckData, ValidationError, ENTITY_ID_REGEX, ENTITY_LABEL_REGEX};
+use anyhow::Ok as AnyOk;
+use lazy_static::lazy_static;
+use poem_openapi::Object;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::{error::Error, path::PathBuf};
+use validator::Validate;
+
+lazy_static! {
+    // The language tags, such as zh, zh-CN and zh-Hans-CN.
+    pub static ref LOCALE_REGEX: Regex = Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]{2,8}){0,3}$").unwrap();
+}
+
+/// The language of the default names of the entities, no translation is needed for it.
+pub const DEFAULT_LANGUAGE: &str = "en";
+/// The max number of the locales which are tried for a request.
+pub const MAX_LOCALES: usize = 10;
+
+/// The name of an entity in a language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct EntityTranslation {
+    // Ignore this field when deserialize from json
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_id should be between 1 and 64."
+    ))]
+    #[validate(regex(
+        path = "ENTITY_ID_REGEX",
+        message = "The entity_id must match the pattern `^[A-Za-z0-9\\-]+:[a-z0-9A-Z\\.\\-_]+$`. Such as `ENTREZ:7157`."
+    ))]
+    pub entity_id: String,
+
+    #[validate(regex(
+        path = "ENTITY_LABEL_REGEX",
+        message = "The entity_type must be a valid entity label, such as `Gene`."
+    ))]
+    #[validate(length(
+        max = 64,
+        min = 1,
+        message = "The length of entity_type should be between 1 and 64."
+    ))]
+    pub entity_type: String,
+
+    /// The language tag, such as `zh` or `zh-CN`.
+    #[validate(regex(
+        path = "LOCALE_REGEX",
+        message = "The locale must be a language tag, such as `zh` or `zh-CN`."
+    ))]
+    pub locale: String,
+
+    #[validate(length(
+        max = 255,
+        min = 1,
+        message = "The length of name should be between 1 and 255."
+    ))]
+    pub name: String,
+}
+
+impl CheckData for EntityTranslation {
+    fn check_csv_is_valid(filepath: &PathBuf) -> Vec<Box<dyn Error>> {
+        Self::check_csv_is_valid_default::<EntityTranslation>(filepath)
+    }
+
+    fn unique_fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "locale".to_string(),
+        ]
+    }
+
+    fn fields() -> Vec<String> {
+        vec![
+            "entity_id".to_string(),
+            "entity_type".to_string(),
+            "locale".to_string(),
+            "name".to_string(),
+        ]
+    }
+
+    fn selectable_fields() -> Vec<String> {
+        let mut fields = vec!["id".to_string()];
+        fields.extend(Self::fields());
+        fields
+    }
+
+    fn sortable_fields() -> Vec<String> {
+        Self::fields()
+    }
+}
+
+impl EntityTranslation {
+    /// Fetch all translations of an entity, they are sorted by the locale.
+    pub async fn get_records_by_entity(
+        pool: &sqlx::PgPool,
+        entity_type: &str,
+        entity_id: &str,
+    ) -> Result<Vec<EntityTranslation>, anyhow::Error> {
+        let sql_str = "SELECT * FROM biomedgps_entity_translation WHERE entity_type = $1 AND entity_id = $2 ORDER BY locale";
+        let records = sqlx::query_as::<_, EntityTranslation>(sql_str)
+            .bind(entity_type)
+            .bind(entity_id)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(records)
+    }
+
+    /// Fetch the translated names of the entities (the pairs of the entity type and the entity id) in the first available locale of the locales (see [`parse_locales`]), the entities without a translation are not in the result.
+    pub async fn get_names(
+        pool: &sqlx::PgPool,
+        locales: &[String],
+        entities: &[(String, String)],
+    ) -> Result<HashMap<(String, String), String>, anyhow::Error> {
+        if locales.is_empty() || entities.is_empty() {
+            return AnyOk(HashMap::new());
+        }
+
+        let (entity_types, entity_ids): (Vec<String>, Vec<String>) =
+            entities.iter().cloned().unzip();
+        let sql_str = "SELECT DISTINCT ON (t.entity_type, t.entity_id)
+                       t.entity_type, t.entity_id, t.name
+                       FROM biomedgps_entity_translation t
+                       JOIN UNNEST($2::TEXT[], $3::TEXT[]) AS e(entity_type, entity_id)
+                       ON t.entity_type = e.entity_type AND t.entity_id = e.entity_id
+                       WHERE LOWER(t.locale) = ANY($1)
+                       ORDER BY t.entity_type, t.entity_id, ARRAY_POSITION($1, LOWER(t.locale))";
+        let rows = sqlx::query_as::<_, (String, String, String)>(sql_str)
+            .bind(locales)
+            .bind(&entity_types)
+            .bind(&entity_ids)
+            .fetch_all(pool)
+            .await?;
+
+        AnyOk(
+            rows.into_iter()
+                .map(|(entity_type, entity_id, name)| ((entity_type, entity_id), name))
+                .collect(),
+        )
+    }
+}
+
+/// Parse the locales of a request in the order of preference, the `locale` param takes precedence over the `Accept-Language` header. Each language tag is followed by its language (such as `zh-cn` and `zh`), and the locales after the default language are dropped because the default names are used then. It is empty if the default names are requested. The invalid `locale` param is an error, but the invalid tags in the header are ignored.
+///
+/// ```
+/// use biomedgps::model::translation::parse_locales;
+///
+/// assert_eq!(parse_locales(None, Some("zh-CN,zh;q=0.9,en;q=0.8")).unwrap(), vec!["zh-cn", "zh"]);
+/// assert_eq!(parse_locales(None, Some("en;q=0.5, ja")).unwrap(), vec!["ja"]);
+/// assert_eq!(parse_locales(Some("zh-TW"), Some("ja")).unwrap(), vec!["zh-tw", "zh"]);
+/// assert!(parse_locales(None, Some("en-US,en;q=0.9,zh;q=0.8")).unwrap().is_empty());
+/// assert!(parse_locales(None, None).unwrap().is_empty());
+/// assert!(parse_locales(Some("chinese!"), None).is_err());
+/// ```
+pub fn parse_locales(
+    locale: Option<&str>,
+    accept_language: Option<&str>,
+) -> Result<Vec<String>, ValidationError> {
+    let tags = match locale {
+        Some(locale) => {
+            if !LOCALE_REGEX.is_match(locale) {
+                return Err(ValidationError::new(&format!(
+                    "Invalid locale: {}, it should be a language tag, such as zh or zh-CN.",
+                    locale
+                )));
+            }
+            vec![locale.to_string()]
+        }
+        None => {
+            let mut tags = accept_language
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|item| {
+                    let mut parts = item.split(';').map(|part| part.trim());
+                    let tag = parts.next()?;
+                    let quality = parts
+                        .find_map(|part| part.strip_prefix("q="))
+                        .map(|q| q.parse::<f64>().unwrap_or(0.0))
+                        .unwrap_or(1.0);
+                    if LOCALE_REGEX.is_match(tag) && quality > 0.0 {
+                        Some((tag.to_string(), quality))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<(String, f64)>>();
+            // The sort is stable, so the tags with the same quality keep their order.
+            tags.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            tags.into_iter().map(|(tag, _)| tag).collect()
+        }
+    };
+
+    let mut locales: Vec<String> = vec![];
+    for tag in tags.iter() {
+        let tag = tag.to_lowercase();
+        let language = tag.split('-').next().unwrap_or_default().to_string();
+        if language == DEFAULT_LANGUAGE {
+            break;
+        }
+
+        for locale in [tag, language] {
+            if !locales.contains(&locale) && locales.len() < MAX_LOCALES {
+                locales.push(locale);
+            }
+        }
+    }
+
+    Ok(locales)
+}
@@ -1,9 +1,159 @@
 //! Utility functions for the model module. Contains functions to import data from CSV files into the database, and to update the metadata tables.
 
+use crate::model::core::{ImportJob, SOFT_DELETE_TABLES};
+use flate2::read::MultiGzDecoder;
 use log::{debug, error, info, warn};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::reader::RowIter;
+use parquet::record::Field;
 use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
 use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use std::{error::Error, path::PathBuf};
+use tracing::Instrument;
+
+// The timeout (in seconds) of a query in the request handlers, 0 means no timeout.
+static QUERY_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+
+/// Set the timeout (in seconds) of a query in the request handlers, 0 means no timeout. It should be called once when the server starts.
+pub fn set_query_timeout(timeout: u64) {
+    QUERY_TIMEOUT.store(timeout, Ordering::Relaxed);
+}
+
+pub fn get_query_timeout() -> u64 {
+    QUERY_TIMEOUT.load(Ordering::Relaxed)
+}
+
+/// The default max number of records in a page of the list endpoints.
+pub const DEFAULT_MAX_PAGE_SIZE: u64 = 1000;
+
+// The max number of records in a page of the list endpoints.
+static MAX_PAGE_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_PAGE_SIZE);
+
+/// Set the max number of records in a page of the list endpoints, a request with a larger page size is rejected. It should be called once when the server starts.
+pub fn set_max_page_size(max_page_size: u64) {
+    MAX_PAGE_SIZE.store(max_page_size, Ordering::Relaxed);
+}
+
+pub fn get_max_page_size() -> u64 {
+    MAX_PAGE_SIZE.load(Ordering::Relaxed)
+}
+
+/// The default seconds to keep a cached count of the list endpoints.
+pub const DEFAULT_COUNT_CACHE_TTL: u64 = 300;
+
+// The seconds to keep a cached count of the list endpoints.
+static COUNT_CACHE_TTL: AtomicU64 = AtomicU64::new(DEFAULT_COUNT_CACHE_TTL);
+
+/// Set the seconds to keep a cached count (see `CountStrategy::Cached`). It should be called once when the server starts.
+pub fn set_count_cache_ttl(ttl: u64) {
+    COUNT_CACHE_TTL.store(ttl, Ordering::Relaxed);
+}
+
+pub fn get_count_cache_ttl() -> u64 {
+    COUNT_CACHE_TTL.load(Ordering::Relaxed)
+}
+
+/// Run a query with the timeout which is set by `set_query_timeout`, so that one pathological query cannot pin a connection of the pool for a long time.
+///
+/// The query is wrapped in a `db_query` tracing span and its elapsed time is emitted as a debug event, so the DB time of a request can be found in the structured logs.
+pub async fn with_query_timeout<T, F>(future: F) -> Result<T, anyhow::Error>
+where
+    F: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    run_traced_query(None, future).await
+}
+
+/// Same as [`with_query_timeout`], but the summary of the statement (see [`summarize_statement`]) is recorded in the `db_query` span, so the query can be recognized in the exported traces.
+pub async fn with_statement_timeout<T, F>(sql_str: &str, future: F) -> Result<T, anyhow::Error>
+where
+    F: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    run_traced_query(Some(summarize_statement(sql_str)), future).await
+}
+
+/// Summarize a sql statement as its operation and the first table, such as `SELECT biomedgps_relation`. The values in the statement are left out, so the summary can be exported safely.
+///
+/// ```
+/// use biomedgps::model::util::summarize_statement;
+///
+/// assert_eq!(summarize_statement("SELECT * FROM biomedgps_relation WHERE id = 1"), "SELECT biomedgps_relation");
+/// assert_eq!(summarize_statement("select row_to_json(t)::TEXT FROM (SELECT id FROM \"biomedgps_entity\" WHERE 1=1) t"), "SELECT biomedgps_entity");
+/// assert_eq!(summarize_statement("UPDATE biomedgps_subgraph SET name = $1"), "UPDATE biomedgps_subgraph");
+/// assert_eq!(summarize_statement("INSERT INTO biomedgps_entity (id) VALUES ($1)"), "INSERT biomedgps_entity");
+/// assert_eq!(summarize_statement("  MATCH (n:Gene) RETURN n"), "MATCH");
+/// ```
+pub fn summarize_statement(sql_str: &str) -> String {
+    let tokens = sql_str.split_whitespace().collect::<Vec<&str>>();
+    let operation = tokens.first().unwrap_or(&"").to_uppercase();
+    let table = tokens.windows(2).find_map(|pair| {
+        let keyword = pair[0].to_uppercase();
+        let table = pair[1].trim_matches(|c: char| c == '"' || c == ',' || c == ';');
+        let is_table = table
+            .chars()
+            .next()
+            .map(|c| c.is_ascii_alphabetic() || c == '_')
+            .unwrap_or(false);
+        if ["FROM", "INTO", "UPDATE", "JOIN"].contains(&keyword.as_str()) && is_table {
+            Some(table.split('(').next().unwrap_or(table).to_string())
+        } else {
+            None
+        }
+    });
+
+    match table {
+        Some(table) => format!("{} {}", operation, table),
+        None => operation,
+    }
+}
+
+async fn run_traced_query<T, F>(statement: Option<String>, future: F) -> Result<T, anyhow::Error>
+where
+    F: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    // The span is exported as a client span of the database, see the telemetry module.
+    let span = tracing::info_span!(
+        "db_query",
+        otel.name = statement.as_deref().unwrap_or("db_query"),
+        otel.kind = "client",
+        db.system = "postgresql",
+        db.statement = statement.as_deref()
+    );
+    let start = Instant::now();
+    let result = run_with_query_timeout(future)
+        .instrument(span.clone())
+        .await;
+    span.in_scope(|| {
+        tracing::debug!(
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            ok = result.is_ok(),
+            "The query is finished."
+        )
+    });
+    result
+}
+
+async fn run_with_query_timeout<T, F>(future: F) -> Result<T, anyhow::Error>
+where
+    F: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    let timeout = get_query_timeout();
+    if timeout == 0 {
+        return future.await.map_err(|e| e.into());
+    }
+
+    match tokio::time::timeout(Duration::from_secs(timeout), future).await {
+        Ok(result) => result.map_err(|e| e.into()),
+        Err(_) => Err(anyhow::anyhow!(
+            "The query is cancelled because it takes more than {} seconds.",
+            timeout
+        )),
+    }
+}
 
 /// A color map for the node labels.
 /// More details on https://colorbrewer2.org/#type=qualitative&scheme=Paired&n=12
@@ -26,15 +176,40 @@ pub fn match_color(entity_type: &str) -> String {
     NODE_COLORS[index as usize].to_string()
 }
 
+/// The compression formats which can be read transparently, the compressed file must keep the original extension, such as `relations.tsv.gz`.
+const COMPRESSED_EXTENSIONS: [&str; 2] = ["gz", "zst"];
+
+/// Get the delimiter of a data file by its extension, the compression extension (gz or zst) is ignored. A parquet file is read as a tsv file (see [`open_data_file`](fn.open_data_file.html)).
+///
+/// ```
+/// use biomedgps::model::util::get_delimiter;
+/// use std::path::PathBuf;
+///
+/// assert_eq!(get_delimiter(&PathBuf::from("relations.tsv.gz")).unwrap(), b'\t');
+/// assert_eq!(get_delimiter(&PathBuf::from("entities.csv.zst")).unwrap(), b',');
+/// assert_eq!(get_delimiter(&PathBuf::from("embeddings.parquet")).unwrap(), b'\t');
+/// assert!(get_delimiter(&PathBuf::from("relations.gz")).is_err());
+/// ```
 pub fn get_delimiter(filepath: &PathBuf) -> Result<u8, Box<dyn Error>> {
-    let suffix = match filepath.extension() {
+    let mut suffix = match filepath.extension() {
         Some(suffix) => suffix.to_str().unwrap(),
         None => return Err("File has no extension".into()),
     };
 
+    if COMPRESSED_EXTENSIONS.contains(&suffix) {
+        suffix = match filepath
+            .file_stem()
+            .map(Path::new)
+            .and_then(|v| v.extension())
+        {
+            Some(suffix) => suffix.to_str().unwrap(),
+            None => return Err("Compressed file has no extension, such as data.tsv.gz".into()),
+        };
+    }
+
     if suffix == "csv" {
         Ok(b',')
-    } else if suffix == "tsv" {
+    } else if suffix == "tsv" || suffix == "parquet" {
         Ok(b'\t')
     } else if suffix == "txt" {
         Ok(b' ')
@@ -43,6 +218,109 @@ pub fn get_delimiter(filepath: &PathBuf) -> Result<u8, Box<dyn Error>> {
     }
 }
 
+/// Convert a parquet value to a csv cell, the elements of a list are joined by `|`, so a list of floats is read as an embedding.
+fn parquet_field_to_string(field: &Field) -> String {
+    match field {
+        Field::Null => "".to_string(),
+        Field::Str(value) => value.clone(),
+        Field::Bytes(value) => String::from_utf8_lossy(value.data()).to_string(),
+        Field::ListInternal(list) => list
+            .elements()
+            .iter()
+            .map(parquet_field_to_string)
+            .collect::<Vec<String>>()
+            .join("|"),
+        _ => field.to_string(),
+    }
+}
+
+/// Encode the values as a tsv line, the values are quoted if necessary.
+fn encode_tsv_line(values: &[String]) -> io::Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(vec![]);
+    writer.write_record(values)?;
+    writer
+        .into_inner()
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Read the rows of a parquet file as tsv lines (the first line is the header), so that the parquet file can be checked and imported like a tsv file.
+struct ParquetTsvReader {
+    rows: RowIter<'static>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl ParquetTsvReader {
+    fn new(filepath: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = SerializedFileReader::new(File::open(filepath)?)?;
+        let columns = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .root_schema()
+            .get_fields()
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect::<Vec<String>>();
+
+        Ok(ParquetTsvReader {
+            rows: reader.into_iter(),
+            buffer: encode_tsv_line(&columns)?,
+            position: 0,
+        })
+    }
+}
+
+impl Read for ParquetTsvReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.buffer.len() {
+            let values = match self.rows.next() {
+                Some(Ok(row)) => row
+                    .get_column_iter()
+                    .map(|(_, field)| parquet_field_to_string(field))
+                    .collect::<Vec<String>>(),
+                Some(Err(e)) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+                None => return Ok(0),
+            };
+
+            self.buffer = encode_tsv_line(&values)?;
+            self.position = 0;
+        }
+
+        let size = buf.len().min(self.buffer.len() - self.position);
+        buf[..size].copy_from_slice(&self.buffer[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Open a data file, the gzip (.gz) and zstd (.zst) compressed files are decompressed on the fly and the parquet files are read as tsv files.
+pub fn open_data_file(filepath: &Path) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    let reader: Box<dyn Read> = match filepath.extension().and_then(|v| v.to_str()) {
+        Some("gz") => Box::new(MultiGzDecoder::new(BufReader::new(File::open(filepath)?))),
+        Some("zst") => Box::new(zstd::stream::read::Decoder::new(File::open(filepath)?)?),
+        Some("parquet") => Box::new(ParquetTsvReader::new(filepath)?),
+        _ => Box::new(File::open(filepath)?),
+    };
+
+    Ok(reader)
+}
+
+/// Create a csv reader of a data file (see [`open_data_file`](fn.open_data_file.html)), it is used instead of `csv::ReaderBuilder::from_path` to support the compressed and parquet files.
+pub fn open_csv_reader(
+    filepath: &Path,
+    delimiter: u8,
+) -> Result<csv::Reader<Box<dyn Read>>, csv::Error> {
+    let reader =
+        open_data_file(filepath).map_err(|e| csv::Error::from(io::Error::other(e.to_string())))?;
+
+    Ok(csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader))
+}
+
 pub async fn drop_table(pool: &sqlx::PgPool, table: &str) {
     debug!("Dropping table {}...", table);
     sqlx::query(&format!(
@@ -128,6 +406,200 @@ pub async fn import_file_in_loop(
     Ok(())
 }
 
+/// The default number of rows which are committed in one transaction when importing a data file.
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 100000;
+
+/// The counters of an import, they are shared by the files which are imported concurrently and reported periodically by [`ImportProgress::report`](struct.ImportProgress.html#method.report).
+#[derive(Debug)]
+pub struct ImportProgress {
+    pub total_files: u64,
+    pub finished_files: AtomicU64,
+    pub rows_read: AtomicU64,
+    pub rows_inserted: AtomicU64,
+    pub errors: AtomicU64,
+    pub started_at: Instant,
+}
+
+impl ImportProgress {
+    pub fn new(total_files: usize) -> Self {
+        ImportProgress {
+            total_files: total_files as u64,
+            finished_files: AtomicU64::new(0),
+            rows_read: AtomicU64::new(0),
+            rows_inserted: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn rows_per_second(&self) -> f64 {
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.rows_read.load(Ordering::Relaxed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Format the progress as one line, such as `[#####---------------] 1/4 files | 1200000 rows read | 1150000 inserted | 0 errors | 52000 rows/s | 23s`.
+    pub fn format(&self) -> String {
+        let width = 20;
+        let finished_files = self.finished_files.load(Ordering::Relaxed);
+        let filled = (finished_files * width)
+            .checked_div(self.total_files)
+            .unwrap_or(0) as usize;
+
+        format!(
+            "[{}{}] {}/{} files | {} rows read | {} inserted | {} errors | {:.0} rows/s | {}s",
+            "#".repeat(filled),
+            "-".repeat(width as usize - filled),
+            finished_files,
+            self.total_files,
+            self.rows_read.load(Ordering::Relaxed),
+            self.rows_inserted.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed),
+            self.rows_per_second(),
+            self.started_at.elapsed().as_secs()
+        )
+    }
+
+    /// Report the progress, redraw the progress bar on stderr if it is a terminal, otherwise log a line.
+    pub fn report(&self, progress_bar: bool) {
+        if progress_bar {
+            eprint!("\r{}", self.format());
+            let _ = io::stderr().flush();
+        } else {
+            info!("Import progress: {}", self.format());
+        }
+    }
+}
+
+/// Import a data file batch by batch. Each batch is copied into a staging table and inserted into the table (the existing rows are skipped by the unique columns) in its own transaction, and the checkpoint of the import job is saved in the same transaction. The import starts from the checkpoint of the job, so an interrupted import can be resumed without duplicating or losing rows.
+///
+/// The data file must only contain the expected columns and a header, such as the file generated by `select_expected_columns`. The stamp columns are filled with the same value for all imported rows, such as the dataset_version of the relations.
+#[allow(clippy::too_many_arguments)]
+pub async fn import_file_in_batches(
+    pool: &sqlx::PgPool,
+    filepath: &Path,
+    table_name: &str,
+    expected_columns: &[String],
+    unique_columns: &[String],
+    stamp_columns: &[(String, String)],
+    delimiter: u8,
+    job: &mut ImportJob,
+    progress: &ImportProgress,
+) -> Result<(), Box<dyn Error>> {
+    let started_at = Instant::now();
+    let mut run_rows = 0;
+    let mut file = File::open(filepath)?;
+    let start_offset = job.file_offset as u64;
+    if start_offset > 0 {
+        info!(
+            "Resuming the import job {} from row {} (byte {}).",
+            job.id, job.committed_rows, start_offset
+        );
+        file.seek(SeekFrom::Start(start_offset))?;
+    }
+
+    // The header has been skipped if the import is resumed.
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(start_offset == 0)
+        .from_reader(file);
+
+    let columns = expected_columns.join(",");
+    let where_clause = unique_columns
+        .iter()
+        .map(|c| format!("{}.{} = staging.{}", table_name, c, c))
+        .collect::<Vec<String>>()
+        .join(" AND ");
+    let copy_str = format!(
+        "COPY staging ({}) FROM STDIN WITH (FORMAT csv, DELIMITER E'{}')",
+        columns, delimiter as char
+    );
+    // The stamp values are bound as parameters, they are appended to the columns of the data file.
+    let mut insert_columns = columns.clone();
+    let mut select_columns = columns.clone();
+    for (i, (column, _)) in stamp_columns.iter().enumerate() {
+        insert_columns.push_str(&format!(",{}", column));
+        select_columns.push_str(&format!(",${}", i + 1));
+    }
+    let insert_str = format!(
+        "INSERT INTO {} ({})
+         SELECT {} FROM staging
+         WHERE NOT EXISTS (SELECT 1 FROM {} WHERE {})",
+        table_name, insert_columns, select_columns, table_name, where_clause
+    );
+    debug!("Importing query string: {}; {}", copy_str, insert_str);
+
+    let batch_size = job.batch_size.max(1) as usize;
+    let mut record = csv::StringRecord::new();
+    let mut finished = false;
+    while !finished {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(vec![]);
+        let mut batch_rows = 0;
+        while batch_rows < batch_size {
+            if !reader.read_record(&mut record)? {
+                finished = true;
+                break;
+            }
+            writer.write_record(&record)?;
+            batch_rows += 1;
+        }
+
+        if batch_rows == 0 {
+            break;
+        }
+        progress
+            .rows_read
+            .fetch_add(batch_rows as u64, Ordering::Relaxed);
+
+        let data = writer.into_inner().map_err(|e| e.into_error())?;
+        let file_offset = start_offset + reader.position().byte();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(&format!(
+            "CREATE TEMPORARY TABLE staging (LIKE {} INCLUDING ALL) ON COMMIT DROP",
+            table_name
+        ))
+        .execute(&mut tx)
+        .await?;
+
+        let mut copy = tx.copy_in_raw(&copy_str).await?;
+        copy.send(data).await?;
+        copy.finish().await?;
+
+        let mut insert_query = sqlx::query(&insert_str);
+        for (_, value) in stamp_columns.iter() {
+            insert_query = insert_query.bind(value);
+        }
+        let inserted_rows = insert_query.execute(&mut tx).await?.rows_affected();
+        run_rows += batch_rows;
+        let rows_per_second = run_rows as f64 / started_at.elapsed().as_secs_f64().max(1e-3);
+        job.checkpoint(
+            &mut tx,
+            file_offset,
+            batch_rows,
+            inserted_rows,
+            rows_per_second,
+        )
+        .await?;
+        tx.commit().await?;
+        progress
+            .rows_inserted
+            .fetch_add(inserted_rows, Ordering::Relaxed);
+
+        debug!(
+            "Import job {}: {} rows have been committed, {} rows are inserted.",
+            job.id, job.committed_rows, job.rows_inserted
+        );
+    }
+
+    Ok(())
+}
+
 pub async fn import_file(
     pool: &sqlx::PgPool,
     filepath: &PathBuf,
@@ -169,15 +641,7 @@ pub async fn update_entity_metadata(pool: &sqlx::PgPool, drop: bool) -> Result<(
 
     info!("Update entity metadata from entity table.");
 
-    let query_str = format!(
-        "
-        INSERT INTO {} (resource, entity_type, entity_count)
-        SELECT resource, label as entity_type, count(*) as entity_count
-        FROM biomedgps_entity
-        GROUP BY resource, label;
-    ",
-        table_name
-    );
+    let query_str = gen_entity_metadata_query(table_name);
 
     sqlx::query(&query_str)
         .execute(pool)
@@ -199,12 +663,7 @@ pub async fn update_relation_metadata(
 
     info!("Update relation metadata from relation table.");
 
-    let query_str = format!("
-        INSERT INTO {} (relation_type, start_entity_type, end_entity_type, relation_count, resource)
-        SELECT relation_type, source_type as start_entity_type, target_type as end_entity_type, count(*) as relation_count, resource
-        FROM biomedgps_relation
-        GROUP BY relation_type, source_type, target_type, resource;
-    ", table_name);
+    let query_str = gen_relation_metadata_query(table_name);
 
     sqlx::query(&query_str)
         .execute(pool)
@@ -215,6 +674,457 @@ pub async fn update_relation_metadata(
     Ok(())
 }
 
+pub async fn update_degree_statistics(
+    pool: &sqlx::PgPool,
+    drop: bool,
+) -> Result<(), Box<dyn Error>> {
+    let table_name = "biomedgps_degree_statistics";
+    if drop {
+        drop_table(&pool, table_name).await;
+    };
+
+    info!("Update degree statistics from relation table.");
+
+    let query_str = gen_degree_statistics_query(table_name);
+
+    sqlx::query(&query_str)
+        .execute(pool)
+        .await
+        .expect("Failed to update data.");
+    info!("{} updated.", table_name);
+
+    Ok(())
+}
+
+pub async fn update_entity_degree(pool: &sqlx::PgPool, drop: bool) -> Result<(), Box<dyn Error>> {
+    let table_name = "biomedgps_entity_degree";
+    if drop {
+        drop_table(&pool, table_name).await;
+    };
+
+    info!("Update entity degree from relation table.");
+
+    let query_str = gen_entity_degree_query(table_name);
+
+    sqlx::query(&query_str)
+        .execute(pool)
+        .await
+        .expect("Failed to update data.");
+    info!("{} updated.", table_name);
+
+    Ok(())
+}
+
+/// Generate the query string to compute the entity metadata from the entity table.
+pub fn gen_entity_metadata_query(table_name: &str) -> String {
+    format!(
+        "
+        INSERT INTO {} (resource, entity_type, entity_count)
+        SELECT resource, label as entity_type, count(*) as entity_count
+        FROM biomedgps_entity
+        GROUP BY resource, label;
+    ",
+        table_name
+    )
+}
+
+/// Generate the query string to compute the relation metadata from the relation table.
+pub fn gen_relation_metadata_query(table_name: &str) -> String {
+    format!("
+        INSERT INTO {} (relation_type, start_entity_type, end_entity_type, relation_count, resource)
+        SELECT relation_type, source_type as start_entity_type, target_type as end_entity_type, count(*) as relation_count, resource
+        FROM biomedgps_relation
+        GROUP BY relation_type, source_type, target_type, resource;
+    ", table_name)
+}
+
+/// Generate the query string to compute the degree distribution summaries from the relation table.
+pub fn gen_degree_statistics_query(table_name: &str) -> String {
+    format!("
+        INSERT INTO {} (entity_type, entity_count, min_degree, max_degree, mean_degree, median_degree, p90_degree)
+        SELECT entity_type, count(*) as entity_count, min(degree) as min_degree, max(degree) as max_degree,
+               avg(degree)::FLOAT as mean_degree,
+               percentile_cont(0.5) WITHIN GROUP (ORDER BY degree) as median_degree,
+               percentile_cont(0.9) WITHIN GROUP (ORDER BY degree) as p90_degree
+        FROM (
+            SELECT node_type as entity_type, node_id, count(*) as degree
+            FROM (
+                SELECT source_type as node_type, source_id as node_id FROM biomedgps_relation
+                UNION ALL
+                SELECT target_type as node_type, target_id as node_id FROM biomedgps_relation
+            ) AS nodes
+            GROUP BY node_type, node_id
+        ) AS degrees
+        GROUP BY entity_type;
+    ", table_name)
+}
+
+/// Generate the query string to compute the global degree of each entity from the relation table.
+pub fn gen_entity_degree_query(table_name: &str) -> String {
+    format!("
+        INSERT INTO {} (entity_type, entity_id, degree)
+        SELECT node_type as entity_type, node_id as entity_id, count(*) as degree
+        FROM (
+            SELECT source_type as node_type, source_id as node_id FROM biomedgps_relation
+            UNION ALL
+            SELECT target_type as node_type, target_id as node_id FROM biomedgps_relation
+        ) AS nodes
+        GROUP BY node_type, node_id;
+    ", table_name)
+}
+
+/// Recompute all statistics tables (entity metadata, relation metadata, degree statistics and entity degree) from the entity and relation tables.
+///
+/// All tables are refreshed in one transaction, so the counts never drift from the data and the statistics page never sees a half-refreshed state.
+pub async fn update_statistics(pool: &sqlx::PgPool) -> Result<(), Box<dyn Error>> {
+    let mut tx = pool.begin().await?;
+
+    let queries = vec![
+        ("biomedgps_entity_metadata", gen_entity_metadata_query("biomedgps_entity_metadata")),
+        ("biomedgps_relation_metadata", gen_relation_metadata_query("biomedgps_relation_metadata")),
+        ("biomedgps_degree_statistics", gen_degree_statistics_query("biomedgps_degree_statistics")),
+        ("biomedgps_entity_degree", gen_entity_degree_query("biomedgps_entity_degree")),
+    ];
+
+    for (table_name, query_str) in queries {
+        info!("Refresh {} ...", table_name);
+        sqlx::query(&format!("DELETE FROM {}", table_name))
+            .execute(&mut tx)
+            .await?;
+
+        debug!("Refreshing query string: {}", query_str);
+        sqlx::query(&query_str).execute(&mut tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("All statistics tables are refreshed.");
+
+    Ok(())
+}
+
+/// An index which the graph queries rely on. The keys are the columns (or the expressions, as they are printed by `pg_get_indexdef`) of the index, an existing index with the same method covers it if its leading keys are the same keys in any order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSpec {
+    pub table_name: &'static str,
+    pub method: &'static str,
+    pub keys: &'static [&'static str],
+    /// Why the index is needed.
+    pub reason: &'static str,
+}
+
+/// The indexes which are expected by the expansion, search and list queries, they are created by the migrations.
+pub const GRAPH_INDEXES: [IndexSpec; 14] = [
+    IndexSpec {
+        table_name: "biomedgps_entity",
+        method: "btree",
+        keys: &["id", "label"],
+        reason: "fetch an entity by id and label",
+    },
+    IndexSpec {
+        table_name: "biomedgps_entity",
+        method: "gin",
+        keys: &["name"],
+        reason: "search the entities by name (trigram)",
+    },
+    IndexSpec {
+        table_name: "biomedgps_entity",
+        method: "gin",
+        keys: &["id"],
+        reason: "search the entities by id (trigram)",
+    },
+    IndexSpec {
+        table_name: "biomedgps_relation",
+        method: "btree",
+        keys: &["relation_type"],
+        reason: "filter the relations by relation type",
+    },
+    IndexSpec {
+        table_name: "biomedgps_relation",
+        method: "btree",
+        keys: &["source_id", "source_type"],
+        reason: "expand a node by its outgoing relations",
+    },
+    IndexSpec {
+        table_name: "biomedgps_relation",
+        method: "btree",
+        keys: &["target_id", "target_type"],
+        reason: "expand a node by its incoming relations",
+    },
+    IndexSpec {
+        table_name: "biomedgps_relation",
+        method: "gin",
+        keys: &["attributes"],
+        reason: "filter the relations by the attributes (jsonb)",
+    },
+    IndexSpec {
+        table_name: "biomedgps_relation",
+        method: "btree",
+        keys: &["split"],
+        reason: "export the triples of a dataset split",
+    },
+    IndexSpec {
+        table_name: "biomedgps_knowledge_curation",
+        method: "btree",
+        keys: &["source_id", "source_type"],
+        reason: "find the curated knowledges of a source node",
+    },
+    IndexSpec {
+        table_name: "biomedgps_knowledge_curation",
+        method: "btree",
+        keys: &["target_id", "target_type"],
+        reason: "find the curated knowledges of a target node",
+    },
+    IndexSpec {
+        table_name: "biomedgps_knowledge_curation",
+        method: "btree",
+        keys: &["curator"],
+        reason: "list the curated knowledges of a curator",
+    },
+    IndexSpec {
+        table_name: "biomedgps_knowledge_curation",
+        method: "gin",
+        keys: &["payload"],
+        reason: "filter the curated knowledges by the payload (jsonb)",
+    },
+    IndexSpec {
+        table_name: "biomedgps_entity2d",
+        method: "btree",
+        keys: &["entity_id", "entity_type"],
+        reason: "fetch the 2D coordinates of an entity",
+    },
+    IndexSpec {
+        table_name: "biomedgps_entity_embedding",
+        method: "btree",
+        keys: &["entity_id", "entity_type"],
+        reason: "fetch the embedding of an entity",
+    },
+    IndexSpec {
+        table_name: "biomedgps_subgraph",
+        method: "btree",
+        keys: &["owner"],
+        reason: "list the subgraphs of an owner",
+    },
+    IndexSpec {
+        table_name: "biomedgps_subgraph",
+        method: "gin",
+        keys: &["(payload -> 'nodes'::text)"],
+        reason: "find the subgraphs which contain a node",
+    },
+];
+
+impl IndexSpec {
+    /// Whether an existing index (its method and keys) covers the expected index.
+    pub fn is_covered_by(&self, method: &str, keys: &[String]) -> bool {
+        if method != self.method || keys.len() < self.keys.len() {
+            return false;
+        }
+
+        let leading_keys = &keys[..self.keys.len()];
+        self.keys
+            .iter()
+            .all(|key| leading_keys.iter().any(|k| k == key))
+    }
+}
+
+/// Find the expected indexes (see [`GRAPH_INDEXES`]) which don't exist in the database. The indexes of the missing tables are also reported.
+pub async fn check_indexes(pool: &sqlx::PgPool) -> Result<Vec<IndexSpec>, Box<dyn Error>> {
+    let table_names = GRAPH_INDEXES
+        .iter()
+        .map(|spec| spec.table_name.to_string())
+        .collect::<Vec<String>>();
+
+    // The keys of each index, the key of an expression index is like `(payload -> 'nodes'::text)`.
+    let sql_str = "
+        SELECT t.relname::TEXT, am.amname::TEXT,
+               ARRAY(SELECT pg_get_indexdef(ix.indexrelid, k, true)
+                     FROM generate_series(1, ix.indnkeyatts) AS k ORDER BY k)
+        FROM pg_index ix
+        JOIN pg_class i ON i.oid = ix.indexrelid
+        JOIN pg_class t ON t.oid = ix.indrelid
+        JOIN pg_am am ON am.oid = i.relam
+        WHERE t.relname = ANY($1) AND pg_table_is_visible(t.oid)
+    ";
+    let indexes = sqlx::query_as::<_, (String, String, Vec<String>)>(sql_str)
+        .bind(&table_names)
+        .fetch_all(pool)
+        .await?;
+
+    let missing = GRAPH_INDEXES
+        .iter()
+        .filter(|spec| {
+            !indexes.iter().any(|(table_name, method, keys)| {
+                table_name == spec.table_name && spec.is_covered_by(method, keys)
+            })
+        })
+        .cloned()
+        .collect();
+
+    Ok(missing)
+}
+
+/// The orphaned rows of a table, they reference the entities or relations which don't exist (e.g. after a partial import).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanReport {
+    pub table_name: String,
+    /// What the orphaned rows reference.
+    pub reason: String,
+    pub count: u64,
+}
+
+/// The condition of an entity reference which doesn't exist in the entity table, the table to check is aliased as `t`.
+pub(crate) fn missing_entity(id_column: &str, type_column: &str) -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM biomedgps_entity e WHERE e.id = t.{} AND e.label = t.{})",
+        id_column, type_column
+    )
+}
+
+/// The tables which may contain orphaned rows, the reasons and the conditions of the orphaned rows. The relation table comes before the relation_embedding table, so the embeddings of the relation types which only belong to the deleted relations are also cleaned.
+fn get_orphan_conditions() -> Vec<(&'static str, &'static str, String)> {
+    // The placeholders are allowed in the curated knowledges, see `check_curated_knowledges`.
+    let curated_entity = |id_column: &str, type_column: &str| {
+        format!(
+            "(t.{} NOT IN ('', 'Unknown:Unknown') AND {})",
+            id_column,
+            missing_entity(id_column, type_column)
+        )
+    };
+
+    vec![
+        (
+            "biomedgps_relation",
+            "missing source or target entities",
+            format!(
+                "{} OR {}",
+                missing_entity("source_id", "source_type"),
+                missing_entity("target_id", "target_type")
+            ),
+        ),
+        (
+            "biomedgps_entity_embedding",
+            "missing entities",
+            missing_entity("entity_id", "entity_type"),
+        ),
+        (
+            "biomedgps_relation_embedding",
+            "missing relation types",
+            "NOT EXISTS (SELECT 1 FROM biomedgps_relation r WHERE r.relation_type = t.relation_type)"
+                .to_string(),
+        ),
+        (
+            "biomedgps_entity2d",
+            "missing entities",
+            missing_entity("entity_id", "entity_type"),
+        ),
+        (
+            "biomedgps_knowledge_curation",
+            "missing source or target entities",
+            format!(
+                "{} OR {}",
+                curated_entity("source_id", "source_type"),
+                curated_entity("target_id", "target_type")
+            ),
+        ),
+        (
+            "biomedgps_entity_attribute",
+            "missing entities",
+            missing_entity("entity_id", "entity_type"),
+        ),
+        (
+            "biomedgps_entity_translation",
+            "missing entities",
+            missing_entity("entity_id", "entity_type"),
+        ),
+        (
+            "biomedgps_compound_structure",
+            "missing entities",
+            missing_entity("entity_id", "entity_type"),
+        ),
+    ]
+}
+
+/// Find the orphaned rows in the entity_embedding, relation_embedding, entity2d, relation, knowledge_curation, entity_attribute, entity_translation and compound_structure tables, and delete them if dry_run is false.
+///
+/// All tables are cleaned in one transaction, so a failure leaves the database untouched. A dry run rolls the transaction back, so it reports the same numbers as a real run. The statistics tables are not refreshed here, call [`update_statistics`] after the relations are deleted.
+pub async fn clean_orphans(
+    pool: &sqlx::PgPool,
+    dry_run: bool,
+) -> Result<Vec<OrphanReport>, Box<dyn Error>> {
+    let mut tx = pool.begin().await?;
+    let mut reports = vec![];
+
+    for (table_name, reason, condition) in get_orphan_conditions() {
+        let query_str = format!("DELETE FROM {} AS t WHERE {}", table_name, condition);
+        debug!("Deleting orphaned rows: {}", query_str);
+        let count = sqlx::query(&query_str)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        reports.push(OrphanReport {
+            table_name: table_name.to_string(),
+            reason: reason.to_string(),
+            count,
+        });
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(reports)
+}
+
+/// The default number of days to keep the soft-deleted curated knowledges and subgraphs before they are purged.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// The soft-deleted rows of a table which are purged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PurgeReport {
+    pub table_name: String,
+    pub count: u64,
+}
+
+/// Hard delete the soft-deleted curated knowledges and subgraphs which were deleted more than retention_days ago, they cannot be restored after that. A purged root subgraph also removes its child subgraphs.
+///
+/// All tables are purged in one transaction, and a dry run rolls the transaction back, so it reports the same numbers as a real run.
+pub async fn purge_deleted(
+    pool: &sqlx::PgPool,
+    retention_days: u32,
+    dry_run: bool,
+) -> Result<Vec<PurgeReport>, Box<dyn Error>> {
+    let mut tx = pool.begin().await?;
+    let mut reports = vec![];
+
+    for table_name in SOFT_DELETE_TABLES {
+        let query_str = format!(
+            "DELETE FROM {} WHERE deleted_at < now() - make_interval(days => $1)",
+            table_name
+        );
+        debug!("Purging the deleted rows: {}", query_str);
+        let count = sqlx::query(&query_str)
+            .bind(retention_days as i32)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        reports.push(PurgeReport {
+            table_name: table_name.to_string(),
+            count,
+        });
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(reports)
+}
+
 pub fn parse_csv_error(e: &csv::Error) -> String {
     match *e.kind() {
         csv::ErrorKind::Deserialize {
@@ -255,3 +1165,107 @@ pub fn show_errors(errors: &Vec<Box<dyn std::error::Error>>, show_all_errors: bo
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn read_records(filepath: &PathBuf) -> Vec<Vec<String>> {
+        let delimiter = get_delimiter(filepath).unwrap();
+        let mut reader = open_csv_reader(filepath, delimiter).unwrap();
+        let mut records = vec![reader
+            .headers()
+            .unwrap()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()];
+        for record in reader.records() {
+            records.push(record.unwrap().iter().map(|v| v.to_string()).collect());
+        }
+        records
+    }
+
+    #[test]
+    fn test_open_data_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "id\tname\nMESH:D001\tfoo bar\nMESH:D002\t\"a\tb\"\n";
+        let expected = vec![
+            vec!["id".to_string(), "name".to_string()],
+            vec!["MESH:D001".to_string(), "foo bar".to_string()],
+            vec!["MESH:D002".to_string(), "a\tb".to_string()],
+        ];
+
+        let gz_filepath = dir.path().join("entity.tsv.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&gz_filepath).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(read_records(&gz_filepath), expected);
+
+        let zst_filepath = dir.path().join("entity.tsv.zst");
+        let compressed = zstd::stream::encode_all(content.as_bytes(), 0).unwrap();
+        std::fs::write(&zst_filepath, compressed).unwrap();
+        assert_eq!(read_records(&zst_filepath), expected);
+
+        let parquet_filepath = dir.path().join("entity.parquet");
+        let schema = parse_message_type(
+            "message schema { REQUIRED BYTE_ARRAY id (UTF8); REQUIRED DOUBLE score; }",
+        )
+        .unwrap();
+        let mut writer = SerializedFileWriter::new(
+            File::create(&parquet_filepath).unwrap(),
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("MESH:D001"), ByteArray::from("a\tb")],
+                None,
+                None,
+            )
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[0.5, 1.0], None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        assert_eq!(
+            read_records(&parquet_filepath),
+            vec![
+                vec!["id".to_string(), "score".to_string()],
+                vec!["MESH:D001".to_string(), "0.5".to_string()],
+                vec!["a\tb".to_string(), "1.0".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_spec() {
+        let spec = &GRAPH_INDEXES[4];
+        assert_eq!(spec.keys, &["source_id", "source_type"]);
+
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+        assert!(spec.is_covered_by("btree", &keys(&["source_type", "source_id"])));
+        assert!(spec.is_covered_by("btree", &keys(&["source_id", "source_type", "id"])));
+        assert!(!spec.is_covered_by("btree", &keys(&["source_id"])));
+        assert!(!spec.is_covered_by("btree", &keys(&["id", "source_id", "source_type"])));
+        assert!(!spec.is_covered_by("gin", &keys(&["source_id", "source_type"])));
+    }
+}
@@ -0,0 +1,321 @@
+//! Vocabulary module contains the controlled vocabularies of the knowledge graph, i.e. the entity labels and the relation types with their start and end entity types. They come from the entity and relation metadata tables, which are refreshed by `biomedgps-cli statdb`.
+//!
+//! The vocabularies are used to validate the query filters and the curated knowledges, so a client gets a helpful error message instead of an empty result when it guesses a wrong label or relation type. The validation is skipped when the metadata tables are empty, e.g. before `biomedgps-cli statdb` is run.
+
+use crate::model::core::{EntityMetadata, RelationMetadata, ValidationError};
+use crate::query_builder::sql_builder::{ComposeQuery, Value};
+use anyhow::Ok as AnyOk;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+/// The fields of a query which hold an entity label.
+pub const LABEL_FIELDS: [&str; 6] = [
+    "label",
+    "entity_type",
+    "source_type",
+    "target_type",
+    "start_entity_type",
+    "end_entity_type",
+];
+/// The fields of a query which hold a relation type.
+pub const RELATION_TYPE_FIELDS: [&str; 1] = ["relation_type"];
+// The max number of the suggested values in an error message.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A relation type and the entity types of its two ends.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Object)]
+pub struct RelationTypeSchema {
+    pub relation_type: String,
+    pub start_entity_type: String,
+    pub end_entity_type: String,
+}
+
+/// The controlled vocabularies of the knowledge graph, the values are distinct and sorted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, Object)]
+pub struct GraphSchema {
+    pub labels: Vec<String>,
+    pub relation_types: Vec<RelationTypeSchema>,
+}
+
+impl GraphSchema {
+    pub fn new(labels: Vec<String>, relation_types: Vec<RelationTypeSchema>) -> Self {
+        let mut labels = labels;
+        labels.sort();
+        labels.dedup();
+
+        let mut relation_types = relation_types;
+        relation_types.sort();
+        relation_types.dedup();
+
+        GraphSchema {
+            labels,
+            relation_types,
+        }
+    }
+
+    pub fn from_metadata(
+        entity_metadata: &[EntityMetadata],
+        relation_metadata: &[RelationMetadata],
+    ) -> Self {
+        let labels = entity_metadata
+            .iter()
+            .map(|metadata| metadata.entity_type.clone())
+            .collect();
+        let relation_types = relation_metadata
+            .iter()
+            .map(|metadata| RelationTypeSchema {
+                relation_type: metadata.relation_type.clone(),
+                start_entity_type: metadata.start_entity_type.clone(),
+                end_entity_type: metadata.end_entity_type.clone(),
+            })
+            .collect();
+
+        GraphSchema::new(labels, relation_types)
+    }
+
+    pub async fn get(pool: &sqlx::PgPool) -> Result<Self, anyhow::Error> {
+        let entity_metadata = EntityMetadata::get_entity_metadata(pool).await?;
+        let relation_metadata = RelationMetadata::get_relation_metadata(pool).await?;
+
+        AnyOk(GraphSchema::from_metadata(
+            &entity_metadata,
+            &relation_metadata,
+        ))
+    }
+
+    /// The distinct relation types without their entity types.
+    pub fn relation_type_names(&self) -> Vec<String> {
+        let mut names = self
+            .relation_types
+            .iter()
+            .map(|r| r.relation_type.clone())
+            .collect::<Vec<String>>();
+        names.dedup();
+        names
+    }
+
+    /// Check whether the label is in the vocabulary, it is always valid when the vocabulary is empty.
+    ///
+    /// ```
+    /// use biomedgps::model::vocabulary::GraphSchema;
+    ///
+    /// let schema = GraphSchema::new(vec!["Compound".to_string(), "Disease".to_string()], vec![]);
+    /// assert!(schema.check_label("Disease").is_ok());
+    /// let err = schema.check_label("disease").unwrap_err();
+    /// assert!(err.to_string().contains("did you mean Disease?"));
+    /// ```
+    pub fn check_label(&self, label: &str) -> Result<(), ValidationError> {
+        if self.labels.is_empty() || self.labels.iter().any(|l| l == label) {
+            return Ok(());
+        }
+
+        Err(ValidationError::new(&format!(
+            "Invalid label: {}, {}",
+            label,
+            suggest(label, &self.labels)
+        )))
+    }
+
+    /// Check whether the relation type is in the vocabulary, it is always valid when the vocabulary is empty.
+    pub fn check_relation_type(&self, relation_type: &str) -> Result<(), ValidationError> {
+        if self.relation_types.is_empty()
+            || self
+                .relation_types
+                .iter()
+                .any(|r| r.relation_type == relation_type)
+        {
+            return Ok(());
+        }
+
+        Err(ValidationError::new(&format!(
+            "Invalid relation type: {}, {}",
+            relation_type,
+            suggest(relation_type, &self.relation_type_names())
+        )))
+    }
+
+    /// Check whether the relation type connects the source and target types. The two ends may be swapped, because a curated knowledge may be recorded in the reversed direction of a symmetric relation.
+    pub fn check_relation(
+        &self,
+        relation_type: &str,
+        source_type: &str,
+        target_type: &str,
+    ) -> Result<(), ValidationError> {
+        self.check_label(source_type)?;
+        self.check_label(target_type)?;
+        self.check_relation_type(relation_type)?;
+        if self.relation_types.is_empty() {
+            return Ok(());
+        }
+
+        let ends = self
+            .relation_types
+            .iter()
+            .filter(|r| r.relation_type == relation_type)
+            .collect::<Vec<&RelationTypeSchema>>();
+        let matched = ends.iter().any(|r| {
+            (r.start_entity_type == source_type && r.end_entity_type == target_type)
+                || (r.start_entity_type == target_type && r.end_entity_type == source_type)
+        });
+        if matched {
+            return Ok(());
+        }
+
+        Err(ValidationError::new(&format!(
+            "The relation type {} cannot connect {} and {}, it connects {}.",
+            relation_type,
+            source_type,
+            target_type,
+            ends.iter()
+                .map(|r| format!("{} and {}", r.start_entity_type, r.end_entity_type))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )))
+    }
+
+    /// Check the labels and relation types in the query filters. Only the exact matches (`=`, `!=`, `<>`, `in` and `not in`) are checked, the patterns of `like` and `ilike` are not.
+    pub fn check_query(&self, query: &ComposeQuery) -> Result<(), ValidationError> {
+        match query {
+            ComposeQuery::QueryItem(item) => {
+                let operator = item.operator.to_lowercase();
+                if !["=", "!=", "<>", "in", "not in"].contains(&operator.as_str()) {
+                    return Ok(());
+                }
+
+                let values = match &item.value {
+                    Value::String(value) => vec![value.clone()],
+                    Value::ArrayString(values) => values.clone(),
+                    _ => return Ok(()),
+                };
+
+                let field = item.field.as_str();
+                for value in values.iter() {
+                    if LABEL_FIELDS.contains(&field) {
+                        self.check_label(value)?;
+                    } else if RELATION_TYPE_FIELDS.contains(&field) {
+                        self.check_relation_type(value)?;
+                    }
+                }
+
+                Ok(())
+            }
+            ComposeQuery::ComposeQueryItem(item) => {
+                for query in item.items.iter() {
+                    self.check_query(query)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Suggest the valid values which are similar to the invalid one, i.e. the same value in a different case or the values which contain it. All the valid values are listed if there are only a few of them.
+fn suggest(value: &str, candidates: &[String]) -> String {
+    let lowercase = value.to_lowercase();
+    if let Some(candidate) = candidates.iter().find(|c| c.to_lowercase() == lowercase) {
+        return format!("did you mean {}?", candidate);
+    }
+
+    let similar = candidates
+        .iter()
+        .filter(|c| {
+            let c = c.to_lowercase();
+            !lowercase.is_empty() && (c.contains(&lowercase) || lowercase.contains(&c))
+        })
+        .take(MAX_SUGGESTIONS)
+        .cloned()
+        .collect::<Vec<String>>();
+    if !similar.is_empty() {
+        return format!("did you mean one of {}?", similar.join(", "));
+    }
+
+    if candidates.len() <= MAX_SUGGESTIONS * 2 {
+        format!("it should be one of {}.", candidates.join(", "))
+    } else {
+        "see /api/v1/schema for the valid values.".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_builder::sql_builder::{ComposeQueryItem, QueryItem};
+
+    fn schema() -> GraphSchema {
+        let relation_type = |name: &str, start: &str, end: &str| RelationTypeSchema {
+            relation_type: name.to_string(),
+            start_entity_type: start.to_string(),
+            end_entity_type: end.to_string(),
+        };
+
+        GraphSchema::new(
+            vec![
+                "Gene".to_string(),
+                "Compound".to_string(),
+                "Disease".to_string(),
+                "Gene".to_string(),
+            ],
+            vec![
+                relation_type("DRUGBANK::treats::Compound:Disease", "Compound", "Disease"),
+                relation_type("STRING::binds::Gene:Gene", "Gene", "Gene"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_check_relation() {
+        let schema = schema();
+        assert_eq!(schema.labels, vec!["Compound", "Disease", "Gene"]);
+        assert!(schema
+            .check_relation("DRUGBANK::treats::Compound:Disease", "Compound", "Disease")
+            .is_ok());
+        // The reversed direction is allowed.
+        assert!(schema
+            .check_relation("DRUGBANK::treats::Compound:Disease", "Disease", "Compound")
+            .is_ok());
+
+        let err = schema
+            .check_relation("DRUGBANK::treats::Compound:Disease", "Gene", "Disease")
+            .unwrap_err();
+        assert!(err.to_string().contains("it connects Compound and Disease"));
+
+        let err = schema.check_relation_type("treats").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("did you mean one of DRUGBANK::treats::Compound:Disease?"));
+
+        // The empty vocabularies accept everything.
+        assert!(GraphSchema::default()
+            .check_relation("treats", "Drug", "Disease")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_check_query() {
+        let schema = schema();
+        let mut query = ComposeQueryItem::new("and");
+        query.items.push(ComposeQuery::QueryItem(QueryItem::new(
+            "label".to_string(),
+            Value::ArrayString(vec!["Gene".to_string(), "Drug".to_string()]),
+            "in".to_string(),
+        )));
+        let err = schema
+            .check_query(&ComposeQuery::ComposeQueryItem(query))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid label: Drug"));
+
+        // The patterns and the other fields are not checked.
+        let query = ComposeQuery::QueryItem(QueryItem::new(
+            "label".to_string(),
+            Value::String("%ene".to_string()),
+            "like".to_string(),
+        ));
+        assert!(schema.check_query(&query).is_ok());
+        let query = ComposeQuery::QueryItem(QueryItem::new(
+            "name".to_string(),
+            Value::String("Drug".to_string()),
+            "=".to_string(),
+        ));
+        assert!(schema.check_query(&query).is_ok());
+    }
+}
@@ -0,0 +1,333 @@
+//! Workspace module is used to share the subgraphs, saved queries and curated knowledges within a team. A workspace has members with roles, the records which have a workspace_id can be listed by all members of the workspace and created or updated by the editors.
+//!
+//! The records without a workspace_id are personal records, they are not affected by the workspaces.
+
+use crate::query_builder::sql_builder::{ComposeQuery, ComposeQueryItem, QueryItem, Value};
+use anyhow::Ok as AnyOk;
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// The role of a workspace member, a role has all permissions of the lower roles.
+///
+/// * `Viewer` - Can list the records of the workspace.
+/// * `Editor` - Can also create and update the records in the workspace.
+/// * `Owner` - Can also invite and remove the members.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Enum)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceRole {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl WorkspaceRole {
+    /// Parse the role which is stored in the database, it returns None if the role is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use biomedgps::model::workspace::WorkspaceRole;
+    ///
+    /// assert_eq!(WorkspaceRole::from_name("Editor"), Some(WorkspaceRole::Editor));
+    /// assert_eq!(WorkspaceRole::from_name("admin"), None);
+    /// ```
+    pub fn from_name(role: &str) -> Option<WorkspaceRole> {
+        match role.to_lowercase().as_str() {
+            "viewer" => Some(WorkspaceRole::Viewer),
+            "editor" => Some(WorkspaceRole::Editor),
+            "owner" => Some(WorkspaceRole::Owner),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            WorkspaceRole::Viewer => "viewer",
+            WorkspaceRole::Editor => "editor",
+            WorkspaceRole::Owner => "owner",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow, Validate)]
+pub struct Workspace {
+    #[serde(skip_deserializing)]
+    #[oai(read_only)]
+    pub id: i64,
+
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "The length of name should be between 1 and 64."
+    ))]
+    pub name: String,
+
+    #[oai(skip_serializing_if_is_none)]
+    pub description: Option<String>,
+
+    /// The user who created the workspace, it is replaced by the username of the access token.
+    #[serde(default)]
+    #[oai(default)]
+    pub owner: String,
+
+    #[serde(skip_deserializing)]
+    #[serde(with = "ts_seconds")]
+    #[oai(read_only)]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Workspace {
+    pub fn update_owner(&mut self, owner: String) -> &Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Create the workspace and add the owner as a member with the owner role.
+    pub async fn insert(&self, pool: &sqlx::PgPool) -> Result<Workspace, anyhow::Error> {
+        let mut tx = pool.begin().await?;
+        let workspace = sqlx::query_as::<_, Workspace>(
+            "INSERT INTO biomedgps_workspace (name, description, owner) VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(&self.owner)
+        .fetch_one(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO biomedgps_workspace_member (workspace_id, username, role, invited_by) VALUES ($1, $2, $3, $2)",
+        )
+        .bind(workspace.id)
+        .bind(&workspace.owner)
+        .bind(WorkspaceRole::Owner.as_str())
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        AnyOk(workspace)
+    }
+
+    /// Fetch a workspace by id, return None if it doesn't exist.
+    pub async fn get_record(
+        pool: &sqlx::PgPool,
+        id: i64,
+    ) -> Result<Option<Workspace>, anyhow::Error> {
+        let record =
+            sqlx::query_as::<_, Workspace>("SELECT * FROM biomedgps_workspace WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool)
+                .await?;
+
+        AnyOk(record)
+    }
+
+    /// Fetch the workspaces which the user is a member of, or all workspaces if `all` is true (for the admins).
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        username: &str,
+        all: bool,
+    ) -> Result<Vec<Workspace>, anyhow::Error> {
+        let records = if all {
+            sqlx::query_as::<_, Workspace>(
+                "SELECT * FROM biomedgps_workspace ORDER BY created_at DESC, id DESC",
+            )
+            .fetch_all(pool)
+            .await?
+        } else {
+            sqlx::query_as::<_, Workspace>(
+                "SELECT w.* FROM biomedgps_workspace w
+                 JOIN biomedgps_workspace_member m ON m.workspace_id = w.id
+                 WHERE m.username = $1 ORDER BY w.created_at DESC, w.id DESC",
+            )
+            .bind(username)
+            .fetch_all(pool)
+            .await?
+        };
+
+        AnyOk(records)
+    }
+
+    /// Merge a condition into the query to keep the records of the workspace.
+    ///
+    /// ```
+    /// use biomedgps::model::workspace::Workspace;
+    /// use biomedgps::query_builder::sql_builder::ComposeQuery;
+    ///
+    /// match Workspace::merge_workspace_query(1, None) {
+    ///     ComposeQuery::QueryItem(item) => assert_eq!(item.format(), "workspace_id = '1'"),
+    ///     _ => panic!("The workspace id should be converted to a query item."),
+    /// }
+    /// ```
+    pub fn merge_workspace_query(workspace_id: i64, query: Option<ComposeQuery>) -> ComposeQuery {
+        // The Int value is an i32, so we use a string literal which is cast to a bigint by postgres.
+        let workspace_query = ComposeQuery::QueryItem(QueryItem::new(
+            "workspace_id".to_string(),
+            Value::String(workspace_id.to_string()),
+            "=".to_string(),
+        ));
+
+        match query {
+            Some(query) => {
+                let mut merged = ComposeQueryItem::new("and");
+                merged.add_item(query).add_item(workspace_query);
+                ComposeQuery::ComposeQueryItem(merged)
+            }
+            None => workspace_query,
+        }
+    }
+
+    /// Merge a condition into the query to keep the personal records and the records of the workspaces, it is used when a user lists the records without a workspace_id.
+    ///
+    /// ```
+    /// use biomedgps::model::workspace::Workspace;
+    /// use biomedgps::query_builder::sql_builder::ComposeQuery;
+    ///
+    /// match Workspace::merge_member_query(&[1, 2], None) {
+    ///     ComposeQuery::ComposeQueryItem(item) => assert_eq!(item.format(), "workspace_id IS NULL or workspace_id in ('1','2')"),
+    ///     _ => panic!("The workspace ids should be converted to a compose query item."),
+    /// }
+    ///
+    /// match Workspace::merge_member_query(&[], None) {
+    ///     ComposeQuery::QueryItem(item) => assert_eq!(item.format(), "workspace_id IS NULL"),
+    ///     _ => panic!("Only the personal records should be kept."),
+    /// }
+    /// ```
+    pub fn merge_member_query(workspace_ids: &[i64], query: Option<ComposeQuery>) -> ComposeQuery {
+        let personal_query = ComposeQuery::QueryItem(QueryItem::new(
+            "workspace_id".to_string(),
+            Value::Null,
+            "is null".to_string(),
+        ));
+
+        let member_query = if workspace_ids.is_empty() {
+            personal_query
+        } else {
+            // Same as merge_workspace_query, the ids are string literals which are cast to bigints by postgres.
+            let workspace_query = ComposeQuery::QueryItem(QueryItem::new(
+                "workspace_id".to_string(),
+                Value::ArrayString(workspace_ids.iter().map(|id| id.to_string()).collect()),
+                "in".to_string(),
+            ));
+            let mut merged = ComposeQueryItem::new("or");
+            merged.add_item(personal_query).add_item(workspace_query);
+            ComposeQuery::ComposeQueryItem(merged)
+        };
+
+        match query {
+            Some(query) => {
+                let mut merged = ComposeQueryItem::new("and");
+                merged.add_item(query).add_item(member_query);
+                ComposeQuery::ComposeQueryItem(merged)
+            }
+            None => member_query,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Object, sqlx::FromRow)]
+pub struct WorkspaceMember {
+    pub id: i64,
+    pub workspace_id: i64,
+    pub username: String,
+    /// One of viewer, editor and owner.
+    pub role: String,
+    pub invited_by: String,
+
+    #[serde(with = "ts_seconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl WorkspaceMember {
+    /// Get the role of the user in the workspace, return None if the user is not a member.
+    pub async fn get_role(
+        pool: &sqlx::PgPool,
+        workspace_id: i64,
+        username: &str,
+    ) -> Result<Option<WorkspaceRole>, anyhow::Error> {
+        let role: Option<String> = sqlx::query_scalar(
+            "SELECT role FROM biomedgps_workspace_member WHERE workspace_id = $1 AND username = $2",
+        )
+        .bind(workspace_id)
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+        AnyOk(role.and_then(|role| WorkspaceRole::from_name(&role)))
+    }
+
+    pub async fn get_records(
+        pool: &sqlx::PgPool,
+        workspace_id: i64,
+    ) -> Result<Vec<WorkspaceMember>, anyhow::Error> {
+        let records = sqlx::query_as::<_, WorkspaceMember>(
+            "SELECT * FROM biomedgps_workspace_member WHERE workspace_id = $1 ORDER BY created_at ASC, id ASC",
+        )
+        .bind(workspace_id)
+        .fetch_all(pool)
+        .await?;
+
+        AnyOk(records)
+    }
+
+    /// Add the user to the workspace, or change the role if the user is already a member.
+    pub async fn upsert(
+        pool: &sqlx::PgPool,
+        workspace_id: i64,
+        username: &str,
+        role: WorkspaceRole,
+        invited_by: &str,
+    ) -> Result<WorkspaceMember, anyhow::Error> {
+        let record = sqlx::query_as::<_, WorkspaceMember>(
+            "INSERT INTO biomedgps_workspace_member (workspace_id, username, role, invited_by) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (workspace_id, username) DO UPDATE SET role = EXCLUDED.role RETURNING *",
+        )
+        .bind(workspace_id)
+        .bind(username)
+        .bind(role.as_str())
+        .bind(invited_by)
+        .fetch_one(pool)
+        .await?;
+
+        AnyOk(record)
+    }
+
+    /// Remove the user from the workspace, return None if the user is not a member.
+    pub async fn delete(
+        pool: &sqlx::PgPool,
+        workspace_id: i64,
+        username: &str,
+    ) -> Result<Option<WorkspaceMember>, anyhow::Error> {
+        let record = sqlx::query_as::<_, WorkspaceMember>(
+            "DELETE FROM biomedgps_workspace_member WHERE workspace_id = $1 AND username = $2 RETURNING *",
+        )
+        .bind(workspace_id)
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+        AnyOk(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_role() {
+        assert!(WorkspaceRole::Owner > WorkspaceRole::Editor);
+        assert!(WorkspaceRole::Editor > WorkspaceRole::Viewer);
+
+        for role in [
+            WorkspaceRole::Viewer,
+            WorkspaceRole::Editor,
+            WorkspaceRole::Owner,
+        ] {
+            assert_eq!(WorkspaceRole::from_name(role.as_str()), Some(role));
+        }
+    }
+}
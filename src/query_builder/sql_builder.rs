@@ -1,15 +1,21 @@
 //! A SQL builder for building SQL queries.
+//!
+//! A query can be formatted with the values inlined (see [`ComposeQueryItem::format`]), or with the values as the bound parameters (see [`ComposeQuery::format_with_params`] and [`bind_values`]).
 
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArguments, Postgres};
+use sqlx::query::{Query, QueryAs};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(untagged)]
 pub enum Value {
     Int(i32),
     Float(f64),
     String(String),
     Bool(bool),
+    #[default]
     Null,
     ArrayString(Vec<String>),
     ArrayInt(Vec<i32>),
@@ -17,69 +23,191 @@ pub enum Value {
     ArrayBool(Vec<bool>),
 }
 
+impl Value {
+    /// The name of the type of the value, it is used in the error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Bool(_) => "boolean",
+            Value::Null => "null",
+            Value::ArrayString(_) => "string array",
+            Value::ArrayInt(_) => "integer array",
+            Value::ArrayFloat(_) => "float array",
+            Value::ArrayBool(_) => "boolean array",
+        }
+    }
+
+    /// The number of the values in an array, None for a scalar value.
+    fn array_len(&self) -> Option<usize> {
+        match self {
+            Value::ArrayString(v) => Some(v.len()),
+            Value::ArrayInt(v) => Some(v.len()),
+            Value::ArrayFloat(v) => Some(v.len()),
+            Value::ArrayBool(v) => Some(v.len()),
+            _ => None,
+        }
+    }
+
+    /// Split an array into its values, a scalar value is returned as it is.
+    fn to_values(&self) -> Vec<Value> {
+        match self {
+            Value::ArrayString(v) => v.iter().cloned().map(Value::String).collect(),
+            Value::ArrayInt(v) => v.iter().cloned().map(Value::Int).collect(),
+            Value::ArrayFloat(v) => v.iter().cloned().map(Value::Float).collect(),
+            Value::ArrayBool(v) => v.iter().cloned().map(Value::Bool).collect(),
+            value => vec![value.clone()],
+        }
+    }
+
+    /// Format the value as a sql literal, the single quotes in a string are escaped.
+    fn to_literal(&self) -> String {
+        match self {
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) => v.to_string(),
+            Value::String(v) => quote_literal(v),
+            Value::Bool(v) => v.to_string(),
+            Value::Null => "NULL".to_string(),
+            _ => self
+                .to_values()
+                .iter()
+                .map(|v| v.to_literal())
+                .collect::<Vec<String>>()
+                .join(","),
+        }
+    }
+}
+
+/// Quote a string as a sql literal, such as `'O''Brien'` for `O'Brien`.
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Escape the wildcards (`%` and `_`) and the escape character (`\`) of a LIKE pattern, so the value is matched literally with `ESCAPE '\'`.
+///
+/// ```
+/// use biomedgps::query_builder::sql_builder::escape_like_pattern;
+///
+/// assert_eq!(escape_like_pattern("50%_off"), r"50\%\_off");
+/// assert_eq!(escape_like_pattern(r"C:\temp"), r"C:\\temp");
+/// ```
+pub fn escape_like_pattern(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('%', r"\%")
+        .replace('_', r"\_")
+}
+
+/// The types of the columns of a table, such as `{"created_at": "timestamptz", "workspace_id": "int8"}` (the `udt_name` in the information schema). A string is sent as a text parameter which postgres doesn't compare with the other types, so it is cast to the type of its column.
+pub type ColumnTypes = HashMap<String, String>;
+
+/// Whether a column of the type can be compared with a text parameter without a cast.
+fn is_text_type(column_type: &str) -> bool {
+    matches!(
+        column_type,
+        "text" | "varchar" | "bpchar" | "name" | "citext"
+    )
+}
+
+/// Bind the parameters of the sql which is formatted by [`ComposeQuery::format_with_params`], in the same order. A null is bound as a null text.
+pub fn bind_values<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    values: &[Value],
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    values
+        .iter()
+        .cloned()
+        .fold(query, |query, value| match value {
+            Value::Int(v) => query.bind(v),
+            Value::Float(v) => query.bind(v),
+            Value::String(v) => query.bind(v),
+            Value::Bool(v) => query.bind(v),
+            Value::Null => query.bind(None::<String>),
+            Value::ArrayString(v) => query.bind(v),
+            Value::ArrayInt(v) => query.bind(v),
+            Value::ArrayFloat(v) => query.bind(v),
+            Value::ArrayBool(v) => query.bind(v),
+        })
+}
+
+/// Same as [`bind_values`], but for the queries whose rows are read by the column names, such as the aggregation queries.
+pub fn bind_query_values<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    values: &[Value],
+) -> Query<'q, Postgres, PgArguments> {
+    values
+        .iter()
+        .cloned()
+        .fold(query, |query, value| match value {
+            Value::Int(v) => query.bind(v),
+            Value::Float(v) => query.bind(v),
+            Value::String(v) => query.bind(v),
+            Value::Bool(v) => query.bind(v),
+            Value::Null => query.bind(None::<String>),
+            Value::ArrayString(v) => query.bind(v),
+            Value::ArrayInt(v) => query.bind(v),
+            Value::ArrayFloat(v) => query.bind(v),
+            Value::ArrayBool(v) => query.bind(v),
+        })
+}
+
+/// The max nesting depth of a query, i.e. the number of the nested `and` and `or` groups.
+pub const MAX_QUERY_DEPTH: usize = 5;
+
+/// An error of a query which is found before the sql is built, such as an unknown field. The path locates the query item, such as `items[1].items[0]`, it is empty for the top level.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryError {
+    pub path: String,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(path: &str, field: Option<&str>, message: String) -> Self {
+        Self {
+            path: path.to_string(),
+            field: field.map(|field| field.to_string()),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+fn join_path(path: &str, index: usize) -> String {
+    if path.is_empty() {
+        format!("items[{}]", index)
+    } else {
+        format!("{}.items[{}]", path, index)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct QueryItem {
     pub field: String,
+    /// The value can be omitted for the `is null` and `is not null` operators.
+    #[serde(default)]
     pub value: Value,
-    pub operator: String, // =, !=, like, not like, ilike, in, not in
+    pub operator: String, // =, !=, like, not like, ilike, not ilike, in, not in, between, not between, is null, is not null, @>
 }
 
 impl QueryItem {
     pub fn new(field: String, value: Value, operator: String) -> Self {
-        let allowed_operators = vec![
-            "=", "!=", "like", "not like", "ilike", "in", "not in", "<>", "<", ">", "<=", ">=",
-        ];
-        if !allowed_operators.contains(&operator.as_str()) {
+        if !QueryItem::allowed_operators(&value).contains(&operator.as_str()) {
             panic!("Invalid operator: {}", operator);
         }
 
-        match value {
-            Value::Int(_) => {
-                if !vec!["=", "!=", ">", "<", "<=", ">="].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::Float(_) => {
-                if !vec!["=", "!=", ">", "<", "<=", ">="].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::String(_) => {
-                if !vec!["=", "!=", "like", "not like", "ilike", "<>"].contains(&operator.as_str())
-                {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::Bool(_) => {
-                if !vec!["=", "!="].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::Null => {
-                if !vec!["=", "!="].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::ArrayString(_) => {
-                if !vec!["in", "not in"].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::ArrayInt(_) => {
-                if !vec!["in", "not in"].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::ArrayFloat(_) => {
-                if !vec!["in", "not in"].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
-            Value::ArrayBool(_) => {
-                if !vec!["in", "not in"].contains(&operator.as_str()) {
-                    panic!("Invalid operator: {}", operator);
-                }
-            }
+        if let Err(e) = QueryItem::check_value(&value, &operator) {
+            panic!("{}", e);
         }
 
         Self {
@@ -89,6 +217,119 @@ impl QueryItem {
         }
     }
 
+    /// The operators which can be used with the type of the value, because the column is compared with the value as the same type.
+    pub fn allowed_operators(value: &Value) -> &'static [&'static str] {
+        match value {
+            Value::Int(_) | Value::Float(_) => &["=", "!=", ">", "<", "<=", ">="],
+            // The @> operator is used for the jsonb containment, the value is a json string.
+            Value::String(_) => &[
+                "=",
+                "!=",
+                "like",
+                "not like",
+                "ilike",
+                "not ilike",
+                "<>",
+                ">",
+                "<",
+                "<=",
+                ">=",
+                "@>",
+            ],
+            Value::Bool(_) => &["=", "!="],
+            // The = and != are kept for the compatibility, they are the same as is null and is not null.
+            Value::Null => &["=", "!=", "is null", "is not null"],
+            // The strings of between are the dates or the versions, such as ["2023-01-01", "2023-12-31"].
+            Value::ArrayString(_) | Value::ArrayInt(_) | Value::ArrayFloat(_) => {
+                &["in", "not in", "between", "not between"]
+            }
+            Value::ArrayBool(_) => &["in", "not in"],
+        }
+    }
+
+    /// Check the number of the values for the operator, `in` needs at least one value and `between` needs two values (the lower and upper bounds).
+    fn check_value(value: &Value, operator: &str) -> Result<(), String> {
+        match (operator, value.array_len()) {
+            ("in" | "not in", Some(0)) => Err(format!(
+                "Invalid value for {}, it needs at least one value.",
+                operator
+            )),
+            ("between" | "not between", Some(len)) if len != 2 => Err(format!(
+                "Invalid value for {}, it needs two values (the lower and upper bounds), but got {}.",
+                operator, len
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the field and the operator, the errors are added to the errors. A key of a jsonb column (such as `attributes.tissue`) is only allowed for the json fields, so is the `@>` operator.
+    fn check(
+        &self,
+        path: &str,
+        fields: &[String],
+        json_fields: &[String],
+        errors: &mut Vec<QueryError>,
+    ) {
+        let field = self.field.as_str();
+        let is_json_field = match self.get_json_key() {
+            Some((column, _)) if json_fields.iter().any(|f| f == column) => true,
+            Some((column, _)) if fields.iter().any(|f| f == column) => {
+                let message = if json_fields.is_empty() {
+                    format!("Invalid field: {}, {} is not a json field.", field, column)
+                } else {
+                    format!(
+                        "Invalid field: {}, {} is not a json field, the json fields are {}",
+                        field,
+                        column,
+                        json_fields.join(", ")
+                    )
+                };
+                errors.push(QueryError::new(path, Some(field), message));
+                return;
+            }
+            _ if fields.iter().any(|f| f == field) => json_fields.iter().any(|f| f == field),
+            _ => {
+                errors.push(QueryError::new(
+                    path,
+                    Some(field),
+                    format!(
+                        "Invalid field: {}, the available fields are {}",
+                        field,
+                        fields.join(", ")
+                    ),
+                ));
+                return;
+            }
+        };
+
+        // The operators are case-insensitive in sql, such as ILIKE.
+        let operator = self.operator.to_lowercase();
+        let allowed_operators = QueryItem::allowed_operators(&self.value);
+        if !allowed_operators.contains(&operator.as_str()) {
+            errors.push(QueryError::new(
+                path,
+                Some(field),
+                format!(
+                    "Invalid operator: {}, the operators for a {} value are {}",
+                    operator,
+                    self.value.type_name(),
+                    allowed_operators.join(", ")
+                ),
+            ));
+        } else if operator == "@>" && !is_json_field {
+            errors.push(QueryError::new(
+                path,
+                Some(field),
+                format!(
+                    "Invalid operator: @>, it can only be used with the json fields, but {} is not.",
+                    field
+                ),
+            ));
+        } else if let Err(message) = QueryItem::check_value(&self.value, &operator) {
+            errors.push(QueryError::new(path, Some(field), message));
+        }
+    }
+
     pub fn get_field(&self) -> &str {
         &self.field
     }
@@ -109,41 +350,171 @@ impl QueryItem {
         )
     }
 
-    pub fn format(&self) -> String {
+    /// Split a field like `attributes.tissue` into the jsonb column and the key, the column and the key must be in snake case.
+    fn get_json_key(&self) -> Option<(&str, &str)> {
+        let is_identifier = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        };
+
+        match self.field.split_once('.') {
+            Some((column, key)) if is_identifier(column) && is_identifier(key) => {
+                Some((column, key))
+            }
+            _ => None,
+        }
+    }
+
+    /// Format the field into the sql. A key of a jsonb column (such as `attributes.tissue` of the relations) is extracted as text, and it is cast to the type of the value, so `{"field": "attributes.confidence", "value": 0.5, "operator": ">"}` becomes `CAST(attributes ->> 'confidence' AS FLOAT8) > 0.5`. The `@>` operator compares the jsonb value, such as an array of tissues.
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::{QueryItem, Value};
+    ///
+    /// let item = QueryItem::new("attributes.tissue".to_string(), Value::String("liver".to_string()), "=".to_string());
+    /// assert_eq!(item.format(), "attributes ->> 'tissue' = 'liver'");
+    ///
+    /// let item = QueryItem::new("attributes.confidence".to_string(), Value::Float(0.5), ">".to_string());
+    /// assert_eq!(item.format(), "CAST(attributes ->> 'confidence' AS FLOAT8) > 0.5");
+    ///
+    /// let item = QueryItem::new("attributes.tissue".to_string(), Value::String(r#"["liver"]"#.to_string()), "@>".to_string());
+    /// assert_eq!(item.format(), r#"attributes -> 'tissue' @> '["liver"]'"#);
+    /// ```
+    fn format_field(&self) -> String {
+        let (column, key) = match self.get_json_key() {
+            Some(pair) => pair,
+            None => return self.field.clone(),
+        };
+
         match &self.value {
-            Value::Int(v) => format!("{} {} {}", self.field, self.operator, v),
-            Value::Float(v) => format!("{} {} {}", self.field, self.operator, v),
-            Value::String(v) => format!("{} {} '{}'", self.field, self.operator, v),
-            Value::Bool(v) => format!("{} {} {}", self.field, self.operator, v),
-            Value::Null => format!("{} {} NULL", self.field, self.operator),
-            Value::ArrayString(v) => {
-                let mut values = vec![];
-                for item in v {
-                    values.push(format!("'{}'", item));
-                }
-                format!("{} {} ({})", self.field, self.operator, values.join(","))
+            Value::String(_) if self.operator == "@>" => format!("{} -> '{}'", column, key),
+            Value::Int(_) | Value::Float(_) | Value::ArrayInt(_) | Value::ArrayFloat(_) => {
+                format!("CAST({} ->> '{}' AS FLOAT8)", column, key)
             }
-            Value::ArrayInt(v) => {
-                let mut values = vec![];
-                for item in v {
-                    values.push(format!("{}", item));
-                }
-                format!("{} {} ({})", self.field, self.operator, values.join(","))
+            Value::Bool(_) | Value::ArrayBool(_) => {
+                format!("CAST({} ->> '{}' AS BOOLEAN)", column, key)
             }
-            Value::ArrayFloat(v) => {
-                let mut values = vec![];
-                for item in v {
-                    values.push(format!("{}", item));
-                }
-                format!("{} {} ({})", self.field, self.operator, values.join(","))
+            _ => format!("{} ->> '{}'", column, key),
+        }
+    }
+
+    /// Format the query item into the sql with the values inlined, the strings are quoted and escaped. The `ilike` operator matches the value literally as a substring (its wildcards are escaped, see [`escape_like_pattern`]), the `like` operator keeps the pattern as it is, and the null is compared by `IS NULL`.
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::{QueryItem, Value};
+    ///
+    /// let item = QueryItem::new("name".to_string(), Value::String("Alzheimer's 50%".to_string()), "ilike".to_string());
+    /// assert_eq!(item.format(), r"name ilike '%Alzheimer''s 50\%%' ESCAPE '\'");
+    ///
+    /// let item = QueryItem::new("name".to_string(), Value::String("Alzheimer%".to_string()), "like".to_string());
+    /// assert_eq!(item.format(), "name like 'Alzheimer%'");
+    ///
+    /// let item = QueryItem::new("score".to_string(), Value::ArrayFloat(vec![0.5, 1.0]), "between".to_string());
+    /// assert_eq!(item.format(), "score BETWEEN 0.5 AND 1");
+    ///
+    /// let item = QueryItem::new("description".to_string(), Value::Null, "is not null".to_string());
+    /// assert_eq!(item.format(), "description IS NOT NULL");
+    /// ```
+    pub fn format(&self) -> String {
+        self.format_sql(None, &ColumnTypes::new())
+    }
+
+    /// Same as [`QueryItem::format`], but the values are replaced by the placeholders (such as `$1`) and pushed to the params, see [`bind_values`]. The strings are cast to the types of their columns if the columns are not text, see [`ColumnTypes`].
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::{ColumnTypes, QueryItem, Value};
+    ///
+    /// let mut params = vec![];
+    /// let column_types = ColumnTypes::from([("created_at".to_string(), "timestamptz".to_string())]);
+    /// let item = QueryItem::new("id".to_string(), Value::ArrayString(vec!["DOID:9352".to_string()]), "not in".to_string());
+    /// assert_eq!(item.format_with_params(&mut params, &column_types), "id <> ALL($1)");
+    ///
+    /// let item = QueryItem::new("name".to_string(), Value::String("50%".to_string()), "ilike".to_string());
+    /// assert_eq!(item.format_with_params(&mut params, &column_types), r"name ilike $2 ESCAPE '\'");
+    /// assert_eq!(params[1], Value::String(r"%50\%%".to_string()));
+    ///
+    /// let item = QueryItem::new("created_at".to_string(), Value::String("2023-01-01".to_string()), ">=".to_string());
+    /// assert_eq!(item.format_with_params(&mut params, &column_types), "created_at >= $3::timestamptz");
+    /// ```
+    pub fn format_with_params(
+        &self,
+        params: &mut Vec<Value>,
+        column_types: &ColumnTypes,
+    ) -> String {
+        self.format_sql(Some(params), column_types)
+    }
+
+    /// Format the query item, the values are inlined as the literals if the params is None, otherwise they are pushed to the params.
+    fn format_sql(
+        &self,
+        mut params: Option<&mut Vec<Value>>,
+        column_types: &ColumnTypes,
+    ) -> String {
+        let is_bound = params.is_some();
+        let operator = self.operator.to_lowercase();
+        // The patterns of like are always text, but the other strings are compared as the type of the column, such as a date.
+        let cast = match (self.get_json_key(), column_types.get(&self.field)) {
+            (None, Some(column_type))
+                if !operator.contains("like")
+                    && !is_text_type(column_type)
+                    && !column_type.starts_with('_') =>
+            {
+                Some(column_type.as_str())
             }
-            Value::ArrayBool(v) => {
-                let mut values = vec![];
-                for item in v {
-                    values.push(format!("{}", item));
+            _ => None,
+        };
+        let mut placeholder = |value: &Value| match params.as_mut() {
+            Some(params) => {
+                params.push(value.clone());
+                match (value, cast) {
+                    // The json is sent as text, so it needs to be cast for the containment.
+                    (Value::String(_), _) if self.operator == "@>" => {
+                        format!("${}::JSONB", params.len())
+                    }
+                    (Value::String(_), Some(column_type)) => {
+                        format!("${}::{}", params.len(), column_type)
+                    }
+                    (Value::ArrayString(_), Some(column_type)) => {
+                        format!("${}::{}[]", params.len(), column_type)
+                    }
+                    _ => format!("${}", params.len()),
                 }
-                format!("{} {} ({})", self.field, self.operator, values.join(","))
             }
+            None => value.to_literal(),
+        };
+
+        let field = self.format_field();
+        match (operator.as_str(), &self.value) {
+            ("=" | "is null", Value::Null) => format!("{} IS NULL", field),
+            ("!=" | "is not null", Value::Null) => format!("{} IS NOT NULL", field),
+            ("between" | "not between", value) => {
+                let values = value.to_values();
+                let lower = values.first().cloned().unwrap_or_default();
+                let upper = values.last().cloned().unwrap_or_default();
+                format!(
+                    "{} {} {} AND {}",
+                    field,
+                    operator.to_uppercase(),
+                    placeholder(&lower),
+                    placeholder(&upper)
+                )
+            }
+            // An array is bound as one parameter, so it is compared by ANY and ALL.
+            ("in", value) if is_bound => format!("{} = ANY({})", field, placeholder(value)),
+            ("not in", value) if is_bound => format!("{} <> ALL({})", field, placeholder(value)),
+            ("in" | "not in", value) => {
+                format!("{} {} ({})", field, self.operator, placeholder(value))
+            }
+            ("ilike" | "not ilike", Value::String(v)) => {
+                let pattern = Value::String(format!("%{}%", escape_like_pattern(v)));
+                format!(
+                    r"{} {} {} ESCAPE '\'",
+                    field,
+                    self.operator,
+                    placeholder(&pattern)
+                )
+            }
+            (_, value) => format!("{} {} {}", field, self.operator, placeholder(value)),
         }
     }
 }
@@ -163,7 +534,119 @@ pub enum ComposeQuery {
     ComposeQueryItem(ComposeQueryItem),
 }
 
+impl ComposeQuery {
+    /// Check the fields, the operators and the nesting depth of the query before the sql is built, all errors are returned with the paths of the query items.
+    ///
+    /// * `fields` - The columns which can be queried.
+    /// * `json_fields` - The jsonb columns, their keys can be queried too, such as `attributes.tissue`.
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::ComposeQuery;
+    ///
+    /// let fields = vec!["name".to_string(), "score".to_string(), "attributes".to_string()];
+    /// let json_fields = vec!["attributes".to_string()];
+    ///
+    /// let query: ComposeQuery = serde_json::from_str(r#"{"operator": "and", "items": [{"field": "name", "value": "TP53", "operator": "="}, {"field": "attributes.tissue", "value": "liver", "operator": "="}]}"#).unwrap();
+    /// assert!(query.check(&fields, &json_fields).is_ok());
+    ///
+    /// let query: ComposeQuery = serde_json::from_str(r#"{"operator": "or", "items": [{"field": "nmae", "value": "TP53", "operator": "="}, {"field": "score", "value": "high", "operator": ">"}]}"#).unwrap();
+    /// let errors = query.check(&fields, &json_fields).unwrap_err();
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].path, "items[0]");
+    /// assert_eq!(errors[1].field, Some("score".to_string()));
+    /// ```
+    pub fn check(&self, fields: &[String], json_fields: &[String]) -> Result<(), Vec<QueryError>> {
+        let mut errors = vec![];
+        match self {
+            ComposeQuery::QueryItem(item) => item.check("", fields, json_fields, &mut errors),
+            ComposeQuery::ComposeQueryItem(item) => {
+                item.check("", 1, fields, json_fields, &mut errors)
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Format the query into the sql whose values are the parameters (such as `$1`), the params are bound by [`bind_values`] in the same order. A parameter is never formatted into the sql, so the values need no escaping. The column types are the types of the columns of the queried table, see [`ColumnTypes`].
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::{ColumnTypes, ComposeQuery, Value};
+    ///
+    /// let query: ComposeQuery = serde_json::from_str(r#"{"operator": "and", "items": [{"field": "label", "value": ["Gene", "Disease"], "operator": "in"}, {"field": "taxid", "operator": "is null"}, {"field": "score", "value": [0.5, 1.0], "operator": "between"}]}"#).unwrap();
+    /// let (sql, params) = query.format_with_params(&ColumnTypes::new());
+    /// assert_eq!(sql, "label = ANY($1) and taxid IS NULL and score BETWEEN $2 AND $3");
+    /// assert_eq!(params, vec![Value::ArrayString(vec!["Gene".to_string(), "Disease".to_string()]), Value::Float(0.5), Value::Float(1.0)]);
+    /// ```
+    pub fn format_with_params(&self, column_types: &ColumnTypes) -> (String, Vec<Value>) {
+        let mut params = vec![];
+        let sql = match self {
+            ComposeQuery::QueryItem(item) => item.format_with_params(&mut params, column_types),
+            ComposeQuery::ComposeQueryItem(item) => {
+                item.format_with_params(&mut params, column_types)
+            }
+        };
+        (sql, params)
+    }
+}
+
 impl ComposeQueryItem {
+    /// Check the group and its items recursively, the top-level group is at the depth 1.
+    fn check(
+        &self,
+        path: &str,
+        depth: usize,
+        fields: &[String],
+        json_fields: &[String],
+        errors: &mut Vec<QueryError>,
+    ) {
+        if depth > MAX_QUERY_DEPTH {
+            errors.push(QueryError::new(
+                path,
+                None,
+                format!(
+                    "The query is nested too deeply, the max depth is {}.",
+                    MAX_QUERY_DEPTH
+                ),
+            ));
+            return;
+        }
+
+        if !["and", "or"].contains(&self.operator.to_lowercase().as_str()) {
+            errors.push(QueryError::new(
+                path,
+                None,
+                format!(
+                    "Invalid operator: {}, the items can only be combined by and or or.",
+                    self.operator
+                ),
+            ));
+        }
+
+        if self.items.is_empty() {
+            errors.push(QueryError::new(
+                path,
+                None,
+                "The items cannot be empty.".to_string(),
+            ));
+        }
+
+        for (i, item) in self.items.iter().enumerate() {
+            let item_path = join_path(path, i);
+            match item {
+                ComposeQuery::QueryItem(item) => {
+                    item.check(&item_path, fields, json_fields, errors)
+                }
+                ComposeQuery::ComposeQueryItem(item) => {
+                    item.check(&item_path, depth + 1, fields, json_fields, errors)
+                }
+            }
+        }
+    }
+
     pub fn new(operator: &str) -> Self {
         Self {
             operator: operator.to_string(),
@@ -244,6 +727,34 @@ impl ComposeQueryItem {
         }
         query
     }
+
+    /// Same as [`ComposeQueryItem::format`], but the values are pushed to the params, see [`QueryItem::format_with_params`].
+    pub fn format_with_params(
+        &self,
+        params: &mut Vec<Value>,
+        column_types: &ColumnTypes,
+    ) -> String {
+        let mut query = String::new();
+
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                query.push_str(&format!(" {} ", self.operator));
+            }
+
+            match item {
+                ComposeQuery::QueryItem(item) => {
+                    query.push_str(&item.format_with_params(params, column_types));
+                }
+                ComposeQuery::ComposeQueryItem(item) => {
+                    query.push_str(&format!(
+                        "({})",
+                        item.format_with_params(params, column_types)
+                    ));
+                }
+            }
+        }
+        query
+    }
 }
 
 pub fn get_all_fields(query: &ComposeQuery) -> Vec<String> {
@@ -315,6 +826,111 @@ pub fn make_order_clause_by_pairs(pairs: Vec<(String, String)>, topk: usize) ->
     order_by
 }
 
+/// The aggregation mode of the query DSL, it groups the records by the group-by fields and computes the aggregation function (count, sum or avg) on a field.
+///
+/// All fields must be in the whitelists of the table, so the fields can be formatted into the sql safely.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AggregateQuery {
+    pub group_by: Vec<String>,
+    /// count, sum, avg
+    pub function: String,
+    /// The field to aggregate, it is ignored when the function is count.
+    pub field: Option<String>,
+}
+
+impl AggregateQuery {
+    pub fn new(group_by: Vec<String>, function: &str, field: Option<String>) -> Self {
+        Self {
+            group_by,
+            function: function.to_lowercase(),
+            field,
+        }
+    }
+
+    /// Check the aggregation function and the fields against the whitelists.
+    ///
+    /// * `groupable_fields` - The fields which can be used in the GROUP BY clause.
+    /// * `numeric_fields` - The fields which can be aggregated by sum and avg.
+    pub fn validate(
+        &self,
+        groupable_fields: &[&str],
+        numeric_fields: &[&str],
+    ) -> Result<(), String> {
+        if self.group_by.is_empty() {
+            return Err("At least one group-by field is required.".to_string());
+        }
+
+        for field in self.group_by.iter() {
+            if !groupable_fields.contains(&field.as_str()) {
+                return Err(format!(
+                    "Invalid group-by field: {}, it must be one of {:?}.",
+                    field, groupable_fields
+                ));
+            }
+        }
+
+        match self.function.as_str() {
+            "count" => Ok(()),
+            "sum" | "avg" => match &self.field {
+                Some(field) if numeric_fields.contains(&field.as_str()) => Ok(()),
+                Some(field) => Err(format!(
+                    "Invalid aggregation field: {}, it must be one of {:?}.",
+                    field, numeric_fields
+                )),
+                None => Err(format!(
+                    "The field is required when the function is {}.",
+                    self.function
+                )),
+            },
+            _ => Err(format!(
+                "Invalid aggregation function: {}, it must be one of count, sum and avg.",
+                self.function
+            )),
+        }
+    }
+
+    /// Format the aggregation query, the group-by values are returned as text and the aggregated value is returned as a float named `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use biomedgps::query_builder::sql_builder::AggregateQuery;
+    ///
+    /// let query = AggregateQuery::new(vec!["relation_type".to_string()], "count", None);
+    /// assert_eq!(
+    ///     query.format("biomedgps_relation", "resource = 'drkg'", 100),
+    ///     "SELECT CAST(relation_type AS TEXT) AS relation_type, CAST(COUNT(*) AS FLOAT8) AS value FROM biomedgps_relation WHERE resource = 'drkg' GROUP BY relation_type ORDER BY value DESC LIMIT 100"
+    /// );
+    /// ```
+    pub fn format(&self, table_name: &str, where_str: &str, limit: u64) -> String {
+        let group_by_columns = self
+            .group_by
+            .iter()
+            .map(|field| format!("CAST({} AS TEXT) AS {}", field, field))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let value = match self.function.as_str() {
+            "sum" | "avg" => format!(
+                "CAST({}({}) AS FLOAT8)",
+                self.function.to_uppercase(),
+                self.field.clone().unwrap_or_default()
+            ),
+            _ => "CAST(COUNT(*) AS FLOAT8)".to_string(),
+        };
+
+        format!(
+            "SELECT {}, {} AS value FROM {} WHERE {} GROUP BY {} ORDER BY value DESC LIMIT {}",
+            group_by_columns,
+            value,
+            table_name,
+            where_str,
+            self.group_by.join(", "),
+            limit
+        )
+    }
+}
+
 // Test code
 #[cfg(test)]
 mod tests {
@@ -366,4 +982,153 @@ mod tests {
         debug!("pairs: {:?}", pairs);
         assert_eq!(2, pairs.len());
     }
+
+    #[test]
+    fn test_check_query() {
+        let fields = vec![
+            "id".to_string(),
+            "name".to_string(),
+            "attributes".to_string(),
+        ];
+        let json_fields = vec!["attributes".to_string()];
+
+        let query: ComposeQuery = serde_json::from_str(
+            r#"{"operator": "and", "items": [
+                {"field": "id", "value": [1, 2], "operator": "in"},
+                {"field": "attributes", "value": "{\"tissue\": \"liver\"}", "operator": "@>"},
+                {"operator": "or", "items": [{"field": "name", "value": "TP53%", "operator": "like"}]}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(query.check(&fields, &json_fields).is_ok());
+
+        let query: ComposeQuery = serde_json::from_str(
+            r#"{"operator": "xor", "items": [
+                {"field": "name", "value": "TP53", "operator": "@>"},
+                {"field": "name.key", "value": "TP53", "operator": "="},
+                {"field": "id", "value": 1, "operator": "like"},
+                {"operator": "and", "items": []}
+            ]}"#,
+        )
+        .unwrap();
+        let errors = query.check(&fields, &json_fields).unwrap_err();
+        debug!("errors: {:?}", errors);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.path.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["", "items[0]", "items[1]", "items[2]", "items[3]"]
+        );
+
+        let mut query = ComposeQuery::QueryItem(QueryItem::new(
+            "id".to_string(),
+            Value::Int(1),
+            "=".to_string(),
+        ));
+        for _ in 0..MAX_QUERY_DEPTH {
+            let mut item = ComposeQueryItem::new("and");
+            item.add_item(query);
+            query = ComposeQuery::ComposeQueryItem(item);
+        }
+        assert!(query.check(&fields, &json_fields).is_ok());
+
+        let mut item = ComposeQueryItem::new("and");
+        item.add_item(query);
+        let query = ComposeQuery::ComposeQueryItem(item);
+        let errors = query.check(&fields, &json_fields).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("nested too deeply"));
+    }
+
+    #[test]
+    fn test_query_operators() {
+        let query: ComposeQuery = serde_json::from_str(
+            r#"{"operator": "or", "items": [
+                {"field": "name", "value": "o'neil_1%", "operator": "not ilike"},
+                {"field": "id", "value": [1, 2], "operator": "not in"},
+                {"field": "created_at", "value": ["2023-01-01", "2023-12-31"], "operator": "between"},
+                {"field": "attributes.confidence", "value": [0.5, 0.9], "operator": "not between"},
+                {"field": "taxid", "value": null, "operator": "="}
+            ]}"#,
+        )
+        .unwrap();
+
+        let item = match &query {
+            ComposeQuery::ComposeQueryItem(item) => item,
+            _ => panic!("The query should be a compose query item."),
+        };
+        assert_eq!(
+            item.format(),
+            r"name not ilike '%o''neil\_1\%%' ESCAPE '\' or id not in (1,2) or created_at BETWEEN '2023-01-01' AND '2023-12-31' or CAST(attributes ->> 'confidence' AS FLOAT8) NOT BETWEEN 0.5 AND 0.9 or taxid IS NULL"
+        );
+
+        let column_types = ColumnTypes::from([
+            ("name".to_string(), "varchar".to_string()),
+            ("id".to_string(), "int8".to_string()),
+            ("created_at".to_string(), "timestamptz".to_string()),
+            ("attributes".to_string(), "jsonb".to_string()),
+        ]);
+        let (sql, params) = query.format_with_params(&column_types);
+        assert_eq!(
+            sql,
+            r"name not ilike $1 ESCAPE '\' or id <> ALL($2) or created_at BETWEEN $3::timestamptz AND $4::timestamptz or CAST(attributes ->> 'confidence' AS FLOAT8) NOT BETWEEN $5 AND $6 or taxid IS NULL"
+        );
+        assert_eq!(params.len(), 6);
+        assert_eq!(params[0], Value::String(r"%o'neil\_1\%%".to_string()));
+        assert_eq!(params[1], Value::ArrayInt(vec![1, 2]));
+        assert_eq!(params[4], Value::Float(0.5));
+
+        // The ids of a bigint column are sent as a text array, so the array is cast too.
+        let query: ComposeQuery =
+            serde_json::from_str(r#"{"field": "id", "value": ["1", "2"], "operator": "in"}"#)
+                .unwrap();
+        let (sql, params) = query.format_with_params(&column_types);
+        assert_eq!(sql, "id = ANY($1::int8[])");
+        assert_eq!(
+            params,
+            vec![Value::ArrayString(vec!["1".to_string(), "2".to_string()])]
+        );
+
+        let fields = vec!["id".to_string(), "score".to_string()];
+        for query_str in [
+            r#"{"field": "score", "value": [0.5], "operator": "between"}"#,
+            r#"{"field": "id", "value": [], "operator": "in"}"#,
+            r#"{"field": "score", "value": true, "operator": "between"}"#,
+            r#"{"field": "score", "value": 1, "operator": "is null"}"#,
+        ] {
+            let query: ComposeQuery = serde_json::from_str(query_str).unwrap();
+            assert!(query.check(&fields, &[]).is_err(), "{}", query_str);
+        }
+    }
+
+    #[test]
+    fn test_aggregate_query() {
+        let groupable_fields = ["relation_type", "resource"];
+        let numeric_fields = ["score"];
+
+        let query = AggregateQuery::new(
+            vec!["relation_type".to_string()],
+            "AVG",
+            Some("score".to_string()),
+        );
+        assert!(query.validate(&groupable_fields, &numeric_fields).is_ok());
+        assert_eq!(
+            query.format("biomedgps_relation", "1=1", 10),
+            "SELECT CAST(relation_type AS TEXT) AS relation_type, CAST(AVG(score) AS FLOAT8) AS value FROM biomedgps_relation WHERE 1=1 GROUP BY relation_type ORDER BY value DESC LIMIT 10"
+        );
+
+        let query = AggregateQuery::new(vec!["key_sentence".to_string()], "count", None);
+        assert!(query.validate(&groupable_fields, &numeric_fields).is_err());
+
+        let query = AggregateQuery::new(
+            vec!["resource".to_string()],
+            "sum",
+            Some("id; DROP TABLE".to_string()),
+        );
+        assert!(query.validate(&groupable_fields, &numeric_fields).is_err());
+
+        let query = AggregateQuery::new(vec!["resource".to_string()], "max", None);
+        assert!(query.validate(&groupable_fields, &numeric_fields).is_err());
+    }
 }
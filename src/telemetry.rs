@@ -0,0 +1,102 @@
+//! Telemetry module exports the tracing spans to an OpenTelemetry collector by OTLP (gRPC), so a request can be followed across the API, the postgresql database and the neo4j database in one trace.
+//!
+//! The export is configured by the standard environment variables: OTEL_EXPORTER_OTLP_ENDPOINT (such as `http://localhost:4317`, the export is disabled if it is not set), OTEL_SERVICE_NAME (default: the name of the binary) and OTEL_TRACES_SAMPLER_ARG (the ratio of the sampled traces, default: 1.0). The `traceparent` header of a request is respected, so the spans are joined into the trace of the caller.
+//!
+//! The exported spans are the requests (`request`, see [`crate::api::middleware::RequestId`]), the database queries (`db_query`, the statement is summarized as the operation and the table, see [`crate::model::util::summarize_statement`], so no value of the query is exported) and the cypher queries (`neo4j_query`).
+
+use opentelemetry::propagation::Extractor;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer};
+use opentelemetry_sdk::Resource;
+use poem::http::HeaderMap;
+
+/// The settings of the OTLP exporter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryConfig {
+    /// The endpoint of the collector, such as `http://localhost:4317`.
+    pub endpoint: String,
+    pub service_name: String,
+    /// The ratio of the sampled traces, between 0 and 1. The traces which are sampled by the caller are always sampled.
+    pub sample_ratio: f64,
+}
+
+impl TelemetryConfig {
+    /// Load the settings from the environment variables, it returns None if OTEL_EXPORTER_OTLP_ENDPOINT is not set.
+    pub fn from_env(default_service_name: &str) -> Result<Option<TelemetryConfig>, String> {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let endpoint = match env("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Some(endpoint) => endpoint,
+            None => return Ok(None),
+        };
+
+        let sample_ratio = match env("OTEL_TRACES_SAMPLER_ARG") {
+            Some(v) => match v.parse::<f64>() {
+                Ok(ratio) if (0.0..=1.0).contains(&ratio) => ratio,
+                _ => {
+                    return Err(format!(
+                        "The environment variable OTEL_TRACES_SAMPLER_ARG is invalid: {}, it must be between 0 and 1.",
+                        v
+                    ))
+                }
+            },
+            None => 1.0,
+        };
+
+        Ok(Some(TelemetryConfig {
+            endpoint,
+            service_name: env("OTEL_SERVICE_NAME").unwrap_or(default_service_name.to_string()),
+            sample_ratio,
+        }))
+    }
+
+    /// Install the OTLP exporter and return the tracer for the tracing layer, the spans are exported in batches by the tokio runtime, so it must be called in the runtime.
+    pub fn install_tracer(&self) -> Result<Tracer, String> {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(self.sample_ratio)));
+        opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(&self.endpoint),
+            )
+            .with_trace_config(
+                opentelemetry_sdk::trace::config()
+                    .with_sampler(sampler)
+                    .with_resource(Resource::new(vec![KeyValue::new(
+                        "service.name",
+                        self.service_name.clone(),
+                    )])),
+            )
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .map_err(|e| format!("couldn't initialize the OTLP exporter. Reason: {}", e))
+    }
+}
+
+/// Export the remaining spans, it should be called before the process exits.
+pub fn shutdown_telemetry() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Extract the trace context of the caller from the `traceparent` and `tracestate` headers, it is empty if the headers are missing or the export is disabled.
+pub fn extract_context(headers: &HeaderMap) -> opentelemetry::Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    })
+}